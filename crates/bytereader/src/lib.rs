@@ -1,7 +1,10 @@
-use std::{fs::File, io::Read, string::FromUtf8Error};
+use std::{borrow::Cow, str::Utf8Error};
 
 use thiserror::Error;
 
+mod writer;
+pub use writer::{ByteWriter, ToByteWriter};
+
 #[derive(Debug, Error)]
 pub enum ByteReaderError {
     #[error(
@@ -19,7 +22,7 @@ pub enum ByteReaderError {
     FailedTypeCast { from: String, to: String },
 
     #[error("Failed to parse Utf8String from bytes")]
-    Utf8Error(#[from] FromUtf8Error),
+    Utf8Error(#[from] Utf8Error),
 
     #[error("An unknown error occurred: '{0}'")]
     UnknownError(String),
@@ -31,46 +34,38 @@ pub enum Endian {
     Big,
 }
 
+/// Reads values from a borrowed byte buffer, bytes and strings are returned as slices of it without copying
 #[derive(Clone)]
-pub struct ByteReader {
+pub struct ByteReader<'a> {
     /// The byte buyffer
-    data: Vec<u8>,
+    data: &'a [u8],
     /// The current offset (position) in the buffer
     offset: usize,
     /// Endian to use when reading for reading numbers
     endian: Endian,
     /// If the push_index function has been ran, the Option contains a value at that position, and can be returned to this position later using the pop_index function
     push_offsets: Vec<usize>,
+    /// End offsets of the windows opened with push_limit, reads can't go past the innermost one
+    limits: Vec<usize>,
 
     /// If needed enable this for verbose printing
     debug: bool,
 }
 
-impl ByteReader {
-    pub fn from_vec(vec: &[u8]) -> Self {
+impl<'a> ByteReader<'a> {
+    pub fn from_slice(data: &'a [u8]) -> Self {
         Self {
-            data: vec.to_vec(),
-            offset: 0,
-            endian: Endian::Little,
-            push_offsets: vec![],
-            debug: false,
-        }
-    }
-
-    pub fn from_file(file: &mut File) -> std::io::Result<Self> {
-        let mut data = vec![];
-        file.read_to_end(&mut data)?;
-        Ok(Self {
             data,
             offset: 0,
             endian: Endian::Little,
             push_offsets: vec![],
+            limits: vec![],
             debug: false,
-        })
+        }
     }
 }
 
-impl ByteReader {
+impl<'a> ByteReader<'a> {
     pub fn set_debug(&mut self, debug: bool) -> &mut Self {
         self.debug = debug;
         self
@@ -100,10 +95,43 @@ impl ByteReader {
         self
     }
 
+    /// Restricts reading to the next `length` bytes until pop_limit is called, offsets stay relative to the whole buffer
+    pub fn push_limit(&mut self, length: usize) -> Result<&mut Self, ByteReaderError> {
+        let end = self.offset + length;
+        if end > self.end() {
+            return Err(ByteReaderError::OutOfBounds {
+                length: self.end(),
+                start: self.offset,
+                end,
+            });
+        }
+        self.limits.push(end);
+        Ok(self)
+    }
+    pub fn pop_limit(&mut self) -> &mut Self {
+        self.limits.pop();
+        self
+    }
+
+    /// The offset reading stops at, either the end of the innermost limit or of the buffer
+    pub fn end(&self) -> usize {
+        self.limits.last().copied().unwrap_or(self.data.len())
+    }
+
+    /// Number of bytes left before the end of the innermost limit
+    pub fn remaining(&self) -> usize {
+        self.end().saturating_sub(self.offset)
+    }
+
     pub fn get_file_length(&self) -> usize {
         self.data.len()
     }
 
+    /// The whole underlying buffer, regardless of the current offset and limits
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.data
+    }
+
     pub fn get_current_offset(&self) -> usize {
         self.offset
     }
@@ -116,32 +144,11 @@ impl ByteReader {
                 return Ok(false);
             }
         }
-        return Ok(true);
+        Ok(true)
     }
 
-    pub fn read_bytes(&mut self, bytes: usize) -> Result<&[u8], ByteReaderError> {
-        // Was hoping for an easier way by using peak_bytes, but couldn't figure out with out the borrowing problems
-        // --- example ---
-        // let data = self.peak_bytes()?;
-        // self.offset += bytes;
-        // return Ok(data)
-
-        if self.offset + bytes > self.data.len() {
-            return Err(ByteReaderError::OutOfBounds {
-                length: self.data.len(),
-                start: self.offset,
-                end: self.offset + bytes,
-            });
-        }
-
-        let data = self.data.get(self.offset..self.offset + bytes).ok_or(
-            ByteReaderError::OutOfBounds {
-                length: self.data.len(),
-                start: self.offset,
-                end: self.offset + bytes,
-            },
-        )?;
-
+    pub fn read_bytes(&mut self, bytes: usize) -> Result<&'a [u8], ByteReaderError> {
+        let data = self.peak_bytes(bytes)?;
         self.offset += bytes;
 
         if self.debug {
@@ -150,10 +157,10 @@ impl ByteReader {
 
         Ok(data)
     }
-    pub fn peak_bytes(&self, bytes: usize) -> Result<&[u8], ByteReaderError> {
-        if self.offset + bytes > self.data.len() {
+    pub fn peak_bytes(&self, bytes: usize) -> Result<&'a [u8], ByteReaderError> {
+        if self.offset + bytes > self.end() {
             return Err(ByteReaderError::OutOfBounds {
-                length: self.data.len(),
+                length: self.end(),
                 start: self.offset,
                 end: self.offset + bytes,
             });
@@ -169,12 +176,12 @@ impl ByteReader {
         Ok(data)
     }
 
-    pub fn read_rest(&mut self) -> Result<&[u8], ByteReaderError> {
-        self.read_bytes(self.data.len() - self.offset)
+    pub fn read_rest(&mut self) -> Result<&'a [u8], ByteReaderError> {
+        self.read_bytes(self.remaining())
     }
 
-    pub fn peak_rest(&self) -> Result<&[u8], ByteReaderError> {
-        self.peak_bytes(self.data.len() - self.offset)
+    pub fn peak_rest(&self) -> Result<&'a [u8], ByteReaderError> {
+        self.peak_bytes(self.remaining())
     }
 
     pub fn find_next(&self, sequence: &[u8]) -> Result<usize, ByteReaderError> {
@@ -216,58 +223,68 @@ impl ByteReader {
             sequence: sequence.to_vec(),
         })
     }
-    pub fn read_string_length<T: FromByteReader + Into<usize>>(
+    pub fn read_string_length<T: FromByteReader<'a, Error = ByteReaderError> + Into<usize>>(
         &mut self,
-    ) -> Result<String, ByteReaderError> {
+    ) -> Result<&'a str, ByteReaderError> {
         let length = self.read::<T>()?;
         self.read_string(length.into())
     }
-    pub fn read_string_lossy_length<T: FromByteReader + Into<usize>>(
+    pub fn read_string_lossy_length<
+        T: FromByteReader<'a, Error = ByteReaderError> + Into<usize>,
+    >(
         &mut self,
-    ) -> Result<String, ByteReaderError> {
+    ) -> Result<Cow<'a, str>, ByteReaderError> {
         let length = self.read::<T>()?;
         self.read_string_lossy(length.into())
     }
 
-    pub fn read_string(&mut self, length: usize) -> Result<String, ByteReaderError> {
-        String::from_utf8(self.read_bytes(length)?.to_vec()).map_err(ByteReaderError::Utf8Error)
+    pub fn read_string(&mut self, length: usize) -> Result<&'a str, ByteReaderError> {
+        std::str::from_utf8(self.read_bytes(length)?).map_err(ByteReaderError::Utf8Error)
     }
 
-    pub fn read_string_lossy(&mut self, length: usize) -> Result<String, ByteReaderError> {
-        Ok(String::from_utf8_lossy(self.read_bytes(length)?).to_string())
+    /// Invalid UTF-8 sequences are replaced, which is the only case where the string is copied
+    pub fn read_string_lossy(&mut self, length: usize) -> Result<Cow<'a, str>, ByteReaderError> {
+        Ok(String::from_utf8_lossy(self.read_bytes(length)?))
     }
 
-    pub fn read<T: FromByteReader>(&mut self) -> Result<T, ByteReaderError> {
+    pub fn read<T: FromByteReader<'a>>(&mut self) -> Result<T, T::Error> {
         let v = T::read_from_byte_reader(self)?;
         Ok(v)
     }
 
-    pub fn peak<T: FromByteReader>(&mut self) -> Result<T, ByteReaderError> {
+    pub fn peak<T: FromByteReader<'a>>(&mut self) -> Result<T, T::Error> {
         T::peak_from_byte_reader(self)
     }
 }
 
-pub trait FromByteReader {
-    fn read_from_byte_reader(reader: &mut ByteReader) -> Result<Self, ByteReaderError>
+/// A value read from a [`ByteReader`] over a buffer living for `'a`, which it's allowed to borrow from
+pub trait FromByteReader<'a> {
+    /// The error returned when the bytes don't hold a valid value, failed reads from the
+    /// underlying buffer have to be convertible into it
+    type Error: From<ByteReaderError>;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, Self::Error>
     where
         Self: Sized;
 
-    fn peak_from_byte_reader(reader: &mut ByteReader) -> Result<Self, ByteReaderError>
+    fn peak_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, Self::Error>
     where
         Self: Sized,
     {
         reader.push_index();
-        let v = Self::read_from_byte_reader(reader)?;
+        let v = Self::read_from_byte_reader(reader);
         reader.pop_index();
-        Ok(v)
+        v
     }
 }
 
 macro_rules! impl_from_byte_reader {
     ($ty:ident) => {
-        impl FromByteReader for $ty {
+        impl<'a> FromByteReader<'a> for $ty {
+            type Error = ByteReaderError;
+
             fn read_from_byte_reader(
-                reader: &mut crate::ByteReader,
+                reader: &mut crate::ByteReader<'a>,
             ) -> Result<$ty, ByteReaderError> {
                 let bytes = std::mem::size_of::<Self>();
                 let data: [u8; std::mem::size_of::<Self>()] = reader
@@ -294,5 +311,7 @@ impl_from_byte_reader!(u32);
 impl_from_byte_reader!(i32);
 impl_from_byte_reader!(u64);
 impl_from_byte_reader!(i64);
+impl_from_byte_reader!(u128);
+impl_from_byte_reader!(i128);
 impl_from_byte_reader!(f32);
 impl_from_byte_reader!(f64);
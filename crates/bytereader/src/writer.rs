@@ -0,0 +1,87 @@
+use crate::Endian;
+
+/// Writes values to a growable byte buffer, the counterpart of [`crate::ByteReader`]
+#[derive(Clone)]
+pub struct ByteWriter {
+    /// The bytes written so far
+    data: Vec<u8>,
+    /// Endian to use when writing numbers
+    endian: Endian,
+}
+
+impl Default for ByteWriter {
+    fn default() -> Self {
+        Self {
+            data: vec![],
+            endian: Endian::Little,
+        }
+    }
+}
+
+impl ByteWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) -> &mut Self {
+        self.endian = endian;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.data.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn write<T: ToByteWriter + ?Sized>(&mut self, value: &T) -> &mut Self {
+        value.write_to_byte_writer(self);
+        self
+    }
+}
+
+/// A value that can be written to a [`ByteWriter`], the counterpart of [`crate::FromByteReader`]
+pub trait ToByteWriter {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter);
+}
+
+macro_rules! impl_to_byte_writer {
+    ($ty:ident) => {
+        impl ToByteWriter for $ty {
+            fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+                match writer.endian {
+                    Endian::Little => writer.write_bytes(&self.to_le_bytes()),
+                    Endian::Big => writer.write_bytes(&self.to_be_bytes()),
+                };
+            }
+        }
+    };
+}
+
+impl_to_byte_writer!(u8);
+impl_to_byte_writer!(i8);
+impl_to_byte_writer!(u16);
+impl_to_byte_writer!(i16);
+impl_to_byte_writer!(u32);
+impl_to_byte_writer!(i32);
+impl_to_byte_writer!(u64);
+impl_to_byte_writer!(i64);
+impl_to_byte_writer!(u128);
+impl_to_byte_writer!(i128);
+impl_to_byte_writer!(f32);
+impl_to_byte_writer!(f64);
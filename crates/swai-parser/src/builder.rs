@@ -0,0 +1,208 @@
+use std::borrow::Cow;
+
+use crate::{
+    error::{WasmParserError, WasmParserErrorKind},
+    sections::WasmSections,
+    types::{
+        Code, DataSegment, ExportDesc, Expr, FunctionType, GlobalType, ImportDesc, Indecies,
+        Locals, MemType, Name, SegmentMode, ValueType,
+    },
+    wasm::WasmModule,
+};
+
+/// Builds a module from Rust, assigning the indices of types, functions, memories, globals and data segments
+/// as they're added. Every `add_*` and `import_*` method returns the index of the new definition in its index space.
+///
+/// Imports come first in the index spaces, so they have to be added before definitions of the same kind.
+/// The builder doesn't validate, use [`crate::validate`] on the built module to check it
+#[derive(Debug, Default)]
+pub struct ModuleBuilder {
+    sections: WasmSections<'static>,
+}
+
+impl ModuleBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a function type, or returns the index of an equal one that's already been added
+    pub fn add_type(&mut self, ty: FunctionType) -> u32 {
+        let types = &mut self.sections.types;
+        match types.iter().position(|existing| existing == &ty) {
+            Some(index) => index as u32,
+            None => {
+                types.push(ty);
+                types.len() as u32 - 1
+            }
+        }
+    }
+
+    /// Fails if a function has already been defined, since the import would shift its index
+    pub fn import_function(
+        &mut self,
+        module: impl Into<String>,
+        name: impl Into<String>,
+        ty: FunctionType,
+    ) -> Result<u32, WasmParserError> {
+        if !self.sections.functions.is_empty() {
+            return Err(WasmParserError::new(
+                WasmParserErrorKind::ImportAfterDefinition { kind: "functions" },
+            ));
+        }
+        let ty = self.add_type(ty);
+        Ok(self.import(module, name, ImportDesc::TypeIdx(Indecies::TypeIdx(ty))))
+    }
+
+    /// Fails if a memory has already been defined, since the import would shift its index
+    pub fn import_memory(
+        &mut self,
+        module: impl Into<String>,
+        name: impl Into<String>,
+        limits: MemType,
+    ) -> Result<u32, WasmParserError> {
+        if !self.sections.memory.is_empty() {
+            return Err(WasmParserError::new(
+                WasmParserErrorKind::ImportAfterDefinition { kind: "memories" },
+            ));
+        }
+        Ok(self.import(module, name, ImportDesc::MemType(limits)))
+    }
+
+    /// Fails if a global has already been defined, since the import would shift its index
+    pub fn import_global(
+        &mut self,
+        module: impl Into<String>,
+        name: impl Into<String>,
+        ty: GlobalType,
+    ) -> Result<u32, WasmParserError> {
+        if !self.sections.global.is_empty() {
+            return Err(WasmParserError::new(
+                WasmParserErrorKind::ImportAfterDefinition { kind: "globals" },
+            ));
+        }
+        Ok(self.import(module, name, ImportDesc::GlobalType(ty)))
+    }
+
+    /// Adds a function with the given locals, after the parameters, and body. The body doesn't end with `end`
+    pub fn add_function(&mut self, ty: FunctionType, locals: &[ValueType], body: Expr) -> u32 {
+        let ty = self.add_type(ty);
+        self.sections.functions.push(Indecies::TypeIdx(ty));
+        self.sections
+            .code
+            .push(Code::new(compress_locals(locals), body));
+        self.imported(|desc| matches!(desc, ImportDesc::TypeIdx(_)))
+            + self.sections.functions.len() as u32
+            - 1
+    }
+
+    pub fn add_memory(&mut self, limits: MemType) -> u32 {
+        self.sections.memory.push(limits);
+        self.imported(|desc| matches!(desc, ImportDesc::MemType(_)))
+            + self.sections.memory.len() as u32
+            - 1
+    }
+
+    /// Adds a global, `init` is a constant expression without the `end`
+    pub fn add_global(&mut self, ty: GlobalType, init: Expr) -> u32 {
+        self.sections.global.push((ty, init));
+        self.imported(|desc| matches!(desc, ImportDesc::GlobalType(_)))
+            + self.sections.global.len() as u32
+            - 1
+    }
+
+    /// Adds a data segment that's copied into `memory` at `offset`, a constant expression, when the module is instantiated
+    pub fn add_data(&mut self, memory: u32, offset: Expr, bytes: impl Into<Vec<u8>>) -> u32 {
+        self.data(
+            SegmentMode::Active {
+                memory_index: memory,
+                offset,
+            },
+            bytes.into(),
+        )
+    }
+
+    /// Adds a data segment that's only used by `memory.init`
+    pub fn add_passive_data(&mut self, bytes: impl Into<Vec<u8>>) -> u32 {
+        self.data(SegmentMode::Passive, bytes.into())
+    }
+
+    pub fn export_function(&mut self, name: impl Into<String>, index: u32) -> &mut Self {
+        self.export(name, ExportDesc::FuncIdx(index))
+    }
+
+    pub fn export_memory(&mut self, name: impl Into<String>, index: u32) -> &mut Self {
+        self.export(name, ExportDesc::MemIdx(index))
+    }
+
+    pub fn export_global(&mut self, name: impl Into<String>, index: u32) -> &mut Self {
+        self.export(name, ExportDesc::GlobalIdx(index))
+    }
+
+    /// Sets the function that's called when the module is instantiated
+    pub fn start(&mut self, function: u32) -> &mut Self {
+        self.sections.start = Some(Indecies::FuncIdx(function));
+        self
+    }
+
+    pub fn build(self) -> WasmModule<'static> {
+        WasmModule {
+            sections: self.sections,
+        }
+    }
+
+    /// Builds the module and encodes it in the binary format
+    pub fn to_bytes(self) -> Vec<u8> {
+        self.build().to_bytes()
+    }
+
+    fn import(
+        &mut self,
+        module: impl Into<String>,
+        name: impl Into<String>,
+        desc: ImportDesc,
+    ) -> u32 {
+        let index = self
+            .imported(|existing| std::mem::discriminant(existing) == std::mem::discriminant(&desc));
+        self.sections
+            .imports
+            .push((Name::from(module.into()), Name::from(name.into()), desc));
+        index
+    }
+
+    /// The number of imports of the kind `select` picks
+    fn imported(&self, select: impl Fn(&ImportDesc) -> bool) -> u32 {
+        self.sections
+            .imports
+            .iter()
+            .filter(|(_, _, desc)| select(desc))
+            .count() as u32
+    }
+
+    fn data(&mut self, mode: SegmentMode, bytes: Vec<u8>) -> u32 {
+        let data = &mut self.sections.data;
+        data.push(DataSegment {
+            mode,
+            bytes: Cow::Owned(bytes),
+        });
+        // The data count lets function bodies use `memory.init` and `data.drop`
+        self.sections.data_count = Some(data.len() as u32);
+        data.len() as u32 - 1
+    }
+
+    fn export(&mut self, name: impl Into<String>, desc: ExportDesc) -> &mut Self {
+        self.sections.export.push((Name::from(name.into()), desc));
+        self
+    }
+}
+
+/// Run-length encodes locals the way the code section stores them
+fn compress_locals(locals: &[ValueType]) -> Locals {
+    let mut compressed: Locals = vec![];
+    for &local in locals {
+        match compressed.last_mut() {
+            Some((count, ty)) if *ty == local => *count += 1,
+            _ => compressed.push((1, local)),
+        }
+    }
+    compressed
+}
@@ -0,0 +1,1078 @@
+use bytereader::{ByteReader, FromByteReader};
+use std::{fs::File, io::Read};
+
+use crate::{
+    custom::CustomSection,
+    error::{ResultExt, WasmParserError, WasmParserErrorKind},
+    leb128::Leb128Readers,
+    sections::{expect_consumed, read_custom_section, WasmSections},
+    types::{read_byte_as, FunctionType, ImportDesc, Name},
+    wasm::{read_header, read_preamble, Encoding, WasmModule},
+};
+
+/// Section names by id, used for errors. Id 0 is a custom section, which is named by its contents
+const SECTION_NAMES: [&str; 12] = [
+    "custom section",
+    "core module section",
+    "core instance section",
+    "core type section",
+    "component section",
+    "instance section",
+    "alias section",
+    "type section",
+    "canon section",
+    "start section",
+    "import section",
+    "export section",
+];
+
+/// The deepest nesting of components and of type declarations the parser accepts.
+/// Both are read recursively, so the limit keeps malicious binaries from overflowing the stack
+pub const MAX_COMPONENT_NESTING_DEPTH: usize = 64;
+
+/// A parsed component of the component model, as described by:
+/// https://github.com/WebAssembly/component-model/blob/main/design/mvp/Binary.md
+///
+/// Sections can repeat and appear in any order, each one adds to the index spaces in the order it appears.
+/// Names are owned, nested core modules borrow from the component bytes like a [`WasmModule`] does
+#[derive(Debug, PartialEq)]
+pub struct WasmComponent<'a> {
+    pub sections: Vec<ComponentSection<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ComponentSection<'a> {
+    Custom(CustomSection<'a>),
+    CoreModule(Box<WasmModule<'a>>),
+    CoreInstances(Vec<CoreInstance>),
+    CoreTypes(Vec<CoreType>),
+    Component(WasmComponent<'a>),
+    Instances(Vec<ComponentInstance>),
+    Aliases(Vec<Alias>),
+    Types(Vec<ComponentType>),
+    Canonicals(Vec<Canonical>),
+    Start(ComponentStart),
+    Imports(Vec<ComponentImport>),
+    Exports(Vec<ComponentExport>),
+}
+
+impl WasmComponent<'static> {
+    pub fn from_file(file: &mut File) -> Result<WasmComponent<'static>, WasmParserError> {
+        let mut buffer = vec![];
+        file.read_to_end(&mut buffer)?;
+        Ok(WasmComponent::from_bytes(&buffer)?.into_owned())
+    }
+}
+
+impl<'a> WasmComponent<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<WasmComponent<'a>, WasmParserError> {
+        let mut reader = ByteReader::from_slice(bytes);
+        read_component(&mut reader, 0)
+    }
+
+    /// The imports of the component itself, not of nested components or modules
+    pub fn imports(&self) -> impl Iterator<Item = &ComponentImport> {
+        self.sections.iter().flat_map(|section| match section {
+            ComponentSection::Imports(imports) => imports.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// The exports of the component itself, not of nested components or modules
+    pub fn exports(&self) -> impl Iterator<Item = &ComponentExport> {
+        self.sections.iter().flat_map(|section| match section {
+            ComponentSection::Exports(exports) => exports.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// The core modules defined directly in the component, in the order of the core module index space
+    pub fn modules(&self) -> impl Iterator<Item = &WasmModule<'a>> {
+        self.sections.iter().filter_map(|section| match section {
+            ComponentSection::CoreModule(module) => Some(module.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Copies everything borrowed from the component bytes
+    pub fn into_owned(self) -> WasmComponent<'static> {
+        WasmComponent {
+            sections: self
+                .sections
+                .into_iter()
+                .map(|section| match section {
+                    ComponentSection::Custom(section) => {
+                        ComponentSection::Custom(section.into_owned())
+                    }
+                    ComponentSection::CoreModule(module) => {
+                        ComponentSection::CoreModule(Box::new(module.into_owned()))
+                    }
+                    ComponentSection::Component(component) => {
+                        ComponentSection::Component(component.into_owned())
+                    }
+                    ComponentSection::CoreInstances(instances) => {
+                        ComponentSection::CoreInstances(instances)
+                    }
+                    ComponentSection::CoreTypes(types) => ComponentSection::CoreTypes(types),
+                    ComponentSection::Instances(instances) => {
+                        ComponentSection::Instances(instances)
+                    }
+                    ComponentSection::Aliases(aliases) => ComponentSection::Aliases(aliases),
+                    ComponentSection::Types(types) => ComponentSection::Types(types),
+                    ComponentSection::Canonicals(functions) => {
+                        ComponentSection::Canonicals(functions)
+                    }
+                    ComponentSection::Start(start) => ComponentSection::Start(start),
+                    ComponentSection::Imports(imports) => ComponentSection::Imports(imports),
+                    ComponentSection::Exports(exports) => ComponentSection::Exports(exports),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Reads a component from its preamble up to the end of the reader, which is bounded for nested components
+fn read_component<'a>(
+    reader: &mut ByteReader<'a>,
+    depth: usize,
+) -> Result<WasmComponent<'a>, WasmParserError> {
+    read_preamble(reader, Encoding::Component)?;
+
+    let mut sections = vec![];
+    let mut last = None;
+    while let Ok(id) = reader.read::<u8>() {
+        let id_offset = reader.get_current_offset() - 1;
+        let size = reader
+            .read_uleb128::<u32>()
+            .context(reader, "section size")?;
+        let section = *SECTION_NAMES.get(id as usize).ok_or_else(|| {
+            WasmParserError::at(id_offset, WasmParserErrorKind::InvalidSectionId { id })
+                .in_context(reader, "section id".to_string())
+        })?;
+
+        if id == 0 {
+            sections.push(ComponentSection::Custom(read_custom_section(
+                reader, size, last,
+            )?));
+            continue;
+        }
+        last = Some(id);
+
+        let start = reader.get_current_offset();
+        reader
+            .push_limit(size as usize)
+            .map(|_| ())
+            .context(reader, section)?;
+        sections.push(read_section(reader, id, depth).context(reader, section)?);
+        expect_consumed(reader, section, start, size).context(reader, section)?;
+        reader.pop_limit();
+    }
+    Ok(WasmComponent { sections })
+}
+
+fn read_section<'a>(
+    reader: &mut ByteReader<'a>,
+    id: u8,
+    depth: usize,
+) -> Result<ComponentSection<'a>, WasmParserError> {
+    Ok(match id {
+        // Nested modules and components are complete binaries filling the section
+        1 => {
+            read_header(reader)?;
+            ComponentSection::CoreModule(Box::new(WasmModule {
+                sections: WasmSections::from_reader(reader)?,
+            }))
+        }
+        4 => {
+            if depth >= MAX_COMPONENT_NESTING_DEPTH {
+                return Err(nesting_limit(reader, "component nesting depth"));
+            }
+            ComponentSection::Component(read_component(reader, depth + 1)?)
+        }
+        2 => ComponentSection::CoreInstances(read_items(reader, "core instance", |reader| {
+            reader.read()
+        })?),
+        3 => ComponentSection::CoreTypes(read_items(reader, "core type", |reader| {
+            read_core_type(reader, 0)
+        })?),
+        5 => ComponentSection::Instances(read_items(reader, "instance", |reader| reader.read())?),
+        6 => ComponentSection::Aliases(read_items(reader, "alias", |reader| reader.read())?),
+        7 => ComponentSection::Types(read_items(reader, "type", |reader| {
+            read_component_type(reader, 0)
+        })?),
+        8 => ComponentSection::Canonicals(read_items(reader, "canonical function", |reader| {
+            reader.read()
+        })?),
+        9 => ComponentSection::Start(reader.read()?),
+        10 => ComponentSection::Imports(read_items(reader, "import", |reader| reader.read())?),
+        11 => ComponentSection::Exports(read_items(reader, "export", |reader| reader.read())?),
+        _ => unreachable!("section ids are checked against SECTION_NAMES"),
+    })
+}
+
+/// Reads a vector, adding `item` and the index of the item being read to the error context
+fn read_items<'a, T>(
+    reader: &mut ByteReader<'a>,
+    item: &str,
+    mut read: impl FnMut(&mut ByteReader<'a>) -> Result<T, WasmParserError>,
+) -> Result<Vec<T>, WasmParserError> {
+    (0..reader.read_uleb128::<u32>()?)
+        .map(|i| read(reader).with_context(reader, || format!("{item} {i}")))
+        .collect()
+}
+
+fn read_name(reader: &mut ByteReader) -> Result<Name<'static>, WasmParserError> {
+    Ok(reader.read::<Name>()?.into_owned())
+}
+
+/// Reads `T?`, which is encoded as 0x00 or 0x01 followed by the value
+fn read_optional<'a, T>(
+    reader: &mut ByteReader<'a>,
+    construct: &'static str,
+    mut read: impl FnMut(&mut ByteReader<'a>) -> Result<T, WasmParserError>,
+) -> Result<Option<T>, WasmParserError> {
+    match read_byte_as(reader, construct, |byte| (byte <= 0x01).then_some(byte))? {
+        0x00 => Ok(None),
+        _ => read(reader).map(Some),
+    }
+}
+
+fn nesting_limit(reader: &ByteReader, construct: &'static str) -> WasmParserError {
+    WasmParserError::at(
+        reader.get_current_offset(),
+        WasmParserErrorKind::ImplementationLimit {
+            construct,
+            limit: MAX_COMPONENT_NESTING_DEPTH,
+        },
+    )
+}
+
+/// The kinds of definitions in a core module or core instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreSort {
+    Func,     // 0x00
+    Table,    // 0x01
+    Memory,   // 0x02
+    Global,   // 0x03
+    Tag,      // 0x04
+    Type,     // 0x10
+    Module,   // 0x11
+    Instance, // 0x12
+}
+
+impl CoreSort {
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0x00 => Some(CoreSort::Func),
+            0x01 => Some(CoreSort::Table),
+            0x02 => Some(CoreSort::Memory),
+            0x03 => Some(CoreSort::Global),
+            0x04 => Some(CoreSort::Tag),
+            0x10 => Some(CoreSort::Type),
+            0x11 => Some(CoreSort::Module),
+            0x12 => Some(CoreSort::Instance),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromByteReader<'a> for CoreSort {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        read_byte_as(reader, "core sort", CoreSort::from_byte)
+    }
+}
+
+/// The index spaces of a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    Core(CoreSort), // 0x00 ...
+    Func,           // 0x01
+    Value,          // 0x02
+    Type,           // 0x03
+    Component,      // 0x04
+    Instance,       // 0x05
+}
+
+impl<'a> FromByteReader<'a> for Sort {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        match reader.peak::<u8>()? {
+            0x00 => {
+                reader.jump(1);
+                Ok(Sort::Core(reader.read()?))
+            }
+            _ => read_byte_as(reader, "sort", |byte| match byte {
+                0x01 => Some(Sort::Func),
+                0x02 => Some(Sort::Value),
+                0x03 => Some(Sort::Type),
+                0x04 => Some(Sort::Component),
+                0x05 => Some(Sort::Instance),
+                _ => None,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoreSortIndex {
+    pub sort: CoreSort,
+    pub index: u32,
+}
+
+impl<'a> FromByteReader<'a> for CoreSortIndex {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        Ok(CoreSortIndex {
+            sort: reader.read()?,
+            index: reader.read_uleb128::<u32>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortIndex {
+    pub sort: Sort,
+    pub index: u32,
+}
+
+impl<'a> FromByteReader<'a> for SortIndex {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        Ok(SortIndex {
+            sort: reader.read()?,
+            index: reader.read_uleb128::<u32>()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CoreInstance {
+    /// Instantiates a core module with named core instances as its imports
+    Instantiate {
+        module: u32,
+        args: Vec<(Name<'static>, u32)>,
+    }, // 0x00
+    /// An instance made of existing core definitions
+    FromExports(Vec<(Name<'static>, CoreSortIndex)>), // 0x01
+}
+
+impl<'a> FromByteReader<'a> for CoreInstance {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let kind = read_byte_as(reader, "core instance", |byte| {
+            (byte <= 0x01).then_some(byte)
+        })?;
+        Ok(match kind {
+            0x00 => {
+                let module = reader.read_uleb128::<u32>()?;
+                let args = read_items(reader, "argument", |reader| {
+                    let name = read_name(reader)?;
+                    // Modules can only be instantiated with instances
+                    read_byte_as(reader, "instantiation argument", |byte| {
+                        (byte == 0x12).then_some(())
+                    })?;
+                    Ok((name, reader.read_uleb128::<u32>()?))
+                })?;
+                CoreInstance::Instantiate { module, args }
+            }
+            _ => CoreInstance::FromExports(read_items(reader, "export", |reader| {
+                Ok((read_name(reader)?, reader.read()?))
+            })?),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ComponentInstance {
+    /// Instantiates a component with named definitions as its imports
+    Instantiate {
+        component: u32,
+        args: Vec<(Name<'static>, SortIndex)>,
+    }, // 0x00
+    /// An instance made of existing definitions
+    FromExports(Vec<(Name<'static>, SortIndex)>), // 0x01
+}
+
+impl<'a> FromByteReader<'a> for ComponentInstance {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let kind = read_byte_as(reader, "instance", |byte| (byte <= 0x01).then_some(byte))?;
+        Ok(match kind {
+            0x00 => ComponentInstance::Instantiate {
+                component: reader.read_uleb128::<u32>()?,
+                args: read_items(reader, "argument", |reader| {
+                    Ok((read_name(reader)?, reader.read()?))
+                })?,
+            },
+            _ => ComponentInstance::FromExports(read_items(reader, "export", |reader| {
+                Ok((read_extern_name(reader)?, reader.read()?))
+            })?),
+        })
+    }
+}
+
+/// Makes a definition of another instance or an enclosing component available in this one
+#[derive(Debug, PartialEq)]
+pub struct Alias {
+    pub sort: Sort,
+    pub target: AliasTarget,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AliasTarget {
+    Export {
+        instance: u32,
+        name: Name<'static>,
+    }, // 0x00
+    CoreExport {
+        instance: u32,
+        name: Name<'static>,
+    }, // 0x01
+    /// The definition at `index` of the component `count` levels out
+    Outer {
+        count: u32,
+        index: u32,
+    }, // 0x02
+}
+
+impl<'a> FromByteReader<'a> for Alias {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let sort = reader.read()?;
+        let kind = read_byte_as(reader, "alias target", |byte| {
+            (byte <= 0x02).then_some(byte)
+        })?;
+        let target = match kind {
+            0x00 => AliasTarget::Export {
+                instance: reader.read_uleb128::<u32>()?,
+                name: read_name(reader)?,
+            },
+            0x01 => AliasTarget::CoreExport {
+                instance: reader.read_uleb128::<u32>()?,
+                name: read_name(reader)?,
+            },
+            _ => AliasTarget::Outer {
+                count: reader.read_uleb128::<u32>()?,
+                index: reader.read_uleb128::<u32>()?,
+            },
+        };
+        Ok(Alias { sort, target })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CoreType {
+    Function(FunctionType), // 0x60 ...
+    /// The imports and exports of a core module
+    Module(Vec<ModuleDeclaration>), // 0x50 ...
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ModuleDeclaration {
+    Import(Name<'static>, Name<'static>, ImportDesc), // 0x00
+    Type(CoreType),                                   // 0x01
+    /// An outer alias, the only kind of alias in a module type
+    Alias {
+        sort: CoreSort,
+        count: u32,
+        index: u32,
+    }, // 0x02
+    Export(Name<'static>, ImportDesc),                // 0x03
+}
+
+fn read_core_type(reader: &mut ByteReader, depth: usize) -> Result<CoreType, WasmParserError> {
+    if reader.peak::<u8>()? == 0x60 {
+        return Ok(CoreType::Function(reader.read()?));
+    }
+    read_byte_as(reader, "core type", |byte| (byte == 0x50).then_some(()))?;
+    if depth >= MAX_COMPONENT_NESTING_DEPTH {
+        return Err(nesting_limit(reader, "type nesting depth"));
+    }
+
+    let declarations = read_items(reader, "declaration", |reader| {
+        let kind = read_byte_as(reader, "module declaration", |byte| {
+            (byte <= 0x03).then_some(byte)
+        })?;
+        Ok(match kind {
+            0x00 => {
+                ModuleDeclaration::Import(read_name(reader)?, read_name(reader)?, reader.read()?)
+            }
+            0x01 => ModuleDeclaration::Type(read_core_type(reader, depth + 1)?),
+            0x02 => {
+                let sort = reader.read()?;
+                read_byte_as(reader, "alias target", |byte| (byte == 0x01).then_some(()))?;
+                ModuleDeclaration::Alias {
+                    sort,
+                    count: reader.read_uleb128::<u32>()?,
+                    index: reader.read_uleb128::<u32>()?,
+                }
+            }
+            _ => ModuleDeclaration::Export(read_name(reader)?, reader.read()?),
+        })
+    })?;
+    Ok(CoreType::Module(declarations))
+}
+
+/// The primitive value types of the component model, named like in WIT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimitiveType {
+    Bool,         // 0x7F
+    S8,           // 0x7E
+    U8,           // 0x7D
+    S16,          // 0x7C
+    U16,          // 0x7B
+    S32,          // 0x7A
+    U32,          // 0x79
+    S64,          // 0x78
+    U64,          // 0x77
+    F32,          // 0x76
+    F64,          // 0x75
+    Char,         // 0x74
+    String,       // 0x73
+    ErrorContext, // 0x64
+}
+
+impl PrimitiveType {
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0x7F => Some(PrimitiveType::Bool),
+            0x7E => Some(PrimitiveType::S8),
+            0x7D => Some(PrimitiveType::U8),
+            0x7C => Some(PrimitiveType::S16),
+            0x7B => Some(PrimitiveType::U16),
+            0x7A => Some(PrimitiveType::S32),
+            0x79 => Some(PrimitiveType::U32),
+            0x78 => Some(PrimitiveType::S64),
+            0x77 => Some(PrimitiveType::U64),
+            0x76 => Some(PrimitiveType::F32),
+            0x75 => Some(PrimitiveType::F64),
+            0x74 => Some(PrimitiveType::Char),
+            0x73 => Some(PrimitiveType::String),
+            0x64 => Some(PrimitiveType::ErrorContext),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PrimitiveType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::S8 => "s8",
+            PrimitiveType::U8 => "u8",
+            PrimitiveType::S16 => "s16",
+            PrimitiveType::U16 => "u16",
+            PrimitiveType::S32 => "s32",
+            PrimitiveType::U32 => "u32",
+            PrimitiveType::S64 => "s64",
+            PrimitiveType::U64 => "u64",
+            PrimitiveType::F32 => "f32",
+            PrimitiveType::F64 => "f64",
+            PrimitiveType::Char => "char",
+            PrimitiveType::String => "string",
+            PrimitiveType::ErrorContext => "error-context",
+        })
+    }
+}
+
+/// The type of a value passed to or from a component function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentValueType {
+    Primitive(PrimitiveType),
+    /// A type defined in the type index space, encoded as a positive s33
+    Type(u32),
+}
+
+impl<'a> FromByteReader<'a> for ComponentValueType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let offset = reader.get_current_offset();
+        let byte = reader.peak::<u8>()?;
+        if let Some(ty) = PrimitiveType::from_byte(byte) {
+            reader.jump(1);
+            return Ok(ComponentValueType::Primitive(ty));
+        }
+
+        // Primitive types are single byte negative s33s, so any other negative one is unknown
+        let index = reader.read_s33().context(reader, "value type")?;
+        u32::try_from(index)
+            .map(ComponentValueType::Type)
+            .map_err(|_| {
+                WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedByte {
+                        construct: "value type",
+                        byte,
+                    },
+                )
+            })
+    }
+}
+
+fn read_optional_value_type(
+    reader: &mut ByteReader,
+) -> Result<Option<ComponentValueType>, WasmParserError> {
+    read_optional(reader, "optional value type", |reader| reader.read())
+}
+
+/// A value type defined in the type section
+#[derive(Debug, PartialEq)]
+pub enum DefinedType {
+    Primitive(PrimitiveType),
+    Record(Vec<(Name<'static>, ComponentValueType)>), // 0x72
+    Variant(Vec<VariantCase>),                        // 0x71
+    List(ComponentValueType),                         // 0x70
+    Tuple(Vec<ComponentValueType>),                   // 0x6F
+    Flags(Vec<Name<'static>>),                        // 0x6E
+    Enum(Vec<Name<'static>>),                         // 0x6D
+    Option(ComponentValueType),                       // 0x6B
+    Result {
+        ok: Option<ComponentValueType>,
+        err: Option<ComponentValueType>,
+    }, // 0x6A
+    /// An owned handle to a resource
+    Own(u32),    // 0x69
+    /// A borrowed handle to a resource
+    Borrow(u32), // 0x68
+    Future(Option<ComponentValueType>),               // 0x65
+    Stream(Option<ComponentValueType>),               // 0x66
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VariantCase {
+    pub name: Name<'static>,
+    pub ty: Option<ComponentValueType>,
+    /// The case this one refines, which older encoders write and newer ones always leave out
+    pub refines: Option<u32>,
+}
+
+impl<'a> FromByteReader<'a> for DefinedType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let offset = reader.get_current_offset();
+        let byte = reader.read::<u8>()?;
+        if let Some(ty) = PrimitiveType::from_byte(byte) {
+            return Ok(DefinedType::Primitive(ty));
+        }
+        let names = |reader: &mut ByteReader| read_items(reader, "name", read_name);
+
+        Ok(match byte {
+            0x72 => DefinedType::Record(read_items(reader, "field", |reader| {
+                Ok((read_name(reader)?, reader.read()?))
+            })?),
+            0x71 => DefinedType::Variant(read_items(reader, "case", |reader| {
+                Ok(VariantCase {
+                    name: read_name(reader)?,
+                    ty: read_optional_value_type(reader)?,
+                    refines: read_optional(reader, "case refinement", |reader| {
+                        Ok(reader.read_uleb128::<u32>()?)
+                    })?,
+                })
+            })?),
+            0x70 => DefinedType::List(reader.read()?),
+            0x6F => DefinedType::Tuple(read_items(reader, "type", |reader| reader.read())?),
+            0x6E => DefinedType::Flags(names(reader)?),
+            0x6D => DefinedType::Enum(names(reader)?),
+            0x6B => DefinedType::Option(reader.read()?),
+            0x6A => DefinedType::Result {
+                ok: read_optional_value_type(reader)?,
+                err: read_optional_value_type(reader)?,
+            },
+            0x69 => DefinedType::Own(reader.read_uleb128::<u32>()?),
+            0x68 => DefinedType::Borrow(reader.read_uleb128::<u32>()?),
+            0x66 => DefinedType::Stream(read_optional_value_type(reader)?),
+            0x65 => DefinedType::Future(read_optional_value_type(reader)?),
+            byte => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedByte {
+                        construct: "component type",
+                        byte,
+                    },
+                ))
+            }
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ComponentFunctionType {
+    pub params: Vec<(Name<'static>, ComponentValueType)>,
+    pub results: ComponentResults,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ComponentResults {
+    /// A single result without a name
+    Unnamed(ComponentValueType), // 0x00 t
+    /// Named results, which are empty for functions without results
+    Named(Vec<(Name<'static>, ComponentValueType)>), // 0x01 ...
+}
+
+impl<'a> FromByteReader<'a> for ComponentFunctionType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let params = read_items(reader, "param", |reader| {
+            Ok((read_name(reader)?, reader.read()?))
+        })?;
+        let results =
+            match read_byte_as(reader, "result list", |byte| (byte <= 0x01).then_some(byte))? {
+                0x00 => ComponentResults::Unnamed(reader.read()?),
+                _ => ComponentResults::Named(read_items(reader, "result", |reader| {
+                    Ok((read_name(reader)?, reader.read()?))
+                })?),
+            };
+        Ok(ComponentFunctionType { params, results })
+    }
+}
+
+/// An entry of the type section
+#[derive(Debug, PartialEq)]
+pub enum ComponentType {
+    Defined(DefinedType),
+    Function(ComponentFunctionType), // 0x40 ...
+    /// The imports and exports of a component
+    Component(Vec<TypeDeclaration>), // 0x41 ...
+    /// The exports of an instance, declarations never contain imports
+    Instance(Vec<TypeDeclaration>), // 0x42 ...
+    /// A resource type with its optional destructor, a core function
+    Resource {
+        destructor: Option<u32>,
+    }, // 0x3F 0x7F ...
+}
+
+/// A declaration in a component or instance type
+#[derive(Debug, PartialEq)]
+pub enum TypeDeclaration {
+    CoreType(CoreType),                             // 0x00
+    Type(ComponentType),                            // 0x01
+    Alias(Alias),                                   // 0x02
+    Import(ComponentImport),                        // 0x03
+    Export { name: Name<'static>, ty: ExternDesc }, // 0x04
+}
+
+fn read_component_type(
+    reader: &mut ByteReader,
+    depth: usize,
+) -> Result<ComponentType, WasmParserError> {
+    let declarations = |reader: &mut ByteReader, imports: bool| {
+        if depth >= MAX_COMPONENT_NESTING_DEPTH {
+            return Err(nesting_limit(reader, "type nesting depth"));
+        }
+        read_items(reader, "declaration", |reader| {
+            let kind = read_byte_as(reader, "type declaration", |byte| match byte {
+                0x03 => imports.then_some(byte),
+                _ => (byte <= 0x04).then_some(byte),
+            })?;
+            Ok(match kind {
+                0x00 => TypeDeclaration::CoreType(read_core_type(reader, depth + 1)?),
+                0x01 => TypeDeclaration::Type(read_component_type(reader, depth + 1)?),
+                0x02 => TypeDeclaration::Alias(reader.read()?),
+                0x03 => TypeDeclaration::Import(reader.read()?),
+                _ => TypeDeclaration::Export {
+                    name: read_extern_name(reader)?,
+                    ty: reader.read()?,
+                },
+            })
+        })
+    };
+
+    Ok(match reader.peak::<u8>()? {
+        0x40 => {
+            reader.jump(1);
+            ComponentType::Function(reader.read()?)
+        }
+        0x41 => {
+            reader.jump(1);
+            ComponentType::Component(declarations(reader, true)?)
+        }
+        0x42 => {
+            reader.jump(1);
+            ComponentType::Instance(declarations(reader, false)?)
+        }
+        0x3F => {
+            reader.jump(1);
+            // The representation of a resource, i32 is the only one
+            read_byte_as(reader, "resource representation", |byte| {
+                (byte == 0x7F).then_some(())
+            })?;
+            ComponentType::Resource {
+                destructor: read_optional(reader, "resource destructor", |reader| {
+                    Ok(reader.read_uleb128::<u32>()?)
+                })?,
+            }
+        }
+        _ => ComponentType::Defined(reader.read()?),
+    })
+}
+
+/// The type of an import or export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternDesc {
+    /// A core module of the core type at the index
+    CoreModule(u32), // 0x00 0x11
+    Func(u32),         // 0x01
+    Value(ValueBound), // 0x02
+    Type(TypeBound),   // 0x03
+    Component(u32),    // 0x04
+    Instance(u32),     // 0x05
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueBound {
+    /// The same value as the one at the index
+    Eq(u32), // 0x00
+    Type(ComponentValueType), // 0x01
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeBound {
+    /// The same type as the one at the index
+    Eq(u32), // 0x00
+    /// A new resource type
+    SubResource, // 0x01
+}
+
+impl<'a> FromByteReader<'a> for ExternDesc {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let kind = read_byte_as(reader, "extern description", |byte| {
+            (byte <= 0x05).then_some(byte)
+        })?;
+        Ok(match kind {
+            0x00 => {
+                read_byte_as(reader, "extern description", |byte| {
+                    (byte == 0x11).then_some(())
+                })?;
+                ExternDesc::CoreModule(reader.read_uleb128::<u32>()?)
+            }
+            0x01 => ExternDesc::Func(reader.read_uleb128::<u32>()?),
+            0x02 => ExternDesc::Value(
+                match read_byte_as(reader, "value bound", |byte| (byte <= 0x01).then_some(byte))? {
+                    0x00 => ValueBound::Eq(reader.read_uleb128::<u32>()?),
+                    _ => ValueBound::Type(reader.read()?),
+                },
+            ),
+            0x03 => ExternDesc::Type(
+                match read_byte_as(reader, "type bound", |byte| (byte <= 0x01).then_some(byte))? {
+                    0x00 => TypeBound::Eq(reader.read_uleb128::<u32>()?),
+                    _ => TypeBound::SubResource,
+                },
+            ),
+            0x04 => ExternDesc::Component(reader.read_uleb128::<u32>()?),
+            _ => ExternDesc::Instance(reader.read_uleb128::<u32>()?),
+        })
+    }
+}
+
+/// Reads the name of an import or export. The prefix byte is 0x00, or 0x01 in binaries of older encoders
+fn read_extern_name(reader: &mut ByteReader) -> Result<Name<'static>, WasmParserError> {
+    read_byte_as(reader, "extern name", |byte| (byte <= 0x01).then_some(()))?;
+    read_name(reader)
+}
+
+/// An import of the component, `name` is a plain WIT name like `log` or an interface like `wasi:cli/stdout@0.2.0`
+#[derive(Debug, PartialEq)]
+pub struct ComponentImport {
+    pub name: Name<'static>,
+    pub ty: ExternDesc,
+}
+
+impl<'a> FromByteReader<'a> for ComponentImport {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        Ok(ComponentImport {
+            name: read_extern_name(reader)?,
+            ty: reader.read()?,
+        })
+    }
+}
+
+/// An export of the component, `ty` optionally ascribes a type to the exported definition
+#[derive(Debug, PartialEq)]
+pub struct ComponentExport {
+    pub name: Name<'static>,
+    pub index: SortIndex,
+    pub ty: Option<ExternDesc>,
+}
+
+impl<'a> FromByteReader<'a> for ComponentExport {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        Ok(ComponentExport {
+            name: read_extern_name(reader)?,
+            index: reader.read()?,
+            ty: read_optional(reader, "export type", |reader| reader.read())?,
+        })
+    }
+}
+
+/// A function of the canon section, which converts between core functions and component functions
+#[derive(Debug, PartialEq)]
+pub enum Canonical {
+    /// A component function of the type at `ty`, implemented by a core function
+    Lift {
+        core_function: u32,
+        options: Vec<CanonicalOption>,
+        ty: u32,
+    }, // 0x00 0x00
+    /// A core function calling a component function
+    Lower {
+        function: u32,
+        options: Vec<CanonicalOption>,
+    }, // 0x01 0x00
+    ResourceNew(u32),  // 0x02
+    ResourceDrop(u32), // 0x03
+    ResourceRep(u32),  // 0x04
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalOption {
+    Utf8,            // 0x00
+    Utf16,           // 0x01
+    CompactUtf16,    // 0x02
+    Memory(u32),     // 0x03
+    Realloc(u32),    // 0x04
+    PostReturn(u32), // 0x05
+    Async,           // 0x06
+    Callback(u32),   // 0x07
+}
+
+impl<'a> FromByteReader<'a> for CanonicalOption {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let kind = read_byte_as(reader, "canonical option", |byte| {
+            (byte <= 0x07).then_some(byte)
+        })?;
+        Ok(match kind {
+            0x00 => CanonicalOption::Utf8,
+            0x01 => CanonicalOption::Utf16,
+            0x02 => CanonicalOption::CompactUtf16,
+            0x03 => CanonicalOption::Memory(reader.read_uleb128::<u32>()?),
+            0x04 => CanonicalOption::Realloc(reader.read_uleb128::<u32>()?),
+            0x05 => CanonicalOption::PostReturn(reader.read_uleb128::<u32>()?),
+            0x06 => CanonicalOption::Async,
+            _ => CanonicalOption::Callback(reader.read_uleb128::<u32>()?),
+        })
+    }
+}
+
+impl<'a> FromByteReader<'a> for Canonical {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let kind = read_byte_as(reader, "canonical function", |byte| {
+            (byte <= 0x04).then_some(byte)
+        })?;
+        // Lifting and lowering are followed by a byte that's always 0x00
+        let function = |reader: &mut ByteReader| -> Result<u32, WasmParserError> {
+            read_byte_as(reader, "canonical function", |byte| {
+                (byte == 0x00).then_some(())
+            })?;
+            Ok(reader.read_uleb128::<u32>()?)
+        };
+        let options =
+            |reader: &mut ByteReader| read_items(reader, "option", |reader| reader.read());
+
+        Ok(match kind {
+            0x00 => Canonical::Lift {
+                core_function: function(reader)?,
+                options: options(reader)?,
+                ty: reader.read_uleb128::<u32>()?,
+            },
+            0x01 => Canonical::Lower {
+                function: function(reader)?,
+                options: options(reader)?,
+            },
+            0x02 => Canonical::ResourceNew(reader.read_uleb128::<u32>()?),
+            0x03 => Canonical::ResourceDrop(reader.read_uleb128::<u32>()?),
+            _ => Canonical::ResourceRep(reader.read_uleb128::<u32>()?),
+        })
+    }
+}
+
+/// The start function of the component, called with values from the value index space
+#[derive(Debug, PartialEq)]
+pub struct ComponentStart {
+    pub function: u32,
+    pub args: Vec<u32>,
+    /// The number of values the function returns
+    pub results: u32,
+}
+
+impl<'a> FromByteReader<'a> for ComponentStart {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        Ok(ComponentStart {
+            function: reader.read_uleb128::<u32>()?,
+            args: read_items(reader, "argument", |reader| {
+                Ok(reader.read_uleb128::<u32>()?)
+            })?,
+            results: reader.read_uleb128::<u32>()?,
+        })
+    }
+}
@@ -0,0 +1,120 @@
+use bytereader::{ByteReader, FromByteReader};
+use std::{any::Any, borrow::Cow, collections::HashMap, fmt::Debug, ops::Range};
+
+use crate::{
+    error::{ResultExt, WasmParserError},
+    types::Name,
+};
+
+/// A custom section (id 0), kept as raw bytes alongside anything a registered decoder made of it
+#[derive(Debug)]
+pub struct CustomSection<'a> {
+    pub name: Name<'a>,
+    /// The section contents following the name, borrowed from the module bytes when parsed from them
+    pub data: Cow<'a, [u8]>,
+    /// The byte range of `data` in the module
+    pub range: Range<usize>,
+    /// The id of the last non-custom section before this one, `None` if it comes before all of them.
+    /// Encoding a module writes the section back at the same position
+    pub after: Option<u8>,
+    /// The result of the decoder registered for the section name, if there is one
+    pub decoded: Option<Result<Box<dyn DecodedCustomSection>, WasmParserError>>,
+    /// Why a `name`, `linking` or `reloc.*` section couldn't be decoded into its field of
+    /// [`crate::sections::WasmSections`]. The field is left empty instead of failing the module parse
+    pub decode_error: Option<Box<WasmParserError>>,
+}
+
+impl CustomSection<'_> {
+    /// Returns the decoded value if a decoder producing `T` was registered for this section.
+    /// Decoding failures are returned as errors instead of aborting the module parse
+    pub fn decoded<T: Any>(&self) -> Option<Result<&T, &WasmParserError>> {
+        match self.decoded.as_ref()? {
+            Ok(value) => (**value).as_any().downcast_ref::<T>().map(Ok),
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    pub fn into_owned(self) -> CustomSection<'static> {
+        CustomSection {
+            name: self.name.into_owned(),
+            data: Cow::Owned(self.data.into_owned()),
+            range: self.range,
+            after: self.after,
+            decoded: self.decoded,
+            decode_error: self.decode_error,
+        }
+    }
+}
+
+/// Compares name, contents and position only, `range`, `decoded` and `decode_error` follow from where the
+/// section was parsed and which decoders were registered
+impl PartialEq for CustomSection<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.data == other.data && self.after == other.after
+    }
+}
+
+/// A value produced by a custom section decoder
+pub trait DecodedCustomSection: Any + Debug {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Debug> DecodedCustomSection for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+type Decoder =
+    Box<dyn Fn(&mut ByteReader) -> Result<Box<dyn DecodedCustomSection>, WasmParserError>>;
+
+/// Decoders for custom sections, keyed by section name.
+///
+/// ```ignore
+/// let mut registry = CustomSectionRegistry::new();
+/// registry.register::<BuildId>("build_id");
+///
+/// let module = WasmModule::from_bytes_with_registry(&bytes, &registry)?;
+/// let build_id = module.sections.custom_section("build_id").and_then(|s| s.decoded::<BuildId>());
+/// ```
+#[derive(Default)]
+pub struct CustomSectionRegistry {
+    decoders: HashMap<String, Decoder>,
+}
+
+impl CustomSectionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes every custom section called `name` as `T`, replacing any decoder already registered for the name.
+    /// The reader passed to `T` is bounded to the section contents
+    pub fn register<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: for<'a> FromByteReader<'a> + Debug + 'static,
+        for<'a> WasmParserError: From<<T as FromByteReader<'a>>::Error>,
+    {
+        self.decoders.insert(
+            name.to_string(),
+            Box::new(|reader| Ok(Box::new(T::read_from_byte_reader(reader)?))),
+        );
+        self
+    }
+
+    /// Decodes the section contents at `range` in a window over `reader`, leaving the reader at an unspecified offset
+    pub(crate) fn decode(
+        &self,
+        name: &str,
+        reader: &mut ByteReader,
+        range: Range<usize>,
+    ) -> Option<Result<Box<dyn DecodedCustomSection>, WasmParserError>> {
+        let decoder = self.decoders.get(name)?;
+        reader.move_to(range.start);
+        if let Err(err) = reader.push_limit(range.len()) {
+            return Some(Err(err.into()));
+        }
+        let decoded = decoder(reader).with_context(reader, || format!("custom section '{name}'"));
+        reader.pop_limit();
+        Some(decoded)
+    }
+}
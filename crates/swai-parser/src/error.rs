@@ -1,8 +1,26 @@
-use bytereader::ByteReaderError;
+use crate::types::{ReferenceTypes, ValueType};
+use bytereader::{ByteReader, ByteReaderError};
+use std::fmt;
 use thiserror::Error;
 
+/// Number of bytes shown on each line of the hex snippet
+const SNIPPET_WIDTH: usize = 16;
+
+/// An error from parsing a module, along with where in the input it happened
+#[derive(Debug)]
+pub struct WasmParserError {
+    pub kind: WasmParserErrorKind,
+    /// The absolute offset in the module bytes the error points at, `None` if it didn't come from reading them
+    pub offset: Option<usize>,
+    /// Where the parser was when the error happened, outermost first.
+    /// For example `["code section", "function 12", "instruction 33", "memarg"]`
+    pub context: Vec<String>,
+    /// The input bytes around `offset`, captured so the error can be displayed on its own
+    snippet: Option<(usize, Vec<u8>)>,
+}
+
 #[derive(Error, Debug)]
-pub enum WasmParserError {
+pub enum WasmParserErrorKind {
     #[error("Failed to parse bytes: '{bytes:?}' into a string")]
     StringFromBytes { bytes: Vec<u8> },
 
@@ -12,13 +30,369 @@ pub enum WasmParserError {
     #[error("Unknown section id: {id}")]
     InvalidSectionId { id: u8 },
 
+    #[error("Duplicate {section}")]
+    DuplicateSection { section: &'static str },
+
+    #[error("The {section} has to come before the {previous}")]
+    SectionOutOfOrder {
+        section: &'static str,
+        previous: &'static str,
+    },
+
     #[error("Invalid wasm bytes: '{message}'")]
     InvalidWasmBytes { message: String },
 
+    #[error("Unexpected end of input, {needed} more bytes are needed")]
+    UnexpectedEof { needed: usize },
+
+    #[error("Unexpected byte 0x{byte:02X} while parsing {construct}")]
+    UnexpectedByte { construct: &'static str, byte: u8 },
+
+    #[error("Unexpected value {value} while parsing {construct}")]
+    UnexpectedValue { construct: &'static str, value: u32 },
+
+    #[error("{construct} exceeds the implementation limit of {limit}")]
+    ImplementationLimit {
+        construct: &'static str,
+        limit: usize,
+    },
+
+    #[error("{construct} declares a size of {declared} bytes, but {consumed} bytes were read")]
+    SizeMismatch {
+        construct: &'static str,
+        declared: usize,
+        consumed: usize,
+    },
+
+    #[error("{kind} have to be imported before any are defined")]
+    ImportAfterDefinition { kind: &'static str },
+
+    #[error("{message} at line {line}, column {column}")]
+    InvalidText {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+
     // From other error types
-    #[error("Failed to parse wasm bytes. Reader error: {0:#?}")]
+    #[error("Failed to parse wasm bytes. Reader error: {0}")]
     ParserError(#[from] ByteReaderError),
 
-    #[error("I/O error: {0:#?}")]
+    #[error("I/O error: {0}")]
     IOError(#[from] std::io::Error),
 }
+
+impl WasmParserError {
+    pub fn new(kind: WasmParserErrorKind) -> Self {
+        Self {
+            kind,
+            offset: None,
+            context: vec![],
+            snippet: None,
+        }
+    }
+
+    /// An error pointing at the absolute `offset` in the module bytes
+    pub fn at(offset: usize, kind: WasmParserErrorKind) -> Self {
+        Self {
+            offset: Some(offset),
+            ..Self::new(kind)
+        }
+    }
+
+    /// The context joined into a single breadcrumb, e.g. `code section › function 12 › instruction 33`
+    pub fn context_path(&self) -> String {
+        self.context.join(" › ")
+    }
+
+    /// Moves the error by `base` bytes, for errors from a reader over part of the module
+    pub(crate) fn offset_by(mut self, base: usize) -> Self {
+        self.offset = self.offset.map(|offset| offset + base);
+        if let Some((start, _)) = &mut self.snippet {
+            *start += base;
+        }
+        self
+    }
+
+    /// Adds an outer level of context. The first time this is called the error is also tied to the
+    /// bytes of `reader`: errors without an offset point at where the reader stopped
+    pub(crate) fn in_context(mut self, reader: &ByteReader, context: String) -> Self {
+        let offset = *self.offset.get_or_insert(reader.get_current_offset());
+        if self.snippet.is_none() {
+            let start = (offset / SNIPPET_WIDTH).saturating_sub(1) * SNIPPET_WIDTH;
+            let bytes = reader.as_slice();
+            let end = (start + SNIPPET_WIDTH * 2).min(bytes.len());
+            self.snippet = bytes.get(start..end).map(|bytes| (start, bytes.to_vec()));
+        }
+        self.context.insert(0, context);
+        self
+    }
+}
+
+impl fmt::Display for WasmParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        let Some(offset) = self.offset else {
+            return Ok(());
+        };
+        write!(f, "\n  at offset {offset} (0x{offset:X})")?;
+        if !self.context.is_empty() {
+            write!(f, " in {}", self.context_path())?;
+        }
+
+        let Some((start, bytes)) = &self.snippet else {
+            return Ok(());
+        };
+        for (row, line) in bytes.chunks(SNIPPET_WIDTH).enumerate() {
+            let line_start = start + row * SNIPPET_WIDTH;
+            let hex = line
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            write!(f, "\n  {line_start:08x} | {hex}")?;
+
+            if (line_start..line_start + SNIPPET_WIDTH).contains(&offset) {
+                let column = (offset - line_start) * 3;
+                write!(f, "\n  {:8} | {:column$}^^", "", "")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WasmParserError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+impl From<WasmParserErrorKind> for WasmParserError {
+    fn from(kind: WasmParserErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<ByteReaderError> for WasmParserError {
+    fn from(err: ByteReaderError) -> Self {
+        Self::new(err.into())
+    }
+}
+
+impl From<std::io::Error> for WasmParserError {
+    fn from(err: std::io::Error) -> Self {
+        Self::new(err.into())
+    }
+}
+
+/// Attaches parser context to the errors of a result, see [`WasmParserError::in_context`]
+pub(crate) trait ResultExt<T> {
+    fn context(self, reader: &ByteReader, context: &str) -> Result<T, WasmParserError>;
+
+    fn with_context(
+        self,
+        reader: &ByteReader,
+        context: impl FnOnce() -> String,
+    ) -> Result<T, WasmParserError>;
+}
+
+impl<T, E: Into<WasmParserError>> ResultExt<T> for Result<T, E> {
+    fn context(self, reader: &ByteReader, context: &str) -> Result<T, WasmParserError> {
+        self.with_context(reader, || context.to_string())
+    }
+
+    fn with_context(
+        self,
+        reader: &ByteReader,
+        context: impl FnOnce() -> String,
+    ) -> Result<T, WasmParserError> {
+        self.map_err(|err| err.into().in_context(reader, context()))
+    }
+}
+
+/// An error from validating a module, see [`crate::validation::validate`]
+#[derive(Debug)]
+pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    /// The function the error is in, as an index into the function index space (imports first)
+    pub function: Option<u32>,
+    /// The instruction the error is at, counting every opcode of the body including `else` and `end`,
+    /// the same way the parser does in [`WasmParserError::context`]
+    pub instruction: Option<usize>,
+    /// Where the validator was, outermost first. For example `["function 3", "instruction 12"]`
+    pub context: Vec<String>,
+}
+
+impl ValidationError {
+    pub(crate) fn new(kind: ValidationErrorKind, context: &str) -> Self {
+        Self {
+            kind,
+            function: None,
+            instruction: None,
+            context: vec![context.to_string()],
+        }
+    }
+
+    pub(crate) fn in_function(
+        kind: ValidationErrorKind,
+        function: u32,
+        instruction: usize,
+    ) -> Self {
+        Self {
+            kind,
+            function: Some(function),
+            instruction: Some(instruction),
+            context: vec![
+                format!("function {function}"),
+                format!("instruction {instruction}"),
+            ],
+        }
+    }
+
+    /// The context joined into a single breadcrumb, e.g. `function 3 › instruction 12`
+    pub fn context_path(&self) -> String {
+        self.context.join(" › ")
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.context.is_empty() {
+            write!(f, "\n  in {}", self.context_path())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ValidationErrorKind {
+    #[error("Type mismatch: expected {expected}, found {actual}")]
+    TypeMismatch {
+        expected: ValueType,
+        actual: ValueType,
+    },
+
+    #[error("Type mismatch: expected {expected}, but the operand stack is empty")]
+    StackUnderflow { expected: ValueType },
+
+    #[error("Expected an operand, but the operand stack is empty")]
+    MissingOperand,
+
+    #[error("Type mismatch: expected {expected}, found {actual}")]
+    InvalidOperand {
+        expected: &'static str,
+        actual: ValueType,
+    },
+
+    #[error("Type mismatch: expected {expected}, found {actual}")]
+    ReferenceTypeMismatch {
+        expected: ReferenceTypes,
+        actual: ReferenceTypes,
+    },
+
+    #[error("{count} values are left on the operand stack at the end of the block")]
+    UnexpectedOperands { count: usize },
+
+    #[error("An 'if' without an 'else' has to have the same parameter and result types")]
+    MissingElse,
+
+    #[error("The labels of a 'br_table' have to have the same arity, expected {expected} but found {actual}")]
+    LabelArityMismatch { expected: usize, actual: usize },
+
+    #[error("A typed 'select' has to have exactly one type, found {count}")]
+    SelectArity { count: usize },
+
+    #[error("Unknown {space} {index}")]
+    UnknownIndex { space: &'static str, index: u32 },
+
+    #[error("Global {index} is immutable")]
+    ImmutableGlobal { index: u32 },
+
+    #[error("Alignment 2^{align} is larger than the natural alignment 2^{natural}")]
+    InvalidAlignment { align: u32, natural: u32 },
+
+    #[error(
+        "Alignment 2^{align} of an atomic access differs from the natural alignment 2^{natural}"
+    )]
+    InvalidAtomicAlignment { align: u32, natural: u32 },
+
+    #[error("Lane index {lane} is out of range for {lanes} lanes")]
+    InvalidLaneIndex { lane: u8, lanes: u8 },
+
+    #[error("Only constant instructions are allowed in a constant expression")]
+    NonConstantInstruction,
+
+    #[error("Function {index} is referenced with 'ref.func' but isn't declared outside of function bodies")]
+    UndeclaredFunctionReference { index: u32 },
+
+    #[error("'memory.init' and 'data.drop' require a data count section")]
+    MissingDataCount,
+
+    #[error("The number of locals exceeds {limit}")]
+    TooManyLocals { limit: u32 },
+
+    #[error("The limits minimum {min} is larger than the maximum {max}")]
+    InvalidLimits { min: u64, max: u64 },
+
+    #[error("Tables can't be shared")]
+    SharedTable,
+
+    #[error("{construct} of {value} exceeds the limit of {limit}")]
+    LimitExceeded {
+        construct: &'static str,
+        value: u64,
+        limit: u64,
+    },
+
+    #[error("At most one memory is allowed, found {count}")]
+    MultipleMemories { count: usize },
+
+    #[error("Duplicate import '{module}'::'{name}'")]
+    DuplicateImport { module: String, name: String },
+
+    #[error("Duplicate export name '{name}'")]
+    DuplicateExport { name: String },
+
+    #[error("The start function has to take no parameters and return no results")]
+    InvalidStartFunction,
+
+    #[error("The type of a tag has to have no results")]
+    InvalidTagType,
+
+    #[error("A tail call returns {actual:?} from a function that returns {expected:?}")]
+    TailCallTypeMismatch {
+        expected: Vec<ValueType>,
+        actual: Vec<ValueType>,
+    },
+
+    #[error("A catch clause passes {actual:?} to a label that expects {expected:?}")]
+    CatchTypeMismatch {
+        expected: Vec<ValueType>,
+        actual: Vec<ValueType>,
+    },
+
+    #[error(
+        "Local {index} is read before it's set, which is required for non-nullable references"
+    )]
+    UninitializedLocal { index: u32 },
+
+    #[error("The label of a 'br_on_non_null' has to take a reference last, label {label} doesn't")]
+    InvalidBranchOnNonNull { label: u32 },
+
+    #[error("Tables can't have non-nullable elements of type {elem}")]
+    NonNullableTable { elem: ReferenceTypes },
+
+    #[error("The function section declares {functions} functions but the code section has {bodies} bodies")]
+    FunctionCountMismatch { functions: usize, bodies: usize },
+
+    #[error("The data count section declares {declared} data segments but the data section has {actual}")]
+    DataCountMismatch { declared: u32, actual: usize },
+}
@@ -1,88 +1,269 @@
-use bytereader::{ByteReader, ByteReaderError, FromByteReader};
+use bytereader::{ByteReader, ByteWriter, FromByteReader, ToByteWriter};
 
-use crate::{error::WasmParserError, leb128::Leb128Readers, types::Indecies};
+use crate::{
+    error::{ResultExt, WasmParserError, WasmParserErrorKind},
+    leb128::{Leb128Readers, Leb128Writers},
+    types::{read_byte_as, read_vec, write_vec, Expr, Indecies, ReferenceTypes, ValueType},
+};
+
+/// The type of a `block`, `loop` or `if` instruction
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockType {
+    Empty,            // 0x40
+    Value(ValueType), // t
+    /// A function type giving the block its params and results, encoded as a positive s33
+    TypeIndex(u32),
+}
+
+impl<'a> FromByteReader<'a> for BlockType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let offset = reader.get_current_offset();
+        let byte = reader.peak::<u8>()?;
+        if byte == 0x40 {
+            reader.jump(1);
+            return Ok(BlockType::Empty);
+        }
+        if let Some(ty) = ValueType::from_byte(byte) {
+            reader.jump(1);
+            return Ok(BlockType::Value(ty));
+        }
+        if byte == 0x63 || byte == 0x64 {
+            return Ok(BlockType::Value(reader.read()?));
+        }
+
+        // Value types are single byte negative s33s, so any other negative one is unknown
+        let index = reader.read_s33().context(reader, "block type")?;
+        u32::try_from(index).map(BlockType::TypeIndex).map_err(|_| {
+            WasmParserError::at(
+                offset,
+                WasmParserErrorKind::UnexpectedByte {
+                    construct: "block type",
+                    byte,
+                },
+            )
+        })
+    }
+}
+
+impl ToByteWriter for BlockType {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        match self {
+            BlockType::Empty => writer.write(&0x40u8),
+            BlockType::Value(ty) => writer.write(ty),
+            BlockType::TypeIndex(index) => writer.write_leb128(*index as i64),
+        };
+    }
+}
+
+/// A catch clause of a `try_table`, the labels are relative to the block around the `try_table`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Catch {
+    Catch(Indecies, Indecies),    // 0x00 x l
+    CatchRef(Indecies, Indecies), // 0x01 x l
+    CatchAll(Indecies),           // 0x02 l
+    CatchAllRef(Indecies),        // 0x03 l
+}
+
+impl Catch {
+    /// The label the clause branches to
+    pub fn label(&self) -> &Indecies {
+        match self {
+            Catch::Catch(_, label)
+            | Catch::CatchRef(_, label)
+            | Catch::CatchAll(label)
+            | Catch::CatchAllRef(label) => label,
+        }
+    }
+
+    /// The tag the clause catches, `None` if it catches every exception
+    pub fn tag(&self) -> Option<&Indecies> {
+        match self {
+            Catch::Catch(tag, _) | Catch::CatchRef(tag, _) => Some(tag),
+            Catch::CatchAll(_) | Catch::CatchAllRef(_) => None,
+        }
+    }
+
+    /// Whether the clause also passes the caught exception as an `exnref`
+    pub fn is_ref(&self) -> bool {
+        matches!(self, Catch::CatchRef(..) | Catch::CatchAllRef(_))
+    }
+}
+
+impl<'a> FromByteReader<'a> for Catch {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let kind = read_byte_as(reader, "catch clause", |byte| {
+            (byte <= 0x03).then_some(byte)
+        })?;
+        Ok(match kind {
+            0x00 => Catch::Catch(
+                read_index(reader, Indecies::TagIdx)?,
+                read_index(reader, Indecies::LabelIdx)?,
+            ),
+            0x01 => Catch::CatchRef(
+                read_index(reader, Indecies::TagIdx)?,
+                read_index(reader, Indecies::LabelIdx)?,
+            ),
+            0x02 => Catch::CatchAll(read_index(reader, Indecies::LabelIdx)?),
+            _ => Catch::CatchAllRef(read_index(reader, Indecies::LabelIdx)?),
+        })
+    }
+}
+
+impl ToByteWriter for Catch {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        match self {
+            Catch::Catch(tag, label) => writer.write(&0x00u8).write(tag).write(label),
+            Catch::CatchRef(tag, label) => writer.write(&0x01u8).write(tag).write(label),
+            Catch::CatchAll(label) => writer.write(&0x02u8).write(label),
+            Catch::CatchAllRef(label) => writer.write(&0x03u8).write(label),
+        };
+    }
+}
+
+/// The memory immediate of load and store instructions
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemArg {
+    pub align: u32,
+    /// Offsets can only exceed 32 bits for memories with 64 bit limits
+    pub offset: u64,
+}
+
+impl<'a> FromByteReader<'a> for MemArg {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        Ok(MemArg {
+            align: reader.read_uleb128::<u32>().context(reader, "memarg")?,
+            offset: reader.read_uleb128::<u64>().context(reader, "memarg")?,
+        })
+    }
+}
+
+impl ToByteWriter for MemArg {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer.write_uleb128(self.align).write_uleb128(self.offset);
+    }
+}
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instructions {
     // Control Instructions
-    BlockType,      // 0x40
-    Unreachable,    // 0x00
-    Nop,            // 0x01
-    Block,          // 0x02
-    Loop,           // 0x03
-    IfElse,         // 0x04  0x0B
-    IfElseJmp,      // 0x04  0x05
-    Br,             // 0x0C
-    BrIf,           // 0x0D
-    BrTable,        // 0x0E
-    Return,         // 0x0F
+    Unreachable, // 0x00
+    Nop,         // 0x01
+    Block {
+        ty: BlockType,
+        body: Expr,
+    }, // 0x02 ... 0x0B
+    Loop {
+        ty: BlockType,
+        body: Expr,
+    }, // 0x03 ... 0x0B
+    If {
+        ty: BlockType,
+        then: Expr,
+        else_: Expr,
+    }, // 0x04 ... (0x05 ...) 0x0B
+    Br(Indecies), // 0x0C
+    BrIf(Indecies), // 0x0D
+    BrTable(Vec<Indecies>, Indecies), // 0x0E
+    Return,      // 0x0F
     Call(Indecies), // 0x10
-    CallIndirect,   // 0x11
+    CallIndirect(Indecies, Indecies), // 0x11
+    ReturnCall(Indecies), // 0x12
+    ReturnCallIndirect(Indecies, Indecies), // 0x13
+    CallRef(Indecies), // 0x14
+    ReturnCallRef(Indecies), // 0x15
+    BrOnNull(Indecies), // 0xD5
+    BrOnNonNull(Indecies), // 0xD6
+
+    // Exception Handling Instructions
+    Throw(Indecies), // 0x08
+    ThrowRef,        // 0x0A
+    TryTable {
+        ty: BlockType,
+        catches: Vec<Catch>,
+        body: Expr,
+    }, // 0x1F ... 0x0B
 
     // Reference Instructions
-    RefNull,   // 0xD0
-    RefIsNull, // 0xD1
-    RefFunc,   // 0xD2
+    RefNull(ReferenceTypes), // 0xD0
+    RefIsNull,               // 0xD1
+    RefFunc(Indecies),       // 0xD2
+    RefAsNonNull,            // 0xD4
 
     // Parametric Instructions
-    Drop,           // 0x1A
-    Select,         // 0x1B
-    SelectMultiple, // 0x1C  - TODO: Double check this one
+    Drop,                           // 0x1A
+    Select,                         // 0x1B
+    SelectMultiple(Vec<ValueType>), // 0x1C
 
     // Variable Instructions
-    LocalGet(Indecies), // 0x20
-    LocalSet,           // 0x21
-    LocalTee,           // 0x22
-    GlobalGet,          // 0x23
-    GlobalSet,          // 0x24
+    LocalGet(Indecies),  // 0x20
+    LocalSet(Indecies),  // 0x21
+    LocalTee(Indecies),  // 0x22
+    GlobalGet(Indecies), // 0x23
+    GlobalSet(Indecies), // 0x24
 
     // Table Instructions
-    TableGet,  // 0x25
-    TableSet,  // 0x26
-    TableInit, // 0xFC 12
-    ElemDrop,  // 0xFC 13
-    TableCopy, // 0xFC 14
-    TableGrow, // 0xFC 15
-    TableSize, // 0xFC 16
-    TableFill, // 0xFC 17
+    TableGet(Indecies),            // 0x25
+    TableSet(Indecies),            // 0x26
+    TableInit(Indecies, Indecies), // 0xFC 12
+    ElemDrop(Indecies),            // 0xFC 13
+    TableCopy(Indecies, Indecies), // 0xFC 14
+    TableGrow(Indecies),           // 0xFC 15
+    TableSize(Indecies),           // 0xFC 16
+    TableFill(Indecies),           // 0xFC 17
 
     // Memory Instructions
-    i32_load,             // 0x28
-    i64_load,             // 0x29
-    f32_load,             // 0x2A
-    f64_load,             // 0x2B
-    i32_load_8s,          // 0x2C
-    i32_load_8u,          // 0x2D
-    i32_load_16s,         // 0x2E
-    i32_load_16u,         // 0x2F
-    i64_load_8s,          // 0x30
-    i64_load_8u,          // 0x31
-    i64_load_16s,         // 0x32
-    i64_load_16u,         // 0x33
-    i64_load_32s,         // 0x34
-    i64_load_32u,         // 0x35
-    i32_store,            // 0x36
-    i64_store,            // 0x37
-    f32_store,            // 0x38
-    f64_store,            // 0x39
-    i32_store_8,          // 0x3A
-    i32_store_16,         // 0x3B
-    i64_store_8,          // 0x3C
-    i64_store_16,         // 0x3D
-    i64_store_32,         // 0x3E
+    i32_load(MemArg),     // 0x28
+    i64_load(MemArg),     // 0x29
+    f32_load(MemArg),     // 0x2A
+    f64_load(MemArg),     // 0x2B
+    i32_load_8s(MemArg),  // 0x2C
+    i32_load_8u(MemArg),  // 0x2D
+    i32_load_16s(MemArg), // 0x2E
+    i32_load_16u(MemArg), // 0x2F
+    i64_load_8s(MemArg),  // 0x30
+    i64_load_8u(MemArg),  // 0x31
+    i64_load_16s(MemArg), // 0x32
+    i64_load_16u(MemArg), // 0x33
+    i64_load_32s(MemArg), // 0x34
+    i64_load_32u(MemArg), // 0x35
+    i32_store(MemArg),    // 0x36
+    i64_store(MemArg),    // 0x37
+    f32_store(MemArg),    // 0x38
+    f64_store(MemArg),    // 0x39
+    i32_store_8(MemArg),  // 0x3A
+    i32_store_16(MemArg), // 0x3B
+    i64_store_8(MemArg),  // 0x3C
+    i64_store_16(MemArg), // 0x3D
+    i64_store_32(MemArg), // 0x3E
     MemorySize,           // 0x3F 0x00
     MemoryGrow,           // 0x40 0x00
     MemoryInit(Indecies), // 0xFC 8
-    DataDrop,             // 0xFC 9
+    DataDrop(Indecies),   // 0xFC 9
     MemoryCopy,           // 0xFC 10
     MemoryFill,           // 0xFC 11
 
     // Numeric Instructions
     i32_const(i32), // 0x41
     i64_const(i64), // 0x42
-    f32_const(f32), // 0x43
-    f64_const(f64), // 0x44
+    // Floats are kept as their bit patterns so NaN payloads and -0.0 compare equal after a round trip
+    f32_const(u32), // 0x43
+    f64_const(u64), // 0x44
 
     i32_eqz,  // 0x45
     i32_eq,   // 0x46
@@ -196,7 +377,7 @@ pub enum Instructions {
     i32_trunc_f64_s,     // 0xAA
     i32_trunc_f64_u,     // 0xAB
     i64_extend_i32_s,    // 0xAC
-    i64_extend_i32_u,    // 0XAD
+    i64_extend_i32_u,    // 0xAD
     i64_trunc_f32_s,     // 0xAE
     i64_trunc_f32_u,     // 0xAF
     i64_trunc_f64_s,     // 0xB0
@@ -230,56 +411,1814 @@ pub enum Instructions {
     i64_trunc_sat_f32_u, // 0xFC 5
     i64_trunc_sat_f64_s, // 0xFC 6
     i64_trunc_sat_f64_u, // 0xFC 7
+
+    // Vector Instructions
+    v128_load(MemArg),             // 0xFD 0
+    v128_load8x8_s(MemArg),        // 0xFD 1
+    v128_load8x8_u(MemArg),        // 0xFD 2
+    v128_load16x4_s(MemArg),       // 0xFD 3
+    v128_load16x4_u(MemArg),       // 0xFD 4
+    v128_load32x2_s(MemArg),       // 0xFD 5
+    v128_load32x2_u(MemArg),       // 0xFD 6
+    v128_load8_splat(MemArg),      // 0xFD 7
+    v128_load16_splat(MemArg),     // 0xFD 8
+    v128_load32_splat(MemArg),     // 0xFD 9
+    v128_load64_splat(MemArg),     // 0xFD 10
+    v128_store(MemArg),            // 0xFD 11
+    v128_const(u128),              // 0xFD 12
+    i8x16_shuffle([u8; 16]),       // 0xFD 13
+    i8x16_swizzle,                 // 0xFD 14
+    i8x16_splat,                   // 0xFD 15
+    i16x8_splat,                   // 0xFD 16
+    i32x4_splat,                   // 0xFD 17
+    i64x2_splat,                   // 0xFD 18
+    f32x4_splat,                   // 0xFD 19
+    f64x2_splat,                   // 0xFD 20
+    i8x16_extract_lane_s(u8),      // 0xFD 21
+    i8x16_extract_lane_u(u8),      // 0xFD 22
+    i8x16_replace_lane(u8),        // 0xFD 23
+    i16x8_extract_lane_s(u8),      // 0xFD 24
+    i16x8_extract_lane_u(u8),      // 0xFD 25
+    i16x8_replace_lane(u8),        // 0xFD 26
+    i32x4_extract_lane(u8),        // 0xFD 27
+    i32x4_replace_lane(u8),        // 0xFD 28
+    i64x2_extract_lane(u8),        // 0xFD 29
+    i64x2_replace_lane(u8),        // 0xFD 30
+    f32x4_extract_lane(u8),        // 0xFD 31
+    f32x4_replace_lane(u8),        // 0xFD 32
+    f64x2_extract_lane(u8),        // 0xFD 33
+    f64x2_replace_lane(u8),        // 0xFD 34
+    i8x16_eq,                      // 0xFD 35
+    i8x16_ne,                      // 0xFD 36
+    i8x16_lt_s,                    // 0xFD 37
+    i8x16_lt_u,                    // 0xFD 38
+    i8x16_gt_s,                    // 0xFD 39
+    i8x16_gt_u,                    // 0xFD 40
+    i8x16_le_s,                    // 0xFD 41
+    i8x16_le_u,                    // 0xFD 42
+    i8x16_ge_s,                    // 0xFD 43
+    i8x16_ge_u,                    // 0xFD 44
+    i16x8_eq,                      // 0xFD 45
+    i16x8_ne,                      // 0xFD 46
+    i16x8_lt_s,                    // 0xFD 47
+    i16x8_lt_u,                    // 0xFD 48
+    i16x8_gt_s,                    // 0xFD 49
+    i16x8_gt_u,                    // 0xFD 50
+    i16x8_le_s,                    // 0xFD 51
+    i16x8_le_u,                    // 0xFD 52
+    i16x8_ge_s,                    // 0xFD 53
+    i16x8_ge_u,                    // 0xFD 54
+    i32x4_eq,                      // 0xFD 55
+    i32x4_ne,                      // 0xFD 56
+    i32x4_lt_s,                    // 0xFD 57
+    i32x4_lt_u,                    // 0xFD 58
+    i32x4_gt_s,                    // 0xFD 59
+    i32x4_gt_u,                    // 0xFD 60
+    i32x4_le_s,                    // 0xFD 61
+    i32x4_le_u,                    // 0xFD 62
+    i32x4_ge_s,                    // 0xFD 63
+    i32x4_ge_u,                    // 0xFD 64
+    f32x4_eq,                      // 0xFD 65
+    f32x4_ne,                      // 0xFD 66
+    f32x4_lt,                      // 0xFD 67
+    f32x4_gt,                      // 0xFD 68
+    f32x4_le,                      // 0xFD 69
+    f32x4_ge,                      // 0xFD 70
+    f64x2_eq,                      // 0xFD 71
+    f64x2_ne,                      // 0xFD 72
+    f64x2_lt,                      // 0xFD 73
+    f64x2_gt,                      // 0xFD 74
+    f64x2_le,                      // 0xFD 75
+    f64x2_ge,                      // 0xFD 76
+    v128_not,                      // 0xFD 77
+    v128_and,                      // 0xFD 78
+    v128_andnot,                   // 0xFD 79
+    v128_or,                       // 0xFD 80
+    v128_xor,                      // 0xFD 81
+    v128_bitselect,                // 0xFD 82
+    v128_any_true,                 // 0xFD 83
+    v128_load8_lane(MemArg, u8),   // 0xFD 84
+    v128_load16_lane(MemArg, u8),  // 0xFD 85
+    v128_load32_lane(MemArg, u8),  // 0xFD 86
+    v128_load64_lane(MemArg, u8),  // 0xFD 87
+    v128_store8_lane(MemArg, u8),  // 0xFD 88
+    v128_store16_lane(MemArg, u8), // 0xFD 89
+    v128_store32_lane(MemArg, u8), // 0xFD 90
+    v128_store64_lane(MemArg, u8), // 0xFD 91
+    v128_load32_zero(MemArg),      // 0xFD 92
+    v128_load64_zero(MemArg),      // 0xFD 93
+    f32x4_demote_f64x2_zero,       // 0xFD 94
+    f64x2_promote_low_f32x4,       // 0xFD 95
+    i8x16_abs,                     // 0xFD 96
+    i8x16_neg,                     // 0xFD 97
+    i8x16_popcnt,                  // 0xFD 98
+    i8x16_all_true,                // 0xFD 99
+    i8x16_bitmask,                 // 0xFD 100
+    i8x16_narrow_i16x8_s,          // 0xFD 101
+    i8x16_narrow_i16x8_u,          // 0xFD 102
+    f32x4_ceil,                    // 0xFD 103
+    f32x4_floor,                   // 0xFD 104
+    f32x4_trunc,                   // 0xFD 105
+    f32x4_nearest,                 // 0xFD 106
+    i8x16_shl,                     // 0xFD 107
+    i8x16_shr_s,                   // 0xFD 108
+    i8x16_shr_u,                   // 0xFD 109
+    i8x16_add,                     // 0xFD 110
+    i8x16_add_sat_s,               // 0xFD 111
+    i8x16_add_sat_u,               // 0xFD 112
+    i8x16_sub,                     // 0xFD 113
+    i8x16_sub_sat_s,               // 0xFD 114
+    i8x16_sub_sat_u,               // 0xFD 115
+    f64x2_ceil,                    // 0xFD 116
+    f64x2_floor,                   // 0xFD 117
+    i8x16_min_s,                   // 0xFD 118
+    i8x16_min_u,                   // 0xFD 119
+    i8x16_max_s,                   // 0xFD 120
+    i8x16_max_u,                   // 0xFD 121
+    f64x2_trunc,                   // 0xFD 122
+    i8x16_avgr_u,                  // 0xFD 123
+    i16x8_extadd_pairwise_i8x16_s, // 0xFD 124
+    i16x8_extadd_pairwise_i8x16_u, // 0xFD 125
+    i32x4_extadd_pairwise_i16x8_s, // 0xFD 126
+    i32x4_extadd_pairwise_i16x8_u, // 0xFD 127
+    i16x8_abs,                     // 0xFD 128
+    i16x8_neg,                     // 0xFD 129
+    i16x8_q15mulr_sat_s,           // 0xFD 130
+    i16x8_all_true,                // 0xFD 131
+    i16x8_bitmask,                 // 0xFD 132
+    i16x8_narrow_i32x4_s,          // 0xFD 133
+    i16x8_narrow_i32x4_u,          // 0xFD 134
+    i16x8_extend_low_i8x16_s,      // 0xFD 135
+    i16x8_extend_high_i8x16_s,     // 0xFD 136
+    i16x8_extend_low_i8x16_u,      // 0xFD 137
+    i16x8_extend_high_i8x16_u,     // 0xFD 138
+    i16x8_shl,                     // 0xFD 139
+    i16x8_shr_s,                   // 0xFD 140
+    i16x8_shr_u,                   // 0xFD 141
+    i16x8_add,                     // 0xFD 142
+    i16x8_add_sat_s,               // 0xFD 143
+    i16x8_add_sat_u,               // 0xFD 144
+    i16x8_sub,                     // 0xFD 145
+    i16x8_sub_sat_s,               // 0xFD 146
+    i16x8_sub_sat_u,               // 0xFD 147
+    f64x2_nearest,                 // 0xFD 148
+    i16x8_mul,                     // 0xFD 149
+    i16x8_min_s,                   // 0xFD 150
+    i16x8_min_u,                   // 0xFD 151
+    i16x8_max_s,                   // 0xFD 152
+    i16x8_max_u,                   // 0xFD 153
+    i16x8_avgr_u,                  // 0xFD 155
+    i16x8_extmul_low_i8x16_s,      // 0xFD 156
+    i16x8_extmul_high_i8x16_s,     // 0xFD 157
+    i16x8_extmul_low_i8x16_u,      // 0xFD 158
+    i16x8_extmul_high_i8x16_u,     // 0xFD 159
+    i32x4_abs,                     // 0xFD 160
+    i32x4_neg,                     // 0xFD 161
+    i32x4_all_true,                // 0xFD 163
+    i32x4_bitmask,                 // 0xFD 164
+    i32x4_extend_low_i16x8_s,      // 0xFD 167
+    i32x4_extend_high_i16x8_s,     // 0xFD 168
+    i32x4_extend_low_i16x8_u,      // 0xFD 169
+    i32x4_extend_high_i16x8_u,     // 0xFD 170
+    i32x4_shl,                     // 0xFD 171
+    i32x4_shr_s,                   // 0xFD 172
+    i32x4_shr_u,                   // 0xFD 173
+    i32x4_add,                     // 0xFD 174
+    i32x4_sub,                     // 0xFD 177
+    i32x4_mul,                     // 0xFD 181
+    i32x4_min_s,                   // 0xFD 182
+    i32x4_min_u,                   // 0xFD 183
+    i32x4_max_s,                   // 0xFD 184
+    i32x4_max_u,                   // 0xFD 185
+    i32x4_dot_i16x8_s,             // 0xFD 186
+    i32x4_extmul_low_i16x8_s,      // 0xFD 188
+    i32x4_extmul_high_i16x8_s,     // 0xFD 189
+    i32x4_extmul_low_i16x8_u,      // 0xFD 190
+    i32x4_extmul_high_i16x8_u,     // 0xFD 191
+    i64x2_abs,                     // 0xFD 192
+    i64x2_neg,                     // 0xFD 193
+    i64x2_all_true,                // 0xFD 195
+    i64x2_bitmask,                 // 0xFD 196
+    i64x2_extend_low_i32x4_s,      // 0xFD 199
+    i64x2_extend_high_i32x4_s,     // 0xFD 200
+    i64x2_extend_low_i32x4_u,      // 0xFD 201
+    i64x2_extend_high_i32x4_u,     // 0xFD 202
+    i64x2_shl,                     // 0xFD 203
+    i64x2_shr_s,                   // 0xFD 204
+    i64x2_shr_u,                   // 0xFD 205
+    i64x2_add,                     // 0xFD 206
+    i64x2_sub,                     // 0xFD 209
+    i64x2_mul,                     // 0xFD 213
+    i64x2_eq,                      // 0xFD 214
+    i64x2_ne,                      // 0xFD 215
+    i64x2_lt_s,                    // 0xFD 216
+    i64x2_gt_s,                    // 0xFD 217
+    i64x2_le_s,                    // 0xFD 218
+    i64x2_ge_s,                    // 0xFD 219
+    i64x2_extmul_low_i32x4_s,      // 0xFD 220
+    i64x2_extmul_high_i32x4_s,     // 0xFD 221
+    i64x2_extmul_low_i32x4_u,      // 0xFD 222
+    i64x2_extmul_high_i32x4_u,     // 0xFD 223
+    f32x4_abs,                     // 0xFD 224
+    f32x4_neg,                     // 0xFD 225
+    f32x4_sqrt,                    // 0xFD 227
+    f32x4_add,                     // 0xFD 228
+    f32x4_sub,                     // 0xFD 229
+    f32x4_mul,                     // 0xFD 230
+    f32x4_div,                     // 0xFD 231
+    f32x4_min,                     // 0xFD 232
+    f32x4_max,                     // 0xFD 233
+    f32x4_pmin,                    // 0xFD 234
+    f32x4_pmax,                    // 0xFD 235
+    f64x2_abs,                     // 0xFD 236
+    f64x2_neg,                     // 0xFD 237
+    f64x2_sqrt,                    // 0xFD 239
+    f64x2_add,                     // 0xFD 240
+    f64x2_sub,                     // 0xFD 241
+    f64x2_mul,                     // 0xFD 242
+    f64x2_div,                     // 0xFD 243
+    f64x2_min,                     // 0xFD 244
+    f64x2_max,                     // 0xFD 245
+    f64x2_pmin,                    // 0xFD 246
+    f64x2_pmax,                    // 0xFD 247
+    i32x4_trunc_sat_f32x4_s,       // 0xFD 248
+    i32x4_trunc_sat_f32x4_u,       // 0xFD 249
+    f32x4_convert_i32x4_s,         // 0xFD 250
+    f32x4_convert_i32x4_u,         // 0xFD 251
+    i32x4_trunc_sat_f64x2_s_zero,  // 0xFD 252
+    i32x4_trunc_sat_f64x2_u_zero,  // 0xFD 253
+    f64x2_convert_low_i32x4_s,     // 0xFD 254
+    f64x2_convert_low_i32x4_u,     // 0xFD 255
+
+    // Atomic Memory Instructions
+    memory_atomic_notify(MemArg),       // 0xFE 0
+    memory_atomic_wait32(MemArg),       // 0xFE 1
+    memory_atomic_wait64(MemArg),       // 0xFE 2
+    atomic_fence,                       // 0xFE 3
+    i32_atomic_load(MemArg),            // 0xFE 16
+    i64_atomic_load(MemArg),            // 0xFE 17
+    i32_atomic_load8_u(MemArg),         // 0xFE 18
+    i32_atomic_load16_u(MemArg),        // 0xFE 19
+    i64_atomic_load8_u(MemArg),         // 0xFE 20
+    i64_atomic_load16_u(MemArg),        // 0xFE 21
+    i64_atomic_load32_u(MemArg),        // 0xFE 22
+    i32_atomic_store(MemArg),           // 0xFE 23
+    i64_atomic_store(MemArg),           // 0xFE 24
+    i32_atomic_store8(MemArg),          // 0xFE 25
+    i32_atomic_store16(MemArg),         // 0xFE 26
+    i64_atomic_store8(MemArg),          // 0xFE 27
+    i64_atomic_store16(MemArg),         // 0xFE 28
+    i64_atomic_store32(MemArg),         // 0xFE 29
+    i32_atomic_rmw_add(MemArg),         // 0xFE 30
+    i64_atomic_rmw_add(MemArg),         // 0xFE 31
+    i32_atomic_rmw8_add_u(MemArg),      // 0xFE 32
+    i32_atomic_rmw16_add_u(MemArg),     // 0xFE 33
+    i64_atomic_rmw8_add_u(MemArg),      // 0xFE 34
+    i64_atomic_rmw16_add_u(MemArg),     // 0xFE 35
+    i64_atomic_rmw32_add_u(MemArg),     // 0xFE 36
+    i32_atomic_rmw_sub(MemArg),         // 0xFE 37
+    i64_atomic_rmw_sub(MemArg),         // 0xFE 38
+    i32_atomic_rmw8_sub_u(MemArg),      // 0xFE 39
+    i32_atomic_rmw16_sub_u(MemArg),     // 0xFE 40
+    i64_atomic_rmw8_sub_u(MemArg),      // 0xFE 41
+    i64_atomic_rmw16_sub_u(MemArg),     // 0xFE 42
+    i64_atomic_rmw32_sub_u(MemArg),     // 0xFE 43
+    i32_atomic_rmw_and(MemArg),         // 0xFE 44
+    i64_atomic_rmw_and(MemArg),         // 0xFE 45
+    i32_atomic_rmw8_and_u(MemArg),      // 0xFE 46
+    i32_atomic_rmw16_and_u(MemArg),     // 0xFE 47
+    i64_atomic_rmw8_and_u(MemArg),      // 0xFE 48
+    i64_atomic_rmw16_and_u(MemArg),     // 0xFE 49
+    i64_atomic_rmw32_and_u(MemArg),     // 0xFE 50
+    i32_atomic_rmw_or(MemArg),          // 0xFE 51
+    i64_atomic_rmw_or(MemArg),          // 0xFE 52
+    i32_atomic_rmw8_or_u(MemArg),       // 0xFE 53
+    i32_atomic_rmw16_or_u(MemArg),      // 0xFE 54
+    i64_atomic_rmw8_or_u(MemArg),       // 0xFE 55
+    i64_atomic_rmw16_or_u(MemArg),      // 0xFE 56
+    i64_atomic_rmw32_or_u(MemArg),      // 0xFE 57
+    i32_atomic_rmw_xor(MemArg),         // 0xFE 58
+    i64_atomic_rmw_xor(MemArg),         // 0xFE 59
+    i32_atomic_rmw8_xor_u(MemArg),      // 0xFE 60
+    i32_atomic_rmw16_xor_u(MemArg),     // 0xFE 61
+    i64_atomic_rmw8_xor_u(MemArg),      // 0xFE 62
+    i64_atomic_rmw16_xor_u(MemArg),     // 0xFE 63
+    i64_atomic_rmw32_xor_u(MemArg),     // 0xFE 64
+    i32_atomic_rmw_xchg(MemArg),        // 0xFE 65
+    i64_atomic_rmw_xchg(MemArg),        // 0xFE 66
+    i32_atomic_rmw8_xchg_u(MemArg),     // 0xFE 67
+    i32_atomic_rmw16_xchg_u(MemArg),    // 0xFE 68
+    i64_atomic_rmw8_xchg_u(MemArg),     // 0xFE 69
+    i64_atomic_rmw16_xchg_u(MemArg),    // 0xFE 70
+    i64_atomic_rmw32_xchg_u(MemArg),    // 0xFE 71
+    i32_atomic_rmw_cmpxchg(MemArg),     // 0xFE 72
+    i64_atomic_rmw_cmpxchg(MemArg),     // 0xFE 73
+    i32_atomic_rmw8_cmpxchg_u(MemArg),  // 0xFE 74
+    i32_atomic_rmw16_cmpxchg_u(MemArg), // 0xFE 75
+    i64_atomic_rmw8_cmpxchg_u(MemArg),  // 0xFE 76
+    i64_atomic_rmw16_cmpxchg_u(MemArg), // 0xFE 77
+    i64_atomic_rmw32_cmpxchg_u(MemArg), // 0xFE 78
 }
 
-impl FromByteReader for Instructions {
-    fn read_from_byte_reader(
-        reader: &mut bytereader::ByteReader,
-    ) -> Result<Self, bytereader::ByteReaderError>
+impl<'a> FromByteReader<'a> for Instructions {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
-        Ok(match reader.read::<u8>()? {
-            0x10 => Instructions::Call(reader.read_uleb128::<u32>().map(Indecies::FuncIdx)?),
-            0x20 => Instructions::LocalGet(reader.read_uleb128::<u32>().map(Indecies::LocalIdx)?),
-            0x41 => Instructions::i32_const(reader.read_leb128::<i32>()?),
-            0x6A => Instructions::i32_add,
-
-            0xFC => match reader.read_uleb128::<u32>()? {
-                8 => {
-                    let v =
-                        Instructions::MemoryInit(Indecies::DataIdx(reader.read_uleb128::<u32>()?));
-                    reader.read_expect(&[0x00])?;
-                    v
+        let offset = reader.get_current_offset();
+        match read_token(reader)? {
+            Token::Instruction(instruction) => Ok(instruction),
+            Token::Start(kind, ty) => {
+                let (body, else_offset) = read_body(reader)?;
+                match (&kind, else_offset) {
+                    (BlockKind::If, Some(_)) => {
+                        Ok(close_block(kind, ty, Some(body), read_expr(reader)?))
+                    }
+                    (_, None) => Ok(close_block(kind, ty, None, body)),
+                    (_, Some(offset)) => Err(unexpected_else(offset)),
                 }
+            }
+            Token::Else => Err(unexpected_else(offset)),
+            Token::End => Err(WasmParserError::at(
+                offset,
+                WasmParserErrorKind::UnexpectedByte {
+                    construct: "instruction outside of a block",
+                    byte: 0x0B,
+                },
+            )),
+        }
+    }
+}
+
+/// The deepest nesting of `block`, `loop`, `if` and `try_table` instructions the parser accepts
+pub const MAX_NESTING_DEPTH: usize = 4096;
+
+/// The instructions that open a nested instruction sequence
+#[derive(Debug, Clone, PartialEq)]
+enum BlockKind {
+    Block,
+    Loop,
+    If,
+    TryTable(Vec<Catch>),
+}
+
+/// A single decoded opcode, before the structured instructions have been assembled
+enum Token {
+    Instruction(Instructions),
+    Start(BlockKind, BlockType),
+    Else,
+    End,
+}
+
+/// A structured instruction whose `end` opcode hasn't been read yet
+struct OpenBlock {
+    kind: BlockKind,
+    ty: BlockType,
+    /// The `then` branch of an `if`, once its `else` opcode has been read
+    then: Option<Expr>,
+    /// The instruction sequence the block is nested in
+    outer: Expr,
+}
 
-                variant => {
-                    return Err(bytereader::ByteReaderError::UnknownError(format!(
-                        "Invalid instruction opcode variant for opcode '0xFC': '{}'",
-                        variant
-                    )))
+fn close_block(kind: BlockKind, ty: BlockType, then: Option<Expr>, body: Expr) -> Instructions {
+    match (kind, then) {
+        (BlockKind::Block, _) => Instructions::Block { ty, body },
+        (BlockKind::Loop, _) => Instructions::Loop { ty, body },
+        (BlockKind::TryTable(catches), _) => Instructions::TryTable { ty, catches, body },
+        (BlockKind::If, Some(then)) => Instructions::If {
+            ty,
+            then,
+            else_: body,
+        },
+        (BlockKind::If, None) => Instructions::If {
+            ty,
+            then: body,
+            else_: vec![],
+        },
+    }
+}
+
+fn unexpected_else(offset: usize) -> WasmParserError {
+    WasmParserError::at(
+        offset,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "instruction outside of an 'if' block",
+            byte: 0x05,
+        },
+    )
+}
+
+fn read_token(reader: &mut ByteReader) -> Result<Token, WasmParserError> {
+    let opcode = match reader.peak::<u8>()? {
+        opcode @ (0x02 | 0x03 | 0x04 | 0x1F) => opcode,
+        0x05 => {
+            reader.jump(1);
+            return Ok(Token::Else);
+        }
+        0x0B => {
+            reader.jump(1);
+            return Ok(Token::End);
+        }
+        _ => return read_instruction(reader).map(Token::Instruction),
+    };
+    reader.jump(1);
+    let ty = reader.read().context(reader, "block type")?;
+    let kind = match opcode {
+        0x02 => BlockKind::Block,
+        0x03 => BlockKind::Loop,
+        0x04 => BlockKind::If,
+        _ => BlockKind::TryTable(read_vec(reader).context(reader, "catch clauses")?),
+    };
+    Ok(Token::Start(kind, ty))
+}
+
+/// Reads an instruction sequence up to the `end` or `else` opcode that closes it.
+///
+/// Nested blocks are tracked on an explicit stack instead of by recursion, so deeply nested
+/// input can't overflow the call stack. Returns the offset of the `else` opcode if the sequence was closed by one
+fn read_body(reader: &mut ByteReader) -> Result<(Expr, Option<usize>), WasmParserError> {
+    let mut open: Vec<OpenBlock> = vec![];
+    let mut current = vec![];
+    let mut index = 0;
+
+    loop {
+        let offset = reader.get_current_offset();
+        let token = read_token(reader).with_context(reader, || format!("instruction {index}"))?;
+        index += 1;
+        match token {
+            Token::Instruction(instruction) => current.push(instruction),
+            Token::Start(..) if open.len() >= MAX_NESTING_DEPTH => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::ImplementationLimit {
+                        construct: "block nesting depth",
+                        limit: MAX_NESTING_DEPTH,
+                    },
+                ))
+            }
+            Token::Start(kind, ty) => open.push(OpenBlock {
+                kind,
+                ty,
+                then: None,
+                outer: std::mem::take(&mut current),
+            }),
+            Token::Else => match open.last_mut() {
+                None => return Ok((current, Some(offset))),
+                Some(block) if block.kind == BlockKind::If && block.then.is_none() => {
+                    block.then = Some(std::mem::take(&mut current));
+                }
+                Some(_) => return Err(unexpected_else(offset)),
+            },
+            Token::End => match open.pop() {
+                None => return Ok((current, None)),
+                Some(OpenBlock {
+                    kind,
+                    ty,
+                    then,
+                    outer,
+                }) => {
+                    let instruction =
+                        close_block(kind, ty, then, std::mem::replace(&mut current, outer));
+                    current.push(instruction);
                 }
             },
+        }
+    }
+}
+
+fn read_instruction(reader: &mut ByteReader) -> Result<Instructions, WasmParserError> {
+    use Instructions::*;
+
+    let offset = reader.get_current_offset();
+    Ok(match reader.read::<u8>()? {
+        // Control Instructions
+        0x00 => Unreachable,
+        0x01 => Nop,
+        0x0C => Br(read_index(reader, Indecies::LabelIdx)?),
+        0x0D => BrIf(read_index(reader, Indecies::LabelIdx)?),
+        0x0E => {
+            let labels = (0..reader.read_uleb128::<u32>()?)
+                .map(|_| read_index(reader, Indecies::LabelIdx))
+                .collect::<Result<_, _>>()?;
+            BrTable(labels, read_index(reader, Indecies::LabelIdx)?)
+        }
+        0x0F => Return,
+        0x10 => Call(read_index(reader, Indecies::FuncIdx)?),
+        0x11 => {
+            let type_index = read_index(reader, Indecies::TypeIdx)?;
+            CallIndirect(type_index, read_index(reader, Indecies::TableIdx)?)
+        }
+        0x12 => ReturnCall(read_index(reader, Indecies::FuncIdx)?),
+        0x13 => {
+            let type_index = read_index(reader, Indecies::TypeIdx)?;
+            ReturnCallIndirect(type_index, read_index(reader, Indecies::TableIdx)?)
+        }
+        0x14 => CallRef(read_index(reader, Indecies::TypeIdx)?),
+        0x15 => ReturnCallRef(read_index(reader, Indecies::TypeIdx)?),
+        0xD5 => BrOnNull(read_index(reader, Indecies::LabelIdx)?),
+        0xD6 => BrOnNonNull(read_index(reader, Indecies::LabelIdx)?),
+
+        // Exception Handling Instructions
+        0x08 => Throw(read_index(reader, Indecies::TagIdx)?),
+        0x0A => ThrowRef,
+
+        // Reference Instructions
+        0xD0 => RefNull(ReferenceTypes::new(true, reader.read()?)),
+        0xD1 => RefIsNull,
+        0xD2 => RefFunc(read_index(reader, Indecies::FuncIdx)?),
+        0xD4 => RefAsNonNull,
+
+        // Parametric Instructions
+        0x1A => Drop,
+        0x1B => Select,
+        0x1C => SelectMultiple(read_vec(reader)?),
+
+        // Variable Instructions
+        0x20 => LocalGet(read_index(reader, Indecies::LocalIdx)?),
+        0x21 => LocalSet(read_index(reader, Indecies::LocalIdx)?),
+        0x22 => LocalTee(read_index(reader, Indecies::LocalIdx)?),
+        0x23 => GlobalGet(read_index(reader, Indecies::GlobalIdx)?),
+        0x24 => GlobalSet(read_index(reader, Indecies::GlobalIdx)?),
+
+        // Table Instructions
+        0x25 => TableGet(read_index(reader, Indecies::TableIdx)?),
+        0x26 => TableSet(read_index(reader, Indecies::TableIdx)?),
 
-            opcode_id => {
-                return Err(bytereader::ByteReaderError::UnknownError(format!(
-                    "Invalid instruction opcode id: '0x{:X?}'",
-                    opcode_id
-                )))
+        // Memory Instructions
+        0x28 => i32_load(reader.read()?),
+        0x29 => i64_load(reader.read()?),
+        0x2A => f32_load(reader.read()?),
+        0x2B => f64_load(reader.read()?),
+        0x2C => i32_load_8s(reader.read()?),
+        0x2D => i32_load_8u(reader.read()?),
+        0x2E => i32_load_16s(reader.read()?),
+        0x2F => i32_load_16u(reader.read()?),
+        0x30 => i64_load_8s(reader.read()?),
+        0x31 => i64_load_8u(reader.read()?),
+        0x32 => i64_load_16s(reader.read()?),
+        0x33 => i64_load_16u(reader.read()?),
+        0x34 => i64_load_32s(reader.read()?),
+        0x35 => i64_load_32u(reader.read()?),
+        0x36 => i32_store(reader.read()?),
+        0x37 => i64_store(reader.read()?),
+        0x38 => f32_store(reader.read()?),
+        0x39 => f64_store(reader.read()?),
+        0x3A => i32_store_8(reader.read()?),
+        0x3B => i32_store_16(reader.read()?),
+        0x3C => i64_store_8(reader.read()?),
+        0x3D => i64_store_16(reader.read()?),
+        0x3E => i64_store_32(reader.read()?),
+        0x3F => {
+            read_reserved_byte(reader)?;
+            MemorySize
+        }
+        0x40 => {
+            read_reserved_byte(reader)?;
+            MemoryGrow
+        }
+
+        // Numeric Instructions
+        0x41 => i32_const(reader.read_leb128::<i32>()?),
+        0x42 => i64_const(reader.read_leb128::<i64>()?),
+        0x43 => f32_const(reader.read::<u32>()?),
+        0x44 => f64_const(reader.read::<u64>()?),
+
+        0x45 => i32_eqz,
+        0x46 => i32_eq,
+        0x47 => i32_ne,
+        0x48 => i32_lt_s,
+        0x49 => i32_lt_u,
+        0x4A => i32_gt_s,
+        0x4B => i32_gt_u,
+        0x4C => i32_le_s,
+        0x4D => i32_le_u,
+        0x4E => i32_ge_s,
+        0x4F => i32_ge_u,
+
+        0x50 => i64_eqz,
+        0x51 => i64_eq,
+        0x52 => i64_ne,
+        0x53 => i64_lt_s,
+        0x54 => i64_lt_u,
+        0x55 => i64_gt_s,
+        0x56 => i64_gt_u,
+        0x57 => i64_le_s,
+        0x58 => i64_le_u,
+        0x59 => i64_ge_s,
+        0x5A => i64_ge_u,
+
+        0x5B => f32_eq,
+        0x5C => f32_ne,
+        0x5D => f32_lt,
+        0x5E => f32_gt,
+        0x5F => f32_le,
+        0x60 => f32_ge,
+
+        0x61 => f64_eq,
+        0x62 => f64_ne,
+        0x63 => f64_lt,
+        0x64 => f64_gt,
+        0x65 => f64_le,
+        0x66 => f64_ge,
+
+        0x67 => i32_clz,
+        0x68 => i32_ctz,
+        0x69 => i32_popcnt,
+        0x6A => i32_add,
+        0x6B => i32_sub,
+        0x6C => i32_mul,
+        0x6D => i32_div_s,
+        0x6E => i32_div_u,
+        0x6F => i32_rem_s,
+        0x70 => i32_rem_u,
+        0x71 => i32_and,
+        0x72 => i32_or,
+        0x73 => i32_xor,
+        0x74 => i32_shl,
+        0x75 => i32_shr_s,
+        0x76 => i32_shr_u,
+        0x77 => i32_rotl,
+        0x78 => i32_rotr,
+
+        0x79 => i64_clz,
+        0x7A => i64_ctz,
+        0x7B => i64_popcnt,
+        0x7C => i64_add,
+        0x7D => i64_sub,
+        0x7E => i64_mul,
+        0x7F => i64_div_s,
+        0x80 => i64_div_u,
+        0x81 => i64_rem_s,
+        0x82 => i64_rem_u,
+        0x83 => i64_and,
+        0x84 => i64_or,
+        0x85 => i64_xor,
+        0x86 => i64_shl,
+        0x87 => i64_shr_s,
+        0x88 => i64_shr_u,
+        0x89 => i64_rotl,
+        0x8A => i64_rotr,
+
+        0x8B => f32_abs,
+        0x8C => f32_neg,
+        0x8D => f32_ceil,
+        0x8E => f32_floor,
+        0x8F => f32_trunc,
+        0x90 => f32_nearest,
+        0x91 => f32_sqrt,
+        0x92 => f32_add,
+        0x93 => f32_sub,
+        0x94 => f32_mul,
+        0x95 => f32_div,
+        0x96 => f32_min,
+        0x97 => f32_max,
+        0x98 => f32_copysign,
+
+        0x99 => f64_abs,
+        0x9A => f64_neg,
+        0x9B => f64_ceil,
+        0x9C => f64_floor,
+        0x9D => f64_trunc,
+        0x9E => f64_nearest,
+        0x9F => f64_sqrt,
+        0xA0 => f64_add,
+        0xA1 => f64_sub,
+        0xA2 => f64_mul,
+        0xA3 => f64_div,
+        0xA4 => f64_min,
+        0xA5 => f64_max,
+        0xA6 => f64_copysign,
+
+        0xA7 => i32_wrap_i64,
+        0xA8 => i32_trunc_f32_s,
+        0xA9 => i32_trunc_f32_u,
+        0xAA => i32_trunc_f64_s,
+        0xAB => i32_trunc_f64_u,
+        0xAC => i64_extend_i32_s,
+        0xAD => i64_extend_i32_u,
+        0xAE => i64_trunc_f32_s,
+        0xAF => i64_trunc_f32_u,
+        0xB0 => i64_trunc_f64_s,
+        0xB1 => i64_trunc_f64_u,
+        0xB2 => f32_convert_i32_s,
+        0xB3 => f32_convert_i32_u,
+        0xB4 => f32_convert_i64_s,
+        0xB5 => f32_convert_i64_u,
+        0xB6 => f32_demote_f64,
+        0xB7 => f64_convert_i32_s,
+        0xB8 => f64_convert_i32_u,
+        0xB9 => f64_convert_i64_s,
+        0xBA => f64_convert_i64_u,
+        0xBB => f64_promote_f32,
+        0xBC => i32_reinterpret_f32,
+        0xBD => i64_reinterpret_f64,
+        0xBE => f32_reinterpret_i32,
+        0xBF => f64_reinterpret_i64,
+
+        0xC0 => i32_extend8_s,
+        0xC1 => i32_extend16_s,
+        0xC2 => i64_extend8_s,
+        0xC3 => i64_extend16_s,
+        0xC4 => i64_extend32_s,
+
+        0xFC => match reader.read_uleb128::<u32>()? {
+            0 => i32_trunc_sat_f32_s,
+            1 => i32_trunc_sat_f32_u,
+            2 => i32_trunc_sat_f64_s,
+            3 => i32_trunc_sat_f64_u,
+            4 => i64_trunc_sat_f32_s,
+            5 => i64_trunc_sat_f32_u,
+            6 => i64_trunc_sat_f64_s,
+            7 => i64_trunc_sat_f64_u,
+            8 => {
+                let v = MemoryInit(read_index(reader, Indecies::DataIdx)?);
+                read_reserved_byte(reader)?;
+                v
             }
-        })
+            9 => DataDrop(read_index(reader, Indecies::DataIdx)?),
+            10 => {
+                read_reserved_byte(reader)?;
+                read_reserved_byte(reader)?;
+                MemoryCopy
+            }
+            11 => {
+                read_reserved_byte(reader)?;
+                MemoryFill
+            }
+            12 => {
+                let elem_index = read_index(reader, Indecies::ElemIdx)?;
+                TableInit(elem_index, read_index(reader, Indecies::TableIdx)?)
+            }
+            13 => ElemDrop(read_index(reader, Indecies::ElemIdx)?),
+            14 => {
+                let dst_index = read_index(reader, Indecies::TableIdx)?;
+                TableCopy(dst_index, read_index(reader, Indecies::TableIdx)?)
+            }
+            15 => TableGrow(read_index(reader, Indecies::TableIdx)?),
+            16 => TableSize(read_index(reader, Indecies::TableIdx)?),
+            17 => TableFill(read_index(reader, Indecies::TableIdx)?),
+
+            variant => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedValue {
+                        construct: "0xFC prefixed instruction opcode",
+                        value: variant,
+                    },
+                ))
+            }
+        },
+
+        0xFD => match reader.read_uleb128::<u32>()? {
+            0 => v128_load(reader.read()?),
+            1 => v128_load8x8_s(reader.read()?),
+            2 => v128_load8x8_u(reader.read()?),
+            3 => v128_load16x4_s(reader.read()?),
+            4 => v128_load16x4_u(reader.read()?),
+            5 => v128_load32x2_s(reader.read()?),
+            6 => v128_load32x2_u(reader.read()?),
+            7 => v128_load8_splat(reader.read()?),
+            8 => v128_load16_splat(reader.read()?),
+            9 => v128_load32_splat(reader.read()?),
+            10 => v128_load64_splat(reader.read()?),
+            11 => v128_store(reader.read()?),
+            12 => v128_const(reader.read()?),
+            13 => i8x16_shuffle(read_lane_indices(reader)?),
+            14 => i8x16_swizzle,
+            15 => i8x16_splat,
+            16 => i16x8_splat,
+            17 => i32x4_splat,
+            18 => i64x2_splat,
+            19 => f32x4_splat,
+            20 => f64x2_splat,
+            21 => i8x16_extract_lane_s(reader.read()?),
+            22 => i8x16_extract_lane_u(reader.read()?),
+            23 => i8x16_replace_lane(reader.read()?),
+            24 => i16x8_extract_lane_s(reader.read()?),
+            25 => i16x8_extract_lane_u(reader.read()?),
+            26 => i16x8_replace_lane(reader.read()?),
+            27 => i32x4_extract_lane(reader.read()?),
+            28 => i32x4_replace_lane(reader.read()?),
+            29 => i64x2_extract_lane(reader.read()?),
+            30 => i64x2_replace_lane(reader.read()?),
+            31 => f32x4_extract_lane(reader.read()?),
+            32 => f32x4_replace_lane(reader.read()?),
+            33 => f64x2_extract_lane(reader.read()?),
+            34 => f64x2_replace_lane(reader.read()?),
+            35 => i8x16_eq,
+            36 => i8x16_ne,
+            37 => i8x16_lt_s,
+            38 => i8x16_lt_u,
+            39 => i8x16_gt_s,
+            40 => i8x16_gt_u,
+            41 => i8x16_le_s,
+            42 => i8x16_le_u,
+            43 => i8x16_ge_s,
+            44 => i8x16_ge_u,
+            45 => i16x8_eq,
+            46 => i16x8_ne,
+            47 => i16x8_lt_s,
+            48 => i16x8_lt_u,
+            49 => i16x8_gt_s,
+            50 => i16x8_gt_u,
+            51 => i16x8_le_s,
+            52 => i16x8_le_u,
+            53 => i16x8_ge_s,
+            54 => i16x8_ge_u,
+            55 => i32x4_eq,
+            56 => i32x4_ne,
+            57 => i32x4_lt_s,
+            58 => i32x4_lt_u,
+            59 => i32x4_gt_s,
+            60 => i32x4_gt_u,
+            61 => i32x4_le_s,
+            62 => i32x4_le_u,
+            63 => i32x4_ge_s,
+            64 => i32x4_ge_u,
+            65 => f32x4_eq,
+            66 => f32x4_ne,
+            67 => f32x4_lt,
+            68 => f32x4_gt,
+            69 => f32x4_le,
+            70 => f32x4_ge,
+            71 => f64x2_eq,
+            72 => f64x2_ne,
+            73 => f64x2_lt,
+            74 => f64x2_gt,
+            75 => f64x2_le,
+            76 => f64x2_ge,
+            77 => v128_not,
+            78 => v128_and,
+            79 => v128_andnot,
+            80 => v128_or,
+            81 => v128_xor,
+            82 => v128_bitselect,
+            83 => v128_any_true,
+            84 => {
+                let memarg = reader.read()?;
+                v128_load8_lane(memarg, reader.read()?)
+            }
+            85 => {
+                let memarg = reader.read()?;
+                v128_load16_lane(memarg, reader.read()?)
+            }
+            86 => {
+                let memarg = reader.read()?;
+                v128_load32_lane(memarg, reader.read()?)
+            }
+            87 => {
+                let memarg = reader.read()?;
+                v128_load64_lane(memarg, reader.read()?)
+            }
+            88 => {
+                let memarg = reader.read()?;
+                v128_store8_lane(memarg, reader.read()?)
+            }
+            89 => {
+                let memarg = reader.read()?;
+                v128_store16_lane(memarg, reader.read()?)
+            }
+            90 => {
+                let memarg = reader.read()?;
+                v128_store32_lane(memarg, reader.read()?)
+            }
+            91 => {
+                let memarg = reader.read()?;
+                v128_store64_lane(memarg, reader.read()?)
+            }
+            92 => v128_load32_zero(reader.read()?),
+            93 => v128_load64_zero(reader.read()?),
+            94 => f32x4_demote_f64x2_zero,
+            95 => f64x2_promote_low_f32x4,
+            96 => i8x16_abs,
+            97 => i8x16_neg,
+            98 => i8x16_popcnt,
+            99 => i8x16_all_true,
+            100 => i8x16_bitmask,
+            101 => i8x16_narrow_i16x8_s,
+            102 => i8x16_narrow_i16x8_u,
+            103 => f32x4_ceil,
+            104 => f32x4_floor,
+            105 => f32x4_trunc,
+            106 => f32x4_nearest,
+            107 => i8x16_shl,
+            108 => i8x16_shr_s,
+            109 => i8x16_shr_u,
+            110 => i8x16_add,
+            111 => i8x16_add_sat_s,
+            112 => i8x16_add_sat_u,
+            113 => i8x16_sub,
+            114 => i8x16_sub_sat_s,
+            115 => i8x16_sub_sat_u,
+            116 => f64x2_ceil,
+            117 => f64x2_floor,
+            118 => i8x16_min_s,
+            119 => i8x16_min_u,
+            120 => i8x16_max_s,
+            121 => i8x16_max_u,
+            122 => f64x2_trunc,
+            123 => i8x16_avgr_u,
+            124 => i16x8_extadd_pairwise_i8x16_s,
+            125 => i16x8_extadd_pairwise_i8x16_u,
+            126 => i32x4_extadd_pairwise_i16x8_s,
+            127 => i32x4_extadd_pairwise_i16x8_u,
+            128 => i16x8_abs,
+            129 => i16x8_neg,
+            130 => i16x8_q15mulr_sat_s,
+            131 => i16x8_all_true,
+            132 => i16x8_bitmask,
+            133 => i16x8_narrow_i32x4_s,
+            134 => i16x8_narrow_i32x4_u,
+            135 => i16x8_extend_low_i8x16_s,
+            136 => i16x8_extend_high_i8x16_s,
+            137 => i16x8_extend_low_i8x16_u,
+            138 => i16x8_extend_high_i8x16_u,
+            139 => i16x8_shl,
+            140 => i16x8_shr_s,
+            141 => i16x8_shr_u,
+            142 => i16x8_add,
+            143 => i16x8_add_sat_s,
+            144 => i16x8_add_sat_u,
+            145 => i16x8_sub,
+            146 => i16x8_sub_sat_s,
+            147 => i16x8_sub_sat_u,
+            148 => f64x2_nearest,
+            149 => i16x8_mul,
+            150 => i16x8_min_s,
+            151 => i16x8_min_u,
+            152 => i16x8_max_s,
+            153 => i16x8_max_u,
+            155 => i16x8_avgr_u,
+            156 => i16x8_extmul_low_i8x16_s,
+            157 => i16x8_extmul_high_i8x16_s,
+            158 => i16x8_extmul_low_i8x16_u,
+            159 => i16x8_extmul_high_i8x16_u,
+            160 => i32x4_abs,
+            161 => i32x4_neg,
+            163 => i32x4_all_true,
+            164 => i32x4_bitmask,
+            167 => i32x4_extend_low_i16x8_s,
+            168 => i32x4_extend_high_i16x8_s,
+            169 => i32x4_extend_low_i16x8_u,
+            170 => i32x4_extend_high_i16x8_u,
+            171 => i32x4_shl,
+            172 => i32x4_shr_s,
+            173 => i32x4_shr_u,
+            174 => i32x4_add,
+            177 => i32x4_sub,
+            181 => i32x4_mul,
+            182 => i32x4_min_s,
+            183 => i32x4_min_u,
+            184 => i32x4_max_s,
+            185 => i32x4_max_u,
+            186 => i32x4_dot_i16x8_s,
+            188 => i32x4_extmul_low_i16x8_s,
+            189 => i32x4_extmul_high_i16x8_s,
+            190 => i32x4_extmul_low_i16x8_u,
+            191 => i32x4_extmul_high_i16x8_u,
+            192 => i64x2_abs,
+            193 => i64x2_neg,
+            195 => i64x2_all_true,
+            196 => i64x2_bitmask,
+            199 => i64x2_extend_low_i32x4_s,
+            200 => i64x2_extend_high_i32x4_s,
+            201 => i64x2_extend_low_i32x4_u,
+            202 => i64x2_extend_high_i32x4_u,
+            203 => i64x2_shl,
+            204 => i64x2_shr_s,
+            205 => i64x2_shr_u,
+            206 => i64x2_add,
+            209 => i64x2_sub,
+            213 => i64x2_mul,
+            214 => i64x2_eq,
+            215 => i64x2_ne,
+            216 => i64x2_lt_s,
+            217 => i64x2_gt_s,
+            218 => i64x2_le_s,
+            219 => i64x2_ge_s,
+            220 => i64x2_extmul_low_i32x4_s,
+            221 => i64x2_extmul_high_i32x4_s,
+            222 => i64x2_extmul_low_i32x4_u,
+            223 => i64x2_extmul_high_i32x4_u,
+            224 => f32x4_abs,
+            225 => f32x4_neg,
+            227 => f32x4_sqrt,
+            228 => f32x4_add,
+            229 => f32x4_sub,
+            230 => f32x4_mul,
+            231 => f32x4_div,
+            232 => f32x4_min,
+            233 => f32x4_max,
+            234 => f32x4_pmin,
+            235 => f32x4_pmax,
+            236 => f64x2_abs,
+            237 => f64x2_neg,
+            239 => f64x2_sqrt,
+            240 => f64x2_add,
+            241 => f64x2_sub,
+            242 => f64x2_mul,
+            243 => f64x2_div,
+            244 => f64x2_min,
+            245 => f64x2_max,
+            246 => f64x2_pmin,
+            247 => f64x2_pmax,
+            248 => i32x4_trunc_sat_f32x4_s,
+            249 => i32x4_trunc_sat_f32x4_u,
+            250 => f32x4_convert_i32x4_s,
+            251 => f32x4_convert_i32x4_u,
+            252 => i32x4_trunc_sat_f64x2_s_zero,
+            253 => i32x4_trunc_sat_f64x2_u_zero,
+            254 => f64x2_convert_low_i32x4_s,
+            255 => f64x2_convert_low_i32x4_u,
+
+            variant => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedValue {
+                        construct: "0xFD prefixed instruction opcode",
+                        value: variant,
+                    },
+                ))
+            }
+        },
+
+        0xFE => match reader.read_uleb128::<u32>()? {
+            0 => memory_atomic_notify(reader.read()?),
+            1 => memory_atomic_wait32(reader.read()?),
+            2 => memory_atomic_wait64(reader.read()?),
+            3 => {
+                read_reserved_byte(reader)?;
+                atomic_fence
+            }
+            16 => i32_atomic_load(reader.read()?),
+            17 => i64_atomic_load(reader.read()?),
+            18 => i32_atomic_load8_u(reader.read()?),
+            19 => i32_atomic_load16_u(reader.read()?),
+            20 => i64_atomic_load8_u(reader.read()?),
+            21 => i64_atomic_load16_u(reader.read()?),
+            22 => i64_atomic_load32_u(reader.read()?),
+            23 => i32_atomic_store(reader.read()?),
+            24 => i64_atomic_store(reader.read()?),
+            25 => i32_atomic_store8(reader.read()?),
+            26 => i32_atomic_store16(reader.read()?),
+            27 => i64_atomic_store8(reader.read()?),
+            28 => i64_atomic_store16(reader.read()?),
+            29 => i64_atomic_store32(reader.read()?),
+            30 => i32_atomic_rmw_add(reader.read()?),
+            31 => i64_atomic_rmw_add(reader.read()?),
+            32 => i32_atomic_rmw8_add_u(reader.read()?),
+            33 => i32_atomic_rmw16_add_u(reader.read()?),
+            34 => i64_atomic_rmw8_add_u(reader.read()?),
+            35 => i64_atomic_rmw16_add_u(reader.read()?),
+            36 => i64_atomic_rmw32_add_u(reader.read()?),
+            37 => i32_atomic_rmw_sub(reader.read()?),
+            38 => i64_atomic_rmw_sub(reader.read()?),
+            39 => i32_atomic_rmw8_sub_u(reader.read()?),
+            40 => i32_atomic_rmw16_sub_u(reader.read()?),
+            41 => i64_atomic_rmw8_sub_u(reader.read()?),
+            42 => i64_atomic_rmw16_sub_u(reader.read()?),
+            43 => i64_atomic_rmw32_sub_u(reader.read()?),
+            44 => i32_atomic_rmw_and(reader.read()?),
+            45 => i64_atomic_rmw_and(reader.read()?),
+            46 => i32_atomic_rmw8_and_u(reader.read()?),
+            47 => i32_atomic_rmw16_and_u(reader.read()?),
+            48 => i64_atomic_rmw8_and_u(reader.read()?),
+            49 => i64_atomic_rmw16_and_u(reader.read()?),
+            50 => i64_atomic_rmw32_and_u(reader.read()?),
+            51 => i32_atomic_rmw_or(reader.read()?),
+            52 => i64_atomic_rmw_or(reader.read()?),
+            53 => i32_atomic_rmw8_or_u(reader.read()?),
+            54 => i32_atomic_rmw16_or_u(reader.read()?),
+            55 => i64_atomic_rmw8_or_u(reader.read()?),
+            56 => i64_atomic_rmw16_or_u(reader.read()?),
+            57 => i64_atomic_rmw32_or_u(reader.read()?),
+            58 => i32_atomic_rmw_xor(reader.read()?),
+            59 => i64_atomic_rmw_xor(reader.read()?),
+            60 => i32_atomic_rmw8_xor_u(reader.read()?),
+            61 => i32_atomic_rmw16_xor_u(reader.read()?),
+            62 => i64_atomic_rmw8_xor_u(reader.read()?),
+            63 => i64_atomic_rmw16_xor_u(reader.read()?),
+            64 => i64_atomic_rmw32_xor_u(reader.read()?),
+            65 => i32_atomic_rmw_xchg(reader.read()?),
+            66 => i64_atomic_rmw_xchg(reader.read()?),
+            67 => i32_atomic_rmw8_xchg_u(reader.read()?),
+            68 => i32_atomic_rmw16_xchg_u(reader.read()?),
+            69 => i64_atomic_rmw8_xchg_u(reader.read()?),
+            70 => i64_atomic_rmw16_xchg_u(reader.read()?),
+            71 => i64_atomic_rmw32_xchg_u(reader.read()?),
+            72 => i32_atomic_rmw_cmpxchg(reader.read()?),
+            73 => i64_atomic_rmw_cmpxchg(reader.read()?),
+            74 => i32_atomic_rmw8_cmpxchg_u(reader.read()?),
+            75 => i32_atomic_rmw16_cmpxchg_u(reader.read()?),
+            76 => i64_atomic_rmw8_cmpxchg_u(reader.read()?),
+            77 => i64_atomic_rmw16_cmpxchg_u(reader.read()?),
+            78 => i64_atomic_rmw32_cmpxchg_u(reader.read()?),
+
+            variant => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedValue {
+                        construct: "0xFE prefixed instruction opcode",
+                        value: variant,
+                    },
+                ))
+            }
+        },
+
+        opcode_id => {
+            return Err(WasmParserError::at(
+                offset,
+                WasmParserErrorKind::UnexpectedByte {
+                    construct: "instruction opcode",
+                    byte: opcode_id,
+                },
+            ))
+        }
+    })
+}
+
+fn read_index(
+    reader: &mut ByteReader,
+    index: fn(u32) -> Indecies,
+) -> Result<Indecies, WasmParserError> {
+    Ok(reader.read_uleb128::<u32>().map(index)?)
+}
+
+/// Reads the 16 lane indices of `i8x16.shuffle`, which are plain bytes
+fn read_lane_indices(reader: &mut ByteReader) -> Result<[u8; 16], WasmParserError> {
+    let lanes = reader.read_bytes(16).context(reader, "shuffle lanes")?;
+    Ok(lanes.try_into().expect("16 bytes were read"))
+}
+
+/// Reads the zero byte that some instructions reserve for a future memory index
+fn read_reserved_byte(reader: &mut ByteReader) -> Result<(), WasmParserError> {
+    read_byte_as(reader, "reserved byte", |byte| (byte == 0x00).then_some(()))
+}
+
+/// Reads an instruction sequence terminated by the `end` opcode, including any nested blocks
+pub fn read_expr(reader: &mut ByteReader) -> Result<Expr, WasmParserError> {
+    match read_body(reader)? {
+        (expr, None) => Ok(expr),
+        (_, Some(offset)) => Err(unexpected_else(offset)),
+    }
+}
+
+/// Reads an instruction sequence like [`read_expr`], returning the offset and first byte of every opcode
+/// in it instead, including `else` and the `end` opcodes of nested blocks and the sequence itself
+pub(crate) fn read_expr_offsets(
+    reader: &mut ByteReader,
+) -> Result<Vec<(usize, u8)>, WasmParserError> {
+    let mut offsets = vec![];
+    let mut depth = 0usize;
+    loop {
+        offsets.push((reader.get_current_offset(), reader.peak::<u8>()?));
+        match read_token(reader)? {
+            Token::Start(..) => depth += 1,
+            Token::End if depth == 0 => return Ok(offsets),
+            Token::End => depth -= 1,
+            Token::Instruction(_) | Token::Else => {}
+        }
     }
 }
 
-pub fn read_expr(reader: &mut ByteReader) -> Result<Vec<Instructions>, ByteReaderError> {
-    let mut opcodes = vec![];
+/// Writes an instruction sequence followed by the `end` opcode, the counterpart of [`read_expr`].
+///
+/// Like [`read_body`], nested blocks are tracked on an explicit stack instead of by recursion
+pub fn write_expr(writer: &mut ByteWriter, expr: &Expr) {
+    struct Frame<'e> {
+        instructions: std::slice::Iter<'e, Instructions>,
+        /// The `else` branch of an `if`, written once the `then` branch runs out
+        else_: Option<&'e Expr>,
+    }
+
+    let mut frames = vec![Frame {
+        instructions: expr.iter(),
+        else_: None,
+    }];
+    while let Some(frame) = frames.last_mut() {
+        let Some(instruction) = frame.instructions.next() else {
+            match frame.else_.take() {
+                Some(else_) => {
+                    writer.write(&0x05u8);
+                    frame.instructions = else_.iter();
+                }
+                None => {
+                    writer.write(&0x0Bu8);
+                    frames.pop();
+                }
+            }
+            continue;
+        };
 
-    while let Some(byte) = reader.peak::<u8>().ok() {
-        if byte == 0x0B {
-            break;
-        } // End of expression
-        opcodes.push(reader.read::<Instructions>()?);
+        let (opcode, ty, body, else_) = match instruction {
+            Instructions::Block { ty, body } => (0x02u8, ty, body, None),
+            Instructions::Loop { ty, body } => (0x03, ty, body, None),
+            Instructions::TryTable { ty, body, .. } => (0x1F, ty, body, None),
+            // An empty `else` branch is left out
+            Instructions::If { ty, then, else_ } => (
+                0x04,
+                ty,
+                then,
+                Some(else_).filter(|else_| !else_.is_empty()),
+            ),
+            instruction => {
+                write_instruction(writer, instruction);
+                continue;
+            }
+        };
+        writer.write(&opcode).write(ty);
+        if let Instructions::TryTable { catches, .. } = instruction {
+            write_vec(writer, catches);
+        }
+        frames.push(Frame {
+            instructions: body.iter(),
+            else_,
+        });
     }
-    reader.jump(1); // jump over the 0x0B escape byte
-    return Ok(opcodes);
+}
+
+fn write_instruction(writer: &mut ByteWriter, instruction: &Instructions) {
+    use Instructions::*;
+
+    match instruction {
+        // Control Instructions
+        Block { .. } | Loop { .. } | If { .. } | TryTable { .. } => {
+            unreachable!("structured instructions are written by write_expr")
+        }
+        Unreachable => writer.write(&0x00u8),
+        Nop => writer.write(&0x01u8),
+        Br(label) => writer.write(&0x0Cu8).write(label),
+        BrIf(label) => writer.write(&0x0Du8).write(label),
+        BrTable(labels, default) => {
+            writer.write(&0x0Eu8);
+            write_vec(writer, labels);
+            writer.write(default)
+        }
+        Return => writer.write(&0x0Fu8),
+        Call(function) => writer.write(&0x10u8).write(function),
+        CallIndirect(ty, table) => writer.write(&0x11u8).write(ty).write(table),
+        ReturnCall(function) => writer.write(&0x12u8).write(function),
+        ReturnCallIndirect(ty, table) => writer.write(&0x13u8).write(ty).write(table),
+        CallRef(ty) => writer.write(&0x14u8).write(ty),
+        ReturnCallRef(ty) => writer.write(&0x15u8).write(ty),
+        BrOnNull(label) => writer.write(&0xD5u8).write(label),
+        BrOnNonNull(label) => writer.write(&0xD6u8).write(label),
+
+        // Exception Handling Instructions
+        Throw(tag) => writer.write(&0x08u8).write(tag),
+        ThrowRef => writer.write(&0x0Au8),
+
+        // Reference Instructions
+        RefNull(ty) => writer.write(&0xD0u8).write(&ty.heap_type()),
+        RefIsNull => writer.write(&0xD1u8),
+        RefFunc(function) => writer.write(&0xD2u8).write(function),
+        RefAsNonNull => writer.write(&0xD4u8),
+
+        // Parametric Instructions
+        Drop => writer.write(&0x1Au8),
+        Select => writer.write(&0x1Bu8),
+        SelectMultiple(types) => {
+            writer.write(&0x1Cu8);
+            write_vec(writer, types);
+            writer
+        }
+
+        // Variable Instructions
+        LocalGet(local) => writer.write(&0x20u8).write(local),
+        LocalSet(local) => writer.write(&0x21u8).write(local),
+        LocalTee(local) => writer.write(&0x22u8).write(local),
+        GlobalGet(global) => writer.write(&0x23u8).write(global),
+        GlobalSet(global) => writer.write(&0x24u8).write(global),
+
+        // Table Instructions
+        TableGet(table) => writer.write(&0x25u8).write(table),
+        TableSet(table) => writer.write(&0x26u8).write(table),
+        TableInit(elem, table) => write_prefixed(writer, 12).write(elem).write(table),
+        ElemDrop(elem) => write_prefixed(writer, 13).write(elem),
+        TableCopy(dst, src) => write_prefixed(writer, 14).write(dst).write(src),
+        TableGrow(table) => write_prefixed(writer, 15).write(table),
+        TableSize(table) => write_prefixed(writer, 16).write(table),
+        TableFill(table) => write_prefixed(writer, 17).write(table),
+
+        // Memory Instructions
+        i32_load(memarg) => writer.write(&0x28u8).write(memarg),
+        i64_load(memarg) => writer.write(&0x29u8).write(memarg),
+        f32_load(memarg) => writer.write(&0x2Au8).write(memarg),
+        f64_load(memarg) => writer.write(&0x2Bu8).write(memarg),
+        i32_load_8s(memarg) => writer.write(&0x2Cu8).write(memarg),
+        i32_load_8u(memarg) => writer.write(&0x2Du8).write(memarg),
+        i32_load_16s(memarg) => writer.write(&0x2Eu8).write(memarg),
+        i32_load_16u(memarg) => writer.write(&0x2Fu8).write(memarg),
+        i64_load_8s(memarg) => writer.write(&0x30u8).write(memarg),
+        i64_load_8u(memarg) => writer.write(&0x31u8).write(memarg),
+        i64_load_16s(memarg) => writer.write(&0x32u8).write(memarg),
+        i64_load_16u(memarg) => writer.write(&0x33u8).write(memarg),
+        i64_load_32s(memarg) => writer.write(&0x34u8).write(memarg),
+        i64_load_32u(memarg) => writer.write(&0x35u8).write(memarg),
+        i32_store(memarg) => writer.write(&0x36u8).write(memarg),
+        i64_store(memarg) => writer.write(&0x37u8).write(memarg),
+        f32_store(memarg) => writer.write(&0x38u8).write(memarg),
+        f64_store(memarg) => writer.write(&0x39u8).write(memarg),
+        i32_store_8(memarg) => writer.write(&0x3Au8).write(memarg),
+        i32_store_16(memarg) => writer.write(&0x3Bu8).write(memarg),
+        i64_store_8(memarg) => writer.write(&0x3Cu8).write(memarg),
+        i64_store_16(memarg) => writer.write(&0x3Du8).write(memarg),
+        i64_store_32(memarg) => writer.write(&0x3Eu8).write(memarg),
+        MemorySize => writer.write(&0x3Fu8).write(&0x00u8),
+        MemoryGrow => writer.write(&0x40u8).write(&0x00u8),
+        MemoryInit(data) => write_prefixed(writer, 8).write(data).write(&0x00u8),
+        DataDrop(data) => write_prefixed(writer, 9).write(data),
+        MemoryCopy => write_prefixed(writer, 10).write(&0x00u8).write(&0x00u8),
+        MemoryFill => write_prefixed(writer, 11).write(&0x00u8),
+
+        // Numeric Instructions
+        i32_const(value) => writer.write(&0x41u8).write_leb128(*value),
+        i64_const(value) => writer.write(&0x42u8).write_leb128(*value),
+        f32_const(value) => writer.write(&0x43u8).write(value),
+        f64_const(value) => writer.write(&0x44u8).write(value),
+        i32_eqz => writer.write(&0x45u8),
+        i32_eq => writer.write(&0x46u8),
+        i32_ne => writer.write(&0x47u8),
+        i32_lt_s => writer.write(&0x48u8),
+        i32_lt_u => writer.write(&0x49u8),
+        i32_gt_s => writer.write(&0x4Au8),
+        i32_gt_u => writer.write(&0x4Bu8),
+        i32_le_s => writer.write(&0x4Cu8),
+        i32_le_u => writer.write(&0x4Du8),
+        i32_ge_s => writer.write(&0x4Eu8),
+        i32_ge_u => writer.write(&0x4Fu8),
+
+        i64_eqz => writer.write(&0x50u8),
+        i64_eq => writer.write(&0x51u8),
+        i64_ne => writer.write(&0x52u8),
+        i64_lt_s => writer.write(&0x53u8),
+        i64_lt_u => writer.write(&0x54u8),
+        i64_gt_s => writer.write(&0x55u8),
+        i64_gt_u => writer.write(&0x56u8),
+        i64_le_s => writer.write(&0x57u8),
+        i64_le_u => writer.write(&0x58u8),
+        i64_ge_s => writer.write(&0x59u8),
+        i64_ge_u => writer.write(&0x5Au8),
+
+        f32_eq => writer.write(&0x5Bu8),
+        f32_ne => writer.write(&0x5Cu8),
+        f32_lt => writer.write(&0x5Du8),
+        f32_gt => writer.write(&0x5Eu8),
+        f32_le => writer.write(&0x5Fu8),
+        f32_ge => writer.write(&0x60u8),
+
+        f64_eq => writer.write(&0x61u8),
+        f64_ne => writer.write(&0x62u8),
+        f64_lt => writer.write(&0x63u8),
+        f64_gt => writer.write(&0x64u8),
+        f64_le => writer.write(&0x65u8),
+        f64_ge => writer.write(&0x66u8),
+
+        i32_clz => writer.write(&0x67u8),
+        i32_ctz => writer.write(&0x68u8),
+        i32_popcnt => writer.write(&0x69u8),
+        i32_add => writer.write(&0x6Au8),
+        i32_sub => writer.write(&0x6Bu8),
+        i32_mul => writer.write(&0x6Cu8),
+        i32_div_s => writer.write(&0x6Du8),
+        i32_div_u => writer.write(&0x6Eu8),
+        i32_rem_s => writer.write(&0x6Fu8),
+        i32_rem_u => writer.write(&0x70u8),
+        i32_and => writer.write(&0x71u8),
+        i32_or => writer.write(&0x72u8),
+        i32_xor => writer.write(&0x73u8),
+        i32_shl => writer.write(&0x74u8),
+        i32_shr_s => writer.write(&0x75u8),
+        i32_shr_u => writer.write(&0x76u8),
+        i32_rotl => writer.write(&0x77u8),
+        i32_rotr => writer.write(&0x78u8),
+
+        i64_clz => writer.write(&0x79u8),
+        i64_ctz => writer.write(&0x7Au8),
+        i64_popcnt => writer.write(&0x7Bu8),
+        i64_add => writer.write(&0x7Cu8),
+        i64_sub => writer.write(&0x7Du8),
+        i64_mul => writer.write(&0x7Eu8),
+        i64_div_s => writer.write(&0x7Fu8),
+        i64_div_u => writer.write(&0x80u8),
+        i64_rem_s => writer.write(&0x81u8),
+        i64_rem_u => writer.write(&0x82u8),
+        i64_and => writer.write(&0x83u8),
+        i64_or => writer.write(&0x84u8),
+        i64_xor => writer.write(&0x85u8),
+        i64_shl => writer.write(&0x86u8),
+        i64_shr_s => writer.write(&0x87u8),
+        i64_shr_u => writer.write(&0x88u8),
+        i64_rotl => writer.write(&0x89u8),
+        i64_rotr => writer.write(&0x8Au8),
+
+        f32_abs => writer.write(&0x8Bu8),
+        f32_neg => writer.write(&0x8Cu8),
+        f32_ceil => writer.write(&0x8Du8),
+        f32_floor => writer.write(&0x8Eu8),
+        f32_trunc => writer.write(&0x8Fu8),
+        f32_nearest => writer.write(&0x90u8),
+        f32_sqrt => writer.write(&0x91u8),
+        f32_add => writer.write(&0x92u8),
+        f32_sub => writer.write(&0x93u8),
+        f32_mul => writer.write(&0x94u8),
+        f32_div => writer.write(&0x95u8),
+        f32_min => writer.write(&0x96u8),
+        f32_max => writer.write(&0x97u8),
+        f32_copysign => writer.write(&0x98u8),
+
+        f64_abs => writer.write(&0x99u8),
+        f64_neg => writer.write(&0x9Au8),
+        f64_ceil => writer.write(&0x9Bu8),
+        f64_floor => writer.write(&0x9Cu8),
+        f64_trunc => writer.write(&0x9Du8),
+        f64_nearest => writer.write(&0x9Eu8),
+        f64_sqrt => writer.write(&0x9Fu8),
+        f64_add => writer.write(&0xA0u8),
+        f64_sub => writer.write(&0xA1u8),
+        f64_mul => writer.write(&0xA2u8),
+        f64_div => writer.write(&0xA3u8),
+        f64_min => writer.write(&0xA4u8),
+        f64_max => writer.write(&0xA5u8),
+        f64_copysign => writer.write(&0xA6u8),
+
+        i32_wrap_i64 => writer.write(&0xA7u8),
+        i32_trunc_f32_s => writer.write(&0xA8u8),
+        i32_trunc_f32_u => writer.write(&0xA9u8),
+        i32_trunc_f64_s => writer.write(&0xAAu8),
+        i32_trunc_f64_u => writer.write(&0xABu8),
+        i64_extend_i32_s => writer.write(&0xACu8),
+        i64_extend_i32_u => writer.write(&0xADu8),
+        i64_trunc_f32_s => writer.write(&0xAEu8),
+        i64_trunc_f32_u => writer.write(&0xAFu8),
+        i64_trunc_f64_s => writer.write(&0xB0u8),
+        i64_trunc_f64_u => writer.write(&0xB1u8),
+        f32_convert_i32_s => writer.write(&0xB2u8),
+        f32_convert_i32_u => writer.write(&0xB3u8),
+        f32_convert_i64_s => writer.write(&0xB4u8),
+        f32_convert_i64_u => writer.write(&0xB5u8),
+        f32_demote_f64 => writer.write(&0xB6u8),
+        f64_convert_i32_s => writer.write(&0xB7u8),
+        f64_convert_i32_u => writer.write(&0xB8u8),
+        f64_convert_i64_s => writer.write(&0xB9u8),
+        f64_convert_i64_u => writer.write(&0xBAu8),
+        f64_promote_f32 => writer.write(&0xBBu8),
+        i32_reinterpret_f32 => writer.write(&0xBCu8),
+        i64_reinterpret_f64 => writer.write(&0xBDu8),
+        f32_reinterpret_i32 => writer.write(&0xBEu8),
+        f64_reinterpret_i64 => writer.write(&0xBFu8),
+
+        i32_extend8_s => writer.write(&0xC0u8),
+        i32_extend16_s => writer.write(&0xC1u8),
+        i64_extend8_s => writer.write(&0xC2u8),
+        i64_extend16_s => writer.write(&0xC3u8),
+        i64_extend32_s => writer.write(&0xC4u8),
+
+        i32_trunc_sat_f32_s => write_prefixed(writer, 0),
+        i32_trunc_sat_f32_u => write_prefixed(writer, 1),
+        i32_trunc_sat_f64_s => write_prefixed(writer, 2),
+        i32_trunc_sat_f64_u => write_prefixed(writer, 3),
+        i64_trunc_sat_f32_s => write_prefixed(writer, 4),
+        i64_trunc_sat_f32_u => write_prefixed(writer, 5),
+        i64_trunc_sat_f64_s => write_prefixed(writer, 6),
+        i64_trunc_sat_f64_u => write_prefixed(writer, 7),
+
+        v128_load(memarg) => write_vector_prefixed(writer, 0).write(memarg),
+        v128_load8x8_s(memarg) => write_vector_prefixed(writer, 1).write(memarg),
+        v128_load8x8_u(memarg) => write_vector_prefixed(writer, 2).write(memarg),
+        v128_load16x4_s(memarg) => write_vector_prefixed(writer, 3).write(memarg),
+        v128_load16x4_u(memarg) => write_vector_prefixed(writer, 4).write(memarg),
+        v128_load32x2_s(memarg) => write_vector_prefixed(writer, 5).write(memarg),
+        v128_load32x2_u(memarg) => write_vector_prefixed(writer, 6).write(memarg),
+        v128_load8_splat(memarg) => write_vector_prefixed(writer, 7).write(memarg),
+        v128_load16_splat(memarg) => write_vector_prefixed(writer, 8).write(memarg),
+        v128_load32_splat(memarg) => write_vector_prefixed(writer, 9).write(memarg),
+        v128_load64_splat(memarg) => write_vector_prefixed(writer, 10).write(memarg),
+        v128_store(memarg) => write_vector_prefixed(writer, 11).write(memarg),
+        v128_const(value) => write_vector_prefixed(writer, 12).write(value),
+        i8x16_shuffle(lanes) => write_vector_prefixed(writer, 13).write_bytes(lanes),
+        i8x16_swizzle => write_vector_prefixed(writer, 14),
+        i8x16_splat => write_vector_prefixed(writer, 15),
+        i16x8_splat => write_vector_prefixed(writer, 16),
+        i32x4_splat => write_vector_prefixed(writer, 17),
+        i64x2_splat => write_vector_prefixed(writer, 18),
+        f32x4_splat => write_vector_prefixed(writer, 19),
+        f64x2_splat => write_vector_prefixed(writer, 20),
+        i8x16_extract_lane_s(lane) => write_vector_prefixed(writer, 21).write(lane),
+        i8x16_extract_lane_u(lane) => write_vector_prefixed(writer, 22).write(lane),
+        i8x16_replace_lane(lane) => write_vector_prefixed(writer, 23).write(lane),
+        i16x8_extract_lane_s(lane) => write_vector_prefixed(writer, 24).write(lane),
+        i16x8_extract_lane_u(lane) => write_vector_prefixed(writer, 25).write(lane),
+        i16x8_replace_lane(lane) => write_vector_prefixed(writer, 26).write(lane),
+        i32x4_extract_lane(lane) => write_vector_prefixed(writer, 27).write(lane),
+        i32x4_replace_lane(lane) => write_vector_prefixed(writer, 28).write(lane),
+        i64x2_extract_lane(lane) => write_vector_prefixed(writer, 29).write(lane),
+        i64x2_replace_lane(lane) => write_vector_prefixed(writer, 30).write(lane),
+        f32x4_extract_lane(lane) => write_vector_prefixed(writer, 31).write(lane),
+        f32x4_replace_lane(lane) => write_vector_prefixed(writer, 32).write(lane),
+        f64x2_extract_lane(lane) => write_vector_prefixed(writer, 33).write(lane),
+        f64x2_replace_lane(lane) => write_vector_prefixed(writer, 34).write(lane),
+        i8x16_eq => write_vector_prefixed(writer, 35),
+        i8x16_ne => write_vector_prefixed(writer, 36),
+        i8x16_lt_s => write_vector_prefixed(writer, 37),
+        i8x16_lt_u => write_vector_prefixed(writer, 38),
+        i8x16_gt_s => write_vector_prefixed(writer, 39),
+        i8x16_gt_u => write_vector_prefixed(writer, 40),
+        i8x16_le_s => write_vector_prefixed(writer, 41),
+        i8x16_le_u => write_vector_prefixed(writer, 42),
+        i8x16_ge_s => write_vector_prefixed(writer, 43),
+        i8x16_ge_u => write_vector_prefixed(writer, 44),
+        i16x8_eq => write_vector_prefixed(writer, 45),
+        i16x8_ne => write_vector_prefixed(writer, 46),
+        i16x8_lt_s => write_vector_prefixed(writer, 47),
+        i16x8_lt_u => write_vector_prefixed(writer, 48),
+        i16x8_gt_s => write_vector_prefixed(writer, 49),
+        i16x8_gt_u => write_vector_prefixed(writer, 50),
+        i16x8_le_s => write_vector_prefixed(writer, 51),
+        i16x8_le_u => write_vector_prefixed(writer, 52),
+        i16x8_ge_s => write_vector_prefixed(writer, 53),
+        i16x8_ge_u => write_vector_prefixed(writer, 54),
+        i32x4_eq => write_vector_prefixed(writer, 55),
+        i32x4_ne => write_vector_prefixed(writer, 56),
+        i32x4_lt_s => write_vector_prefixed(writer, 57),
+        i32x4_lt_u => write_vector_prefixed(writer, 58),
+        i32x4_gt_s => write_vector_prefixed(writer, 59),
+        i32x4_gt_u => write_vector_prefixed(writer, 60),
+        i32x4_le_s => write_vector_prefixed(writer, 61),
+        i32x4_le_u => write_vector_prefixed(writer, 62),
+        i32x4_ge_s => write_vector_prefixed(writer, 63),
+        i32x4_ge_u => write_vector_prefixed(writer, 64),
+        f32x4_eq => write_vector_prefixed(writer, 65),
+        f32x4_ne => write_vector_prefixed(writer, 66),
+        f32x4_lt => write_vector_prefixed(writer, 67),
+        f32x4_gt => write_vector_prefixed(writer, 68),
+        f32x4_le => write_vector_prefixed(writer, 69),
+        f32x4_ge => write_vector_prefixed(writer, 70),
+        f64x2_eq => write_vector_prefixed(writer, 71),
+        f64x2_ne => write_vector_prefixed(writer, 72),
+        f64x2_lt => write_vector_prefixed(writer, 73),
+        f64x2_gt => write_vector_prefixed(writer, 74),
+        f64x2_le => write_vector_prefixed(writer, 75),
+        f64x2_ge => write_vector_prefixed(writer, 76),
+        v128_not => write_vector_prefixed(writer, 77),
+        v128_and => write_vector_prefixed(writer, 78),
+        v128_andnot => write_vector_prefixed(writer, 79),
+        v128_or => write_vector_prefixed(writer, 80),
+        v128_xor => write_vector_prefixed(writer, 81),
+        v128_bitselect => write_vector_prefixed(writer, 82),
+        v128_any_true => write_vector_prefixed(writer, 83),
+        v128_load8_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 84).write(memarg).write(lane)
+        }
+        v128_load16_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 85).write(memarg).write(lane)
+        }
+        v128_load32_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 86).write(memarg).write(lane)
+        }
+        v128_load64_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 87).write(memarg).write(lane)
+        }
+        v128_store8_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 88).write(memarg).write(lane)
+        }
+        v128_store16_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 89).write(memarg).write(lane)
+        }
+        v128_store32_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 90).write(memarg).write(lane)
+        }
+        v128_store64_lane(memarg, lane) => {
+            write_vector_prefixed(writer, 91).write(memarg).write(lane)
+        }
+        v128_load32_zero(memarg) => write_vector_prefixed(writer, 92).write(memarg),
+        v128_load64_zero(memarg) => write_vector_prefixed(writer, 93).write(memarg),
+        f32x4_demote_f64x2_zero => write_vector_prefixed(writer, 94),
+        f64x2_promote_low_f32x4 => write_vector_prefixed(writer, 95),
+        i8x16_abs => write_vector_prefixed(writer, 96),
+        i8x16_neg => write_vector_prefixed(writer, 97),
+        i8x16_popcnt => write_vector_prefixed(writer, 98),
+        i8x16_all_true => write_vector_prefixed(writer, 99),
+        i8x16_bitmask => write_vector_prefixed(writer, 100),
+        i8x16_narrow_i16x8_s => write_vector_prefixed(writer, 101),
+        i8x16_narrow_i16x8_u => write_vector_prefixed(writer, 102),
+        f32x4_ceil => write_vector_prefixed(writer, 103),
+        f32x4_floor => write_vector_prefixed(writer, 104),
+        f32x4_trunc => write_vector_prefixed(writer, 105),
+        f32x4_nearest => write_vector_prefixed(writer, 106),
+        i8x16_shl => write_vector_prefixed(writer, 107),
+        i8x16_shr_s => write_vector_prefixed(writer, 108),
+        i8x16_shr_u => write_vector_prefixed(writer, 109),
+        i8x16_add => write_vector_prefixed(writer, 110),
+        i8x16_add_sat_s => write_vector_prefixed(writer, 111),
+        i8x16_add_sat_u => write_vector_prefixed(writer, 112),
+        i8x16_sub => write_vector_prefixed(writer, 113),
+        i8x16_sub_sat_s => write_vector_prefixed(writer, 114),
+        i8x16_sub_sat_u => write_vector_prefixed(writer, 115),
+        f64x2_ceil => write_vector_prefixed(writer, 116),
+        f64x2_floor => write_vector_prefixed(writer, 117),
+        i8x16_min_s => write_vector_prefixed(writer, 118),
+        i8x16_min_u => write_vector_prefixed(writer, 119),
+        i8x16_max_s => write_vector_prefixed(writer, 120),
+        i8x16_max_u => write_vector_prefixed(writer, 121),
+        f64x2_trunc => write_vector_prefixed(writer, 122),
+        i8x16_avgr_u => write_vector_prefixed(writer, 123),
+        i16x8_extadd_pairwise_i8x16_s => write_vector_prefixed(writer, 124),
+        i16x8_extadd_pairwise_i8x16_u => write_vector_prefixed(writer, 125),
+        i32x4_extadd_pairwise_i16x8_s => write_vector_prefixed(writer, 126),
+        i32x4_extadd_pairwise_i16x8_u => write_vector_prefixed(writer, 127),
+        i16x8_abs => write_vector_prefixed(writer, 128),
+        i16x8_neg => write_vector_prefixed(writer, 129),
+        i16x8_q15mulr_sat_s => write_vector_prefixed(writer, 130),
+        i16x8_all_true => write_vector_prefixed(writer, 131),
+        i16x8_bitmask => write_vector_prefixed(writer, 132),
+        i16x8_narrow_i32x4_s => write_vector_prefixed(writer, 133),
+        i16x8_narrow_i32x4_u => write_vector_prefixed(writer, 134),
+        i16x8_extend_low_i8x16_s => write_vector_prefixed(writer, 135),
+        i16x8_extend_high_i8x16_s => write_vector_prefixed(writer, 136),
+        i16x8_extend_low_i8x16_u => write_vector_prefixed(writer, 137),
+        i16x8_extend_high_i8x16_u => write_vector_prefixed(writer, 138),
+        i16x8_shl => write_vector_prefixed(writer, 139),
+        i16x8_shr_s => write_vector_prefixed(writer, 140),
+        i16x8_shr_u => write_vector_prefixed(writer, 141),
+        i16x8_add => write_vector_prefixed(writer, 142),
+        i16x8_add_sat_s => write_vector_prefixed(writer, 143),
+        i16x8_add_sat_u => write_vector_prefixed(writer, 144),
+        i16x8_sub => write_vector_prefixed(writer, 145),
+        i16x8_sub_sat_s => write_vector_prefixed(writer, 146),
+        i16x8_sub_sat_u => write_vector_prefixed(writer, 147),
+        f64x2_nearest => write_vector_prefixed(writer, 148),
+        i16x8_mul => write_vector_prefixed(writer, 149),
+        i16x8_min_s => write_vector_prefixed(writer, 150),
+        i16x8_min_u => write_vector_prefixed(writer, 151),
+        i16x8_max_s => write_vector_prefixed(writer, 152),
+        i16x8_max_u => write_vector_prefixed(writer, 153),
+        i16x8_avgr_u => write_vector_prefixed(writer, 155),
+        i16x8_extmul_low_i8x16_s => write_vector_prefixed(writer, 156),
+        i16x8_extmul_high_i8x16_s => write_vector_prefixed(writer, 157),
+        i16x8_extmul_low_i8x16_u => write_vector_prefixed(writer, 158),
+        i16x8_extmul_high_i8x16_u => write_vector_prefixed(writer, 159),
+        i32x4_abs => write_vector_prefixed(writer, 160),
+        i32x4_neg => write_vector_prefixed(writer, 161),
+        i32x4_all_true => write_vector_prefixed(writer, 163),
+        i32x4_bitmask => write_vector_prefixed(writer, 164),
+        i32x4_extend_low_i16x8_s => write_vector_prefixed(writer, 167),
+        i32x4_extend_high_i16x8_s => write_vector_prefixed(writer, 168),
+        i32x4_extend_low_i16x8_u => write_vector_prefixed(writer, 169),
+        i32x4_extend_high_i16x8_u => write_vector_prefixed(writer, 170),
+        i32x4_shl => write_vector_prefixed(writer, 171),
+        i32x4_shr_s => write_vector_prefixed(writer, 172),
+        i32x4_shr_u => write_vector_prefixed(writer, 173),
+        i32x4_add => write_vector_prefixed(writer, 174),
+        i32x4_sub => write_vector_prefixed(writer, 177),
+        i32x4_mul => write_vector_prefixed(writer, 181),
+        i32x4_min_s => write_vector_prefixed(writer, 182),
+        i32x4_min_u => write_vector_prefixed(writer, 183),
+        i32x4_max_s => write_vector_prefixed(writer, 184),
+        i32x4_max_u => write_vector_prefixed(writer, 185),
+        i32x4_dot_i16x8_s => write_vector_prefixed(writer, 186),
+        i32x4_extmul_low_i16x8_s => write_vector_prefixed(writer, 188),
+        i32x4_extmul_high_i16x8_s => write_vector_prefixed(writer, 189),
+        i32x4_extmul_low_i16x8_u => write_vector_prefixed(writer, 190),
+        i32x4_extmul_high_i16x8_u => write_vector_prefixed(writer, 191),
+        i64x2_abs => write_vector_prefixed(writer, 192),
+        i64x2_neg => write_vector_prefixed(writer, 193),
+        i64x2_all_true => write_vector_prefixed(writer, 195),
+        i64x2_bitmask => write_vector_prefixed(writer, 196),
+        i64x2_extend_low_i32x4_s => write_vector_prefixed(writer, 199),
+        i64x2_extend_high_i32x4_s => write_vector_prefixed(writer, 200),
+        i64x2_extend_low_i32x4_u => write_vector_prefixed(writer, 201),
+        i64x2_extend_high_i32x4_u => write_vector_prefixed(writer, 202),
+        i64x2_shl => write_vector_prefixed(writer, 203),
+        i64x2_shr_s => write_vector_prefixed(writer, 204),
+        i64x2_shr_u => write_vector_prefixed(writer, 205),
+        i64x2_add => write_vector_prefixed(writer, 206),
+        i64x2_sub => write_vector_prefixed(writer, 209),
+        i64x2_mul => write_vector_prefixed(writer, 213),
+        i64x2_eq => write_vector_prefixed(writer, 214),
+        i64x2_ne => write_vector_prefixed(writer, 215),
+        i64x2_lt_s => write_vector_prefixed(writer, 216),
+        i64x2_gt_s => write_vector_prefixed(writer, 217),
+        i64x2_le_s => write_vector_prefixed(writer, 218),
+        i64x2_ge_s => write_vector_prefixed(writer, 219),
+        i64x2_extmul_low_i32x4_s => write_vector_prefixed(writer, 220),
+        i64x2_extmul_high_i32x4_s => write_vector_prefixed(writer, 221),
+        i64x2_extmul_low_i32x4_u => write_vector_prefixed(writer, 222),
+        i64x2_extmul_high_i32x4_u => write_vector_prefixed(writer, 223),
+        f32x4_abs => write_vector_prefixed(writer, 224),
+        f32x4_neg => write_vector_prefixed(writer, 225),
+        f32x4_sqrt => write_vector_prefixed(writer, 227),
+        f32x4_add => write_vector_prefixed(writer, 228),
+        f32x4_sub => write_vector_prefixed(writer, 229),
+        f32x4_mul => write_vector_prefixed(writer, 230),
+        f32x4_div => write_vector_prefixed(writer, 231),
+        f32x4_min => write_vector_prefixed(writer, 232),
+        f32x4_max => write_vector_prefixed(writer, 233),
+        f32x4_pmin => write_vector_prefixed(writer, 234),
+        f32x4_pmax => write_vector_prefixed(writer, 235),
+        f64x2_abs => write_vector_prefixed(writer, 236),
+        f64x2_neg => write_vector_prefixed(writer, 237),
+        f64x2_sqrt => write_vector_prefixed(writer, 239),
+        f64x2_add => write_vector_prefixed(writer, 240),
+        f64x2_sub => write_vector_prefixed(writer, 241),
+        f64x2_mul => write_vector_prefixed(writer, 242),
+        f64x2_div => write_vector_prefixed(writer, 243),
+        f64x2_min => write_vector_prefixed(writer, 244),
+        f64x2_max => write_vector_prefixed(writer, 245),
+        f64x2_pmin => write_vector_prefixed(writer, 246),
+        f64x2_pmax => write_vector_prefixed(writer, 247),
+        i32x4_trunc_sat_f32x4_s => write_vector_prefixed(writer, 248),
+        i32x4_trunc_sat_f32x4_u => write_vector_prefixed(writer, 249),
+        f32x4_convert_i32x4_s => write_vector_prefixed(writer, 250),
+        f32x4_convert_i32x4_u => write_vector_prefixed(writer, 251),
+        i32x4_trunc_sat_f64x2_s_zero => write_vector_prefixed(writer, 252),
+        i32x4_trunc_sat_f64x2_u_zero => write_vector_prefixed(writer, 253),
+        f64x2_convert_low_i32x4_s => write_vector_prefixed(writer, 254),
+        f64x2_convert_low_i32x4_u => write_vector_prefixed(writer, 255),
+
+        memory_atomic_notify(memarg) => write_atomic_prefixed(writer, 0).write(memarg),
+        memory_atomic_wait32(memarg) => write_atomic_prefixed(writer, 1).write(memarg),
+        memory_atomic_wait64(memarg) => write_atomic_prefixed(writer, 2).write(memarg),
+        atomic_fence => write_atomic_prefixed(writer, 3).write(&0x00u8),
+        i32_atomic_load(memarg) => write_atomic_prefixed(writer, 16).write(memarg),
+        i64_atomic_load(memarg) => write_atomic_prefixed(writer, 17).write(memarg),
+        i32_atomic_load8_u(memarg) => write_atomic_prefixed(writer, 18).write(memarg),
+        i32_atomic_load16_u(memarg) => write_atomic_prefixed(writer, 19).write(memarg),
+        i64_atomic_load8_u(memarg) => write_atomic_prefixed(writer, 20).write(memarg),
+        i64_atomic_load16_u(memarg) => write_atomic_prefixed(writer, 21).write(memarg),
+        i64_atomic_load32_u(memarg) => write_atomic_prefixed(writer, 22).write(memarg),
+        i32_atomic_store(memarg) => write_atomic_prefixed(writer, 23).write(memarg),
+        i64_atomic_store(memarg) => write_atomic_prefixed(writer, 24).write(memarg),
+        i32_atomic_store8(memarg) => write_atomic_prefixed(writer, 25).write(memarg),
+        i32_atomic_store16(memarg) => write_atomic_prefixed(writer, 26).write(memarg),
+        i64_atomic_store8(memarg) => write_atomic_prefixed(writer, 27).write(memarg),
+        i64_atomic_store16(memarg) => write_atomic_prefixed(writer, 28).write(memarg),
+        i64_atomic_store32(memarg) => write_atomic_prefixed(writer, 29).write(memarg),
+        i32_atomic_rmw_add(memarg) => write_atomic_prefixed(writer, 30).write(memarg),
+        i64_atomic_rmw_add(memarg) => write_atomic_prefixed(writer, 31).write(memarg),
+        i32_atomic_rmw8_add_u(memarg) => write_atomic_prefixed(writer, 32).write(memarg),
+        i32_atomic_rmw16_add_u(memarg) => write_atomic_prefixed(writer, 33).write(memarg),
+        i64_atomic_rmw8_add_u(memarg) => write_atomic_prefixed(writer, 34).write(memarg),
+        i64_atomic_rmw16_add_u(memarg) => write_atomic_prefixed(writer, 35).write(memarg),
+        i64_atomic_rmw32_add_u(memarg) => write_atomic_prefixed(writer, 36).write(memarg),
+        i32_atomic_rmw_sub(memarg) => write_atomic_prefixed(writer, 37).write(memarg),
+        i64_atomic_rmw_sub(memarg) => write_atomic_prefixed(writer, 38).write(memarg),
+        i32_atomic_rmw8_sub_u(memarg) => write_atomic_prefixed(writer, 39).write(memarg),
+        i32_atomic_rmw16_sub_u(memarg) => write_atomic_prefixed(writer, 40).write(memarg),
+        i64_atomic_rmw8_sub_u(memarg) => write_atomic_prefixed(writer, 41).write(memarg),
+        i64_atomic_rmw16_sub_u(memarg) => write_atomic_prefixed(writer, 42).write(memarg),
+        i64_atomic_rmw32_sub_u(memarg) => write_atomic_prefixed(writer, 43).write(memarg),
+        i32_atomic_rmw_and(memarg) => write_atomic_prefixed(writer, 44).write(memarg),
+        i64_atomic_rmw_and(memarg) => write_atomic_prefixed(writer, 45).write(memarg),
+        i32_atomic_rmw8_and_u(memarg) => write_atomic_prefixed(writer, 46).write(memarg),
+        i32_atomic_rmw16_and_u(memarg) => write_atomic_prefixed(writer, 47).write(memarg),
+        i64_atomic_rmw8_and_u(memarg) => write_atomic_prefixed(writer, 48).write(memarg),
+        i64_atomic_rmw16_and_u(memarg) => write_atomic_prefixed(writer, 49).write(memarg),
+        i64_atomic_rmw32_and_u(memarg) => write_atomic_prefixed(writer, 50).write(memarg),
+        i32_atomic_rmw_or(memarg) => write_atomic_prefixed(writer, 51).write(memarg),
+        i64_atomic_rmw_or(memarg) => write_atomic_prefixed(writer, 52).write(memarg),
+        i32_atomic_rmw8_or_u(memarg) => write_atomic_prefixed(writer, 53).write(memarg),
+        i32_atomic_rmw16_or_u(memarg) => write_atomic_prefixed(writer, 54).write(memarg),
+        i64_atomic_rmw8_or_u(memarg) => write_atomic_prefixed(writer, 55).write(memarg),
+        i64_atomic_rmw16_or_u(memarg) => write_atomic_prefixed(writer, 56).write(memarg),
+        i64_atomic_rmw32_or_u(memarg) => write_atomic_prefixed(writer, 57).write(memarg),
+        i32_atomic_rmw_xor(memarg) => write_atomic_prefixed(writer, 58).write(memarg),
+        i64_atomic_rmw_xor(memarg) => write_atomic_prefixed(writer, 59).write(memarg),
+        i32_atomic_rmw8_xor_u(memarg) => write_atomic_prefixed(writer, 60).write(memarg),
+        i32_atomic_rmw16_xor_u(memarg) => write_atomic_prefixed(writer, 61).write(memarg),
+        i64_atomic_rmw8_xor_u(memarg) => write_atomic_prefixed(writer, 62).write(memarg),
+        i64_atomic_rmw16_xor_u(memarg) => write_atomic_prefixed(writer, 63).write(memarg),
+        i64_atomic_rmw32_xor_u(memarg) => write_atomic_prefixed(writer, 64).write(memarg),
+        i32_atomic_rmw_xchg(memarg) => write_atomic_prefixed(writer, 65).write(memarg),
+        i64_atomic_rmw_xchg(memarg) => write_atomic_prefixed(writer, 66).write(memarg),
+        i32_atomic_rmw8_xchg_u(memarg) => write_atomic_prefixed(writer, 67).write(memarg),
+        i32_atomic_rmw16_xchg_u(memarg) => write_atomic_prefixed(writer, 68).write(memarg),
+        i64_atomic_rmw8_xchg_u(memarg) => write_atomic_prefixed(writer, 69).write(memarg),
+        i64_atomic_rmw16_xchg_u(memarg) => write_atomic_prefixed(writer, 70).write(memarg),
+        i64_atomic_rmw32_xchg_u(memarg) => write_atomic_prefixed(writer, 71).write(memarg),
+        i32_atomic_rmw_cmpxchg(memarg) => write_atomic_prefixed(writer, 72).write(memarg),
+        i64_atomic_rmw_cmpxchg(memarg) => write_atomic_prefixed(writer, 73).write(memarg),
+        i32_atomic_rmw8_cmpxchg_u(memarg) => write_atomic_prefixed(writer, 74).write(memarg),
+        i32_atomic_rmw16_cmpxchg_u(memarg) => write_atomic_prefixed(writer, 75).write(memarg),
+        i64_atomic_rmw8_cmpxchg_u(memarg) => write_atomic_prefixed(writer, 76).write(memarg),
+        i64_atomic_rmw16_cmpxchg_u(memarg) => write_atomic_prefixed(writer, 77).write(memarg),
+        i64_atomic_rmw32_cmpxchg_u(memarg) => write_atomic_prefixed(writer, 78).write(memarg),
+    };
+}
+
+/// Writes the 0xFC prefix of the numeric and bulk memory instructions, followed by their opcode
+fn write_prefixed(writer: &mut ByteWriter, opcode: u32) -> &mut ByteWriter {
+    writer.write(&0xFCu8).write_uleb128(opcode)
+}
+
+/// Writes the 0xFD prefix of the vector instructions, followed by their opcode
+fn write_vector_prefixed(writer: &mut ByteWriter, opcode: u32) -> &mut ByteWriter {
+    writer.write(&0xFDu8).write_uleb128(opcode)
+}
+
+/// Writes the 0xFE prefix of the atomic memory instructions, followed by their opcode
+fn write_atomic_prefixed(writer: &mut ByteWriter, opcode: u32) -> &mut ByteWriter {
+    writer.write(&0xFEu8).write_uleb128(opcode)
 }
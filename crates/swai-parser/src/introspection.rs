@@ -0,0 +1,396 @@
+use crate::{
+    sections::WasmSections,
+    types::{
+        ExportDesc, Expr, FunctionType, GlobalType, ImportDesc, Indecies, Locals, MemType,
+        TableType,
+    },
+    wasm::WasmModule,
+};
+
+/// Where a definition in one of the index spaces of a module comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin<'m> {
+    Imported {
+        module: &'m str,
+        name: &'m str,
+    },
+    /// Defined by the module itself, `index` is the position in its section, e.g. the function and code sections for functions
+    Defined {
+        index: u32,
+    },
+}
+
+impl Origin<'_> {
+    pub fn is_imported(&self) -> bool {
+        matches!(self, Origin::Imported { .. })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FunctionEntry<'m> {
+    pub index: u32,
+    pub type_index: u32,
+    pub ty: &'m FunctionType,
+    pub origin: Origin<'m>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableEntry<'m> {
+    pub index: u32,
+    pub ty: &'m TableType,
+    pub origin: Origin<'m>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryEntry<'m> {
+    pub index: u32,
+    pub ty: &'m MemType,
+    pub origin: Origin<'m>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalEntry<'m> {
+    pub index: u32,
+    pub ty: &'m GlobalType,
+    pub origin: Origin<'m>,
+    /// The initializer of a defined global, imported ones are initialized by the embedder
+    pub init: Option<&'m Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TagEntry<'m> {
+    pub index: u32,
+    pub type_index: u32,
+    /// The type of the values an exception with the tag carries
+    pub ty: &'m FunctionType,
+    pub origin: Origin<'m>,
+}
+
+/// The kinds of definitions a module can import and export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternKind {
+    Func,
+    Table,
+    Memory,
+    Global,
+    Tag,
+}
+
+impl std::fmt::Display for ExternKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ExternKind::Func => "func",
+            ExternKind::Table => "table",
+            ExternKind::Memory => "memory",
+            ExternKind::Global => "global",
+            ExternKind::Tag => "tag",
+        })
+    }
+}
+
+/// The resolved type of an import or export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExternType<'m> {
+    Func(&'m FunctionType),
+    Table(&'m TableType),
+    Memory(&'m MemType),
+    Global(&'m GlobalType),
+    Tag(&'m FunctionType),
+}
+
+impl ExternType<'_> {
+    pub fn kind(&self) -> ExternKind {
+        match self {
+            ExternType::Func(_) => ExternKind::Func,
+            ExternType::Table(_) => ExternKind::Table,
+            ExternType::Memory(_) => ExternKind::Memory,
+            ExternType::Global(_) => ExternKind::Global,
+            ExternType::Tag(_) => ExternKind::Tag,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Import<'m> {
+    pub module: &'m str,
+    pub name: &'m str,
+    /// The index the import gets in the index space of its kind
+    pub index: u32,
+    pub ty: ExternType<'m>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Export<'m> {
+    pub name: &'m str,
+    /// The index of the exported definition in the index space of its kind
+    pub index: u32,
+    pub ty: ExternType<'m>,
+}
+
+/// The locals and instructions of a function defined by the module
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FunctionBody<'m> {
+    pub index: u32,
+    /// Run-length encoded locals as they appear in the code section, the parameters come before them
+    pub locals: &'m Locals,
+    pub body: &'m Expr,
+}
+
+/// A read-only view of the index spaces of the module, as they're described in the spec:
+/// https://webassembly.github.io/spec/core/syntax/modules.html#indices
+///
+/// Imports come first in every index space. Functions and tags with a type index out of bounds and exports of
+/// definitions that don't exist, which [`crate::validate`] rejects, are left out instead of making every lookup fallible
+impl<'a> WasmModule<'a> {
+    pub fn functions(&self) -> Vec<FunctionEntry<'_>> {
+        let sections = &self.sections;
+        let imported = sections.imported(|desc| match desc {
+            ImportDesc::TypeIdx(index) => Some(index.index()),
+            _ => None,
+        });
+        let defined = sections
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(i, index)| (defined(i), index.index()));
+
+        imported
+            .chain(defined)
+            .enumerate()
+            .filter_map(|(index, (origin, type_index))| {
+                Some(FunctionEntry {
+                    index: index as u32,
+                    type_index,
+                    ty: sections.types.get(type_index as usize)?,
+                    origin,
+                })
+            })
+            .collect()
+    }
+
+    pub fn function(&self, index: u32) -> Option<FunctionEntry<'_>> {
+        self.functions()
+            .into_iter()
+            .find(|function| function.index == index)
+    }
+
+    pub fn tables(&self) -> Vec<TableEntry<'_>> {
+        let sections = &self.sections;
+        let imported = sections.imported(|desc| match desc {
+            ImportDesc::TableType(ty) => Some(ty),
+            _ => None,
+        });
+        let defined = sections
+            .tables
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (defined(i), ty));
+
+        imported
+            .chain(defined)
+            .enumerate()
+            .map(|(index, (origin, ty))| TableEntry {
+                index: index as u32,
+                ty,
+                origin,
+            })
+            .collect()
+    }
+
+    pub fn memories(&self) -> Vec<MemoryEntry<'_>> {
+        let sections = &self.sections;
+        let imported = sections.imported(|desc| match desc {
+            ImportDesc::MemType(ty) => Some(ty),
+            _ => None,
+        });
+        let defined = sections
+            .memory
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (defined(i), ty));
+
+        imported
+            .chain(defined)
+            .enumerate()
+            .map(|(index, (origin, ty))| MemoryEntry {
+                index: index as u32,
+                ty,
+                origin,
+            })
+            .collect()
+    }
+
+    pub fn globals(&self) -> Vec<GlobalEntry<'_>> {
+        let sections = &self.sections;
+        let imported = sections
+            .imported(|desc| match desc {
+                ImportDesc::GlobalType(ty) => Some(ty),
+                _ => None,
+            })
+            .map(|(origin, ty)| (origin, ty, None));
+        let defined = sections
+            .global
+            .iter()
+            .enumerate()
+            .map(|(i, (ty, init))| (defined(i), ty, Some(init)));
+
+        imported
+            .chain(defined)
+            .enumerate()
+            .map(|(index, (origin, ty, init))| GlobalEntry {
+                index: index as u32,
+                ty,
+                origin,
+                init,
+            })
+            .collect()
+    }
+
+    pub fn tags(&self) -> Vec<TagEntry<'_>> {
+        let sections = &self.sections;
+        let imported = sections.imported(|desc| match desc {
+            ImportDesc::TagType(ty) => Some(ty),
+            _ => None,
+        });
+        let defined = sections
+            .tags
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (defined(i), ty));
+
+        imported
+            .chain(defined)
+            .enumerate()
+            .filter_map(|(index, (origin, ty))| {
+                Some(TagEntry {
+                    index: index as u32,
+                    type_index: ty.type_index(),
+                    ty: sections.types.get(ty.type_index() as usize)?,
+                    origin,
+                })
+            })
+            .collect()
+    }
+
+    /// The imports in the order they're declared, with the index each one gets in its index space
+    pub fn imports(&self) -> Vec<Import<'_>> {
+        let sections = &self.sections;
+        let mut counts = [0u32; 5];
+        sections
+            .imports
+            .iter()
+            .filter_map(|(module, name, desc)| {
+                let types = &sections.types;
+                let (kind, ty) = match desc {
+                    ImportDesc::TypeIdx(index) => (
+                        ExternKind::Func,
+                        types.get(index.index() as usize).map(ExternType::Func),
+                    ),
+                    ImportDesc::TableType(ty) => (ExternKind::Table, Some(ExternType::Table(ty))),
+                    ImportDesc::MemType(ty) => (ExternKind::Memory, Some(ExternType::Memory(ty))),
+                    ImportDesc::GlobalType(ty) => {
+                        (ExternKind::Global, Some(ExternType::Global(ty)))
+                    }
+                    ImportDesc::TagType(ty) => (
+                        ExternKind::Tag,
+                        types.get(ty.type_index() as usize).map(ExternType::Tag),
+                    ),
+                };
+                // Imports that are left out still take up their index
+                let index = counts[kind as usize];
+                counts[kind as usize] += 1;
+                Some(Import {
+                    module: module.as_str(),
+                    name: name.as_str(),
+                    index,
+                    ty: ty?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn exports(&self) -> Vec<Export<'_>> {
+        self.sections
+            .export
+            .iter()
+            .filter_map(|(name, desc)| self.resolve_export(name.as_str(), *desc))
+            .collect()
+    }
+
+    /// Looks up an export by its name, e.g. to find the signature of an exported function before calling it
+    pub fn export(&self, name: &str) -> Option<Export<'_>> {
+        let (name, desc) = self
+            .sections
+            .export
+            .iter()
+            .find(|(export, _)| export.as_str() == name)?;
+        self.resolve_export(name.as_str(), *desc)
+    }
+
+    /// The body of the function at `index` in the function index space, `None` for imported functions
+    pub fn function_body(&self, index: u32) -> Option<FunctionBody<'_>> {
+        let Origin::Defined { index: defined } = self.function(index)?.origin else {
+            return None;
+        };
+        let code = self.sections.code.get(defined as usize)?;
+        Some(FunctionBody {
+            index,
+            locals: &code.locals,
+            body: &code.body,
+        })
+    }
+
+    fn resolve_export<'m>(&'m self, name: &'m str, desc: ExportDesc) -> Option<Export<'m>> {
+        let ty = match desc {
+            ExportDesc::FuncIdx(index) => ExternType::Func(self.function(index)?.ty),
+            ExportDesc::TableIdx(index) => ExternType::Table(self.tables().get(index as usize)?.ty),
+            ExportDesc::MemIdx(index) => {
+                ExternType::Memory(self.memories().get(index as usize)?.ty)
+            }
+            ExportDesc::GlobalIdx(index) => {
+                ExternType::Global(self.globals().get(index as usize)?.ty)
+            }
+            ExportDesc::TagIdx(index) => {
+                ExternType::Tag(self.tags().into_iter().find(|tag| tag.index == index)?.ty)
+            }
+        };
+        Some(Export {
+            name,
+            index: desc.index(),
+            ty,
+        })
+    }
+}
+
+impl WasmSections<'_> {
+    /// The imports `select` picks out of one index space, in the order they're declared
+    fn imported<'m, T: 'm>(
+        &'m self,
+        select: impl Fn(&'m ImportDesc) -> Option<T> + 'm,
+    ) -> impl Iterator<Item = (Origin<'m>, T)> + 'm {
+        self.imports.iter().filter_map(move |(module, name, desc)| {
+            let origin = Origin::Imported {
+                module: module.as_str(),
+                name: name.as_str(),
+            };
+            Some((origin, select(desc)?))
+        })
+    }
+
+    /// The type index of the function at `index` in the function index space
+    pub(crate) fn function_type_index(&self, index: u32) -> Option<u32> {
+        self.imported(|desc| match desc {
+            ImportDesc::TypeIdx(index) => Some(index.index()),
+            _ => None,
+        })
+        .map(|(_, type_index)| type_index)
+        .chain(self.functions.iter().map(Indecies::index))
+        .nth(index as usize)
+    }
+}
+
+fn defined<'m>(index: usize) -> Origin<'m> {
+    Origin::Defined {
+        index: index as u32,
+    }
+}
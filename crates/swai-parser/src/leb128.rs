@@ -1,4 +1,4 @@
-use bytereader::{ByteReader, ByteReaderError};
+use bytereader::{ByteReader, ByteReaderError, ByteWriter};
 use std::ops::{BitOrAssign, Shl};
 
 pub trait Leb128Readers {
@@ -8,33 +8,39 @@ pub trait Leb128Readers {
     fn read_leb128<T>(&mut self) -> Result<T, ByteReaderError>
     where
         T: Sized + Shl<usize, Output = T> + BitOrAssign + From<u8> + From<i32>;
+    fn read_s33(&mut self) -> Result<i64, ByteReaderError>;
 
+    #[allow(dead_code)]
     fn peak_uleb128<T>(&mut self) -> Result<T, ByteReaderError>
     where
         T: Sized + Shl<usize, Output = T> + BitOrAssign + From<u8>;
+    #[allow(dead_code)]
     fn peak_leb128<T>(&mut self) -> Result<T, ByteReaderError>
     where
         T: Sized + Shl<usize, Output = T> + BitOrAssign + From<u8> + From<i32>;
 }
 
-impl Leb128Readers for ByteReader {
+impl Leb128Readers for ByteReader<'_> {
     fn read_uleb128<T>(&mut self) -> Result<T, ByteReaderError>
     where
         T: Sized + Shl<usize, Output = T> + BitOrAssign + From<u8>,
     {
         let mut result: T = T::from(0);
         let mut shift = 0;
+        let size = core::mem::size_of::<T>() * 8;
 
         let mut i = 0;
         while let Ok(byte) = self.read::<u8>() {
+            // The last byte can't continue, and its bits above the width of T have to be zero
+            if i == leb128_size::<T>() - 1 && byte >> (size - shift) != 0 {
+                return Err(ByteReaderError::UnknownError(
+                    "Number is too large (Integer overflow)".to_string(),
+                ));
+            }
             result |= T::from(byte & 0x7F) << shift;
 
             if (byte & 0x80) == 0 {
                 return Ok(result);
-            } else if i >= leb128_size::<Self>() - 1 {
-                return Err(ByteReaderError::UnknownError(
-                    "Number is too large (Integer overflow)".to_string(),
-                ));
             }
 
             shift += 7;
@@ -52,24 +58,36 @@ impl Leb128Readers for ByteReader {
     {
         let mut result: T = T::from(0);
         let mut shift = 0;
-        let size = core::mem::size_of::<i64>() * 8;
+        let size = core::mem::size_of::<T>() * 8;
 
         let mut i = 0;
 
         while let Ok(byte) = self.read::<u8>() {
+            if i == leb128_size::<T>() - 1 {
+                // The last byte can't continue, and its bits above the width of T have to copy the sign bit
+                let value_bits = size - shift;
+                let unused = 0x7F & !((1u8 << value_bits) - 1);
+                let sign_extension = if byte & (1 << (value_bits - 1)) != 0 {
+                    unused
+                } else {
+                    0
+                };
+                if byte & 0x80 != 0 || byte & unused != sign_extension {
+                    return Err(ByteReaderError::UnknownError(
+                        "Number is too large (Integer overflow)".to_string(),
+                    ));
+                }
+            }
             result |= T::from(byte & 0x7F) << shift;
             shift += 7;
 
             if (byte & 0x80) == 0 {
+                // Sign extend the result if the sign bit of the last byte is set
                 if (shift < size) && (byte & 0x40) != 0 {
-                    result |= T::from(!0 << shift);
+                    result |= T::from(-1i32) << shift;
                 }
 
                 return Ok(result);
-            } else if i >= leb128_size::<u64>() - 1 {
-                return Err(ByteReaderError::UnknownError(
-                    "Number is too large (Integer overflow)".to_string(),
-                ));
             }
 
             i += 1;
@@ -80,6 +98,35 @@ impl Leb128Readers for ByteReader {
         ))
     }
 
+    /// Reads a signed 33 bit integer, which block types use so they can hold any u32 type index.
+    /// At most 5 bytes are read, and the unused bits of the last one have to extend the sign
+    fn read_s33(&mut self) -> Result<i64, ByteReaderError> {
+        let mut result = 0i64;
+
+        for i in 0..5 {
+            let byte = self.read::<u8>()?;
+            result |= i64::from(byte & 0x7F) << (i * 7);
+
+            if i == 4 {
+                // Only the low 5 bits of the fifth byte hold value bits, the 2 above them copy the sign
+                let sign_extension = if byte & 0x10 != 0 { 0x60 } else { 0 };
+                if byte & 0x80 != 0 || byte & 0x60 != sign_extension {
+                    return Err(ByteReaderError::UnknownError(
+                        "Number is too large (Integer overflow)".to_string(),
+                    ));
+                }
+                return Ok((result << 31) >> 31);
+            }
+
+            if byte & 0x80 == 0 {
+                let shift = 64 - (i + 1) * 7;
+                return Ok((result << shift) >> shift);
+            }
+        }
+
+        unreachable!("the loop returns at the fifth byte")
+    }
+
     fn peak_uleb128<T>(&mut self) -> Result<T, ByteReaderError>
     where
         T: Sized + Shl<usize, Output = T> + BitOrAssign + From<u8>,
@@ -101,8 +148,42 @@ impl Leb128Readers for ByteReader {
     }
 }
 
+pub trait Leb128Writers {
+    fn write_uleb128<T: Into<u64>>(&mut self, value: T) -> &mut Self;
+    fn write_leb128<T: Into<i64>>(&mut self, value: T) -> &mut Self;
+}
+
+impl Leb128Writers for ByteWriter {
+    /// Writes the shortest encoding of `value`
+    fn write_uleb128<T: Into<u64>>(&mut self, value: T) -> &mut Self {
+        let mut value = value.into();
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                return self.write(&byte);
+            }
+            self.write(&(byte | 0x80));
+        }
+    }
+
+    /// Writes the shortest encoding of `value`, the sign is taken from bit 6 of the last byte
+    fn write_leb128<T: Into<i64>>(&mut self, value: T) -> &mut Self {
+        let mut value = value.into();
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+            if done {
+                return self.write(&byte);
+            }
+            self.write(&(byte | 0x80));
+        }
+    }
+}
+
 // Thanks to the nom-leb128 crate for the size determin function: https://github.com/milkey-mouse/nom-leb128/blob/58f37d293eeb4d43f44a38650802b1defda607c3/src/lib.rs#L17-L20
 fn leb128_size<T>() -> usize {
     let bits = std::mem::size_of::<T>() * 8;
-    (bits + 6) / 7
+    bits.div_ceil(7)
 }
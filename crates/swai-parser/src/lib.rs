@@ -1,8 +1,20 @@
+pub mod builder;
+pub mod component;
+pub mod custom;
 pub mod error;
 pub mod instructions;
+pub mod introspection;
 mod leb128;
+pub mod linking;
+pub mod names;
+pub mod parser;
 pub mod sections;
 pub mod types;
+pub mod validation;
 pub mod wasm;
+pub mod wat;
 
+pub use builder::ModuleBuilder;
+pub use component::WasmComponent;
+pub use validation::validate;
 pub use wasm::WasmModule;
@@ -0,0 +1,455 @@
+use bytereader::{ByteReader, FromByteReader};
+
+use crate::{
+    error::{ResultExt, WasmParserError, WasmParserErrorKind},
+    leb128::Leb128Readers,
+    types::{read_byte_as, Name},
+};
+
+// Symbol flags, see: https://github.com/WebAssembly/tool-conventions/blob/main/Linking.md#symbol-table-subsection
+pub const WASM_SYM_BINDING_WEAK: u32 = 0x01;
+pub const WASM_SYM_BINDING_LOCAL: u32 = 0x02;
+pub const WASM_SYM_VISIBILITY_HIDDEN: u32 = 0x04;
+pub const WASM_SYM_UNDEFINED: u32 = 0x10;
+pub const WASM_SYM_EXPORTED: u32 = 0x20;
+pub const WASM_SYM_EXPLICIT_NAME: u32 = 0x40;
+pub const WASM_SYM_NO_STRIP: u32 = 0x80;
+pub const WASM_SYM_TLS: u32 = 0x100;
+pub const WASM_SYM_ABSOLUTE: u32 = 0x200;
+
+/// The `linking` custom section of a relocatable object file, as described by:
+/// https://github.com/WebAssembly/tool-conventions/blob/main/Linking.md#linking-metadata-section
+///
+/// Symbol, segment and comdat names are owned, like the names in [`crate::names::NameSection`]
+#[derive(Debug, PartialEq)]
+pub struct LinkingSection {
+    pub version: u32,
+    pub symbols: Vec<SymbolInfo>,
+    pub segments: Vec<SegmentInfo>,
+    pub init_funcs: Vec<InitFunc>,
+    pub comdats: Vec<Comdat>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SymbolInfo {
+    pub flags: u32,
+    pub kind: SymbolKind,
+}
+
+impl SymbolInfo {
+    pub fn is_undefined(&self) -> bool {
+        self.flags & WASM_SYM_UNDEFINED != 0
+    }
+
+    /// The symbol name, `None` for undefined symbols that reuse the name of their import
+    pub fn name(&self) -> Option<&Name<'static>> {
+        match &self.kind {
+            SymbolKind::Function { name, .. }
+            | SymbolKind::Global { name, .. }
+            | SymbolKind::Tag { name, .. }
+            | SymbolKind::Table { name, .. } => name.as_ref(),
+            SymbolKind::Data { name, .. } => Some(name),
+            SymbolKind::Section { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SymbolKind {
+    Function {
+        index: u32,
+        name: Option<Name<'static>>,
+    },
+    Data {
+        name: Name<'static>,
+        definition: Option<DataSymbolDefinition>,
+    },
+    Global {
+        index: u32,
+        name: Option<Name<'static>>,
+    },
+    Section {
+        section: u32,
+    },
+    Tag {
+        index: u32,
+        name: Option<Name<'static>>,
+    },
+    Table {
+        index: u32,
+        name: Option<Name<'static>>,
+    },
+}
+
+/// The location of a defined data symbol
+#[derive(Debug, PartialEq)]
+pub struct DataSymbolDefinition {
+    pub segment_index: u32,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SegmentInfo {
+    pub name: Name<'static>,
+    /// The alignment of the segment as a power of two
+    pub alignment: u32,
+    pub flags: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InitFunc {
+    pub priority: u32,
+    pub symbol_index: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Comdat {
+    pub name: Name<'static>,
+    pub flags: u32,
+    pub symbols: Vec<ComdatSymbol>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ComdatSymbol {
+    pub kind: ComdatSymbolKind,
+    pub index: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ComdatSymbolKind {
+    Data,     // 0
+    Function, // 1
+    Global,   // 2
+    Tag,      // 3
+    Table,    // 4
+    Section,  // 5
+}
+
+impl<'a> FromByteReader<'a> for LinkingSection {
+    type Error = WasmParserError;
+
+    /// Reads subsections until the end of the reader, which should be bounded to the section contents
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let offset = reader.get_current_offset();
+        let mut linking = LinkingSection {
+            version: reader.read_uleb128::<u32>()?,
+            symbols: vec![],
+            segments: vec![],
+            init_funcs: vec![],
+            comdats: vec![],
+        };
+
+        if linking.version != 2 {
+            return Err(WasmParserError::at(
+                offset,
+                WasmParserErrorKind::UnexpectedValue {
+                    construct: "linking metadata version",
+                    value: linking.version,
+                },
+            ));
+        }
+
+        while reader.remaining() > 0 {
+            let subsection_id = reader.read::<u8>()?;
+            let subsection_size = reader.read_uleb128::<u32>()? as usize;
+            let subsection_end = reader.get_current_offset() + subsection_size;
+            reader.push_limit(subsection_size)?;
+
+            match subsection_id {
+                5 => {
+                    linking.segments = (0..reader.read_uleb128::<u32>()?)
+                        .map(|_| {
+                            Ok(SegmentInfo {
+                                name: reader.read::<Name>()?.into_owned(),
+                                alignment: reader.read_uleb128::<u32>()?,
+                                flags: reader.read_uleb128::<u32>()?,
+                            })
+                        })
+                        .collect::<Result<_, WasmParserError>>()
+                        .context(reader, "segment info")?
+                }
+                6 => {
+                    linking.init_funcs = (0..reader.read_uleb128::<u32>()?)
+                        .map(|_| {
+                            Ok(InitFunc {
+                                priority: reader.read_uleb128::<u32>()?,
+                                symbol_index: reader.read_uleb128::<u32>()?,
+                            })
+                        })
+                        .collect::<Result<_, WasmParserError>>()
+                        .context(reader, "init functions")?
+                }
+                7 => {
+                    linking.comdats = (0..reader.read_uleb128::<u32>()?)
+                        .map(|_| reader.read())
+                        .collect::<Result<_, _>>()
+                        .context(reader, "comdat info")?
+                }
+                8 => {
+                    linking.symbols = (0..reader.read_uleb128::<u32>()?)
+                        .map(|_| reader.read())
+                        .collect::<Result<_, _>>()
+                        .context(reader, "symbol table")?
+                }
+                // Unknown subsections are skipped
+                _ => {}
+            }
+            reader.pop_limit().move_to(subsection_end);
+        }
+
+        Ok(linking)
+    }
+}
+
+impl<'a> FromByteReader<'a> for SymbolInfo {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let offset = reader.get_current_offset();
+        let kind = reader.read::<u8>()?;
+        let flags = reader.read_uleb128::<u32>()?;
+        let undefined = flags & WASM_SYM_UNDEFINED != 0;
+        let has_name = !undefined || flags & WASM_SYM_EXPLICIT_NAME != 0;
+
+        let read_indexed =
+            |reader: &mut ByteReader| -> Result<(u32, Option<Name<'static>>), WasmParserError> {
+                let index = reader.read_uleb128::<u32>()?;
+                let name = if has_name {
+                    Some(reader.read::<Name>()?.into_owned())
+                } else {
+                    None
+                };
+                Ok((index, name))
+            };
+
+        let kind = match kind {
+            0 => {
+                let (index, name) = read_indexed(reader)?;
+                SymbolKind::Function { index, name }
+            }
+            1 => SymbolKind::Data {
+                name: reader.read::<Name>()?.into_owned(),
+                definition: if undefined {
+                    None
+                } else {
+                    Some(DataSymbolDefinition {
+                        segment_index: reader.read_uleb128::<u32>()?,
+                        offset: reader.read_uleb128::<u32>()?,
+                        size: reader.read_uleb128::<u32>()?,
+                    })
+                },
+            },
+            2 => {
+                let (index, name) = read_indexed(reader)?;
+                SymbolKind::Global { index, name }
+            }
+            3 => SymbolKind::Section {
+                section: reader.read_uleb128::<u32>()?,
+            },
+            4 => {
+                let (index, name) = read_indexed(reader)?;
+                SymbolKind::Tag { index, name }
+            }
+            5 => {
+                let (index, name) = read_indexed(reader)?;
+                SymbolKind::Table { index, name }
+            }
+            byte => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedByte {
+                        construct: "symbol kind",
+                        byte,
+                    },
+                ))
+            }
+        };
+
+        Ok(SymbolInfo { flags, kind })
+    }
+}
+
+impl<'a> FromByteReader<'a> for Comdat {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        Ok(Comdat {
+            name: reader.read::<Name>()?.into_owned(),
+            flags: reader.read_uleb128::<u32>()?,
+            symbols: (0..reader.read_uleb128::<u32>()?)
+                .map(|_| {
+                    Ok(ComdatSymbol {
+                        kind: read_byte_as(reader, "comdat symbol kind", |byte| match byte {
+                            0 => Some(ComdatSymbolKind::Data),
+                            1 => Some(ComdatSymbolKind::Function),
+                            2 => Some(ComdatSymbolKind::Global),
+                            3 => Some(ComdatSymbolKind::Tag),
+                            4 => Some(ComdatSymbolKind::Table),
+                            5 => Some(ComdatSymbolKind::Section),
+                            _ => None,
+                        })?,
+                        index: reader.read_uleb128::<u32>()?,
+                    })
+                })
+                .collect::<Result<_, WasmParserError>>()?,
+        })
+    }
+}
+
+/// A `reloc.*` custom section, holding the relocations that apply to a single section:
+/// https://github.com/WebAssembly/tool-conventions/blob/main/Linking.md#relocation-sections
+#[derive(Debug, PartialEq)]
+pub struct RelocSection {
+    /// The full name of the custom section, such as `reloc.CODE`
+    pub name: Name<'static>,
+    /// The index of the section the relocations apply to
+    pub section_index: u32,
+    pub entries: Vec<RelocEntry>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RelocEntry {
+    pub ty: RelocType,
+    /// The offset of the value to rewrite, relative to the start of the section contents
+    pub offset: u32,
+    /// The symbol index, or the type index for `TypeIndexLeb` relocations
+    pub index: u32,
+    pub addend: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelocType {
+    FunctionIndexLeb,    // 0
+    TableIndexSleb,      // 1
+    TableIndexI32,       // 2
+    MemoryAddrLeb,       // 3
+    MemoryAddrSleb,      // 4
+    MemoryAddrI32,       // 5
+    TypeIndexLeb,        // 6
+    GlobalIndexLeb,      // 7
+    FunctionOffsetI32,   // 8
+    SectionOffsetI32,    // 9
+    TagIndexLeb,         // 10
+    MemoryAddrRelSleb,   // 11
+    TableIndexRelSleb,   // 12
+    GlobalIndexI32,      // 13
+    MemoryAddrLeb64,     // 14
+    MemoryAddrSleb64,    // 15
+    MemoryAddrI64,       // 16
+    MemoryAddrRelSleb64, // 17
+    TableIndexSleb64,    // 18
+    TableIndexI64,       // 19
+    TableNumberLeb,      // 20
+    MemoryAddrTlsSleb,   // 21
+    FunctionOffsetI64,   // 22
+    MemoryAddrLocrelI32, // 23
+    TableIndexRelSleb64, // 24
+    MemoryAddrTlsSleb64, // 25
+    FunctionIndexI32,    // 26
+}
+
+impl RelocType {
+    /// Whether relocation entries of this type carry an addend
+    pub fn has_addend(&self) -> bool {
+        matches!(
+            self,
+            RelocType::MemoryAddrLeb
+                | RelocType::MemoryAddrSleb
+                | RelocType::MemoryAddrI32
+                | RelocType::FunctionOffsetI32
+                | RelocType::SectionOffsetI32
+                | RelocType::MemoryAddrRelSleb
+                | RelocType::MemoryAddrLeb64
+                | RelocType::MemoryAddrSleb64
+                | RelocType::MemoryAddrI64
+                | RelocType::MemoryAddrRelSleb64
+                | RelocType::MemoryAddrTlsSleb
+                | RelocType::FunctionOffsetI64
+                | RelocType::MemoryAddrLocrelI32
+                | RelocType::MemoryAddrTlsSleb64
+        )
+    }
+}
+
+impl RelocType {
+    pub fn from_byte(value: u8) -> Option<Self> {
+        use RelocType::*;
+
+        Some(match value {
+            0 => FunctionIndexLeb,
+            1 => TableIndexSleb,
+            2 => TableIndexI32,
+            3 => MemoryAddrLeb,
+            4 => MemoryAddrSleb,
+            5 => MemoryAddrI32,
+            6 => TypeIndexLeb,
+            7 => GlobalIndexLeb,
+            8 => FunctionOffsetI32,
+            9 => SectionOffsetI32,
+            10 => TagIndexLeb,
+            11 => MemoryAddrRelSleb,
+            12 => TableIndexRelSleb,
+            13 => GlobalIndexI32,
+            14 => MemoryAddrLeb64,
+            15 => MemoryAddrSleb64,
+            16 => MemoryAddrI64,
+            17 => MemoryAddrRelSleb64,
+            18 => TableIndexSleb64,
+            19 => TableIndexI64,
+            20 => TableNumberLeb,
+            21 => MemoryAddrTlsSleb,
+            22 => FunctionOffsetI64,
+            23 => MemoryAddrLocrelI32,
+            24 => TableIndexRelSleb64,
+            25 => MemoryAddrTlsSleb64,
+            26 => FunctionIndexI32,
+            _ => return None,
+        })
+    }
+}
+
+impl<'a> FromByteReader<'a> for RelocEntry {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let ty = read_byte_as(reader, "relocation type", RelocType::from_byte)?;
+        Ok(RelocEntry {
+            ty,
+            offset: reader.read_uleb128::<u32>()?,
+            index: reader.read_uleb128::<u32>()?,
+            addend: if ty.has_addend() {
+                Some(reader.read_leb128::<i64>()?)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+impl RelocSection {
+    pub fn from_reader(
+        name: Name<'static>,
+        reader: &mut ByteReader,
+    ) -> Result<Self, WasmParserError> {
+        Ok(RelocSection {
+            name,
+            section_index: reader.read_uleb128::<u32>()?,
+            entries: (0..reader.read_uleb128::<u32>()?)
+                .map(|_| reader.read())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use bytereader::{ByteReader, ByteWriter, FromByteReader, ToByteWriter};
+
+use crate::{
+    error::{ResultExt, WasmParserError},
+    leb128::{Leb128Readers, Leb128Writers},
+    types::Name,
+};
+
+/// Maps an index in one of the module's index spaces to a name
+pub type NameMap = Vec<(u32, Name<'static>)>;
+/// Maps a function index to the names of its locals or labels
+pub type IndirectNameMap = Vec<(u32, NameMap)>;
+
+/// The contents of the `name` custom section, as described by the extended name section proposal:
+/// https://github.com/WebAssembly/extended-name-section/blob/main/proposals/extended-name-section/Overview.md
+///
+/// Names are copied out of the module, so the section can also be produced by a registered decoder
+#[derive(Debug, Default, PartialEq)]
+pub struct NameSection {
+    pub module: Option<Name<'static>>,
+    pub functions: NameMap,
+    pub locals: IndirectNameMap,
+    pub labels: IndirectNameMap,
+    pub types: NameMap,
+    pub tables: NameMap,
+    pub memories: NameMap,
+    pub globals: NameMap,
+    pub elements: NameMap,
+    pub data: NameMap,
+    pub tags: NameMap,
+}
+
+impl NameSection {
+    pub fn function_name(&self, func_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.functions, func_index)
+    }
+
+    pub fn local_name(&self, func_index: u32, local_index: u32) -> Option<&Name<'static>> {
+        lookup_indirect(&self.locals, func_index, local_index)
+    }
+
+    pub fn label_name(&self, func_index: u32, label_index: u32) -> Option<&Name<'static>> {
+        lookup_indirect(&self.labels, func_index, label_index)
+    }
+
+    pub fn type_name(&self, type_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.types, type_index)
+    }
+
+    pub fn table_name(&self, table_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.tables, table_index)
+    }
+
+    pub fn memory_name(&self, mem_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.memories, mem_index)
+    }
+
+    pub fn global_name(&self, global_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.globals, global_index)
+    }
+
+    pub fn element_name(&self, elem_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.elements, elem_index)
+    }
+
+    pub fn data_name(&self, data_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.data, data_index)
+    }
+
+    pub fn tag_name(&self, tag_index: u32) -> Option<&Name<'static>> {
+        lookup(&self.tags, tag_index)
+    }
+}
+
+fn lookup(map: &NameMap, index: u32) -> Option<&Name<'static>> {
+    map.iter().find(|(i, _)| *i == index).map(|(_, name)| name)
+}
+
+fn lookup_indirect(map: &IndirectNameMap, outer: u32, inner: u32) -> Option<&Name<'static>> {
+    map.iter()
+        .find(|(i, _)| *i == outer)
+        .and_then(|(_, names)| lookup(names, inner))
+}
+
+fn read_name_map(reader: &mut ByteReader) -> Result<NameMap, WasmParserError> {
+    (0..reader.read_uleb128::<u32>()?)
+        .map(|_| {
+            Ok((
+                reader.read_uleb128::<u32>()?,
+                reader.read::<Name>()?.into_owned(),
+            ))
+        })
+        .collect()
+}
+
+fn read_indirect_name_map(reader: &mut ByteReader) -> Result<IndirectNameMap, WasmParserError> {
+    (0..reader.read_uleb128::<u32>()?)
+        .map(|_| Ok((reader.read_uleb128::<u32>()?, read_name_map(reader)?)))
+        .collect()
+}
+
+impl<'a> FromByteReader<'a> for NameSection {
+    type Error = WasmParserError;
+
+    /// Reads subsections until the end of the reader, which should be bounded to the section contents
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let mut names = NameSection::default();
+
+        while reader.remaining() > 0 {
+            let subsection_id = reader.read::<u8>()?;
+            let subsection_size = reader.read_uleb128::<u32>()? as usize;
+            let subsection_end = reader.get_current_offset() + subsection_size;
+            reader.push_limit(subsection_size)?;
+
+            match subsection_id {
+                0 => {
+                    names.module = Some(
+                        reader
+                            .read::<Name>()
+                            .map(Name::into_owned)
+                            .context(reader, "module name")?,
+                    )
+                }
+                1 => names.functions = read_name_map(reader).context(reader, "function names")?,
+                2 => {
+                    names.locals = read_indirect_name_map(reader).context(reader, "local names")?
+                }
+                3 => {
+                    names.labels = read_indirect_name_map(reader).context(reader, "label names")?
+                }
+                4 => names.types = read_name_map(reader).context(reader, "type names")?,
+                5 => names.tables = read_name_map(reader).context(reader, "table names")?,
+                6 => names.memories = read_name_map(reader).context(reader, "memory names")?,
+                7 => names.globals = read_name_map(reader).context(reader, "global names")?,
+                8 => names.elements = read_name_map(reader).context(reader, "element names")?,
+                9 => names.data = read_name_map(reader).context(reader, "data names")?,
+                11 => names.tags = read_name_map(reader).context(reader, "tag names")?,
+                // Subsections from later proposals are skipped
+                _ => {}
+            }
+            reader.pop_limit().move_to(subsection_end);
+        }
+
+        Ok(names)
+    }
+}
+
+/// Writes a subsection with the given id, whose contents are written by `write`
+fn write_subsection(writer: &mut ByteWriter, id: u8, write: impl FnOnce(&mut ByteWriter)) {
+    let mut contents = ByteWriter::new();
+    write(&mut contents);
+    writer
+        .write(&id)
+        .write_uleb128(contents.len() as u64)
+        .write_bytes(contents.as_slice());
+}
+
+fn write_name_map(writer: &mut ByteWriter, map: &NameMap) {
+    writer.write_uleb128(map.len() as u64);
+    for (index, name) in map {
+        writer.write_uleb128(*index).write(name);
+    }
+}
+
+fn write_indirect_name_map(writer: &mut ByteWriter, map: &IndirectNameMap) {
+    writer.write_uleb128(map.len() as u64);
+    for (index, names) in map {
+        writer.write_uleb128(*index);
+        write_name_map(writer, names);
+    }
+}
+
+impl ToByteWriter for NameSection {
+    /// Writes the subsections in order of their ids, leaving out empty ones
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        if let Some(module) = &self.module {
+            write_subsection(writer, 0, |contents| {
+                contents.write(module);
+            });
+        }
+        if !self.functions.is_empty() {
+            write_subsection(writer, 1, |contents| {
+                write_name_map(contents, &self.functions)
+            });
+        }
+        for (id, map) in [(2, &self.locals), (3, &self.labels)] {
+            if !map.is_empty() {
+                write_subsection(writer, id, |contents| {
+                    write_indirect_name_map(contents, map)
+                });
+            }
+        }
+        let maps = [
+            (4, &self.types),
+            (5, &self.tables),
+            (6, &self.memories),
+            (7, &self.globals),
+            (8, &self.elements),
+            (9, &self.data),
+            (11, &self.tags),
+        ];
+        for (id, map) in maps {
+            if !map.is_empty() {
+                write_subsection(writer, id, |contents| write_name_map(contents, map));
+            }
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use bytereader::ByteReader;
+use std::ops::Range;
+
+use crate::{
+    custom::CustomSection,
+    error::{ResultExt, WasmParserError, WasmParserErrorKind},
+    leb128::Leb128Readers,
+    sections::{
+        expect_consumed, read_custom_section, read_function_body, read_section, SectionOrder,
+    },
+    types::{
+        Code, DataSegment, ElementSegment, ExportDesc, Expr, FunctionType, GlobalType, ImportDesc,
+        Indecies, Locals, MemType, Name, TableType, TagType,
+    },
+    wasm::read_header,
+};
+
+/// The result of passing bytes to [`Parser::parse`]
+#[derive(Debug)]
+pub enum Chunk<'a> {
+    /// At least this many more bytes are needed before the next payload can be parsed
+    NeedMoreData(u64),
+    /// `payload` was parsed from the first `consumed` bytes, which shouldn't be passed to the parser again
+    Parsed {
+        consumed: usize,
+        payload: Payload<'a>,
+    },
+}
+
+/// A part of a module, in the order it appears in the binary
+#[derive(Debug)]
+pub enum Payload<'a> {
+    /// The magic number and version, always the first payload
+    Header,
+    TypeSection(Vec<FunctionType>),
+    ImportSection(Vec<(Name<'a>, Name<'a>, ImportDesc)>),
+    FunctionSection(Vec<Indecies>),
+    TableSection(Vec<TableType>),
+    MemorySection(Vec<MemType>),
+    TagSection(Vec<TagType>),
+    GlobalSection(Vec<(GlobalType, Expr)>),
+    ExportSection(Vec<(Name<'a>, ExportDesc)>),
+    StartSection(Indecies),
+    ElementSection(Vec<ElementSegment>),
+    DataCountSection(u32),
+    DataSection(Vec<DataSegment<'a>>),
+    /// A custom section with its raw contents, `decoded` is always `None`
+    CustomSection(CustomSection<'a>),
+    /// The start of the code section, followed by `count` [`Payload::FunctionBody`] payloads.
+    /// `range` is the byte range of the section contents in the module
+    CodeSectionStart {
+        count: u32,
+        range: Range<usize>,
+    },
+    /// A code section entry, `index` is the function's index in the function index space
+    FunctionBody {
+        index: u32,
+        locals: Locals,
+        body: Expr,
+    },
+    /// The end of the module, no more payloads follow
+    End,
+}
+
+/// A pull parser over a module whose bytes arrive incrementally, e.g. from a socket.
+/// Sections are parsed as soon as all their bytes are available, function bodies one at a time.
+///
+/// ```ignore
+/// let mut parser = Parser::new();
+/// let mut buffer = vec![];
+/// let mut eof = false;
+/// loop {
+///     match parser.parse(&buffer, eof)? {
+///         Chunk::NeedMoreData(_) => eof = read_more(&mut buffer)?,
+///         Chunk::Parsed { consumed, payload } => {
+///             buffer.drain(..consumed);
+///             match payload {
+///                 Payload::FunctionBody { index, body, .. } => compile(index, body),
+///                 Payload::End => break,
+///                 _ => {}
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Parser {
+    state: State,
+    /// The absolute offset of the first byte passed to the next `parse` call
+    offset: usize,
+    order: SectionOrder,
+    /// Function bodies are numbered after the imported functions
+    imported_functions: u32,
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Header,
+    Section,
+    FunctionBodies {
+        remaining: u32,
+        index: u32,
+        /// The absolute byte range of the code section contents
+        range: Range<usize>,
+    },
+    End,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The absolute offset in the module of the first byte of `data` in the next `parse` call
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Parses the next payload from `data`, which has to start at the first byte that hasn't been consumed yet.
+    /// `eof` tells the parser no bytes follow `data`, running out of bytes is an error instead of [`Chunk::NeedMoreData`]
+    pub fn parse<'a>(&mut self, data: &'a [u8], eof: bool) -> Result<Chunk<'a>, WasmParserError> {
+        let chunk = self.parse_payload(data, eof)?;
+        if let Chunk::Parsed { consumed, .. } = &chunk {
+            self.offset += consumed;
+        }
+        Ok(chunk)
+    }
+
+    fn parse_payload<'a>(
+        &mut self,
+        data: &'a [u8],
+        eof: bool,
+    ) -> Result<Chunk<'a>, WasmParserError> {
+        match &self.state {
+            State::Header => {
+                if let Some(needed) = self.missing(data, 8, eof)? {
+                    return Ok(Chunk::NeedMoreData(needed));
+                }
+                self.read(&data[..8], read_header)?;
+                self.state = State::Section;
+                Ok(parsed(8, Payload::Header))
+            }
+            State::Section => self.parse_section(data, eof),
+            State::FunctionBodies {
+                remaining: 0,
+                range,
+                ..
+            } => {
+                if self.offset != range.end {
+                    return Err(WasmParserError::at(
+                        self.offset,
+                        WasmParserErrorKind::SizeMismatch {
+                            construct: "code section",
+                            declared: range.len(),
+                            consumed: self.offset - range.start,
+                        },
+                    ));
+                }
+                self.state = State::Section;
+                self.parse_section(data, eof)
+            }
+            State::FunctionBodies { index, range, .. } => {
+                let chunk = self.parse_function_body(data, eof, *index, range.clone())?;
+                if let (
+                    Chunk::Parsed { .. },
+                    State::FunctionBodies {
+                        remaining, index, ..
+                    },
+                ) = (&chunk, &mut self.state)
+                {
+                    *remaining -= 1;
+                    *index += 1;
+                }
+                Ok(chunk)
+            }
+            State::End => Ok(parsed(0, Payload::End)),
+        }
+    }
+
+    /// Parses the code section entry of function `index`
+    fn parse_function_body<'a>(
+        &self,
+        data: &'a [u8],
+        eof: bool,
+        index: u32,
+        range: Range<usize>,
+    ) -> Result<Chunk<'a>, WasmParserError> {
+        let Some(size_length) = self.leb128_length(data, eof)? else {
+            return Ok(Chunk::NeedMoreData(1));
+        };
+        let size = self.read(&data[..size_length], |reader| {
+            Ok(reader.read_uleb128::<u32>()?)
+        })? as usize;
+
+        let total = size_length + size;
+        if self.offset + total > range.end {
+            return Err(WasmParserError::at(
+                self.offset,
+                WasmParserErrorKind::SizeMismatch {
+                    construct: "code section",
+                    declared: range.len(),
+                    consumed: self.offset + total - range.start,
+                },
+            ));
+        }
+        if let Some(needed) = self.missing(data, total, eof)? {
+            return Ok(Chunk::NeedMoreData(needed));
+        }
+
+        let Code { locals, body, .. } = self.read(&data[..total], |reader| {
+            read_function_body(reader, index).context(reader, "code section")
+        })?;
+        Ok(parsed(
+            total,
+            Payload::FunctionBody {
+                index,
+                locals,
+                body,
+            },
+        ))
+    }
+
+    fn parse_section<'a>(
+        &mut self,
+        data: &'a [u8],
+        eof: bool,
+    ) -> Result<Chunk<'a>, WasmParserError> {
+        if data.is_empty() {
+            if !eof {
+                return Ok(Chunk::NeedMoreData(1));
+            }
+            self.state = State::End;
+            return Ok(parsed(0, Payload::End));
+        }
+
+        // The section id followed by the size
+        let Some(size_length) = self.leb128_length(&data[1..], eof)? else {
+            return Ok(Chunk::NeedMoreData(1));
+        };
+        let header = 1 + size_length;
+        let (id, size) = self.read(&data[..header], |reader| {
+            let id = reader.read::<u8>()?;
+            let size = reader
+                .read_uleb128::<u32>()
+                .context(reader, "section size")?;
+            Ok((id, size))
+        })?;
+        let size = size as usize;
+
+        // The order is only recorded once the section is parsed, it's checked again if more data is needed
+        let mut order = self.order;
+        let section = Self::read_at(self.offset, &data[..header], |reader| {
+            order.check(id).map_err(|kind| {
+                WasmParserError::at(0, kind).in_context(reader, "section id".to_string())
+            })
+        })?;
+
+        if id == 10 {
+            // The code section is split up into its function bodies
+            let Some(count_length) = self.leb128_length(&data[header..], eof)? else {
+                return Ok(Chunk::NeedMoreData(1));
+            };
+            let count = Self::read_at(
+                self.offset + header,
+                &data[header..header + count_length],
+                |reader| Ok(reader.read_uleb128::<u32>()?),
+            )?;
+
+            let start = self.offset + header;
+            let range = start..start + size;
+            self.order = order;
+            self.state = State::FunctionBodies {
+                remaining: count,
+                index: self.imported_functions,
+                range: range.clone(),
+            };
+            return Ok(parsed(
+                header + count_length,
+                Payload::CodeSectionStart { count, range },
+            ));
+        }
+
+        let total = header + size;
+        if let Some(needed) = self.missing(data, total, eof)? {
+            return Ok(Chunk::NeedMoreData(needed));
+        }
+
+        let start = self.offset + header;
+        let payload = Self::read_at(start, &data[header..total], |reader| {
+            if id == 0 {
+                let mut custom = read_custom_section(reader, size as u32, order.last())?;
+                custom.range = start + custom.range.start..start + custom.range.end;
+                return Ok(Payload::CustomSection(custom));
+            }
+            let payload = read_section(reader, id).context(reader, section)?;
+            expect_consumed(reader, section, 0, size as u32).context(reader, section)?;
+            Ok(payload)
+        })?;
+
+        self.order = order;
+        if let Payload::ImportSection(imports) = &payload {
+            self.imported_functions = imports
+                .iter()
+                .filter(|(_, _, desc)| matches!(desc, ImportDesc::TypeIdx(_)))
+                .count() as u32;
+        }
+        Ok(parsed(total, payload))
+    }
+
+    /// Returns how many more bytes are needed if `data` is shorter than `needed`, failing if no more bytes will arrive
+    fn missing(
+        &self,
+        data: &[u8],
+        needed: usize,
+        eof: bool,
+    ) -> Result<Option<u64>, WasmParserError> {
+        if data.len() >= needed {
+            return Ok(None);
+        }
+        if eof {
+            return Err(WasmParserError::at(
+                self.offset + data.len(),
+                WasmParserErrorKind::UnexpectedEof {
+                    needed: needed - data.len(),
+                },
+            ));
+        }
+        Ok(Some((needed - data.len()) as u64))
+    }
+
+    /// Returns the length of the LEB128 number at the start of `data`, or `None` if more bytes are needed to tell.
+    /// Malformed numbers return a length covering the available bytes, so reading them reports the error
+    fn leb128_length(&self, data: &[u8], eof: bool) -> Result<Option<usize>, WasmParserError> {
+        const MAX_LENGTH: usize = 5;
+        match data
+            .iter()
+            .take(MAX_LENGTH)
+            .position(|byte| byte & 0x80 == 0)
+        {
+            Some(end) => Ok(Some(end + 1)),
+            None if data.len() < MAX_LENGTH => {
+                self.missing(data, data.len() + 1, eof).map(|_| None)
+            }
+            None => Ok(Some(MAX_LENGTH)),
+        }
+    }
+
+    fn read<'a, T>(
+        &self,
+        bytes: &'a [u8],
+        read: impl FnOnce(&mut ByteReader<'a>) -> Result<T, WasmParserError>,
+    ) -> Result<T, WasmParserError> {
+        Self::read_at(self.offset, bytes, read)
+    }
+
+    /// Reads from `bytes`, which start at the absolute offset `offset` in the module, moving errors to absolute offsets
+    fn read_at<'a, T>(
+        offset: usize,
+        bytes: &'a [u8],
+        read: impl FnOnce(&mut ByteReader<'a>) -> Result<T, WasmParserError>,
+    ) -> Result<T, WasmParserError> {
+        read(&mut ByteReader::from_slice(bytes)).map_err(|err| err.offset_by(offset))
+    }
+}
+
+fn parsed(consumed: usize, payload: Payload<'_>) -> Chunk<'_> {
+    Chunk::Parsed { consumed, payload }
+}
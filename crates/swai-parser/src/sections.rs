@@ -1,117 +1,465 @@
-use bytereader::ByteReader;
+use bytereader::{ByteReader, ByteWriter};
+use std::borrow::Cow;
 
 use super::types::FunctionType;
 use crate::{
-    error::WasmParserError,
-    leb128::Leb128Readers,
-    types::{read_vec, Indecies, Name, ValueType, ImportDesc, TableType, MemType, DataSegment, GlobalType, Expr}, instructions::{Instructions, read_expr},
+    custom::{CustomSection, CustomSectionRegistry},
+    error::{ResultExt, WasmParserError, WasmParserErrorKind},
+    leb128::{Leb128Readers, Leb128Writers},
+    linking::{LinkingSection, RelocSection},
+    names::NameSection,
+    parser::Payload,
+    types::{Indecies, Name, ImportDesc, ExportDesc, TableType, MemType, TagType, DataSegment, GlobalType, Expr, Code, Locals, ElementSegment}, instructions::{read_expr, write_expr},
 };
 
-#[derive(Debug)]
-pub struct WasmSections {
-    pub custom: Vec<()>,
+/// Section names by id, used for errors. Id 0 is a custom section, which is named by its contents
+const SECTION_NAMES: [&str; 14] = [
+    "custom section", "type section", "import section", "function section", "table section",
+    "memory section", "global section", "export section", "start section", "element section",
+    "code section", "data section", "data count section", "tag section",
+];
+
+/// The order non-custom sections have to appear in, see: https://webassembly.github.io/spec/core/binary/modules.html#binary-module.
+/// The data count section comes before the code section even though its id is higher, and so does
+/// the tag section of the exception handling proposal before the global section
+pub(crate) const SECTION_ORDER: [u8; 13] = [1, 2, 3, 4, 5, 13, 6, 7, 8, 9, 12, 10, 11];
+
+/// The sections of a module, names, data and function bodies borrow from the module bytes when parsed from them
+#[derive(Debug, Default, PartialEq)]
+pub struct WasmSections<'a> {
+    pub custom: Vec<CustomSection<'a>>,
+    pub names: Option<NameSection>,
+    pub linking: Option<LinkingSection>,
+    pub relocations: Vec<RelocSection>,
     pub types: Vec<FunctionType>,
-    pub imports: Vec<(Name, Name, ImportDesc)>,
+    pub imports: Vec<(Name<'a>, Name<'a>, ImportDesc)>,
     pub functions: Vec<Indecies>,
     pub tables: Vec<TableType>,
     pub memory: Vec<MemType>,
+    pub tags: Vec<TagType>,
     pub global: Vec<(GlobalType, Expr)>,
-    pub export: Vec<(Name, Indecies)>,
+    pub export: Vec<(Name<'a>, ExportDesc)>,
     pub start: Option<Indecies>,
-    pub element: Vec<()>,
-    pub code: Vec<(Vec<(u32, ValueType)>, Vec<Instructions>)>,
-    pub data: Vec<DataSegment>,
+    pub element: Vec<ElementSegment>,
+    pub code: Vec<Code<'a>>,
+    pub data: Vec<DataSegment<'a>>,
     pub data_count: Option<u32>,
 }
 
-impl WasmSections {
-    pub fn from_reader(reader: &mut ByteReader) -> Result<Self, WasmParserError> {
-        let mut sections = WasmSections {
-            custom: vec![],
-            types: vec![],
-            imports: vec![],
-            functions: vec![],
-            tables: vec![],
-            memory: vec![],
-            global: vec![],
-            export: vec![],
-            start: None,
-            element: vec![],
-            code: vec![],
-            data: vec![],
-            data_count: None,
-        };
+impl<'a> WasmSections<'a> {
+    pub fn from_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError> {
+        Self::from_reader_with_registry(reader, &CustomSectionRegistry::default())
+    }
+
+    /// Parses the sections, decoding custom sections with the decoders in `registry`
+    pub fn from_reader_with_registry(
+        reader: &mut ByteReader<'a>,
+        registry: &CustomSectionRegistry,
+    ) -> Result<Self, WasmParserError> {
+        let mut sections = WasmSections::default();
 
+        let mut order = SectionOrder::default();
         while let Ok(section_id) = reader.read::<u8>() {
-            let _section_size = reader.read_uleb128::<u32>()?;
-
-            println!("Id: {section_id}  |  Size: {_section_size}");
-            match section_id {
-                0 => {
-                    reader.jump(_section_size as usize);
-                    println!("TODO: implement custom section");
-                }
-                1 => sections.types = read_vec(reader)?,
-                2 => sections.imports = (0..reader.read_uleb128::<u32>()?).map(|_| Ok((reader.read::<Name>()?, reader.read::<Name>()?, reader.read::<ImportDesc>()?))).collect::<Result<_,WasmParserError>>()?,
-                3 => {
-                    sections.functions = (0..reader.read_uleb128::<u32>()?)
-                        .map(|_| reader.read_uleb128::<u32>().map(Indecies::TypeIdx))
-                        .collect::<Result<_, _>>()?;
-                }
-                4 => sections.tables = read_vec(reader)?,
-                5 => sections.memory = read_vec(reader)?,
-                6 => sections.global = (0..reader.read_uleb128::<u32>()?).map(|_| {
-					Ok((reader.read::<GlobalType>()?, read_expr(reader)?))
-				}).collect::<Result<_, WasmParserError>>()?,
-                7 => {
-                    sections.export = (0..reader.read_uleb128::<u32>()?)
-                        .map(|_| {
-                            Ok((
-                                reader.read()?,
-                                match reader.read::<u8>()? {
-                                    0x00 => reader.read_uleb128::<u32>().map(Indecies::FuncIdx)?,
-                                    0x01 => reader.read_uleb128::<u32>().map(Indecies::TableIdx)?,
-                                    0x02 => reader.read_uleb128::<u32>().map(Indecies::MemIdx)?,
-                                    0x03 => {
-                                        reader.read_uleb128::<u32>().map(Indecies::GlobalIdx)?
-                                    }
-                                    id => {
-                                        return Err(WasmParserError::InvalidSectionError {
-                                            message: format!(
-                                                "Export section had an invalid (Name, Index) pair. The provided index ({}) isn't valid", id
-											),
-                                        })
-                                    }
-                                },
-                            ))
-                        })
-                        .collect::<Result<_, _>>()?
-                }
-                8 => sections.start = Some(reader.read_uleb128::<u32>().map(Indecies::FuncIdx)?),
-                9 => todo!("element section"),
-                10 => {
-					sections.code = (0..reader.read_uleb128::<u32>()?).map(|_| {
-						let code_sec_bytes = reader.read_uleb128::<u32>()?;
-						let bytes_end = reader.get_current_offset() + code_sec_bytes as usize;
-						let locals = (0..reader.read_uleb128::<u32>()?).map(|_| Ok((reader.read()?, reader.read()?))).collect::<Result<Vec<(u32, ValueType)>, WasmParserError>>()?;
-						let code_bytes = reader.read_bytes(bytes_end - reader.get_current_offset())?.to_vec();
-						
-						let mut code_reader = ByteReader::from_vec(&code_bytes);
-						let opcodes = read_expr(&mut code_reader)?;
-
-						println!("Opcodes:\n{:#?}", opcodes);
-
-						Ok((locals, opcodes))
-					}).collect::<Result<_,WasmParserError>>()?;
-
-				},
-                11 => sections.data = read_vec::<DataSegment>(reader)?,
-                12 => { sections.data_count = reader.read_uleb128::<u32>().ok() },
-
-                id => return Err(WasmParserError::InvalidSectionId { id }),
+            let id_offset = reader.get_current_offset() - 1;
+            let section_size = reader.read_uleb128::<u32>().context(reader, "section size")?;
+            let section = order.check(section_id).map_err(|kind| {
+                WasmParserError::at(id_offset, kind).in_context(reader, "section id".to_string())
+            })?;
+
+            if section_id == 0 {
+                sections.read_custom_section(reader, section_size, order.last(), registry)?;
+                continue;
             }
+
+            // Each section is parsed in a window bounded to its declared size so it can't read into the next one
+            let content_start = reader.get_current_offset();
+            reader
+                .push_limit(section_size as usize)
+                .map(|_| ())
+                .context(reader, section)?;
+            sections.read_section(reader, section_id).context(reader, section)?;
+            expect_consumed(reader, section, content_start, section_size).context(reader, section)?;
+            reader.pop_limit();
         }
 
         Ok(sections)
     }
 }
+
+impl<'a> WasmSections<'a> {
+    fn read_custom_section(
+        &mut self,
+        reader: &mut ByteReader<'a>,
+        section_size: u32,
+        after: Option<u8>,
+        registry: &CustomSectionRegistry,
+    ) -> Result<(), WasmParserError> {
+        let mut section = read_custom_section(reader, section_size, after)?;
+        let context = || format!("custom section '{}'", section.name);
+
+        // The contents are parsed by a copy of the reader in a window over the module bytes, so error offsets stay
+        // absolute and a decoder failing inside one of its own windows can't leave the module reader bounded by it
+        let mut contents = reader.clone();
+        contents
+            .move_to(section.range.start)
+            .push_limit(section.range.len())?;
+        // A malformed section is still kept as raw bytes, along with the error
+        let decoded = match section.name.as_str() {
+            "name" => contents.read().map(|names| self.names = Some(names)),
+            "linking" => contents.read().map(|linking| self.linking = Some(linking)),
+            reloc if reloc.starts_with("reloc.") => RelocSection::from_reader(Name::from(reloc).into_owned(), &mut contents)
+                .map(|relocations| self.relocations.push(relocations)),
+            _ => Ok(()),
+        };
+        section.decode_error = decoded.with_context(&contents, context).err().map(Box::new);
+
+        section.decoded = registry.decode(section.name.as_str(), &mut reader.clone(), section.range.clone());
+        self.custom.push(section);
+        Ok(())
+    }
+
+    fn read_section(&mut self, reader: &mut ByteReader<'a>, section_id: u8) -> Result<(), WasmParserError> {
+        if section_id == 10 {
+            let imported = self
+                .imports
+                .iter()
+                .filter(|(_, _, desc)| matches!(desc, ImportDesc::TypeIdx(_)))
+                .count() as u32;
+            self.code = (0..reader.read_uleb128::<u32>()?)
+                .map(|i| read_function_body(reader, imported + i))
+                .collect::<Result<_, _>>()?;
+            return Ok(());
+        }
+
+        match read_section(reader, section_id)? {
+            Payload::TypeSection(types) => self.types = types,
+            Payload::ImportSection(imports) => self.imports = imports,
+            Payload::FunctionSection(functions) => self.functions = functions,
+            Payload::TableSection(tables) => self.tables = tables,
+            Payload::MemorySection(memory) => self.memory = memory,
+            Payload::TagSection(tags) => self.tags = tags,
+            Payload::GlobalSection(global) => self.global = global,
+            Payload::ExportSection(export) => self.export = export,
+            Payload::StartSection(start) => self.start = Some(start),
+            Payload::ElementSection(element) => self.element = element,
+            Payload::DataSection(data) => self.data = data,
+            Payload::DataCountSection(count) => self.data_count = Some(count),
+            _ => unreachable!("read_section only returns the payloads of non-custom sections"),
+        }
+        Ok(())
+    }
+}
+
+/// Reads the contents of the non-custom section `section_id`, except for the code section which is read a function at a time
+pub(crate) fn read_section<'a>(reader: &mut ByteReader<'a>, section_id: u8) -> Result<Payload<'a>, WasmParserError> {
+    Ok(match section_id {
+        1 => Payload::TypeSection(read_items(reader, "type", |reader| reader.read())?),
+        2 => Payload::ImportSection(read_items(reader, "import", |reader| {
+            Ok((reader.read()?, reader.read()?, reader.read()?))
+        })?),
+        3 => Payload::FunctionSection(read_items(reader, "function", |reader| {
+            Ok(reader.read_uleb128::<u32>().map(Indecies::TypeIdx)?)
+        })?),
+        4 => Payload::TableSection(read_items(reader, "table", |reader| reader.read())?),
+        5 => Payload::MemorySection(read_items(reader, "memory", |reader| reader.read())?),
+        6 => Payload::GlobalSection(read_items(reader, "global", |reader| {
+            Ok((reader.read()?, read_expr(reader).context(reader, "init expression")?))
+        })?),
+        7 => Payload::ExportSection(read_items(reader, "export", |reader| {
+            Ok((reader.read()?, reader.read()?))
+        })?),
+        8 => Payload::StartSection(reader.read_uleb128::<u32>().map(Indecies::FuncIdx)?),
+        9 => Payload::ElementSection(read_items(reader, "element segment", |reader| reader.read())?),
+        11 => Payload::DataSection(read_items(reader, "data segment", |reader| reader.read())?),
+        12 => Payload::DataCountSection(reader.read_uleb128::<u32>()?),
+        13 => Payload::TagSection(read_items(reader, "tag", |reader| reader.read())?),
+        _ => unreachable!("section ids are checked by SectionOrder"),
+    })
+}
+
+/// Tracks the sections read so far, rejecting unknown ids, duplicates and sections out of order
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct SectionOrder {
+    /// The position in SECTION_ORDER of the last non-custom section
+    last: Option<usize>,
+}
+
+impl SectionOrder {
+    /// Records the section `id`, returning its name if it can follow the sections before it
+    pub(crate) fn check(&mut self, id: u8) -> Result<&'static str, WasmParserErrorKind> {
+        let section = SECTION_NAMES
+            .get(id as usize)
+            .copied()
+            .ok_or(WasmParserErrorKind::InvalidSectionId { id })?;
+        if id == 0 {
+            return Ok(section);
+        }
+
+        let position = SECTION_ORDER
+            .iter()
+            .position(|order| *order == id)
+            .expect("every named section has an order");
+        match self.last {
+            Some(previous) if previous == position => {
+                Err(WasmParserErrorKind::DuplicateSection { section })
+            }
+            Some(previous) if previous > position => Err(WasmParserErrorKind::SectionOutOfOrder {
+                section,
+                previous: SECTION_NAMES[SECTION_ORDER[previous] as usize],
+            }),
+            _ => {
+                self.last = Some(position);
+                Ok(section)
+            }
+        }
+    }
+
+    /// The id of the last non-custom section, `None` before the first one
+    pub(crate) fn last(&self) -> Option<u8> {
+        self.last.map(|position| SECTION_ORDER[position])
+    }
+}
+
+impl<'a> WasmSections<'a> {
+    /// Returns the first custom section called `name`
+    pub fn custom_section(&self, name: &str) -> Option<&CustomSection<'a>> {
+        self.custom.iter().find(|section| section.name.as_str() == name)
+    }
+
+    /// Copies everything borrowed from the module bytes, so the sections can outlive them
+    pub fn into_owned(self) -> WasmSections<'static> {
+        WasmSections {
+            custom: self.custom.into_iter().map(CustomSection::into_owned).collect(),
+            names: self.names,
+            linking: self.linking,
+            relocations: self.relocations,
+            types: self.types,
+            imports: self
+                .imports
+                .into_iter()
+                .map(|(module, name, desc)| (module.into_owned(), name.into_owned(), desc))
+                .collect(),
+            functions: self.functions,
+            tables: self.tables,
+            memory: self.memory,
+            tags: self.tags,
+            global: self.global,
+            export: self
+                .export
+                .into_iter()
+                .map(|(name, desc)| (name.into_owned(), desc))
+                .collect(),
+            start: self.start,
+            element: self.element,
+            code: self.code.into_iter().map(Code::into_owned).collect(),
+            data: self.data.into_iter().map(DataSegment::into_owned).collect(),
+            data_count: self.data_count,
+        }
+    }
+}
+
+impl WasmSections<'_> {
+    /// Writes every section in the order of the binary format, leaving out empty ones.
+    ///
+    /// Custom sections are written back byte-for-byte at their original position, decoded views of them
+    /// such as `names` aren't encoded. Relocation offsets in `reloc.*` sections stay meaningful only if the
+    /// sections they apply to encode to the same bytes they were parsed from
+    pub fn write(&self, writer: &mut ByteWriter) {
+        self.write_custom_sections(writer, None);
+        for id in SECTION_ORDER {
+            let mut contents = ByteWriter::new();
+            let empty = match id {
+                1 => write_items(&mut contents, &self.types, |writer, ty| {
+                    writer.write(ty);
+                }),
+                2 => write_items(&mut contents, &self.imports, |writer, (module, name, desc)| {
+                    writer.write(module).write(name).write(desc);
+                }),
+                3 => write_items(&mut contents, &self.functions, |writer, ty| {
+                    writer.write(ty);
+                }),
+                4 => write_items(&mut contents, &self.tables, |writer, table| {
+                    writer.write(table);
+                }),
+                5 => write_items(&mut contents, &self.memory, |writer, memory| {
+                    writer.write(memory);
+                }),
+                13 => write_items(&mut contents, &self.tags, |writer, tag| {
+                    writer.write(tag);
+                }),
+                6 => write_items(&mut contents, &self.global, |writer, (ty, init)| {
+                    writer.write(ty);
+                    write_expr(writer, init);
+                }),
+                7 => write_items(&mut contents, &self.export, |writer, (name, desc)| {
+                    writer.write(name).write(desc);
+                }),
+                8 => match &self.start {
+                    Some(start) => contents.write(start).is_empty(),
+                    None => true,
+                },
+                9 => write_items(&mut contents, &self.element, |writer, segment| {
+                    writer.write(segment);
+                }),
+                12 => match self.data_count {
+                    Some(count) => contents.write_uleb128(count).is_empty(),
+                    None => true,
+                },
+                10 => write_items(&mut contents, &self.code, |writer, code| {
+                    let mut entry = ByteWriter::new();
+                    entry.write(code);
+                    writer.write_uleb128(entry.len() as u64).write_bytes(entry.as_slice());
+                }),
+                11 => write_items(&mut contents, &self.data, |writer, segment| {
+                    writer.write(segment);
+                }),
+                _ => unreachable!("SECTION_ORDER only holds non-custom section ids"),
+            };
+
+            // A section that custom sections were placed after is kept even if empty, so they can follow it again
+            if !empty || self.custom.iter().any(|section| section.after == Some(id)) {
+                writer
+                    .write(&id)
+                    .write_uleb128(contents.len() as u64)
+                    .write_bytes(contents.as_slice());
+            }
+            self.write_custom_sections(writer, Some(id));
+        }
+
+        // Custom sections placed after an unknown section id go last
+        for section in &self.custom {
+            if section.after.is_some_and(|id| !SECTION_ORDER.contains(&id)) {
+                write_custom_section(writer, section);
+            }
+        }
+    }
+
+    fn write_custom_sections(&self, writer: &mut ByteWriter, after: Option<u8>) {
+        for section in self.custom.iter().filter(|section| section.after == after) {
+            write_custom_section(writer, section);
+        }
+    }
+}
+
+fn write_custom_section(writer: &mut ByteWriter, section: &CustomSection) {
+    let mut contents = ByteWriter::new();
+    contents.write(&section.name).write_bytes(&section.data);
+    writer
+        .write(&0x00u8)
+        .write_uleb128(contents.len() as u64)
+        .write_bytes(contents.as_slice());
+}
+
+/// Writes a vector of items, returning whether it was empty
+fn write_items<T>(writer: &mut ByteWriter, items: &[T], mut write: impl FnMut(&mut ByteWriter, &T)) -> bool {
+    writer.write_uleb128(items.len() as u64);
+    for item in items {
+        write(writer, item);
+    }
+    items.is_empty()
+}
+
+/// Reads the name and raw contents of a custom section of `section_size` bytes, without decoding it.
+/// `after` is the id of the last non-custom section before it
+pub(crate) fn read_custom_section<'a>(
+    reader: &mut ByteReader<'a>,
+    section_size: u32,
+    after: Option<u8>,
+) -> Result<CustomSection<'a>, WasmParserError> {
+    let section_start = reader.get_current_offset();
+    let name = reader.read::<Name>().context(reader, "custom section")?;
+
+    let content_start = reader.get_current_offset();
+    let data = read_rest_of(reader, "custom section", section_start, section_size)
+        .map(Cow::Borrowed)
+        .with_context(reader, || format!("custom section '{name}'"))?;
+
+    Ok(CustomSection {
+        name,
+        data,
+        range: content_start..reader.get_current_offset(),
+        after,
+        decoded: None,
+        decode_error: None,
+    })
+}
+
+/// Reads the remaining bytes of a construct that started at `start` and declared a size of `size` bytes
+fn read_rest_of<'a>(
+    reader: &mut ByteReader<'a>,
+    construct: &'static str,
+    start: usize,
+    size: u32,
+) -> Result<&'a [u8], WasmParserError> {
+    let declared = size as usize;
+    let consumed = reader.get_current_offset() - start;
+    let remaining = declared
+        .checked_sub(consumed)
+        .ok_or(WasmParserError::at(start, WasmParserErrorKind::SizeMismatch {
+            construct,
+            declared,
+            consumed,
+        }))?;
+    Ok(reader.read_bytes(remaining)?)
+}
+
+/// Checks that a construct which started at `start` and declared a size of `size` bytes was read exactly up to its end
+pub(crate) fn expect_consumed(
+    reader: &ByteReader,
+    construct: &'static str,
+    start: usize,
+    size: u32,
+) -> Result<(), WasmParserError> {
+    let consumed = reader.get_current_offset() - start;
+    if consumed != size as usize {
+        return Err(WasmParserError::at(
+            reader.get_current_offset(),
+            WasmParserErrorKind::SizeMismatch {
+                construct,
+                declared: size as usize,
+                consumed,
+            },
+        ));
+    }
+    Ok(())
+}
+
+/// Reads a vector, adding `item` and the index of the item being read to the error context
+fn read_items<'a, T>(
+    reader: &mut ByteReader<'a>,
+    item: &str,
+    mut read: impl FnMut(&mut ByteReader<'a>) -> Result<T, WasmParserError>,
+) -> Result<Vec<T>, WasmParserError> {
+    (0..reader.read_uleb128::<u32>()?)
+        .map(|i| read(reader).with_context(reader, || format!("{item} {i}")))
+        .collect()
+}
+
+/// Reads the code section entry of function `index`, the body is parsed in a window bounded to its declared size and has to fill it
+pub(crate) fn read_function_body<'a>(
+    reader: &mut ByteReader<'a>,
+    index: u32,
+) -> Result<Code<'a>, WasmParserError> {
+    read_code(reader).with_context(reader, || format!("function {index}"))
+}
+
+fn read_code<'a>(reader: &mut ByteReader<'a>) -> Result<Code<'a>, WasmParserError> {
+    let size = reader.read_uleb128::<u32>()? as usize;
+    let start = reader.get_current_offset();
+    reader.push_limit(size)?;
+
+    let locals = (0..reader.read_uleb128::<u32>()?)
+        .map(|_| Ok((reader.read_uleb128::<u32>()?, reader.read()?)))
+        .collect::<Result<Locals, WasmParserError>>()
+        .context(reader, "locals")?;
+    let body = read_expr(reader)?;
+    expect_consumed(reader, "function body", start, size as u32)?;
+
+    reader.pop_limit();
+    Ok(Code {
+        locals,
+        body,
+        bytes: Cow::Borrowed(&reader.as_slice()[start..start + size]),
+        range: start..start + size,
+    })
+}
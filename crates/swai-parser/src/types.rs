@@ -1,22 +1,25 @@
 #![allow(non_camel_case_types, unused, non_snake_case)]
 use crate::{
-    instructions::{read_expr, Instructions},
-    leb128::Leb128Readers,
+    error::{ResultExt, WasmParserError, WasmParserErrorKind},
+    instructions::{read_expr, write_expr, Instructions},
+    leb128::{Leb128Readers, Leb128Writers},
     sections::WasmSections,
 };
-use bytereader::{ByteReader, ByteReaderError, FromByteReader};
-use std::ops::{RangeFrom, RangeInclusive};
+use bytereader::{ByteReader, ByteWriter, FromByteReader, ToByteWriter};
+use std::{borrow::Cow, ops::Range};
 
 pub type MemType = Limits;
 pub type Expr = Vec<Instructions>;
+pub type Locals = Vec<(u32, ValueType)>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Indecies {
     TypeIdx(u32),
     FuncIdx(u32),
     TableIdx(u32),
     MemIdx(u32),
     GlobalIdx(u32),
+    TagIdx(u32),
     ElemIdx(u32),
     DataIdx(u32),
     LocalIdx(u32),
@@ -24,287 +27,1170 @@ pub enum Indecies {
 }
 
 impl Indecies {
-    pub fn get_function(&self, sections: &WasmSections) -> Option<Vec<Indecies>> {
+    /// The index value, regardless of the index space
+    pub fn index(&self) -> u32 {
         match self {
-            Indecies::FuncIdx(func_index) => {
-                let _fn_sec = sections.functions.get(*func_index as usize)?;
-
-                dbg!(&_fn_sec);
-                dbg!(func_index);
-                todo!()
-            }
-            invalid_index => todo!(),
+            Indecies::TypeIdx(index)
+            | Indecies::FuncIdx(index)
+            | Indecies::TableIdx(index)
+            | Indecies::MemIdx(index)
+            | Indecies::GlobalIdx(index)
+            | Indecies::TagIdx(index)
+            | Indecies::ElemIdx(index)
+            | Indecies::DataIdx(index)
+            | Indecies::LocalIdx(index)
+            | Indecies::LabelIdx(index) => *index,
         }
     }
+
+    /// Resolves a function index, through the imports and the function section, or a type index to its function type
+    pub fn get_function<'m>(&self, sections: &'m WasmSections) -> Option<&'m FunctionType> {
+        let type_index = match self {
+            Indecies::FuncIdx(index) => sections.function_type_index(*index)?,
+            Indecies::TypeIdx(index) => *index,
+            _ => return None,
+        };
+        sections.types.get(type_index as usize)
+    }
 }
 
-#[derive(Debug)]
-pub struct Name(String);
+impl ToByteWriter for Indecies {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer.write_uleb128(self.index());
+    }
+}
+
+/// A UTF-8 name, borrowed from the module bytes when parsed from them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name<'a>(Cow<'a, str>);
+
+impl Name<'_> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Copies the name if it's borrowed, so it no longer depends on the module bytes
+    pub fn into_owned(self) -> Name<'static> {
+        Name(Cow::Owned(self.0.into_owned()))
+    }
+}
+
+impl<'a> From<&'a str> for Name<'a> {
+    fn from(value: &'a str) -> Self {
+        Self(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for Name<'static> {
+    fn from(value: String) -> Self {
+        Self(Cow::Owned(value))
+    }
+}
+
+impl std::fmt::Display for Name<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'a> FromByteReader<'a> for Name<'a> {
+    type Error = WasmParserError;
 
-impl FromByteReader for Name {
-    fn read_from_byte_reader(
-        reader: &mut bytereader::ByteReader,
-    ) -> Result<Self, bytereader::ByteReaderError>
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
         let str_len = reader.read_uleb128::<u32>()?;
         let str = reader.read_string(str_len as usize)?;
 
-        Ok(Self(str))
+        Ok(Self(Cow::Borrowed(str)))
     }
 }
 
-#[derive(Debug)]
+impl ToByteWriter for Name<'_> {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer
+            .write_uleb128(self.0.len() as u64)
+            .write_bytes(self.0.as_bytes());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NumberTypes {
     i32,
     i64,
     f32,
     f64,
 }
-impl From<u8> for NumberTypes {
-    fn from(value: u8) -> Self {
+
+impl NumberTypes {
+    /// See the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#number-types
+    pub fn from_byte(value: u8) -> Option<Self> {
         match value {
-            0x7F => NumberTypes::i32,
-            0x7E => NumberTypes::i64,
-            0x7D => NumberTypes::f32,
-            0x7C => NumberTypes::f64,
-            _ => unreachable!("Check the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#number-types"),
+            0x7F => Some(NumberTypes::i32),
+            0x7E => Some(NumberTypes::i64),
+            0x7D => Some(NumberTypes::f32),
+            0x7C => Some(NumberTypes::f64),
+            _ => None,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            NumberTypes::i32 => 0x7F,
+            NumberTypes::i64 => 0x7E,
+            NumberTypes::f32 => 0x7D,
+            NumberTypes::f64 => 0x7C,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VectorTypes {
     v128,
 }
 
-impl From<u8> for VectorTypes {
-    fn from(value: u8) -> Self {
+impl VectorTypes {
+    /// See the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#vector-types
+    pub fn from_byte(value: u8) -> Option<Self> {
         match value {
-            0x7B => VectorTypes::v128,
-            _ => unreachable!("Check the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#vector-types")
+            0x7B => Some(VectorTypes::v128),
+            _ => None,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            VectorTypes::v128 => 0x7B,
         }
     }
 }
 
-#[derive(Debug)]
+/// The kind of value a reference points to, see the function references proposal:
+/// https://webassembly.github.io/function-references/core/syntax/types.html#heap-types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapType {
+    Func,
+    Extern,
+    Exn,
+    /// A function of the type at the index
+    TypeIdx(u32),
+}
+
+impl HeapType {
+    /// The abstract heap types, which are encoded as single bytes
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0x70 => Some(HeapType::Func),
+            0x6F => Some(HeapType::Extern),
+            0x69 => Some(HeapType::Exn),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromByteReader<'a> for HeapType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        let offset = reader.get_current_offset();
+        let byte = reader.peak::<u8>()?;
+        if let Some(heap) = HeapType::from_byte(byte) {
+            reader.jump(1);
+            return Ok(heap);
+        }
+
+        // Abstract heap types are single byte negative s33s, so any other negative one is unknown
+        let index = reader.read_s33().context(reader, "heap type")?;
+        u32::try_from(index).map(HeapType::TypeIdx).map_err(|_| {
+            WasmParserError::at(
+                offset,
+                WasmParserErrorKind::UnexpectedByte {
+                    construct: "heap type",
+                    byte,
+                },
+            )
+        })
+    }
+}
+
+impl ToByteWriter for HeapType {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        match self {
+            HeapType::Func => writer.write(&0x70u8),
+            HeapType::Extern => writer.write(&0x6Fu8),
+            HeapType::Exn => writer.write(&0x69u8),
+            HeapType::TypeIdx(index) => writer.write_leb128(*index as i64),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReferenceTypes {
     funcref,
     externref,
+    exnref,
+    /// `(ref null? heap)` of the function references proposal. Nullable references to an
+    /// abstract heap type are always one of the shorthands above, so each type is represented once
+    Ref {
+        nullable: bool,
+        heap: HeapType,
+    },
 }
 
-impl From<u8> for ReferenceTypes {
-    fn from(value: u8) -> Self {
+impl ReferenceTypes {
+    /// See the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#reference-types
+    pub fn from_byte(value: u8) -> Option<Self> {
         match value {
-            0x70 => ReferenceTypes::funcref,
-            0x6F => ReferenceTypes::externref,
-            _ => unreachable!("Check the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#reference-types")
+            0x70 => Some(ReferenceTypes::funcref),
+            0x6F => Some(ReferenceTypes::externref),
+            0x69 => Some(ReferenceTypes::exnref),
+            _ => None,
+        }
+    }
+
+    /// The single byte encoding of the shorthands, `None` for types that need a heap type immediate
+    pub fn to_byte(self) -> Option<u8> {
+        match self {
+            ReferenceTypes::funcref => Some(0x70),
+            ReferenceTypes::externref => Some(0x6F),
+            ReferenceTypes::exnref => Some(0x69),
+            ReferenceTypes::Ref { .. } => None,
+        }
+    }
+
+    /// The type `(ref null? heap)`, which is a shorthand if there is one
+    pub fn new(nullable: bool, heap: HeapType) -> Self {
+        match (nullable, heap) {
+            (true, HeapType::Func) => ReferenceTypes::funcref,
+            (true, HeapType::Extern) => ReferenceTypes::externref,
+            (true, HeapType::Exn) => ReferenceTypes::exnref,
+            _ => ReferenceTypes::Ref { nullable, heap },
+        }
+    }
+
+    pub fn heap_type(self) -> HeapType {
+        match self {
+            ReferenceTypes::funcref => HeapType::Func,
+            ReferenceTypes::externref => HeapType::Extern,
+            ReferenceTypes::exnref => HeapType::Exn,
+            ReferenceTypes::Ref { heap, .. } => heap,
+        }
+    }
+
+    pub fn is_nullable(self) -> bool {
+        match self {
+            ReferenceTypes::Ref { nullable, .. } => nullable,
+            _ => true,
+        }
+    }
+
+    /// The type of the same references without null
+    pub fn as_non_null(self) -> Self {
+        ReferenceTypes::new(false, self.heap_type())
+    }
+
+    /// Whether a reference of this type can be used where one of `expected` is expected, see:
+    /// https://webassembly.github.io/function-references/core/valid/matching.html#reference-types.
+    /// `types` are the function types of the module, references to equal ones match each other
+    pub fn matches(self, expected: ReferenceTypes, types: &[FunctionType]) -> bool {
+        if self.is_nullable() && !expected.is_nullable() {
+            return false;
+        }
+        match (self.heap_type(), expected.heap_type()) {
+            (HeapType::TypeIdx(_), HeapType::Func) => true,
+            (HeapType::TypeIdx(actual), HeapType::TypeIdx(expected)) => {
+                actual == expected
+                    || matches!(
+                        (types.get(actual as usize), types.get(expected as usize)),
+                        (Some(actual), Some(expected)) if actual == expected
+                    )
+            }
+            (actual, expected) => actual == expected,
         }
     }
 }
 
-#[derive(Debug)]
+impl<'a> FromByteReader<'a> for ReferenceTypes {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        // 0x63 prefixes a nullable and 0x64 a non-null reference with a heap type
+        match reader.peak::<u8>()? {
+            byte @ (0x63 | 0x64) => {
+                reader.jump(1);
+                Ok(ReferenceTypes::new(byte == 0x63, reader.read()?))
+            }
+            _ => read_byte_as(reader, "reference type", ReferenceTypes::from_byte),
+        }
+    }
+}
+
+impl ToByteWriter for ReferenceTypes {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        match self.to_byte() {
+            Some(byte) => writer.write(&byte),
+            None => writer
+                .write(&if self.is_nullable() { 0x63u8 } else { 0x64u8 })
+                .write(&self.heap_type()),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ValueType {
     NumType(NumberTypes),
     VecType(VectorTypes),
     RefType(ReferenceTypes),
 }
 
-impl From<u8> for ValueType {
-    fn from(value: u8) -> Self {
-        match value {
-            0x7C..=0x7F => ValueType::NumType(NumberTypes::from(value)),
-            0x7B => ValueType::VecType(VectorTypes::from(value)),
-            0x6F..=0x70 => ValueType::RefType(ReferenceTypes::from(value)),
-            _ => unreachable!("Check the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#value-types")
+impl ValueType {
+    /// See the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#value-types
+    pub fn from_byte(value: u8) -> Option<Self> {
+        NumberTypes::from_byte(value)
+            .map(ValueType::NumType)
+            .or_else(|| VectorTypes::from_byte(value).map(ValueType::VecType))
+            .or_else(|| ReferenceTypes::from_byte(value).map(ValueType::RefType))
+    }
+
+    /// The single byte encoding of the type, `None` for reference types that need a heap type immediate
+    pub fn to_byte(self) -> Option<u8> {
+        match self {
+            ValueType::NumType(ty) => Some(ty.to_byte()),
+            ValueType::VecType(ty) => Some(ty.to_byte()),
+            ValueType::RefType(ty) => ty.to_byte(),
+        }
+    }
+
+    /// Whether a value of this type can be used where one of `expected` is expected,
+    /// which only differs from equality for reference types
+    pub fn matches(self, expected: ValueType, types: &[FunctionType]) -> bool {
+        match (self, expected) {
+            (ValueType::RefType(actual), ValueType::RefType(expected)) => {
+                actual.matches(expected, types)
+            }
+            _ => self == expected,
         }
     }
+
+    /// Whether the type has a default value, non-null references don't
+    pub fn is_defaultable(self) -> bool {
+        !matches!(self, ValueType::RefType(ty) if !ty.is_nullable())
+    }
 }
 
-impl FromByteReader for ValueType {
-    fn read_from_byte_reader(
-        reader: &mut bytereader::ByteReader,
-    ) -> Result<Self, bytereader::ByteReaderError>
+impl<'a> FromByteReader<'a> for ValueType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
-        Ok(Self::from(reader.read::<u8>()?))
+        match reader.peak::<u8>()? {
+            0x63 | 0x64 => Ok(ValueType::RefType(reader.read()?)),
+            _ => read_byte_as(reader, "value type", ValueType::from_byte),
+        }
     }
 }
 
-#[derive(Debug)]
+impl ToByteWriter for ValueType {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        match self {
+            ValueType::NumType(ty) => writer.write(&ty.to_byte()),
+            ValueType::VecType(ty) => writer.write(&ty.to_byte()),
+            ValueType::RefType(ty) => writer.write(ty),
+        };
+    }
+}
+
+impl std::fmt::Display for NumberTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            NumberTypes::i32 => "i32",
+            NumberTypes::i64 => "i64",
+            NumberTypes::f32 => "f32",
+            NumberTypes::f64 => "f64",
+        })
+    }
+}
+
+impl std::fmt::Display for VectorTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("v128")
+    }
+}
+
+impl std::fmt::Display for HeapType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeapType::Func => f.write_str("func"),
+            HeapType::Extern => f.write_str("extern"),
+            HeapType::Exn => f.write_str("exn"),
+            HeapType::TypeIdx(index) => index.fmt(f),
+        }
+    }
+}
+
+impl std::fmt::Display for ReferenceTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferenceTypes::funcref => f.write_str("funcref"),
+            ReferenceTypes::externref => f.write_str("externref"),
+            ReferenceTypes::exnref => f.write_str("exnref"),
+            ReferenceTypes::Ref {
+                nullable: true,
+                heap,
+            } => write!(f, "(ref null {heap})"),
+            ReferenceTypes::Ref {
+                nullable: false,
+                heap,
+            } => write!(f, "(ref {heap})"),
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueType::NumType(ty) => ty.fmt(f),
+            ValueType::VecType(ty) => ty.fmt(f),
+            ValueType::RefType(ty) => ty.fmt(f),
+        }
+    }
+}
+
+/// Reads a single byte and converts it with `convert`, failing with
+/// [`WasmParserError::UnexpectedByte`] if the byte doesn't encode any value of `T`
+pub(crate) fn read_byte_as<T>(
+    reader: &mut ByteReader,
+    construct: &'static str,
+    convert: impl FnOnce(u8) -> Option<T>,
+) -> Result<T, WasmParserError> {
+    let offset = reader.get_current_offset();
+    let byte = reader.read::<u8>()?;
+    convert(byte).ok_or(WasmParserError::at(
+        offset,
+        WasmParserErrorKind::UnexpectedByte { construct, byte },
+    ))
+}
+
+#[derive(Debug, PartialEq)]
 pub struct FunctionType {
-    params: Vec<ValueType>,
-    result: Vec<ValueType>,
+    pub(crate) params: Vec<ValueType>,
+    pub(crate) result: Vec<ValueType>,
 }
 
-impl FromByteReader for FunctionType {
-    fn read_from_byte_reader(
-        reader: &mut bytereader::ByteReader,
-    ) -> Result<Self, bytereader::ByteReaderError>
+impl FunctionType {
+    pub fn new(params: Vec<ValueType>, results: Vec<ValueType>) -> Self {
+        Self {
+            params,
+            result: results,
+        }
+    }
+
+    pub fn params(&self) -> &[ValueType] {
+        &self.params
+    }
+
+    pub fn results(&self) -> &[ValueType] {
+        &self.result
+    }
+}
+
+impl<'a> FromByteReader<'a> for FunctionType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
-        reader.read_expect(&[0x60])?;
+        read_byte_as(reader, "function type", |byte| (byte == 0x60).then_some(()))?;
 
         Ok(Self {
-            params: read_vec(reader)?,
-            result: read_vec(reader)?,
+            params: read_vec(reader).context(reader, "parameters")?,
+            result: read_vec(reader).context(reader, "results")?,
         })
     }
 }
 
-#[derive(Debug)]
-pub enum Limits {
-    min(RangeFrom<u32>),
-    minmax(RangeInclusive<u32>),
+impl ToByteWriter for FunctionType {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer.write(&0x60u8);
+        write_vec(writer, &self.params);
+        write_vec(writer, &self.result);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    pub(crate) min: u64,
+    pub(crate) max: Option<u64>,
+    /// The limits of a memory shared between threads, which always has a maximum
+    pub(crate) shared: bool,
+    /// Memories and tables with 64 bit limits are indexed with `i64` instead of `i32`
+    pub(crate) is_64: bool,
+}
+
+impl Limits {
+    pub fn new(min: u64, max: Option<u64>) -> Self {
+        Self {
+            min,
+            max,
+            shared: false,
+            is_64: false,
+        }
+    }
+
+    /// The limits of a memory shared between threads, which needs a maximum
+    pub fn shared(min: u64, max: u64) -> Self {
+        Self {
+            shared: true,
+            ..Self::new(min, Some(max))
+        }
+    }
+
+    /// The same limits for a memory or table indexed with `i64`
+    pub fn with_64_bit_indices(self) -> Self {
+        Self {
+            is_64: true,
+            ..self
+        }
+    }
+
+    pub fn minimum(&self) -> u64 {
+        self.min
+    }
+
+    pub fn maximum(&self) -> Option<u64> {
+        self.max
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    /// The type of the addresses into a memory or the indices into a table with these limits
+    pub fn index_type(&self) -> NumberTypes {
+        match self.is_64 {
+            true => NumberTypes::i64,
+            false => NumberTypes::i32,
+        }
+    }
 }
 
-impl FromByteReader for Limits {
-    fn read_from_byte_reader(
-        reader: &mut bytereader::ByteReader,
-    ) -> Result<Self, bytereader::ByteReaderError>
+impl<'a> FromByteReader<'a> for Limits {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
-        let limit_type = reader.read::<u8>()?;
-        let n = reader.read_uleb128::<u32>()?;
-
-        match limit_type {
-            0x00 => Ok(Self::min(n..)),
-            0x01 => {
-                let m = reader.read_uleb128::<u32>()?;
-                Ok((Self::minmax(n..=m)))
-            }
+        // Bit 0 flags a maximum, bit 1 a shared memory and bit 2 64 bit limits.
+        // Shared limits without a maximum (flags 0x02 and 0x06) aren't allowed
+        let flags = read_byte_as(reader, "limits", |byte| match byte {
+            0x00 | 0x01 | 0x03 | 0x04 | 0x05 | 0x07 => Some(byte),
+            _ => None,
+        })?;
+        let is_64 = flags & 0x04 != 0;
+        let mut read_bound = || match is_64 {
+            true => reader.read_uleb128::<u64>(),
+            false => reader.read_uleb128::<u32>().map(u64::from),
+        };
 
-            v => Err(bytereader::ByteReaderError::UnknownError(format!(
-                "Failed to parse Limits: limit_type must be either '0x00' or '0x01' got '0x{v:X?}'"
-            ))),
+        let min = read_bound()?;
+        let max = match flags & 0x01 != 0 {
+            true => Some(read_bound()?),
+            false => None,
+        };
+        Ok(Self {
+            min,
+            max,
+            shared: flags & 0x02 != 0,
+            is_64,
+        })
+    }
+}
+
+impl ToByteWriter for Limits {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        let flags =
+            u8::from(self.max.is_some()) | u8::from(self.shared) << 1 | u8::from(self.is_64) << 2;
+        writer.write(&flags).write_uleb128(self.min);
+        if let Some(max) = self.max {
+            writer.write_uleb128(max);
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct TableType {
-    elem: ReferenceTypes,
-    lim: Limits,
+    pub(crate) elem: ReferenceTypes,
+    pub(crate) lim: Limits,
 }
 
-impl FromByteReader for TableType {
-    fn read_from_byte_reader(
-        reader: &mut bytereader::ByteReader,
-    ) -> Result<Self, bytereader::ByteReaderError>
+impl TableType {
+    pub fn new(element_type: ReferenceTypes, limits: Limits) -> Self {
+        Self {
+            elem: element_type,
+            lim: limits,
+        }
+    }
+
+    pub fn element_type(&self) -> ReferenceTypes {
+        self.elem
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.lim
+    }
+}
+
+impl<'a> FromByteReader<'a> for TableType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
         Ok(TableType {
-            elem: ReferenceTypes::from(reader.read::<u8>()?),
+            elem: reader.read()?,
             lim: reader.read()?,
         })
     }
 }
 
-#[derive(Debug)]
+impl ToByteWriter for TableType {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer.write(&self.elem).write(&self.lim);
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct GlobalType {
-    vtype: ValueType,
-    mutability: Mutability,
+    pub(crate) vtype: ValueType,
+    pub(crate) mutability: Mutability,
 }
 
-impl FromByteReader for GlobalType {
-    fn read_from_byte_reader(
-        reader: &mut bytereader::ByteReader,
-    ) -> Result<Self, bytereader::ByteReaderError>
+impl GlobalType {
+    pub fn new(value_type: ValueType, mutability: Mutability) -> Self {
+        Self {
+            vtype: value_type,
+            mutability,
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        self.vtype
+    }
+
+    pub fn mutability(&self) -> Mutability {
+        self.mutability
+    }
+}
+
+impl<'a> FromByteReader<'a> for GlobalType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
         Ok(GlobalType {
             vtype: reader.read()?,
-            mutability: Mutability::from(reader.read::<u8>()?),
+            mutability: read_byte_as(reader, "global mutability", Mutability::from_byte)?,
         })
     }
 }
 
-#[derive(Debug)]
+impl ToByteWriter for GlobalType {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer.write(&self.vtype).write(&self.mutability.to_byte());
+    }
+}
+
+/// The type of an exception tag, its function type gives the values an exception with the tag carries
+#[derive(Debug, PartialEq)]
+pub struct TagType {
+    pub(crate) ty: u32,
+}
+
+impl TagType {
+    pub fn new(type_index: u32) -> Self {
+        Self { ty: type_index }
+    }
+
+    pub fn type_index(&self) -> u32 {
+        self.ty
+    }
+}
+
+impl<'a> FromByteReader<'a> for TagType {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        // Exceptions are the only kind of tag so far, see: https://webassembly.github.io/exception-handling/core/binary/types.html#tag-types
+        read_byte_as(reader, "tag attribute", |byte| (byte == 0x00).then_some(()))?;
+        Ok(TagType {
+            ty: reader.read_uleb128::<u32>()?,
+        })
+    }
+}
+
+impl ToByteWriter for TagType {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer.write(&0x00u8).write_uleb128(self.ty);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mutability {
     Const,
     Var,
 }
 
-impl From<u8> for Mutability {
-    fn from(value: u8) -> Self {
+impl Mutability {
+    /// See the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#global-types
+    pub fn from_byte(value: u8) -> Option<Self> {
         match value {
-            0x00 => Mutability::Const,
-            0x01 => Mutability::Var,
-            _ => unreachable!("Check the wasm spec for more info: https://webassembly.github.io/spec/core/binary/types.html#global-types")
+            0x00 => Some(Mutability::Const),
+            0x01 => Some(Mutability::Var),
+            _ => None,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Mutability::Const => 0x00,
+            Mutability::Var => 0x01,
         }
     }
 }
 
-pub fn read_vec<T>(reader: &mut ByteReader) -> Result<Vec<T>, ByteReaderError>
+pub fn read_vec<'a, T>(reader: &mut ByteReader<'a>) -> Result<Vec<T>, WasmParserError>
 where
-    T: FromByteReader,
+    T: FromByteReader<'a>,
+    WasmParserError: From<T::Error>,
 {
     (0..reader.read_uleb128::<u32>()?)
-        .map(|_| T::read_from_byte_reader(reader))
+        .map(|_| Ok(T::read_from_byte_reader(reader)?))
         .collect()
 }
 
-#[derive(Debug)]
+pub fn write_vec<T: ToByteWriter>(writer: &mut ByteWriter, items: &[T]) {
+    writer.write_uleb128(items.len() as u64);
+    for item in items {
+        writer.write(item);
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub enum ImportDesc {
     TypeIdx(Indecies),
     TableType(TableType),
     MemType(MemType),
     GlobalType(GlobalType),
+    TagType(TagType),
 }
 
-impl FromByteReader for ImportDesc {
-    fn read_from_byte_reader(reader: &mut ByteReader) -> Result<Self, ByteReaderError>
+impl<'a> FromByteReader<'a> for ImportDesc {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
+        // See the wasm spec for more info: https://webassembly.github.io/spec/core/binary/modules.html#binary-importsec
+        let offset = reader.get_current_offset();
         Ok(match reader.read::<u8>()? {
             0x00 => ImportDesc::TypeIdx(reader.read_uleb128::<u32>().map(Indecies::TypeIdx)?),
             0x01 => ImportDesc::TableType(reader.read()?),
             0x02 => ImportDesc::MemType(reader.read()?),
             0x03 => ImportDesc::GlobalType(reader.read()?),
-			_ => unreachable!("Check the wasm spec for more info: https://webassembly.github.io/spec/core/binary/modules.html#binary-importsec")
-
+            0x04 => ImportDesc::TagType(reader.read()?),
+            byte => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedByte {
+                        construct: "import description",
+                        byte,
+                    },
+                ))
+            }
         })
     }
 }
 
+impl ToByteWriter for ImportDesc {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        match self {
+            ImportDesc::TypeIdx(index) => writer.write(&0x00u8).write(index),
+            ImportDesc::TableType(table) => writer.write(&0x01u8).write(table),
+            ImportDesc::MemType(memory) => writer.write(&0x02u8).write(memory),
+            ImportDesc::GlobalType(global) => writer.write(&0x03u8).write(global),
+            ImportDesc::TagType(tag) => writer.write(&0x04u8).write(tag),
+        };
+    }
+}
+
+/// The definition an export refers to, by its index in one of the index spaces that can be exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportDesc {
+    FuncIdx(u32),
+    TableIdx(u32),
+    MemIdx(u32),
+    GlobalIdx(u32),
+    TagIdx(u32),
+}
+
+impl ExportDesc {
+    /// The index value, regardless of the index space
+    pub fn index(&self) -> u32 {
+        match self {
+            ExportDesc::FuncIdx(index)
+            | ExportDesc::TableIdx(index)
+            | ExportDesc::MemIdx(index)
+            | ExportDesc::GlobalIdx(index)
+            | ExportDesc::TagIdx(index) => *index,
+        }
+    }
+}
+
+impl<'a> FromByteReader<'a> for ExportDesc {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        // See the wasm spec for more info: https://webassembly.github.io/spec/core/binary/modules.html#binary-exportsec
+        let offset = reader.get_current_offset();
+        let desc = match reader.read::<u8>()? {
+            0x00 => ExportDesc::FuncIdx,
+            0x01 => ExportDesc::TableIdx,
+            0x02 => ExportDesc::MemIdx,
+            0x03 => ExportDesc::GlobalIdx,
+            0x04 => ExportDesc::TagIdx,
+            byte => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedByte {
+                        construct: "export description",
+                        byte,
+                    },
+                ))
+            }
+        };
+        Ok(reader.read_uleb128::<u32>().map(desc)?)
+    }
+}
+
+impl ToByteWriter for ExportDesc {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        let kind: u8 = match self {
+            ExportDesc::FuncIdx(_) => 0x00,
+            ExportDesc::TableIdx(_) => 0x01,
+            ExportDesc::MemIdx(_) => 0x02,
+            ExportDesc::GlobalIdx(_) => 0x03,
+            ExportDesc::TagIdx(_) => 0x04,
+        };
+        writer.write(&kind).write_uleb128(self.index());
+    }
+}
+
+/// A code section entry, the locals and instructions of a function defined by the module
 #[derive(Debug)]
-pub struct DataSegment {
+pub struct Code<'a> {
+    /// Run-length encoded locals, the parameters come before them
+    pub locals: Locals,
+    pub body: Expr,
+    /// The encoded locals and instructions, without the size in front of them, borrowed from the module
+    /// bytes when parsed from them. Encoding a module writes `locals` and `body`, so changes to them aren't lost
+    pub bytes: Cow<'a, [u8]>,
+    /// The byte range of `bytes` in the module, `0..0` for functions that weren't parsed from one
+    pub range: Range<usize>,
+}
+
+impl Code<'_> {
+    /// A function that wasn't parsed from a module, `bytes` holds its encoding
+    pub fn new(locals: Locals, body: Expr) -> Code<'static> {
+        let mut code = Code {
+            locals,
+            body,
+            bytes: Cow::Borrowed(&[]),
+            range: 0..0,
+        };
+        let mut writer = ByteWriter::new();
+        writer.write(&code);
+        code.bytes = Cow::Owned(writer.into_vec());
+        code
+    }
+
+    pub fn into_owned(self) -> Code<'static> {
+        Code {
+            locals: self.locals,
+            body: self.body,
+            bytes: Cow::Owned(self.bytes.into_owned()),
+            range: self.range,
+        }
+    }
+}
+
+/// Compares locals and instructions only, `bytes` and `range` follow from where the function was parsed
+impl PartialEq for Code<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.locals == other.locals && self.body == other.body
+    }
+}
+
+impl ToByteWriter for Code<'_> {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        writer.write_uleb128(self.locals.len() as u64);
+        for (count, ty) in &self.locals {
+            writer.write_uleb128(*count).write(ty);
+        }
+        write_expr(writer, &self.body);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DataSegment<'a> {
     pub mode: SegmentMode,
-    pub bytes: Vec<u8>,
+    /// The initial memory contents, borrowed from the module bytes when parsed from them
+    pub bytes: Cow<'a, [u8]>,
 }
-#[derive(Debug)]
+
+impl DataSegment<'_> {
+    pub fn into_owned(self) -> DataSegment<'static> {
+        DataSegment {
+            mode: self.mode,
+            bytes: Cow::Owned(self.bytes.into_owned()),
+        }
+    }
+}
+#[derive(Debug, PartialEq)]
 pub enum SegmentMode {
     Passive,
     Active { memory_index: u32, offset: Expr },
 }
 
-impl FromByteReader for DataSegment {
-    fn read_from_byte_reader(reader: &mut ByteReader) -> Result<Self, ByteReaderError>
+impl<'a> FromByteReader<'a> for DataSegment<'a> {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
     where
         Self: Sized,
     {
-        let bitfield = reader.read_uleb128::<u32>()?;
-        Ok(DataSegment {
-            mode: if bitfield & 0b01 == 0 {
+        let offset = reader.get_current_offset();
+        let mode = match reader.read_uleb128::<u32>()? {
+            0 => SegmentMode::Active {
+                memory_index: 0,
+                offset: read_expr(reader).context(reader, "offset expression")?,
+            },
+            1 => SegmentMode::Passive,
+            2 => {
+                let memory_index = reader.read_uleb128::<u32>()?;
                 SegmentMode::Active {
-                    offset: read_expr(reader)?,
-                    memory_index: if bitfield == 2 {
-                        reader.read_uleb128::<u32>()?
-                    } else {
-                        0
-                    },
+                    memory_index,
+                    offset: read_expr(reader).context(reader, "offset expression")?,
                 }
-            } else {
-                SegmentMode::Passive
-            },
-            bytes: read_vec::<u8>(reader)?,
+            }
+            value => {
+                return Err(WasmParserError::at(
+                    offset,
+                    WasmParserErrorKind::UnexpectedValue {
+                        construct: "data segment flags",
+                        value,
+                    },
+                ))
+            }
+        };
+
+        let length = reader.read_uleb128::<u32>()?;
+        Ok(DataSegment {
+            mode,
+            bytes: Cow::Borrowed(reader.read_bytes(length as usize)?),
         })
     }
 }
+
+impl ToByteWriter for DataSegment<'_> {
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        match &self.mode {
+            SegmentMode::Passive => {
+                writer.write_uleb128(1u32);
+            }
+            SegmentMode::Active {
+                memory_index: 0,
+                offset,
+            } => {
+                writer.write_uleb128(0u32);
+                write_expr(writer, offset);
+            }
+            SegmentMode::Active {
+                memory_index,
+                offset,
+            } => {
+                writer.write_uleb128(2u32).write_uleb128(*memory_index);
+                write_expr(writer, offset);
+            }
+        }
+        writer
+            .write_uleb128(self.bytes.len() as u64)
+            .write_bytes(&self.bytes);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ElementSegment {
+    pub ty: ReferenceTypes,
+    pub init: ElementInit,
+    pub mode: ElementMode,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ElementInit {
+    /// A vector of function indices, as used by the encodings with the `elemkind` byte
+    Functions(Vec<Indecies>),
+    /// A vector of constant expressions, each producing a single reference
+    Expressions(Vec<Expr>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ElementMode {
+    Passive,
+    Active { table_index: u32, offset: Expr },
+    Declarative,
+}
+
+impl<'a> FromByteReader<'a> for ElementSegment {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader<'a>) -> Result<Self, WasmParserError>
+    where
+        Self: Sized,
+    {
+        // Bit 0: passive or declarative, bit 1: explicit table index (active) or declarative,
+        // bit 2: the initializers are expressions instead of function indices
+        let offset = reader.get_current_offset();
+        let bitfield = reader.read_uleb128::<u32>()?;
+        if bitfield > 7 {
+            return Err(WasmParserError::at(
+                offset,
+                WasmParserErrorKind::UnexpectedValue {
+                    construct: "element segment flags",
+                    value: bitfield,
+                },
+            ));
+        }
+
+        let mode = match bitfield & 0b011 {
+            0b000 => ElementMode::Active {
+                table_index: 0,
+                offset: read_expr(reader).context(reader, "offset expression")?,
+            },
+            0b010 => {
+                let table_index = reader.read_uleb128::<u32>()?;
+                ElementMode::Active {
+                    table_index,
+                    offset: read_expr(reader).context(reader, "offset expression")?,
+                }
+            }
+            0b001 => ElementMode::Passive,
+            _ => ElementMode::Declarative,
+        };
+
+        // Encodings 0 and 4 imply `funcref`, the rest spell out an elemkind or a reftype
+        let explicit_type = bitfield & 0b011 != 0;
+        let uses_expressions = bitfield & 0b100 != 0;
+
+        let ty = match (explicit_type, uses_expressions) {
+            (false, _) => ReferenceTypes::funcref,
+            (true, false) => read_byte_as(reader, "element kind", |byte| {
+                (byte == 0x00).then_some(ReferenceTypes::funcref)
+            })?,
+            (true, true) => reader.read()?,
+        };
+
+        let init = if uses_expressions {
+            ElementInit::Expressions(
+                (0..reader.read_uleb128::<u32>()?)
+                    .map(|i| read_expr(reader).with_context(reader, || format!("element {i}")))
+                    .collect::<Result<_, _>>()?,
+            )
+        } else {
+            ElementInit::Functions(
+                (0..reader.read_uleb128::<u32>()?)
+                    .map(|_| reader.read_uleb128::<u32>().map(Indecies::FuncIdx))
+                    .collect::<Result<_, _>>()?,
+            )
+        };
+
+        Ok(ElementSegment { ty, init, mode })
+    }
+}
+
+impl ToByteWriter for ElementSegment {
+    /// Picks the shortest of the eight encodings that can represent the segment
+    fn write_to_byte_writer(&self, writer: &mut ByteWriter) {
+        // Function indices have an elemkind which can only be funcref, typed references need expressions
+        let uses_expressions =
+            matches!(self.init, ElementInit::Expressions(_)) || self.ty != ReferenceTypes::funcref;
+        // Encodings 0 and 4 can only describe funcref segments in table 0
+        let implicit = match &self.mode {
+            ElementMode::Active { table_index, .. } => {
+                *table_index == 0 && self.ty == ReferenceTypes::funcref
+            }
+            _ => false,
+        };
+        let mode_bits = match &self.mode {
+            ElementMode::Active { .. } if implicit => 0b000,
+            ElementMode::Active { .. } => 0b010,
+            ElementMode::Passive => 0b001,
+            ElementMode::Declarative => 0b011,
+        };
+        writer.write_uleb128(mode_bits | if uses_expressions { 0b100u32 } else { 0 });
+
+        if let ElementMode::Active {
+            table_index,
+            offset,
+        } = &self.mode
+        {
+            if !implicit {
+                writer.write_uleb128(*table_index);
+            }
+            write_expr(writer, offset);
+        }
+        if !implicit {
+            match uses_expressions {
+                // The elemkind of function indices, only funcref is defined
+                false => writer.write(&0x00u8),
+                true => writer.write(&self.ty),
+            };
+        }
+
+        match &self.init {
+            ElementInit::Functions(functions) if uses_expressions => {
+                writer.write_uleb128(functions.len() as u64);
+                for function in functions {
+                    write_expr(writer, &vec![Instructions::RefFunc(function.clone())]);
+                }
+            }
+            ElementInit::Functions(functions) => write_vec(writer, functions),
+            ElementInit::Expressions(expressions) => {
+                writer.write_uleb128(expressions.len() as u64);
+                for expr in expressions {
+                    write_expr(writer, expr);
+                }
+            }
+        }
+    }
+}
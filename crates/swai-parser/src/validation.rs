@@ -0,0 +1,1702 @@
+use std::collections::HashSet;
+
+use crate::{
+    error::{ValidationError, ValidationErrorKind},
+    instructions::{BlockType, Catch, Instructions, MemArg},
+    types::{
+        DataSegment, ElementInit, ElementMode, ElementSegment, ExportDesc, Expr, FunctionType,
+        GlobalType, HeapType, ImportDesc, Indecies, Limits, Locals, Mutability, NumberTypes,
+        ReferenceTypes, SegmentMode, TableType, ValueType, VectorTypes,
+    },
+    WasmModule,
+};
+
+const I32: ValueType = ValueType::NumType(NumberTypes::i32);
+const I64: ValueType = ValueType::NumType(NumberTypes::i64);
+const F32: ValueType = ValueType::NumType(NumberTypes::f32);
+const F64: ValueType = ValueType::NumType(NumberTypes::f64);
+const V128: ValueType = ValueType::VecType(VectorTypes::v128);
+const EXNREF: ValueType = ValueType::RefType(ReferenceTypes::exnref);
+
+/// The largest memory size in 64 KiB pages, which covers the whole 32-bit address space
+pub const MAX_MEMORY_PAGES: u32 = 65536;
+/// The largest size of a memory with 64 bit limits in 64 KiB pages, which covers a 64-bit address space
+pub const MAX_MEMORY64_PAGES: u64 = 1 << 48;
+
+/// Checks that a parsed module is valid, following the validation algorithm in the appendix of the spec:
+/// https://webassembly.github.io/spec/core/appendix/algorithm.html
+///
+/// Besides the unique export names the spec requires, every `(module, name)` pair can only be imported once
+pub fn validate(module: &WasmModule) -> Result<(), ValidationError> {
+    let context = Context::new(module)?;
+    let sections = &module.sections;
+
+    let mut import_names = HashSet::new();
+    for (i, (module, name, _)) in sections.imports.iter().enumerate() {
+        if !import_names.insert((module.as_str(), name.as_str())) {
+            return Err(ValidationError::new(
+                ValidationErrorKind::DuplicateImport {
+                    module: module.to_string(),
+                    name: name.to_string(),
+                },
+                &format!("import {i}"),
+            ));
+        }
+    }
+
+    for (i, ty) in sections.types.iter().enumerate() {
+        for ty in ty.params.iter().chain(&ty.result) {
+            context
+                .value_type(*ty)
+                .map_err(|kind| ValidationError::new(kind, &format!("type {i}")))?;
+        }
+    }
+    for (i, ty) in sections.tables.iter().enumerate() {
+        context
+            .table_type(ty)
+            .map_err(|kind| ValidationError::new(kind, &format!("table {i}")))?;
+    }
+    for (i, limits) in sections.memory.iter().enumerate() {
+        validate_memory_type(limits)
+            .map_err(|kind| ValidationError::new(kind, &format!("memory {i}")))?;
+    }
+    if context.memories.len() > 1 {
+        return Err(ValidationError::new(
+            ValidationErrorKind::MultipleMemories {
+                count: context.memories.len(),
+            },
+            "memory section",
+        ));
+    }
+
+    // Global initializers can only refer to imported globals
+    for (i, (ty, init)) in sections.global.iter().enumerate() {
+        let global = context.imported_globals + i;
+        context
+            .value_type(ty.vtype)
+            .and_then(|_| context.validate_constant(init, ty.vtype, context.imported_globals))
+            .map_err(|kind| ValidationError::new(kind, &format!("global {global}")))?;
+    }
+
+    for (i, segment) in sections.element.iter().enumerate() {
+        context
+            .validate_element(segment)
+            .map_err(|kind| ValidationError::new(kind, &format!("element segment {i}")))?;
+    }
+    for (i, segment) in sections.data.iter().enumerate() {
+        context
+            .validate_data(segment)
+            .map_err(|kind| ValidationError::new(kind, &format!("data segment {i}")))?;
+    }
+
+    if let Some(start) = &sections.start {
+        let ty = context
+            .function_type(start.index())
+            .map_err(|kind| ValidationError::new(kind, "start section"))?;
+        if !ty.params.is_empty() || !ty.result.is_empty() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidStartFunction,
+                "start section",
+            ));
+        }
+    }
+
+    for (i, ty) in sections.tags.iter().enumerate() {
+        let tag = context.imported_tags + i;
+        context
+            .validate_tag(ty.ty)
+            .map_err(|kind| ValidationError::new(kind, &format!("tag {tag}")))?;
+    }
+
+    let mut export_names = HashSet::new();
+    for (i, (name, desc)) in sections.export.iter().enumerate() {
+        let error = |kind| ValidationError::new(kind, &format!("export {i}"));
+        context.validate_export(*desc).map_err(error)?;
+        if !export_names.insert(name.as_str()) {
+            return Err(error(ValidationErrorKind::DuplicateExport {
+                name: name.to_string(),
+            }));
+        }
+    }
+
+    if sections.functions.len() != sections.code.len() {
+        return Err(ValidationError::new(
+            ValidationErrorKind::FunctionCountMismatch {
+                functions: sections.functions.len(),
+                bodies: sections.code.len(),
+            },
+            "code section",
+        ));
+    }
+    for (i, code) in sections.code.iter().enumerate() {
+        let function = (context.imported_functions + i) as u32;
+        context
+            .validate_function(function, &code.locals, &code.body)
+            .map_err(|(instruction, kind)| {
+                ValidationError::in_function(kind, function, instruction)
+            })?;
+    }
+
+    Ok(())
+}
+
+/// The index spaces of a module, the `C` of the spec
+struct Context<'a> {
+    types: &'a [FunctionType],
+    /// The type index of every function, imports first
+    functions: Vec<u32>,
+    imported_functions: usize,
+    tables: Vec<&'a TableType>,
+    memories: Vec<&'a Limits>,
+    globals: Vec<&'a GlobalType>,
+    imported_globals: usize,
+    /// The type index of every tag, imports first
+    tags: Vec<u32>,
+    imported_tags: usize,
+    elements: Vec<ReferenceTypes>,
+    data_count: Option<u32>,
+    /// Functions that can be referenced with `ref.func` in function bodies
+    references: HashSet<u32>,
+}
+
+impl<'a> Context<'a> {
+    fn new(module: &'a WasmModule) -> Result<Self, ValidationError> {
+        let sections = &module.sections;
+        let mut context = Context {
+            types: &sections.types,
+            functions: vec![],
+            imported_functions: 0,
+            tables: vec![],
+            memories: vec![],
+            globals: vec![],
+            imported_globals: 0,
+            tags: vec![],
+            imported_tags: 0,
+            elements: sections.element.iter().map(|segment| segment.ty).collect(),
+            data_count: sections.data_count,
+            references: HashSet::new(),
+        };
+
+        for (i, (_, _, desc)) in sections.imports.iter().enumerate() {
+            let error = |kind| ValidationError::new(kind, &format!("import {i}"));
+            match desc {
+                ImportDesc::TypeIdx(index) => {
+                    context.function_type_at(index.index()).map_err(error)?;
+                    context.functions.push(index.index());
+                }
+                ImportDesc::TableType(ty) => {
+                    context.table_type(ty).map_err(error)?;
+                    context.tables.push(ty);
+                }
+                ImportDesc::MemType(limits) => {
+                    validate_memory_type(limits).map_err(error)?;
+                    context.memories.push(limits);
+                }
+                ImportDesc::GlobalType(ty) => {
+                    context.value_type(ty.vtype).map_err(error)?;
+                    context.globals.push(ty);
+                }
+                ImportDesc::TagType(ty) => {
+                    context.validate_tag(ty.ty).map_err(error)?;
+                    context.tags.push(ty.ty);
+                }
+            }
+        }
+        context.imported_functions = context.functions.len();
+        context.imported_globals = context.globals.len();
+        context.imported_tags = context.tags.len();
+
+        for (i, index) in sections.functions.iter().enumerate() {
+            let function = context.imported_functions + i;
+            context
+                .function_type_at(index.index())
+                .map_err(|kind| ValidationError::new(kind, &format!("function {function}")))?;
+            context.functions.push(index.index());
+        }
+        context.tables.extend(&sections.tables);
+        context.memories.extend(&sections.memory);
+        context
+            .globals
+            .extend(sections.global.iter().map(|(ty, _)| ty));
+        context.tags.extend(sections.tags.iter().map(|ty| ty.ty));
+
+        if let Some(declared) = sections.data_count {
+            if declared as usize != sections.data.len() {
+                return Err(ValidationError::new(
+                    ValidationErrorKind::DataCountMismatch {
+                        declared,
+                        actual: sections.data.len(),
+                    },
+                    "data section",
+                ));
+            }
+        }
+
+        // Functions referenced outside of function bodies, see: https://webassembly.github.io/spec/core/valid/modules.html#valid-module
+        let mut references = HashSet::new();
+        let expressions =
+            sections
+                .global
+                .iter()
+                .map(|(_, init)| init)
+                .chain(sections.element.iter().flat_map(|segment| {
+                    let offset = match &segment.mode {
+                        ElementMode::Active { offset, .. } => Some(offset),
+                        _ => None,
+                    };
+                    let init = match &segment.init {
+                        ElementInit::Expressions(init) => init.as_slice(),
+                        ElementInit::Functions(_) => &[],
+                    };
+                    offset.into_iter().chain(init)
+                }));
+        for expr in expressions {
+            references.extend(expr.iter().filter_map(|instruction| match instruction {
+                Instructions::RefFunc(index) => Some(index.index()),
+                _ => None,
+            }));
+        }
+        for segment in &sections.element {
+            if let ElementInit::Functions(functions) = &segment.init {
+                references.extend(functions.iter().map(Indecies::index));
+            }
+        }
+        references.extend(sections.export.iter().filter_map(|(_, desc)| match desc {
+            ExportDesc::FuncIdx(index) => Some(*index),
+            _ => None,
+        }));
+        context.references = references;
+
+        Ok(context)
+    }
+
+    fn function_type_at(&self, index: u32) -> Result<&'a FunctionType, ValidationErrorKind> {
+        lookup(self.types, "type", index)
+    }
+
+    fn function_type(&self, index: u32) -> Result<&'a FunctionType, ValidationErrorKind> {
+        self.function_type_at(*lookup(&self.functions, "function", index)?)
+    }
+
+    /// Checks that the type indices of reference types exist
+    fn value_type(&self, ty: ValueType) -> Result<(), ValidationErrorKind> {
+        match ty {
+            ValueType::RefType(ty) => match ty.heap_type() {
+                HeapType::TypeIdx(index) => self.function_type_at(index).map(|_| ()),
+                _ => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Tables are filled with null when they're created or grown, so their elements have to be nullable
+    fn table_type(&self, ty: &TableType) -> Result<(), ValidationErrorKind> {
+        self.value_type(ValueType::RefType(ty.elem))?;
+        if !ty.elem.is_nullable() {
+            return Err(ValidationErrorKind::NonNullableTable { elem: ty.elem });
+        }
+        validate_table_type(ty)
+    }
+
+    /// Whether values of the `actual` types can be used where the `expected` ones are expected
+    fn matches(&self, actual: &[ValueType], expected: &[ValueType]) -> bool {
+        actual.len() == expected.len()
+            && actual
+                .iter()
+                .zip(expected)
+                .all(|(actual, expected)| actual.matches(*expected, self.types))
+    }
+
+    fn table(&self, index: u32) -> Result<&'a TableType, ValidationErrorKind> {
+        lookup(&self.tables, "table", index).copied()
+    }
+
+    fn memory(&self, index: u32) -> Result<&'a Limits, ValidationErrorKind> {
+        lookup(&self.memories, "memory", index).copied()
+    }
+
+    /// The function type of a tag, whose params are the values an exception with the tag carries
+    fn tag(&self, index: u32) -> Result<&'a FunctionType, ValidationErrorKind> {
+        self.function_type_at(*lookup(&self.tags, "tag", index)?)
+    }
+
+    fn validate_tag(&self, ty: u32) -> Result<(), ValidationErrorKind> {
+        match self.function_type_at(ty)?.result.is_empty() {
+            true => Ok(()),
+            false => Err(ValidationErrorKind::InvalidTagType),
+        }
+    }
+
+    fn element(&self, index: u32) -> Result<ReferenceTypes, ValidationErrorKind> {
+        lookup(&self.elements, "element segment", index).copied()
+    }
+
+    fn data(&self, index: u32) -> Result<(), ValidationErrorKind> {
+        let count = self
+            .data_count
+            .ok_or(ValidationErrorKind::MissingDataCount)?;
+        match index < count {
+            true => Ok(()),
+            false => Err(ValidationErrorKind::UnknownIndex {
+                space: "data segment",
+                index,
+            }),
+        }
+    }
+
+    fn validate_element(&self, segment: &ElementSegment) -> Result<(), ValidationErrorKind> {
+        self.value_type(ValueType::RefType(segment.ty))?;
+        if let ElementMode::Active {
+            table_index,
+            offset,
+        } = &segment.mode
+        {
+            let table = self.table(*table_index)?;
+            if !segment.ty.matches(table.elem, self.types) {
+                return Err(ValidationErrorKind::ReferenceTypeMismatch {
+                    expected: table.elem,
+                    actual: segment.ty,
+                });
+            }
+            let index = ValueType::NumType(table.lim.index_type());
+            self.validate_constant(offset, index, self.globals.len())?;
+        }
+
+        match &segment.init {
+            ElementInit::Functions(functions) => {
+                for function in functions {
+                    self.function_type(function.index())?;
+                }
+            }
+            ElementInit::Expressions(init) => {
+                for expr in init {
+                    self.validate_constant(
+                        expr,
+                        ValueType::RefType(segment.ty),
+                        self.globals.len(),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_data(&self, segment: &DataSegment) -> Result<(), ValidationErrorKind> {
+        if let SegmentMode::Active {
+            memory_index,
+            offset,
+        } = &segment.mode
+        {
+            let address = ValueType::NumType(self.memory(*memory_index)?.index_type());
+            self.validate_constant(offset, address, self.globals.len())?;
+        }
+        Ok(())
+    }
+
+    fn validate_export(&self, desc: ExportDesc) -> Result<(), ValidationErrorKind> {
+        match desc {
+            ExportDesc::FuncIdx(index) => self.function_type(index).map(|_| ()),
+            ExportDesc::TableIdx(index) => self.table(index).map(|_| ()),
+            ExportDesc::MemIdx(index) => self.memory(index).map(|_| ()),
+            ExportDesc::GlobalIdx(index) => lookup(&self.globals, "global", index).map(|_| ()),
+            ExportDesc::TagIdx(index) => self.tag(index).map(|_| ()),
+        }
+    }
+
+    /// Checks that `expr` is a constant expression producing a single `ty`, only referring to the first `globals` globals
+    fn validate_constant(
+        &self,
+        expr: &Expr,
+        ty: ValueType,
+        globals: usize,
+    ) -> Result<(), ValidationErrorKind> {
+        let mut validator = FunctionValidator::new(self, vec![], vec![ty]);
+        validator.constant = Some(globals);
+        validator.validate_body(expr).map_err(|(_, kind)| kind)
+    }
+
+    fn validate_function(
+        &self,
+        function: u32,
+        locals: &Locals,
+        body: &Expr,
+    ) -> Result<(), (usize, ValidationErrorKind)> {
+        let ty = self.function_type(function).map_err(|kind| (0, kind))?;
+
+        // Locals are stored as runs of the same type, so a huge declared count doesn't allocate anything
+        let mut runs = vec![];
+        let mut count = 0u64;
+        for ty in &ty.params {
+            count += 1;
+            runs.push((count, *ty));
+        }
+        for (n, ty) in locals {
+            self.value_type(*ty).map_err(|kind| (0, kind))?;
+            count += *n as u64;
+            runs.push((count, *ty));
+        }
+        if count > u32::MAX as u64 {
+            return Err((0, ValidationErrorKind::TooManyLocals { limit: u32::MAX }));
+        }
+
+        let mut validator = FunctionValidator::new(self, runs, ty.result.clone());
+        validator.params = ty.params.len() as u32;
+        validator.validate_body(body)
+    }
+}
+
+fn lookup<'t, T>(
+    items: &'t [T],
+    space: &'static str,
+    index: u32,
+) -> Result<&'t T, ValidationErrorKind> {
+    items
+        .get(index as usize)
+        .ok_or(ValidationErrorKind::UnknownIndex { space, index })
+}
+
+fn validate_limits(limits: &Limits) -> Result<(), ValidationErrorKind> {
+    match limits.maximum() {
+        Some(max) if max < limits.minimum() => Err(ValidationErrorKind::InvalidLimits {
+            min: limits.minimum(),
+            max,
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn validate_table_type(ty: &TableType) -> Result<(), ValidationErrorKind> {
+    if ty.lim.is_shared() {
+        return Err(ValidationErrorKind::SharedTable);
+    }
+    validate_limits(&ty.lim)
+}
+
+fn validate_memory_type(limits: &Limits) -> Result<(), ValidationErrorKind> {
+    validate_limits(limits)?;
+    let limit = match limits.index_type() {
+        NumberTypes::i64 => MAX_MEMORY64_PAGES,
+        _ => MAX_MEMORY_PAGES.into(),
+    };
+    for value in [Some(limits.minimum()), limits.maximum()]
+        .into_iter()
+        .flatten()
+    {
+        if value > limit {
+            return Err(ValidationErrorKind::LimitExceeded {
+                construct: "Memory size",
+                value,
+                limit,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameKind {
+    Block,
+    Loop,
+    If,
+    Else,
+}
+
+/// A control frame of the validation algorithm
+struct Frame {
+    kind: FrameKind,
+    params: Vec<ValueType>,
+    results: Vec<ValueType>,
+    /// The height of the operand stack when the frame was entered
+    height: usize,
+    /// The number of initialized locals when the frame was entered, locals set inside of it are
+    /// uninitialized again after it
+    inits: usize,
+    /// Set after an unconditional branch, the rest of the frame is type checked against an unknown stack
+    unreachable: bool,
+}
+
+impl Frame {
+    /// The types a branch to this frame has to provide
+    fn label_types(&self) -> &[ValueType] {
+        match self.kind {
+            FrameKind::Loop => &self.params,
+            _ => &self.results,
+        }
+    }
+}
+
+/// Type checks a single function body or constant expression.
+/// `None` operands are of unknown type, they're produced by popping from an unreachable frame
+struct FunctionValidator<'c, 'a> {
+    context: &'c Context<'a>,
+    /// The locals as runs of the same type, each tagged with the index one past its end
+    locals: Vec<(u64, ValueType)>,
+    results: Vec<ValueType>,
+    /// The number of params at the start of the locals, which are always initialized
+    params: u32,
+    /// The non-defaultable locals that have been set, see:
+    /// https://webassembly.github.io/function-references/core/valid/conventions.html#local-types
+    inits: Vec<u32>,
+    operands: Vec<Option<ValueType>>,
+    frames: Vec<Frame>,
+    /// The number of globals a constant expression can refer to, `None` when validating a function body
+    constant: Option<usize>,
+}
+
+impl<'c, 'a> FunctionValidator<'c, 'a> {
+    fn new(
+        context: &'c Context<'a>,
+        locals: Vec<(u64, ValueType)>,
+        results: Vec<ValueType>,
+    ) -> Self {
+        Self {
+            context,
+            locals,
+            results,
+            params: 0,
+            inits: vec![],
+            operands: vec![],
+            frames: vec![],
+            constant: None,
+        }
+    }
+
+    /// Validates an instruction sequence ending with the implicit `end` of the function, returning
+    /// the index of the offending instruction along with the error
+    fn validate_body(&mut self, body: &Expr) -> Result<(), (usize, ValidationErrorKind)> {
+        /// An instruction sequence that's being validated, nested blocks are tracked on an explicit
+        /// stack so deeply nested bodies can't overflow the call stack
+        struct Sequence<'e> {
+            rest: std::slice::Iter<'e, Instructions>,
+            /// The `else` branch of an `if`, validated after the `then` branch
+            else_: Option<&'e Expr>,
+        }
+
+        let mut sequences = vec![Sequence {
+            rest: body.iter(),
+            else_: None,
+        }];
+        self.push_frame(FrameKind::Block, vec![], self.results.clone());
+
+        let mut index = 0;
+        while let Some(sequence) = sequences.last_mut() {
+            let Some(instruction) = sequence.rest.next() else {
+                // The end of a sequence is either an `else` or an `end` opcode
+                if let Some(else_) = sequence.else_.take() {
+                    let frame = self.pop_frame().map_err(|kind| (index, kind))?;
+                    self.push_frame(FrameKind::Else, frame.params, frame.results);
+                    sequence.rest = else_.iter();
+                } else {
+                    let frame = self.pop_frame().map_err(|kind| (index, kind))?;
+                    if frame.kind == FrameKind::If && frame.params != frame.results {
+                        return Err((index, ValidationErrorKind::MissingElse));
+                    }
+                    self.push_all(&frame.results);
+                    sequences.pop();
+                }
+                index += 1;
+                continue;
+            };
+
+            self.validate_instruction(instruction)
+                .map_err(|kind| (index, kind))?;
+            index += 1;
+
+            match instruction {
+                Instructions::Block { body, .. }
+                | Instructions::Loop { body, .. }
+                | Instructions::TryTable { body, .. } => sequences.push(Sequence {
+                    rest: body.iter(),
+                    else_: None,
+                }),
+                Instructions::If { then, else_, .. } => sequences.push(Sequence {
+                    rest: then.iter(),
+                    else_: (!else_.is_empty()).then_some(else_),
+                }),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, ty: Option<ValueType>) {
+        self.operands.push(ty);
+    }
+
+    fn push_all(&mut self, types: &[ValueType]) {
+        self.operands.extend(types.iter().copied().map(Some));
+    }
+
+    fn pop(&mut self) -> Result<Option<ValueType>, ValidationErrorKind> {
+        let frame = self
+            .frames
+            .last()
+            .expect("the function frame is never popped early");
+        if self.operands.len() == frame.height {
+            return match frame.unreachable {
+                true => Ok(None),
+                false => Err(ValidationErrorKind::MissingOperand),
+            };
+        }
+        Ok(self.operands.pop().flatten())
+    }
+
+    fn pop_expect(&mut self, expected: ValueType) -> Result<(), ValidationErrorKind> {
+        match self.pop() {
+            Ok(Some(actual)) if !actual.matches(expected, self.context.types) => {
+                Err(ValidationErrorKind::TypeMismatch { expected, actual })
+            }
+            Ok(_) => Ok(()),
+            Err(_) => Err(ValidationErrorKind::StackUnderflow { expected }),
+        }
+    }
+
+    fn pop_all(&mut self, types: &[ValueType]) -> Result<(), ValidationErrorKind> {
+        for ty in types.iter().rev() {
+            self.pop_expect(*ty)?;
+        }
+        Ok(())
+    }
+
+    /// Pops `params` and pushes `results`
+    fn operation(
+        &mut self,
+        params: &[ValueType],
+        results: &[ValueType],
+    ) -> Result<(), ValidationErrorKind> {
+        self.pop_all(params)?;
+        self.push_all(results);
+        Ok(())
+    }
+
+    fn push_frame(&mut self, kind: FrameKind, params: Vec<ValueType>, results: Vec<ValueType>) {
+        self.frames.push(Frame {
+            kind,
+            height: self.operands.len(),
+            inits: self.inits.len(),
+            unreachable: false,
+            results,
+            params: params.clone(),
+        });
+        self.push_all(&params);
+    }
+
+    fn pop_frame(&mut self) -> Result<Frame, ValidationErrorKind> {
+        let results = self
+            .frames
+            .last()
+            .expect("frames are balanced")
+            .results
+            .clone();
+        self.pop_all(&results)?;
+
+        let frame = self.frames.pop().expect("frames are balanced");
+        self.inits.truncate(frame.inits);
+        if self.operands.len() != frame.height {
+            return Err(ValidationErrorKind::UnexpectedOperands {
+                count: self.operands.len() - frame.height,
+            });
+        }
+        Ok(frame)
+    }
+
+    fn set_unreachable(&mut self) {
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("the function frame is never popped early");
+        self.operands.truncate(frame.height);
+        frame.unreachable = true;
+    }
+
+    fn label(&self, label: &Indecies) -> Result<&Frame, ValidationErrorKind> {
+        let depth = label.index() as usize;
+        self.frames
+            .len()
+            .checked_sub(depth + 1)
+            .map(|i| &self.frames[i])
+            .ok_or(ValidationErrorKind::UnknownIndex {
+                space: "label",
+                index: label.index(),
+            })
+    }
+
+    fn local(&self, index: &Indecies) -> Result<ValueType, ValidationErrorKind> {
+        let index = index.index();
+        let run = self.locals.partition_point(|(end, _)| *end <= index as u64);
+        self.locals
+            .get(run)
+            .map(|(_, ty)| *ty)
+            .ok_or(ValidationErrorKind::UnknownIndex {
+                space: "local",
+                index,
+            })
+    }
+
+    /// Pops a reference, returning its type unless it's unknown
+    fn pop_reference(&mut self) -> Result<Option<ReferenceTypes>, ValidationErrorKind> {
+        match self.pop()? {
+            Some(ValueType::RefType(ty)) => Ok(Some(ty)),
+            Some(actual) => Err(ValidationErrorKind::InvalidOperand {
+                expected: "a reference type",
+                actual,
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks that a local can be read, locals without a default value have to be set first
+    fn local_get(&self, index: &Indecies) -> Result<ValueType, ValidationErrorKind> {
+        let ty = self.local(index)?;
+        let index = index.index();
+        if !ty.is_defaultable() && index >= self.params && !self.inits.contains(&index) {
+            return Err(ValidationErrorKind::UninitializedLocal { index });
+        }
+        Ok(ty)
+    }
+
+    /// Marks a local as set
+    fn local_set(&mut self, index: &Indecies) -> Result<ValueType, ValidationErrorKind> {
+        let ty = self.local(index)?;
+        if !ty.is_defaultable() && !self.inits.contains(&index.index()) {
+            self.inits.push(index.index());
+        }
+        Ok(ty)
+    }
+
+    fn global(&self, index: &Indecies) -> Result<&'a GlobalType, ValidationErrorKind> {
+        let globals = match self.constant {
+            Some(visible) => &self.context.globals[..visible],
+            None => &self.context.globals,
+        };
+        lookup(globals, "global", index.index()).copied()
+    }
+
+    fn block_types(
+        &self,
+        ty: &BlockType,
+    ) -> Result<(Vec<ValueType>, Vec<ValueType>), ValidationErrorKind> {
+        Ok(match ty {
+            BlockType::Empty => (vec![], vec![]),
+            BlockType::Value(ty) => {
+                self.context.value_type(*ty)?;
+                (vec![], vec![*ty])
+            }
+            BlockType::TypeIndex(index) => {
+                let ty = self.context.function_type_at(*index)?;
+                (ty.params.clone(), ty.result.clone())
+            }
+        })
+    }
+
+    /// Checks that the values a catch clause passes to its label are the ones the label takes
+    fn validate_catch(&self, catch: &Catch) -> Result<(), ValidationErrorKind> {
+        let mut values = match catch.tag() {
+            Some(tag) => self.context.tag(tag.index())?.params.clone(),
+            None => vec![],
+        };
+        if catch.is_ref() {
+            values.push(EXNREF);
+        }
+
+        let label_types = self.label(catch.label())?.label_types();
+        if !self.context.matches(&values, label_types) {
+            return Err(ValidationErrorKind::CatchTypeMismatch {
+                expected: label_types.to_vec(),
+                actual: values,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the table of `call_indirect` or `return_call_indirect`, pops the index into it
+    /// and returns the type of the called function
+    fn indirect_call(
+        &mut self,
+        ty: &Indecies,
+        table: &Indecies,
+    ) -> Result<&'a FunctionType, ValidationErrorKind> {
+        let table = self.context.table(table.index())?;
+        if !table
+            .elem
+            .matches(ReferenceTypes::funcref, self.context.types)
+        {
+            return Err(ValidationErrorKind::ReferenceTypeMismatch {
+                expected: ReferenceTypes::funcref,
+                actual: table.elem,
+            });
+        }
+        let ty = self.context.function_type_at(ty.index())?;
+        self.pop_expect(ValueType::NumType(table.lim.index_type()))?;
+        Ok(ty)
+    }
+
+    /// Pops the function reference of `call_ref` or `return_call_ref`, which has to be of the type
+    /// at `ty`, and returns that type
+    fn call_ref(&mut self, ty: &Indecies) -> Result<&'a FunctionType, ValidationErrorKind> {
+        let function = self.context.function_type_at(ty.index())?;
+        let reference = ReferenceTypes::new(true, HeapType::TypeIdx(ty.index()));
+        self.pop_expect(ValueType::RefType(reference))?;
+        Ok(function)
+    }
+
+    /// The callee of a tail call returns in place of the current function, so it has to have the same results
+    fn tail_call(&mut self, ty: &FunctionType) -> Result<(), ValidationErrorKind> {
+        if !self.context.matches(&ty.result, &self.results) {
+            return Err(ValidationErrorKind::TailCallTypeMismatch {
+                expected: self.results.clone(),
+                actual: ty.result.clone(),
+            });
+        }
+        self.pop_all(&ty.params)?;
+        self.set_unreachable();
+        Ok(())
+    }
+
+    /// The type of the addresses into memory 0, which has to exist
+    fn address_type(&self) -> Result<ValueType, ValidationErrorKind> {
+        Ok(ValueType::NumType(self.context.memory(0)?.index_type()))
+    }
+
+    /// The type of the indices into a table
+    fn table_index_type(&self, table: &Indecies) -> Result<ValueType, ValidationErrorKind> {
+        Ok(ValueType::NumType(
+            self.context.table(table.index())?.lim.index_type(),
+        ))
+    }
+
+    /// Checks that memory 0 can be accessed with `memarg`'s offset and returns the type of its addresses
+    fn memory_offset(&self, memarg: &MemArg) -> Result<ValueType, ValidationErrorKind> {
+        let address = self.address_type()?;
+        if address == I32 && memarg.offset > u32::MAX.into() {
+            return Err(ValidationErrorKind::LimitExceeded {
+                construct: "Memory offset",
+                value: memarg.offset,
+                limit: u32::MAX.into(),
+            });
+        }
+        Ok(address)
+    }
+
+    /// Checks that memory 0 exists and `memarg` isn't aligned beyond `natural`, which is an exponent of 2.
+    /// Returns the type of the address operand
+    fn memory_access(
+        &self,
+        memarg: &MemArg,
+        natural: u32,
+    ) -> Result<ValueType, ValidationErrorKind> {
+        let address = self.memory_offset(memarg)?;
+        if memarg.align > natural {
+            return Err(ValidationErrorKind::InvalidAlignment {
+                align: memarg.align,
+                natural,
+            });
+        }
+        Ok(address)
+    }
+
+    /// Checks that memory 0 exists and `memarg` is aligned exactly to `natural`, as atomic accesses have to be.
+    /// Returns the type of the address operand
+    fn atomic_access(
+        &self,
+        memarg: &MemArg,
+        natural: u32,
+    ) -> Result<ValueType, ValidationErrorKind> {
+        let address = self.memory_offset(memarg)?;
+        if memarg.align != natural {
+            return Err(ValidationErrorKind::InvalidAtomicAlignment {
+                align: memarg.align,
+                natural,
+            });
+        }
+        Ok(address)
+    }
+
+    fn validate_instruction(
+        &mut self,
+        instruction: &Instructions,
+    ) -> Result<(), ValidationErrorKind> {
+        use Instructions::*;
+
+        if self.constant.is_some() {
+            match instruction {
+                i32_const(_) | i64_const(_) | f32_const(_) | f64_const(_) | v128_const(_)
+                | RefNull(_) | RefFunc(_) => {}
+                GlobalGet(index) if self.global(index)?.mutability == Mutability::Const => {}
+                _ => return Err(ValidationErrorKind::NonConstantInstruction),
+            }
+        }
+
+        if let Some((lane, lanes)) = lane_index(instruction) {
+            if lane >= lanes {
+                return Err(ValidationErrorKind::InvalidLaneIndex { lane, lanes });
+            }
+        }
+        if let Some((params, results)) = numeric_signature(instruction) {
+            return self.operation(params, results);
+        }
+
+        match instruction {
+            // Control Instructions
+            Unreachable => self.set_unreachable(),
+            Nop => {}
+            Block { ty, .. } | Loop { ty, .. } => {
+                let (params, results) = self.block_types(ty)?;
+                self.pop_all(&params)?;
+                let kind = match instruction {
+                    Loop { .. } => FrameKind::Loop,
+                    _ => FrameKind::Block,
+                };
+                self.push_frame(kind, params, results);
+            }
+            If { ty, .. } => {
+                let (params, results) = self.block_types(ty)?;
+                self.pop_expect(I32)?;
+                self.pop_all(&params)?;
+                self.push_frame(FrameKind::If, params, results);
+            }
+            Br(label) => {
+                let types = self.label(label)?.label_types().to_vec();
+                self.pop_all(&types)?;
+                self.set_unreachable();
+            }
+            BrIf(label) => {
+                let types = self.label(label)?.label_types().to_vec();
+                self.pop_expect(I32)?;
+                self.operation(&types, &types)?;
+            }
+            BrTable(labels, default) => {
+                self.pop_expect(I32)?;
+                let types = self.label(default)?.label_types().to_vec();
+                for label in labels {
+                    let label_types = self.label(label)?.label_types().to_vec();
+                    if label_types.len() != types.len() {
+                        return Err(ValidationErrorKind::LabelArityMismatch {
+                            expected: types.len(),
+                            actual: label_types.len(),
+                        });
+                    }
+                    // Operands of unknown type have to be checked against every label without being refined
+                    let operands = self.operands.clone();
+                    self.pop_all(&label_types)?;
+                    self.operands = operands;
+                }
+                self.pop_all(&types)?;
+                self.set_unreachable();
+            }
+            Return => {
+                self.pop_all(&self.results.clone())?;
+                self.set_unreachable();
+            }
+            Call(function) => {
+                let ty = self.context.function_type(function.index())?;
+                self.operation(&ty.params, &ty.result)?;
+            }
+            CallIndirect(ty, table) => {
+                let ty = self.indirect_call(ty, table)?;
+                self.operation(&ty.params, &ty.result)?;
+            }
+            ReturnCall(function) => {
+                let ty = self.context.function_type(function.index())?;
+                self.tail_call(ty)?;
+            }
+            ReturnCallIndirect(ty, table) => {
+                let ty = self.indirect_call(ty, table)?;
+                self.tail_call(ty)?;
+            }
+            CallRef(ty) => {
+                let ty = self.call_ref(ty)?;
+                self.operation(&ty.params, &ty.result)?;
+            }
+            ReturnCallRef(ty) => {
+                let ty = self.call_ref(ty)?;
+                self.tail_call(ty)?;
+            }
+            BrOnNull(label) => {
+                let ty = self.pop_reference()?;
+                let types = self.label(label)?.label_types().to_vec();
+                self.operation(&types, &types)?;
+                self.push(ty.map(|ty| ValueType::RefType(ty.as_non_null())));
+            }
+            BrOnNonNull(label) => {
+                // The label takes the operands and the reference, which isn't null when branching
+                let ty = self.pop_reference()?;
+                let types = self.label(label)?.label_types().to_vec();
+                let Some((ValueType::RefType(_), rest)) = types.split_last() else {
+                    return Err(ValidationErrorKind::InvalidBranchOnNonNull {
+                        label: label.index(),
+                    });
+                };
+                self.push(ty.map(|ty| ValueType::RefType(ty.as_non_null())));
+                self.operation(&types, rest)?;
+            }
+
+            // Exception Handling Instructions
+            Throw(tag) => {
+                let ty = self.context.tag(tag.index())?;
+                self.pop_all(&ty.params)?;
+                self.set_unreachable();
+            }
+            ThrowRef => {
+                self.pop_expect(EXNREF)?;
+                self.set_unreachable();
+            }
+            TryTable { ty, catches, .. } => {
+                // The labels of the catch clauses are outside of the try_table
+                for catch in catches {
+                    self.validate_catch(catch)?;
+                }
+                let (params, results) = self.block_types(ty)?;
+                self.pop_all(&params)?;
+                self.push_frame(FrameKind::Block, params, results);
+            }
+
+            // Reference Instructions
+            RefNull(ty) => {
+                self.context.value_type(ValueType::RefType(*ty))?;
+                self.push(Some(ValueType::RefType(*ty)));
+            }
+            RefIsNull => {
+                self.pop_reference()?;
+                self.push(Some(I32));
+            }
+            RefAsNonNull => {
+                let ty = self.pop_reference()?;
+                self.push(ty.map(|ty| ValueType::RefType(ty.as_non_null())));
+            }
+            RefFunc(function) => {
+                self.context.function_type(function.index())?;
+                if self.constant.is_none() && !self.context.references.contains(&function.index()) {
+                    return Err(ValidationErrorKind::UndeclaredFunctionReference {
+                        index: function.index(),
+                    });
+                }
+                // The reference has the exact type of the function
+                let ty = self.context.functions[function.index() as usize];
+                self.push(Some(ValueType::RefType(ReferenceTypes::new(
+                    false,
+                    HeapType::TypeIdx(ty),
+                ))));
+            }
+
+            // Parametric Instructions
+            Drop => {
+                self.pop()?;
+            }
+            Select => {
+                self.pop_expect(I32)?;
+                let first = self.pop()?;
+                let second = self.pop()?;
+                for ty in [first, second].into_iter().flatten() {
+                    if matches!(ty, ValueType::RefType(_)) {
+                        return Err(ValidationErrorKind::InvalidOperand {
+                            expected: "a numeric or vector type",
+                            actual: ty,
+                        });
+                    }
+                }
+                if let (Some(expected), Some(actual)) = (first, second) {
+                    if expected != actual {
+                        return Err(ValidationErrorKind::TypeMismatch { expected, actual });
+                    }
+                }
+                self.push(first.or(second));
+            }
+            SelectMultiple(types) => {
+                let [ty] = types.as_slice() else {
+                    return Err(ValidationErrorKind::SelectArity { count: types.len() });
+                };
+                self.context.value_type(*ty)?;
+                self.pop_expect(I32)?;
+                self.operation(&[*ty, *ty], &[*ty])?;
+            }
+
+            // Variable Instructions
+            LocalGet(index) => {
+                let ty = self.local_get(index)?;
+                self.push(Some(ty));
+            }
+            LocalSet(index) => {
+                let ty = self.local_set(index)?;
+                self.pop_expect(ty)?;
+            }
+            LocalTee(index) => {
+                let ty = self.local_set(index)?;
+                self.operation(&[ty], &[ty])?;
+            }
+            GlobalGet(index) => {
+                let global = self.global(index)?;
+                self.push(Some(global.vtype));
+            }
+            GlobalSet(index) => {
+                let global = self.global(index)?;
+                if global.mutability != Mutability::Var {
+                    return Err(ValidationErrorKind::ImmutableGlobal {
+                        index: index.index(),
+                    });
+                }
+                self.pop_expect(global.vtype)?;
+            }
+
+            // Table Instructions
+            TableGet(table) => {
+                let ty = ValueType::RefType(self.context.table(table.index())?.elem);
+                self.operation(&[self.table_index_type(table)?], &[ty])?;
+            }
+            TableSet(table) => {
+                let ty = ValueType::RefType(self.context.table(table.index())?.elem);
+                self.operation(&[self.table_index_type(table)?, ty], &[])?;
+            }
+            TableInit(element, table) => {
+                let expected = self.context.table(table.index())?.elem;
+                let actual = self.context.element(element.index())?;
+                if !actual.matches(expected, self.context.types) {
+                    return Err(ValidationErrorKind::ReferenceTypeMismatch { expected, actual });
+                }
+                self.operation(&[self.table_index_type(table)?, I32, I32], &[])?;
+            }
+            ElemDrop(element) => {
+                self.context.element(element.index())?;
+            }
+            TableCopy(destination, source) => {
+                let expected = self.context.table(destination.index())?.elem;
+                let actual = self.context.table(source.index())?.elem;
+                if !actual.matches(expected, self.context.types) {
+                    return Err(ValidationErrorKind::ReferenceTypeMismatch { expected, actual });
+                }
+                // The length has to fit into both tables, so it's only 64 bit if both of them are
+                let (destination, source) = (
+                    self.table_index_type(destination)?,
+                    self.table_index_type(source)?,
+                );
+                let length = if destination == I64 && source == I64 {
+                    I64
+                } else {
+                    I32
+                };
+                self.operation(&[destination, source, length], &[])?;
+            }
+            TableGrow(table) => {
+                let ty = ValueType::RefType(self.context.table(table.index())?.elem);
+                let index = self.table_index_type(table)?;
+                self.operation(&[ty, index], &[index])?;
+            }
+            TableSize(table) => {
+                let index = self.table_index_type(table)?;
+                self.push(Some(index));
+            }
+            TableFill(table) => {
+                let ty = ValueType::RefType(self.context.table(table.index())?.elem);
+                let index = self.table_index_type(table)?;
+                self.operation(&[index, ty, index], &[])?;
+            }
+
+            // Memory Instructions
+            i32_load(memarg) => self.load(memarg, 2, I32)?,
+            i64_load(memarg) => self.load(memarg, 3, I64)?,
+            f32_load(memarg) => self.load(memarg, 2, F32)?,
+            f64_load(memarg) => self.load(memarg, 3, F64)?,
+            i32_load_8s(memarg) | i32_load_8u(memarg) => self.load(memarg, 0, I32)?,
+            i32_load_16s(memarg) | i32_load_16u(memarg) => self.load(memarg, 1, I32)?,
+            i64_load_8s(memarg) | i64_load_8u(memarg) => self.load(memarg, 0, I64)?,
+            i64_load_16s(memarg) | i64_load_16u(memarg) => self.load(memarg, 1, I64)?,
+            i64_load_32s(memarg) | i64_load_32u(memarg) => self.load(memarg, 2, I64)?,
+            i32_store(memarg) => self.store(memarg, 2, I32)?,
+            i64_store(memarg) => self.store(memarg, 3, I64)?,
+            f32_store(memarg) => self.store(memarg, 2, F32)?,
+            f64_store(memarg) => self.store(memarg, 3, F64)?,
+            i32_store_8(memarg) => self.store(memarg, 0, I32)?,
+            i32_store_16(memarg) => self.store(memarg, 1, I32)?,
+            i64_store_8(memarg) => self.store(memarg, 0, I64)?,
+            i64_store_16(memarg) => self.store(memarg, 1, I64)?,
+            i64_store_32(memarg) => self.store(memarg, 2, I64)?,
+            MemorySize => {
+                let address = self.address_type()?;
+                self.push(Some(address));
+            }
+            MemoryGrow => {
+                let address = self.address_type()?;
+                self.operation(&[address], &[address])?;
+            }
+            MemoryInit(data) => {
+                let address = self.address_type()?;
+                self.context.data(data.index())?;
+                self.operation(&[address, I32, I32], &[])?;
+            }
+            DataDrop(data) => self.context.data(data.index())?,
+            MemoryCopy => {
+                let address = self.address_type()?;
+                self.operation(&[address, address, address], &[])?;
+            }
+            MemoryFill => {
+                let address = self.address_type()?;
+                self.operation(&[address, I32, address], &[])?;
+            }
+
+            // Vector Memory Instructions
+            v128_load(memarg) => self.load(memarg, 4, V128)?,
+            v128_load8x8_s(memarg)
+            | v128_load8x8_u(memarg)
+            | v128_load16x4_s(memarg)
+            | v128_load16x4_u(memarg)
+            | v128_load32x2_s(memarg)
+            | v128_load32x2_u(memarg)
+            | v128_load64_splat(memarg)
+            | v128_load64_zero(memarg) => self.load(memarg, 3, V128)?,
+            v128_load8_splat(memarg) => self.load(memarg, 0, V128)?,
+            v128_load16_splat(memarg) => self.load(memarg, 1, V128)?,
+            v128_load32_splat(memarg) | v128_load32_zero(memarg) => self.load(memarg, 2, V128)?,
+            v128_store(memarg) => self.store(memarg, 4, V128)?,
+            v128_load8_lane(memarg, _)
+            | v128_load16_lane(memarg, _)
+            | v128_load32_lane(memarg, _)
+            | v128_load64_lane(memarg, _) => {
+                let address = self.memory_access(memarg, lane_width(instruction))?;
+                self.operation(&[address, V128], &[V128])?;
+            }
+            v128_store8_lane(memarg, _)
+            | v128_store16_lane(memarg, _)
+            | v128_store32_lane(memarg, _)
+            | v128_store64_lane(memarg, _) => {
+                let address = self.memory_access(memarg, lane_width(instruction))?;
+                self.operation(&[address, V128], &[])?;
+            }
+
+            // Atomic Memory Instructions
+            memory_atomic_notify(memarg) => self.atomic(memarg, 2, &[I32], &[I32])?,
+            memory_atomic_wait32(memarg) => self.atomic(memarg, 2, &[I32, I64], &[I32])?,
+            memory_atomic_wait64(memarg) => self.atomic(memarg, 3, &[I64, I64], &[I32])?,
+            atomic_fence => {}
+            i32_atomic_load(memarg) => self.atomic(memarg, 2, &[], &[I32])?,
+            i64_atomic_load(memarg) => self.atomic(memarg, 3, &[], &[I64])?,
+            i32_atomic_load8_u(memarg) => self.atomic(memarg, 0, &[], &[I32])?,
+            i32_atomic_load16_u(memarg) => self.atomic(memarg, 1, &[], &[I32])?,
+            i64_atomic_load8_u(memarg) => self.atomic(memarg, 0, &[], &[I64])?,
+            i64_atomic_load16_u(memarg) => self.atomic(memarg, 1, &[], &[I64])?,
+            i64_atomic_load32_u(memarg) => self.atomic(memarg, 2, &[], &[I64])?,
+            i32_atomic_store(memarg) => self.atomic(memarg, 2, &[I32], &[])?,
+            i64_atomic_store(memarg) => self.atomic(memarg, 3, &[I64], &[])?,
+            i32_atomic_store8(memarg) => self.atomic(memarg, 0, &[I32], &[])?,
+            i32_atomic_store16(memarg) => self.atomic(memarg, 1, &[I32], &[])?,
+            i64_atomic_store8(memarg) => self.atomic(memarg, 0, &[I64], &[])?,
+            i64_atomic_store16(memarg) => self.atomic(memarg, 1, &[I64], &[])?,
+            i64_atomic_store32(memarg) => self.atomic(memarg, 2, &[I64], &[])?,
+            i32_atomic_rmw_add(memarg)
+            | i32_atomic_rmw_sub(memarg)
+            | i32_atomic_rmw_and(memarg)
+            | i32_atomic_rmw_or(memarg)
+            | i32_atomic_rmw_xor(memarg)
+            | i32_atomic_rmw_xchg(memarg) => self.atomic(memarg, 2, &[I32], &[I32])?,
+            i64_atomic_rmw_add(memarg)
+            | i64_atomic_rmw_sub(memarg)
+            | i64_atomic_rmw_and(memarg)
+            | i64_atomic_rmw_or(memarg)
+            | i64_atomic_rmw_xor(memarg)
+            | i64_atomic_rmw_xchg(memarg) => self.atomic(memarg, 3, &[I64], &[I64])?,
+            i32_atomic_rmw8_add_u(memarg)
+            | i32_atomic_rmw8_sub_u(memarg)
+            | i32_atomic_rmw8_and_u(memarg)
+            | i32_atomic_rmw8_or_u(memarg)
+            | i32_atomic_rmw8_xor_u(memarg)
+            | i32_atomic_rmw8_xchg_u(memarg) => self.atomic(memarg, 0, &[I32], &[I32])?,
+            i32_atomic_rmw16_add_u(memarg)
+            | i32_atomic_rmw16_sub_u(memarg)
+            | i32_atomic_rmw16_and_u(memarg)
+            | i32_atomic_rmw16_or_u(memarg)
+            | i32_atomic_rmw16_xor_u(memarg)
+            | i32_atomic_rmw16_xchg_u(memarg) => self.atomic(memarg, 1, &[I32], &[I32])?,
+            i64_atomic_rmw8_add_u(memarg)
+            | i64_atomic_rmw8_sub_u(memarg)
+            | i64_atomic_rmw8_and_u(memarg)
+            | i64_atomic_rmw8_or_u(memarg)
+            | i64_atomic_rmw8_xor_u(memarg)
+            | i64_atomic_rmw8_xchg_u(memarg) => self.atomic(memarg, 0, &[I64], &[I64])?,
+            i64_atomic_rmw16_add_u(memarg)
+            | i64_atomic_rmw16_sub_u(memarg)
+            | i64_atomic_rmw16_and_u(memarg)
+            | i64_atomic_rmw16_or_u(memarg)
+            | i64_atomic_rmw16_xor_u(memarg)
+            | i64_atomic_rmw16_xchg_u(memarg) => self.atomic(memarg, 1, &[I64], &[I64])?,
+            i64_atomic_rmw32_add_u(memarg)
+            | i64_atomic_rmw32_sub_u(memarg)
+            | i64_atomic_rmw32_and_u(memarg)
+            | i64_atomic_rmw32_or_u(memarg)
+            | i64_atomic_rmw32_xor_u(memarg)
+            | i64_atomic_rmw32_xchg_u(memarg) => self.atomic(memarg, 2, &[I64], &[I64])?,
+            i32_atomic_rmw_cmpxchg(memarg) => self.atomic(memarg, 2, &[I32, I32], &[I32])?,
+            i64_atomic_rmw_cmpxchg(memarg) => self.atomic(memarg, 3, &[I64, I64], &[I64])?,
+            i32_atomic_rmw8_cmpxchg_u(memarg) => self.atomic(memarg, 0, &[I32, I32], &[I32])?,
+            i32_atomic_rmw16_cmpxchg_u(memarg) => self.atomic(memarg, 1, &[I32, I32], &[I32])?,
+            i64_atomic_rmw8_cmpxchg_u(memarg) => self.atomic(memarg, 0, &[I64, I64], &[I64])?,
+            i64_atomic_rmw16_cmpxchg_u(memarg) => self.atomic(memarg, 1, &[I64, I64], &[I64])?,
+            i64_atomic_rmw32_cmpxchg_u(memarg) => self.atomic(memarg, 2, &[I64, I64], &[I64])?,
+
+            _ => unreachable!("numeric instructions are handled by numeric_signature"),
+        }
+        Ok(())
+    }
+
+    fn load(
+        &mut self,
+        memarg: &MemArg,
+        natural: u32,
+        ty: ValueType,
+    ) -> Result<(), ValidationErrorKind> {
+        let address = self.memory_access(memarg, natural)?;
+        self.operation(&[address], &[ty])
+    }
+
+    fn store(
+        &mut self,
+        memarg: &MemArg,
+        natural: u32,
+        ty: ValueType,
+    ) -> Result<(), ValidationErrorKind> {
+        let address = self.memory_access(memarg, natural)?;
+        self.operation(&[address, ty], &[])
+    }
+
+    /// An atomic access taking an address followed by `operands`
+    fn atomic(
+        &mut self,
+        memarg: &MemArg,
+        natural: u32,
+        operands: &[ValueType],
+        results: &[ValueType],
+    ) -> Result<(), ValidationErrorKind> {
+        let address = self.atomic_access(memarg, natural)?;
+        let params: Vec<_> = [address]
+            .into_iter()
+            .chain(operands.iter().copied())
+            .collect();
+        self.operation(&params, results)
+    }
+}
+
+/// The parameter and result types of the numeric instructions, which don't depend on the module
+pub(crate) fn numeric_signature(
+    instruction: &Instructions,
+) -> Option<(&'static [ValueType], &'static [ValueType])> {
+    use Instructions::*;
+
+    Some(match instruction {
+        i32_const(_) => (&[], &[I32]),
+        i64_const(_) => (&[], &[I64]),
+        f32_const(_) => (&[], &[F32]),
+        f64_const(_) => (&[], &[F64]),
+
+        i32_eqz => (&[I32], &[I32]),
+        i32_eq | i32_ne | i32_lt_s | i32_lt_u | i32_gt_s | i32_gt_u | i32_le_s | i32_le_u
+        | i32_ge_s | i32_ge_u => (&[I32, I32], &[I32]),
+        i64_eqz => (&[I64], &[I32]),
+        i64_eq | i64_ne | i64_lt_s | i64_lt_u | i64_gt_s | i64_gt_u | i64_le_s | i64_le_u
+        | i64_ge_s | i64_ge_u => (&[I64, I64], &[I32]),
+        f32_eq | f32_ne | f32_lt | f32_gt | f32_le | f32_ge => (&[F32, F32], &[I32]),
+        f64_eq | f64_ne | f64_lt | f64_gt | f64_le | f64_ge => (&[F64, F64], &[I32]),
+
+        i32_clz | i32_ctz | i32_popcnt | i32_extend8_s | i32_extend16_s => (&[I32], &[I32]),
+        i32_add | i32_sub | i32_mul | i32_div_s | i32_div_u | i32_rem_s | i32_rem_u | i32_and
+        | i32_or | i32_xor | i32_shl | i32_shr_s | i32_shr_u | i32_rotl | i32_rotr => {
+            (&[I32, I32], &[I32])
+        }
+        i64_clz | i64_ctz | i64_popcnt | i64_extend8_s | i64_extend16_s | i64_extend32_s => {
+            (&[I64], &[I64])
+        }
+        i64_add | i64_sub | i64_mul | i64_div_s | i64_div_u | i64_rem_s | i64_rem_u | i64_and
+        | i64_or | i64_xor | i64_shl | i64_shr_s | i64_shr_u | i64_rotl | i64_rotr => {
+            (&[I64, I64], &[I64])
+        }
+        f32_abs | f32_neg | f32_ceil | f32_floor | f32_trunc | f32_nearest | f32_sqrt => {
+            (&[F32], &[F32])
+        }
+        f32_add | f32_sub | f32_mul | f32_div | f32_min | f32_max | f32_copysign => {
+            (&[F32, F32], &[F32])
+        }
+        f64_abs | f64_neg | f64_ceil | f64_floor | f64_trunc | f64_nearest | f64_sqrt => {
+            (&[F64], &[F64])
+        }
+        f64_add | f64_sub | f64_mul | f64_div | f64_min | f64_max | f64_copysign => {
+            (&[F64, F64], &[F64])
+        }
+
+        i32_wrap_i64 => (&[I64], &[I32]),
+        i32_trunc_f32_s | i32_trunc_f32_u | i32_trunc_sat_f32_s | i32_trunc_sat_f32_u
+        | i32_reinterpret_f32 => (&[F32], &[I32]),
+        i32_trunc_f64_s | i32_trunc_f64_u | i32_trunc_sat_f64_s | i32_trunc_sat_f64_u => {
+            (&[F64], &[I32])
+        }
+        i64_extend_i32_s | i64_extend_i32_u => (&[I32], &[I64]),
+        i64_trunc_f32_s | i64_trunc_f32_u | i64_trunc_sat_f32_s | i64_trunc_sat_f32_u => {
+            (&[F32], &[I64])
+        }
+        i64_trunc_f64_s | i64_trunc_f64_u | i64_trunc_sat_f64_s | i64_trunc_sat_f64_u
+        | i64_reinterpret_f64 => (&[F64], &[I64]),
+        f32_convert_i32_s | f32_convert_i32_u | f32_reinterpret_i32 => (&[I32], &[F32]),
+        f32_convert_i64_s | f32_convert_i64_u => (&[I64], &[F32]),
+        f32_demote_f64 => (&[F64], &[F32]),
+        f64_convert_i32_s | f64_convert_i32_u => (&[I32], &[F64]),
+        f64_convert_i64_s | f64_convert_i64_u | f64_reinterpret_i64 => (&[I64], &[F64]),
+        f64_promote_f32 => (&[F32], &[F64]),
+
+        v128_const(_) => (&[], &[V128]),
+        i8x16_shuffle(_)
+        | i8x16_swizzle
+        | i8x16_eq
+        | i8x16_ne
+        | i8x16_lt_s
+        | i8x16_lt_u
+        | i8x16_gt_s
+        | i8x16_gt_u
+        | i8x16_le_s
+        | i8x16_le_u
+        | i8x16_ge_s
+        | i8x16_ge_u
+        | i16x8_eq
+        | i16x8_ne
+        | i16x8_lt_s
+        | i16x8_lt_u
+        | i16x8_gt_s
+        | i16x8_gt_u
+        | i16x8_le_s
+        | i16x8_le_u
+        | i16x8_ge_s
+        | i16x8_ge_u
+        | i32x4_eq
+        | i32x4_ne
+        | i32x4_lt_s
+        | i32x4_lt_u
+        | i32x4_gt_s
+        | i32x4_gt_u
+        | i32x4_le_s
+        | i32x4_le_u
+        | i32x4_ge_s
+        | i32x4_ge_u
+        | f32x4_eq
+        | f32x4_ne
+        | f32x4_lt
+        | f32x4_gt
+        | f32x4_le
+        | f32x4_ge
+        | f64x2_eq
+        | f64x2_ne
+        | f64x2_lt
+        | f64x2_gt
+        | f64x2_le
+        | f64x2_ge
+        | v128_and
+        | v128_andnot
+        | v128_or
+        | v128_xor
+        | i8x16_narrow_i16x8_s
+        | i8x16_narrow_i16x8_u
+        | i8x16_add
+        | i8x16_add_sat_s
+        | i8x16_add_sat_u
+        | i8x16_sub
+        | i8x16_sub_sat_s
+        | i8x16_sub_sat_u
+        | i8x16_min_s
+        | i8x16_min_u
+        | i8x16_max_s
+        | i8x16_max_u
+        | i8x16_avgr_u
+        | i16x8_q15mulr_sat_s
+        | i16x8_narrow_i32x4_s
+        | i16x8_narrow_i32x4_u
+        | i16x8_add
+        | i16x8_add_sat_s
+        | i16x8_add_sat_u
+        | i16x8_sub
+        | i16x8_sub_sat_s
+        | i16x8_sub_sat_u
+        | i16x8_mul
+        | i16x8_min_s
+        | i16x8_min_u
+        | i16x8_max_s
+        | i16x8_max_u
+        | i16x8_avgr_u
+        | i16x8_extmul_low_i8x16_s
+        | i16x8_extmul_high_i8x16_s
+        | i16x8_extmul_low_i8x16_u
+        | i16x8_extmul_high_i8x16_u
+        | i32x4_add
+        | i32x4_sub
+        | i32x4_mul
+        | i32x4_min_s
+        | i32x4_min_u
+        | i32x4_max_s
+        | i32x4_max_u
+        | i32x4_dot_i16x8_s
+        | i32x4_extmul_low_i16x8_s
+        | i32x4_extmul_high_i16x8_s
+        | i32x4_extmul_low_i16x8_u
+        | i32x4_extmul_high_i16x8_u
+        | i64x2_add
+        | i64x2_sub
+        | i64x2_mul
+        | i64x2_eq
+        | i64x2_ne
+        | i64x2_lt_s
+        | i64x2_gt_s
+        | i64x2_le_s
+        | i64x2_ge_s
+        | i64x2_extmul_low_i32x4_s
+        | i64x2_extmul_high_i32x4_s
+        | i64x2_extmul_low_i32x4_u
+        | i64x2_extmul_high_i32x4_u
+        | f32x4_add
+        | f32x4_sub
+        | f32x4_mul
+        | f32x4_div
+        | f32x4_min
+        | f32x4_max
+        | f32x4_pmin
+        | f32x4_pmax
+        | f64x2_add
+        | f64x2_sub
+        | f64x2_mul
+        | f64x2_div
+        | f64x2_min
+        | f64x2_max
+        | f64x2_pmin
+        | f64x2_pmax => (&[V128, V128], &[V128]),
+        i8x16_splat | i16x8_splat | i32x4_splat => (&[I32], &[V128]),
+        i64x2_splat => (&[I64], &[V128]),
+        f32x4_splat => (&[F32], &[V128]),
+        f64x2_splat => (&[F64], &[V128]),
+        i8x16_extract_lane_s(_)
+        | i8x16_extract_lane_u(_)
+        | i16x8_extract_lane_s(_)
+        | i16x8_extract_lane_u(_)
+        | i32x4_extract_lane(_)
+        | v128_any_true
+        | i8x16_all_true
+        | i8x16_bitmask
+        | i16x8_all_true
+        | i16x8_bitmask
+        | i32x4_all_true
+        | i32x4_bitmask
+        | i64x2_all_true
+        | i64x2_bitmask => (&[V128], &[I32]),
+        i8x16_replace_lane(_)
+        | i16x8_replace_lane(_)
+        | i32x4_replace_lane(_)
+        | i8x16_shl
+        | i8x16_shr_s
+        | i8x16_shr_u
+        | i16x8_shl
+        | i16x8_shr_s
+        | i16x8_shr_u
+        | i32x4_shl
+        | i32x4_shr_s
+        | i32x4_shr_u
+        | i64x2_shl
+        | i64x2_shr_s
+        | i64x2_shr_u => (&[V128, I32], &[V128]),
+        i64x2_extract_lane(_) => (&[V128], &[I64]),
+        i64x2_replace_lane(_) => (&[V128, I64], &[V128]),
+        f32x4_extract_lane(_) => (&[V128], &[F32]),
+        f32x4_replace_lane(_) => (&[V128, F32], &[V128]),
+        f64x2_extract_lane(_) => (&[V128], &[F64]),
+        f64x2_replace_lane(_) => (&[V128, F64], &[V128]),
+        v128_not
+        | f32x4_demote_f64x2_zero
+        | f64x2_promote_low_f32x4
+        | i8x16_abs
+        | i8x16_neg
+        | i8x16_popcnt
+        | f32x4_ceil
+        | f32x4_floor
+        | f32x4_trunc
+        | f32x4_nearest
+        | f64x2_ceil
+        | f64x2_floor
+        | f64x2_trunc
+        | i16x8_extadd_pairwise_i8x16_s
+        | i16x8_extadd_pairwise_i8x16_u
+        | i32x4_extadd_pairwise_i16x8_s
+        | i32x4_extadd_pairwise_i16x8_u
+        | i16x8_abs
+        | i16x8_neg
+        | i16x8_extend_low_i8x16_s
+        | i16x8_extend_high_i8x16_s
+        | i16x8_extend_low_i8x16_u
+        | i16x8_extend_high_i8x16_u
+        | f64x2_nearest
+        | i32x4_abs
+        | i32x4_neg
+        | i32x4_extend_low_i16x8_s
+        | i32x4_extend_high_i16x8_s
+        | i32x4_extend_low_i16x8_u
+        | i32x4_extend_high_i16x8_u
+        | i64x2_abs
+        | i64x2_neg
+        | i64x2_extend_low_i32x4_s
+        | i64x2_extend_high_i32x4_s
+        | i64x2_extend_low_i32x4_u
+        | i64x2_extend_high_i32x4_u
+        | f32x4_abs
+        | f32x4_neg
+        | f32x4_sqrt
+        | f64x2_abs
+        | f64x2_neg
+        | f64x2_sqrt
+        | i32x4_trunc_sat_f32x4_s
+        | i32x4_trunc_sat_f32x4_u
+        | f32x4_convert_i32x4_s
+        | f32x4_convert_i32x4_u
+        | i32x4_trunc_sat_f64x2_s_zero
+        | i32x4_trunc_sat_f64x2_u_zero
+        | f64x2_convert_low_i32x4_s
+        | f64x2_convert_low_i32x4_u => (&[V128], &[V128]),
+        v128_bitselect => (&[V128, V128, V128], &[V128]),
+
+        _ => return None,
+    })
+}
+
+/// The lane index immediate of an instruction along with the number of lanes it can select from.
+/// For `i8x16.shuffle` that's the largest of its indices, which select from the lanes of both operands
+fn lane_index(instruction: &Instructions) -> Option<(u8, u8)> {
+    use Instructions::*;
+
+    Some(match instruction {
+        i8x16_shuffle(lanes) => (lanes.iter().copied().max().unwrap_or(0), 32),
+        i8x16_extract_lane_s(lane)
+        | i8x16_extract_lane_u(lane)
+        | i8x16_replace_lane(lane)
+        | v128_load8_lane(_, lane)
+        | v128_store8_lane(_, lane) => (*lane, 16),
+        i16x8_extract_lane_s(lane)
+        | i16x8_extract_lane_u(lane)
+        | i16x8_replace_lane(lane)
+        | v128_load16_lane(_, lane)
+        | v128_store16_lane(_, lane) => (*lane, 8),
+        i32x4_extract_lane(lane)
+        | i32x4_replace_lane(lane)
+        | f32x4_extract_lane(lane)
+        | f32x4_replace_lane(lane)
+        | v128_load32_lane(_, lane)
+        | v128_store32_lane(_, lane) => (*lane, 4),
+        i64x2_extract_lane(lane)
+        | i64x2_replace_lane(lane)
+        | f64x2_extract_lane(lane)
+        | f64x2_replace_lane(lane)
+        | v128_load64_lane(_, lane)
+        | v128_store64_lane(_, lane) => (*lane, 2),
+        _ => return None,
+    })
+}
+
+/// The log2 of the byte width of the lane a `load_lane` or `store_lane` instruction accesses,
+/// which is also its natural alignment
+fn lane_width(instruction: &Instructions) -> u32 {
+    use Instructions::*;
+
+    match instruction {
+        v128_load8_lane(..) | v128_store8_lane(..) => 0,
+        v128_load16_lane(..) | v128_store16_lane(..) => 1,
+        v128_load32_lane(..) | v128_store32_lane(..) => 2,
+        _ => 3,
+    }
+}
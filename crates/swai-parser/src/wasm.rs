@@ -1,35 +1,134 @@
-use crate::error::WasmParserError;
-use bytereader::ByteReader;
+use crate::{custom::CustomSectionRegistry, error::{WasmParserError, WasmParserErrorKind}};
+use bytereader::{ByteReader, ByteWriter};
 use std::{fs::File, io::Read};
 
 use crate::sections::WasmSections;
 
 // pub use sections::WasmSections;
 
-#[derive(Debug)]
-pub struct WasmModule {
-    pub sections: WasmSections,
+/// A parsed module, borrowing names, data segments and custom sections from the bytes it was parsed from.
+/// Use [`WasmModule::into_owned`] to keep it around longer than the bytes
+#[derive(Debug, PartialEq)]
+pub struct WasmModule<'a> {
+    pub sections: WasmSections<'a>,
 }
 
-impl WasmModule {
-    pub fn from_file(file: &mut File) -> Result<WasmModule, WasmParserError> {
+impl WasmModule<'static> {
+    pub fn from_file(file: &mut File) -> Result<WasmModule<'static>, WasmParserError> {
         let mut buffer = vec![];
         file.read_to_end(&mut buffer)?;
-        WasmModule::from_bytes(&buffer)
+        Ok(WasmModule::from_bytes(&buffer)?.into_owned())
     }
-    pub fn from_bytes(bytes: &[u8]) -> Result<WasmModule, WasmParserError> {
-        let mut reader = ByteReader::from_vec(bytes);
-        println!("{:?}", reader.peak_rest());
-        let Ok(_magic) = reader.read_expect(b"\0asm") else {
-			return Err(WasmParserError::InvalidWasmBytes { message: "The first four bytes in an wasm file / byte buffer should start with '\\0asm' (0x00, 0x61, 0x73, 0x6D)".to_string() })
-		};
 
-        let Ok(_version) = reader.read_expect(&[0x01, 0x00, 0x00, 0x00]) else {
-			return Err(WasmParserError::InvalidWasmBytes { message: "The bytes (4 through 7) should be the version number of the wasm binary and currently needs to be exactly (0x01, 0x00, 0x00, 0x00)".to_string() })
-		};
+    /// Parses a module in the text format, see [`crate::wat::parse`]
+    pub fn from_wat(source: &str) -> Result<WasmModule<'static>, WasmParserError> {
+        let bytes = crate::wat::parse(source)?;
+        Ok(WasmModule::from_bytes(&bytes)?.into_owned())
+    }
+}
+
+impl<'a> WasmModule<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<WasmModule<'a>, WasmParserError> {
+        WasmModule::from_bytes_with_registry(bytes, &CustomSectionRegistry::default())
+    }
+
+    /// Parses the module, decoding custom sections with the decoders in `registry`
+    pub fn from_bytes_with_registry(
+        bytes: &'a [u8],
+        registry: &CustomSectionRegistry,
+    ) -> Result<WasmModule<'a>, WasmParserError> {
+        let mut reader = ByteReader::from_slice(bytes);
+        read_header(&mut reader)?;
 
         Ok(WasmModule {
-            sections: WasmSections::from_reader(&mut reader)?,
+            sections: WasmSections::from_reader_with_registry(&mut reader, registry)?,
         })
     }
+
+    /// Encodes the module in the binary format, parsing the result gives back an equal module
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new();
+        writer.write_bytes(b"\0asm").write_bytes(&[0x01, 0x00, 0x00, 0x00]);
+        self.sections.write(&mut writer);
+        writer.into_vec()
+    }
+
+    /// Prints the module in the text format, see [`crate::wat::print`]
+    pub fn to_wat(&self, options: &crate::wat::PrintOptions) -> Result<String, WasmParserError> {
+        crate::wat::print(self, options)
+    }
+
+    /// Copies everything borrowed from the module bytes
+    pub fn into_owned(self) -> WasmModule<'static> {
+        WasmModule {
+            sections: self.sections.into_owned(),
+        }
+    }
+}
+
+/// The magic number every module and component starts with
+pub(crate) const MAGIC: [u8; 4] = *b"\0asm";
+
+/// The two kinds of binaries that start with the magic number, told apart by the version and layer after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// A core module, parsed with [`WasmModule`]
+    Module,
+    /// A component of the component model, parsed with [`crate::component::WasmComponent`]
+    Component,
+}
+
+impl Encoding {
+    /// Detects the kind of binary from its first 8 bytes, `None` if they aren't a known preamble
+    pub fn detect(bytes: &[u8]) -> Option<Encoding> {
+        let (magic, version) = bytes.get(..8)?.split_at(4);
+        if magic != MAGIC {
+            return None;
+        }
+        [Encoding::Module, Encoding::Component]
+            .into_iter()
+            .find(|encoding| encoding.version() == version)
+    }
+
+    /// The version and layer that follow the magic number
+    pub(crate) fn version(self) -> [u8; 4] {
+        match self {
+            Encoding::Module => [0x01, 0x00, 0x00, 0x00],
+            Encoding::Component => [0x0D, 0x00, 0x01, 0x00],
+        }
+    }
+}
+
+/// Reads the magic number and version that every module starts with
+pub(crate) fn read_header(reader: &mut ByteReader) -> Result<(), WasmParserError> {
+    read_preamble(reader, Encoding::Module)
+}
+
+/// Reads the magic number and the version of `encoding`. A binary of the other encoding is rejected with a hint at its parser
+pub(crate) fn read_preamble(reader: &mut ByteReader, encoding: Encoding) -> Result<(), WasmParserError> {
+    let start = reader.get_current_offset();
+    let context = match encoding {
+        Encoding::Module => "module header",
+        Encoding::Component => "component header",
+    };
+    let Ok(true) = reader.read_expect(&MAGIC) else {
+		return Err(WasmParserError::at(start, WasmParserErrorKind::InvalidWasmBytes { message: "The first four bytes in an wasm file / byte buffer should start with '\\0asm' (0x00, 0x61, 0x73, 0x6D)".to_string() }).in_context(reader, context.to_string()))
+	};
+
+    let version = reader.peak_bytes(4).unwrap_or_default();
+    if version == encoding.version() {
+        reader.jump(4);
+        return Ok(());
+    }
+    let message = match encoding {
+        Encoding::Module if version == Encoding::Component.version() => {
+            "The bytes are a component, which has to be parsed with WasmComponent".to_string()
+        }
+        Encoding::Component if version == Encoding::Module.version() => {
+            "The bytes are a core module, which has to be parsed with WasmModule".to_string()
+        }
+        Encoding::Module => "The bytes (4 through 7) should be the version number of the wasm binary and currently needs to be exactly (0x01, 0x00, 0x00, 0x00)".to_string(),
+        Encoding::Component => "The bytes (4 through 7) should be the version and layer of a component, (0x0D, 0x00, 0x01, 0x00)".to_string(),
+    };
+    Err(WasmParserError::at(start + 4, WasmParserErrorKind::InvalidWasmBytes { message }).in_context(reader, context.to_string()))
 }
@@ -0,0 +1,53 @@
+mod lexer;
+mod mnemonics;
+mod offsets;
+mod parser;
+mod printer;
+
+pub use parser::parse;
+pub use printer::{print, print_bytes, PrintOptions};
+
+/// The index spaces of a module that `$names` can refer to
+#[derive(Debug, Clone, Copy)]
+enum Space {
+    Type,
+    Func,
+    Table,
+    Memory,
+    Global,
+    Tag,
+    Elem,
+    Data,
+}
+
+impl Space {
+    fn describe(self) -> &'static str {
+        match self {
+            Space::Type => "type",
+            Space::Func => "function",
+            Space::Table => "table",
+            Space::Memory => "memory",
+            Space::Global => "global",
+            Space::Tag => "tag",
+            Space::Elem => "element segment",
+            Space::Data => "data segment",
+        }
+    }
+}
+
+/// The section names of `(@custom ...)` placements, along with the section ids
+const SECTION_KEYWORDS: [(&str, u8); 13] = [
+    ("type", 1),
+    ("import", 2),
+    ("func", 3),
+    ("table", 4),
+    ("memory", 5),
+    ("tag", 13),
+    ("global", 6),
+    ("export", 7),
+    ("start", 8),
+    ("elem", 9),
+    ("code", 10),
+    ("data", 11),
+    ("datacount", 12),
+];
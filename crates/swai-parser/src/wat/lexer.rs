@@ -0,0 +1,211 @@
+use crate::error::{WasmParserError, WasmParserErrorKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind<'a> {
+    LParen,
+    RParen,
+    /// The start of an annotation like `(@custom`, holding the name after the `@`
+    Annotation(&'a str),
+    /// A token starting with a lowercase letter, like `module` or `i32.add`
+    Keyword(&'a str),
+    /// A symbolic identifier, without the leading `$`
+    Id(&'a str),
+    /// A string literal with its escapes resolved, strings don't have to be valid UTF-8
+    String(Vec<u8>),
+    /// Any other run of identifier characters, numbers are parsed from these
+    Reserved(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    /// The byte offset of the token in the source text
+    pub offset: usize,
+}
+
+/// An error at `offset` in `source`, with the line and column computed from it
+pub(crate) fn text_error(source: &str, offset: usize, message: String) -> WasmParserError {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    WasmParserError::at(
+        offset,
+        WasmParserErrorKind::InvalidText {
+            message,
+            line,
+            column,
+        },
+    )
+}
+
+/// See the spec for the characters allowed in keywords, ids and numbers:
+/// https://webassembly.github.io/spec/core/text/values.html#text-idchar
+pub(crate) fn is_idchar(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"!#$%&'*+-./:<=>?@\\^_`|~".contains(&c)
+}
+
+/// Splits `source` into tokens, dropping whitespace and comments
+pub(crate) fn tokenize(source: &str) -> Result<Vec<Token<'_>>, WasmParserError> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let start = offset;
+        let kind = match bytes[offset] {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                offset += 1;
+                continue;
+            }
+            b';' if bytes.get(offset + 1) == Some(&b';') => {
+                offset = source[offset..]
+                    .find('\n')
+                    .map_or(bytes.len(), |end| offset + end);
+                continue;
+            }
+            b'(' if bytes.get(offset + 1) == Some(&b';') => {
+                offset = skip_block_comment(source, offset)?;
+                continue;
+            }
+            b'(' if bytes.get(offset + 1) == Some(&b'@') => {
+                offset += 2;
+                let name = idchars(source, &mut offset);
+                if name.is_empty() {
+                    return Err(text_error(
+                        source,
+                        start,
+                        "expected an annotation name after '(@'".to_string(),
+                    ));
+                }
+                TokenKind::Annotation(name)
+            }
+            b'(' => {
+                offset += 1;
+                TokenKind::LParen
+            }
+            b')' => {
+                offset += 1;
+                TokenKind::RParen
+            }
+            b'"' => TokenKind::String(string(source, &mut offset)?),
+            c if is_idchar(c) => {
+                let text = idchars(source, &mut offset);
+                match c {
+                    b'$' if text.len() > 1 => TokenKind::Id(&text[1..]),
+                    b'a'..=b'z' => TokenKind::Keyword(text),
+                    _ => TokenKind::Reserved(text),
+                }
+            }
+            _ => {
+                let c = source[offset..].chars().next().unwrap_or_default();
+                return Err(text_error(
+                    source,
+                    start,
+                    format!("unexpected character {c:?}"),
+                ));
+            }
+        };
+        tokens.push(Token {
+            kind,
+            offset: start,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn idchars<'a>(source: &'a str, offset: &mut usize) -> &'a str {
+    let start = *offset;
+    while source
+        .as_bytes()
+        .get(*offset)
+        .copied()
+        .is_some_and(is_idchar)
+    {
+        *offset += 1;
+    }
+    &source[start..*offset]
+}
+
+/// Skips a `(; ... ;)` comment, which can be nested, returning the offset after it
+fn skip_block_comment(source: &str, start: usize) -> Result<usize, WasmParserError> {
+    let bytes = source.as_bytes();
+    let mut offset = start;
+    let mut depth = 0;
+    while offset + 1 < bytes.len() {
+        match &bytes[offset..offset + 2] {
+            b"(;" => {
+                depth += 1;
+                offset += 2;
+            }
+            b";)" => {
+                depth -= 1;
+                offset += 2;
+                if depth == 0 {
+                    return Ok(offset);
+                }
+            }
+            _ => offset += 1,
+        }
+    }
+    Err(text_error(
+        source,
+        start,
+        "unterminated block comment".to_string(),
+    ))
+}
+
+/// Reads a string literal starting at the opening quote, resolving escapes
+fn string(source: &str, offset: &mut usize) -> Result<Vec<u8>, WasmParserError> {
+    let bytes = source.as_bytes();
+    let start = *offset;
+    let mut value = vec![];
+    *offset += 1;
+
+    loop {
+        let escape = *offset;
+        let Some(&c) = bytes.get(*offset) else {
+            return Err(text_error(source, start, "unterminated string".to_string()));
+        };
+        *offset += 1;
+        match c {
+            b'"' => return Ok(value),
+            b'\n' => return Err(text_error(source, start, "unterminated string".to_string())),
+            b'\\' => {
+                let invalid = || text_error(source, escape, "invalid escape sequence".to_string());
+                let c = *bytes.get(*offset).ok_or_else(invalid)?;
+                *offset += 1;
+                match c {
+                    b't' => value.push(b'\t'),
+                    b'n' => value.push(b'\n'),
+                    b'r' => value.push(b'\r'),
+                    b'"' | b'\'' | b'\\' => value.push(c),
+                    b'u' => {
+                        let digits = source[*offset..]
+                            .strip_prefix('{')
+                            .and_then(|rest| rest.split_once('}'))
+                            .map(|(digits, _)| digits)
+                            .ok_or_else(invalid)?;
+                        let c = u32::from_str_radix(&digits.replace('_', ""), 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(invalid)?;
+                        *offset += digits.len() + 2;
+                        value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                    high if high.is_ascii_hexdigit() => {
+                        let low = bytes
+                            .get(*offset)
+                            .and_then(|c| (*c as char).to_digit(16))
+                            .ok_or_else(invalid)?;
+                        *offset += 1;
+                        let high = (high as char).to_digit(16).unwrap_or_default();
+                        value.push((high << 4 | low) as u8);
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => value.push(c),
+        }
+    }
+}
@@ -0,0 +1,684 @@
+use crate::instructions::{Instructions, Instructions::*, MemArg};
+
+/// Instructions without immediates, by mnemonic
+pub(crate) static PLAIN_INSTRUCTIONS: [(&str, Instructions); 347] = [
+    ("unreachable", Unreachable),
+    ("nop", Nop),
+    ("return", Return),
+    ("throw_ref", ThrowRef),
+    ("ref.is_null", RefIsNull),
+    ("ref.as_non_null", RefAsNonNull),
+    ("drop", Drop),
+    ("select", Select),
+    ("memory.size", MemorySize),
+    ("memory.grow", MemoryGrow),
+    ("memory.copy", MemoryCopy),
+    ("memory.fill", MemoryFill),
+    ("atomic.fence", atomic_fence),
+    ("i32.eqz", i32_eqz),
+    ("i32.eq", i32_eq),
+    ("i32.ne", i32_ne),
+    ("i32.lt_s", i32_lt_s),
+    ("i32.lt_u", i32_lt_u),
+    ("i32.gt_s", i32_gt_s),
+    ("i32.gt_u", i32_gt_u),
+    ("i32.le_s", i32_le_s),
+    ("i32.le_u", i32_le_u),
+    ("i32.ge_s", i32_ge_s),
+    ("i32.ge_u", i32_ge_u),
+    ("i64.eqz", i64_eqz),
+    ("i64.eq", i64_eq),
+    ("i64.ne", i64_ne),
+    ("i64.lt_s", i64_lt_s),
+    ("i64.lt_u", i64_lt_u),
+    ("i64.gt_s", i64_gt_s),
+    ("i64.gt_u", i64_gt_u),
+    ("i64.le_s", i64_le_s),
+    ("i64.le_u", i64_le_u),
+    ("i64.ge_s", i64_ge_s),
+    ("i64.ge_u", i64_ge_u),
+    ("f32.eq", f32_eq),
+    ("f32.ne", f32_ne),
+    ("f32.lt", f32_lt),
+    ("f32.gt", f32_gt),
+    ("f32.le", f32_le),
+    ("f32.ge", f32_ge),
+    ("f64.eq", f64_eq),
+    ("f64.ne", f64_ne),
+    ("f64.lt", f64_lt),
+    ("f64.gt", f64_gt),
+    ("f64.le", f64_le),
+    ("f64.ge", f64_ge),
+    ("i32.clz", i32_clz),
+    ("i32.ctz", i32_ctz),
+    ("i32.popcnt", i32_popcnt),
+    ("i32.add", i32_add),
+    ("i32.sub", i32_sub),
+    ("i32.mul", i32_mul),
+    ("i32.div_s", i32_div_s),
+    ("i32.div_u", i32_div_u),
+    ("i32.rem_s", i32_rem_s),
+    ("i32.rem_u", i32_rem_u),
+    ("i32.and", i32_and),
+    ("i32.or", i32_or),
+    ("i32.xor", i32_xor),
+    ("i32.shl", i32_shl),
+    ("i32.shr_s", i32_shr_s),
+    ("i32.shr_u", i32_shr_u),
+    ("i32.rotl", i32_rotl),
+    ("i32.rotr", i32_rotr),
+    ("i64.clz", i64_clz),
+    ("i64.ctz", i64_ctz),
+    ("i64.popcnt", i64_popcnt),
+    ("i64.add", i64_add),
+    ("i64.sub", i64_sub),
+    ("i64.mul", i64_mul),
+    ("i64.div_s", i64_div_s),
+    ("i64.div_u", i64_div_u),
+    ("i64.rem_s", i64_rem_s),
+    ("i64.rem_u", i64_rem_u),
+    ("i64.and", i64_and),
+    ("i64.or", i64_or),
+    ("i64.xor", i64_xor),
+    ("i64.shl", i64_shl),
+    ("i64.shr_s", i64_shr_s),
+    ("i64.shr_u", i64_shr_u),
+    ("i64.rotl", i64_rotl),
+    ("i64.rotr", i64_rotr),
+    ("f32.abs", f32_abs),
+    ("f32.neg", f32_neg),
+    ("f32.ceil", f32_ceil),
+    ("f32.floor", f32_floor),
+    ("f32.trunc", f32_trunc),
+    ("f32.nearest", f32_nearest),
+    ("f32.sqrt", f32_sqrt),
+    ("f32.add", f32_add),
+    ("f32.sub", f32_sub),
+    ("f32.mul", f32_mul),
+    ("f32.div", f32_div),
+    ("f32.min", f32_min),
+    ("f32.max", f32_max),
+    ("f32.copysign", f32_copysign),
+    ("f64.abs", f64_abs),
+    ("f64.neg", f64_neg),
+    ("f64.ceil", f64_ceil),
+    ("f64.floor", f64_floor),
+    ("f64.trunc", f64_trunc),
+    ("f64.nearest", f64_nearest),
+    ("f64.sqrt", f64_sqrt),
+    ("f64.add", f64_add),
+    ("f64.sub", f64_sub),
+    ("f64.mul", f64_mul),
+    ("f64.div", f64_div),
+    ("f64.min", f64_min),
+    ("f64.max", f64_max),
+    ("f64.copysign", f64_copysign),
+    ("i32.wrap_i64", i32_wrap_i64),
+    ("i32.trunc_f32_s", i32_trunc_f32_s),
+    ("i32.trunc_f32_u", i32_trunc_f32_u),
+    ("i32.trunc_f64_s", i32_trunc_f64_s),
+    ("i32.trunc_f64_u", i32_trunc_f64_u),
+    ("i64.extend_i32_s", i64_extend_i32_s),
+    ("i64.extend_i32_u", i64_extend_i32_u),
+    ("i64.trunc_f32_s", i64_trunc_f32_s),
+    ("i64.trunc_f32_u", i64_trunc_f32_u),
+    ("i64.trunc_f64_s", i64_trunc_f64_s),
+    ("i64.trunc_f64_u", i64_trunc_f64_u),
+    ("f32.convert_i32_s", f32_convert_i32_s),
+    ("f32.convert_i32_u", f32_convert_i32_u),
+    ("f32.convert_i64_s", f32_convert_i64_s),
+    ("f32.convert_i64_u", f32_convert_i64_u),
+    ("f32.demote_f64", f32_demote_f64),
+    ("f64.convert_i32_s", f64_convert_i32_s),
+    ("f64.convert_i32_u", f64_convert_i32_u),
+    ("f64.convert_i64_s", f64_convert_i64_s),
+    ("f64.convert_i64_u", f64_convert_i64_u),
+    ("f64.promote_f32", f64_promote_f32),
+    ("i32.reinterpret_f32", i32_reinterpret_f32),
+    ("i64.reinterpret_f64", i64_reinterpret_f64),
+    ("f32.reinterpret_i32", f32_reinterpret_i32),
+    ("f64.reinterpret_i64", f64_reinterpret_i64),
+    ("i32.extend8_s", i32_extend8_s),
+    ("i32.extend16_s", i32_extend16_s),
+    ("i64.extend8_s", i64_extend8_s),
+    ("i64.extend16_s", i64_extend16_s),
+    ("i64.extend32_s", i64_extend32_s),
+    ("i32.trunc_sat_f32_s", i32_trunc_sat_f32_s),
+    ("i32.trunc_sat_f32_u", i32_trunc_sat_f32_u),
+    ("i32.trunc_sat_f64_s", i32_trunc_sat_f64_s),
+    ("i32.trunc_sat_f64_u", i32_trunc_sat_f64_u),
+    ("i64.trunc_sat_f32_s", i64_trunc_sat_f32_s),
+    ("i64.trunc_sat_f32_u", i64_trunc_sat_f32_u),
+    ("i64.trunc_sat_f64_s", i64_trunc_sat_f64_s),
+    ("i64.trunc_sat_f64_u", i64_trunc_sat_f64_u),
+    ("i8x16.swizzle", i8x16_swizzle),
+    ("i8x16.splat", i8x16_splat),
+    ("i16x8.splat", i16x8_splat),
+    ("i32x4.splat", i32x4_splat),
+    ("i64x2.splat", i64x2_splat),
+    ("f32x4.splat", f32x4_splat),
+    ("f64x2.splat", f64x2_splat),
+    ("i8x16.eq", i8x16_eq),
+    ("i8x16.ne", i8x16_ne),
+    ("i8x16.lt_s", i8x16_lt_s),
+    ("i8x16.lt_u", i8x16_lt_u),
+    ("i8x16.gt_s", i8x16_gt_s),
+    ("i8x16.gt_u", i8x16_gt_u),
+    ("i8x16.le_s", i8x16_le_s),
+    ("i8x16.le_u", i8x16_le_u),
+    ("i8x16.ge_s", i8x16_ge_s),
+    ("i8x16.ge_u", i8x16_ge_u),
+    ("i16x8.eq", i16x8_eq),
+    ("i16x8.ne", i16x8_ne),
+    ("i16x8.lt_s", i16x8_lt_s),
+    ("i16x8.lt_u", i16x8_lt_u),
+    ("i16x8.gt_s", i16x8_gt_s),
+    ("i16x8.gt_u", i16x8_gt_u),
+    ("i16x8.le_s", i16x8_le_s),
+    ("i16x8.le_u", i16x8_le_u),
+    ("i16x8.ge_s", i16x8_ge_s),
+    ("i16x8.ge_u", i16x8_ge_u),
+    ("i32x4.eq", i32x4_eq),
+    ("i32x4.ne", i32x4_ne),
+    ("i32x4.lt_s", i32x4_lt_s),
+    ("i32x4.lt_u", i32x4_lt_u),
+    ("i32x4.gt_s", i32x4_gt_s),
+    ("i32x4.gt_u", i32x4_gt_u),
+    ("i32x4.le_s", i32x4_le_s),
+    ("i32x4.le_u", i32x4_le_u),
+    ("i32x4.ge_s", i32x4_ge_s),
+    ("i32x4.ge_u", i32x4_ge_u),
+    ("f32x4.eq", f32x4_eq),
+    ("f32x4.ne", f32x4_ne),
+    ("f32x4.lt", f32x4_lt),
+    ("f32x4.gt", f32x4_gt),
+    ("f32x4.le", f32x4_le),
+    ("f32x4.ge", f32x4_ge),
+    ("f64x2.eq", f64x2_eq),
+    ("f64x2.ne", f64x2_ne),
+    ("f64x2.lt", f64x2_lt),
+    ("f64x2.gt", f64x2_gt),
+    ("f64x2.le", f64x2_le),
+    ("f64x2.ge", f64x2_ge),
+    ("v128.not", v128_not),
+    ("v128.and", v128_and),
+    ("v128.andnot", v128_andnot),
+    ("v128.or", v128_or),
+    ("v128.xor", v128_xor),
+    ("v128.bitselect", v128_bitselect),
+    ("v128.any_true", v128_any_true),
+    ("f32x4.demote_f64x2_zero", f32x4_demote_f64x2_zero),
+    ("f64x2.promote_low_f32x4", f64x2_promote_low_f32x4),
+    ("i8x16.abs", i8x16_abs),
+    ("i8x16.neg", i8x16_neg),
+    ("i8x16.popcnt", i8x16_popcnt),
+    ("i8x16.all_true", i8x16_all_true),
+    ("i8x16.bitmask", i8x16_bitmask),
+    ("i8x16.narrow_i16x8_s", i8x16_narrow_i16x8_s),
+    ("i8x16.narrow_i16x8_u", i8x16_narrow_i16x8_u),
+    ("f32x4.ceil", f32x4_ceil),
+    ("f32x4.floor", f32x4_floor),
+    ("f32x4.trunc", f32x4_trunc),
+    ("f32x4.nearest", f32x4_nearest),
+    ("i8x16.shl", i8x16_shl),
+    ("i8x16.shr_s", i8x16_shr_s),
+    ("i8x16.shr_u", i8x16_shr_u),
+    ("i8x16.add", i8x16_add),
+    ("i8x16.add_sat_s", i8x16_add_sat_s),
+    ("i8x16.add_sat_u", i8x16_add_sat_u),
+    ("i8x16.sub", i8x16_sub),
+    ("i8x16.sub_sat_s", i8x16_sub_sat_s),
+    ("i8x16.sub_sat_u", i8x16_sub_sat_u),
+    ("f64x2.ceil", f64x2_ceil),
+    ("f64x2.floor", f64x2_floor),
+    ("i8x16.min_s", i8x16_min_s),
+    ("i8x16.min_u", i8x16_min_u),
+    ("i8x16.max_s", i8x16_max_s),
+    ("i8x16.max_u", i8x16_max_u),
+    ("f64x2.trunc", f64x2_trunc),
+    ("i8x16.avgr_u", i8x16_avgr_u),
+    (
+        "i16x8.extadd_pairwise_i8x16_s",
+        i16x8_extadd_pairwise_i8x16_s,
+    ),
+    (
+        "i16x8.extadd_pairwise_i8x16_u",
+        i16x8_extadd_pairwise_i8x16_u,
+    ),
+    (
+        "i32x4.extadd_pairwise_i16x8_s",
+        i32x4_extadd_pairwise_i16x8_s,
+    ),
+    (
+        "i32x4.extadd_pairwise_i16x8_u",
+        i32x4_extadd_pairwise_i16x8_u,
+    ),
+    ("i16x8.abs", i16x8_abs),
+    ("i16x8.neg", i16x8_neg),
+    ("i16x8.q15mulr_sat_s", i16x8_q15mulr_sat_s),
+    ("i16x8.all_true", i16x8_all_true),
+    ("i16x8.bitmask", i16x8_bitmask),
+    ("i16x8.narrow_i32x4_s", i16x8_narrow_i32x4_s),
+    ("i16x8.narrow_i32x4_u", i16x8_narrow_i32x4_u),
+    ("i16x8.extend_low_i8x16_s", i16x8_extend_low_i8x16_s),
+    ("i16x8.extend_high_i8x16_s", i16x8_extend_high_i8x16_s),
+    ("i16x8.extend_low_i8x16_u", i16x8_extend_low_i8x16_u),
+    ("i16x8.extend_high_i8x16_u", i16x8_extend_high_i8x16_u),
+    ("i16x8.shl", i16x8_shl),
+    ("i16x8.shr_s", i16x8_shr_s),
+    ("i16x8.shr_u", i16x8_shr_u),
+    ("i16x8.add", i16x8_add),
+    ("i16x8.add_sat_s", i16x8_add_sat_s),
+    ("i16x8.add_sat_u", i16x8_add_sat_u),
+    ("i16x8.sub", i16x8_sub),
+    ("i16x8.sub_sat_s", i16x8_sub_sat_s),
+    ("i16x8.sub_sat_u", i16x8_sub_sat_u),
+    ("f64x2.nearest", f64x2_nearest),
+    ("i16x8.mul", i16x8_mul),
+    ("i16x8.min_s", i16x8_min_s),
+    ("i16x8.min_u", i16x8_min_u),
+    ("i16x8.max_s", i16x8_max_s),
+    ("i16x8.max_u", i16x8_max_u),
+    ("i16x8.avgr_u", i16x8_avgr_u),
+    ("i16x8.extmul_low_i8x16_s", i16x8_extmul_low_i8x16_s),
+    ("i16x8.extmul_high_i8x16_s", i16x8_extmul_high_i8x16_s),
+    ("i16x8.extmul_low_i8x16_u", i16x8_extmul_low_i8x16_u),
+    ("i16x8.extmul_high_i8x16_u", i16x8_extmul_high_i8x16_u),
+    ("i32x4.abs", i32x4_abs),
+    ("i32x4.neg", i32x4_neg),
+    ("i32x4.all_true", i32x4_all_true),
+    ("i32x4.bitmask", i32x4_bitmask),
+    ("i32x4.extend_low_i16x8_s", i32x4_extend_low_i16x8_s),
+    ("i32x4.extend_high_i16x8_s", i32x4_extend_high_i16x8_s),
+    ("i32x4.extend_low_i16x8_u", i32x4_extend_low_i16x8_u),
+    ("i32x4.extend_high_i16x8_u", i32x4_extend_high_i16x8_u),
+    ("i32x4.shl", i32x4_shl),
+    ("i32x4.shr_s", i32x4_shr_s),
+    ("i32x4.shr_u", i32x4_shr_u),
+    ("i32x4.add", i32x4_add),
+    ("i32x4.sub", i32x4_sub),
+    ("i32x4.mul", i32x4_mul),
+    ("i32x4.min_s", i32x4_min_s),
+    ("i32x4.min_u", i32x4_min_u),
+    ("i32x4.max_s", i32x4_max_s),
+    ("i32x4.max_u", i32x4_max_u),
+    ("i32x4.dot_i16x8_s", i32x4_dot_i16x8_s),
+    ("i32x4.extmul_low_i16x8_s", i32x4_extmul_low_i16x8_s),
+    ("i32x4.extmul_high_i16x8_s", i32x4_extmul_high_i16x8_s),
+    ("i32x4.extmul_low_i16x8_u", i32x4_extmul_low_i16x8_u),
+    ("i32x4.extmul_high_i16x8_u", i32x4_extmul_high_i16x8_u),
+    ("i64x2.abs", i64x2_abs),
+    ("i64x2.neg", i64x2_neg),
+    ("i64x2.all_true", i64x2_all_true),
+    ("i64x2.bitmask", i64x2_bitmask),
+    ("i64x2.extend_low_i32x4_s", i64x2_extend_low_i32x4_s),
+    ("i64x2.extend_high_i32x4_s", i64x2_extend_high_i32x4_s),
+    ("i64x2.extend_low_i32x4_u", i64x2_extend_low_i32x4_u),
+    ("i64x2.extend_high_i32x4_u", i64x2_extend_high_i32x4_u),
+    ("i64x2.shl", i64x2_shl),
+    ("i64x2.shr_s", i64x2_shr_s),
+    ("i64x2.shr_u", i64x2_shr_u),
+    ("i64x2.add", i64x2_add),
+    ("i64x2.sub", i64x2_sub),
+    ("i64x2.mul", i64x2_mul),
+    ("i64x2.eq", i64x2_eq),
+    ("i64x2.ne", i64x2_ne),
+    ("i64x2.lt_s", i64x2_lt_s),
+    ("i64x2.gt_s", i64x2_gt_s),
+    ("i64x2.le_s", i64x2_le_s),
+    ("i64x2.ge_s", i64x2_ge_s),
+    ("i64x2.extmul_low_i32x4_s", i64x2_extmul_low_i32x4_s),
+    ("i64x2.extmul_high_i32x4_s", i64x2_extmul_high_i32x4_s),
+    ("i64x2.extmul_low_i32x4_u", i64x2_extmul_low_i32x4_u),
+    ("i64x2.extmul_high_i32x4_u", i64x2_extmul_high_i32x4_u),
+    ("f32x4.abs", f32x4_abs),
+    ("f32x4.neg", f32x4_neg),
+    ("f32x4.sqrt", f32x4_sqrt),
+    ("f32x4.add", f32x4_add),
+    ("f32x4.sub", f32x4_sub),
+    ("f32x4.mul", f32x4_mul),
+    ("f32x4.div", f32x4_div),
+    ("f32x4.min", f32x4_min),
+    ("f32x4.max", f32x4_max),
+    ("f32x4.pmin", f32x4_pmin),
+    ("f32x4.pmax", f32x4_pmax),
+    ("f64x2.abs", f64x2_abs),
+    ("f64x2.neg", f64x2_neg),
+    ("f64x2.sqrt", f64x2_sqrt),
+    ("f64x2.add", f64x2_add),
+    ("f64x2.sub", f64x2_sub),
+    ("f64x2.mul", f64x2_mul),
+    ("f64x2.div", f64x2_div),
+    ("f64x2.min", f64x2_min),
+    ("f64x2.max", f64x2_max),
+    ("f64x2.pmin", f64x2_pmin),
+    ("f64x2.pmax", f64x2_pmax),
+    ("i32x4.trunc_sat_f32x4_s", i32x4_trunc_sat_f32x4_s),
+    ("i32x4.trunc_sat_f32x4_u", i32x4_trunc_sat_f32x4_u),
+    ("f32x4.convert_i32x4_s", f32x4_convert_i32x4_s),
+    ("f32x4.convert_i32x4_u", f32x4_convert_i32x4_u),
+    ("i32x4.trunc_sat_f64x2_s_zero", i32x4_trunc_sat_f64x2_s_zero),
+    ("i32x4.trunc_sat_f64x2_u_zero", i32x4_trunc_sat_f64x2_u_zero),
+    ("f64x2.convert_low_i32x4_s", f64x2_convert_low_i32x4_s),
+    ("f64x2.convert_low_i32x4_u", f64x2_convert_low_i32x4_u),
+];
+
+type MemoryInstruction = fn(MemArg) -> Instructions;
+
+/// Loads, stores and atomic memory instructions by mnemonic, with the log2 of their natural alignment
+pub(crate) const MEMORY_INSTRUCTIONS: [(&str, MemoryInstruction, u32); 103] = [
+    ("i32.load", i32_load, 2),
+    ("i64.load", i64_load, 3),
+    ("f32.load", f32_load, 2),
+    ("f64.load", f64_load, 3),
+    ("i32.load8_s", i32_load_8s, 0),
+    ("i32.load8_u", i32_load_8u, 0),
+    ("i32.load16_s", i32_load_16s, 1),
+    ("i32.load16_u", i32_load_16u, 1),
+    ("i64.load8_s", i64_load_8s, 0),
+    ("i64.load8_u", i64_load_8u, 0),
+    ("i64.load16_s", i64_load_16s, 1),
+    ("i64.load16_u", i64_load_16u, 1),
+    ("i64.load32_s", i64_load_32s, 2),
+    ("i64.load32_u", i64_load_32u, 2),
+    ("i32.store", i32_store, 2),
+    ("i64.store", i64_store, 3),
+    ("f32.store", f32_store, 2),
+    ("f64.store", f64_store, 3),
+    ("i32.store8", i32_store_8, 0),
+    ("i32.store16", i32_store_16, 1),
+    ("i64.store8", i64_store_8, 0),
+    ("i64.store16", i64_store_16, 1),
+    ("i64.store32", i64_store_32, 2),
+    ("v128.load", v128_load, 4),
+    ("v128.load8x8_s", v128_load8x8_s, 3),
+    ("v128.load8x8_u", v128_load8x8_u, 3),
+    ("v128.load16x4_s", v128_load16x4_s, 3),
+    ("v128.load16x4_u", v128_load16x4_u, 3),
+    ("v128.load32x2_s", v128_load32x2_s, 3),
+    ("v128.load32x2_u", v128_load32x2_u, 3),
+    ("v128.load8_splat", v128_load8_splat, 0),
+    ("v128.load16_splat", v128_load16_splat, 1),
+    ("v128.load32_splat", v128_load32_splat, 2),
+    ("v128.load64_splat", v128_load64_splat, 3),
+    ("v128.store", v128_store, 4),
+    ("v128.load32_zero", v128_load32_zero, 2),
+    ("v128.load64_zero", v128_load64_zero, 3),
+    ("memory.atomic.notify", memory_atomic_notify, 2),
+    ("memory.atomic.wait32", memory_atomic_wait32, 2),
+    ("memory.atomic.wait64", memory_atomic_wait64, 3),
+    ("i32.atomic.load", i32_atomic_load, 2),
+    ("i64.atomic.load", i64_atomic_load, 3),
+    ("i32.atomic.load8_u", i32_atomic_load8_u, 0),
+    ("i32.atomic.load16_u", i32_atomic_load16_u, 1),
+    ("i64.atomic.load8_u", i64_atomic_load8_u, 0),
+    ("i64.atomic.load16_u", i64_atomic_load16_u, 1),
+    ("i64.atomic.load32_u", i64_atomic_load32_u, 2),
+    ("i32.atomic.store", i32_atomic_store, 2),
+    ("i64.atomic.store", i64_atomic_store, 3),
+    ("i32.atomic.store8", i32_atomic_store8, 0),
+    ("i32.atomic.store16", i32_atomic_store16, 1),
+    ("i64.atomic.store8", i64_atomic_store8, 0),
+    ("i64.atomic.store16", i64_atomic_store16, 1),
+    ("i64.atomic.store32", i64_atomic_store32, 2),
+    ("i32.atomic.rmw.add", i32_atomic_rmw_add, 2),
+    ("i64.atomic.rmw.add", i64_atomic_rmw_add, 3),
+    ("i32.atomic.rmw8.add_u", i32_atomic_rmw8_add_u, 0),
+    ("i32.atomic.rmw16.add_u", i32_atomic_rmw16_add_u, 1),
+    ("i64.atomic.rmw8.add_u", i64_atomic_rmw8_add_u, 0),
+    ("i64.atomic.rmw16.add_u", i64_atomic_rmw16_add_u, 1),
+    ("i64.atomic.rmw32.add_u", i64_atomic_rmw32_add_u, 2),
+    ("i32.atomic.rmw.sub", i32_atomic_rmw_sub, 2),
+    ("i64.atomic.rmw.sub", i64_atomic_rmw_sub, 3),
+    ("i32.atomic.rmw8.sub_u", i32_atomic_rmw8_sub_u, 0),
+    ("i32.atomic.rmw16.sub_u", i32_atomic_rmw16_sub_u, 1),
+    ("i64.atomic.rmw8.sub_u", i64_atomic_rmw8_sub_u, 0),
+    ("i64.atomic.rmw16.sub_u", i64_atomic_rmw16_sub_u, 1),
+    ("i64.atomic.rmw32.sub_u", i64_atomic_rmw32_sub_u, 2),
+    ("i32.atomic.rmw.and", i32_atomic_rmw_and, 2),
+    ("i64.atomic.rmw.and", i64_atomic_rmw_and, 3),
+    ("i32.atomic.rmw8.and_u", i32_atomic_rmw8_and_u, 0),
+    ("i32.atomic.rmw16.and_u", i32_atomic_rmw16_and_u, 1),
+    ("i64.atomic.rmw8.and_u", i64_atomic_rmw8_and_u, 0),
+    ("i64.atomic.rmw16.and_u", i64_atomic_rmw16_and_u, 1),
+    ("i64.atomic.rmw32.and_u", i64_atomic_rmw32_and_u, 2),
+    ("i32.atomic.rmw.or", i32_atomic_rmw_or, 2),
+    ("i64.atomic.rmw.or", i64_atomic_rmw_or, 3),
+    ("i32.atomic.rmw8.or_u", i32_atomic_rmw8_or_u, 0),
+    ("i32.atomic.rmw16.or_u", i32_atomic_rmw16_or_u, 1),
+    ("i64.atomic.rmw8.or_u", i64_atomic_rmw8_or_u, 0),
+    ("i64.atomic.rmw16.or_u", i64_atomic_rmw16_or_u, 1),
+    ("i64.atomic.rmw32.or_u", i64_atomic_rmw32_or_u, 2),
+    ("i32.atomic.rmw.xor", i32_atomic_rmw_xor, 2),
+    ("i64.atomic.rmw.xor", i64_atomic_rmw_xor, 3),
+    ("i32.atomic.rmw8.xor_u", i32_atomic_rmw8_xor_u, 0),
+    ("i32.atomic.rmw16.xor_u", i32_atomic_rmw16_xor_u, 1),
+    ("i64.atomic.rmw8.xor_u", i64_atomic_rmw8_xor_u, 0),
+    ("i64.atomic.rmw16.xor_u", i64_atomic_rmw16_xor_u, 1),
+    ("i64.atomic.rmw32.xor_u", i64_atomic_rmw32_xor_u, 2),
+    ("i32.atomic.rmw.xchg", i32_atomic_rmw_xchg, 2),
+    ("i64.atomic.rmw.xchg", i64_atomic_rmw_xchg, 3),
+    ("i32.atomic.rmw8.xchg_u", i32_atomic_rmw8_xchg_u, 0),
+    ("i32.atomic.rmw16.xchg_u", i32_atomic_rmw16_xchg_u, 1),
+    ("i64.atomic.rmw8.xchg_u", i64_atomic_rmw8_xchg_u, 0),
+    ("i64.atomic.rmw16.xchg_u", i64_atomic_rmw16_xchg_u, 1),
+    ("i64.atomic.rmw32.xchg_u", i64_atomic_rmw32_xchg_u, 2),
+    ("i32.atomic.rmw.cmpxchg", i32_atomic_rmw_cmpxchg, 2),
+    ("i64.atomic.rmw.cmpxchg", i64_atomic_rmw_cmpxchg, 3),
+    ("i32.atomic.rmw8.cmpxchg_u", i32_atomic_rmw8_cmpxchg_u, 0),
+    ("i32.atomic.rmw16.cmpxchg_u", i32_atomic_rmw16_cmpxchg_u, 1),
+    ("i64.atomic.rmw8.cmpxchg_u", i64_atomic_rmw8_cmpxchg_u, 0),
+    ("i64.atomic.rmw16.cmpxchg_u", i64_atomic_rmw16_cmpxchg_u, 1),
+    ("i64.atomic.rmw32.cmpxchg_u", i64_atomic_rmw32_cmpxchg_u, 2),
+];
+
+type LaneInstruction = fn(u8) -> Instructions;
+
+/// Instructions with a lane index immediate, by mnemonic
+pub(crate) const LANE_INSTRUCTIONS: [(&str, LaneInstruction); 14] = [
+    ("i8x16.extract_lane_s", i8x16_extract_lane_s),
+    ("i8x16.extract_lane_u", i8x16_extract_lane_u),
+    ("i8x16.replace_lane", i8x16_replace_lane),
+    ("i16x8.extract_lane_s", i16x8_extract_lane_s),
+    ("i16x8.extract_lane_u", i16x8_extract_lane_u),
+    ("i16x8.replace_lane", i16x8_replace_lane),
+    ("i32x4.extract_lane", i32x4_extract_lane),
+    ("i32x4.replace_lane", i32x4_replace_lane),
+    ("i64x2.extract_lane", i64x2_extract_lane),
+    ("i64x2.replace_lane", i64x2_replace_lane),
+    ("f32x4.extract_lane", f32x4_extract_lane),
+    ("f32x4.replace_lane", f32x4_replace_lane),
+    ("f64x2.extract_lane", f64x2_extract_lane),
+    ("f64x2.replace_lane", f64x2_replace_lane),
+];
+
+type LaneMemoryInstruction = fn(MemArg, u8) -> Instructions;
+
+/// Loads and stores of a single vector lane by mnemonic, with the log2 of their natural alignment
+pub(crate) const LANE_MEMORY_INSTRUCTIONS: [(&str, LaneMemoryInstruction, u32); 8] = [
+    ("v128.load8_lane", v128_load8_lane, 0),
+    ("v128.load16_lane", v128_load16_lane, 1),
+    ("v128.load32_lane", v128_load32_lane, 2),
+    ("v128.load64_lane", v128_load64_lane, 3),
+    ("v128.store8_lane", v128_store8_lane, 0),
+    ("v128.store16_lane", v128_store16_lane, 1),
+    ("v128.store32_lane", v128_store32_lane, 2),
+    ("v128.store64_lane", v128_store64_lane, 3),
+];
+
+pub(crate) fn plain_instruction(name: &str) -> Option<Instructions> {
+    PLAIN_INSTRUCTIONS
+        .iter()
+        .find(|(mnemonic, _)| *mnemonic == name)
+        .map(|(_, instruction)| instruction.clone())
+}
+
+/// The mnemonic of an instruction without immediates
+pub(crate) fn plain_mnemonic(instruction: &Instructions) -> Option<&'static str> {
+    PLAIN_INSTRUCTIONS
+        .iter()
+        .find(|(_, plain)| plain == instruction)
+        .map(|(mnemonic, _)| *mnemonic)
+}
+
+/// The mnemonic, memarg and natural alignment of a load or store
+pub(crate) fn memory_instruction(
+    instruction: &Instructions,
+) -> Option<(&'static str, &MemArg, u32)> {
+    let memarg = match instruction {
+        i32_load(memarg)
+        | i64_load(memarg)
+        | f32_load(memarg)
+        | f64_load(memarg)
+        | i32_load_8s(memarg)
+        | i32_load_8u(memarg)
+        | i32_load_16s(memarg)
+        | i32_load_16u(memarg)
+        | i64_load_8s(memarg)
+        | i64_load_8u(memarg)
+        | i64_load_16s(memarg)
+        | i64_load_16u(memarg)
+        | i64_load_32s(memarg)
+        | i64_load_32u(memarg)
+        | i32_store(memarg)
+        | i64_store(memarg)
+        | f32_store(memarg)
+        | f64_store(memarg)
+        | i32_store_8(memarg)
+        | i32_store_16(memarg)
+        | i64_store_8(memarg)
+        | i64_store_16(memarg)
+        | i64_store_32(memarg)
+        | v128_load(memarg)
+        | v128_load8x8_s(memarg)
+        | v128_load8x8_u(memarg)
+        | v128_load16x4_s(memarg)
+        | v128_load16x4_u(memarg)
+        | v128_load32x2_s(memarg)
+        | v128_load32x2_u(memarg)
+        | v128_load8_splat(memarg)
+        | v128_load16_splat(memarg)
+        | v128_load32_splat(memarg)
+        | v128_load64_splat(memarg)
+        | v128_store(memarg)
+        | v128_load32_zero(memarg)
+        | v128_load64_zero(memarg)
+        | memory_atomic_notify(memarg)
+        | memory_atomic_wait32(memarg)
+        | memory_atomic_wait64(memarg)
+        | i32_atomic_load(memarg)
+        | i64_atomic_load(memarg)
+        | i32_atomic_load8_u(memarg)
+        | i32_atomic_load16_u(memarg)
+        | i64_atomic_load8_u(memarg)
+        | i64_atomic_load16_u(memarg)
+        | i64_atomic_load32_u(memarg)
+        | i32_atomic_store(memarg)
+        | i64_atomic_store(memarg)
+        | i32_atomic_store8(memarg)
+        | i32_atomic_store16(memarg)
+        | i64_atomic_store8(memarg)
+        | i64_atomic_store16(memarg)
+        | i64_atomic_store32(memarg)
+        | i32_atomic_rmw_add(memarg)
+        | i64_atomic_rmw_add(memarg)
+        | i32_atomic_rmw8_add_u(memarg)
+        | i32_atomic_rmw16_add_u(memarg)
+        | i64_atomic_rmw8_add_u(memarg)
+        | i64_atomic_rmw16_add_u(memarg)
+        | i64_atomic_rmw32_add_u(memarg)
+        | i32_atomic_rmw_sub(memarg)
+        | i64_atomic_rmw_sub(memarg)
+        | i32_atomic_rmw8_sub_u(memarg)
+        | i32_atomic_rmw16_sub_u(memarg)
+        | i64_atomic_rmw8_sub_u(memarg)
+        | i64_atomic_rmw16_sub_u(memarg)
+        | i64_atomic_rmw32_sub_u(memarg)
+        | i32_atomic_rmw_and(memarg)
+        | i64_atomic_rmw_and(memarg)
+        | i32_atomic_rmw8_and_u(memarg)
+        | i32_atomic_rmw16_and_u(memarg)
+        | i64_atomic_rmw8_and_u(memarg)
+        | i64_atomic_rmw16_and_u(memarg)
+        | i64_atomic_rmw32_and_u(memarg)
+        | i32_atomic_rmw_or(memarg)
+        | i64_atomic_rmw_or(memarg)
+        | i32_atomic_rmw8_or_u(memarg)
+        | i32_atomic_rmw16_or_u(memarg)
+        | i64_atomic_rmw8_or_u(memarg)
+        | i64_atomic_rmw16_or_u(memarg)
+        | i64_atomic_rmw32_or_u(memarg)
+        | i32_atomic_rmw_xor(memarg)
+        | i64_atomic_rmw_xor(memarg)
+        | i32_atomic_rmw8_xor_u(memarg)
+        | i32_atomic_rmw16_xor_u(memarg)
+        | i64_atomic_rmw8_xor_u(memarg)
+        | i64_atomic_rmw16_xor_u(memarg)
+        | i64_atomic_rmw32_xor_u(memarg)
+        | i32_atomic_rmw_xchg(memarg)
+        | i64_atomic_rmw_xchg(memarg)
+        | i32_atomic_rmw8_xchg_u(memarg)
+        | i32_atomic_rmw16_xchg_u(memarg)
+        | i64_atomic_rmw8_xchg_u(memarg)
+        | i64_atomic_rmw16_xchg_u(memarg)
+        | i64_atomic_rmw32_xchg_u(memarg)
+        | i32_atomic_rmw_cmpxchg(memarg)
+        | i64_atomic_rmw_cmpxchg(memarg)
+        | i32_atomic_rmw8_cmpxchg_u(memarg)
+        | i32_atomic_rmw16_cmpxchg_u(memarg)
+        | i64_atomic_rmw8_cmpxchg_u(memarg)
+        | i64_atomic_rmw16_cmpxchg_u(memarg)
+        | i64_atomic_rmw32_cmpxchg_u(memarg) => memarg,
+        _ => return None,
+    };
+    MEMORY_INSTRUCTIONS
+        .iter()
+        .find(|(_, constructor, _)| constructor(memarg.clone()) == *instruction)
+        .map(|(mnemonic, _, align)| (*mnemonic, memarg, *align))
+}
+
+/// The mnemonic and lane index of an instruction with a lane index immediate
+pub(crate) fn lane_instruction(instruction: &Instructions) -> Option<(&'static str, u8)> {
+    let lane = match instruction {
+        i8x16_extract_lane_s(lane)
+        | i8x16_extract_lane_u(lane)
+        | i8x16_replace_lane(lane)
+        | i16x8_extract_lane_s(lane)
+        | i16x8_extract_lane_u(lane)
+        | i16x8_replace_lane(lane)
+        | i32x4_extract_lane(lane)
+        | i32x4_replace_lane(lane)
+        | i64x2_extract_lane(lane)
+        | i64x2_replace_lane(lane)
+        | f32x4_extract_lane(lane)
+        | f32x4_replace_lane(lane)
+        | f64x2_extract_lane(lane)
+        | f64x2_replace_lane(lane) => *lane,
+        _ => return None,
+    };
+    LANE_INSTRUCTIONS
+        .iter()
+        .find(|(_, constructor)| constructor(lane) == *instruction)
+        .map(|(mnemonic, _)| (*mnemonic, lane))
+}
+
+/// The mnemonic, memarg, natural alignment and lane index of a single lane load or store
+pub(crate) fn lane_memory_instruction(
+    instruction: &Instructions,
+) -> Option<(&'static str, &MemArg, u32, u8)> {
+    let (memarg, lane) = match instruction {
+        v128_load8_lane(memarg, lane)
+        | v128_load16_lane(memarg, lane)
+        | v128_load32_lane(memarg, lane)
+        | v128_load64_lane(memarg, lane)
+        | v128_store8_lane(memarg, lane)
+        | v128_store16_lane(memarg, lane)
+        | v128_store32_lane(memarg, lane)
+        | v128_store64_lane(memarg, lane) => (memarg, *lane),
+        _ => return None,
+    };
+    LANE_MEMORY_INSTRUCTIONS
+        .iter()
+        .find(|(_, constructor, _)| constructor(memarg.clone(), lane) == *instruction)
+        .map(|(mnemonic, _, align)| (*mnemonic, memarg, *align, lane))
+}
@@ -0,0 +1,102 @@
+use bytereader::ByteReader;
+
+use crate::{
+    error::WasmParserError,
+    instructions::{read_expr, read_expr_offsets},
+    leb128::Leb128Readers,
+    types::{
+        DataSegment, ElementSegment, ExportDesc, FunctionType, GlobalType, ImportDesc, Limits,
+        Name, TableType, TagType, ValueType,
+    },
+    wasm::read_header,
+};
+
+/// Where the parts of a binary module start, shown in the comments of the printer
+#[derive(Debug, Default)]
+pub(super) struct Offsets {
+    /// The offset of every entry of the non-custom sections by section id, the start section has a single entry
+    pub entries: [Vec<usize>; 14],
+    /// The offset of every custom section, in order
+    pub custom: Vec<usize>,
+    /// The opcodes of every function body, see [`read_expr_offsets`]
+    pub code: Vec<Vec<(usize, u8)>>,
+}
+
+impl Offsets {
+    /// Walks the sections of a module, which has to parse
+    pub(super) fn read(bytes: &[u8]) -> Result<Self, WasmParserError> {
+        let mut offsets = Offsets::default();
+        let mut reader = ByteReader::from_slice(bytes);
+        read_header(&mut reader)?;
+
+        while let Ok(id) = reader.read::<u8>() {
+            let section_start = reader.get_current_offset() - 1;
+            let size = reader.read_uleb128::<u32>()? as usize;
+            let section_end = reader.get_current_offset() + size;
+            match id {
+                0 => offsets.custom.push(section_start),
+                8 => offsets.entries[8].push(reader.get_current_offset()),
+                12 => {}
+                _ => {
+                    for _ in 0..reader.read_uleb128::<u32>()? {
+                        offsets.entries[id as usize].push(reader.get_current_offset());
+                        offsets.skip_entry(&mut reader, id)?;
+                    }
+                }
+            }
+            reader.move_to(section_end);
+        }
+        Ok(offsets)
+    }
+
+    fn skip_entry(&mut self, reader: &mut ByteReader, id: u8) -> Result<(), WasmParserError> {
+        match id {
+            1 => {
+                reader.read::<FunctionType>()?;
+            }
+            2 => {
+                reader.read::<Name>()?;
+                reader.read::<Name>()?;
+                reader.read::<ImportDesc>()?;
+            }
+            4 => {
+                reader.read::<TableType>()?;
+            }
+            5 => {
+                reader.read::<Limits>()?;
+            }
+            6 => {
+                reader.read::<GlobalType>()?;
+                read_expr(reader)?;
+            }
+            7 => {
+                reader.read::<Name>()?;
+                reader.read::<ExportDesc>()?;
+            }
+            9 => {
+                reader.read::<ElementSegment>()?;
+            }
+            10 => {
+                let size = reader.read_uleb128::<u32>()? as usize;
+                let end = reader.get_current_offset() + size;
+                for _ in 0..reader.read_uleb128::<u32>()? {
+                    reader.read_uleb128::<u32>()?;
+                    reader.read::<ValueType>()?;
+                }
+                self.code.push(read_expr_offsets(reader)?);
+                reader.move_to(end);
+            }
+            11 => {
+                reader.read::<DataSegment>()?;
+            }
+            13 => {
+                reader.read::<TagType>()?;
+            }
+            // The function section holds type indices
+            _ => {
+                reader.read_uleb128::<u32>()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1992 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use bytereader::ByteWriter;
+
+use super::{
+    lexer::{text_error, tokenize, Token, TokenKind},
+    mnemonics::{
+        plain_instruction, LANE_INSTRUCTIONS, LANE_MEMORY_INSTRUCTIONS, MEMORY_INSTRUCTIONS,
+    },
+    Space, SECTION_KEYWORDS,
+};
+use crate::{
+    custom::CustomSection,
+    error::WasmParserError,
+    instructions::{BlockType, Catch, Instructions, MemArg, MAX_NESTING_DEPTH},
+    names::{NameMap, NameSection},
+    sections::{WasmSections, SECTION_ORDER},
+    types::{
+        Code, DataSegment, ElementInit, ElementMode, ElementSegment, ExportDesc, Expr,
+        FunctionType, GlobalType, HeapType, ImportDesc, Indecies, Limits, Locals, Mutability, Name,
+        NumberTypes, ReferenceTypes, SegmentMode, TableType, TagType, ValueType, VectorTypes,
+    },
+};
+
+/// Parses a module in the text format and encodes it in the binary format.
+///
+/// Symbolic `$names`, folded instructions, inline import and export abbreviations and
+/// `(@custom ...)` annotations are resolved, see: https://webassembly.github.io/spec/core/text/index.html.
+/// The `$names` are kept in a `name` custom section at the end, unless the module has its own.
+/// Error offsets point into `source`
+pub fn parse(source: &str) -> Result<Vec<u8>, WasmParserError> {
+    let mut parser = Parser {
+        source,
+        tokens: tokenize(source)?,
+        position: 0,
+        sections: WasmSections::default(),
+        spaces: Default::default(),
+        customs: vec![],
+        names: NameSection::default(),
+        has_definitions: false,
+        uses_data_count: false,
+    };
+    parser.module()?;
+
+    let mut writer = ByteWriter::new();
+    writer
+        .write_bytes(b"\0asm")
+        .write_bytes(&[0x01, 0x00, 0x00, 0x00]);
+    parser.sections.write(&mut writer);
+    Ok(writer.into_vec())
+}
+
+/// Assigns indices in one index space, in the order they're declared
+#[derive(Default)]
+struct Namespace<'a> {
+    ids: HashMap<&'a str, u32>,
+    count: u32,
+}
+
+/// Where an `(@custom ...)` annotation places its section, see:
+/// https://github.com/WebAssembly/tool-conventions/blob/main/Annotations.md#custom-sections
+enum Placement {
+    Before(Option<u8>),
+    After(Option<u8>),
+}
+
+/// The labels and locals a function body can refer to
+#[derive(Default)]
+struct Scope<'a> {
+    locals: HashMap<&'a str, u32>,
+    /// The labels of the enclosing blocks, innermost last
+    labels: Vec<Option<&'a str>>,
+    /// The number of blocks entered so far, which is the index of the next label in the name section
+    blocks: u32,
+    label_names: NameMap,
+}
+
+impl<'a> Scope<'a> {
+    /// Brings the label of a block into scope, blocks are numbered in the order they're entered
+    fn enter(&mut self, label: Option<&'a str>) {
+        if let Some(label) = label {
+            self.label_names
+                .push((self.blocks, Name::from(label.to_string())));
+        }
+        self.blocks += 1;
+        self.labels.push(label);
+    }
+}
+
+/// A block or folded instruction whose closing `end` or `)` hasn't been read yet
+enum Open<'a> {
+    Flat {
+        kind: &'a str,
+        label: Option<&'a str>,
+        ty: BlockType,
+        catches: Vec<Catch>,
+        /// The `then` branch of an `if`, once its `else` has been read
+        then: Option<Expr>,
+        /// The instruction sequence the block is nested in
+        outer: Expr,
+    },
+    /// A folded plain instruction, pushed after its operands once the `)` is read
+    Folded(Instructions),
+    FoldedBlock {
+        kind: &'a str,
+        ty: BlockType,
+        catches: Vec<Catch>,
+        outer: Expr,
+    },
+    FoldedIf {
+        label: Option<&'a str>,
+        ty: BlockType,
+        phase: IfPhase,
+        then: Expr,
+        /// The instruction sequence the `if` is nested in, the condition is folded into it
+        outer: Expr,
+    },
+}
+
+/// Which part of a folded `if` is being parsed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IfPhase {
+    Condition,
+    Then,
+    AfterThen,
+    Else,
+    AfterElse,
+}
+
+/// The names of the parameters in a type use, along with their offsets
+type ParamNames<'a> = Vec<Option<(&'a str, usize)>>;
+
+/// A module field, `(func ...)`, `(@custom ...)` and so on
+struct Field<'a> {
+    kind: &'a str,
+    annotation: bool,
+    /// The index of the token after the field keyword
+    position: usize,
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    /// The index of the next token
+    position: usize,
+    sections: WasmSections<'static>,
+    spaces: [Namespace<'a>; 8],
+    customs: Vec<(CustomSection<'static>, Placement)>,
+    /// The module name and the names of locals and labels, the other `$names` are taken from `spaces` once the
+    /// module is parsed
+    names: NameSection,
+    /// Whether a function, table, memory, global or tag that isn't imported has been declared
+    has_definitions: bool,
+    /// Whether a `memory.init` or `data.drop` instruction needs the data count section
+    uses_data_count: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn module(&mut self) -> Result<(), WasmParserError> {
+        let wrapped = self.peek_list("module");
+        if wrapped {
+            self.position += 2;
+            self.names.module = self.id().map(|id| Name::from(id.to_string()));
+        }
+
+        let mut fields = vec![];
+        while let Some(token) = self.tokens.get(self.position) {
+            match token.kind {
+                TokenKind::RParen if wrapped => break,
+                TokenKind::Annotation(kind) => {
+                    fields.push(Field {
+                        kind,
+                        annotation: true,
+                        position: self.position + 1,
+                    });
+                }
+                TokenKind::LParen => {
+                    match self.tokens.get(self.position + 1).map(|token| &token.kind) {
+                        Some(TokenKind::Keyword(kind)) => fields.push(Field {
+                            kind,
+                            annotation: false,
+                            position: self.position + 2,
+                        }),
+                        _ => {
+                            return Err(self.error_at(self.position + 1, "expected a module field"))
+                        }
+                    }
+                }
+                _ => return Err(self.error("expected a module field")),
+            }
+            self.skip_list()?;
+        }
+        if wrapped {
+            self.expect_rparen()?;
+        }
+        if self.position < self.tokens.len() {
+            return Err(self.error("unexpected tokens after the module"));
+        }
+
+        // Annotations other than `@custom` are ignored
+        for field in fields.iter().filter(|field| !field.annotation) {
+            self.position = field.position;
+            self.declare(field.kind)?;
+        }
+        for field in &fields {
+            self.position = field.position;
+            match (field.annotation, field.kind) {
+                (true, "custom") => self.custom()?,
+                (false, "type") | (true, _) => continue,
+                (false, kind) => self.define(kind)?,
+            }
+            self.expect_rparen()?;
+        }
+
+        if self.uses_data_count {
+            self.sections.data_count = Some(self.sections.data.len() as u32);
+        }
+        if !self
+            .customs
+            .iter()
+            .any(|(section, _)| section.name.as_str() == "name")
+        {
+            self.name_section();
+        }
+        for (mut section, placement) in std::mem::take(&mut self.customs) {
+            section.after = self.resolve_placement(placement);
+            self.sections.custom.push(section);
+        }
+        Ok(())
+    }
+
+    /// Assigns indices to the `$names` a field declares, so they can be referred to before their definition.
+    /// Types are parsed completely, since inline function types are matched against them
+    fn declare(&mut self, kind: &'a str) -> Result<(), WasmParserError> {
+        let offset = self.offset();
+        let field_offset = self.tokens[self.position - 2].offset;
+        match kind {
+            "type" => {
+                let id = self.id();
+                self.declare_index(Space::Type, id, offset)?;
+                self.expect_list("func")?;
+                let (ty, _) = self.function_type()?;
+                self.expect_rparen()?;
+                self.sections.types.push(ty);
+                self.expect_rparen()?;
+            }
+            "import" => {
+                self.string()?;
+                self.string()?;
+                self.expect_lparen()?;
+                let space = match self.keyword()? {
+                    "func" => Space::Func,
+                    "table" => Space::Table,
+                    "memory" => Space::Memory,
+                    "global" => Space::Global,
+                    "tag" => Space::Tag,
+                    other => {
+                        return Err(self.error_before(&format!("unknown import kind '{other}'")))
+                    }
+                };
+                self.check_import_order(field_offset)?;
+                let id = self.id();
+                self.declare_index(space, id, offset)?;
+            }
+            "func" | "table" | "memory" | "global" | "tag" => {
+                let space = match kind {
+                    "func" => Space::Func,
+                    "table" => Space::Table,
+                    "memory" => Space::Memory,
+                    "global" => Space::Global,
+                    _ => Space::Tag,
+                };
+                let id = self.id();
+                self.declare_index(space, id, offset)?;
+                self.exports()?;
+                if self.peek_list("import") {
+                    self.check_import_order(field_offset)?;
+                } else {
+                    self.has_definitions = true;
+                }
+
+                // The inline `(elem ...)` and `(data ...)` abbreviations also declare a segment
+                if kind == "table" && self.peek_inline_elem() {
+                    self.declare_index(Space::Elem, None, offset)?;
+                }
+                if kind == "memory" && self.peek_list("data") {
+                    self.declare_index(Space::Data, None, offset)?;
+                }
+            }
+            "elem" | "data" => {
+                let id = self.id();
+                let space = if kind == "elem" {
+                    Space::Elem
+                } else {
+                    Space::Data
+                };
+                self.declare_index(space, id, offset)?;
+            }
+            "export" | "start" => {}
+            other => {
+                return Err(self.error_at(
+                    self.position - 1,
+                    &format!("unknown module field '{other}'"),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the contents of a field to the module, `declare` has seen every field by now
+    fn define(&mut self, kind: &'a str) -> Result<(), WasmParserError> {
+        match kind {
+            "import" => self.import(),
+            "func" => self.func(),
+            "table" => self.table(),
+            "memory" => self.memory(),
+            "global" => self.global(),
+            "tag" => self.tag(),
+            "export" => {
+                let name = self.name()?;
+                let desc = self.export_desc()?;
+                self.sections.export.push((name, desc));
+                Ok(())
+            }
+            "start" => {
+                if self.sections.start.is_some() {
+                    return Err(self.error_before("multiple start functions"));
+                }
+                self.sections.start = Some(Indecies::FuncIdx(self.index(Space::Func)?));
+                Ok(())
+            }
+            "elem" => self.elem(),
+            _ => self.data(),
+        }
+    }
+
+    fn import(&mut self) -> Result<(), WasmParserError> {
+        let module = self.name()?;
+        let name = self.name()?;
+        self.expect_lparen()?;
+        let kind = self.keyword()?;
+        self.id();
+        let desc = self.import_desc(kind)?;
+        self.expect_rparen()?;
+        self.sections.imports.push((module, name, desc));
+        Ok(())
+    }
+
+    fn import_desc(&mut self, kind: &str) -> Result<ImportDesc, WasmParserError> {
+        Ok(match kind {
+            "func" => ImportDesc::TypeIdx(Indecies::TypeIdx(self.type_use()?.0)),
+            "table" => ImportDesc::TableType(self.table_type()?),
+            "memory" => ImportDesc::MemType(self.limits()?),
+            "tag" => ImportDesc::TagType(TagType {
+                ty: self.type_use()?.0,
+            }),
+            _ => ImportDesc::GlobalType(self.global_type()?),
+        })
+    }
+
+    /// Parses an inline `(import "module" "name")` abbreviation, if there is one
+    fn inline_import(&mut self, kind: &str) -> Result<bool, WasmParserError> {
+        if !self.peek_list("import") {
+            return Ok(false);
+        }
+        self.position += 2;
+        let module = self.name()?;
+        let name = self.name()?;
+        self.expect_rparen()?;
+        let desc = self.import_desc(kind)?;
+        self.sections.imports.push((module, name, desc));
+        Ok(true)
+    }
+
+    /// Parses the inline `(export "name")` abbreviations of the item at `index`
+    fn inline_exports(&mut self, desc: ExportDesc) -> Result<(), WasmParserError> {
+        while self.peek_list("export") {
+            self.position += 2;
+            let name = self.name()?;
+            self.expect_rparen()?;
+            self.sections.export.push((name, desc));
+        }
+        Ok(())
+    }
+
+    fn func(&mut self) -> Result<(), WasmParserError> {
+        self.id();
+        let index = self.defined_index(Space::Func);
+        self.inline_exports(ExportDesc::FuncIdx(index))?;
+        if self.inline_import("func")? {
+            return Ok(());
+        }
+
+        let (type_index, params) = self.type_use()?;
+        let mut scope = Scope::default();
+        for (index, param) in params.iter().enumerate() {
+            if let Some((id, offset)) = param {
+                if scope.locals.insert(id, index as u32).is_some() {
+                    return Err(text_error(
+                        self.source,
+                        *offset,
+                        format!("duplicate local ${id}"),
+                    ));
+                }
+            }
+        }
+
+        let mut locals: Locals = vec![];
+        let mut count = params.len() as u32;
+        while self.peek_list("local") {
+            self.position += 2;
+            let mut types = vec![];
+            if let Some(id) = self.id() {
+                if scope.locals.insert(id, count).is_some() {
+                    return Err(self.error_before(&format!("duplicate local ${id}")));
+                }
+                types.push(self.value_type()?);
+            } else {
+                while !self.peek_rparen() {
+                    types.push(self.value_type()?);
+                }
+            }
+            self.expect_rparen()?;
+
+            // Runs of locals with the same type share an entry, like in the binary format
+            for ty in types {
+                count += 1;
+                match locals.last_mut() {
+                    Some((run, last)) if *last == ty => *run += 1,
+                    _ => locals.push((1, ty)),
+                }
+            }
+        }
+
+        let body = self.instructions(&mut scope)?;
+        let mut local_names: NameMap = scope
+            .locals
+            .iter()
+            .map(|(id, index)| (*index, Name::from(id.to_string())))
+            .collect();
+        local_names.sort_by_key(|(index, _)| *index);
+        if !local_names.is_empty() {
+            self.names.locals.push((index, local_names));
+        }
+        if !scope.label_names.is_empty() {
+            self.names.labels.push((index, scope.label_names));
+        }
+        self.sections.functions.push(Indecies::TypeIdx(type_index));
+        self.sections.code.push(Code::new(locals, body));
+        Ok(())
+    }
+
+    fn table(&mut self) -> Result<(), WasmParserError> {
+        self.id();
+        let index = self.defined_index(Space::Table);
+        self.inline_exports(ExportDesc::TableIdx(index))?;
+        if self.inline_import("table")? {
+            return Ok(());
+        }
+
+        // `(table reftype (elem ...))` declares a table just large enough for the segment
+        let is_64 = self.index_type();
+        if self.peek_inline_elem() {
+            let ty = self.reference_type()?;
+            self.expect_list("elem")?;
+            let init = if self.peek_index() {
+                ElementInit::Functions(self.function_indices()?)
+            } else {
+                ElementInit::Expressions(self.element_expressions()?)
+            };
+            self.expect_rparen()?;
+
+            let length = match &init {
+                ElementInit::Functions(indices) => indices.len(),
+                ElementInit::Expressions(exprs) => exprs.len(),
+            } as u64;
+            self.sections.tables.push(TableType {
+                elem: ty,
+                lim: Limits {
+                    min: length,
+                    max: Some(length),
+                    shared: false,
+                    is_64,
+                },
+            });
+            self.sections.element.push(ElementSegment {
+                ty,
+                init,
+                mode: ElementMode::Active {
+                    table_index: index,
+                    offset: vec![zero_offset(is_64)],
+                },
+            });
+            return Ok(());
+        }
+
+        let lim = self.bounds(is_64)?;
+        let elem = self.reference_type()?;
+        self.sections.tables.push(TableType { elem, lim });
+        Ok(())
+    }
+
+    fn memory(&mut self) -> Result<(), WasmParserError> {
+        self.id();
+        let index = self.defined_index(Space::Memory);
+        self.inline_exports(ExportDesc::MemIdx(index))?;
+        if self.inline_import("memory")? {
+            return Ok(());
+        }
+
+        // `(memory (data ...))` declares a memory just large enough for the segment
+        let is_64 = self.index_type();
+        if self.peek_list("data") {
+            self.position += 2;
+            let bytes = self.strings()?;
+            self.expect_rparen()?;
+
+            let pages = bytes.len().div_ceil(65536) as u64;
+            self.sections.memory.push(Limits {
+                min: pages,
+                max: Some(pages),
+                shared: false,
+                is_64,
+            });
+            self.sections.data.push(DataSegment {
+                mode: SegmentMode::Active {
+                    memory_index: index,
+                    offset: vec![zero_offset(is_64)],
+                },
+                bytes: Cow::Owned(bytes),
+            });
+            return Ok(());
+        }
+
+        let limits = self.bounds(is_64)?;
+        self.sections.memory.push(limits);
+        Ok(())
+    }
+
+    fn global(&mut self) -> Result<(), WasmParserError> {
+        self.id();
+        let index = self.defined_index(Space::Global);
+        self.inline_exports(ExportDesc::GlobalIdx(index))?;
+        if self.inline_import("global")? {
+            return Ok(());
+        }
+
+        let ty = self.global_type()?;
+        let init = self.instructions(&mut Scope::default())?;
+        self.sections.global.push((ty, init));
+        Ok(())
+    }
+
+    fn tag(&mut self) -> Result<(), WasmParserError> {
+        self.id();
+        let index = self.defined_index(Space::Tag);
+        self.inline_exports(ExportDesc::TagIdx(index))?;
+        if self.inline_import("tag")? {
+            return Ok(());
+        }
+
+        let (ty, _) = self.type_use()?;
+        self.sections.tags.push(TagType { ty });
+        Ok(())
+    }
+
+    fn elem(&mut self) -> Result<(), WasmParserError> {
+        self.id();
+
+        let mode = if self.keyword_is("declare") {
+            self.position += 1;
+            ElementMode::Declarative
+        } else if self.peek_list("table") || self.peek_offset() {
+            let table_index = if self.peek_list("table") {
+                self.position += 2;
+                let index = self.index(Space::Table)?;
+                self.expect_rparen()?;
+                index
+            } else {
+                0
+            };
+            ElementMode::Active {
+                table_index,
+                offset: self.offset_expression()?,
+            }
+        } else {
+            ElementMode::Passive
+        };
+
+        let (ty, init) = match self.peek() {
+            Some(TokenKind::Keyword("func")) => {
+                self.position += 1;
+                (
+                    ReferenceTypes::funcref,
+                    ElementInit::Functions(self.function_indices()?),
+                )
+            }
+            Some(TokenKind::Keyword(_)) => {
+                let ty = self.reference_type()?;
+                (ty, ElementInit::Expressions(self.element_expressions()?))
+            }
+            _ if self.peek_list("ref") => {
+                let ty = self.reference_type()?;
+                (ty, ElementInit::Expressions(self.element_expressions()?))
+            }
+            // Function indices without `func` are only allowed after an offset
+            _ if matches!(mode, ElementMode::Active { .. }) => (
+                ReferenceTypes::funcref,
+                ElementInit::Functions(self.function_indices()?),
+            ),
+            _ => return Err(self.error("expected an element type")),
+        };
+
+        self.sections
+            .element
+            .push(ElementSegment { ty, init, mode });
+        Ok(())
+    }
+
+    fn data(&mut self) -> Result<(), WasmParserError> {
+        self.id();
+
+        let mode = if self.peek_list("memory") || self.peek_offset() {
+            let memory_index = if self.peek_list("memory") {
+                self.position += 2;
+                let index = self.index(Space::Memory)?;
+                self.expect_rparen()?;
+                index
+            } else {
+                0
+            };
+            SegmentMode::Active {
+                memory_index,
+                offset: self.offset_expression()?,
+            }
+        } else {
+            SegmentMode::Passive
+        };
+
+        let bytes = self.strings()?;
+        self.sections.data.push(DataSegment {
+            mode,
+            bytes: Cow::Owned(bytes),
+        });
+        Ok(())
+    }
+
+    fn custom(&mut self) -> Result<(), WasmParserError> {
+        let name = self.name()?;
+
+        let mut placement = Placement::After(None);
+        if self.peek_list("before") || self.peek_list("after") {
+            let before = self.peek_list("before");
+            self.position += 2;
+            let section = match self.keyword()? {
+                "first" if before => None,
+                "last" if !before => None,
+                section => Some(
+                    SECTION_KEYWORDS
+                        .iter()
+                        .find(|(keyword, _)| *keyword == section)
+                        .map(|(_, id)| *id)
+                        .ok_or_else(|| {
+                            self.error_before(&format!("unknown section '{section}'"))
+                        })?,
+                ),
+            };
+            self.expect_rparen()?;
+            placement = match before {
+                true => Placement::Before(section),
+                false => Placement::After(section),
+            };
+        }
+
+        let data = self.strings()?;
+        self.customs.push((
+            CustomSection {
+                name,
+                data: Cow::Owned(data),
+                range: 0..0,
+                after: None,
+                decoded: None,
+                decode_error: None,
+            },
+            placement,
+        ));
+        Ok(())
+    }
+
+    /// Adds a `name` custom section after the other sections if any `$names` were declared
+    fn name_section(&mut self) {
+        let mut names = std::mem::take(&mut self.names);
+        let maps = [
+            (Space::Type, &mut names.types),
+            (Space::Func, &mut names.functions),
+            (Space::Table, &mut names.tables),
+            (Space::Memory, &mut names.memories),
+            (Space::Global, &mut names.globals),
+            (Space::Tag, &mut names.tags),
+            (Space::Elem, &mut names.elements),
+            (Space::Data, &mut names.data),
+        ];
+        for (space, map) in maps {
+            *map = self.spaces[space as usize]
+                .ids
+                .iter()
+                .map(|(id, index)| (*index, Name::from(id.to_string())))
+                .collect();
+            map.sort_by_key(|(index, _)| *index);
+        }
+        if names == NameSection::default() {
+            return;
+        }
+
+        let mut data = ByteWriter::new();
+        data.write(&names);
+        self.customs.push((
+            CustomSection {
+                name: Name::from("name"),
+                data: Cow::Owned(data.into_vec()),
+                range: 0..0,
+                after: None,
+                decoded: None,
+                decode_error: None,
+            },
+            Placement::After(None),
+        ));
+    }
+
+    /// Turns a placement into the id of the section the custom section follows
+    fn resolve_placement(&self, placement: Placement) -> Option<u8> {
+        let present = |id: &&u8| match id {
+            1 => !self.sections.types.is_empty(),
+            2 => !self.sections.imports.is_empty(),
+            3 => !self.sections.functions.is_empty(),
+            4 => !self.sections.tables.is_empty(),
+            5 => !self.sections.memory.is_empty(),
+            13 => !self.sections.tags.is_empty(),
+            6 => !self.sections.global.is_empty(),
+            7 => !self.sections.export.is_empty(),
+            8 => self.sections.start.is_some(),
+            9 => !self.sections.element.is_empty(),
+            12 => self.sections.data_count.is_some(),
+            10 => !self.sections.code.is_empty(),
+            _ => !self.sections.data.is_empty(),
+        };
+        match placement {
+            Placement::After(Some(id)) => Some(id),
+            Placement::After(None) => SECTION_ORDER.iter().rev().find(present).copied(),
+            Placement::Before(None) => None,
+            Placement::Before(Some(id)) => SECTION_ORDER
+                .iter()
+                .take_while(|section| **section != id)
+                .filter(present)
+                .last()
+                .copied(),
+        }
+    }
+
+    /// Parses `(type x)? (param ...)* (result ...)*`, returning the type index along with the
+    /// names and offsets of the parameters. Function types without a `(type x)` are added to the
+    /// type section unless an identical one exists
+    fn type_use(&mut self) -> Result<(u32, ParamNames<'a>), WasmParserError> {
+        let offset = self.offset();
+        let explicit = if self.peek_list("type") {
+            self.position += 2;
+            let index = self.index(Space::Type)?;
+            self.expect_rparen()?;
+            Some(index)
+        } else {
+            None
+        };
+
+        let inline_start = self.position;
+        let (ty, params) = self.function_type()?;
+        let inline = self.position != inline_start;
+
+        match explicit {
+            Some(index) => {
+                let declared = self.sections.types.get(index as usize).ok_or_else(|| {
+                    text_error(self.source, offset, format!("unknown type {index}"))
+                })?;
+                if inline && *declared != ty {
+                    return Err(text_error(
+                        self.source,
+                        offset,
+                        "inline function type doesn't match the referenced type".to_string(),
+                    ));
+                }
+                let params = match inline {
+                    true => params,
+                    false => vec![None; declared.params.len()],
+                };
+                Ok((index, params))
+            }
+            None => {
+                let index = match self
+                    .sections
+                    .types
+                    .iter()
+                    .position(|existing| *existing == ty)
+                {
+                    Some(index) => index,
+                    None => {
+                        self.sections.types.push(ty);
+                        self.sections.types.len() - 1
+                    }
+                };
+                Ok((index as u32, params))
+            }
+        }
+    }
+
+    /// Parses `(param ...)* (result ...)*`, returning the type and the names and offsets of the parameters
+    fn function_type(&mut self) -> Result<(FunctionType, ParamNames<'a>), WasmParserError> {
+        let mut params = vec![];
+        let mut names = vec![];
+        while self.peek_list("param") {
+            self.position += 2;
+            let offset = self.offset();
+            if let Some(id) = self.id() {
+                params.push(self.value_type()?);
+                names.push(Some((id, offset)));
+            } else {
+                while !self.peek_rparen() {
+                    params.push(self.value_type()?);
+                    names.push(None);
+                }
+            }
+            self.expect_rparen()?;
+        }
+
+        let mut result = vec![];
+        while self.peek_list("result") {
+            self.position += 2;
+            while !self.peek_rparen() {
+                result.push(self.value_type()?);
+            }
+            self.expect_rparen()?;
+        }
+
+        Ok((FunctionType { params, result }, names))
+    }
+
+    /// Parses the type of a block. Unlike other type uses, an inline type without params and with
+    /// at most one result doesn't add a function type to the type section
+    fn block_type(&mut self) -> Result<BlockType, WasmParserError> {
+        let start = self.position;
+        if !self.peek_list("type") {
+            let (ty, _) = self.function_type()?;
+            match (ty.params.as_slice(), ty.result.as_slice()) {
+                ([], []) => return Ok(BlockType::Empty),
+                ([], [result]) => return Ok(BlockType::Value(*result)),
+                _ => self.position = start,
+            }
+        }
+        let (index, _) = self.type_use()?;
+        Ok(BlockType::TypeIndex(index))
+    }
+
+    /// Parses instructions, flat or folded, up to the `)` that closes them
+    fn instructions(&mut self, scope: &mut Scope<'a>) -> Result<Expr, WasmParserError> {
+        self.instruction_sequence(scope, false)
+    }
+
+    /// Parses a single folded instruction, like the `(i32.const 0)` offset of a segment
+    fn folded_instruction(&mut self, scope: &mut Scope<'a>) -> Result<Expr, WasmParserError> {
+        if self.peek() != Some(&TokenKind::LParen) {
+            return Err(self.error("expected '('"));
+        }
+        self.instruction_sequence(scope, true)
+    }
+
+    /// Parses instructions up to the `)` that closes them, or just the first one if `single` is set.
+    ///
+    /// Like [`crate::instructions::read_expr`], nested blocks and folded instructions are tracked on
+    /// an explicit stack instead of by recursion, so deeply nested input can't overflow the call stack
+    fn instruction_sequence(
+        &mut self,
+        scope: &mut Scope<'a>,
+        single: bool,
+    ) -> Result<Expr, WasmParserError> {
+        let mut expr = vec![];
+        let mut open: Vec<Open<'a>> = vec![];
+        loop {
+            match (self.peek(), open.last_mut()) {
+                (
+                    Some(TokenKind::LParen),
+                    Some(Open::FoldedIf {
+                        label,
+                        phase,
+                        outer,
+                        ..
+                    }),
+                ) if *phase == IfPhase::Condition && self.peek_list("then") => {
+                    self.position += 2;
+                    scope.enter(*label);
+                    *outer = std::mem::take(&mut expr);
+                    *phase = IfPhase::Then;
+                }
+                (Some(TokenKind::LParen), Some(Open::FoldedIf { phase, .. }))
+                    if *phase == IfPhase::AfterThen && self.peek_list("else") =>
+                {
+                    self.position += 2;
+                    *phase = IfPhase::Else;
+                }
+                (
+                    Some(TokenKind::LParen),
+                    Some(Open::FoldedIf {
+                        phase: IfPhase::AfterThen | IfPhase::AfterElse,
+                        ..
+                    }),
+                ) => {
+                    return Err(self.error("expected '(else' or ')'"));
+                }
+                (Some(TokenKind::LParen), _) => {
+                    self.position += 1;
+                    match self.keyword()? {
+                        kind @ ("block" | "loop" | "try_table") => {
+                            self.check_nesting(&open)?;
+                            let label = self.id();
+                            let ty = self.block_type()?;
+                            let catches = self.catches(kind, scope)?;
+                            scope.enter(label);
+                            open.push(Open::FoldedBlock {
+                                kind,
+                                ty,
+                                catches,
+                                outer: std::mem::take(&mut expr),
+                            });
+                        }
+                        "if" => {
+                            self.check_nesting(&open)?;
+                            let label = self.id();
+                            let ty = self.block_type()?;
+                            open.push(Open::FoldedIf {
+                                label,
+                                ty,
+                                phase: IfPhase::Condition,
+                                then: vec![],
+                                outer: vec![],
+                            });
+                        }
+                        _ => {
+                            self.position -= 1;
+                            let instruction = self.instruction(scope)?;
+                            open.push(Open::Folded(instruction));
+                        }
+                    }
+                }
+                (Some(TokenKind::RParen), Some(Open::FoldedIf { phase, then, .. }))
+                    if matches!(phase, IfPhase::Then | IfPhase::Else) =>
+                {
+                    self.position += 1;
+                    if *phase == IfPhase::Then {
+                        *then = std::mem::take(&mut expr);
+                        *phase = IfPhase::AfterThen;
+                    } else {
+                        *phase = IfPhase::AfterElse;
+                    }
+                }
+                (Some(TokenKind::RParen), Some(Open::FoldedIf { phase, .. }))
+                    if *phase == IfPhase::Condition =>
+                {
+                    return Err(self.error("expected '(then'"));
+                }
+                (Some(TokenKind::RParen), Some(Open::Flat { .. })) | (None, Some(_)) => {
+                    return Err(self.error("expected 'end'"));
+                }
+                (Some(TokenKind::RParen), Some(_)) => {
+                    self.position += 1;
+                    let instruction = match open.pop() {
+                        Some(Open::Folded(instruction)) => instruction,
+                        Some(Open::FoldedBlock {
+                            kind,
+                            ty,
+                            catches,
+                            outer,
+                        }) => {
+                            scope.labels.pop();
+                            let body = std::mem::replace(&mut expr, outer);
+                            structured(kind, ty, catches, body, vec![])
+                        }
+                        Some(Open::FoldedIf {
+                            ty, then, outer, ..
+                        }) => {
+                            scope.labels.pop();
+                            let else_ = std::mem::replace(&mut expr, outer);
+                            structured("if", ty, vec![], then, else_)
+                        }
+                        _ => unreachable!("flat blocks are closed by 'end'"),
+                    };
+                    expr.push(instruction);
+                    if single && open.is_empty() {
+                        return Ok(expr);
+                    }
+                }
+                (Some(TokenKind::RParen), None) | (None, None) => return Ok(expr),
+                // Folded instructions only take folded operands, and the condition of a folded `if` is folded too
+                (Some(TokenKind::Keyword(_)), Some(Open::Folded(_))) => {
+                    return Err(self.error("expected '(' or ')'"));
+                }
+                (Some(TokenKind::Keyword(_)), Some(Open::FoldedIf { phase, .. }))
+                    if !matches!(phase, IfPhase::Then | IfPhase::Else) =>
+                {
+                    return Err(self.error("expected '(' or ')'"));
+                }
+                (Some(TokenKind::Keyword(kind @ ("block" | "loop" | "if" | "try_table"))), _) => {
+                    let kind = *kind;
+                    self.position += 1;
+                    self.check_nesting(&open)?;
+                    let label = self.id();
+                    let ty = self.block_type()?;
+                    let catches = self.catches(kind, scope)?;
+                    scope.enter(label);
+                    open.push(Open::Flat {
+                        kind,
+                        label,
+                        ty,
+                        catches,
+                        then: None,
+                        outer: std::mem::take(&mut expr),
+                    });
+                }
+                (
+                    Some(TokenKind::Keyword("else")),
+                    Some(Open::Flat {
+                        kind: "if",
+                        label,
+                        then: then @ None,
+                        ..
+                    }),
+                ) => {
+                    let label = *label;
+                    *then = Some(std::mem::take(&mut expr));
+                    self.position += 1;
+                    self.block_end_label(label)?;
+                }
+                (Some(TokenKind::Keyword("end")), Some(Open::Flat { label, .. })) => {
+                    let label = *label;
+                    self.position += 1;
+                    self.block_end_label(label)?;
+                    let Some(Open::Flat {
+                        kind,
+                        ty,
+                        catches,
+                        then,
+                        outer,
+                        ..
+                    }) = open.pop()
+                    else {
+                        unreachable!("the innermost block is flat")
+                    };
+                    scope.labels.pop();
+                    let body = std::mem::replace(&mut expr, outer);
+                    expr.push(match then {
+                        Some(then) => structured(kind, ty, catches, then, body),
+                        None => structured(kind, ty, catches, body, vec![]),
+                    });
+                }
+                (Some(TokenKind::Keyword(keyword @ ("end" | "else"))), _) => {
+                    return Err(self.error(&format!("unexpected '{keyword}'")));
+                }
+                (Some(TokenKind::Keyword(_)), _) => {
+                    let instruction = self.instruction(scope)?;
+                    expr.push(instruction);
+                }
+                (Some(_), _) => return Err(self.error("expected an instruction")),
+            }
+        }
+    }
+
+    /// Parses the catch clauses of a `try_table`, the labels they branch to are outside of it
+    fn catches(&mut self, kind: &str, scope: &Scope<'a>) -> Result<Vec<Catch>, WasmParserError> {
+        let mut catches = vec![];
+        if kind != "try_table" {
+            return Ok(catches);
+        }
+        while let Some(clause) = ["catch", "catch_ref", "catch_all", "catch_all_ref"]
+            .into_iter()
+            .find(|clause| self.peek_list(clause))
+        {
+            self.position += 2;
+            catches.push(match clause {
+                "catch" => Catch::Catch(
+                    Indecies::TagIdx(self.index(Space::Tag)?),
+                    self.label(scope)?,
+                ),
+                "catch_ref" => Catch::CatchRef(
+                    Indecies::TagIdx(self.index(Space::Tag)?),
+                    self.label(scope)?,
+                ),
+                "catch_all" => Catch::CatchAll(self.label(scope)?),
+                _ => Catch::CatchAllRef(self.label(scope)?),
+            });
+            self.expect_rparen()?;
+        }
+        Ok(catches)
+    }
+
+    /// Nesting of blocks is limited like in the binary parser, folded operands don't count
+    fn check_nesting(&self, open: &[Open<'a>]) -> Result<(), WasmParserError> {
+        let blocks = open
+            .iter()
+            .filter(|open| !matches!(open, Open::Folded(_)))
+            .count();
+        if blocks >= MAX_NESTING_DEPTH {
+            return Err(self.error_before(&format!(
+                "instructions are nested deeper than the limit of {MAX_NESTING_DEPTH}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks the optional label after `else` or `end`, which has to repeat the label of the block
+    fn block_end_label(&mut self, label: Option<&'a str>) -> Result<(), WasmParserError> {
+        let offset = self.offset();
+        match self.id() {
+            Some(id) if label != Some(id) => Err(text_error(
+                self.source,
+                offset,
+                format!("mismatched label ${id}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parses a non-structured instruction and its immediates
+    fn instruction(&mut self, scope: &mut Scope<'a>) -> Result<Instructions, WasmParserError> {
+        use Instructions::*;
+
+        let name = self.keyword()?;
+        Ok(match name {
+            "br" => Br(self.label(scope)?),
+            "br_if" => BrIf(self.label(scope)?),
+            "br_table" => {
+                let mut labels = vec![self.label(scope)?];
+                while self.peek_index() {
+                    labels.push(self.label(scope)?);
+                }
+                let default = labels.pop().expect("br_table has at least one label");
+                BrTable(labels, default)
+            }
+            "call" => Call(Indecies::FuncIdx(self.index(Space::Func)?)),
+            "call_indirect" => {
+                let table = self.optional_index(Space::Table)?.unwrap_or(0);
+                let (ty, _) = self.type_use()?;
+                CallIndirect(Indecies::TypeIdx(ty), Indecies::TableIdx(table))
+            }
+            "return_call" => ReturnCall(Indecies::FuncIdx(self.index(Space::Func)?)),
+            "return_call_indirect" => {
+                let table = self.optional_index(Space::Table)?.unwrap_or(0);
+                let (ty, _) = self.type_use()?;
+                ReturnCallIndirect(Indecies::TypeIdx(ty), Indecies::TableIdx(table))
+            }
+            "call_ref" => CallRef(Indecies::TypeIdx(self.index(Space::Type)?)),
+            "return_call_ref" => ReturnCallRef(Indecies::TypeIdx(self.index(Space::Type)?)),
+            "br_on_null" => BrOnNull(self.label(scope)?),
+            "br_on_non_null" => BrOnNonNull(self.label(scope)?),
+            "ref.null" => RefNull(ReferenceTypes::new(true, self.heap_type()?)),
+            "ref.func" => RefFunc(Indecies::FuncIdx(self.index(Space::Func)?)),
+            "throw" => Throw(Indecies::TagIdx(self.index(Space::Tag)?)),
+            "select" if self.peek_list("result") => {
+                let mut types = vec![];
+                while self.peek_list("result") {
+                    self.position += 2;
+                    while !self.peek_rparen() {
+                        types.push(self.value_type()?);
+                    }
+                    self.expect_rparen()?;
+                }
+                SelectMultiple(types)
+            }
+            "local.get" => LocalGet(self.local(scope)?),
+            "local.set" => LocalSet(self.local(scope)?),
+            "local.tee" => LocalTee(self.local(scope)?),
+            "global.get" => GlobalGet(Indecies::GlobalIdx(self.index(Space::Global)?)),
+            "global.set" => GlobalSet(Indecies::GlobalIdx(self.index(Space::Global)?)),
+            "table.get" => TableGet(self.default_table()?),
+            "table.set" => TableSet(self.default_table()?),
+            "table.size" => TableSize(self.default_table()?),
+            "table.grow" => TableGrow(self.default_table()?),
+            "table.fill" => TableFill(self.default_table()?),
+            "table.copy" => {
+                let dst = self.default_table()?;
+                let src = self.default_table()?;
+                TableCopy(dst, src)
+            }
+            "table.init" => {
+                // The table index is optional, but comes before the element index
+                let table = match self.peek_at(1) {
+                    Some(TokenKind::Id(_) | TokenKind::Reserved(_)) => self.index(Space::Table)?,
+                    _ => 0,
+                };
+                TableInit(
+                    Indecies::ElemIdx(self.index(Space::Elem)?),
+                    Indecies::TableIdx(table),
+                )
+            }
+            "elem.drop" => ElemDrop(Indecies::ElemIdx(self.index(Space::Elem)?)),
+            "memory.init" => {
+                self.uses_data_count = true;
+                MemoryInit(Indecies::DataIdx(self.index(Space::Data)?))
+            }
+            "data.drop" => {
+                self.uses_data_count = true;
+                DataDrop(Indecies::DataIdx(self.index(Space::Data)?))
+            }
+            "i32.const" => i32_const(self.number(name, parse_i32)?),
+            "i64.const" => i64_const(self.number(name, parse_i64)?),
+            "f32.const" => f32_const(self.float(name, parse_f32)?.to_bits()),
+            "f64.const" => f64_const(self.float(name, parse_f64)?.to_bits()),
+            "v128.const" => v128_const(self.v128_lanes()?),
+            "i8x16.shuffle" => {
+                let mut lanes = [0; 16];
+                for lane in &mut lanes {
+                    *lane = self.number("lane index", parse_lane_index)?;
+                }
+                i8x16_shuffle(lanes)
+            }
+            _ => {
+                if let Some((_, constructor, natural_align)) = MEMORY_INSTRUCTIONS
+                    .iter()
+                    .find(|(mnemonic, ..)| *mnemonic == name)
+                {
+                    return Ok(constructor(self.memarg(*natural_align)?));
+                }
+                if let Some((_, constructor)) = LANE_INSTRUCTIONS
+                    .iter()
+                    .find(|(mnemonic, _)| *mnemonic == name)
+                {
+                    return Ok(constructor(self.number("lane index", parse_lane_index)?));
+                }
+                if let Some((_, constructor, natural_align)) = LANE_MEMORY_INSTRUCTIONS
+                    .iter()
+                    .find(|(mnemonic, ..)| *mnemonic == name)
+                {
+                    let memarg = self.memarg(*natural_align)?;
+                    return Ok(constructor(
+                        memarg,
+                        self.number("lane index", parse_lane_index)?,
+                    ));
+                }
+                return plain_instruction(name)
+                    .ok_or_else(|| self.error_before(&format!("unknown instruction '{name}'")));
+            }
+        })
+    }
+
+    /// The shape and lanes of a `v128.const`, like `i32x4 1 2 3 4`, as a little endian integer
+    fn v128_lanes(&mut self) -> Result<u128, WasmParserError> {
+        let lanes: Vec<u128> = match self.keyword()? {
+            "i8x16" => self.lanes(16, parse_i8)?,
+            "i16x8" => self.lanes(8, parse_i16)?,
+            "i32x4" => self.lanes(4, |text| parse_i32(text).map(|lane| lane as u32))?,
+            "i64x2" => self.lanes(2, |text| parse_i64(text).map(|lane| lane as u64))?,
+            "f32x4" => (0..4)
+                .map(|_| Ok(self.float("vector lane", parse_f32)?.to_bits().into()))
+                .collect::<Result<_, WasmParserError>>()?,
+            "f64x2" => (0..2)
+                .map(|_| Ok(self.float("vector lane", parse_f64)?.to_bits().into()))
+                .collect::<Result<_, WasmParserError>>()?,
+            other => return Err(self.error_before(&format!("unknown vector shape '{other}'"))),
+        };
+        let width = 128 / lanes.len();
+        Ok(lanes
+            .iter()
+            .enumerate()
+            .fold(0, |value, (i, lane)| value | lane << (i * width)))
+    }
+
+    fn lanes<T: Into<u128>>(
+        &mut self,
+        count: usize,
+        parse: fn(&str) -> Option<T>,
+    ) -> Result<Vec<u128>, WasmParserError> {
+        (0..count)
+            .map(|_| self.number("vector lane", parse).map(Into::into))
+            .collect()
+    }
+
+    /// Parses the optional `offset=` and `align=` of a load or store, the alignment is given in bytes
+    fn memarg(&mut self, natural_align: u32) -> Result<MemArg, WasmParserError> {
+        let mut memarg = MemArg {
+            align: natural_align,
+            offset: 0,
+        };
+        if let Some(TokenKind::Keyword(keyword)) = self.peek() {
+            if let Some(offset) = keyword.strip_prefix("offset=") {
+                memarg.offset =
+                    parse_u64(offset).ok_or_else(|| self.error("invalid memory offset"))?;
+                self.position += 1;
+            }
+        }
+        if let Some(TokenKind::Keyword(keyword)) = self.peek() {
+            if let Some(align) = keyword.strip_prefix("align=") {
+                memarg.align = parse_u32(align)
+                    .filter(|align| align.is_power_of_two())
+                    .map(u32::trailing_zeros)
+                    .ok_or_else(|| self.error("alignment has to be a power of two"))?;
+                self.position += 1;
+            }
+        }
+        Ok(memarg)
+    }
+
+    fn label(&mut self, scope: &Scope<'a>) -> Result<Indecies, WasmParserError> {
+        match self.peek() {
+            Some(TokenKind::Id(id)) => {
+                let id = *id;
+                let depth = scope
+                    .labels
+                    .iter()
+                    .rev()
+                    .position(|label| *label == Some(id))
+                    .ok_or_else(|| self.error(&format!("unknown label ${id}")))?;
+                self.position += 1;
+                Ok(Indecies::LabelIdx(depth as u32))
+            }
+            _ => Ok(Indecies::LabelIdx(self.number("label", parse_u32)?)),
+        }
+    }
+
+    fn local(&mut self, scope: &Scope<'a>) -> Result<Indecies, WasmParserError> {
+        match self.peek() {
+            Some(TokenKind::Id(id)) => {
+                let index = *scope
+                    .locals
+                    .get(id)
+                    .ok_or_else(|| self.error(&format!("unknown local ${id}")))?;
+                self.position += 1;
+                Ok(Indecies::LocalIdx(index))
+            }
+            _ => Ok(Indecies::LocalIdx(self.number("local", parse_u32)?)),
+        }
+    }
+
+    fn default_table(&mut self) -> Result<Indecies, WasmParserError> {
+        Ok(Indecies::TableIdx(
+            self.optional_index(Space::Table)?.unwrap_or(0),
+        ))
+    }
+
+    fn offset_expression(&mut self) -> Result<Expr, WasmParserError> {
+        if self.peek_list("offset") {
+            self.position += 2;
+            let expr = self.instructions(&mut Scope::default())?;
+            self.expect_rparen()?;
+            return Ok(expr);
+        }
+        self.folded_instruction(&mut Scope::default())
+    }
+
+    /// Whether the next tokens are an offset expression, `(offset ...)` or a single folded instruction
+    fn peek_offset(&self) -> bool {
+        self.peek_list("offset")
+            || (self.peek() == Some(&TokenKind::LParen)
+                && matches!(self.peek_at(1), Some(TokenKind::Keyword(keyword)) if !matches!(*keyword, "item" | "func")))
+    }
+
+    /// Whether the next tokens are `reftype (elem`
+    fn peek_inline_elem(&self) -> bool {
+        // The reference type is a keyword, `(ref heaptype)` or `(ref null heaptype)`
+        let length = match (self.peek_list("ref"), self.peek_at(2)) {
+            (true, Some(TokenKind::Keyword("null"))) => 5,
+            (true, _) => 4,
+            (false, _) => 1,
+        };
+        self.peek_at(length) == Some(&TokenKind::LParen)
+            && self.peek_at(length + 1) == Some(&TokenKind::Keyword("elem"))
+    }
+
+    fn function_indices(&mut self) -> Result<Vec<Indecies>, WasmParserError> {
+        let mut indices = vec![];
+        while !self.peek_rparen() {
+            indices.push(Indecies::FuncIdx(self.index(Space::Func)?));
+        }
+        Ok(indices)
+    }
+
+    /// Parses `(item expr)` and single folded instruction element initializers
+    fn element_expressions(&mut self) -> Result<Vec<Expr>, WasmParserError> {
+        let mut exprs = vec![];
+        while !self.peek_rparen() {
+            if self.peek_list("item") {
+                self.position += 2;
+                exprs.push(self.instructions(&mut Scope::default())?);
+                self.expect_rparen()?;
+            } else {
+                exprs.push(self.folded_instruction(&mut Scope::default())?);
+            }
+        }
+        Ok(exprs)
+    }
+
+    fn exports(&mut self) -> Result<(), WasmParserError> {
+        while self.peek_list("export") {
+            self.skip_list()?;
+        }
+        Ok(())
+    }
+
+    fn export_desc(&mut self) -> Result<ExportDesc, WasmParserError> {
+        self.expect_lparen()?;
+        let desc = match self.keyword()? {
+            "func" => ExportDesc::FuncIdx(self.index(Space::Func)?),
+            "table" => ExportDesc::TableIdx(self.index(Space::Table)?),
+            "memory" => ExportDesc::MemIdx(self.index(Space::Memory)?),
+            "global" => ExportDesc::GlobalIdx(self.index(Space::Global)?),
+            "tag" => ExportDesc::TagIdx(self.index(Space::Tag)?),
+            other => return Err(self.error_before(&format!("unknown export kind '{other}'"))),
+        };
+        self.expect_rparen()?;
+        Ok(desc)
+    }
+
+    fn value_type(&mut self) -> Result<ValueType, WasmParserError> {
+        // `(ref null? heaptype)`, the keyword types are shorthands for nullable abstract references
+        if self.peek_list("ref") {
+            self.position += 2;
+            let nullable = self.keyword_is("null");
+            if nullable {
+                self.position += 1;
+            }
+            let heap = self.heap_type()?;
+            self.expect_rparen()?;
+            return Ok(ValueType::RefType(ReferenceTypes::new(nullable, heap)));
+        }
+        Ok(match self.keyword()? {
+            "i32" => ValueType::NumType(NumberTypes::i32),
+            "i64" => ValueType::NumType(NumberTypes::i64),
+            "f32" => ValueType::NumType(NumberTypes::f32),
+            "f64" => ValueType::NumType(NumberTypes::f64),
+            "v128" => ValueType::VecType(VectorTypes::v128),
+            "funcref" => ValueType::RefType(ReferenceTypes::funcref),
+            "externref" => ValueType::RefType(ReferenceTypes::externref),
+            "exnref" => ValueType::RefType(ReferenceTypes::exnref),
+            other => return Err(self.error_before(&format!("unknown value type '{other}'"))),
+        })
+    }
+
+    /// An abstract heap type or the index of a function type
+    fn heap_type(&mut self) -> Result<HeapType, WasmParserError> {
+        if self.peek_index() {
+            return Ok(HeapType::TypeIdx(self.index(Space::Type)?));
+        }
+        Ok(match self.keyword()? {
+            "func" => HeapType::Func,
+            "extern" => HeapType::Extern,
+            "exn" => HeapType::Exn,
+            other => return Err(self.error_before(&format!("unknown heap type '{other}'"))),
+        })
+    }
+
+    fn reference_type(&mut self) -> Result<ReferenceTypes, WasmParserError> {
+        match self.value_type()? {
+            ValueType::RefType(ty) => Ok(ty),
+            _ => Err(self.error_before("expected a reference type")),
+        }
+    }
+
+    /// An optional `i32` or `i64` in front of limits, returns whether the limits are 64 bit
+    fn index_type(&mut self) -> bool {
+        let is_64 = self.keyword_is("i64");
+        if is_64 || self.keyword_is("i32") {
+            self.position += 1;
+        }
+        is_64
+    }
+
+    fn limits(&mut self) -> Result<Limits, WasmParserError> {
+        let is_64 = self.index_type();
+        self.bounds(is_64)
+    }
+
+    /// The minimum, maximum and `shared` flag of limits after their index type
+    fn bounds(&mut self, is_64: bool) -> Result<Limits, WasmParserError> {
+        let parse = match is_64 {
+            true => parse_u64,
+            false => |text: &str| parse_u32(text).map(u64::from),
+        };
+        let min = self.number("limits", parse)?;
+        let max = match self.peek_index() {
+            true => Some(self.number("limits", parse)?),
+            false => None,
+        };
+        let shared = max.is_some() && self.keyword_is("shared");
+        if shared {
+            self.position += 1;
+        }
+        Ok(Limits {
+            min,
+            max,
+            shared,
+            is_64,
+        })
+    }
+
+    fn table_type(&mut self) -> Result<TableType, WasmParserError> {
+        let lim = self.limits()?;
+        Ok(TableType {
+            elem: self.reference_type()?,
+            lim,
+        })
+    }
+
+    fn global_type(&mut self) -> Result<GlobalType, WasmParserError> {
+        if self.peek_list("mut") {
+            self.position += 2;
+            let vtype = self.value_type()?;
+            self.expect_rparen()?;
+            return Ok(GlobalType {
+                vtype,
+                mutability: Mutability::Var,
+            });
+        }
+        Ok(GlobalType {
+            vtype: self.value_type()?,
+            mutability: Mutability::Const,
+        })
+    }
+
+    fn check_import_order(&self, offset: usize) -> Result<(), WasmParserError> {
+        if self.has_definitions {
+            return Err(text_error(
+                self.source,
+                offset,
+                "imports have to come before all function, table, memory and global definitions"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn declare_index(
+        &mut self,
+        space: Space,
+        id: Option<&'a str>,
+        offset: usize,
+    ) -> Result<(), WasmParserError> {
+        let namespace = &mut self.spaces[space as usize];
+        if let Some(id) = id {
+            if namespace.ids.insert(id, namespace.count).is_some() {
+                return Err(text_error(
+                    self.source,
+                    offset,
+                    format!("duplicate {} ${id}", space.describe()),
+                ));
+            }
+        }
+        namespace.count += 1;
+        Ok(())
+    }
+
+    /// The index of the function, table, memory or global being defined, which is the number of them defined
+    /// so far since fields are defined in the order they were declared in
+    fn defined_index(&self, space: Space) -> u32 {
+        (match space {
+            Space::Func => {
+                self.imported(|desc| matches!(desc, ImportDesc::TypeIdx(_)))
+                    + self.sections.functions.len()
+            }
+            Space::Table => {
+                self.imported(|desc| matches!(desc, ImportDesc::TableType(_)))
+                    + self.sections.tables.len()
+            }
+            Space::Memory => {
+                self.imported(|desc| matches!(desc, ImportDesc::MemType(_)))
+                    + self.sections.memory.len()
+            }
+            _ => {
+                self.imported(|desc| matches!(desc, ImportDesc::GlobalType(_)))
+                    + self.sections.global.len()
+            }
+        }) as u32
+    }
+
+    fn imported(&self, kind: impl Fn(&ImportDesc) -> bool) -> usize {
+        self.sections
+            .imports
+            .iter()
+            .filter(|(_, _, desc)| kind(desc))
+            .count()
+    }
+
+    fn index(&mut self, space: Space) -> Result<u32, WasmParserError> {
+        self.optional_index(space)?
+            .ok_or_else(|| self.error(&format!("expected a {} index", space.describe())))
+    }
+
+    fn optional_index(&mut self, space: Space) -> Result<Option<u32>, WasmParserError> {
+        match self.peek() {
+            Some(TokenKind::Id(id)) => {
+                let index = *self.spaces[space as usize]
+                    .ids
+                    .get(id)
+                    .ok_or_else(|| self.error(&format!("unknown {} ${id}", space.describe())))?;
+                self.position += 1;
+                Ok(Some(index))
+            }
+            Some(TokenKind::Reserved(_)) => Ok(Some(self.number("index", parse_u32)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether the next token is an index, symbolic or numeric
+    fn peek_index(&self) -> bool {
+        matches!(self.peek(), Some(TokenKind::Id(_) | TokenKind::Reserved(_)))
+    }
+
+    fn number<T>(
+        &mut self,
+        construct: &str,
+        parse: fn(&str) -> Option<T>,
+    ) -> Result<T, WasmParserError> {
+        let value = match self.peek() {
+            Some(TokenKind::Reserved(text) | TokenKind::Keyword(text)) => parse(text),
+            _ => None,
+        };
+        let value =
+            value.ok_or_else(|| self.error(&format!("expected a number for the {construct}")))?;
+        self.position += 1;
+        Ok(value)
+    }
+
+    /// Like [`Self::number`] for floats, literals other than `inf` that round to infinity are out of range
+    fn float<T: Into<f64> + Copy>(
+        &mut self,
+        construct: &str,
+        parse: fn(&str) -> Option<T>,
+    ) -> Result<T, WasmParserError> {
+        let literal = self.peek().cloned();
+        let value = self.number(construct, parse)?;
+        let infinity = matches!(
+            literal,
+            Some(TokenKind::Reserved(text) | TokenKind::Keyword(text)) if split_sign(text).1 == "inf"
+        );
+        if value.into().is_infinite() && !infinity {
+            return Err(self.error_before("constant out of range"));
+        }
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, WasmParserError> {
+        match self.peek() {
+            Some(TokenKind::String(bytes)) => {
+                let bytes = bytes.clone();
+                self.position += 1;
+                Ok(bytes)
+            }
+            _ => Err(self.error("expected a string")),
+        }
+    }
+
+    /// Concatenates the strings up to the next `)`
+    fn strings(&mut self) -> Result<Vec<u8>, WasmParserError> {
+        let mut bytes = vec![];
+        while !self.peek_rparen() {
+            bytes.extend(self.string()?);
+        }
+        Ok(bytes)
+    }
+
+    fn name(&mut self) -> Result<Name<'static>, WasmParserError> {
+        let offset = self.offset();
+        let bytes = self.string()?;
+        String::from_utf8(bytes).map(Name::from).map_err(|_| {
+            text_error(
+                self.source,
+                offset,
+                "names have to be valid UTF-8".to_string(),
+            )
+        })
+    }
+
+    fn id(&mut self) -> Option<&'a str> {
+        match self.peek() {
+            Some(TokenKind::Id(id)) => {
+                let id = *id;
+                self.position += 1;
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+
+    fn keyword(&mut self) -> Result<&'a str, WasmParserError> {
+        match self.peek() {
+            Some(TokenKind::Keyword(keyword)) => {
+                let keyword = *keyword;
+                self.position += 1;
+                Ok(keyword)
+            }
+            _ => Err(self.error("expected a keyword")),
+        }
+    }
+
+    fn keyword_is(&self, keyword: &str) -> bool {
+        self.peek() == Some(&TokenKind::Keyword(keyword))
+    }
+
+    fn peek(&self) -> Option<&TokenKind<'a>> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, ahead: usize) -> Option<&TokenKind<'a>> {
+        self.tokens
+            .get(self.position + ahead)
+            .map(|token| &token.kind)
+    }
+
+    fn peek_rparen(&self) -> bool {
+        matches!(self.peek(), Some(TokenKind::RParen) | None)
+    }
+
+    /// Whether the next tokens open a list starting with `keyword`, like `(param`
+    fn peek_list(&self, keyword: &str) -> bool {
+        self.peek() == Some(&TokenKind::LParen)
+            && self.peek_at(1) == Some(&TokenKind::Keyword(keyword))
+    }
+
+    fn expect_list(&mut self, keyword: &str) -> Result<(), WasmParserError> {
+        if !self.peek_list(keyword) {
+            return Err(self.error(&format!("expected '({keyword}'")));
+        }
+        self.position += 2;
+        Ok(())
+    }
+
+    fn expect_lparen(&mut self) -> Result<(), WasmParserError> {
+        match self.peek() {
+            Some(TokenKind::LParen) => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error("expected '('")),
+        }
+    }
+
+    fn expect_rparen(&mut self) -> Result<(), WasmParserError> {
+        match self.peek() {
+            Some(TokenKind::RParen) => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error("expected ')'")),
+        }
+    }
+
+    /// Skips the list starting at the next token, an opening paren or annotation, up to its closing paren
+    fn skip_list(&mut self) -> Result<(), WasmParserError> {
+        let start = self.position;
+        let mut depth = 0;
+        while let Some(token) = self.tokens.get(self.position) {
+            self.position += 1;
+            match token.kind {
+                TokenKind::LParen | TokenKind::Annotation(_) => depth += 1,
+                TokenKind::RParen => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+        Err(self.error_at(start, "unclosed '('"))
+    }
+
+    /// The offset of the next token, or the end of the source
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.source.len(), |token| token.offset)
+    }
+
+    /// An error at the next token
+    fn error(&self, message: &str) -> WasmParserError {
+        self.error_at(self.position, message)
+    }
+
+    /// An error at the token that was just read
+    fn error_before(&self, message: &str) -> WasmParserError {
+        self.error_at(self.position.saturating_sub(1), message)
+    }
+
+    fn error_at(&self, position: usize, message: &str) -> WasmParserError {
+        let offset = self
+            .tokens
+            .get(position)
+            .map_or(self.source.len(), |token| token.offset);
+        text_error(self.source, offset, message.to_string())
+    }
+}
+
+fn structured(
+    kind: &str,
+    ty: BlockType,
+    catches: Vec<Catch>,
+    body: Expr,
+    else_: Expr,
+) -> Instructions {
+    match kind {
+        "block" => Instructions::Block { ty, body },
+        "loop" => Instructions::Loop { ty, body },
+        "try_table" => Instructions::TryTable { ty, catches, body },
+        _ => Instructions::If {
+            ty,
+            then: body,
+            else_,
+        },
+    }
+}
+
+/// Splits a number into its sign and digits, dropping the `_` separators
+fn split_sign(text: &str) -> (bool, String) {
+    let (negative, digits) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    (negative, digits.replace('_', ""))
+}
+
+fn parse_magnitude(digits: &str) -> Option<u64> {
+    match digits.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None if digits.bytes().all(|c| c.is_ascii_digit()) => digits.parse().ok(),
+        None => None,
+    }
+}
+
+/// Indices, offsets and limits are unsigned and can't have a sign
+fn parse_u32(text: &str) -> Option<u32> {
+    if text.starts_with(['+', '-']) {
+        return None;
+    }
+    parse_magnitude(&text.replace('_', ""))?.try_into().ok()
+}
+
+fn parse_u64(text: &str) -> Option<u64> {
+    if text.starts_with(['+', '-']) {
+        return None;
+    }
+    parse_magnitude(&text.replace('_', ""))
+}
+
+/// The offset of an inline element or data segment, which has the index type of its table or memory
+fn zero_offset(is_64: bool) -> Instructions {
+    match is_64 {
+        true => Instructions::i64_const(0),
+        false => Instructions::i32_const(0),
+    }
+}
+
+/// Integers can be written signed or unsigned, `-1` and `0xFFFFFFFF` are the same `i32`
+fn parse_i32(text: &str) -> Option<i32> {
+    let (negative, digits) = split_sign(text);
+    let magnitude = parse_magnitude(&digits)?;
+    match negative {
+        true if magnitude <= 1 << 31 => Some((magnitude as i64).wrapping_neg() as i32),
+        false if magnitude <= u32::MAX as u64 => Some(magnitude as u32 as i32),
+        _ => None,
+    }
+}
+
+/// The narrow lanes of `v128.const`, which can be written signed or unsigned as well
+fn parse_i8(text: &str) -> Option<u8> {
+    parse_narrow(text, 8).map(|lane| lane as u8)
+}
+
+fn parse_i16(text: &str) -> Option<u16> {
+    parse_narrow(text, 16).map(|lane| lane as u16)
+}
+
+fn parse_narrow(text: &str, bits: u32) -> Option<u32> {
+    let (negative, digits) = split_sign(text);
+    let magnitude = parse_magnitude(&digits)?;
+    let mask = (1 << bits) - 1;
+    match negative {
+        true if magnitude <= 1 << (bits - 1) => Some(magnitude.wrapping_neg() as u32 & mask),
+        false if magnitude <= mask as u64 => Some(magnitude as u32),
+        _ => None,
+    }
+}
+
+/// Lane indices are unsigned bytes, their range depends on the instruction and is checked by validation
+fn parse_lane_index(text: &str) -> Option<u8> {
+    parse_u32(text)?.try_into().ok()
+}
+
+fn parse_i64(text: &str) -> Option<i64> {
+    let (negative, digits) = split_sign(text);
+    let magnitude = parse_magnitude(&digits)?;
+    match negative {
+        true if magnitude <= 1 << 63 => Some((magnitude as i64).wrapping_neg()),
+        false => Some(magnitude as i64),
+        _ => None,
+    }
+}
+
+fn parse_f32(text: &str) -> Option<f32> {
+    let (negative, digits) = split_sign(text);
+    let value = match digits.as_str() {
+        "inf" => f32::INFINITY,
+        "nan" => f32::NAN,
+        nan if nan.starts_with("nan:0x") => {
+            let payload = u32::from_str_radix(&nan[6..], 16).ok()?;
+            if payload == 0 || payload >= 1 << 23 {
+                return None;
+            }
+            f32::from_bits(0x7F80_0000 | payload)
+        }
+        hex if hex.starts_with("0x") => {
+            let (mantissa, exponent) = parse_hex_float(&hex[2..])?;
+            scale(mantissa as f32 as f64, exponent) as f32
+        }
+        decimal => parse_decimal(decimal)?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn parse_f64(text: &str) -> Option<f64> {
+    let (negative, digits) = split_sign(text);
+    let value = match digits.as_str() {
+        "inf" => f64::INFINITY,
+        "nan" => f64::NAN,
+        nan if nan.starts_with("nan:0x") => {
+            let payload = u64::from_str_radix(&nan[6..], 16).ok()?;
+            if payload == 0 || payload >= 1 << 52 {
+                return None;
+            }
+            f64::from_bits(0x7FF0_0000_0000_0000 | payload)
+        }
+        hex if hex.starts_with("0x") => {
+            let (mantissa, exponent) = parse_hex_float(&hex[2..])?;
+            scale(mantissa as f64, exponent)
+        }
+        decimal => parse_decimal(decimal)?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn parse_decimal<T: std::str::FromStr>(digits: &str) -> Option<T> {
+    // Rust accepts `inf` and `nan` spellings the text format doesn't, those are handled before
+    match digits.as_bytes().first() {
+        Some(c) if c.is_ascii_digit() => digits.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Parses the digits of a hexadecimal float after the `0x`, returning a mantissa and a power of two
+/// to multiply it with. Digits past the precision of the mantissa are folded into its lowest bit,
+/// so converting it to a float still rounds correctly
+fn parse_hex_float(digits: &str) -> Option<(u64, i64)> {
+    let (significand, exponent) = match digits.split_once(['p', 'P']) {
+        Some((significand, exponent)) => (significand, exponent.parse::<i64>().ok()?),
+        None => (digits, 0),
+    };
+    let (integer, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+    if integer.is_empty() {
+        return None;
+    }
+
+    let mut mantissa = 0u64;
+    let mut exponent = exponent;
+    let mut sticky = false;
+    for (i, c) in integer.chars().chain(fraction.chars()).enumerate() {
+        let digit = c.to_digit(16)? as u64;
+        if i >= integer.len() {
+            exponent -= 4;
+        }
+        if mantissa >> 59 == 0 {
+            mantissa = mantissa << 4 | digit;
+        } else {
+            sticky |= digit != 0;
+            exponent += 4;
+        }
+    }
+    Some((mantissa | sticky as u64, exponent))
+}
+
+/// Multiplies `value` by 2^`exponent` in steps, so intermediate powers don't overflow
+fn scale(mut value: f64, mut exponent: i64) -> f64 {
+    while exponent > 1000 {
+        value *= 2f64.powi(1000);
+        exponent -= 1000;
+    }
+    while exponent < -1000 {
+        value *= 2f64.powi(-1000);
+        exponent += 1000;
+    }
+    value * 2f64.powi(exponent as i32)
+}
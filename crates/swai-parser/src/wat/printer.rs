@@ -0,0 +1,1118 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    iter::Peekable,
+    slice,
+};
+
+use super::{
+    lexer::is_idchar,
+    mnemonics::{lane_instruction, lane_memory_instruction, memory_instruction, plain_mnemonic},
+    offsets::Offsets,
+    Space, SECTION_KEYWORDS,
+};
+use crate::{
+    error::WasmParserError,
+    instructions::{BlockType, Catch, Instructions, MemArg},
+    names::NameMap,
+    sections::WasmSections,
+    types::{
+        ElementInit, ElementMode, ExportDesc, Expr, FunctionType, GlobalType, HeapType, ImportDesc,
+        Indecies, Limits, Mutability, SegmentMode, ValueType,
+    },
+    validation::numeric_signature,
+    wasm::WasmModule,
+};
+
+/// How [`print`] lays out a module
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrintOptions {
+    /// Nests the operands of an instruction inside of it, like `(i32.add (local.get 0) (i32.const 1))`,
+    /// instead of printing one instruction per line
+    pub folded: bool,
+    /// Starts every line with a `(;@1f;)` comment holding the offset of what it shows in the binary module
+    pub offsets: bool,
+}
+
+/// Prints a module in the text format.
+///
+/// Items are named after the `name` section where it gives them valid and unique `$names`, custom sections
+/// are kept as `(@custom ...)` annotations. Parsing the output with [`crate::wat::parse`] gives back an equal
+/// module, except that runs of locals with the same type are merged and the data count section is only
+/// kept if `memory.init` or `data.drop` need it.
+///
+/// Offsets are those of [`WasmModule::to_bytes`], see [`print_bytes`] for the offsets in the bytes a module was parsed from.
+/// They're read back from the encoding, which fails for modules built by hand that the parser rejects,
+/// e.g. ones with blocks nested deeper than [`crate::instructions::MAX_NESTING_DEPTH`]
+pub fn print(module: &WasmModule, options: &PrintOptions) -> Result<String, WasmParserError> {
+    let offsets = match options.offsets {
+        true => Some(Offsets::read(&module.to_bytes())?),
+        false => None,
+    };
+    Ok(Printer::new(&module.sections, options, offsets).module())
+}
+
+/// Parses a binary module and prints it in the text format, see [`print`]
+pub fn print_bytes(bytes: &[u8], options: &PrintOptions) -> Result<String, WasmParserError> {
+    let module = WasmModule::from_bytes(bytes)?;
+    let offsets = match options.offsets {
+        true => Some(Offsets::read(bytes)?),
+        false => None,
+    };
+    Ok(Printer::new(&module.sections, options, offsets).module())
+}
+
+struct Line {
+    /// The offset shown in front of the line
+    offset: Option<usize>,
+    depth: usize,
+    text: String,
+}
+
+/// An instruction, along with the instructions folded into it as operands
+struct Node {
+    /// The mnemonic and immediates, like `i32.load offset=4` or `block $exit (result i32)`
+    text: String,
+    offset: Option<usize>,
+    operands: Vec<usize>,
+    block: Option<Block>,
+}
+
+/// The contents of a `block`, `loop` or `if` node
+struct Block {
+    is_if: bool,
+    body: Vec<usize>,
+    /// The `else` branch of an `if`, along with the offset of the `else` opcode
+    else_: Option<(Option<usize>, Vec<usize>)>,
+    end: Option<usize>,
+}
+
+/// An instruction sequence that is being turned into nodes
+struct Frame<'e> {
+    instructions: slice::Iter<'e, Instructions>,
+    /// The `else` branch of an `if`, walked once the `then` branch runs out
+    else_: Option<&'e Expr>,
+    /// The node of the block, `None` for the outermost sequence
+    node: Option<usize>,
+    /// The `$label` of the block
+    label: Option<String>,
+    nodes: Vec<usize>,
+    /// How many nodes at the end of `nodes` leave exactly one value each, so they can be folded into the next instruction
+    foldable: usize,
+}
+
+/// A step of printing nodes, kept on an explicit stack so deeply nested code can't overflow the call stack
+enum Step {
+    Node(usize),
+    Text(&'static str, Option<usize>),
+    /// Closes the parenthesis opened last, at the end of the last line
+    Close,
+    Indent,
+    Dedent,
+}
+
+/// What the instructions of a function body or constant expression can refer to besides the module's items
+#[derive(Default)]
+struct Scope {
+    /// The function index, for the names of its labels
+    function: Option<u32>,
+    locals: HashMap<u32, String>,
+}
+
+/// The offsets of the opcodes of an instruction sequence, taken in the order the instructions are visited
+struct Opcodes<'o>(Peekable<slice::Iter<'o, (usize, u8)>>);
+
+impl Opcodes<'_> {
+    fn next(&mut self) -> Option<usize> {
+        self.0.next().map(|(offset, _)| *offset)
+    }
+
+    /// Takes the next offset only if it's that of `opcode`
+    fn next_if(&mut self, opcode: u8) -> Option<usize> {
+        self.0
+            .next_if(|(_, byte)| *byte == opcode)
+            .map(|(offset, _)| *offset)
+    }
+}
+
+struct Printer<'m, 'a> {
+    sections: &'m WasmSections<'a>,
+    options: &'m PrintOptions,
+    offsets: Option<Offsets>,
+    /// The `$names` of the items in each index space, see [`ids`]
+    ids: [HashMap<u32, String>; 8],
+    /// The type index of every function, imported ones first
+    function_types: Vec<u32>,
+    lines: Vec<Line>,
+}
+
+impl<'m, 'a> Printer<'m, 'a> {
+    fn new(
+        sections: &'m WasmSections<'a>,
+        options: &'m PrintOptions,
+        offsets: Option<Offsets>,
+    ) -> Self {
+        let mut ids: [HashMap<u32, String>; 8] = Default::default();
+        if let Some(names) = &sections.names {
+            for (space, map) in [
+                (Space::Type, &names.types),
+                (Space::Func, &names.functions),
+                (Space::Table, &names.tables),
+                (Space::Memory, &names.memories),
+                (Space::Global, &names.globals),
+                (Space::Tag, &names.tags),
+                (Space::Elem, &names.elements),
+                (Space::Data, &names.data),
+            ] {
+                ids[space as usize] = self::ids(map);
+            }
+        }
+
+        let function_types = sections
+            .imports
+            .iter()
+            .filter_map(|(_, _, desc)| match desc {
+                ImportDesc::TypeIdx(ty) => Some(ty.index()),
+                _ => None,
+            })
+            .chain(sections.functions.iter().map(Indecies::index))
+            .collect();
+
+        Self {
+            sections,
+            options,
+            offsets,
+            ids,
+            function_types,
+            lines: vec![],
+        }
+    }
+
+    fn module(mut self) -> String {
+        let sections = self.sections;
+        let module_id = sections
+            .names
+            .as_ref()
+            .and_then(|names| names.module.as_ref())
+            .filter(|name| is_id(name.as_str()))
+            .map_or(String::new(), |name| format!(" ${name}"));
+        self.line(None, 0, format!("(module{module_id}"));
+
+        for (index, ty) in sections.types.iter().enumerate() {
+            let text = format!(
+                "(type{} (func{}))",
+                self.declaration(Space::Type, index),
+                signature(ty, &Scope::default())
+            );
+            self.line(self.entry_offset(1, index), 1, text);
+        }
+
+        let mut counts = [0u32; 8];
+        for (index, (module, name, desc)) in sections.imports.iter().enumerate() {
+            let (kind, space, description) = match desc {
+                ImportDesc::TypeIdx(ty) => (
+                    "func",
+                    Space::Func,
+                    format!(" (type {})", self.index(Space::Type, ty.index())),
+                ),
+                ImportDesc::TableType(table) => (
+                    "table",
+                    Space::Table,
+                    format!(" {} {}", limits(&table.lim), table.elem),
+                ),
+                ImportDesc::MemType(memory) => {
+                    ("memory", Space::Memory, format!(" {}", limits(memory)))
+                }
+                ImportDesc::GlobalType(global) => {
+                    ("global", Space::Global, format!(" {}", global_type(global)))
+                }
+                ImportDesc::TagType(tag) => (
+                    "tag",
+                    Space::Tag,
+                    format!(" (type {})", self.index(Space::Type, tag.ty)),
+                ),
+            };
+            let declaration = self.declaration(space, counts[space as usize] as usize);
+            counts[space as usize] += 1;
+            let text = format!(
+                "(import {} {} ({kind}{declaration}{description}))",
+                string(module.as_str().as_bytes()),
+                string(name.as_str().as_bytes())
+            );
+            self.line(self.entry_offset(2, index), 1, text);
+        }
+
+        for (index, (ty, code)) in sections.functions.iter().zip(&sections.code).enumerate() {
+            self.function(
+                counts[Space::Func as usize] + index as u32,
+                index,
+                ty.index(),
+                &code.locals,
+                &code.body,
+            );
+        }
+
+        for (index, table) in sections.tables.iter().enumerate() {
+            let declaration =
+                self.declaration(Space::Table, counts[Space::Table as usize] as usize + index);
+            let text = format!("(table{declaration} {} {})", limits(&table.lim), table.elem);
+            self.line(self.entry_offset(4, index), 1, text);
+        }
+        for (index, memory) in sections.memory.iter().enumerate() {
+            let declaration = self.declaration(
+                Space::Memory,
+                counts[Space::Memory as usize] as usize + index,
+            );
+            let text = format!("(memory{declaration} {})", limits(memory));
+            self.line(self.entry_offset(5, index), 1, text);
+        }
+        for (index, tag) in sections.tags.iter().enumerate() {
+            let declaration =
+                self.declaration(Space::Tag, counts[Space::Tag as usize] as usize + index);
+            let text = format!(
+                "(tag{declaration} (type {}))",
+                self.index(Space::Type, tag.ty)
+            );
+            self.line(self.entry_offset(13, index), 1, text);
+        }
+        for (index, (ty, init)) in sections.global.iter().enumerate() {
+            let declaration = self.declaration(
+                Space::Global,
+                counts[Space::Global as usize] as usize + index,
+            );
+            let text = format!(
+                "(global{declaration} {} {})",
+                global_type(ty),
+                self.inline_expression(init)
+            );
+            self.line(self.entry_offset(6, index), 1, text);
+        }
+
+        for (index, (name, export)) in sections.export.iter().enumerate() {
+            let (kind, space) = match export {
+                ExportDesc::FuncIdx(_) => ("func", Space::Func),
+                ExportDesc::TableIdx(_) => ("table", Space::Table),
+                ExportDesc::MemIdx(_) => ("memory", Space::Memory),
+                ExportDesc::GlobalIdx(_) => ("global", Space::Global),
+                ExportDesc::TagIdx(_) => ("tag", Space::Tag),
+            };
+            let text = format!(
+                "(export {} ({kind} {}))",
+                string(name.as_str().as_bytes()),
+                self.index(space, export.index())
+            );
+            self.line(self.entry_offset(7, index), 1, text);
+        }
+        if let Some(start) = &sections.start {
+            let text = format!("(start {})", self.index(Space::Func, start.index()));
+            self.line(self.entry_offset(8, 0), 1, text);
+        }
+
+        for (index, segment) in sections.element.iter().enumerate() {
+            let mut text = format!("(elem{}", self.declaration(Space::Elem, index));
+            match &segment.mode {
+                ElementMode::Passive => {}
+                ElementMode::Declarative => text.push_str(" declare"),
+                ElementMode::Active {
+                    table_index,
+                    offset,
+                } => {
+                    if *table_index != 0 {
+                        write!(text, " (table {})", self.index(Space::Table, *table_index))
+                            .unwrap();
+                    }
+                    write!(text, " (offset {})", self.inline_expression(offset)).unwrap();
+                }
+            }
+            match &segment.init {
+                ElementInit::Functions(functions) => {
+                    text.push_str(" func");
+                    for function in functions {
+                        write!(text, " {}", self.index(Space::Func, function.index())).unwrap();
+                    }
+                }
+                ElementInit::Expressions(exprs) => {
+                    write!(text, " {}", segment.ty).unwrap();
+                    for expr in exprs {
+                        write!(text, " (item {})", self.inline_expression(expr)).unwrap();
+                    }
+                }
+            }
+            text.push(')');
+            self.line(self.entry_offset(9, index), 1, text);
+        }
+
+        for (index, segment) in sections.data.iter().enumerate() {
+            let mut text = format!("(data{}", self.declaration(Space::Data, index));
+            if let SegmentMode::Active {
+                memory_index,
+                offset,
+            } = &segment.mode
+            {
+                if *memory_index != 0 {
+                    write!(
+                        text,
+                        " (memory {})",
+                        self.index(Space::Memory, *memory_index)
+                    )
+                    .unwrap();
+                }
+                write!(text, " (offset {})", self.inline_expression(offset)).unwrap();
+            }
+            write!(text, " {})", string(&segment.bytes)).unwrap();
+            self.line(self.entry_offset(11, index), 1, text);
+        }
+
+        for (index, section) in sections.custom.iter().enumerate() {
+            let placement = match section.after {
+                None => "before first".to_string(),
+                Some(id) => match SECTION_KEYWORDS.iter().find(|(_, section)| *section == id) {
+                    Some((keyword, _)) => format!("after {keyword}"),
+                    None => "after last".to_string(),
+                },
+            };
+            let text = format!(
+                "(@custom {} ({placement}) {})",
+                string(section.name.as_str().as_bytes()),
+                string(&section.data)
+            );
+            let offset = self
+                .offsets
+                .as_ref()
+                .and_then(|offsets| offsets.custom.get(index).copied());
+            self.line(offset, 1, text);
+        }
+
+        self.close();
+        self.finish()
+    }
+
+    /// Prints the function at `index` in the function index space, the `code`-th one in the code section
+    fn function(
+        &mut self,
+        index: u32,
+        code: usize,
+        ty: u32,
+        locals: &[(u32, ValueType)],
+        body: &Expr,
+    ) {
+        let mut scope = Scope {
+            function: Some(index),
+            locals: HashMap::new(),
+        };
+        if let Some(names) = self.sections.names.as_ref() {
+            if let Some((_, map)) = names.locals.iter().find(|(function, _)| *function == index) {
+                scope.locals = ids(map);
+            }
+        }
+
+        let mut text = format!(
+            "(func{} (type {})",
+            self.declaration(Space::Func, index as usize),
+            self.index(Space::Type, ty)
+        );
+        if let Some(ty) = self.sections.types.get(ty as usize) {
+            text.push_str(&signature(ty, &scope));
+        }
+        self.line(self.entry_offset(10, code), 1, text);
+
+        let first_local = self
+            .sections
+            .types
+            .get(ty as usize)
+            .map_or(0, |ty| ty.params.len() as u32);
+        let types = locals
+            .iter()
+            .flat_map(|(count, ty)| (0..*count).map(move |_| *ty));
+        let mut unnamed = vec![];
+        for (index, ty) in (first_local..).zip(types) {
+            match scope.locals.get(&index) {
+                Some(id) => {
+                    self.unnamed_locals(&mut unnamed);
+                    self.line(None, 2, format!("(local {id} {ty})"));
+                }
+                None => unnamed.push(ty),
+            }
+        }
+        self.unnamed_locals(&mut unnamed);
+
+        let opcodes = match &self.offsets {
+            Some(offsets) => offsets.code.get(code).map_or(&[][..], Vec::as_slice),
+            None => &[],
+        };
+        let lines = self.expression(body, &scope, opcodes);
+        self.lines.extend(lines.into_iter().map(|line| Line {
+            depth: line.depth + 2,
+            ..line
+        }));
+        self.close();
+    }
+
+    fn unnamed_locals(&mut self, types: &mut Vec<ValueType>) {
+        if !types.is_empty() {
+            self.line(None, 2, format!("(local{})", value_types(types)));
+            types.clear();
+        }
+    }
+
+    /// Prints a constant expression on a single line
+    fn inline_expression(&self, expr: &Expr) -> String {
+        self.expression(expr, &Scope::default(), &[])
+            .into_iter()
+            .map(|line| line.text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Prints an instruction sequence, `opcodes` are the offsets of its opcodes if they're shown
+    fn expression(&self, expr: &Expr, scope: &Scope, opcodes: &[(usize, u8)]) -> Vec<Line> {
+        let (nodes, roots) = self.nodes(expr, scope, &mut Opcodes(opcodes.iter().peekable()));
+
+        let mut lines: Vec<Line> = vec![];
+        let mut depth = 0;
+        let mut steps: Vec<Step> = roots.into_iter().rev().map(Step::Node).collect();
+        while let Some(step) = steps.pop() {
+            let node = match step {
+                Step::Node(node) => &nodes[node],
+                Step::Text(text, offset) => {
+                    lines.push(Line {
+                        offset,
+                        depth,
+                        text: text.to_string(),
+                    });
+                    continue;
+                }
+                Step::Close => {
+                    if let Some(line) = lines.last_mut() {
+                        line.text.push(')');
+                    }
+                    continue;
+                }
+                Step::Indent => {
+                    depth += 1;
+                    continue;
+                }
+                Step::Dedent => {
+                    depth -= 1;
+                    continue;
+                }
+            };
+
+            let folded = self.options.folded;
+            lines.push(Line {
+                offset: node.offset,
+                depth,
+                text: match folded {
+                    true => format!("({}", node.text),
+                    false => node.text.clone(),
+                },
+            });
+
+            // The steps of the node in order, they're pushed onto the stack in reverse
+            let mut next = vec![];
+            let nested = |next: &mut Vec<Step>, nodes: &[usize]| {
+                next.push(Step::Indent);
+                next.extend(nodes.iter().copied().map(Step::Node));
+                next.push(Step::Dedent);
+            };
+            match (&node.block, folded) {
+                (None, true) => {
+                    if !node.operands.is_empty() {
+                        nested(&mut next, &node.operands);
+                    }
+                    next.push(Step::Close);
+                }
+                (None, false) => {}
+                (Some(block), true) if block.is_if => {
+                    next.push(Step::Indent);
+                    next.extend(node.operands.iter().copied().map(Step::Node));
+                    next.push(Step::Text("(then", None));
+                    nested(&mut next, &block.body);
+                    next.push(Step::Close);
+                    if let Some((offset, else_)) = &block.else_ {
+                        next.push(Step::Text("(else", *offset));
+                        nested(&mut next, else_);
+                        next.push(Step::Close);
+                    }
+                    next.push(Step::Dedent);
+                    next.push(Step::Close);
+                }
+                (Some(block), true) => {
+                    nested(&mut next, &block.body);
+                    next.push(Step::Close);
+                }
+                (Some(block), false) => {
+                    nested(&mut next, &block.body);
+                    if let Some((offset, else_)) = &block.else_ {
+                        next.push(Step::Text("else", *offset));
+                        nested(&mut next, else_);
+                    }
+                    next.push(Step::Text("end", block.end));
+                }
+            }
+            steps.extend(next.into_iter().rev());
+        }
+        lines
+    }
+
+    /// Turns an instruction sequence into nodes, returning them along with the nodes of the outermost sequence.
+    /// In the folded form, instructions take the nodes before them as operands when those leave exactly the
+    /// values they consume. Operands are always the nodes right before an instruction, so the order of the
+    /// instructions stays the same even where the types aren't known
+    fn nodes(&self, expr: &Expr, scope: &Scope, opcodes: &mut Opcodes) -> (Vec<Node>, Vec<usize>) {
+        let mut nodes: Vec<Node> = vec![];
+        let mut frames = vec![Frame {
+            instructions: expr.iter(),
+            else_: None,
+            node: None,
+            label: None,
+            nodes: vec![],
+            foldable: 0,
+        }];
+        let mut labels = 0;
+
+        while let Some(frame) = frames.last_mut() {
+            let Some(instruction) = frame.instructions.next() else {
+                if let Some(else_) = frame.else_.take() {
+                    let then = std::mem::take(&mut frame.nodes);
+                    frame.instructions = else_.iter();
+                    frame.foldable = 0;
+                    let block = block_of(&mut nodes, frame.node);
+                    block.body = then;
+                    block.else_ = Some((opcodes.next_if(0x05), vec![]));
+                    continue;
+                }
+
+                let frame = frames.pop().expect("the loop runs while there are frames");
+                if frame.node.is_none() {
+                    // The `end` of the expression itself isn't shown
+                    opcodes.next();
+                    return (nodes, frame.nodes);
+                }
+                let block = block_of(&mut nodes, frame.node);
+                match &mut block.else_ {
+                    Some((_, else_)) => *else_ = frame.nodes,
+                    None => {
+                        // An empty `else` branch isn't kept in the module, but its opcode can still be in the binary
+                        opcodes.next_if(0x05);
+                        block.body = frame.nodes;
+                    }
+                }
+                block.end = opcodes.next();
+                continue;
+            };
+
+            let arity = self.arity(instruction);
+            let (ty, body, else_, is_if, kind) = match instruction {
+                Instructions::Block { ty, body } => (ty, body, None, false, "block"),
+                Instructions::Loop { ty, body } => (ty, body, None, false, "loop"),
+                Instructions::TryTable { ty, body, .. } => (ty, body, None, false, "try_table"),
+                Instructions::If { ty, then, else_ } => (
+                    ty,
+                    then,
+                    Some(else_).filter(|else_| !else_.is_empty()),
+                    true,
+                    "if",
+                ),
+                instruction => {
+                    let node = nodes.len();
+                    nodes.push(Node {
+                        text: self.instruction(instruction, scope, &frames),
+                        offset: opcodes.next(),
+                        operands: vec![],
+                        block: None,
+                    });
+                    let frame = frames
+                        .last_mut()
+                        .expect("the loop runs while there are frames");
+                    self.add_node(frame, &mut nodes, node, arity);
+                    continue;
+                }
+            };
+
+            let label = scope
+                .function
+                .and_then(|function| self.sections.names.as_ref()?.label_name(function, labels))
+                .filter(|name| is_id(name.as_str()))
+                .map(|name| format!("${name}"));
+            labels += 1;
+            let mut text = kind.to_string();
+            if let Some(label) = &label {
+                write!(text, " {label}").unwrap();
+            }
+            match ty {
+                BlockType::Empty => {}
+                BlockType::Value(ty) => write!(text, " (result {ty})").unwrap(),
+                BlockType::TypeIndex(index) => {
+                    write!(text, " (type {})", self.index(Space::Type, *index)).unwrap()
+                }
+            }
+            // The labels of catch clauses are outside of the try_table, like the frames so far
+            if let Instructions::TryTable { catches, .. } = instruction {
+                for catch in catches {
+                    text.push_str(&self.catch(catch, &frames));
+                }
+            }
+
+            let node = nodes.len();
+            nodes.push(Node {
+                text,
+                offset: opcodes.next(),
+                operands: vec![],
+                block: Some(Block {
+                    is_if,
+                    body: vec![],
+                    else_: None,
+                    end: None,
+                }),
+            });
+            let frame = frames
+                .last_mut()
+                .expect("the loop runs while there are frames");
+            self.add_node(frame, &mut nodes, node, arity);
+            frames.push(Frame {
+                instructions: body.iter(),
+                else_,
+                node: Some(node),
+                label,
+                nodes: vec![],
+                foldable: 0,
+            });
+        }
+        unreachable!("the outermost frame returns")
+    }
+
+    /// Adds a node to the sequence of `frame`, folding the nodes before it into it if it consumes their values
+    fn add_node(
+        &self,
+        frame: &mut Frame,
+        nodes: &mut [Node],
+        node: usize,
+        arity: Option<(usize, usize)>,
+    ) {
+        let Some((params, results)) = arity else {
+            frame.foldable = 0;
+            frame.nodes.push(node);
+            return;
+        };
+
+        let fold = self.options.folded && params <= frame.foldable;
+        if fold {
+            nodes[node].operands = frame.nodes.split_off(frame.nodes.len() - params);
+        }
+        frame.foldable = match (results, fold) {
+            (1, true) => frame.foldable - params + 1,
+            (1, false) => 1,
+            _ => 0,
+        };
+        frame.nodes.push(node);
+    }
+
+    /// How many values an instruction consumes and leaves on the stack, `None` if that depends on a type that doesn't exist
+    fn arity(&self, instruction: &Instructions) -> Option<(usize, usize)> {
+        use Instructions::*;
+
+        let signature = |ty: u32| {
+            let ty = self.sections.types.get(ty as usize)?;
+            Some((ty.params.len(), ty.result.len()))
+        };
+        // Params of a block stay in front of it, folding them would move them into its body
+        let results = |ty: &BlockType| match ty {
+            BlockType::Empty => Some(0),
+            BlockType::Value(_) => Some(1),
+            BlockType::TypeIndex(index) => match signature(*index)? {
+                (0, results) => Some(results),
+                _ => None,
+            },
+        };
+        Some(match instruction {
+            Block { ty, .. } | Loop { ty, .. } | TryTable { ty, .. } => (0, results(ty)?),
+            Throw(tag) => {
+                let tags = self
+                    .sections
+                    .imports
+                    .iter()
+                    .filter_map(|(_, _, desc)| match desc {
+                        ImportDesc::TagType(tag) => Some(tag),
+                        _ => None,
+                    });
+                let tag = tags.chain(&self.sections.tags).nth(tag.index() as usize)?;
+                (signature(tag.ty)?.0, 0)
+            }
+            ThrowRef => (1, 0),
+            If { ty, .. } => (1, results(ty)?),
+            Call(function) => signature(*self.function_types.get(function.index() as usize)?)?,
+            CallIndirect(ty, _) => {
+                let (params, results) = signature(ty.index())?;
+                (params + 1, results)
+            }
+            // Tail calls return from the function, so like `return` nothing follows them
+            ReturnCall(function) => (
+                signature(*self.function_types.get(function.index() as usize)?)?.0,
+                0,
+            ),
+            ReturnCallIndirect(ty, _) => (signature(ty.index())?.0 + 1, 0),
+            CallRef(ty) => {
+                let (params, results) = signature(ty.index())?;
+                (params + 1, results)
+            }
+            ReturnCallRef(ty) => (signature(ty.index())?.0 + 1, 0),
+            // Like `br_if` the reference stays on the stack when the branch isn't taken
+            BrOnNull(_) | RefAsNonNull => (1, 1),
+            BrOnNonNull(_) => (1, 0),
+            Unreachable | Nop | Br(_) | Return | ElemDrop(_) | DataDrop(_) | atomic_fence => (0, 0),
+            BrIf(_) | BrTable(..) | Drop | LocalSet(_) | GlobalSet(_) => (1, 0),
+            RefNull(_) | RefFunc(_) | LocalGet(_) | GlobalGet(_) | TableSize(_) | MemorySize
+            | i32_const(_) | i64_const(_) | f32_const(_) | f64_const(_) => (0, 1),
+            RefIsNull | LocalTee(_) | TableGet(_) | MemoryGrow => (1, 1),
+            TableSet(_) => (2, 0),
+            TableGrow(_) => (2, 1),
+            TableInit(..) | TableCopy(..) | TableFill(_) | MemoryInit(_) | MemoryCopy
+            | MemoryFill => (3, 0),
+            Select | SelectMultiple(_) => (3, 1),
+            instruction => match (
+                memory_instruction(instruction),
+                lane_memory_instruction(instruction),
+            ) {
+                (Some((mnemonic, ..)), _) | (_, Some((mnemonic, ..))) => {
+                    let contains = |part| mnemonic.contains(part);
+                    if contains("store") {
+                        (2, 0)
+                    } else if contains("cmpxchg") || contains("wait") {
+                        (3, 1)
+                    } else if contains("lane") || contains("rmw") || contains("notify") {
+                        (2, 1)
+                    } else {
+                        (1, 1)
+                    }
+                }
+                (None, None) => {
+                    let (params, results) = numeric_signature(instruction)?;
+                    (params.len(), results.len())
+                }
+            },
+        })
+    }
+
+    /// The mnemonic and immediates of an instruction that isn't a `block`, `loop` or `if`
+    fn instruction(&self, instruction: &Instructions, scope: &Scope, frames: &[Frame]) -> String {
+        use Instructions::*;
+
+        let local = |local: &Indecies| match scope.locals.get(&local.index()) {
+            Some(id) => id.clone(),
+            None => local.index().to_string(),
+        };
+        match instruction {
+            Br(label) => format!("br {}", self::label(frames, label.index())),
+            BrIf(label) => format!("br_if {}", self::label(frames, label.index())),
+            BrTable(labels, default) => {
+                let mut text = "br_table".to_string();
+                for label in labels.iter().chain([default]) {
+                    write!(text, " {}", self::label(frames, label.index())).unwrap();
+                }
+                text
+            }
+            Call(function) => format!("call {}", self.index(Space::Func, function.index())),
+            CallIndirect(ty, table) => format!(
+                "call_indirect {} (type {})",
+                self.index(Space::Table, table.index()),
+                self.index(Space::Type, ty.index())
+            ),
+            ReturnCall(function) => {
+                format!("return_call {}", self.index(Space::Func, function.index()))
+            }
+            ReturnCallIndirect(ty, table) => format!(
+                "return_call_indirect {} (type {})",
+                self.index(Space::Table, table.index()),
+                self.index(Space::Type, ty.index())
+            ),
+            CallRef(ty) => format!("call_ref {}", self.index(Space::Type, ty.index())),
+            ReturnCallRef(ty) => {
+                format!("return_call_ref {}", self.index(Space::Type, ty.index()))
+            }
+            BrOnNull(label) => format!("br_on_null {}", self::label(frames, label.index())),
+            BrOnNonNull(label) => {
+                format!("br_on_non_null {}", self::label(frames, label.index()))
+            }
+            RefNull(ty) => match ty.heap_type() {
+                HeapType::TypeIdx(index) => format!("ref.null {}", self.index(Space::Type, index)),
+                heap => format!("ref.null {heap}"),
+            },
+            RefFunc(function) => format!("ref.func {}", self.index(Space::Func, function.index())),
+            Throw(tag) => format!("throw {}", self.index(Space::Tag, tag.index())),
+            SelectMultiple(types) => format!("select (result{})", value_types(types)),
+            LocalGet(index) => format!("local.get {}", local(index)),
+            LocalSet(index) => format!("local.set {}", local(index)),
+            LocalTee(index) => format!("local.tee {}", local(index)),
+            GlobalGet(global) => {
+                format!("global.get {}", self.index(Space::Global, global.index()))
+            }
+            GlobalSet(global) => {
+                format!("global.set {}", self.index(Space::Global, global.index()))
+            }
+            TableGet(table) => format!("table.get {}", self.index(Space::Table, table.index())),
+            TableSet(table) => format!("table.set {}", self.index(Space::Table, table.index())),
+            TableSize(table) => format!("table.size {}", self.index(Space::Table, table.index())),
+            TableGrow(table) => format!("table.grow {}", self.index(Space::Table, table.index())),
+            TableFill(table) => format!("table.fill {}", self.index(Space::Table, table.index())),
+            TableCopy(destination, source) => format!(
+                "table.copy {} {}",
+                self.index(Space::Table, destination.index()),
+                self.index(Space::Table, source.index())
+            ),
+            TableInit(elem, table) => format!(
+                "table.init {} {}",
+                self.index(Space::Table, table.index()),
+                self.index(Space::Elem, elem.index())
+            ),
+            ElemDrop(elem) => format!("elem.drop {}", self.index(Space::Elem, elem.index())),
+            MemoryInit(data) => format!("memory.init {}", self.index(Space::Data, data.index())),
+            DataDrop(data) => format!("data.drop {}", self.index(Space::Data, data.index())),
+            i32_const(value) => format!("i32.const {value}"),
+            i64_const(value) => format!("i64.const {value}"),
+            f32_const(bits) => format!("f32.const {}", f32(f32::from_bits(*bits))),
+            f64_const(bits) => format!("f64.const {}", f64(f64::from_bits(*bits))),
+            v128_const(value) => {
+                // Four 32-bit lanes in hexadecimal show the bytes without losing any of them
+                let mut text = "v128.const i32x4".to_string();
+                for lane in 0..4 {
+                    write!(text, " 0x{:08x}", (value >> (lane * 32)) as u32).unwrap();
+                }
+                text
+            }
+            i8x16_shuffle(lanes) => {
+                let mut text = "i8x16.shuffle".to_string();
+                for lane in lanes {
+                    write!(text, " {lane}").unwrap();
+                }
+                text
+            }
+            instruction => {
+                if let Some((mnemonic, memarg, natural_align)) = memory_instruction(instruction) {
+                    return format!("{mnemonic}{}", self::memarg(memarg, natural_align));
+                }
+                if let Some((mnemonic, memarg, natural_align, lane)) =
+                    lane_memory_instruction(instruction)
+                {
+                    return format!("{mnemonic}{} {lane}", self::memarg(memarg, natural_align));
+                }
+                if let Some((mnemonic, lane)) = lane_instruction(instruction) {
+                    return format!("{mnemonic} {lane}");
+                }
+                plain_mnemonic(instruction)
+                    .expect("every instruction with immediates is handled above")
+                    .to_string()
+            }
+        }
+    }
+
+    /// The `$name` of an item, or its index if it doesn't have a name
+    /// A catch clause of a `try_table`, with a leading space
+    fn catch(&self, catch: &Catch, frames: &[Frame]) -> String {
+        let label = label(frames, catch.label().index());
+        match catch {
+            Catch::Catch(tag, _) => {
+                format!(" (catch {} {label})", self.index(Space::Tag, tag.index()))
+            }
+            Catch::CatchRef(tag, _) => {
+                format!(
+                    " (catch_ref {} {label})",
+                    self.index(Space::Tag, tag.index())
+                )
+            }
+            Catch::CatchAll(_) => format!(" (catch_all {label})"),
+            Catch::CatchAllRef(_) => format!(" (catch_all_ref {label})"),
+        }
+    }
+
+    fn index(&self, space: Space, index: u32) -> String {
+        match self.ids[space as usize].get(&index) {
+            Some(id) => id.clone(),
+            None => index.to_string(),
+        }
+    }
+
+    /// The `$name` and a comment with the index of an item where it's defined
+    fn declaration(&self, space: Space, index: usize) -> String {
+        match self.ids[space as usize].get(&(index as u32)) {
+            Some(id) => format!(" {id} (;{index};)"),
+            None => format!(" (;{index};)"),
+        }
+    }
+
+    /// The offset of an entry of the section `id`
+    fn entry_offset(&self, id: usize, index: usize) -> Option<usize> {
+        self.offsets.as_ref()?.entries[id].get(index).copied()
+    }
+
+    fn line(&mut self, offset: Option<usize>, depth: usize, text: String) {
+        self.lines.push(Line {
+            offset,
+            depth,
+            text,
+        });
+    }
+
+    /// Closes the parenthesis opened last, at the end of the last line
+    fn close(&mut self) {
+        if let Some(line) = self.lines.last_mut() {
+            line.text.push(')');
+        }
+    }
+
+    fn finish(self) -> String {
+        // Lines without an offset are padded so the code after the comments lines up
+        let width = self
+            .lines
+            .iter()
+            .filter_map(|line| line.offset)
+            .max()
+            .map_or(1, |offset| format!("{offset:x}").len());
+
+        let mut text = String::new();
+        for line in &self.lines {
+            if self.options.offsets {
+                match line.offset {
+                    Some(offset) => write!(text, "(;@{offset:<width$x};)  ").unwrap(),
+                    None => text.push_str(&" ".repeat(width + 7)),
+                }
+            }
+            for _ in 0..line.depth {
+                text.push_str("  ");
+            }
+            text.push_str(&line.text);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// The `$names` printed for the items in a name map. Names that aren't valid identifiers or that were
+/// already given to an earlier item are left out, those items are referred to by their index
+fn ids(map: &NameMap) -> HashMap<u32, String> {
+    let mut taken = HashSet::new();
+    map.iter()
+        .filter(|(_, name)| is_id(name.as_str()) && taken.insert(name.as_str()))
+        .map(|(index, name)| (*index, format!("${name}")))
+        .collect()
+}
+
+fn is_id(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(is_idchar)
+}
+
+fn block_of(nodes: &mut [Node], node: Option<usize>) -> &mut Block {
+    node.and_then(|node| nodes[node].block.as_mut())
+        .expect("only blocks have nested frames")
+}
+
+/// The label at `depth` by name, unless an inner label with the same name hides it
+fn label(frames: &[Frame], depth: u32) -> String {
+    let mut labels = frames.iter().rev().map(|frame| frame.label.as_deref());
+    let inner: Vec<_> = labels.by_ref().take(depth as usize).collect();
+    match labels.next() {
+        Some(Some(label)) if !inner.contains(&Some(label)) => label.to_string(),
+        _ => depth.to_string(),
+    }
+}
+
+/// The parameters and results of a function type, parameters with a local name get their own `(param ...)`
+fn signature(ty: &FunctionType, scope: &Scope) -> String {
+    let mut text = String::new();
+    let mut unnamed = vec![];
+    for (index, param) in ty.params.iter().enumerate() {
+        match scope.locals.get(&(index as u32)) {
+            Some(id) => {
+                if !unnamed.is_empty() {
+                    write!(text, " (param{})", value_types(&unnamed)).unwrap();
+                    unnamed.clear();
+                }
+                write!(text, " (param {id} {param})").unwrap();
+            }
+            None => unnamed.push(*param),
+        }
+    }
+    if !unnamed.is_empty() {
+        write!(text, " (param{})", value_types(&unnamed)).unwrap();
+    }
+    if !ty.result.is_empty() {
+        write!(text, " (result{})", value_types(&ty.result)).unwrap();
+    }
+    text
+}
+
+fn value_types(types: &[ValueType]) -> String {
+    types.iter().map(|ty| format!(" {ty}")).collect()
+}
+
+fn limits(limits: &Limits) -> String {
+    let index_type = match limits.is_64 {
+        true => "i64 ",
+        false => "",
+    };
+    match limits.maximum() {
+        Some(max) if limits.is_shared() => {
+            format!("{index_type}{} {max} shared", limits.minimum())
+        }
+        Some(max) => format!("{index_type}{} {max}", limits.minimum()),
+        None => format!("{index_type}{}", limits.minimum()),
+    }
+}
+
+fn global_type(ty: &GlobalType) -> String {
+    match ty.mutability {
+        Mutability::Const => ty.vtype.to_string(),
+        Mutability::Var => format!("(mut {})", ty.vtype),
+    }
+}
+
+/// A float in the shortest decimal form that parses back to the same value, NaNs are written with their payload
+/// The `offset=` and `align=` of a memory instruction, if they aren't the defaults
+fn memarg(memarg: &MemArg, natural_align: u32) -> String {
+    let mut text = String::new();
+    if memarg.offset != 0 {
+        write!(text, " offset={}", memarg.offset).unwrap();
+    }
+    if memarg.align != natural_align {
+        write!(text, " align={}", 1u64 << memarg.align.min(63)).unwrap();
+    }
+    text
+}
+
+fn f32(value: f32) -> String {
+    match value.is_nan() {
+        true => nan(value.is_sign_negative(), value.to_bits() as u64 & 0x7F_FFFF),
+        false => format!("{value:?}"),
+    }
+}
+
+fn f64(value: f64) -> String {
+    match value.is_nan() {
+        true => nan(
+            value.is_sign_negative(),
+            value.to_bits() & 0xF_FFFF_FFFF_FFFF,
+        ),
+        false => format!("{value:?}"),
+    }
+}
+
+fn nan(negative: bool, payload: u64) -> String {
+    format!("{}nan:0x{payload:x}", if negative { "-" } else { "" })
+}
+
+/// A string literal, bytes other than printable ASCII are escaped
+fn string(bytes: &[u8]) -> String {
+    let mut text = String::from('"');
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => {
+                text.push('\\');
+                text.push(*byte as char);
+            }
+            0x20..=0x7E => text.push(*byte as char),
+            _ => write!(text, "\\{byte:02x}").unwrap(),
+        }
+    }
+    text.push('"');
+    text
+}
@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+use bytereader::ByteReader;
+use swai_parser::{
+    instructions::Instructions,
+    types::{Code, NumberTypes, ValueType},
+    WasmModule,
+};
+
+mod common;
+
+use common::{FUNC_TYPE, ONE_FUNCTION};
+
+/// True if `slice` lies within `bytes`
+fn points_into(bytes: &[u8], slice: &[u8]) -> bool {
+    bytes.as_ptr_range().contains(&slice.as_ptr())
+}
+
+#[test]
+fn reader_returns_slices_of_the_input() {
+    let bytes = b"\x05hello world";
+    let mut reader = ByteReader::from_slice(bytes);
+
+    let length = reader.read::<u8>().unwrap() as usize;
+    let string = reader.read_string(length).unwrap();
+    let rest = reader.read_rest().unwrap();
+    drop(reader);
+
+    assert_eq!(string, "hello");
+    assert!(points_into(bytes, string.as_bytes()));
+    assert_eq!(rest, b" world");
+    assert!(points_into(bytes, rest));
+}
+
+#[test]
+fn parsed_modules_borrow_from_the_input() {
+    let bytes = include_bytes!("../../../tests/helloworld.wasm");
+    let module = WasmModule::from_bytes(bytes).unwrap();
+
+    let (import_module, import_name, _) = &module.sections.imports[0];
+    assert!(points_into(bytes, import_module.as_str().as_bytes()));
+    assert!(points_into(bytes, import_name.as_str().as_bytes()));
+
+    let segment = &module.sections.data[0];
+    assert!(matches!(segment.bytes, Cow::Borrowed(_)));
+    assert!(points_into(bytes, &segment.bytes));
+
+    let custom = &module.sections.custom[0];
+    assert!(points_into(bytes, &custom.data));
+    assert_eq!(&bytes[custom.range.clone()], &custom.data[..]);
+
+    for code in &module.sections.code {
+        assert!(matches!(code.bytes, Cow::Borrowed(_)));
+        assert_eq!(&bytes[code.range.clone()], &code.bytes[..]);
+    }
+}
+
+#[test]
+fn owned_modules_outlive_the_input() {
+    let bytes = include_bytes!("../../../tests/helloworld.wasm").to_vec();
+    let borrowed = WasmModule::from_bytes(&bytes).unwrap();
+    let expected = format!("{borrowed:?}");
+
+    let owned = borrowed.into_owned();
+    drop(bytes);
+
+    assert!(matches!(owned.sections.data[0].bytes, Cow::Owned(_)));
+    assert!(matches!(owned.sections.code[0].bytes, Cow::Owned(_)));
+    assert_eq!(format!("{owned:?}"), expected);
+}
+
+#[test]
+fn code_bytes_are_the_encoded_locals_and_body() {
+    let i32 = ValueType::NumType(NumberTypes::i32);
+    let code = Code::new(vec![(2, i32)], vec![Instructions::Nop]);
+    assert_eq!(code.bytes.as_ref(), [0x01, 0x02, 0x7F, 0x01, 0x0B]);
+    assert_eq!(code.range, 0..0);
+
+    // Parsing a module with the function borrows the same bytes back
+    let bytes = common::module(&[
+        FUNC_TYPE,
+        ONE_FUNCTION,
+        (10, &common::code(&[0x01, 0x02, 0x7F], &[0x01])),
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    assert_eq!(module.sections.code[0].bytes, code.bytes);
+    assert_eq!(module.sections.code[0], code);
+}
@@ -0,0 +1,130 @@
+use swai_parser::{
+    error::WasmParserErrorKind,
+    instructions::{Instructions, MemArg},
+    types::{FunctionType, GlobalType, Indecies, Limits, Mutability, NumberTypes, ValueType},
+    validate, ModuleBuilder, WasmModule,
+};
+
+const I32: ValueType = ValueType::NumType(NumberTypes::i32);
+const I64: ValueType = ValueType::NumType(NumberTypes::i64);
+
+#[test]
+fn indices_are_assigned_after_imports() {
+    let mut builder = ModuleBuilder::new();
+    let log = builder
+        .import_function("env", "log", FunctionType::new(vec![I32], vec![]))
+        .unwrap();
+    let memory = builder
+        .import_memory("env", "memory", Limits::new(1, None))
+        .unwrap();
+    let offset = builder
+        .import_global("env", "offset", GlobalType::new(I32, Mutability::Const))
+        .unwrap();
+    let print = builder
+        .import_function("env", "print", FunctionType::new(vec![I32], vec![]))
+        .unwrap();
+    assert_eq!((log, memory, offset, print), (0, 0, 0, 1));
+
+    let counter = builder.add_global(
+        GlobalType::new(I64, Mutability::Var),
+        vec![Instructions::i64_const(0)],
+    );
+    let limit = builder.add_global(
+        GlobalType::new(I32, Mutability::Const),
+        vec![Instructions::i32_const(64)],
+    );
+    let call_log = builder.add_function(
+        FunctionType::new(vec![], vec![]),
+        &[],
+        vec![
+            Instructions::GlobalGet(Indecies::GlobalIdx(offset)),
+            Instructions::Call(Indecies::FuncIdx(log)),
+        ],
+    );
+    assert_eq!((counter, limit, call_log), (1, 2, 2));
+    builder.export_function("call_log", call_log);
+
+    let module = builder.build();
+    validate(&module).unwrap();
+
+    // Equal function types are only added once
+    assert_eq!(module.sections.types.len(), 2);
+    assert_eq!(
+        module.export("call_log").map(|export| export.index),
+        Some(2)
+    );
+}
+
+#[test]
+fn built_modules_round_trip() {
+    let mut builder = ModuleBuilder::new();
+    let memory = builder.add_memory(Limits::new(1, None));
+    builder.add_data(memory, vec![Instructions::i32_const(8)], b"hello");
+    let passive = builder.add_passive_data(*b"world");
+    assert_eq!(passive, 1);
+
+    let load = builder.add_function(
+        FunctionType::new(vec![I32], vec![I32]),
+        &[I32, I32, I64],
+        vec![
+            Instructions::LocalGet(Indecies::LocalIdx(0)),
+            Instructions::i32_load(MemArg {
+                align: 2,
+                offset: 0,
+            }),
+        ],
+    );
+    let init = builder.add_function(
+        FunctionType::new(vec![], vec![]),
+        &[],
+        vec![Instructions::DataDrop(Indecies::DataIdx(passive))],
+    );
+    builder
+        .export_function("load", load)
+        .export_memory("memory", memory)
+        .start(init);
+
+    let bytes = builder.to_bytes();
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+    assert_eq!(module.to_bytes(), bytes);
+
+    // Locals are stored run-length encoded and the data count is set for `data.drop`
+    assert_eq!(module.sections.code[0].locals, [(2, I32), (1, I64)]);
+    assert_eq!(module.sections.data_count, Some(2));
+    assert_eq!(module.sections.start, Some(Indecies::FuncIdx(init)));
+}
+
+#[test]
+fn imports_after_definitions_are_errors() {
+    let mut builder = ModuleBuilder::new();
+    builder.add_function(FunctionType::new(vec![], vec![]), &[], vec![]);
+    builder.add_global(
+        GlobalType::new(I32, Mutability::Const),
+        vec![Instructions::i32_const(0)],
+    );
+    // Memories aren't defined, so importing one still works
+    assert_eq!(
+        builder
+            .import_memory("env", "memory", Limits::new(1, None))
+            .unwrap(),
+        0
+    );
+
+    let err = builder
+        .import_function("env", "late", FunctionType::new(vec![], vec![]))
+        .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::ImportAfterDefinition { kind: "functions" }
+    ));
+    let err = builder
+        .import_global("env", "late", GlobalType::new(I32, Mutability::Const))
+        .unwrap_err();
+    assert_eq!(
+        err.kind.to_string(),
+        "globals have to be imported before any are defined"
+    );
+    // The failed imports aren't added
+    assert_eq!(builder.build().sections.imports.len(), 1);
+}
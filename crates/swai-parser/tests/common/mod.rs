@@ -0,0 +1,59 @@
+//! Builders for binary modules the integration tests share, for the tests that need bytes
+//! the text format or [`swai_parser::ModuleBuilder`] can't produce
+#![allow(dead_code)]
+
+/// The magic number and version of a core module
+pub const HEADER: [u8; 8] = [0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+
+/// The `(type (func))` type section
+pub const FUNC_TYPE: (u8, &[u8]) = (1, &[0x01, 0x60, 0x00, 0x00]);
+/// A function section declaring a single function of type 0
+pub const ONE_FUNCTION: (u8, &[u8]) = (3, &[0x01, 0x00]);
+
+/// The unsigned LEB128 encoding of `value`
+pub fn leb128(mut value: u64) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// A section with the given id and contents, preceded by its size
+pub fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+    [&[id], leb128(contents.len() as u64).as_slice(), contents].concat()
+}
+
+/// Builds a module from the header and `(id, contents)` sections, in the given order
+pub fn module(sections: &[(u8, &[u8])]) -> Vec<u8> {
+    let mut bytes = HEADER.to_vec();
+    for (id, contents) in sections {
+        bytes.extend(section(*id, contents));
+    }
+    bytes
+}
+
+/// The contents of a code section with a single function with the given locals and body,
+/// the `end` of the body is added
+pub fn code(locals: &[u8], body: &[u8]) -> Vec<u8> {
+    let entry = [locals, body, &[0x0B]].concat();
+    [&[0x01], leb128(entry.len() as u64).as_slice(), &entry].concat()
+}
+
+/// Builds a module from the given sections, followed by a code section with a single function
+/// without locals that has the given body
+pub fn function(sections: &[(u8, &[u8])], body: &[u8]) -> Vec<u8> {
+    let code = code(&[0x00], body);
+    module(&[sections, &[(10, code.as_slice())]].concat())
+}
+
+/// A module with a memory of the given limits and a single `(func)` with the given body
+pub fn memory_function(limits: &[u8], body: &[u8]) -> Vec<u8> {
+    let memory = [&[0x01], limits].concat();
+    function(&[FUNC_TYPE, ONE_FUNCTION, (5, &memory)], body)
+}
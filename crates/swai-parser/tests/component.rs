@@ -0,0 +1,282 @@
+use swai_parser::{
+    component::{
+        Canonical, CanonicalOption, ComponentResults, ComponentSection, ComponentType,
+        ComponentValueType, DefinedType, ExternDesc, PrimitiveType, Sort, SortIndex,
+        TypeDeclaration, MAX_COMPONENT_NESTING_DEPTH,
+    },
+    error::WasmParserErrorKind,
+    wasm::Encoding,
+    WasmComponent, WasmModule,
+};
+
+mod common;
+
+use common::{module, section, FUNC_TYPE};
+
+const PREAMBLE: [u8; 8] = [0x00, 0x61, 0x73, 0x6D, 0x0D, 0x00, 0x01, 0x00];
+
+/// A module with a single (type (func))
+fn core_module() -> Vec<u8> {
+    module(&[FUNC_TYPE])
+}
+
+/// A component importing `log: func(x: u32) -> u32` and exporting it again as `run`,
+/// next to a core module that gets instantiated
+fn component() -> Vec<u8> {
+    [
+        PREAMBLE.to_vec(),
+        // (type (func (param "x" u32) (result u32)))
+        section(7, &[0x01, 0x40, 0x01, 0x01, b'x', 0x79, 0x00, 0x79]),
+        // (import "log" (func (type 0)))
+        section(10, &[0x01, 0x00, 0x03, b'l', b'o', b'g', 0x01, 0x00]),
+        section(1, &core_module()),
+        // (core instance (instantiate 0))
+        section(2, &[0x01, 0x00, 0x00, 0x00]),
+        // (core func (alias core export 0 "f"))
+        section(6, &[0x01, 0x00, 0x00, 0x01, 0x00, 0x01, b'f']),
+        // (func (canon lift (core func 0) string-encoding=utf8) (type 0))
+        section(8, &[0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00]),
+        // (export "run" (func 0))
+        section(11, &[0x01, 0x00, 0x03, b'r', b'u', b'n', 0x01, 0x00, 0x00]),
+    ]
+    .concat()
+}
+
+fn component_with_section(id: u8, contents: &[u8]) -> Vec<u8> {
+    [PREAMBLE.as_slice(), &section(id, contents)].concat()
+}
+
+#[test]
+fn preambles_are_detected() {
+    assert_eq!(Encoding::detect(&core_module()), Some(Encoding::Module));
+    assert_eq!(Encoding::detect(&component()), Some(Encoding::Component));
+    assert_eq!(Encoding::detect(&PREAMBLE[..6]), None);
+    assert_eq!(
+        Encoding::detect(&[0x00, 0x61, 0x73, 0x6D, 0x02, 0x00, 0x00, 0x00]),
+        None
+    );
+
+    let err = WasmModule::from_bytes(&component()).unwrap_err();
+    match err.kind {
+        WasmParserErrorKind::InvalidWasmBytes { message } => {
+            assert!(message.contains("WasmComponent"), "{message}")
+        }
+        kind => panic!("expected an InvalidWasmBytes error, got {kind:?}"),
+    }
+    assert_eq!(err.offset, Some(4));
+
+    let err = WasmComponent::from_bytes(&core_module()).unwrap_err();
+    match err.kind {
+        WasmParserErrorKind::InvalidWasmBytes { message } => {
+            assert!(message.contains("WasmModule"), "{message}")
+        }
+        kind => panic!("expected an InvalidWasmBytes error, got {kind:?}"),
+    }
+    assert_eq!(err.context, ["component header"]);
+}
+
+#[test]
+fn imports_and_exports_are_listed() {
+    let bytes = component();
+    let component = WasmComponent::from_bytes(&bytes).unwrap();
+    assert_eq!(component.sections.len(), 7);
+
+    let imports: Vec<_> = component
+        .imports()
+        .map(|import| (import.name.as_str(), import.ty))
+        .collect();
+    assert_eq!(imports, [("log", ExternDesc::Func(0))]);
+
+    let export = component.exports().next().unwrap();
+    assert_eq!(export.name.as_str(), "run");
+    assert_eq!(
+        export.index,
+        SortIndex {
+            sort: Sort::Func,
+            index: 0
+        }
+    );
+    assert_eq!(export.ty, None);
+
+    let ComponentSection::Types(types) = &component.sections[0] else {
+        panic!("expected a type section, got {:?}", component.sections[0]);
+    };
+    let ComponentType::Function(function) = &types[0] else {
+        panic!("expected a function type, got {:?}", types[0]);
+    };
+    assert_eq!(function.params[0].0.as_str(), "x");
+    assert_eq!(
+        function.results,
+        ComponentResults::Unnamed(ComponentValueType::Primitive(PrimitiveType::U32))
+    );
+    assert_eq!(
+        component.sections[5],
+        ComponentSection::Canonicals(vec![Canonical::Lift {
+            core_function: 0,
+            options: vec![CanonicalOption::Utf8],
+            ty: 0
+        }])
+    );
+
+    // Owned components don't borrow from the bytes
+    let owned = WasmComponent::from_bytes(&bytes).unwrap().into_owned();
+    drop(bytes);
+    assert_eq!(owned.exports().count(), 1);
+}
+
+#[test]
+fn nested_modules_and_components_are_decoded() {
+    let inner = [PREAMBLE.to_vec(), section(1, &core_module())].concat();
+    let bytes = [
+        PREAMBLE.to_vec(),
+        section(4, &inner),
+        section(1, &core_module()),
+    ]
+    .concat();
+    let component = WasmComponent::from_bytes(&bytes).unwrap();
+
+    // Modules are parsed like top level ones
+    let modules: Vec<_> = component.modules().collect();
+    assert_eq!(modules.len(), 1);
+    assert_eq!(modules[0].to_bytes(), core_module());
+
+    let ComponentSection::Component(nested) = &component.sections[0] else {
+        panic!("expected a component, got {:?}", component.sections[0]);
+    };
+    assert_eq!(nested.modules().count(), 1);
+}
+
+#[test]
+fn defined_types_are_decoded() {
+    let bytes = component_with_section(
+        7,
+        &[
+            0x04, //
+            // (record (field "a" string))
+            0x72, 0x01, 0x01, b'a', 0x73, //
+            // (variant (case "none") (case "some" (type 0)))
+            0x71, 0x02, 0x04, b'n', b'o', b'n', b'e', 0x00, 0x00, 0x04, b's', b'o', b'm', b'e',
+            0x01, 0x00, 0x00, //
+            // (result (error u8))
+            0x6A, 0x00, 0x01, 0x7D, //
+            // (resource (rep i32) (dtor 2))
+            0x3F, 0x7F, 0x01, 0x02,
+        ],
+    );
+    let component = WasmComponent::from_bytes(&bytes).unwrap();
+    let ComponentSection::Types(types) = &component.sections[0] else {
+        panic!("expected a type section, got {:?}", component.sections[0]);
+    };
+    assert!(
+        matches!(&types[0], ComponentType::Defined(DefinedType::Record(fields)) if fields.len() == 1)
+    );
+    let ComponentType::Defined(DefinedType::Variant(cases)) = &types[1] else {
+        panic!("expected a variant, got {:?}", types[1]);
+    };
+    assert_eq!(cases[1].ty, Some(ComponentValueType::Type(0)));
+    assert_eq!(
+        types[2],
+        ComponentType::Defined(DefinedType::Result {
+            ok: None,
+            err: Some(ComponentValueType::Primitive(PrimitiveType::U8))
+        })
+    );
+    assert_eq!(
+        types[3],
+        ComponentType::Resource {
+            destructor: Some(2)
+        }
+    );
+}
+
+#[test]
+fn malformed_components_are_errors() {
+    let err = WasmComponent::from_bytes(&component_with_section(12, &[])).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::InvalidSectionId { id: 12 }
+    ));
+
+    // A canonical function of kind 0x09
+    let err = WasmComponent::from_bytes(&component_with_section(8, &[0x01, 0x09])).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "canonical function",
+            byte: 0x09
+        }
+    ));
+    assert_eq!(err.context, ["canon section", "canonical function 0"]);
+
+    // A list of the negative s33 -0x20, which isn't a primitive type
+    let err =
+        WasmComponent::from_bytes(&component_with_section(7, &[0x01, 0x70, 0x60])).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "value type",
+            byte: 0x60
+        }
+    ));
+
+    // Instance types can't import
+    let err = WasmComponent::from_bytes(&component_with_section(
+        7,
+        &[0x01, 0x42, 0x01, 0x03, 0x00, 0x01, b'f', 0x01, 0x00],
+    ))
+    .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "type declaration",
+            byte: 0x03
+        }
+    ));
+
+    // Errors in nested modules are reported like in top level ones
+    let module = module(&[(1, &[0x01, 0x61, 0x00, 0x00])]);
+    let err = WasmComponent::from_bytes(&component_with_section(1, &module)).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "function type",
+            ..
+        }
+    ));
+    assert_eq!(err.context[0], "core module section");
+}
+
+#[test]
+fn nesting_is_limited() {
+    // Instance types declaring instance types
+    let mut ty = vec![0x42, 0x00];
+    for _ in 0..MAX_COMPONENT_NESTING_DEPTH + 1 {
+        ty = [[0x42, 0x01, 0x01].as_slice(), &ty].concat();
+    }
+    let contents = [[0x01].as_slice(), &ty].concat();
+    let err = WasmComponent::from_bytes(&component_with_section(7, &contents)).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::ImplementationLimit {
+            construct: "type nesting depth",
+            ..
+        }
+    ));
+
+    // Just below the limit is fine
+    let mut ty = vec![0x42, 0x00];
+    for _ in 0..MAX_COMPONENT_NESTING_DEPTH - 1 {
+        ty = [[0x42, 0x01, 0x01].as_slice(), &ty].concat();
+    }
+    let contents = [[0x01].as_slice(), &ty].concat();
+    let bytes = component_with_section(7, &contents);
+    let component = WasmComponent::from_bytes(&bytes).unwrap();
+    let ComponentSection::Types(types) = &component.sections[0] else {
+        panic!("expected a type section, got {:?}", component.sections[0]);
+    };
+    assert!(matches!(
+        &types[0],
+        ComponentType::Instance(declarations)
+            if matches!(declarations[0], TypeDeclaration::Type(_))
+    ));
+}
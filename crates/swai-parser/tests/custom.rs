@@ -0,0 +1,115 @@
+use bytereader::{ByteReader, ByteReaderError, FromByteReader};
+use swai_parser::{
+    custom::CustomSectionRegistry,
+    error::{WasmParserError, WasmParserErrorKind},
+    WasmModule,
+};
+
+mod common;
+
+use common::{section, FUNC_TYPE, ONE_FUNCTION};
+
+/// A `build_id` section holding a length prefixed id
+#[derive(Debug, PartialEq)]
+struct BuildId(Vec<u8>);
+
+impl FromByteReader<'_> for BuildId {
+    type Error = WasmParserError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader) -> Result<Self, WasmParserError> {
+        let length = reader.read::<u8>()? as usize;
+        Ok(BuildId(reader.read_bytes(length)?.to_vec()))
+    }
+}
+
+/// Only the first byte of a section
+#[derive(Debug, PartialEq)]
+struct FirstByte(u8);
+
+impl FromByteReader<'_> for FirstByte {
+    type Error = ByteReaderError;
+
+    fn read_from_byte_reader(reader: &mut ByteReader) -> Result<Self, ByteReaderError> {
+        reader.read().map(FirstByte)
+    }
+}
+
+/// A module with a single `(func)`, the given custom sections and a `producers` section after them
+fn module(custom: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut bytes = common::function(&[FUNC_TYPE, ONE_FUNCTION], &[]);
+    for (name, contents) in custom.iter().chain(&[("producers", &[0x00][..])]) {
+        let name = [&[name.len() as u8], name.as_bytes()].concat();
+        bytes.extend(section(0, &[&name, *contents].concat()));
+    }
+    bytes
+}
+
+#[test]
+fn registered_sections_are_decoded() {
+    let bytes = module(&[("build_id", &[0x03, 0xAA, 0xBB, 0xCC])]);
+    let mut registry = CustomSectionRegistry::new();
+    registry.register::<BuildId>("build_id");
+    let module = WasmModule::from_bytes_with_registry(&bytes, &registry).unwrap();
+
+    let build_id = module.sections.custom_section("build_id").unwrap();
+    assert_eq!(
+        build_id.decoded::<BuildId>().unwrap().unwrap(),
+        &BuildId(vec![0xAA, 0xBB, 0xCC])
+    );
+    // Asking for another type doesn't find anything
+    assert!(build_id.decoded::<FirstByte>().is_none());
+    // Sections without a decoder are only kept as raw bytes
+    let producers = module.sections.custom_section("producers").unwrap();
+    assert!(producers.decoded.is_none());
+    assert_eq!(producers.data, [0x00].as_slice());
+
+    // Without the registry nothing is decoded
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    assert!(module.sections.custom[0].decoded::<BuildId>().is_none());
+}
+
+#[test]
+fn decoders_cant_read_past_their_section() {
+    // The id claims 4 bytes but the section only has 3, the next section follows right after
+    let bytes = module(&[("build_id", &[0x04, 0xAA, 0xBB, 0xCC])]);
+    let mut registry = CustomSectionRegistry::new();
+    registry.register::<BuildId>("build_id");
+    let module = WasmModule::from_bytes_with_registry(&bytes, &registry).unwrap();
+
+    let error = module.sections.custom[0]
+        .decoded::<BuildId>()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(
+        error.kind,
+        WasmParserErrorKind::ParserError(ByteReaderError::OutOfBounds { .. })
+    ));
+    assert_eq!(error.context, ["custom section 'build_id'"]);
+
+    // The failure only affects the section, the ones after it are still parsed
+    assert_eq!(module.sections.custom.len(), 2);
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn registering_a_name_again_replaces_its_decoder() {
+    let bytes = module(&[("build_id", &[0x01, 0xAA]), ("version", &[0x02])]);
+    let mut registry = CustomSectionRegistry::new();
+    registry
+        .register::<BuildId>("build_id")
+        .register::<FirstByte>("build_id")
+        .register::<FirstByte>("version");
+    let module = WasmModule::from_bytes_with_registry(&bytes, &registry).unwrap();
+
+    let build_id = module.sections.custom_section("build_id").unwrap();
+    assert!(build_id.decoded::<BuildId>().is_none());
+    assert_eq!(
+        build_id.decoded::<FirstByte>().unwrap().unwrap(),
+        &FirstByte(0x01)
+    );
+    let version = module.sections.custom_section("version").unwrap();
+    assert_eq!(
+        version.decoded::<FirstByte>().unwrap().unwrap(),
+        &FirstByte(0x02)
+    );
+}
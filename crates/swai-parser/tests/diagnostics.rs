@@ -0,0 +1,53 @@
+use swai_parser::{error::WasmParserErrorKind, WasmModule};
+
+/// (func (i32.load <truncated memarg>)), the body ends in the middle of the alignment LEB
+const TRUNCATED_MEMARG: [u8; 29] = [
+    0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+    0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section
+    0x03, 0x02, 0x01, 0x00, // function section
+    0x0A, 0x07, 0x01, 0x05, 0x00, 0x41, 0x00, 0x28, 0x80, // code section
+    0x00, 0x00, // bytes past the end of the function body
+];
+
+#[test]
+fn errors_carry_the_context_path() {
+    let err = WasmModule::from_bytes(&TRUNCATED_MEMARG).unwrap_err();
+
+    assert!(matches!(err.kind, WasmParserErrorKind::ParserError(_)));
+    assert_eq!(
+        err.context,
+        ["code section", "function 0", "instruction 1", "memarg"]
+    );
+    assert_eq!(
+        err.context_path(),
+        "code section › function 0 › instruction 1 › memarg"
+    );
+}
+
+#[test]
+fn offsets_inside_function_bodies_are_absolute() {
+    let err = WasmModule::from_bytes(&TRUNCATED_MEMARG).unwrap_err();
+
+    // Reading stops at the end of the function body, not at the end of the module
+    assert_eq!(err.offset, Some(27));
+}
+
+#[test]
+fn display_renders_a_hex_snippet() {
+    // (type (func (param <0x42>)))
+    let bytes = [
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x01, 0x42, 0x00,
+    ];
+    let err = WasmModule::from_bytes(&bytes).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        [
+            "Unexpected byte 0x42 while parsing value type",
+            "  at offset 13 (0xD) in type section › type 0 › parameters",
+            "  00000000 | 00 61 73 6d 01 00 00 00 01 05 01 60 01 42 00",
+            "           |                                        ^^",
+        ]
+        .join("\n")
+    );
+}
@@ -0,0 +1,138 @@
+use swai_parser::{
+    error::WasmParserErrorKind,
+    instructions::Instructions,
+    types::{ElementInit, ElementMode, ElementSegment, Indecies, ReferenceTypes},
+    WasmModule,
+};
+
+mod common;
+
+use common::{FUNC_TYPE, ONE_FUNCTION};
+
+/// A module with a single `(func)` and an element section with the given contents
+fn module(elements: &[u8]) -> Vec<u8> {
+    common::function(&[FUNC_TYPE, ONE_FUNCTION, (9, elements)], &[])
+}
+
+fn active(table_index: u32, offset: i32) -> ElementMode {
+    ElementMode::Active {
+        table_index,
+        offset: vec![Instructions::i32_const(offset)],
+    }
+}
+
+fn functions() -> ElementInit {
+    ElementInit::Functions(vec![Indecies::FuncIdx(0)])
+}
+
+fn expressions(expression: Instructions) -> ElementInit {
+    ElementInit::Expressions(vec![vec![expression]])
+}
+
+#[test]
+fn all_eight_encodings_are_decoded() {
+    #[rustfmt::skip]
+    let elements = [
+        0x08,
+        // 0: active in table 0 with function indices
+        0x00, 0x41, 0x01, 0x0B, 0x01, 0x00,
+        // 1: passive with an elemkind and function indices
+        0x01, 0x00, 0x01, 0x00,
+        // 2: active in table 1 with an elemkind and function indices
+        0x02, 0x01, 0x41, 0x02, 0x0B, 0x00, 0x01, 0x00,
+        // 3: declarative with an elemkind and function indices
+        0x03, 0x00, 0x01, 0x00,
+        // 4: active in table 0 with expressions
+        0x04, 0x41, 0x03, 0x0B, 0x01, 0xD2, 0x00, 0x0B,
+        // 5: passive with a reftype and expressions
+        0x05, 0x6F, 0x01, 0xD0, 0x6F, 0x0B,
+        // 6: active in table 1 with a reftype and expressions
+        0x06, 0x01, 0x41, 0x04, 0x0B, 0x70, 0x01, 0xD2, 0x00, 0x0B,
+        // 7: declarative with a reftype and expressions
+        0x07, 0x70, 0x01, 0xD2, 0x00, 0x0B,
+    ];
+    let bytes = module(&elements);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    let ref_func = Instructions::RefFunc(Indecies::FuncIdx(0));
+    assert_eq!(
+        module.sections.element,
+        [
+            ElementSegment {
+                ty: ReferenceTypes::funcref,
+                init: functions(),
+                mode: active(0, 1),
+            },
+            ElementSegment {
+                ty: ReferenceTypes::funcref,
+                init: functions(),
+                mode: ElementMode::Passive,
+            },
+            ElementSegment {
+                ty: ReferenceTypes::funcref,
+                init: functions(),
+                mode: active(1, 2),
+            },
+            ElementSegment {
+                ty: ReferenceTypes::funcref,
+                init: functions(),
+                mode: ElementMode::Declarative,
+            },
+            ElementSegment {
+                ty: ReferenceTypes::funcref,
+                init: expressions(ref_func.clone()),
+                mode: active(0, 3),
+            },
+            ElementSegment {
+                ty: ReferenceTypes::externref,
+                init: expressions(Instructions::RefNull(ReferenceTypes::externref)),
+                mode: ElementMode::Passive,
+            },
+            ElementSegment {
+                ty: ReferenceTypes::funcref,
+                init: expressions(ref_func.clone()),
+                mode: active(1, 4),
+            },
+            ElementSegment {
+                ty: ReferenceTypes::funcref,
+                init: expressions(ref_func),
+                mode: ElementMode::Declarative,
+            },
+        ]
+    );
+
+    // Each segment already uses the shortest encoding, so it's written back unchanged
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn segments_are_encoded_with_the_shortest_encoding() {
+    // Encoding 2 naming table 0 explicitly
+    let bytes = module(&[0x01, 0x02, 0x00, 0x41, 0x00, 0x0B, 0x00, 0x01, 0x00]);
+    let decoded = WasmModule::from_bytes(&bytes).unwrap();
+    let encoded = decoded.to_bytes();
+
+    assert_eq!(encoded, module(&[0x01, 0x00, 0x41, 0x00, 0x0B, 0x01, 0x00]));
+    assert_eq!(WasmModule::from_bytes(&encoded).unwrap(), decoded);
+}
+
+#[test]
+fn invalid_flags_and_element_kinds_are_rejected() {
+    let error = WasmModule::from_bytes(&module(&[0x01, 0x08])).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        WasmParserErrorKind::UnexpectedValue {
+            construct: "element segment flags",
+            value: 8
+        }
+    ));
+
+    let error = WasmModule::from_bytes(&module(&[0x01, 0x01, 0x01, 0x00])).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "element kind",
+            byte: 0x01
+        }
+    ));
+}
@@ -0,0 +1,144 @@
+use swai_parser::{instructions::Instructions, WasmModule};
+
+mod common;
+
+use common::{module, FUNC_TYPE, HEADER, ONE_FUNCTION};
+
+const FIXTURES: [(&str, &[u8]); 6] = [
+    ("add", include_bytes!("../../../tests/add.wasm")),
+    ("asc_test", include_bytes!("../../../tests/asc_test.wasm")),
+    (
+        "helloworld",
+        include_bytes!("../../../tests/helloworld.wasm"),
+    ),
+    ("memory", include_bytes!("../../../tests/memory.wasm")),
+    ("module", include_bytes!("../../../tests/module.wasm")),
+    ("test", include_bytes!("../../../tests/test.wasm")),
+];
+
+#[test]
+fn fixtures_round_trip() {
+    for (name, bytes) in FIXTURES {
+        let module = WasmModule::from_bytes(bytes).unwrap();
+        let encoded = module.to_bytes();
+        let decoded = WasmModule::from_bytes(&encoded)
+            .unwrap_or_else(|err| panic!("{name}: re-encoded module does not parse: {err}"));
+
+        assert_eq!(decoded, module, "{name}");
+        assert_eq!(
+            decoded.to_bytes(),
+            encoded,
+            "{name}: encoding is not stable"
+        );
+    }
+}
+
+#[test]
+fn custom_sections_are_preserved_byte_for_byte() {
+    for (name, bytes) in FIXTURES {
+        let module = WasmModule::from_bytes(bytes).unwrap();
+        let encoded = module.to_bytes();
+        let decoded = WasmModule::from_bytes(&encoded).unwrap();
+
+        assert_eq!(
+            decoded.sections.custom.len(),
+            module.sections.custom.len(),
+            "{name}"
+        );
+        for (original, copy) in module.sections.custom.iter().zip(&decoded.sections.custom) {
+            assert_eq!(copy.name, original.name, "{name}");
+            assert_eq!(
+                &encoded[copy.range.clone()],
+                &bytes[original.range.clone()],
+                "{name}"
+            );
+        }
+    }
+}
+
+#[test]
+fn canonical_modules_encode_to_the_same_bytes() {
+    #[rustfmt::skip]
+    let code: &[u8] = &[
+        0x01, 0x25, 0x00,
+        // i32.const 1, if (result i32), i32.const -1, else, i32.const i32::MAX, end, drop
+        0x41, 0x01, 0x04, 0x7F, 0x41, 0x7F, 0x05, 0x41, 0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x0B, 0x1A,
+        // i32.const 0, if, nop, end
+        0x41, 0x00, 0x04, 0x40, 0x01, 0x0B,
+        // i64.const i64::MIN, drop
+        0x42, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7F, 0x1A,
+        // i32.const -64, end
+        0x41, 0x40, 0x0B,
+    ];
+    let bytes = module(&[
+        (0, &[0x05, b'f', b'i', b'r', b's', b't', 0x01, 0x02]),
+        (1, &[0x01, 0x60, 0x00, 0x01, 0x7F]),
+        (0, &[0x06, b's', b'e', b'c', b'o', b'n', b'd']),
+        (3, &[0x01, 0x00]),
+        (4, &[0x01, 0x70, 0x00, 0x01]),
+        (5, &[0x01, 0x00, 0x01]),
+        (
+            9,
+            &[
+                0x02, 0x00, 0x41, 0x00, 0x0B, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00,
+            ],
+        ),
+        (12, &[0x02]),
+        (10, code),
+        (
+            11,
+            &[
+                0x02, 0x01, 0x02, 0xAA, 0xBB, 0x00, 0x41, 0x00, 0x0B, 0x01, 0xCC,
+            ],
+        ),
+        (0, &[0x04, b'l', b'a', b's', b't']),
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn custom_sections_keep_their_position() {
+    // a custom section after an empty type section keeps the type section around
+    let bytes = module(&[(1, &[0x00]), (0, &[0x01, b'a']), (3, &[0x00])]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(module.sections.custom[0].after, Some(1));
+    assert_eq!(
+        module.to_bytes(),
+        [&HEADER[..], &[0x01, 0x01, 0x00, 0x00, 0x02, 0x01, b'a']].concat()
+    );
+}
+
+#[test]
+fn float_constants_keep_their_bit_patterns() {
+    #[rustfmt::skip]
+    let body = [
+        // f32.const nan:0x1, f32.const -0.0
+        0x43, 0x01, 0x00, 0xC0, 0x7F, 0x43, 0x00, 0x00, 0x00, 0x80,
+        // f64.const -nan:0x8000000000001, f64.const -0.0
+        0x44, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF8, 0xFF,
+        0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+    ];
+    let bytes = common::function(&[FUNC_TYPE, ONE_FUNCTION], &body);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    let encoded = module.to_bytes();
+
+    assert_eq!(encoded, bytes);
+    assert_eq!(WasmModule::from_bytes(&encoded).unwrap(), module);
+    assert_eq!(
+        module.sections.code[0].body,
+        [
+            Instructions::f32_const(0x7FC0_0001),
+            Instructions::f32_const((-0.0f32).to_bits()),
+            Instructions::f64_const(0xFFF8_0000_0000_0001),
+            Instructions::f64_const((-0.0f64).to_bits()),
+        ]
+    );
+    // -0.0 and 0.0 are different constants
+    assert_ne!(
+        Instructions::f32_const((-0.0f32).to_bits()),
+        Instructions::f32_const(0.0f32.to_bits())
+    );
+}
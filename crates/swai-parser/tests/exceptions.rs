@@ -0,0 +1,127 @@
+use swai_parser::{
+    error::{ValidationErrorKind, WasmParserErrorKind},
+    instructions::{BlockType, Catch, Instructions},
+    types::Indecies,
+    validate,
+    wat::PrintOptions,
+    WasmModule,
+};
+
+mod common;
+
+use common::section;
+
+/// A module with the types (func (param i32)) and (func), a tag of type 0 and a `(func)` with the given body
+fn module(body: &[u8]) -> Vec<u8> {
+    common::function(
+        &[
+            (1, &[0x02, 0x60, 0x01, 0x7F, 0x00, 0x60, 0x00, 0x00]),
+            (3, &[0x01, 0x01]),
+            (13, &[0x01, 0x00, 0x00]),
+        ],
+        body,
+    )
+}
+
+#[test]
+fn tags_and_catch_clauses_are_decoded() {
+    // block (result i32) try_table (catch 0 0) i32.const 7 throw 0 end unreachable end drop
+    let bytes = module(&[
+        0x02, 0x7F, 0x1F, 0x40, 0x01, 0x00, 0x00, 0x00, 0x41, 0x07, 0x08, 0x00, 0x0B, 0x00, 0x0B,
+        0x1A,
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    assert_eq!(module.sections.tags.len(), 1);
+    assert_eq!(module.sections.tags[0].type_index(), 0);
+    let Instructions::Block { body, .. } = &module.sections.code[0].body[0] else {
+        panic!("expected a block")
+    };
+    assert_eq!(
+        body[0],
+        Instructions::TryTable {
+            ty: BlockType::Empty,
+            catches: vec![Catch::Catch(Indecies::TagIdx(0), Indecies::LabelIdx(0))],
+            body: vec![
+                Instructions::i32_const(7),
+                Instructions::Throw(Indecies::TagIdx(0))
+            ],
+        }
+    );
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn sections_are_ordered() {
+    // The tag section goes between the memory and the global section
+    let mut bytes = module(&[]);
+    bytes.extend(section(5, &[0x01, 0x00, 0x01]));
+    let error = WasmModule::from_bytes(&bytes).unwrap_err();
+    assert!(
+        matches!(error.kind, WasmParserErrorKind::SectionOutOfOrder { .. }),
+        "{error}"
+    );
+}
+
+#[test]
+fn catch_clauses_are_type_checked() {
+    let module = WasmModule::from_wat(
+        r#"(module
+          (tag $e (param i32))
+          (func
+            (block $b (result i64)
+              (try_table (catch $e $b))
+              (i64.const 0))
+            drop))"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::CatchTypeMismatch { .. }
+    ));
+
+    // Tags can't have results
+    let module =
+        WasmModule::from_wat("(module (type (func (result i32))) (tag (type 0)))").unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::InvalidTagType
+    ));
+
+    let module =
+        WasmModule::from_wat("(module (func (param exnref) (throw_ref (ref.null exn))))").unwrap();
+    validate(&module).unwrap();
+}
+
+#[test]
+fn text_format_round_trips() {
+    let source = r#"
+        (module
+          (import "env" "error" (tag $error (param i32)))
+          (tag $other (export "other") (param i64 i32))
+          (func (param i32) (result i32 exnref)
+            (block $caught (result i32 exnref)
+              (block $any
+                (try_table (catch_ref $error $caught) (catch_all $any)
+                  (throw $error (local.get 0))))
+              (throw $other (i64.const 1) (i32.const 2)))))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap();
+    assert_eq!(module.sections.tags.len(), 1);
+
+    for options in [
+        PrintOptions::default(),
+        PrintOptions {
+            folded: true,
+            ..PrintOptions::default()
+        },
+    ] {
+        let printed = module.to_wat(&options).unwrap();
+        let reparsed = WasmModule::from_wat(&printed)
+            .unwrap_or_else(|err| panic!("{printed}\ndoes not parse: {err}"));
+        validate(&reparsed).unwrap_or_else(|err| panic!("{printed}\ndoes not validate: {err}"));
+        assert_eq!(reparsed.to_bytes(), module.to_bytes(), "{printed}");
+    }
+}
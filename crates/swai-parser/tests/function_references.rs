@@ -0,0 +1,223 @@
+use swai_parser::{
+    error::ValidationErrorKind,
+    instructions::Instructions,
+    types::{HeapType, Indecies, ReferenceTypes, ValueType},
+    validate,
+    wat::PrintOptions,
+    WasmModule,
+};
+
+mod common;
+
+fn validation_error(source: &str) -> ValidationErrorKind {
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap_err().kind
+}
+
+#[test]
+fn typed_references_are_decoded() {
+    // (type (func (param i32) (result i32)))
+    // (type (func (param (ref null 0)) (result i32)))
+    // (func (type 1) i32.const 5 local.get 0 ref.as_non_null call_ref 0)
+    let bytes = common::function(
+        &[
+            (
+                1,
+                &[
+                    0x02, 0x60, 0x01, 0x7F, 0x01, 0x7F, 0x60, 0x01, 0x63, 0x00, 0x01, 0x7F,
+                ],
+            ),
+            (3, &[0x01, 0x01]),
+        ],
+        &[0x41, 0x05, 0x20, 0x00, 0xD4, 0x14, 0x00],
+    );
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    assert_eq!(
+        module.sections.types[1].params(),
+        [ValueType::RefType(ReferenceTypes::Ref {
+            nullable: true,
+            heap: HeapType::TypeIdx(0)
+        })]
+    );
+    let code = &module.sections.code[0].body;
+    assert_eq!(code[2], Instructions::RefAsNonNull);
+    assert_eq!(code[3], Instructions::CallRef(Indecies::TypeIdx(0)));
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn nullable_abstract_references_are_shorthands() {
+    let module = WasmModule::from_wat(
+        "(module (func (param (ref null func) (ref extern)) (result (ref null exn)) (unreachable)))",
+    )
+    .unwrap();
+    assert_eq!(
+        module.sections.types[0].params(),
+        [
+            ValueType::RefType(ReferenceTypes::funcref),
+            ValueType::RefType(ReferenceTypes::Ref {
+                nullable: false,
+                heap: HeapType::Extern
+            })
+        ]
+    );
+    assert_eq!(
+        module.sections.types[0].results(),
+        [ValueType::RefType(ReferenceTypes::exnref)]
+    );
+}
+
+#[test]
+fn references_are_checked_at_the_call_site() {
+    assert!(matches!(
+        validation_error(
+            r#"(module
+              (type $t (func (param i32)))
+              (type $u (func (param i64)))
+              (func (param (ref null $t)) (call_ref $u (i64.const 0) (local.get 0))))"#
+        ),
+        ValidationErrorKind::TypeMismatch { .. }
+    ));
+
+    // Function references have the type of their function, equal types are interchangeable
+    let module = WasmModule::from_wat(
+        r#"(module
+          (type $t (func (param i32)))
+          (type $same (func (param i32)))
+          (func $f (type $t))
+          (elem declare func $f)
+          (func (result (ref $same)) (ref.func $f))
+          (func (result funcref) (ref.func $f)))"#,
+    )
+    .unwrap();
+    validate(&module).unwrap();
+
+    assert!(matches!(
+        validation_error(
+            r#"(module
+              (type $t (func (param i32)))
+              (type $u (func (param i64)))
+              (func $f (type $t))
+              (elem declare func $f)
+              (func (result (ref $u)) (ref.func $f)))"#
+        ),
+        ValidationErrorKind::TypeMismatch { .. }
+    ));
+
+    // Nullable references can't be used where non-null ones are expected
+    assert!(matches!(
+        validation_error(
+            r#"(module
+              (type $t (func))
+              (func (param (ref null $t)) (result (ref $t)) (local.get 0)))"#
+        ),
+        ValidationErrorKind::TypeMismatch { .. }
+    ));
+    assert!(matches!(
+        validation_error("(module (func (param (ref 3))))"),
+        ValidationErrorKind::UnknownIndex {
+            space: "type",
+            index: 3
+        }
+    ));
+}
+
+#[test]
+fn non_nullable_locals_have_to_be_set() {
+    assert!(matches!(
+        validation_error("(module (func (local (ref extern)) (drop (local.get 0))))"),
+        ValidationErrorKind::UninitializedLocal { index: 0 }
+    ));
+
+    // Locals set inside of a block are unset again after it
+    assert!(matches!(
+        validation_error(
+            r#"(module
+              (func (param (ref extern)) (local (ref extern))
+                (block (local.set 1 (local.get 0)) (drop (local.get 1)))
+                (drop (local.get 1))))"#
+        ),
+        ValidationErrorKind::UninitializedLocal { index: 1 }
+    ));
+
+    let module = WasmModule::from_wat(
+        r#"(module
+          (func (param externref) (result (ref extern)) (local (ref extern))
+            (local.set 1 (ref.as_non_null (local.get 0)))
+            (local.get 1)))"#,
+    )
+    .unwrap();
+    validate(&module).unwrap();
+}
+
+#[test]
+fn null_branches() {
+    let module = WasmModule::from_wat(
+        r#"(module
+          (func (param externref) (result (ref extern))
+            (block $null
+              (return (br_on_null $null (local.get 0))))
+            (unreachable))
+          (func (param externref) (result i32 (ref extern))
+            (br_on_non_null 0 (i32.const 1) (local.get 0))
+            (drop)
+            (unreachable)))"#,
+    )
+    .unwrap();
+    validate(&module).unwrap();
+
+    assert!(matches!(
+        validation_error(
+            "(module (func (param externref) (result i32) (br_on_non_null 0 (local.get 0)) (i32.const 0)))"
+        ),
+        ValidationErrorKind::InvalidBranchOnNonNull { label: 0 }
+    ));
+    assert!(matches!(
+        validation_error("(module (func (br_on_null 0 (i32.const 0)) (drop)))"),
+        ValidationErrorKind::InvalidOperand { .. }
+    ));
+}
+
+#[test]
+fn text_format_round_trips() {
+    let source = r#"
+        (module
+          (type $unary (func (param i32) (result i32)))
+          (table $functions (ref null $unary) (elem $double))
+          (global $default (ref null $unary) (ref.null $unary))
+          (func $double (type $unary) (i32.mul (local.get 0) (i32.const 2)))
+          (func (param (ref null $unary)) (result i32)
+            (block $null
+              (return (call_ref $unary (i32.const 1) (br_on_null $null (local.get 0)))))
+            (return_call_ref $unary
+              (i32.const 2)
+              (ref.as_non_null (table.get $functions (i32.const 0)))))
+          (func (param funcref) (result (ref func))
+            (br_on_non_null 0 (local.get 0))
+            (unreachable)))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap();
+
+    for options in [
+        PrintOptions::default(),
+        PrintOptions {
+            folded: true,
+            ..PrintOptions::default()
+        },
+    ] {
+        let printed = module.to_wat(&options).unwrap();
+        let reparsed = WasmModule::from_wat(&printed)
+            .unwrap_or_else(|err| panic!("{printed}\ndoes not parse: {err}"));
+        validate(&reparsed).unwrap_or_else(|err| panic!("{printed}\ndoes not validate: {err}"));
+        assert_eq!(reparsed.to_bytes(), module.to_bytes(), "{printed}");
+    }
+
+    // The element segment of the typed table needs the expression encoding
+    let bytes = module.to_bytes();
+    let decoded = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&decoded).unwrap();
+    assert_eq!(decoded.to_bytes(), bytes);
+}
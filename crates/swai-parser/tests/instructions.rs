@@ -0,0 +1,297 @@
+use swai_parser::{
+    error::WasmParserErrorKind,
+    instructions::{BlockType, Instructions, MemArg},
+    types::{Indecies, NumberTypes, ValueType},
+    WasmModule,
+};
+
+mod common;
+
+use common::{FUNC_TYPE, ONE_FUNCTION};
+
+/// A module with a memory, a table and a single `(func)` with the given body
+fn function(body: &[u8]) -> Vec<u8> {
+    common::function(
+        &[
+            FUNC_TYPE,
+            ONE_FUNCTION,
+            (4, &[0x01, 0x70, 0x00, 0x01]),
+            (5, &[0x01, 0x00, 0x01]),
+        ],
+        body,
+    )
+}
+
+/// Decodes the body of the function, and checks that encoding the module gives back its bytes
+fn decode(body: &[u8]) -> Vec<Instructions> {
+    let bytes = function(body);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    assert_eq!(module.to_bytes(), bytes);
+    module.sections.code[0].body.clone()
+}
+
+#[test]
+fn mvp_instructions_and_their_immediates_are_decoded() {
+    #[rustfmt::skip]
+    let body = [
+        // i32.const -1, i64.const i64::MIN
+        0x41, 0x7F,
+        0x42, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7F,
+        // f32.const 1.5, f64.const -2
+        0x43, 0x00, 0x00, 0xC0, 0x3F,
+        0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0,
+        // i32.load8_s offset=3, i64.store32 offset=200 align=4
+        0x2C, 0x00, 0x03,
+        0x3E, 0x02, 0xC8, 0x01,
+        // local.tee 1, global.get 2, select, select (result i64), drop
+        0x22, 0x01, 0x23, 0x02, 0x1B, 0x1C, 0x01, 0x7E, 0x1A,
+        // br_table 0 1 2, call 5, call_indirect (type 1) 0, return
+        0x0E, 0x02, 0x00, 0x01, 0x02, 0x10, 0x05, 0x11, 0x01, 0x00, 0x0F,
+        // memory.size, memory.grow, i32.add, f64.sqrt, i64.extend_i32_u, unreachable
+        0x3F, 0x00, 0x40, 0x00, 0x6A, 0x9F, 0xAD, 0x00,
+    ];
+
+    assert_eq!(
+        decode(&body),
+        [
+            Instructions::i32_const(-1),
+            Instructions::i64_const(i64::MIN),
+            Instructions::f32_const(1.5f32.to_bits()),
+            Instructions::f64_const((-2.0f64).to_bits()),
+            Instructions::i32_load_8s(MemArg {
+                align: 0,
+                offset: 3
+            }),
+            Instructions::i64_store_32(MemArg {
+                align: 2,
+                offset: 200
+            }),
+            Instructions::LocalTee(Indecies::LocalIdx(1)),
+            Instructions::GlobalGet(Indecies::GlobalIdx(2)),
+            Instructions::Select,
+            Instructions::SelectMultiple(vec![ValueType::NumType(NumberTypes::i64)]),
+            Instructions::Drop,
+            Instructions::BrTable(
+                vec![Indecies::LabelIdx(0), Indecies::LabelIdx(1)],
+                Indecies::LabelIdx(2)
+            ),
+            Instructions::Call(Indecies::FuncIdx(5)),
+            Instructions::CallIndirect(Indecies::TypeIdx(1), Indecies::TableIdx(0)),
+            Instructions::Return,
+            Instructions::MemorySize,
+            Instructions::MemoryGrow,
+            Instructions::i32_add,
+            Instructions::f64_sqrt,
+            Instructions::i64_extend_i32_u,
+            Instructions::Unreachable,
+        ]
+    );
+}
+
+#[test]
+fn sign_extension_and_saturating_truncation_are_decoded() {
+    #[rustfmt::skip]
+    let body = [
+        0xC0, 0xC1, 0xC2, 0xC3, 0xC4,
+        0xFC, 0x00, 0xFC, 0x01, 0xFC, 0x02, 0xFC, 0x03,
+        0xFC, 0x04, 0xFC, 0x05, 0xFC, 0x06, 0xFC, 0x07,
+    ];
+
+    assert_eq!(
+        decode(&body),
+        [
+            Instructions::i32_extend8_s,
+            Instructions::i32_extend16_s,
+            Instructions::i64_extend8_s,
+            Instructions::i64_extend16_s,
+            Instructions::i64_extend32_s,
+            Instructions::i32_trunc_sat_f32_s,
+            Instructions::i32_trunc_sat_f32_u,
+            Instructions::i32_trunc_sat_f64_s,
+            Instructions::i32_trunc_sat_f64_u,
+            Instructions::i64_trunc_sat_f32_s,
+            Instructions::i64_trunc_sat_f32_u,
+            Instructions::i64_trunc_sat_f64_s,
+            Instructions::i64_trunc_sat_f64_u,
+        ]
+    );
+}
+
+#[test]
+fn bulk_memory_and_table_instructions_carry_their_indices() {
+    #[rustfmt::skip]
+    let body = [
+        // memory.init 1, data.drop 2, memory.copy, memory.fill
+        0xFC, 0x08, 0x01, 0x00, 0xFC, 0x09, 0x02, 0xFC, 0x0A, 0x00, 0x00, 0xFC, 0x0B, 0x00,
+        // table.init 3 0, elem.drop 4, table.copy 0 1
+        0xFC, 0x0C, 0x03, 0x00, 0xFC, 0x0D, 0x04, 0xFC, 0x0E, 0x00, 0x01,
+        // table.grow 0, table.size 1, table.fill 2
+        0xFC, 0x0F, 0x00, 0xFC, 0x10, 0x01, 0xFC, 0x11, 0x02,
+    ];
+
+    assert_eq!(
+        decode(&body),
+        [
+            Instructions::MemoryInit(Indecies::DataIdx(1)),
+            Instructions::DataDrop(Indecies::DataIdx(2)),
+            Instructions::MemoryCopy,
+            Instructions::MemoryFill,
+            Instructions::TableInit(Indecies::ElemIdx(3), Indecies::TableIdx(0)),
+            Instructions::ElemDrop(Indecies::ElemIdx(4)),
+            Instructions::TableCopy(Indecies::TableIdx(0), Indecies::TableIdx(1)),
+            Instructions::TableGrow(Indecies::TableIdx(0)),
+            Instructions::TableSize(Indecies::TableIdx(1)),
+            Instructions::TableFill(Indecies::TableIdx(2)),
+        ]
+    );
+}
+
+#[test]
+fn unknown_opcodes_and_reserved_bytes_are_rejected() {
+    let error = WasmModule::from_bytes(&function(&[0xFC, 0x12])).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        WasmParserErrorKind::UnexpectedValue {
+            construct: "0xFC prefixed instruction opcode",
+            value: 18
+        }
+    ));
+
+    // memory.size and memory.copy take reserved zero bytes
+    for body in [&[0x3F, 0x01][..], &[0xFC, 0x0A, 0x00, 0x01]] {
+        let error = WasmModule::from_bytes(&function(body)).unwrap_err();
+        assert!(
+            matches!(
+                error.kind,
+                WasmParserErrorKind::UnexpectedByte {
+                    construct: "reserved byte",
+                    byte: 0x01
+                }
+            ),
+            "{error}"
+        );
+    }
+}
+
+#[test]
+fn blocks_loops_and_ifs_nest_into_a_tree() {
+    #[rustfmt::skip]
+    let body = [
+        // block (result i32)
+        0x02, 0x7F,
+        //   loop
+        0x03, 0x40,
+        //     i32.const 0, if, br 2, else, br 1, end
+        0x41, 0x00, 0x04, 0x40, 0x0C, 0x02, 0x05, 0x0C, 0x01, 0x0B,
+        //   end
+        0x0B,
+        //   i32.const 1
+        0x41, 0x01,
+        // end
+        0x0B,
+        // if (result i32), i32.const 2, end, drop
+        0x04, 0x7F, 0x41, 0x02, 0x0B, 0x1A,
+    ];
+
+    let i32 = ValueType::NumType(NumberTypes::i32);
+    assert_eq!(
+        decode(&body),
+        [
+            Instructions::Block {
+                ty: BlockType::Value(i32),
+                body: vec![
+                    Instructions::Loop {
+                        ty: BlockType::Empty,
+                        body: vec![
+                            Instructions::i32_const(0),
+                            Instructions::If {
+                                ty: BlockType::Empty,
+                                then: vec![Instructions::Br(Indecies::LabelIdx(2))],
+                                else_: vec![Instructions::Br(Indecies::LabelIdx(1))],
+                            },
+                        ],
+                    },
+                    Instructions::i32_const(1),
+                ],
+            },
+            // Without an else the branch is empty
+            Instructions::If {
+                ty: BlockType::Value(i32),
+                then: vec![Instructions::i32_const(2)],
+                else_: vec![],
+            },
+            Instructions::Drop,
+        ]
+    );
+}
+
+#[test]
+fn else_is_only_allowed_once_inside_if() {
+    for body in [
+        // else in a function body
+        &[0x05][..],
+        // else in a block
+        &[0x02, 0x40, 0x05, 0x0B],
+        // a second else
+        &[0x41, 0x00, 0x04, 0x40, 0x05, 0x05, 0x0B],
+    ] {
+        let error = WasmModule::from_bytes(&function(body)).unwrap_err();
+        assert!(
+            matches!(
+                error.kind,
+                WasmParserErrorKind::UnexpectedByte {
+                    construct: "instruction outside of an 'if' block",
+                    byte: 0x05
+                }
+            ),
+            "{error}"
+        );
+    }
+
+    // A block without its end runs into the end of the body
+    assert!(WasmModule::from_bytes(&function(&[0x02, 0x40])).is_err());
+}
+
+#[test]
+fn unused_bits_of_the_last_leb128_byte_are_rejected() {
+    #[rustfmt::skip]
+    let body = [
+        // i32.const i32::MAX, i32.const i32::MIN, i64.const -1 in 10 bytes
+        0x41, 0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x41, 0x80, 0x80, 0x80, 0x80, 0x78,
+        0x42, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F,
+        // local.get u32::MAX
+        0x20, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F,
+    ];
+    assert_eq!(
+        WasmModule::from_bytes(&function(&body))
+            .unwrap()
+            .sections
+            .code[0]
+            .body,
+        [
+            Instructions::i32_const(i32::MAX),
+            Instructions::i32_const(i32::MIN),
+            Instructions::i64_const(-1),
+            Instructions::LocalGet(Indecies::LocalIdx(u32::MAX)),
+        ]
+    );
+
+    for body in [
+        // Unsigned bits above 32
+        &[0x20, 0x80, 0x80, 0x80, 0x80, 0x70][..],
+        // Signed bits above 32 that don't copy the sign
+        &[0x41, 0x80, 0x80, 0x80, 0x80, 0x70],
+        &[0x41, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F],
+        // Signed bits above 64 that don't copy the sign
+        &[
+            0x42, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01,
+        ],
+        // A sixth byte for a 32 bit integer
+        &[0x41, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00],
+    ] {
+        assert!(
+            WasmModule::from_bytes(&function(body)).is_err(),
+            "{body:02X?}"
+        );
+    }
+}
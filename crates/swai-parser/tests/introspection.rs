@@ -0,0 +1,184 @@
+use swai_parser::{
+    instructions::Instructions,
+    introspection::{ExternKind, ExternType, Origin},
+    types::{
+        FunctionType, GlobalType, Indecies, Limits, Mutability, NumberTypes, ReferenceTypes,
+        TableType, ValueType,
+    },
+    WasmModule,
+};
+
+const SOURCE: &str = r#"
+    (module
+      (type $binary (func (param i32 i32) (result i32)))
+      (import "env" "log" (func $log (param i32)))
+      (import "env" "memory" (memory 1))
+      (import "env" "offset" (global $offset i32))
+      (import "env" "print" (func $print (param i64)))
+      (table $functions 2 funcref)
+      (global $counter (mut i64) (i64.const 0))
+      (func $add (export "add") (type $binary) (i32.add (local.get 0) (local.get 1)))
+      (func $noop (local i32 i32) (local f64))
+      (export "functions" (table $functions))
+      (export "memory" (memory 0))
+      (export "counter" (global $counter))
+      (export "log" (func $log)))
+"#;
+
+const I32: ValueType = ValueType::NumType(NumberTypes::i32);
+
+#[test]
+fn index_spaces_start_with_imports() {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+
+    let functions = module.functions();
+    let origins: Vec<_> = functions
+        .iter()
+        .map(|function| (function.index, function.origin))
+        .collect();
+    assert_eq!(
+        origins,
+        [
+            (
+                0,
+                Origin::Imported {
+                    module: "env",
+                    name: "log"
+                }
+            ),
+            (
+                1,
+                Origin::Imported {
+                    module: "env",
+                    name: "print"
+                }
+            ),
+            (2, Origin::Defined { index: 0 }),
+            (3, Origin::Defined { index: 1 }),
+        ]
+    );
+    assert_eq!(functions[2].type_index, 0);
+    assert_eq!(
+        functions[2].ty,
+        &FunctionType::new(vec![I32, I32], vec![I32])
+    );
+
+    let memories = module.memories();
+    assert_eq!(memories.len(), 1);
+    assert!(memories[0].origin.is_imported());
+    assert_eq!(memories[0].ty, &Limits::new(1, None));
+
+    let globals = module.globals();
+    assert_eq!(globals.len(), 2);
+    assert_eq!(globals[0].init, None);
+    assert_eq!(
+        globals[1].ty,
+        &GlobalType::new(ValueType::NumType(NumberTypes::i64), Mutability::Var)
+    );
+    assert_eq!(globals[1].init, Some(&vec![Instructions::i64_const(0)]));
+
+    assert_eq!(
+        module.tables()[0].ty,
+        &TableType::new(ReferenceTypes::funcref, Limits::new(2, None))
+    );
+    assert!(module.tags().is_empty());
+}
+
+#[test]
+fn exports_are_looked_up_by_name() {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+
+    let add = module.export("add").unwrap();
+    assert_eq!(add.index, 2);
+    assert_eq!(add.ty.kind(), ExternKind::Func);
+    let ExternType::Func(ty) = add.ty else {
+        panic!("expected a function, got {:?}", add.ty);
+    };
+    assert_eq!(ty.params(), [I32, I32]);
+    assert_eq!(ty.results(), [I32]);
+
+    // Exports of imports resolve to the import's type
+    let log = module.export("log").unwrap();
+    assert_eq!(
+        log.ty,
+        ExternType::Func(&FunctionType::new(vec![I32], vec![]))
+    );
+
+    let kinds: Vec<_> = module
+        .exports()
+        .iter()
+        .map(|export| (export.name, export.ty.kind().to_string()))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("add", "func".to_string()),
+            ("functions", "table".to_string()),
+            ("memory", "memory".to_string()),
+            ("counter", "global".to_string()),
+            ("log", "func".to_string()),
+        ]
+    );
+    assert!(module.export("missing").is_none());
+}
+
+#[test]
+fn imports_have_typed_descriptors() {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    let imports: Vec<_> = module
+        .imports()
+        .iter()
+        .map(|import| (import.name, import.index, import.ty.kind()))
+        .collect();
+    assert_eq!(
+        imports,
+        [
+            ("log", 0, ExternKind::Func),
+            ("memory", 0, ExternKind::Memory),
+            ("offset", 0, ExternKind::Global),
+            ("print", 1, ExternKind::Func),
+        ]
+    );
+    assert_eq!(
+        module.imports()[2].ty,
+        ExternType::Global(&GlobalType::new(I32, Mutability::Const))
+    );
+}
+
+#[test]
+fn bodies_of_defined_functions() {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+
+    assert!(module.function_body(0).is_none());
+    assert!(module.function_body(4).is_none());
+
+    let add = module.function_body(2).unwrap();
+    assert_eq!(
+        add.body,
+        &vec![
+            Instructions::LocalGet(Indecies::LocalIdx(0)),
+            Instructions::LocalGet(Indecies::LocalIdx(1)),
+            Instructions::i32_add
+        ]
+    );
+    let noop = module.function_body(3).unwrap();
+    assert_eq!(
+        noop.locals,
+        &vec![(2, I32), (1, ValueType::NumType(NumberTypes::f64))]
+    );
+
+    // The same resolution is available on raw indices
+    assert_eq!(
+        Indecies::FuncIdx(1).get_function(&module.sections),
+        Some(&FunctionType::new(
+            vec![ValueType::NumType(NumberTypes::i64)],
+            vec![]
+        ))
+    );
+    assert_eq!(
+        Indecies::TypeIdx(0).get_function(&module.sections),
+        module.function(2).map(|function| function.ty)
+    );
+    assert_eq!(Indecies::FuncIdx(9).get_function(&module.sections), None);
+    assert_eq!(Indecies::MemIdx(0).get_function(&module.sections), None);
+}
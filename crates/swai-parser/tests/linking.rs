@@ -0,0 +1,261 @@
+use swai_parser::{
+    error::WasmParserErrorKind,
+    linking::{
+        Comdat, ComdatSymbol, ComdatSymbolKind, DataSymbolDefinition, InitFunc, LinkingSection,
+        RelocEntry, RelocSection, RelocType, SegmentInfo, SymbolInfo, SymbolKind,
+        WASM_SYM_BINDING_LOCAL, WASM_SYM_EXPLICIT_NAME, WASM_SYM_UNDEFINED,
+        WASM_SYM_VISIBILITY_HIDDEN,
+    },
+    types::Name,
+    WasmModule,
+};
+
+mod common;
+
+// Subsections have an id and a size like sections
+use common::{leb128, section as subsection, FUNC_TYPE, ONE_FUNCTION};
+
+/// A name as a length followed by its bytes
+fn name(name: &str) -> Vec<u8> {
+    [&[name.len() as u8], name.as_bytes()].concat()
+}
+
+/// A module with a single `(func)` followed by custom sections with the given names and contents
+fn module(custom: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = common::function(&[FUNC_TYPE, ONE_FUNCTION], &[]);
+    for (section_name, contents) in custom {
+        bytes.extend(subsection(
+            0,
+            &[name(section_name), contents.clone()].concat(),
+        ));
+    }
+    bytes
+}
+
+/// The contents of a version 2 linking section made of the given subsections
+fn linking(subsections: &[Vec<u8>]) -> Vec<u8> {
+    [vec![0x02], subsections.concat()].concat()
+}
+
+#[test]
+fn linking_subsections_are_decoded() {
+    #[rustfmt::skip]
+    let symbols = [
+        vec![0x06],
+        // A defined function with its name
+        [&[0x00, 0x00, 0x00][..], &name("main")].concat(),
+        // An undefined function, which takes the name of its import
+        vec![0x00, 0x10, 0x01],
+        // A hidden data symbol defined at offset 4 of segment 0, 16 bytes long
+        [&[0x01, 0x04][..], &name("buf"), &[0x00, 0x04, 0x10]].concat(),
+        // An undefined data symbol
+        [&[0x01, 0x10][..], &name("extern")].concat(),
+        // A local symbol for section 10
+        vec![0x03, 0x02, 0x0A],
+        // An undefined global with an explicit name
+        [&[0x02, 0x50, 0x00][..], &name("sp")].concat(),
+    ]
+    .concat();
+    let segments = [&[0x01][..], &name(".data"), &[0x02, 0x00]].concat();
+    let init_funcs = [&[0x01][..], &leb128(65535), &[0x00]].concat();
+    let comdats = [
+        &[0x01][..],
+        &name("group"),
+        &[0x00, 0x02, 0x01, 0x00, 0x00, 0x00],
+    ]
+    .concat();
+    let bytes = module(&[(
+        "linking",
+        linking(&[
+            subsection(8, &symbols),
+            subsection(5, &segments),
+            subsection(6, &init_funcs),
+            subsection(7, &comdats),
+        ]),
+    )]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    let linking = module.sections.linking.as_ref().unwrap();
+    assert_eq!(
+        linking,
+        &LinkingSection {
+            version: 2,
+            symbols: vec![
+                SymbolInfo {
+                    flags: 0,
+                    kind: SymbolKind::Function {
+                        index: 0,
+                        name: Some(Name::from("main")),
+                    },
+                },
+                SymbolInfo {
+                    flags: WASM_SYM_UNDEFINED,
+                    kind: SymbolKind::Function {
+                        index: 1,
+                        name: None,
+                    },
+                },
+                SymbolInfo {
+                    flags: WASM_SYM_VISIBILITY_HIDDEN,
+                    kind: SymbolKind::Data {
+                        name: Name::from("buf"),
+                        definition: Some(DataSymbolDefinition {
+                            segment_index: 0,
+                            offset: 4,
+                            size: 16,
+                        }),
+                    },
+                },
+                SymbolInfo {
+                    flags: WASM_SYM_UNDEFINED,
+                    kind: SymbolKind::Data {
+                        name: Name::from("extern"),
+                        definition: None,
+                    },
+                },
+                SymbolInfo {
+                    flags: WASM_SYM_BINDING_LOCAL,
+                    kind: SymbolKind::Section { section: 10 },
+                },
+                SymbolInfo {
+                    flags: WASM_SYM_UNDEFINED | WASM_SYM_EXPLICIT_NAME,
+                    kind: SymbolKind::Global {
+                        index: 0,
+                        name: Some(Name::from("sp")),
+                    },
+                },
+            ],
+            segments: vec![SegmentInfo {
+                name: Name::from(".data"),
+                alignment: 2,
+                flags: 0,
+            }],
+            init_funcs: vec![InitFunc {
+                priority: 65535,
+                symbol_index: 0,
+            }],
+            comdats: vec![Comdat {
+                name: Name::from("group"),
+                flags: 0,
+                symbols: vec![
+                    ComdatSymbol {
+                        kind: ComdatSymbolKind::Function,
+                        index: 0,
+                    },
+                    ComdatSymbol {
+                        kind: ComdatSymbolKind::Data,
+                        index: 0,
+                    },
+                ],
+            }],
+        }
+    );
+
+    let undefined: Vec<_> = linking
+        .symbols
+        .iter()
+        .map(|symbol| symbol.is_undefined())
+        .collect();
+    assert_eq!(undefined, [false, true, false, true, false, true]);
+    let names: Vec<_> = linking
+        .symbols
+        .iter()
+        .map(|symbol| symbol.name().map(Name::as_str))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            Some("main"),
+            None,
+            Some("buf"),
+            Some("extern"),
+            None,
+            Some("sp")
+        ]
+    );
+
+    // The raw bytes are written back unchanged
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn relocation_entries_are_decoded() {
+    #[rustfmt::skip]
+    let relocations = vec![
+        // Section 2, two entries
+        0x02, 0x02,
+        // A function index at offset 1 for symbol 0
+        0x00, 0x01, 0x00,
+        // A memory address at offset 6 for symbol 2, with an addend of -8
+        0x04, 0x06, 0x02, 0x78,
+    ];
+    let bytes = module(&[
+        ("linking", linking(&[])),
+        ("reloc.CODE", relocations),
+        ("reloc.DATA", vec![0x03, 0x00]),
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(
+        module.sections.relocations,
+        [
+            RelocSection {
+                name: Name::from("reloc.CODE"),
+                section_index: 2,
+                entries: vec![
+                    RelocEntry {
+                        ty: RelocType::FunctionIndexLeb,
+                        offset: 1,
+                        index: 0,
+                        addend: None,
+                    },
+                    RelocEntry {
+                        ty: RelocType::MemoryAddrSleb,
+                        offset: 6,
+                        index: 2,
+                        addend: Some(-8),
+                    },
+                ],
+            },
+            RelocSection {
+                name: Name::from("reloc.DATA"),
+                section_index: 3,
+                entries: vec![],
+            },
+        ]
+    );
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn unknown_symbol_and_comdat_kinds_are_decode_errors() {
+    let symbols = [&[0x01, 0x06, 0x00][..], &name("x")].concat();
+    let comdats = [&[0x01][..], &name("group"), &[0x00, 0x01, 0x06, 0x00]].concat();
+    let bytes = module(&[
+        ("linking", linking(&[subsection(8, &symbols)])),
+        ("linking", linking(&[subsection(7, &comdats)])),
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(module.sections.linking, None);
+    let kinds: Vec<_> = module
+        .sections
+        .custom
+        .iter()
+        .map(|section| &section.decode_error.as_ref().unwrap().kind)
+        .collect();
+    assert!(matches!(
+        kinds[0],
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "symbol kind",
+            byte: 0x06
+        }
+    ));
+    assert!(matches!(
+        kinds[1],
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "comdat symbol kind",
+            byte: 0x06
+        }
+    ));
+}
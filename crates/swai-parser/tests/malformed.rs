@@ -0,0 +1,142 @@
+use swai_parser::{error::WasmParserErrorKind, instructions::MAX_NESTING_DEPTH, WasmModule};
+
+mod common;
+
+use common::{code, module, HEADER};
+
+const FIXTURES: [&[u8]; 6] = [
+    include_bytes!("../../../tests/add.wasm"),
+    include_bytes!("../../../tests/asc_test.wasm"),
+    include_bytes!("../../../tests/helloworld.wasm"),
+    include_bytes!("../../../tests/memory.wasm"),
+    include_bytes!("../../../tests/module.wasm"),
+    include_bytes!("../../../tests/test.wasm"),
+];
+
+/// A small xorshift generator, so the mutation tests are reproducible without extra dependencies
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[test]
+fn unknown_value_type_reports_byte_and_offset() {
+    // (type (func (param <0x42>)))
+    let bytes = module(&[(1, &[0x01, 0x60, 0x01, 0x42, 0x00])]);
+
+    let err = WasmModule::from_bytes(&bytes).unwrap_err();
+    match err.kind {
+        WasmParserErrorKind::UnexpectedByte { construct, byte } => {
+            assert_eq!(construct, "value type");
+            assert_eq!(byte, 0x42);
+            assert_eq!(err.offset, Some(13));
+        }
+        kind => panic!("expected an UnexpectedByte error, got {kind:?}"),
+    }
+}
+
+#[test]
+fn unknown_constructs_are_errors() {
+    let cases: [(u8, &[u8], &str); 6] = [
+        // Function type without the 0x60 prefix
+        (1, &[0x01, 0x61, 0x00, 0x00], "function type"),
+        // Import with description kind 0x07
+        (2, &[0x01, 0x00, 0x00, 0x07, 0x00], "import description"),
+        // Table with an invalid reference type
+        (4, &[0x01, 0x71, 0x00, 0x00], "reference type"),
+        // Memory with an invalid limits flag
+        (5, &[0x01, 0x09, 0x00], "limits"),
+        // Global with an invalid mutability
+        (
+            6,
+            &[0x01, 0x7F, 0x02, 0x41, 0x00, 0x0B],
+            "global mutability",
+        ),
+        // Function body with an invalid opcode
+        (10, &[0x01, 0x03, 0x00, 0xFF, 0x0B], "instruction opcode"),
+    ];
+
+    for (id, contents, expected) in cases {
+        match WasmModule::from_bytes(&module(&[(id, contents)])).map_err(|err| err.kind) {
+            Err(WasmParserErrorKind::UnexpectedByte { construct, .. }) => {
+                assert_eq!(construct, expected)
+            }
+            result => panic!("expected an UnexpectedByte error for {expected}, got {result:?}"),
+        }
+    }
+}
+
+#[test]
+fn invalid_header_is_rejected() {
+    assert!(matches!(
+        WasmModule::from_bytes(b"\0asn\x01\0\0\0").map_err(|err| err.kind),
+        Err(WasmParserErrorKind::InvalidWasmBytes { .. })
+    ));
+    assert!(matches!(
+        WasmModule::from_bytes(b"\0asm\x02\0\0\0").map_err(|err| err.kind),
+        Err(WasmParserErrorKind::InvalidWasmBytes { .. })
+    ));
+    assert!(WasmModule::from_bytes(b"\0as").is_err());
+}
+
+#[test]
+fn deeply_nested_blocks_hit_the_nesting_limit() {
+    let depth = MAX_NESTING_DEPTH * 4;
+    let mut body = vec![];
+    for _ in 0..depth {
+        body.extend_from_slice(&[0x02, 0x40]);
+    }
+    body.extend(std::iter::repeat_n(0x0B, depth));
+    let bytes = module(&[(10, &code(&[0x00], &body))]);
+
+    assert!(matches!(
+        WasmModule::from_bytes(&bytes).map_err(|err| err.kind),
+        Err(WasmParserErrorKind::ImplementationLimit { .. })
+    ));
+}
+
+#[test]
+fn truncated_fixtures_never_panic() {
+    for fixture in FIXTURES {
+        for length in 0..fixture.len() {
+            let _ = WasmModule::from_bytes(&fixture[..length]);
+        }
+    }
+}
+
+#[test]
+fn mutated_fixtures_never_panic() {
+    let mut rng = XorShift(0x5DEECE66D);
+
+    for fixture in FIXTURES {
+        for _ in 0..2000 {
+            let mut bytes = fixture.to_vec();
+            for _ in 0..=rng.next() % 4 {
+                // Leave the header alone, so the mutations reach the section parsers
+                let index = 8 + (rng.next() as usize % (bytes.len() - 8).max(1));
+                if let Some(byte) = bytes.get_mut(index) {
+                    *byte = rng.next() as u8;
+                }
+            }
+            let _ = WasmModule::from_bytes(&bytes);
+        }
+    }
+}
+
+#[test]
+fn random_sections_never_panic() {
+    let mut rng = XorShift(0x2545F4914F6CDD1D);
+
+    for _ in 0..5000 {
+        let mut bytes = HEADER.to_vec();
+        let length = rng.next() % 64;
+        bytes.extend((0..length).map(|_| rng.next() as u8));
+        let _ = WasmModule::from_bytes(&bytes);
+    }
+}
@@ -0,0 +1,148 @@
+use swai_parser::{
+    error::{ValidationErrorKind, WasmParserErrorKind},
+    instructions::{Instructions, MemArg},
+    types::NumberTypes,
+    validate,
+    wat::PrintOptions,
+    WasmModule,
+};
+
+mod common;
+
+use common::memory_function;
+
+#[test]
+fn memory64_limits_are_decoded() {
+    // A maximum of 2^40 pages doesn't fit into 32 bits
+    let bytes = memory_function(&[0x05, 0x01, 0x80, 0x80, 0x80, 0x80, 0x80, 0x20], &[]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    let memory = &module.sections.memory[0];
+    assert_eq!(memory.index_type(), NumberTypes::i64);
+    assert_eq!((memory.minimum(), memory.maximum()), (1, Some(1 << 40)));
+    assert_eq!(module.to_bytes(), bytes);
+
+    let bytes = memory_function(&[0x07, 0x01, 0x02], &[]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    assert!(module.sections.memory[0].is_shared());
+    assert_eq!(module.to_bytes(), bytes);
+
+    // Shared limits still need a maximum
+    let error = WasmModule::from_bytes(&memory_function(&[0x06, 0x01], &[])).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "limits",
+            byte: 0x06
+        }
+    ));
+}
+
+#[test]
+fn offsets_can_exceed_32_bits() {
+    // i64.const 0 i64.load offset=2^32 drop
+    let body = [0x42, 0x00, 0x29, 0x03, 0x80, 0x80, 0x80, 0x80, 0x10, 0x1A];
+    let bytes = memory_function(&[0x04, 0x01], &body);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+    assert_eq!(
+        module.sections.code[0].body[1],
+        Instructions::i64_load(MemArg {
+            align: 3,
+            offset: 1 << 32
+        })
+    );
+    assert_eq!(module.to_bytes(), bytes);
+
+    // The same load from a 32 bit memory
+    let mut body = body;
+    body[0..2].copy_from_slice(&[0x41, 0x00]);
+    let bytes = memory_function(&[0x00, 0x01], &body);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::LimitExceeded {
+            construct: "Memory offset",
+            value: 0x1_0000_0000,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn addresses_have_the_index_type() {
+    let module = WasmModule::from_wat(
+        "(module (memory i64 1) (func (result i32) (i32.load (i32.const 0))))",
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::TypeMismatch { .. }
+    ));
+
+    let module = WasmModule::from_wat(
+        r#"(module
+          (memory i64 1)
+          (table i64 1 externref)
+          (func (result i64 i64)
+            (memory.fill (i64.const 0) (i32.const 0) (i64.const 1))
+            (memory.size)
+            (table.grow (ref.null extern) (i64.const 1))))"#,
+    )
+    .unwrap();
+    validate(&module).unwrap();
+
+    // Memories with 64 bit limits can have up to 2^48 pages
+    let module = WasmModule::from_wat("(module (memory i64 0x1_0000_0000_0000))").unwrap();
+    validate(&module).unwrap();
+    let module = WasmModule::from_wat("(module (memory i64 0x1_0000_0000_0001))").unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::LimitExceeded {
+            construct: "Memory size",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn text_format_round_trips() {
+    let source = r#"
+        (module
+          (memory (export "memory") i64 1 0x1_0000_0000)
+          (table i64 funcref (elem $f))
+          (data (i64.const 8) "data")
+          (func $f (param i64) (result i64)
+            (i64.store offset=0x1_0000_0000 (local.get 0) (i64.const 1))
+            (table.copy (i64.const 0) (i64.const 0) (i64.const 1))
+            (memory.grow (local.get 0))))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap();
+    assert_eq!(
+        module.sections.tables[0].limits().index_type(),
+        NumberTypes::i64
+    );
+
+    for options in [
+        PrintOptions::default(),
+        PrintOptions {
+            folded: true,
+            ..PrintOptions::default()
+        },
+    ] {
+        let printed = module.to_wat(&options).unwrap();
+        let reparsed = WasmModule::from_wat(&printed)
+            .unwrap_or_else(|err| panic!("{printed}\ndoes not parse: {err}"));
+        validate(&reparsed).unwrap_or_else(|err| panic!("{printed}\ndoes not validate: {err}"));
+        assert_eq!(reparsed.to_bytes(), module.to_bytes(), "{printed}");
+    }
+
+    let printed = module.to_wat(&PrintOptions::default()).unwrap();
+    assert!(
+        printed.contains("(memory (;0;) i64 1 4294967296)"),
+        "{printed}"
+    );
+    assert!(printed.contains("offset=4294967296"), "{printed}");
+}
@@ -0,0 +1,120 @@
+use swai_parser::{
+    error::{ValidationErrorKind, WasmParserErrorKind},
+    instructions::{BlockType, Instructions},
+    validate,
+    wat::PrintOptions,
+    WasmModule,
+};
+
+mod common;
+
+use common::{leb128, ONE_FUNCTION};
+
+/// A module with the given function types and a single function of type 0 with the given body
+fn module(types: &[&[u8]], body: &[u8]) -> Vec<u8> {
+    let type_section = [leb128(types.len() as u64), types.concat()].concat();
+    common::function(&[(1, &type_section), ONE_FUNCTION], body)
+}
+
+#[test]
+fn type_index_block_types_are_decoded() {
+    // Type 64 is (func (param i32) (result i32 i32)), its index takes two bytes as an s33
+    let mut types: Vec<&[u8]> = vec![&[0x60, 0x00, 0x00]; 64];
+    types.push(&[0x60, 0x01, 0x7F, 0x02, 0x7F, 0x7F]);
+    let body = [0x41, 0x01, 0x02, 0xC0, 0x00, 0x41, 0x02, 0x0B, 0x1A, 0x1A];
+
+    let bytes = module(&types, &body);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    assert_eq!(
+        module.sections.code[0].body[1],
+        Instructions::Block {
+            ty: BlockType::TypeIndex(64),
+            body: vec![Instructions::i32_const(2)],
+        }
+    );
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn invalid_block_types_are_rejected() {
+    // 0x41 is a negative s33 that isn't a value type
+    let bytes = module(&[&[0x60, 0x00, 0x00]], &[0x02, 0x41, 0x0B]);
+    let error = WasmModule::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "block type",
+            byte: 0x41
+        }
+    ));
+
+    // The unused bits of the fifth byte have to extend the sign of the s33
+    let bytes = module(
+        &[&[0x60, 0x00, 0x00]],
+        &[0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0x1F, 0x0B],
+    );
+    assert!(WasmModule::from_bytes(&bytes).is_err());
+
+    // u32::MAX is a valid type index, even if the module doesn't have that many types
+    let bytes = module(
+        &[&[0x60, 0x00, 0x00]],
+        &[0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x0B],
+    );
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::UnknownIndex {
+            space: "type",
+            index: u32::MAX
+        }
+    ));
+}
+
+#[test]
+fn block_params_are_type_checked() {
+    let module = WasmModule::from_wat(
+        "(module (func (result i32) i64.const 1 block (param i32) (result i32) end))",
+    )
+    .unwrap();
+    assert!(validate(&module).is_err());
+}
+
+#[test]
+fn text_format_round_trips() {
+    let source = r#"
+        (module
+          (type $pair (func (result i32 i32)))
+          (func (param i32) (result i32 i32)
+            (block $outer (type $pair)
+              (i32.const 1)
+              (i32.const 2)
+              (local.get 0)
+              (br_if $outer))
+            (loop (param i32 i32) (result i32 i32))
+            (if (param i32) (result i32 i32) (local.get 0)
+              (then (i32.const 2))
+              (else (i32.const 3)))
+            drop))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap();
+
+    // Inline types with params or several results are added once, unless an identical one exists
+    assert_eq!(module.sections.types.len(), 3);
+
+    for options in [
+        PrintOptions::default(),
+        PrintOptions {
+            folded: true,
+            ..PrintOptions::default()
+        },
+    ] {
+        let printed = module.to_wat(&options).unwrap();
+        let reparsed = WasmModule::from_wat(&printed)
+            .unwrap_or_else(|err| panic!("{printed}\ndoes not parse: {err}"));
+        validate(&reparsed).unwrap_or_else(|err| panic!("{printed}\ndoes not validate: {err}"));
+        assert_eq!(reparsed.to_bytes(), module.to_bytes(), "{printed}");
+    }
+}
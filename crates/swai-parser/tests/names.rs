@@ -0,0 +1,98 @@
+use swai_parser::WasmModule;
+
+mod common;
+
+// Subsections have an id and a size like sections
+use common::{section as subsection, FUNC_TYPE, ONE_FUNCTION};
+
+/// A name as a length followed by its bytes
+fn name(name: &str) -> Vec<u8> {
+    [&[name.len() as u8], name.as_bytes()].concat()
+}
+
+/// A name map with a single entry
+fn name_map(index: u8, value: &str) -> Vec<u8> {
+    [&[0x01, index], name(value).as_slice()].concat()
+}
+
+/// An indirect name map with a single function and a single entry
+fn indirect_name_map(function: u8, index: u8, value: &str) -> Vec<u8> {
+    [&[0x01, function], name_map(index, value).as_slice()].concat()
+}
+
+/// A module with a single `(func)` and a name section made of the given subsections
+fn module(subsections: &[Vec<u8>]) -> Vec<u8> {
+    let names = [name("name"), subsections.concat()].concat();
+    [
+        common::function(&[FUNC_TYPE, ONE_FUNCTION], &[]),
+        subsection(0, &names),
+    ]
+    .concat()
+}
+
+#[test]
+fn every_subsection_is_decoded() {
+    let bytes = module(&[
+        subsection(0, &name("module")),
+        subsection(1, &name_map(0, "main")),
+        subsection(2, &indirect_name_map(0, 1, "counter")),
+        subsection(3, &indirect_name_map(0, 2, "loop")),
+        subsection(4, &name_map(0, "void")),
+        subsection(5, &name_map(1, "table")),
+        subsection(6, &name_map(0, "memory")),
+        subsection(7, &name_map(3, "global")),
+        subsection(8, &name_map(0, "elements")),
+        subsection(9, &name_map(1, "data")),
+        subsection(11, &name_map(0, "tag")),
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    let names = module.sections.names.as_ref().unwrap();
+
+    assert_eq!(names.module.as_ref().unwrap().as_str(), "module");
+    let lookups = [
+        (names.function_name(0), "main"),
+        (names.local_name(0, 1), "counter"),
+        (names.label_name(0, 2), "loop"),
+        (names.type_name(0), "void"),
+        (names.table_name(1), "table"),
+        (names.memory_name(0), "memory"),
+        (names.global_name(3), "global"),
+        (names.element_name(0), "elements"),
+        (names.data_name(1), "data"),
+        (names.tag_name(0), "tag"),
+    ];
+    for (name, expected) in lookups {
+        assert_eq!(name.map(|name| name.as_str()), Some(expected));
+    }
+
+    // Indices without a name
+    assert_eq!(names.function_name(1), None);
+    assert_eq!(names.local_name(0, 0), None);
+    assert_eq!(names.label_name(1, 2), None);
+}
+
+#[test]
+fn unknown_subsections_and_trailing_bytes_are_skipped() {
+    let bytes = module(&[
+        // A subsection from a later proposal
+        subsection(12, &[0xFF, 0xFF]),
+        // Function names followed by bytes the subsection declares but the map doesn't use
+        subsection(1, &[name_map(0, "main"), vec![0xAA, 0xBB]].concat()),
+        subsection(7, &name_map(0, "global")),
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    let names = module.sections.names.as_ref().unwrap();
+
+    assert_eq!(names.function_name(0).unwrap().as_str(), "main");
+    assert_eq!(names.global_name(0).unwrap().as_str(), "global");
+    assert!(module.sections.custom[0].decode_error.is_none());
+}
+
+#[test]
+fn names_are_owned_by_the_section() {
+    let bytes = module(&[subsection(1, &name_map(0, "main"))]);
+    let names = WasmModule::from_bytes(&bytes).unwrap().sections.names;
+    drop(bytes);
+
+    assert_eq!(names.unwrap().function_name(0).unwrap().as_str(), "main");
+}
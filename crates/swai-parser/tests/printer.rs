@@ -0,0 +1,314 @@
+use swai_parser::{
+    error::WasmParserErrorKind,
+    instructions::{BlockType, Instructions},
+    wat::{print, print_bytes, PrintOptions},
+    WasmModule,
+};
+
+const FIXTURES: [(&str, &[u8]); 6] = [
+    ("add", include_bytes!("../../../tests/add.wasm")),
+    ("asc_test", include_bytes!("../../../tests/asc_test.wasm")),
+    (
+        "helloworld",
+        include_bytes!("../../../tests/helloworld.wasm"),
+    ),
+    ("memory", include_bytes!("../../../tests/memory.wasm")),
+    ("module", include_bytes!("../../../tests/module.wasm")),
+    ("test", include_bytes!("../../../tests/test.wasm")),
+];
+
+const OPTIONS: [PrintOptions; 4] = [
+    PrintOptions {
+        folded: false,
+        offsets: false,
+    },
+    PrintOptions {
+        folded: true,
+        offsets: false,
+    },
+    PrintOptions {
+        folded: false,
+        offsets: true,
+    },
+    PrintOptions {
+        folded: true,
+        offsets: true,
+    },
+];
+
+const FLAT: PrintOptions = OPTIONS[0];
+const FOLDED: PrintOptions = OPTIONS[1];
+
+/// Prints the module with every combination of options and checks that each output parses back to it
+fn assert_round_trips(module: &WasmModule) {
+    for options in &OPTIONS {
+        let text = print(module, options).unwrap();
+        let parsed = WasmModule::from_wat(&text)
+            .unwrap_or_else(|err| panic!("{err}\nin the printed module:\n{text}"));
+        assert_eq!(&parsed, module, "{text}");
+    }
+}
+
+#[test]
+fn fixtures_parse_back_to_the_same_module() {
+    for (name, bytes) in FIXTURES {
+        let module = WasmModule::from_bytes(bytes).unwrap();
+        assert_round_trips(&module);
+
+        for options in &OPTIONS {
+            let text = print_bytes(bytes, options).unwrap();
+            assert_eq!(WasmModule::from_wat(&text).unwrap(), module, "{name}");
+        }
+    }
+}
+
+#[test]
+fn text_modules_parse_back_to_the_same_module() {
+    let sources = [
+        r#"(module
+            (type $binary (func (param i32 i32) (result i32)))
+            (import "env" "table" (table 2 10 funcref))
+            (import "env" "g" (global (mut f64)))
+            (memory 1 2)
+            (global $counter (mut i32) (i32.const 0))
+            (global i64 (i64.const -1))
+            (table $refs 4 externref)
+            (func $add (type $binary) (i32.add (local.get 0) (local.get 1)))
+            (func $floats (result f32)
+                f32.const nan:0x200000
+                f32.const -nan
+                f32.add
+                f32.const 0x1p-149
+                f32.max
+                f64.const -0.0
+                f64.const 1e300
+                f64.mul
+                f32.demote_f64
+                f32.min)
+            (func $control (param i32) (result i32) (local i64 i64) (local f32)
+                (block $outer (result i32)
+                    (loop $again
+                        (br_if $again (i32.eqz (local.get 0)))
+                        (br_table 0 1 $outer (i32.const 7) (local.get 0)))
+                    (if (result i32) (local.get 0)
+                        (then (i32.const 1))
+                        (else (unreachable)))))
+            (func (param i32) (result i32)
+                (call_indirect (type $binary) (local.get 0) (i32.const 2) (i32.const 0))
+                (select (result i32) (i32.const 1) (i32.const 2))
+                i32.add)
+            (func
+                (i64.store32 offset=4 align=2 (i32.const 0) (i64.load8_s offset=0xFFFF (i32.const 8)))
+                (memory.init $passive (i32.const 0) (i32.const 0) (i32.const 3))
+                (data.drop $passive)
+                (table.init 0 $funcs (i32.const 0) (i32.const 0) (i32.const 1))
+                (elem.drop $funcs)
+                (table.copy $refs $refs (i32.const 0) (i32.const 1) (i32.const 1))
+                (drop (table.grow $refs (ref.null extern) (i32.const 1)))
+                (memory.fill (i32.const 0) (i32.const 0) (memory.size))
+                (global.set $counter (i32.const 1)))
+            (export "add" (func $add))
+            (export "counter" (global $counter))
+            (start 4)
+            (elem $funcs func $add $control)
+            (elem declare func $floats)
+            (elem (table 0) (offset (i32.const 1)) funcref (item ref.func $add) (item (ref.null func)))
+            (elem (table $refs) (i32.const 0) externref (ref.null extern))
+            (data $passive "abc")
+            (data (i32.const 16) "\00\01\ff\"\\")
+            (@custom "first" (before first) "1")
+            (@custom "middle" (after global) "")
+        )"#,
+        // Function names "a", "a" and "a b" in a name section
+        r#"(module (func) (func) (func) (@custom "name" "\01\0c\03\00\01a\01\01a\02\03a b"))"#,
+        "(module)",
+    ];
+
+    for source in sources {
+        assert_round_trips(&WasmModule::from_wat(source).unwrap());
+    }
+}
+
+#[test]
+fn names_come_from_the_name_section() {
+    let text = print_bytes(include_bytes!("../../../tests/helloworld.wasm"), &FLAT).unwrap();
+
+    assert!(text.contains("(import \"std::io\" \"print\" (func $print (;0;) (type 0)))"));
+    assert!(text.contains("(func $print_the_text (;1;) (type 1) (param $a i32) (param $b i32)"));
+    assert!(text.contains("    local.get $a\n"));
+    assert!(text.contains("    call $print_the_text)\n"));
+    assert!(text.contains("(start $main)"));
+
+    // Names that are taken or aren't valid identifiers are left out
+    let module = WasmModule::from_wat(
+        r#"(module (func) (func) (func) (@custom "name" "\01\0c\03\00\01a\01\01a\02\03a b"))"#,
+    )
+    .unwrap();
+    let text = print(&module, &FLAT).unwrap();
+    assert!(text.contains("(func $a (;0;) (type 0))"));
+    assert!(text.contains("(func (;1;) (type 0))"));
+    assert!(text.contains("(func (;2;) (type 0))"));
+}
+
+#[test]
+fn names_of_text_modules_are_kept() {
+    let module = WasmModule::from_wat(
+        r#"(module $m
+            (import "env" "log" (func $log (param i32)))
+            (global $counter (mut i32) (i32.const 0))
+            (func $count (param $step i32) (local $total i32)
+                (if $positive (block $condition (result i32) (local.get $step))
+                    (then (global.set $counter (local.get $step))))
+                (block $done (call $log (local.get $total)))))"#,
+    )
+    .unwrap();
+
+    let names = module.sections.names.as_ref().unwrap();
+    assert_eq!(names.module.as_ref().unwrap().as_str(), "m");
+    assert_eq!(names.function_name(0).unwrap().as_str(), "log");
+    assert_eq!(names.function_name(1).unwrap().as_str(), "count");
+    assert_eq!(names.global_name(0).unwrap().as_str(), "counter");
+    assert_eq!(names.local_name(1, 0).unwrap().as_str(), "step");
+    assert_eq!(names.local_name(1, 1).unwrap().as_str(), "total");
+    // The block in the condition of a folded `if` comes before the `if` itself
+    assert_eq!(names.label_name(1, 0).unwrap().as_str(), "condition");
+    assert_eq!(names.label_name(1, 1).unwrap().as_str(), "positive");
+    assert_eq!(names.label_name(1, 2).unwrap().as_str(), "done");
+
+    let text = print(&module, &FOLDED).unwrap();
+    assert!(text.starts_with("(module $m\n"));
+    assert!(text.contains("(func $count (;1;) (type 0) (param $step i32)"));
+    assert!(text.contains("(local $total i32)"));
+    assert!(text.contains("(if $positive"));
+    assert!(text.contains("(block $condition (result i32)"));
+    assert!(text.contains("(global.set $counter"));
+    assert!(text.contains("(call $log"));
+    assert_round_trips(&module);
+}
+
+#[test]
+fn flat_and_folded_forms() {
+    let module = WasmModule::from_wat(
+        r#"(module
+            (func (param i32) (result i32)
+                local.get 0
+                if (result i32)
+                    local.get 0
+                    i32.const 1
+                    i32.sub
+                else
+                    i32.const 0
+                end
+                nop)
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        print(&module, &FLAT).unwrap(),
+        "\
+(module
+  (type (;0;) (func (param i32) (result i32)))
+  (func (;0;) (type 0) (param i32) (result i32)
+    local.get 0
+    if (result i32)
+      local.get 0
+      i32.const 1
+      i32.sub
+    else
+      i32.const 0
+    end
+    nop))
+"
+    );
+    assert_eq!(
+        print(&module, &FOLDED).unwrap(),
+        "\
+(module
+  (type (;0;) (func (param i32) (result i32)))
+  (func (;0;) (type 0) (param i32) (result i32)
+    (if (result i32)
+      (local.get 0)
+      (then
+        (i32.sub
+          (local.get 0)
+          (i32.const 1)))
+      (else
+        (i32.const 0)))
+    (nop)))
+"
+    );
+}
+
+#[test]
+fn offsets_point_into_the_binary() {
+    #[rustfmt::skip]
+    let bytes = [
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+        // type section at 0x08: (func)
+        0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+        // function section at 0x0E
+        0x03, 0x02, 0x01, 0x00,
+        // code section at 0x12, the body starts at 0x15 with a padded i32.const 1 at 0x17
+        0x0A, 0x0A, 0x01, 0x08, 0x00, 0x41, 0x81, 0x80, 0x80, 0x00, 0x1A, 0x0B,
+    ];
+
+    let text = print_bytes(&bytes, &OPTIONS[2]).unwrap();
+    assert_eq!(
+        text,
+        concat!(
+            "         (module\n",
+            "(;@b ;)    (type (;0;) (func))\n",
+            "(;@15;)    (func (;0;) (type 0)\n",
+            "(;@17;)      i32.const 1\n",
+            "(;@1c;)      drop))\n",
+        )
+    );
+
+    // Printing the module itself shows the offsets of its encoding, which doesn't pad the integer
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    assert!(print(&module, &OPTIONS[2])
+        .unwrap()
+        .contains("(;@19;)      drop))"));
+}
+
+#[test]
+fn deep_nesting_does_not_overflow_the_stack() {
+    let depth = swai_parser::instructions::MAX_NESTING_DEPTH;
+    let source = format!(
+        "(module (func {} i32.const 0 {} drop))",
+        "block ".repeat(depth),
+        "end ".repeat(depth),
+    );
+    let module = WasmModule::from_wat(&source).unwrap();
+    for options in [FLAT, FOLDED] {
+        let text = print(&module, &options).unwrap();
+        // The derived comparison of the modules would recurse as deep as the blocks are nested
+        let parsed = WasmModule::from_wat(&text).unwrap();
+        assert_eq!(parsed.to_bytes(), module.to_bytes());
+    }
+}
+
+#[test]
+fn offsets_of_modules_the_parser_rejects_are_errors() {
+    let mut module = WasmModule::from_wat("(module (global i32 (i32.const 0)))").unwrap();
+    let mut body = vec![];
+    for _ in 0..=swai_parser::instructions::MAX_NESTING_DEPTH {
+        body = vec![Instructions::Block {
+            ty: BlockType::Empty,
+            body,
+        }];
+    }
+    module.sections.global[0].1 = body;
+
+    // The offsets are read back from the encoding, which nests the blocks of the initializer too deep
+    let err = print(&module, &OPTIONS[2]).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::ImplementationLimit {
+            construct: "block nesting depth",
+            ..
+        }
+    ));
+    assert!(print(&module, &FLAT).is_ok());
+}
@@ -0,0 +1,200 @@
+use swai_parser::{error::WasmParserErrorKind, WasmModule};
+
+mod common;
+
+use common::module;
+
+const TYPES: (u8, &[u8]) = (1, &[0x01, 0x60, 0x00, 0x00]);
+const FUNCTIONS: (u8, &[u8]) = (3, &[0x01, 0x00]);
+const MEMORY: (u8, &[u8]) = (5, &[0x01, 0x00, 0x01]);
+const DATA_COUNT: (u8, &[u8]) = (12, &[0x00]);
+const CODE: (u8, &[u8]) = (10, &[0x01, 0x02, 0x00, 0x0B]);
+const DATA: (u8, &[u8]) = (11, &[0x00]);
+const CUSTOM: (u8, &[u8]) = (0, &[0x03, b'f', b'o', b'o', 0xAA]);
+
+#[test]
+fn sections_in_order_are_accepted() {
+    let bytes = module(&[
+        CUSTOM, TYPES, CUSTOM, FUNCTIONS, MEMORY, DATA_COUNT, CODE, DATA, CUSTOM,
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(module.sections.code.len(), 1);
+    assert_eq!(module.sections.data_count, Some(0));
+    assert_eq!(module.sections.custom.len(), 3);
+}
+
+#[test]
+fn out_of_order_sections_are_rejected() {
+    let err = WasmModule::from_bytes(&module(&[FUNCTIONS, TYPES])).unwrap_err();
+
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::SectionOutOfOrder {
+            section: "type section",
+            previous: "function section",
+        }
+    ));
+    assert_eq!(err.offset, Some(12));
+}
+
+#[test]
+fn data_count_section_comes_before_the_code_section() {
+    let err = WasmModule::from_bytes(&module(&[TYPES, FUNCTIONS, CODE, DATA_COUNT])).unwrap_err();
+
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::SectionOutOfOrder {
+            section: "data count section",
+            previous: "code section",
+        }
+    ));
+}
+
+#[test]
+fn duplicate_sections_are_rejected() {
+    let err = WasmModule::from_bytes(&module(&[TYPES, TYPES])).unwrap_err();
+
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::DuplicateSection {
+            section: "type section"
+        }
+    ));
+}
+
+#[test]
+fn unread_section_bytes_are_a_size_mismatch() {
+    // A memory section with a trailing byte after its only entry
+    let err = WasmModule::from_bytes(&module(&[(5, &[0x01, 0x00, 0x01, 0x00])])).unwrap_err();
+
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::SizeMismatch {
+            construct: "memory section",
+            declared: 4,
+            consumed: 3,
+        }
+    ));
+    assert_eq!(err.offset, Some(13));
+}
+
+#[test]
+fn unread_function_body_bytes_are_a_size_mismatch() {
+    let code: (u8, &[u8]) = (10, &[0x01, 0x03, 0x00, 0x0B, 0x01]);
+    let err = WasmModule::from_bytes(&module(&[TYPES, FUNCTIONS, code])).unwrap_err();
+
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::SizeMismatch {
+            construct: "function body",
+            declared: 3,
+            consumed: 2,
+        }
+    ));
+    assert_eq!(err.context, ["code section", "function 0"]);
+}
+
+#[test]
+fn sections_cant_read_past_their_declared_size() {
+    // The memory section declares 2 bytes but its limits need 3, the third byte belongs to the next section
+    let mut bytes = module(&[(5, &[0x01, 0x01, 0x01])]);
+    bytes[9] = 2;
+
+    let err = WasmModule::from_bytes(&bytes).unwrap_err();
+
+    assert!(matches!(err.kind, WasmParserErrorKind::ParserError(_)));
+    assert_eq!(err.context[0], "memory section");
+}
+
+#[test]
+fn malformed_name_sections_dont_fail_the_parse() {
+    // A function names subsection declaring 5 bytes, of which only 1 follows
+    let names: (u8, &[u8]) = (0, &[0x04, b'n', b'a', b'm', b'e', 0x01, 0x05, 0x01]);
+    let bytes = module(&[TYPES, FUNCTIONS, CODE, names]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(module.sections.names, None);
+    assert_eq!(module.sections.code.len(), 1);
+    let section = module.sections.custom_section("name").unwrap();
+    assert_eq!(section.data.as_ref(), &[0x01, 0x05, 0x01]);
+    let err = section.decode_error.as_ref().unwrap();
+    assert_eq!(err.context[0], "custom section 'name'");
+
+    // The raw bytes are written back unchanged
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn malformed_linking_and_reloc_sections_dont_fail_the_parse() {
+    let linking: (u8, &[u8]) = (0, &[0x07, b'l', b'i', b'n', b'k', b'i', b'n', b'g', 0x01]);
+    // Section 0, one relocation of the unknown type 0x7F
+    let code_relocations: (u8, &[u8]) = (
+        0,
+        &[
+            0x0A, b'r', b'e', b'l', b'o', b'c', b'.', b'C', b'O', b'D', b'E', 0x00, 0x01, 0x7F,
+        ],
+    );
+    // Section 1, one function index relocation at offset 4 of symbol 0
+    let data_relocations: (u8, &[u8]) = (
+        0,
+        &[
+            0x0A, b'r', b'e', b'l', b'o', b'c', b'.', b'D', b'A', b'T', b'A', 0x01, 0x01, 0x00,
+            0x04, 0x00,
+        ],
+    );
+    let bytes = module(&[
+        TYPES,
+        FUNCTIONS,
+        CODE,
+        linking,
+        code_relocations,
+        data_relocations,
+    ]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(module.sections.linking, None);
+    let err = module.sections.custom[0].decode_error.as_ref().unwrap();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::UnexpectedValue {
+            construct: "linking metadata version",
+            value: 1
+        }
+    ));
+
+    let err = module.sections.custom[1].decode_error.as_ref().unwrap();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "relocation type",
+            byte: 0x7F
+        }
+    ));
+    assert_eq!(err.context[0], "custom section 'reloc.CODE'");
+
+    // Sections after a malformed one are still decoded
+    assert!(module.sections.custom[2].decode_error.is_none());
+    assert_eq!(module.sections.relocations.len(), 1);
+    assert_eq!(module.sections.relocations[0].name.as_str(), "reloc.DATA");
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn sections_after_a_malformed_custom_section_are_parsed() {
+    // A symbol table subsection whose only symbol has the unknown kind 6
+    let linking: (u8, &[u8]) = (
+        0,
+        &[
+            0x07, b'l', b'i', b'n', b'k', b'i', b'n', b'g', 0x02, 0x08, 0x04, 0x01, 0x06, 0x00,
+            0x00,
+        ],
+    );
+    let bytes = module(&[TYPES, FUNCTIONS, linking, CODE, CUSTOM]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert!(module.sections.custom[0].decode_error.is_some());
+    assert_eq!(module.sections.code.len(), 1);
+    assert_eq!(module.sections.custom.len(), 2);
+    assert_eq!(module.to_bytes(), bytes);
+}
@@ -0,0 +1,178 @@
+use swai_parser::{
+    error::ValidationErrorKind,
+    instructions::{Instructions, MemArg},
+    validate,
+    wat::PrintOptions,
+    WasmModule,
+};
+
+mod common;
+
+use common::{FUNC_TYPE, ONE_FUNCTION};
+
+/// A module with a memory and a single `(func)` with the given body
+fn function(body: &[u8]) -> Vec<u8> {
+    common::function(&[FUNC_TYPE, ONE_FUNCTION, (5, &[0x01, 0x00, 0x01])], body)
+}
+
+fn body<'a>(module: &'a WasmModule) -> &'a [Instructions] {
+    &module.sections.code[0].body
+}
+
+#[test]
+fn immediates_are_decoded() {
+    let mut body_bytes = vec![0x41, 0x00, 0xFD, 0x0C];
+    body_bytes.extend(0..16u8);
+    body_bytes.extend([0xFD, 0x0C]);
+    body_bytes.extend((0..16u8).map(|i| 0xF0 | i));
+    body_bytes.extend([0xFD, 0x0D]);
+    body_bytes.extend([0, 17, 2, 19, 4, 21, 6, 23, 8, 25, 10, 27, 12, 29, 14, 31]);
+    body_bytes.extend([0xFD, 0x57, 0x03, 0x10, 0x01]);
+    body_bytes.extend([0xFD, 0x15, 0x0F, 0x1A]);
+
+    let bytes = function(&body_bytes);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    assert_eq!(
+        body(&module),
+        [
+            Instructions::i32_const(0),
+            Instructions::v128_const(0x0F0E0D0C_0B0A0908_07060504_03020100),
+            Instructions::v128_const(0xFFFEFDFC_FBFAF9F8_F7F6F5F4_F3F2F1F0),
+            Instructions::i8x16_shuffle([
+                0, 17, 2, 19, 4, 21, 6, 23, 8, 25, 10, 27, 12, 29, 14, 31
+            ]),
+            Instructions::v128_load64_lane(
+                MemArg {
+                    align: 3,
+                    offset: 16
+                },
+                1
+            ),
+            Instructions::i8x16_extract_lane_s(15),
+            Instructions::Drop,
+        ]
+    );
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn multi_byte_opcodes_are_decoded() {
+    // i32x4.add is opcode 174, which takes two bytes as a LEB128
+    let mut body_bytes = vec![0xFD, 0x0C];
+    body_bytes.extend([0; 16]);
+    body_bytes.extend([0xFD, 0x0C]);
+    body_bytes.extend([0; 16]);
+    body_bytes.extend([0xFD, 0xAE, 0x01, 0x1A]);
+
+    let bytes = function(&body_bytes);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+
+    assert_eq!(body(&module)[2], Instructions::i32x4_add);
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn unknown_opcodes_are_rejected() {
+    let bytes = function(&[0xFD, 0x80, 0x02]);
+    let error = WasmModule::from_bytes(&bytes).unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .contains("0xFD prefixed instruction opcode"),
+        "{error}"
+    );
+}
+
+#[test]
+fn lane_indices_are_bounds_checked() {
+    let module = WasmModule::from_wat(
+        "(module (func (result i32) v128.const i64x2 0 0 i16x8.extract_lane_u 8))",
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::InvalidLaneIndex { lane: 8, lanes: 8 }
+    ));
+
+    let module = WasmModule::from_wat(
+        "(module (func (result v128)
+          v128.const i64x2 0 0 v128.const i64x2 0 0
+          i8x16.shuffle 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 32))",
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::InvalidLaneIndex {
+            lane: 32,
+            lanes: 32
+        }
+    ));
+}
+
+#[test]
+fn operand_types_are_checked() {
+    let module =
+        WasmModule::from_wat("(module (func (result v128) i32.const 1 i32x4.neg))").unwrap();
+    assert!(validate(&module).is_err());
+
+    let module = WasmModule::from_wat(
+        "(module (memory 1) (func (result v128) i32.const 0 v128.load align=32))",
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::InvalidAlignment { .. }
+    ));
+}
+
+#[test]
+fn text_format_round_trips() {
+    let source = r#"
+        (module
+          (memory 1)
+          (func (param v128 i32) (result i32)
+            local.get 0
+            v128.const f32x4 1.5 -0 nan inf
+            f32x4.add
+            v128.const i8x16 -1 255 0 1 2 3 4 5 6 7 8 9 10 11 12 13
+            i8x16.shuffle 0 1 2 3 16 17 18 19 4 5 6 7 20 21 22 23
+            local.get 1
+            i16x8.shl
+            i32.const 0
+            local.get 0
+            v128.store32_lane offset=4 align=2 3
+            i8x16.bitmask))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap();
+
+    for options in [
+        PrintOptions::default(),
+        PrintOptions {
+            folded: true,
+            ..PrintOptions::default()
+        },
+    ] {
+        let printed = module.to_wat(&options).unwrap();
+        let reparsed = WasmModule::from_wat(&printed)
+            .unwrap_or_else(|err| panic!("{printed}\ndoes not parse: {err}"));
+        assert_eq!(reparsed.to_bytes(), module.to_bytes(), "{printed}");
+    }
+
+    let printed = module.to_wat(&PrintOptions::default()).unwrap();
+    assert!(
+        printed.contains("v128.const i32x4 0x3fc00000 0x80000000 0x7fc00000 0x7f800000"),
+        "{printed}"
+    );
+    assert!(
+        printed.contains("i8x16.shuffle 0 1 2 3 16 17 18 19 4 5 6 7 20 21 22 23"),
+        "{printed}"
+    );
+    assert!(
+        printed.contains("v128.store32_lane offset=4 align=2 3"),
+        "{printed}"
+    );
+}
@@ -0,0 +1,153 @@
+use swai_parser::{
+    error::{WasmParserError, WasmParserErrorKind},
+    parser::{Chunk, Parser, Payload},
+    WasmModule,
+};
+
+const FIXTURES: [&[u8]; 6] = [
+    include_bytes!("../../../tests/add.wasm"),
+    include_bytes!("../../../tests/asc_test.wasm"),
+    include_bytes!("../../../tests/helloworld.wasm"),
+    include_bytes!("../../../tests/memory.wasm"),
+    include_bytes!("../../../tests/module.wasm"),
+    include_bytes!("../../../tests/test.wasm"),
+];
+
+/// Feeds `bytes` to a parser `chunk_size` bytes at a time, collecting every payload up to the end
+fn parse_in_chunks(bytes: &[u8], chunk_size: usize) -> Result<Vec<Payload<'_>>, WasmParserError> {
+    let mut parser = Parser::new();
+    let mut payloads = vec![];
+    let mut available = 0;
+    loop {
+        let eof = available == bytes.len();
+        match parser.parse(&bytes[parser.offset()..available], eof)? {
+            Chunk::NeedMoreData(needed) => {
+                assert!(!eof);
+                assert!(needed > 0);
+                available = (available + chunk_size).min(bytes.len());
+            }
+            Chunk::Parsed { payload, .. } => {
+                let end = matches!(payload, Payload::End);
+                payloads.push(payload);
+                if end {
+                    return Ok(payloads);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn payloads_match_the_module() {
+    for bytes in FIXTURES {
+        let module = WasmModule::from_bytes(bytes).unwrap();
+        let payloads = parse_in_chunks(bytes, bytes.len()).unwrap();
+
+        let bodies: Vec<_> = payloads
+            .iter()
+            .filter_map(|payload| match payload {
+                Payload::FunctionBody { locals, body, .. } => Some((locals, body)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bodies.len(), module.sections.code.len());
+        for ((locals, body), code) in bodies.iter().zip(&module.sections.code) {
+            assert_eq!(format!("{locals:?}"), format!("{:?}", code.locals));
+            assert_eq!(format!("{body:?}"), format!("{:?}", code.body));
+        }
+
+        let custom: Vec<_> = payloads
+            .iter()
+            .filter_map(|payload| match payload {
+                Payload::CustomSection(section) => Some(section),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(custom.len(), module.sections.custom.len());
+        for (section, module_section) in custom.iter().zip(&module.sections.custom) {
+            assert_eq!(section.name.as_str(), module_section.name.as_str());
+            assert_eq!(section.range, module_section.range);
+            assert_eq!(section.data, module_section.data);
+        }
+
+        assert!(matches!(payloads[0], Payload::Header));
+    }
+}
+
+#[test]
+fn chunk_size_doesnt_change_the_payloads() {
+    for bytes in FIXTURES {
+        let whole = format!("{:?}", parse_in_chunks(bytes, bytes.len()).unwrap());
+        for chunk_size in [1, 3, 7, 64] {
+            assert_eq!(
+                format!("{:?}", parse_in_chunks(bytes, chunk_size).unwrap()),
+                whole
+            );
+        }
+    }
+}
+
+#[test]
+fn function_bodies_are_parsed_before_the_section_ends() {
+    let bytes = include_bytes!("../../../tests/add.wasm");
+    let mut parser = Parser::new();
+    let mut available = 0;
+    let mut first_body = None;
+    while first_body.is_none() {
+        match parser
+            .parse(&bytes[parser.offset()..available], false)
+            .unwrap()
+        {
+            Chunk::NeedMoreData(_) => available += 1,
+            Chunk::Parsed {
+                payload: Payload::FunctionBody { .. },
+                ..
+            } => first_body = Some(parser.offset()),
+            Chunk::Parsed { .. } => {}
+        }
+    }
+
+    let module = WasmModule::from_bytes(bytes).unwrap();
+    assert!(first_body.unwrap() < module.sections.custom[0].range.start);
+}
+
+#[test]
+fn function_bodies_are_numbered_after_the_imports() {
+    let payloads = parse_in_chunks(include_bytes!("../../../tests/helloworld.wasm"), 5).unwrap();
+    let module = WasmModule::from_bytes(include_bytes!("../../../tests/helloworld.wasm")).unwrap();
+    let imported = module.sections.imports.len() as u32;
+
+    let first = payloads.iter().find_map(|payload| match payload {
+        Payload::FunctionBody { index, .. } => Some(*index),
+        _ => None,
+    });
+    assert_eq!(first, Some(imported));
+}
+
+#[test]
+fn errors_match_the_module_parser() {
+    // A memory section with a trailing byte after its only entry
+    let bytes = [
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, 0x05, 0x04, 0x01, 0x00, 0x01, 0x00,
+    ];
+    let module_err = WasmModule::from_bytes(&bytes).unwrap_err();
+    for chunk_size in [1, bytes.len()] {
+        let err = parse_in_chunks(&bytes, chunk_size).unwrap_err();
+        assert_eq!(format!("{:?}", err.kind), format!("{:?}", module_err.kind));
+        assert_eq!(err.context, module_err.context);
+        assert_eq!(err.offset, Some(13));
+    }
+}
+
+#[test]
+fn truncated_modules_are_unexpected_eof() {
+    let bytes = include_bytes!("../../../tests/add.wasm");
+    let truncated = &bytes[..bytes.len() - 3];
+
+    let err = parse_in_chunks(truncated, 16).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        WasmParserErrorKind::UnexpectedEof { .. }
+    ));
+    assert_eq!(err.offset, Some(truncated.len()));
+}
@@ -0,0 +1,106 @@
+use swai_parser::{
+    error::ValidationErrorKind,
+    instructions::Instructions,
+    types::{Indecies, NumberTypes, ValueType},
+    validate,
+    wat::PrintOptions,
+    WasmModule,
+};
+
+mod common;
+
+use common::ONE_FUNCTION;
+
+#[test]
+fn tail_calls_are_decoded() {
+    // A (func (result i32)) with the body
+    // i32.const 0 return_call_indirect (type 0) 0 return_call 0
+    let bytes = common::function(
+        &[
+            (1, &[0x01, 0x60, 0x00, 0x01, 0x7F]),
+            ONE_FUNCTION,
+            (4, &[0x01, 0x70, 0x00, 0x01]),
+        ],
+        &[0x41, 0x00, 0x13, 0x00, 0x00, 0x12, 0x00],
+    );
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    let code = &module.sections.code[0].body;
+    assert_eq!(
+        code[1],
+        Instructions::ReturnCallIndirect(Indecies::TypeIdx(0), Indecies::TableIdx(0))
+    );
+    assert_eq!(code[2], Instructions::ReturnCall(Indecies::FuncIdx(0)));
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn callees_have_the_results_of_the_caller() {
+    let module = WasmModule::from_wat(
+        r#"(module
+          (func $f (result i64) (i64.const 0))
+          (func (result i32) (return_call $f)))"#,
+    )
+    .unwrap();
+    let error = validate(&module).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ValidationErrorKind::TailCallTypeMismatch { ref expected, ref actual }
+            if expected == &[ValueType::NumType(NumberTypes::i32)]
+                && actual == &[ValueType::NumType(NumberTypes::i64)]
+    ));
+
+    // Nothing is reachable after a tail call
+    let module = WasmModule::from_wat(
+        r#"(module
+          (func $f (param i32))
+          (func (result f64) (return_call $f (i32.const 1)) (i32.add)))"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::TailCallTypeMismatch { .. }
+    ));
+    let module = WasmModule::from_wat(
+        r#"(module
+          (func $f)
+          (func (return_call $f (i32.const 1)) (i32.add) (drop)))"#,
+    )
+    .unwrap();
+    validate(&module).unwrap();
+}
+
+#[test]
+fn text_format_round_trips() {
+    let source = r#"
+        (module
+          (type $binary (func (param i32 i32) (result i32)))
+          (table $operations funcref (elem $add))
+          (func $add (param i32 i32) (result i32) (i32.add (local.get 0) (local.get 1)))
+          (func (param i32) (result i32)
+            (if (result i32) (local.get 0)
+              (then (return_call $add (local.get 0) (i32.const 1)))
+              (else
+                (return_call_indirect $operations (type $binary)
+                  (local.get 0)
+                  (i32.const 2)
+                  (i32.const 0))))))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap();
+
+    for options in [
+        PrintOptions::default(),
+        PrintOptions {
+            folded: true,
+            ..PrintOptions::default()
+        },
+    ] {
+        let printed = module.to_wat(&options).unwrap();
+        let reparsed = WasmModule::from_wat(&printed)
+            .unwrap_or_else(|err| panic!("{printed}\ndoes not parse: {err}"));
+        validate(&reparsed).unwrap_or_else(|err| panic!("{printed}\ndoes not validate: {err}"));
+        assert_eq!(reparsed.to_bytes(), module.to_bytes(), "{printed}");
+    }
+}
@@ -0,0 +1,149 @@
+use swai_parser::{
+    error::{ValidationErrorKind, WasmParserErrorKind},
+    instructions::{Instructions, MemArg},
+    validate,
+    wat::PrintOptions,
+    WasmModule,
+};
+
+mod common;
+
+use common::memory_function;
+
+#[test]
+fn shared_limits_are_decoded() {
+    let bytes = memory_function(&[0x03, 0x01, 0x02], &[]);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    let memory = &module.sections.memory[0];
+    assert_eq!((memory.minimum(), memory.maximum()), (1, Some(2)));
+    assert!(memory.is_shared());
+    assert_eq!(module.to_bytes(), bytes);
+
+    // A shared memory needs a maximum
+    let error = WasmModule::from_bytes(&memory_function(&[0x02, 0x01], &[])).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        WasmParserErrorKind::UnexpectedByte {
+            construct: "limits",
+            byte: 0x02
+        }
+    ));
+}
+
+#[test]
+fn atomic_instructions_are_decoded() {
+    // i32.const 0 i64.const 1 i64.const 2 i64.atomic.rmw32.cmpxchg_u offset=8 drop atomic.fence
+    // i32.const 0 i32.const 1 memory.atomic.notify drop
+    let body = [
+        0x41, 0x00, 0x42, 0x01, 0x42, 0x02, 0xFE, 0x4E, 0x02, 0x08, 0x1A, 0xFE, 0x03, 0x00, 0x41,
+        0x00, 0x41, 0x01, 0xFE, 0x00, 0x02, 0x00, 0x1A,
+    ];
+    let bytes = memory_function(&[0x03, 0x01, 0x01], &body);
+    let module = WasmModule::from_bytes(&bytes).unwrap();
+    validate(&module).unwrap();
+
+    let code = &module.sections.code[0].body;
+    assert_eq!(
+        code[3],
+        Instructions::i64_atomic_rmw32_cmpxchg_u(MemArg {
+            align: 2,
+            offset: 8
+        })
+    );
+    assert_eq!(code[5], Instructions::atomic_fence);
+    assert_eq!(
+        code[8],
+        Instructions::memory_atomic_notify(MemArg {
+            align: 2,
+            offset: 0
+        })
+    );
+    assert_eq!(module.to_bytes(), bytes);
+
+    // atomic.fence reserves a zero byte
+    let bytes = memory_function(&[0x03, 0x01, 0x01], &[0xFE, 0x03, 0x01]);
+    assert!(WasmModule::from_bytes(&bytes).is_err());
+
+    let error = WasmModule::from_bytes(&memory_function(&[0x00, 0x01], &[0xFE, 0x4F])).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("0xFE prefixed instruction opcode"),
+        "{error}"
+    );
+}
+
+#[test]
+fn atomic_accesses_are_naturally_aligned() {
+    let module = WasmModule::from_wat(
+        "(module (memory 1 1 shared) (func (result i32) i32.const 0 i32.atomic.load align=2))",
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::InvalidAtomicAlignment {
+            align: 1,
+            natural: 2
+        }
+    ));
+
+    let module = WasmModule::from_wat(
+        "(module (func (result i64) i32.const 0 i64.const 0 i64.const 0 memory.atomic.wait64))",
+    )
+    .unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::UnknownIndex {
+            space: "memory",
+            ..
+        }
+    ));
+
+    let module = WasmModule::from_wat("(module (table 1 2 shared funcref))").unwrap();
+    assert!(matches!(
+        validate(&module).unwrap_err().kind,
+        ValidationErrorKind::SharedTable
+    ));
+}
+
+#[test]
+fn text_format_round_trips() {
+    let source = r#"
+        (module
+          (memory (export "memory") 1 4 shared)
+          (func (param i32) (result i64)
+            (drop (i32.atomic.rmw8.add_u offset=3 (local.get 0) (i32.const 1)))
+            (i32.atomic.store16 (local.get 0) (i32.const 7))
+            (drop (memory.atomic.wait32 (local.get 0) (i32.const 0) (i64.const -1)))
+            (drop (memory.atomic.notify (local.get 0) (i32.const 1)))
+            atomic.fence
+            (i64.atomic.rmw.cmpxchg (local.get 0) (i64.const 1) (i64.atomic.load (local.get 0)))))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    validate(&module).unwrap();
+    assert!(module.sections.memory[0].is_shared());
+    assert_eq!(module.sections.memory[0].maximum(), Some(4));
+
+    for options in [
+        PrintOptions::default(),
+        PrintOptions {
+            folded: true,
+            ..PrintOptions::default()
+        },
+    ] {
+        let printed = module.to_wat(&options).unwrap();
+        let reparsed = WasmModule::from_wat(&printed)
+            .unwrap_or_else(|err| panic!("{printed}\ndoes not parse: {err}"));
+        validate(&reparsed).unwrap_or_else(|err| panic!("{printed}\ndoes not validate: {err}"));
+        assert_eq!(reparsed.to_bytes(), module.to_bytes(), "{printed}");
+    }
+
+    let printed = module.to_wat(&PrintOptions::default()).unwrap();
+    assert!(printed.contains("(memory (;0;) 1 4 shared)"), "{printed}");
+    assert!(
+        printed.contains("i32.atomic.rmw8.add_u offset=3\n"),
+        "{printed}"
+    );
+}
@@ -0,0 +1,220 @@
+use swai_parser::{error::ValidationErrorKind, validate, WasmModule};
+
+mod common;
+
+/// A module with a single `(func (param i32) (result i32))` with the given locals and body
+fn function(locals: &[u8], body: &[u8]) -> WasmModule<'static> {
+    let bytes = common::module(&[
+        (1, &[0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F]),
+        (3, &[0x01, 0x00]),
+        (5, &[0x01, 0x00, 0x01]),
+        (6, &[0x01, 0x7F, 0x00, 0x41, 0x00, 0x0B]),
+        (10, &common::code(locals, body)),
+    ]);
+    WasmModule::from_bytes(&bytes).unwrap().into_owned()
+}
+
+fn validation_error(module: &WasmModule) -> ValidationErrorKind {
+    validate(module).unwrap_err().kind
+}
+
+#[test]
+fn fixtures_are_valid() {
+    for bytes in [
+        include_bytes!("../../../tests/add.wasm").as_slice(),
+        include_bytes!("../../../tests/asc_test.wasm"),
+        include_bytes!("../../../tests/memory.wasm"),
+        include_bytes!("../../../tests/module.wasm"),
+        include_bytes!("../../../tests/test.wasm"),
+    ] {
+        validate(&WasmModule::from_bytes(bytes).unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn errors_point_at_the_function_and_instruction() {
+    // Function 1 passes two i32s to an import taking (i32, i64)
+    let module = WasmModule::from_bytes(include_bytes!("../../../tests/helloworld.wasm")).unwrap();
+    let err = validate(&module).unwrap_err();
+
+    assert!(matches!(err.kind, ValidationErrorKind::TypeMismatch { .. }));
+    assert_eq!(err.function, Some(1));
+    assert_eq!(err.instruction, Some(2));
+    assert_eq!(err.context_path(), "function 1 › instruction 2");
+}
+
+#[test]
+fn well_typed_bodies_are_valid() {
+    // local.get 0, i32.const 1, i32.add, local.tee 1, block (result i32) local.get 1 end, i32.load
+    let module = function(
+        &[0x01, 0x01, 0x7F],
+        &[
+            0x20, 0x00, 0x41, 0x01, 0x6A, 0x22, 0x01, 0x02, 0x7F, 0x20, 0x01, 0x0B, 0x28, 0x02,
+            0x00, 0x1A,
+        ],
+    );
+    validate(&module).unwrap();
+}
+
+#[test]
+fn operand_types_are_checked() {
+    // i64.const 0 as the i32 result
+    let module = function(&[0x00], &[0x42, 0x00]);
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::TypeMismatch { .. }
+    ));
+
+    // i32.add with a single operand
+    let module = function(&[0x00], &[0x20, 0x00, 0x6A]);
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::StackUnderflow { .. }
+    ));
+
+    // local.get 0 twice leaves an extra value
+    let module = function(&[0x00], &[0x20, 0x00, 0x20, 0x00]);
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::UnexpectedOperands { count: 1 }
+    ));
+}
+
+#[test]
+fn unreachable_code_is_polymorphic() {
+    // unreachable, i32.add, drop, br 0 — the missing operands come from the unreachable stack
+    let module = function(&[0x00], &[0x00, 0x6A, 0x1A, 0x0C, 0x00]);
+    validate(&module).unwrap();
+}
+
+#[test]
+fn if_without_else_cant_produce_values() {
+    // local.get 0, if (result i32) i32.const 1 end
+    let module = function(&[0x00], &[0x20, 0x00, 0x04, 0x7F, 0x41, 0x01, 0x0B]);
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::MissingElse
+    ));
+}
+
+#[test]
+fn indices_are_bounds_checked() {
+    // local.get 5
+    let err = validate(&function(&[0x00], &[0x20, 0x05])).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ValidationErrorKind::UnknownIndex {
+            space: "local",
+            index: 5
+        }
+    ));
+    assert_eq!(err.instruction, Some(0));
+
+    // br 3
+    assert!(matches!(
+        validation_error(&function(&[0x00], &[0x0C, 0x03])),
+        ValidationErrorKind::UnknownIndex { space: "label", .. }
+    ));
+
+    // call 9
+    assert!(matches!(
+        validation_error(&function(&[0x00], &[0x10, 0x09])),
+        ValidationErrorKind::UnknownIndex {
+            space: "function",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn immutable_globals_cant_be_set() {
+    // local.get 0, global.set 0, local.get 0
+    let module = function(&[0x00], &[0x20, 0x00, 0x24, 0x00, 0x20, 0x00]);
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::ImmutableGlobal { index: 0 }
+    ));
+}
+
+#[test]
+fn alignment_cant_exceed_the_natural_alignment() {
+    // local.get 0, i32.load align=8
+    let module = function(&[0x00], &[0x20, 0x00, 0x28, 0x03, 0x00]);
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::InvalidAlignment {
+            align: 3,
+            natural: 2
+        }
+    ));
+}
+
+#[test]
+fn constant_expressions_only_allow_constant_instructions() {
+    let module =
+        WasmModule::from_wat("(module (global i32 (i32.const 1) (i32.const 2) (i32.add)))")
+            .unwrap();
+    let err = validate(&module).unwrap_err();
+
+    assert!(matches!(
+        err.kind,
+        ValidationErrorKind::NonConstantInstruction
+    ));
+    assert_eq!(err.context, ["global 0"]);
+}
+
+#[test]
+fn export_names_are_unique() {
+    let module = WasmModule::from_wat(
+        r#"(module (memory 1) (export "m" (memory 0)) (export "m" (memory 0)))"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::DuplicateExport { .. }
+    ));
+}
+
+#[test]
+fn import_names_are_unique() {
+    let module =
+        WasmModule::from_wat(r#"(module (import "m" "f" (func)) (import "m" "f" (func)))"#)
+            .unwrap();
+    let error = validate(&module).unwrap_err();
+    assert!(matches!(
+        &error.kind,
+        ValidationErrorKind::DuplicateImport { module, name } if module == "m" && name == "f"
+    ));
+    assert_eq!(error.context, ["import 1"]);
+
+    // The same name from another module is a different import
+    let module =
+        WasmModule::from_wat(r#"(module (import "m" "f" (func)) (import "n" "f" (func)))"#)
+            .unwrap();
+    validate(&module).unwrap();
+}
+
+#[test]
+fn limits_are_bounds_checked() {
+    let module = WasmModule::from_wat("(module (memory 2 1))").unwrap();
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::InvalidLimits { min: 2, max: 1 }
+    ));
+
+    let module = WasmModule::from_wat("(module (memory 65537))").unwrap();
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::LimitExceeded { value: 65537, .. }
+    ));
+}
+
+#[test]
+fn data_drop_requires_a_data_count_section() {
+    // data.drop 0
+    let module = function(&[0x00], &[0xFC, 0x09, 0x00, 0x20, 0x00]);
+    assert!(matches!(
+        validation_error(&module),
+        ValidationErrorKind::MissingDataCount
+    ));
+}
@@ -0,0 +1,407 @@
+use swai_parser::{
+    error::WasmParserErrorKind,
+    instructions::{BlockType, Instructions, MemArg},
+    types::{ExportDesc, Indecies, NumberTypes, ValueType},
+    WasmModule,
+};
+
+const I32: ValueType = ValueType::NumType(NumberTypes::i32);
+
+/// Drops custom sections and the views decoded from them, the checked-in fixtures were linked by
+/// other tools which added `name`, `linking` and `reloc.*` sections
+fn without_custom_sections(mut module: WasmModule) -> WasmModule {
+    module.sections.custom.clear();
+    module.sections.names = None;
+    module.sections.linking = None;
+    module.sections.relocations.clear();
+    module
+}
+
+fn code<'m>(module: &'m WasmModule<'_>, index: usize) -> &'m [Instructions] {
+    &module.sections.code[index].body
+}
+
+#[test]
+fn fixtures_match_their_binaries() {
+    let fixtures: [(&str, &[u8]); 5] = [
+        (
+            include_str!("../../../tests/add.wat"),
+            include_bytes!("../../../tests/add.wasm"),
+        ),
+        (
+            include_str!("../../../tests/asc_test.wat"),
+            include_bytes!("../../../tests/asc_test.wasm"),
+        ),
+        (
+            include_str!("../../../tests/memory.wat"),
+            include_bytes!("../../../tests/memory.wasm"),
+        ),
+        (
+            include_str!("../../../tests/module.wat"),
+            include_bytes!("../../../tests/module.wasm"),
+        ),
+        (
+            include_str!("../../../tests/test.wat"),
+            include_bytes!("../../../tests/test.wasm"),
+        ),
+    ];
+
+    for (text, bytes) in fixtures {
+        let from_text = WasmModule::from_wat(text).unwrap();
+        let from_binary = WasmModule::from_bytes(bytes).unwrap();
+        assert_eq!(
+            without_custom_sections(from_text),
+            without_custom_sections(from_binary)
+        );
+    }
+}
+
+#[test]
+fn helloworld_resolves_names() {
+    // The checked-in binary was built from a different version of the source
+    let module = WasmModule::from_wat(include_str!("../../../tests/helloworld.wat")).unwrap();
+
+    let (import_module, import_name, _) = &module.sections.imports[0];
+    assert_eq!(import_module.as_str(), "std::io");
+    assert_eq!(import_name.as_str(), "print");
+    assert_eq!(module.sections.start, Some(Indecies::FuncIdx(1)));
+    assert_eq!(&module.sections.data[0].bytes[..], b"Hello World from WASM");
+    assert_eq!(
+        code(&module, 0),
+        [
+            Instructions::i32_const(0),
+            Instructions::i32_const(0),
+            Instructions::Call(Indecies::FuncIdx(2)),
+        ]
+    );
+}
+
+#[test]
+fn type_uses_are_resolved() {
+    let module = WasmModule::from_wat(
+        r#"(module
+            (type $unary (func (param i32) (result i32)))
+            (func $a (type $unary) local.get 0)
+            (func $b (type $unary) (param $x i32) (result i32) local.get $x)
+            (func $c (param i32) (result i32) local.get 0)
+            (func $d (param i32 i32))
+            (func $e (call_indirect (param i32 i32) (i32.const 1) (i32.const 2) (i32.const 0)))
+            (table 1 funcref)
+        )"#,
+    )
+    .unwrap();
+
+    // Inline types that match an existing one reuse it, the others are added in order of use
+    assert_eq!(module.sections.types.len(), 3);
+    assert_eq!(
+        module.sections.functions,
+        [0, 0, 0, 1, 2].map(Indecies::TypeIdx)
+    );
+    assert_eq!(
+        code(&module, 4).last(),
+        Some(&Instructions::CallIndirect(
+            Indecies::TypeIdx(1),
+            Indecies::TableIdx(0)
+        ))
+    );
+}
+
+#[test]
+fn folded_and_flat_instructions_are_the_same() {
+    let module = WasmModule::from_wat(
+        r#"(module
+            (func (param $n i32) (result i32)
+                (if (result i32) (i32.eqz (local.get $n))
+                    (then (i32.const 1))
+                    (else (i32.mul (local.get $n) (i32.const 2)))))
+            (func (param $n i32) (result i32)
+                local.get $n
+                i32.eqz
+                if (result i32)
+                    i32.const 1
+                else
+                    local.get $n
+                    i32.const 2
+                    i32.mul
+                end)
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(code(&module, 0), code(&module, 1));
+    assert!(matches!(
+        code(&module, 0)[2],
+        Instructions::If {
+            ty: BlockType::Value(I32),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn labels_resolve_to_depths() {
+    let module = WasmModule::from_wat(
+        r#"(module
+            (func
+                (block $outer
+                    (loop $inner
+                        (br_if $inner (i32.const 1))
+                        (br $outer)
+                        (br_table $inner $outer 0 (i32.const 0))))
+                block $named
+                    br $named
+                end $named)
+        )"#,
+    )
+    .unwrap();
+
+    let Instructions::Block { body, .. } = &code(&module, 0)[0] else {
+        panic!("expected a block");
+    };
+    let Instructions::Loop { body, .. } = &body[0] else {
+        panic!("expected a loop");
+    };
+    assert_eq!(body[1], Instructions::BrIf(Indecies::LabelIdx(0)));
+    assert_eq!(body[2], Instructions::Br(Indecies::LabelIdx(1)));
+    assert_eq!(
+        body[4],
+        Instructions::BrTable(
+            vec![Indecies::LabelIdx(0), Indecies::LabelIdx(1)],
+            Indecies::LabelIdx(0)
+        )
+    );
+}
+
+#[test]
+fn inline_imports_and_exports() {
+    let module = WasmModule::from_wat(
+        r#"(module
+            (func $log (import "env" "log") (param i32))
+            (global $counter (import "env" "counter") (mut i32))
+            (memory (export "memory") (data "hi"))
+            (table $table (export "table") funcref (elem $run))
+            (global (export "answer") i32 (i32.const 42))
+            (func $run (export "run") (export "main") (call $log (global.get $counter)))
+        )"#,
+    )
+    .unwrap();
+    let sections = &module.sections;
+
+    assert_eq!(sections.imports.len(), 2);
+    let exports: Vec<_> = sections
+        .export
+        .iter()
+        .map(|(name, desc)| (name.as_str(), *desc))
+        .collect();
+    assert_eq!(
+        exports,
+        [
+            ("memory", ExportDesc::MemIdx(0)),
+            ("table", ExportDesc::TableIdx(0)),
+            ("answer", ExportDesc::GlobalIdx(1)),
+            ("run", ExportDesc::FuncIdx(1)),
+            ("main", ExportDesc::FuncIdx(1)),
+        ]
+    );
+    assert_eq!(sections.memory[0].maximum(), Some(1));
+    assert_eq!(&sections.data[0].bytes[..], b"hi");
+    assert_eq!(sections.element.len(), 1);
+    assert_eq!(
+        code(&module, 0),
+        [
+            Instructions::GlobalGet(Indecies::GlobalIdx(0)),
+            Instructions::Call(Indecies::FuncIdx(0)),
+        ]
+    );
+}
+
+#[test]
+fn strings_and_numbers() {
+    let module = WasmModule::from_wat(
+        r#"(module
+            (memory 1)
+            (data (i32.const 0) "\00\ff\n\t\"\\" "\u{263a}")
+            (func
+                i32.const 0xFFFF_FFFF
+                i32.const -2147483648
+                i64.const -0x8000000000000000
+                f32.const 0x1.8p-1
+                f64.const nan:0x1
+                f64.const -inf
+                f32.const 1_000.5e-1
+                i32.load8_u offset=16 align=1
+                i64.store offset=0x20)
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        &module.sections.data[0].bytes[..],
+        b"\x00\xff\n\t\"\\\xe2\x98\xba"
+    );
+    let body = code(&module, 0);
+    assert_eq!(body[0], Instructions::i32_const(-1));
+    assert_eq!(body[1], Instructions::i32_const(i32::MIN));
+    assert_eq!(body[2], Instructions::i64_const(i64::MIN));
+    assert_eq!(body[3], Instructions::f32_const(0.75f32.to_bits()));
+    assert_eq!(body[4], Instructions::f64_const(0x7FF0_0000_0000_0001));
+    assert_eq!(
+        body[5],
+        Instructions::f64_const(f64::NEG_INFINITY.to_bits())
+    );
+    assert_eq!(body[6], Instructions::f32_const(100.05f32.to_bits()));
+    assert_eq!(
+        body[7],
+        Instructions::i32_load_8u(MemArg {
+            align: 0,
+            offset: 16
+        })
+    );
+    assert_eq!(
+        body[8],
+        Instructions::i64_store(MemArg {
+            align: 3,
+            offset: 32
+        })
+    );
+}
+
+#[test]
+fn custom_annotations_become_custom_sections() {
+    let module = WasmModule::from_wat(
+        r#"(module
+            (@custom "first" (before first) "\01\02")
+            (@custom "after-types" (after type) "a" "b")
+            (@custom "last" "z")
+            (type (func))
+            (func (type 0))
+        )"#,
+    )
+    .unwrap();
+
+    let customs: Vec<_> = module
+        .sections
+        .custom
+        .iter()
+        .map(|section| (section.name.as_str(), &section.data[..], section.after))
+        .collect();
+    assert_eq!(
+        customs,
+        [
+            ("first", &b"\x01\x02"[..], None),
+            ("after-types", b"ab", Some(1)),
+            ("last", b"z", Some(10)),
+        ]
+    );
+}
+
+#[test]
+fn errors_point_at_the_source() {
+    let cases = [
+        (
+            "(module (func call $missing))",
+            1,
+            20,
+            "unknown function $missing",
+        ),
+        (
+            "(module\n  (func $f)\n  (func $f))",
+            3,
+            9,
+            "duplicate function $f",
+        ),
+        (
+            "(module (func) (import \"a\" \"b\" (func)))",
+            1,
+            16,
+            "imports have to come before",
+        ),
+        ("(module (data \"abc))", 1, 15, "unterminated string"),
+        (
+            "(module (func i32.const 0x1_0000_0000))",
+            1,
+            25,
+            "expected a number",
+        ),
+        (
+            "(module (func f32.const 1e40 drop))",
+            1,
+            25,
+            "constant out of range",
+        ),
+        (
+            "(module (func f64.const -0x1p1024 drop))",
+            1,
+            25,
+            "constant out of range",
+        ),
+        (
+            "(module (func (i32.add i32.const 1)))",
+            1,
+            24,
+            "expected '(' or ')'",
+        ),
+    ];
+
+    for (source, expected_line, expected_column, expected_message) in cases {
+        let err = WasmModule::from_wat(source).unwrap_err();
+        let WasmParserErrorKind::InvalidText {
+            message,
+            line,
+            column,
+        } = &err.kind
+        else {
+            panic!("{source}: unexpected error {err}");
+        };
+        assert!(message.starts_with(expected_message), "{source}: {message}");
+        assert_eq!(
+            (*line, *column),
+            (expected_line, expected_column),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn infinity_and_the_largest_finite_floats_are_in_range() {
+    let module = WasmModule::from_wat(
+        "(module (func
+            f32.const inf drop
+            f32.const -inf drop
+            f32.const 0x1.fffffep127 drop
+            f64.const 1.7976931348623157e308 drop))",
+    )
+    .unwrap();
+    assert_eq!(
+        code(&module, 0)
+            .iter()
+            .step_by(2)
+            .cloned()
+            .collect::<Vec<_>>(),
+        [
+            Instructions::f32_const(f32::INFINITY.to_bits()),
+            Instructions::f32_const(f32::NEG_INFINITY.to_bits()),
+            Instructions::f32_const(f32::MAX.to_bits()),
+            Instructions::f64_const(f64::MAX.to_bits()),
+        ]
+    );
+}
+
+#[test]
+fn deep_nesting_does_not_overflow_the_stack() {
+    let depth = swai_parser::instructions::MAX_NESTING_DEPTH;
+    let folded = format!(
+        "(module (func {}{}))",
+        "(block ".repeat(depth),
+        ")".repeat(depth)
+    );
+    assert!(WasmModule::from_wat(&folded).is_ok());
+
+    let flat = format!(
+        "(module (func {}{}))",
+        "block ".repeat(depth + 1),
+        "end ".repeat(depth + 1)
+    );
+    let err = WasmModule::from_wat(&flat).unwrap_err();
+    assert!(err.to_string().contains("nested deeper than the limit"));
+}
@@ -1,11 +1,10 @@
-use swai_parser::instructions::Instructions;
+use swai_parser::{error::ValidationError, instructions::Instructions, types::ValueType};
 use thiserror::Error;
 
+use crate::interpreter::Value;
+
 #[derive(Error, Debug)]
 pub enum WasmInterpreterError {
-    #[error("Failed to parse bytes: '{bytes:?}' into a string")]
-    StringFromBytes { bytes: Vec<u8> },
-
     #[error("Tried to set memory data ({data:?}) at offset ({offset}) failed to set byte at index: {failed_pos} of total memory length ({memory_len})")]
     ModifyMemoryOutOfBounds {
         offset: usize,
@@ -14,12 +13,79 @@ pub enum WasmInterpreterError {
         memory_len: usize,
     },
 
-    #[error("Memory invalid with unknown offset expr: {0:#?}")]
-    InvalidMemorySegmentOffset(Instructions),
+    #[error("Constant expression contains an unsupported instruction: {0:#?}")]
+    InvalidConstantExpression(Instructions),
+
+    #[error("The interpreter doesn't support the instruction: {0:#?}")]
+    UnsupportedInstruction(Instructions),
 
     #[error("The module doesn't have an entry point 'start' function")]
     NoEntryPoint,
 
+    #[error("The module is invalid: {0}")]
+    InvalidModule(#[from] ValidationError),
+
+    #[error(
+        "Importing {module}::{name} isn't supported, only functions, memories and tags can be imported"
+    )]
+    UnsupportedImport { module: String, name: String },
+
+    #[error("The imported function {module}::{name} hasn't been defined")]
+    UndefinedImport { module: String, name: String },
+
+    #[error("The module doesn't export a function named '{0}'")]
+    UnknownExport(String),
+
+    #[error("Expected values of types {expected:?}, got {actual:?}")]
+    TypeMismatch {
+        expected: Vec<ValueType>,
+        actual: Vec<ValueType>,
+    },
+
+    #[error("Couldn't allocate {size} {resource}")]
+    AllocationFailed { resource: &'static str, size: u64 },
+
+    #[error("Trap: {0}")]
+    Trap(#[from] Trap),
+
     #[error("I/O error: {0:#?}")]
     IOError(#[from] std::io::Error),
 }
+
+/// A runtime error that aborts the execution, see the wasm spec for more info: https://webassembly.github.io/spec/core/intro/overview.html#trap
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Trap {
+    #[error("unreachable executed")]
+    Unreachable,
+    #[error("integer divide by zero")]
+    IntegerDivideByZero,
+    #[error("integer overflow")]
+    IntegerOverflow,
+    #[error("invalid conversion to integer")]
+    InvalidConversionToInteger,
+    #[error("out of bounds memory access")]
+    OutOfBoundsMemoryAccess,
+    #[error("out of bounds table access")]
+    OutOfBoundsTableAccess,
+    #[error("undefined element")]
+    UndefinedElement,
+    #[error("uninitialized element")]
+    UninitializedElement,
+    #[error("indirect call type mismatch")]
+    IndirectCallTypeMismatch,
+    #[error("call stack exhausted")]
+    CallStackExhausted,
+    #[error("unaligned atomic")]
+    UnalignedAtomic,
+    #[error("expected shared memory")]
+    ExpectedSharedMemory,
+    #[error("null exception reference")]
+    NullExceptionReference,
+    #[error("null function reference")]
+    NullFunctionReference,
+    #[error("null reference")]
+    NullReference,
+    /// An exception no `try_table` caught, `tag` is its index in the tag index space
+    #[error("uncaught exception with tag {tag} and payload {payload:?}")]
+    UncaughtException { tag: u32, payload: Vec<Value> },
+}
@@ -1,76 +1,909 @@
-use swai_parser::{types::SegmentMode, WasmModule};
+use std::{collections::HashMap, fmt, rc::Rc, sync::Arc};
 
-use crate::error::WasmInterpreterError;
+use swai_parser::{
+    instructions::{BlockType, Catch, Instructions},
+    types::{
+        ElementInit, ElementMode, ExportDesc, Expr, FunctionType, HeapType, ImportDesc, Indecies,
+        Limits, ReferenceTypes, SegmentMode, ValueType,
+    },
+    validate, WasmModule,
+};
 
+use crate::error::{Trap, WasmInterpreterError};
+
+mod memory;
+mod numeric;
+mod shared;
+mod simd;
+mod value;
+
+use memory::Memory;
+pub use memory::{HostMemory, PAGE_SIZE};
+pub use shared::SharedMemory;
+pub use simd::{Lane, V128};
+use value::Stack;
+pub use value::{Exception, FromValue, Value};
+
+/// The deepest function calls can nest before the interpreter traps
+pub const MAX_CALL_DEPTH: usize = 100_000;
+
+/// The most elements a table can have, larger tables fail to instantiate and to grow
+pub const MAX_TABLE_ELEMENTS: u64 = 10_000_000;
+
+/// A function the embedder defines for an import. It gets the memory and the arguments,
+/// and returns the results
+pub type HostFunction =
+    Box<dyn FnMut(HostMemory<'_>, &[Value]) -> Result<Vec<Value>, WasmInterpreterError>>;
+
+/// A function of the function index space, imports come first
 #[derive(Debug)]
+enum Function {
+    Imported {
+        module: String,
+        name: String,
+        ty: u32,
+    },
+    Defined {
+        ty: u32,
+        code: usize,
+    },
+}
+
+impl Function {
+    fn ty(&self) -> u32 {
+        match self {
+            Function::Imported { ty, .. } | Function::Defined { ty, .. } => *ty,
+        }
+    }
+}
+
 pub struct WasmEnvironment<'a> {
-    module: WasmModule,
-    memory: &'a mut [u8],
+    module: Rc<WasmModule<'static>>,
+    memory: Memory<'a>,
+    /// The limits the module declares for its memory, which bound how far it can grow
+    memory_limits: Limits,
+    functions: Vec<Function>,
+    globals: Vec<Value>,
+    tables: Vec<Vec<Value>>,
+    /// The references of each element segment, emptied once the segment is dropped
+    elements: Vec<Vec<Value>>,
+    dropped_data: Vec<bool>,
+    /// The function type of each tag, imports come first
+    tags: Vec<u32>,
+    host_functions: HashMap<(String, String), HostFunction>,
+}
+
+impl fmt::Debug for WasmEnvironment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmEnvironment")
+            .field("module", &self.module)
+            .field("memory", &self.memory.len())
+            .field("globals", &self.globals)
+            .field("tables", &self.tables)
+            .field("host_functions", &self.host_functions.keys())
+            .finish_non_exhaustive()
+    }
 }
 
 impl WasmEnvironment<'_> {
-    pub fn new<'a>(module: WasmModule, memory: &'a mut [u8]) -> WasmEnvironment<'a> {
-        WasmEnvironment { module, memory }
+    /// Validates and instantiates the module: globals and tables are created,
+    /// and active element and data segments are copied into the tables and memory.
+    /// The memory is borrowed from the embedder, so `memory.grow` can't grow it and returns -1
+    pub fn new<'a>(
+        module: WasmModule<'static>,
+        memory: &'a mut [u8],
+    ) -> Result<WasmEnvironment<'a>, WasmInterpreterError> {
+        WasmEnvironment::instantiate_with(module, Memory::Borrowed(memory))
+    }
+
+    /// Instantiates the module like [`WasmEnvironment::new`], with a memory that environments
+    /// on other threads can use at the same time. Only a shared memory can be waited on
+    pub fn with_shared_memory(
+        module: WasmModule<'static>,
+        memory: SharedMemory,
+    ) -> Result<WasmEnvironment<'static>, WasmInterpreterError> {
+        WasmEnvironment::instantiate_with(module, Memory::Shared(memory))
+    }
+
+    /// Instantiates the module like [`WasmEnvironment::new`], with a zeroed memory of the minimum size
+    /// the module declares. `memory.grow` can grow it up to the declared maximum
+    pub fn with_owned_memory(
+        module: WasmModule<'static>,
+    ) -> Result<WasmEnvironment<'static>, WasmInterpreterError> {
+        let pages = memory_limits(&module).minimum();
+        let length = usize::try_from(pages)
+            .ok()
+            .and_then(|pages| pages.checked_mul(PAGE_SIZE));
+        let mut memory = Vec::new();
+        match length {
+            Some(length) if memory.try_reserve_exact(length).is_ok() => memory.resize(length, 0),
+            _ => {
+                return Err(WasmInterpreterError::AllocationFailed {
+                    resource: "memory pages",
+                    size: pages,
+                })
+            }
+        }
+        WasmEnvironment::instantiate_with(module, Memory::Owned(memory))
+    }
+
+    fn instantiate_with<'a>(
+        module: WasmModule<'static>,
+        memory: Memory<'a>,
+    ) -> Result<WasmEnvironment<'a>, WasmInterpreterError> {
+        validate(&module)?;
+
+        let sections = &module.sections;
+        let mut functions = Vec::new();
+        let mut tags = Vec::new();
+        for (module_name, name, desc) in &sections.imports {
+            match desc {
+                ImportDesc::TypeIdx(ty) => functions.push(Function::Imported {
+                    module: module_name.to_string(),
+                    name: name.to_string(),
+                    ty: ty.index(),
+                }),
+                // The memory is always the one the environment is created with
+                ImportDesc::MemType(_) => {}
+                // Every instance creates its own tags, the interpreter doesn't link modules
+                ImportDesc::TagType(tag) => tags.push(tag.type_index()),
+                ImportDesc::TableType(_) | ImportDesc::GlobalType(_) => {
+                    return Err(WasmInterpreterError::UnsupportedImport {
+                        module: module_name.to_string(),
+                        name: name.to_string(),
+                    })
+                }
+            }
+        }
+        functions.extend(sections.functions.iter().enumerate().map(|(code, ty)| {
+            Function::Defined {
+                ty: ty.index(),
+                code,
+            }
+        }));
+        tags.extend(sections.tags.iter().map(|tag| tag.type_index()));
+
+        let memory_limits = memory_limits(&module);
+        let tables = sections
+            .tables
+            .iter()
+            .map(|table| {
+                let size = table.limits().minimum();
+                let mut elements = Vec::new();
+                if size > MAX_TABLE_ELEMENTS || elements.try_reserve_exact(size as usize).is_err() {
+                    return Err(WasmInterpreterError::AllocationFailed {
+                        resource: "table elements",
+                        size,
+                    });
+                }
+                let null = Value::default_of(ValueType::RefType(table.element_type()));
+                elements.resize(size as usize, null);
+                Ok(elements)
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut environment = WasmEnvironment {
+            module: Rc::new(module),
+            memory,
+            memory_limits,
+            functions,
+            globals: Vec::new(),
+            tables,
+            elements: Vec::new(),
+            dropped_data: Vec::new(),
+            tags,
+            host_functions: HashMap::new(),
+        };
+        environment.instantiate()?;
+        Ok(environment)
+    }
+
+    /// Defines the function called for the import `module`::`name`
+    pub fn define_function(
+        &mut self,
+        module: &str,
+        name: &str,
+        function: impl FnMut(HostMemory<'_>, &[Value]) -> Result<Vec<Value>, WasmInterpreterError>
+            + 'static,
+    ) {
+        self.host_functions
+            .insert((module.to_string(), name.to_string()), Box::new(function));
+    }
+
+    /// The memory borrowed from the embedder or owned by the environment, `None` if the memory is shared
+    pub fn memory(&self) -> Option<&[u8]> {
+        self.memory.bytes()
+    }
+
+    /// The memory borrowed from the embedder or owned by the environment, `None` if the memory is shared
+    pub fn memory_mut(&mut self) -> Option<&mut [u8]> {
+        self.memory.bytes_mut()
+    }
+
+    /// The memory the environment was instantiated with by [`WasmEnvironment::with_shared_memory`]
+    pub fn shared_memory(&self) -> Option<&SharedMemory> {
+        match &self.memory {
+            Memory::Borrowed(_) | Memory::Owned(_) => None,
+            Memory::Shared(shared) => Some(shared),
+        }
+    }
+
+    fn instantiate(&mut self) -> Result<(), WasmInterpreterError> {
+        let module = Rc::clone(&self.module);
+        let sections = &module.sections;
+
+        for (_, init) in &sections.global {
+            let value = self.evaluate(init)?;
+            self.globals.push(value);
+        }
+
+        for segment in &sections.element {
+            let references = match &segment.init {
+                ElementInit::Functions(functions) => functions
+                    .iter()
+                    .map(|function| Value::FuncRef(Some(function.index())))
+                    .collect(),
+                ElementInit::Expressions(expressions) => expressions
+                    .iter()
+                    .map(|expression| self.evaluate(expression))
+                    .collect::<Result<_, _>>()?,
+            };
+            self.elements.push(references);
+        }
+
+        for (index, segment) in sections.element.iter().enumerate() {
+            match &segment.mode {
+                ElementMode::Passive => continue,
+                ElementMode::Active {
+                    table_index,
+                    offset,
+                } => {
+                    let offset = self.evaluate(offset)?.as_address();
+                    let offset = offset.expect("validated offsets are i32 or i64");
+                    let length = self.elements[index].len() as u64;
+                    self.table_init(*table_index, index as u32, offset, 0, length)?;
+                }
+                ElementMode::Declarative => {}
+            }
+            self.elements[index].clear();
+        }
+
+        for segment in &sections.data {
+            let SegmentMode::Active { offset, .. } = &segment.mode else {
+                self.dropped_data.push(false);
+                continue;
+            };
+            let offset = self.evaluate(offset)?.as_address();
+            let offset = offset.expect("validated offsets are i32 or i64");
+            let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+
+            let memory_len = self.memory.len();
+            if offset.saturating_add(segment.bytes.len()) > memory_len {
+                return Err(WasmInterpreterError::ModifyMemoryOutOfBounds {
+                    offset,
+                    data: segment.bytes.to_vec(),
+                    failed_pos: offset.max(memory_len),
+                    memory_len,
+                });
+            }
+            self.memory.write(offset, &segment.bytes);
+            self.dropped_data.push(true);
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates the constant expression of a global initializer, segment offset or element
+    fn evaluate(&self, expression: &Expr) -> Result<Value, WasmInterpreterError> {
+        let mut stack = Stack::default();
+        for instruction in expression {
+            match instruction {
+                Instructions::GlobalGet(global) => {
+                    stack.push(self.globals[global.index() as usize].clone())
+                }
+                Instructions::RefNull(ty) => stack.push(Value::default_of(ValueType::RefType(*ty))),
+                Instructions::RefFunc(function) => {
+                    stack.push(Value::FuncRef(Some(function.index())))
+                }
+                instruction => {
+                    if !(numeric::execute(instruction, &mut stack)?
+                        || simd::execute(instruction, &mut stack))
+                    {
+                        return Err(WasmInterpreterError::InvalidConstantExpression(
+                            instruction.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(stack.pop_value())
     }
 }
 
 impl WasmEnvironment<'_> {
     /// Executes the entry point 'start' method
     pub fn start(&mut self) -> Result<(), WasmInterpreterError> {
-        // Initialize the memory
-        println!("[DBG] Initialize Memory");
-        for segment in self.module.sections.data.iter() {
-            let (_memory_index, offset) = match &segment.mode {
-                SegmentMode::Passive => (0, 0),
-                SegmentMode::Active {
-                    memory_index,
-                    offset,
-                } => {
-                    let offset: usize = match offset.get(0) {
-                        Some(expr) => match expr {
-                            swai_parser::instructions::Instructions::i32_const(num) => {
-                                *num as usize
-                            }
-                            swai_parser::instructions::Instructions::i64_const(num) => {
-                                *num as usize
-                            }
-
-                            instr => {
-                                return Err(WasmInterpreterError::InvalidMemorySegmentOffset(
-                                    instr.clone(),
-                                ));
-                            }
-                        },
-                        None => 0,
-                    };
+        let Some(start) = &self.module.sections.start else {
+            return Err(WasmInterpreterError::NoEntryPoint);
+        };
+
+        self.call(start.index(), Vec::new())?;
+        Ok(())
+    }
+
+    /// Calls the exported function `name` and returns its results
+    pub fn invoke(
+        &mut self,
+        name: &str,
+        arguments: &[Value],
+    ) -> Result<Vec<Value>, WasmInterpreterError> {
+        let function = self
+            .module
+            .sections
+            .export
+            .iter()
+            .find_map(|(export, desc)| match desc {
+                ExportDesc::FuncIdx(function) if export.as_str() == name => Some(*function),
+                _ => None,
+            })
+            .ok_or_else(|| WasmInterpreterError::UnknownExport(name.to_string()))?;
+
+        self.check_types(self.function_type(function).params(), arguments)?;
+        self.call(function, arguments.to_vec())
+    }
+
+    fn function_type(&self, function: u32) -> &FunctionType {
+        let ty = self.functions[function as usize].ty();
+        &self.module.sections.types[ty as usize]
+    }
 
-                    (*memory_index, offset)
+    /// Checks that values passed into or out of the module have the expected types
+    fn check_types(
+        &self,
+        expected: &[ValueType],
+        values: &[Value],
+    ) -> Result<(), WasmInterpreterError> {
+        let matches = expected.len() == values.len()
+            && values
+                .iter()
+                .zip(expected)
+                .all(|(value, expected)| self.has_type(value, *expected));
+        if !matches {
+            return Err(WasmInterpreterError::TypeMismatch {
+                expected: expected.to_vec(),
+                actual: values.iter().map(Value::ty).collect(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether `value` is of the type `expected`, function references are of the type of their function
+    fn has_type(&self, value: &Value, expected: ValueType) -> bool {
+        let (ValueType::RefType(actual), ValueType::RefType(expected)) = (value.ty(), expected)
+        else {
+            return value.ty() == expected;
+        };
+        let types = &self.module.sections.types;
+        match value {
+            // A null function reference is one of every nullable function type
+            Value::FuncRef(None) => {
+                expected.is_nullable()
+                    && matches!(expected.heap_type(), HeapType::Func | HeapType::TypeIdx(_))
+            }
+            Value::FuncRef(Some(function)) => {
+                self.functions
+                    .get(*function as usize)
+                    .is_some_and(|function| {
+                        ReferenceTypes::new(false, HeapType::TypeIdx(function.ty()))
+                            .matches(expected, types)
+                    })
+            }
+            value if value.is_null() => actual.matches(expected, types),
+            _ => actual.as_non_null().matches(expected, types),
+        }
+    }
+
+    /// Runs a function to completion. Calls don't recurse on the Rust stack,
+    /// every call pushes a frame onto an explicit stack instead
+    fn call(
+        &mut self,
+        function: u32,
+        arguments: Vec<Value>,
+    ) -> Result<Vec<Value>, WasmInterpreterError> {
+        let module = Rc::clone(&self.module);
+        let mut stack = Stack::default();
+        stack.extend(arguments);
+
+        let mut frames = Vec::new();
+        self.enter(&module, function, &mut frames, &mut stack)?;
+        self.run(&module, &mut frames, &mut stack)?;
+        Ok(stack.into_values())
+    }
+
+    /// Calls a function: host functions run right away, a frame is pushed for anything else
+    fn enter<'m>(
+        &mut self,
+        module: &'m WasmModule<'static>,
+        function: u32,
+        frames: &mut Vec<Frame<'m>>,
+        stack: &mut Stack,
+    ) -> Result<(), WasmInterpreterError> {
+        let ty = &module.sections.types[self.functions[function as usize].ty() as usize];
+        let arguments = stack.pop_many(ty.params().len());
+
+        match &self.functions[function as usize] {
+            Function::Imported { module, name, .. } => {
+                let Some(host_function) =
+                    self.host_functions.get_mut(&(module.clone(), name.clone()))
+                else {
+                    return Err(WasmInterpreterError::UndefinedImport {
+                        module: module.clone(),
+                        name: name.clone(),
+                    });
+                };
+
+                let results = host_function(self.memory.for_host(), &arguments)?;
+                self.check_types(ty.results(), &results)?;
+                stack.extend(results);
+            }
+            Function::Defined { code, .. } => {
+                if frames.len() >= MAX_CALL_DEPTH {
+                    return Err(Trap::CallStackExhausted.into());
+                }
+
+                let code = &module.sections.code[*code];
+                let mut values = arguments;
+                for (count, ty) in &code.locals {
+                    values.extend(std::iter::repeat_n(Value::default_of(*ty), *count as usize));
                 }
-            };
 
-            let bytes = segment.bytes.clone();
-
-            for (i, byte) in bytes.iter().enumerate() {
-                let memory_len = self.memory.len();
-                *self.memory.get_mut(offset + i).ok_or(
-                    WasmInterpreterError::ModifyMemoryOutOfBounds {
-                        offset,
-                        data: bytes.clone(),
-                        failed_pos: offset + i,
-                        memory_len,
-                    },
-                )? = *byte;
+                frames.push(Frame {
+                    locals: values,
+                    labels: vec![Label {
+                        instructions: &code.body,
+                        position: 0,
+                        is_loop: false,
+                        arity: ty.results().len(),
+                        height: stack.len(),
+                        catches: &[],
+                    }],
+                });
             }
         }
+        Ok(())
+    }
+
+    /// Replaces the frame of the executing function with one for `function`, which returns in its place.
+    /// The frame doesn't count towards the call depth anymore, so tail calls can recurse indefinitely
+    fn tail_call<'m>(
+        &mut self,
+        module: &'m WasmModule<'static>,
+        function: u32,
+        frames: &mut Vec<Frame<'m>>,
+        stack: &mut Stack,
+    ) -> Result<(), WasmInterpreterError> {
+        let params = self.function_type(function).params().len();
+        let frame = frames.pop().expect("frame of the executing function");
+        // Only the arguments are left of the operands of the returning function
+        stack.unwind(frame.labels[0].height, params);
+        self.enter(module, function, frames, stack)
+    }
 
-        let Some(start_fn_index) = &self.module.sections.start else {
-        	return Err(WasmInterpreterError::NoEntryPoint);
+    /// Pops the table index of `call_indirect` or `return_call_indirect` and returns the function there
+    fn indirect_callee(
+        &self,
+        module: &WasmModule,
+        ty: &Indecies,
+        table: &Indecies,
+        stack: &mut Stack,
+    ) -> Result<u32, Trap> {
+        let index = stack.pop_address() as usize;
+        let function = match self.tables[table.index() as usize].get(index) {
+            None => return Err(Trap::UndefinedElement),
+            Some(Value::FuncRef(Some(function))) => *function,
+            Some(_) => return Err(Trap::UninitializedElement),
         };
+        if self.function_type(function) != &module.sections.types[ty.index() as usize] {
+            return Err(Trap::IndirectCallTypeMismatch);
+        }
+        Ok(function)
+    }
+
+    /// Executes instructions until the frames that were entered have all returned
+    fn run<'m>(
+        &mut self,
+        module: &'m WasmModule<'static>,
+        frames: &mut Vec<Frame<'m>>,
+        stack: &mut Stack,
+    ) -> Result<(), WasmInterpreterError> {
+        use Instructions::*;
+
+        while let Some(frame) = frames.last_mut() {
+            let Some(label) = frame.labels.last_mut() else {
+                frames.pop();
+                continue;
+            };
+            let Some(instruction) = label.instructions.get(label.position) else {
+                // Falling off the end of a block leaves exactly its results on the stack
+                frame.labels.pop();
+                continue;
+            };
+            label.position += 1;
+
+            match instruction {
+                Unreachable => return Err(Trap::Unreachable.into()),
+                Nop => {}
+                Block { ty, body } => frame.enter(body, false, block_arity(module, ty), &[], stack),
+                Loop { ty, body } => frame.enter(body, true, block_arity(module, ty), &[], stack),
+                If { ty, then, else_ } => {
+                    let body = if stack.pop::<i32>() != 0 { then } else { else_ };
+                    frame.enter(body, false, block_arity(module, ty), &[], stack);
+                }
+                Br(label) => frame.branch(label.index(), stack),
+                BrIf(label) => {
+                    if stack.pop::<i32>() != 0 {
+                        frame.branch(label.index(), stack);
+                    }
+                }
+                BrTable(labels, default) => {
+                    let index = stack.pop::<i32>() as u32 as usize;
+                    frame.branch(labels.get(index).unwrap_or(default).index(), stack);
+                }
+                Return => frame.branch(frame.labels.len() as u32 - 1, stack),
+                Call(function) => self.enter(module, function.index(), frames, stack)?,
+                CallIndirect(ty, table) => {
+                    let function = self.indirect_callee(module, ty, table, stack)?;
+                    self.enter(module, function, frames, stack)?;
+                }
+                ReturnCall(function) => self.tail_call(module, function.index(), frames, stack)?,
+                ReturnCallIndirect(ty, table) => {
+                    let function = self.indirect_callee(module, ty, table, stack)?;
+                    self.tail_call(module, function, frames, stack)?;
+                }
+                CallRef(_) => match stack.pop_value() {
+                    Value::FuncRef(Some(function)) => {
+                        self.enter(module, function, frames, stack)?
+                    }
+                    _ => return Err(Trap::NullFunctionReference.into()),
+                },
+                ReturnCallRef(_) => match stack.pop_value() {
+                    Value::FuncRef(Some(function)) => {
+                        self.tail_call(module, function, frames, stack)?
+                    }
+                    _ => return Err(Trap::NullFunctionReference.into()),
+                },
+                BrOnNull(label) => {
+                    let reference = stack.pop_value();
+                    if reference.is_null() {
+                        frame.branch(label.index(), stack);
+                    } else {
+                        stack.push(reference);
+                    }
+                }
+                BrOnNonNull(label) => {
+                    let reference = stack.pop_value();
+                    if !reference.is_null() {
+                        stack.push(reference);
+                        frame.branch(label.index(), stack);
+                    }
+                }
+
+                // Exception Handling Instructions
+                Throw(tag) => {
+                    let ty = &module.sections.types[self.tags[tag.index() as usize] as usize];
+                    let payload = stack.pop_many(ty.params().len());
+                    let exception = Arc::new(Exception::new(tag.index(), payload));
+                    self.throw(exception, frames, stack)?;
+                }
+                ThrowRef => match stack.pop_value() {
+                    Value::ExnRef(Some(exception)) => self.throw(exception, frames, stack)?,
+                    _ => return Err(Trap::NullExceptionReference.into()),
+                },
+                TryTable { ty, catches, body } => {
+                    frame.enter(body, false, block_arity(module, ty), catches, stack)
+                }
+
+                // Reference Instructions
+                RefNull(ty) => stack.push(Value::default_of(ValueType::RefType(*ty))),
+                RefIsNull => {
+                    let reference = stack.pop_value();
+                    stack.push(i32::from(reference.is_null()));
+                }
+                RefFunc(function) => stack.push(Value::FuncRef(Some(function.index()))),
+                RefAsNonNull => {
+                    let reference = stack.pop_value();
+                    if reference.is_null() {
+                        return Err(Trap::NullReference.into());
+                    }
+                    stack.push(reference);
+                }
+
+                // Parametric Instructions
+                Drop => {
+                    stack.pop_value();
+                }
+                Select | SelectMultiple(_) => {
+                    let condition = stack.pop::<i32>();
+                    let b = stack.pop_value();
+                    let a = stack.pop_value();
+                    stack.push(if condition != 0 { a } else { b });
+                }
+
+                // Variable Instructions
+                LocalGet(local) => stack.push(frame.locals[local.index() as usize].clone()),
+                LocalSet(local) => frame.locals[local.index() as usize] = stack.pop_value(),
+                LocalTee(local) => {
+                    let value = stack.pop_value();
+                    frame.locals[local.index() as usize] = value.clone();
+                    stack.push(value);
+                }
+                GlobalGet(global) => stack.push(self.globals[global.index() as usize].clone()),
+                GlobalSet(global) => self.globals[global.index() as usize] = stack.pop_value(),
+
+                // Table Instructions
+                TableGet(table) => {
+                    let index = stack.pop_address() as usize;
+                    let Some(reference) = self.tables[table.index() as usize].get(index) else {
+                        return Err(Trap::OutOfBoundsTableAccess.into());
+                    };
+                    stack.push(reference.clone());
+                }
+                TableSet(table) => {
+                    let reference = stack.pop_value();
+                    let index = stack.pop_address() as usize;
+                    let Some(slot) = self.tables[table.index() as usize].get_mut(index) else {
+                        return Err(Trap::OutOfBoundsTableAccess.into());
+                    };
+                    *slot = reference;
+                }
+                TableInit(element, table) => {
+                    let length = stack.pop::<i32>() as u32 as u64;
+                    let source = stack.pop::<i32>() as u32 as u64;
+                    let destination = stack.pop_address();
+                    self.table_init(table.index(), element.index(), destination, source, length)?;
+                }
+                ElemDrop(element) => self.elements[element.index() as usize].clear(),
+                TableCopy(destination_table, source_table) => {
+                    let length = stack.pop_address();
+                    let source = stack.pop_address();
+                    let destination = stack.pop_address();
+                    let source =
+                        table_range(&self.tables[source_table.index() as usize], source, length)?;
+                    let destination = table_range(
+                        &self.tables[destination_table.index() as usize],
+                        destination,
+                        length,
+                    )?;
+                    let references = self.tables[source_table.index() as usize][source].to_vec();
+                    self.tables[destination_table.index() as usize][destination]
+                        .clone_from_slice(&references);
+                }
+                TableGrow(table) => {
+                    let delta = stack.pop_address();
+                    let reference = stack.pop_value();
+                    let limits = module.sections.tables[table.index() as usize].limits();
+                    let maximum = limits
+                        .maximum()
+                        .unwrap_or(MAX_TABLE_ELEMENTS)
+                        .min(MAX_TABLE_ELEMENTS);
+                    let elements = &mut self.tables[table.index() as usize];
+                    let size = elements.len() as u64;
+                    match size.checked_add(delta) {
+                        Some(new_size)
+                            if new_size <= maximum
+                                && elements.try_reserve_exact(delta as usize).is_ok() =>
+                        {
+                            elements.resize(new_size as usize, reference);
+                            stack.push(Value::address(size, limits.index_type()));
+                        }
+                        _ => stack.push(Value::address(u64::MAX, limits.index_type())),
+                    }
+                }
+                TableSize(table) => {
+                    let size = self.tables[table.index() as usize].len() as u64;
+                    let limits = module.sections.tables[table.index() as usize].limits();
+                    stack.push(Value::address(size, limits.index_type()));
+                }
+                TableFill(table) => {
+                    let length = stack.pop_address();
+                    let reference = stack.pop_value();
+                    let start = stack.pop_address();
+                    let elements = &mut self.tables[table.index() as usize];
+                    let range = table_range(elements, start, length)?;
+                    elements[range].fill(reference);
+                }
+
+                // Memory Instructions
+                MemoryInit(data) => {
+                    let length = stack.pop::<i32>() as u32 as u64;
+                    let source = stack.pop::<i32>() as u32 as u64;
+                    let destination = stack.pop_address();
+                    let bytes: &[u8] = match self.dropped_data[data.index() as usize] {
+                        true => &[],
+                        false => &module.sections.data[data.index() as usize].bytes,
+                    };
+                    let source = memory::range(bytes.len(), source, length)?;
+                    let destination = memory::range(self.memory.len(), destination, length)?;
+                    self.memory.write(destination.start, &bytes[source]);
+                }
+                DataDrop(data) => self.dropped_data[data.index() as usize] = true,
+
+                instruction => {
+                    let executed =
+                        memory::execute(instruction, &mut self.memory, &self.memory_limits, stack)?
+                            || numeric::execute(instruction, stack)?
+                            || simd::execute(instruction, stack);
+                    if !executed {
+                        return Err(WasmInterpreterError::UnsupportedInstruction(
+                            instruction.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Unwinds the frames and labels up to the innermost `try_table` with a catch clause
+    /// for the exception, and branches to the clause's label
+    fn throw(
+        &self,
+        exception: Arc<Exception>,
+        frames: &mut Vec<Frame>,
+        stack: &mut Stack,
+    ) -> Result<(), Trap> {
+        let tag = exception.tag();
+
+        while let Some(frame) = frames.last_mut() {
+            let handler = frame
+                .labels
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(index, label)| {
+                    let catch = label.catches.iter().find(|catch| {
+                        catch.tag().is_none_or(|catch_tag| catch_tag.index() == tag)
+                    })?;
+                    Some((index, label.height, catch))
+                });
+            let Some((index, height, catch)) = handler else {
+                frames.pop();
+                continue;
+            };
+
+            // The labels of the catch clauses are outside of the try_table
+            stack.unwind(height, 0);
+            frame.labels.truncate(index);
+            if catch.tag().is_some() {
+                stack.extend(exception.payload().iter().cloned());
+            }
+            if catch.is_ref() {
+                stack.push(Value::ExnRef(Some(exception)));
+            }
+            frame.branch(catch.label().index(), stack);
+            return Ok(());
+        }
 
-        let Some(start_fn) = start_fn_index.get_function(&self.module.sections) else {
-			return Err(WasmInterpreterError::NoEntryPoint);
-		};
+        Err(Trap::UncaughtException {
+            tag,
+            payload: exception.payload().to_vec(),
+        })
+    }
 
+    /// Copies `length` references of an element segment into a table
+    fn table_init(
+        &mut self,
+        table: u32,
+        element: u32,
+        destination: u64,
+        source: u64,
+        length: u64,
+    ) -> Result<(), Trap> {
+        let references = &self.elements[element as usize];
+        let elements = &mut self.tables[table as usize];
+        let source = table_range(references, source, length)?;
+        let destination = table_range(elements, destination, length)?;
+        elements[destination].clone_from_slice(&references[source]);
         Ok(())
     }
 }
+
+/// A function call that hasn't returned yet
+struct Frame<'m> {
+    locals: Vec<Value>,
+    /// The blocks being executed, the body of the function itself is the outermost
+    labels: Vec<Label<'m>>,
+}
+
+/// A block, loop, if or function body being executed
+struct Label<'m> {
+    instructions: &'m [Instructions],
+    /// The next instruction to execute
+    position: usize,
+    /// Branching to a loop continues at its start instead of after its end
+    is_loop: bool,
+    /// The number of values a branch to the label carries
+    arity: usize,
+    /// The height of the operand stack below the params of the block
+    height: usize,
+    /// The catch clauses of a `try_table`, empty for any other block
+    catches: &'m [Catch],
+}
+
+impl<'m> Frame<'m> {
+    /// Enters a block that takes `params` values from the stack and leaves `results` values on it
+    fn enter(
+        &mut self,
+        instructions: &'m [Instructions],
+        is_loop: bool,
+        (params, results): (usize, usize),
+        catches: &'m [Catch],
+        stack: &Stack,
+    ) {
+        self.labels.push(Label {
+            instructions,
+            position: 0,
+            is_loop,
+            // Branching to a loop starts it again, so the branch carries its params
+            arity: if is_loop { params } else { results },
+            height: stack.len() - params,
+            catches,
+        });
+    }
+
+    /// Branches to the label `depth` blocks out, keeping the values it carries on the stack.
+    /// Branching to the function body returns from the function
+    fn branch(&mut self, depth: u32, stack: &mut Stack) {
+        let index = self.labels.len() - 1 - depth as usize;
+        let label = &mut self.labels[index];
+        stack.unwind(label.height, label.arity);
+
+        if label.is_loop {
+            label.position = 0;
+            self.labels.truncate(index + 1);
+        } else {
+            self.labels.truncate(index);
+        }
+    }
+}
+
+/// The limits of the module's imported or defined memory, a memory of 0 pages if it has none
+fn memory_limits(module: &WasmModule) -> Limits {
+    let imported = module
+        .sections
+        .imports
+        .iter()
+        .find_map(|(_, _, desc)| match desc {
+            ImportDesc::MemType(memory) => Some(memory),
+            _ => None,
+        });
+    imported
+        .or(module.sections.memory.first())
+        .cloned()
+        .unwrap_or(Limits::new(0, Some(0)))
+}
+
+/// The number of params and results of a block
+fn block_arity(module: &WasmModule, ty: &BlockType) -> (usize, usize) {
+    match ty {
+        BlockType::Empty => (0, 0),
+        BlockType::Value(_) => (0, 1),
+        BlockType::TypeIndex(index) => {
+            let ty = &module.sections.types[*index as usize];
+            (ty.params().len(), ty.results().len())
+        }
+    }
+}
+
+/// The references `length` references starting at `start` occupy, or a trap if they aren't all inside the table
+fn table_range(
+    elements: &[Value],
+    start: u64,
+    length: u64,
+) -> Result<std::ops::Range<usize>, Trap> {
+    match start.checked_add(length) {
+        Some(end) if end <= elements.len() as u64 => Ok(start as usize..end as usize),
+        _ => Err(Trap::OutOfBoundsTableAccess),
+    }
+}
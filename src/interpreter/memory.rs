@@ -0,0 +1,600 @@
+use std::{
+    array,
+    ops::{BitAnd, BitOr, BitXor, Range},
+    sync::atomic::{self, Ordering},
+    time::Duration,
+};
+
+use swai_parser::{
+    instructions::{Instructions, MemArg},
+    types::{Limits, NumberTypes},
+    validation::{MAX_MEMORY64_PAGES, MAX_MEMORY_PAGES},
+};
+
+use super::{
+    shared::SharedMemory,
+    simd::{Lane, V128},
+    value::{FromValue, Stack, Value},
+};
+use crate::error::Trap;
+
+/// The size of a WebAssembly page in bytes
+pub const PAGE_SIZE: usize = 65536;
+
+/// The linear memory of an environment
+pub(super) enum Memory<'a> {
+    /// Memory borrowed from the embedder, no other thread can access it. It can't grow
+    Borrowed(&'a mut [u8]),
+    /// Memory allocated by the environment, which can grow up to the declared maximum
+    Owned(Vec<u8>),
+    Shared(SharedMemory),
+}
+
+impl Memory<'_> {
+    pub fn len(&self) -> usize {
+        match self {
+            Memory::Borrowed(bytes) => bytes.len(),
+            Memory::Owned(bytes) => bytes.len(),
+            Memory::Shared(shared) => shared.len(),
+        }
+    }
+
+    /// The bytes of a memory that no other thread can access
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            Memory::Borrowed(bytes) => Some(bytes),
+            Memory::Owned(bytes) => Some(bytes),
+            Memory::Shared(_) => None,
+        }
+    }
+
+    pub fn bytes_mut(&mut self) -> Option<&mut [u8]> {
+        match self {
+            Memory::Borrowed(bytes) => Some(bytes),
+            Memory::Owned(bytes) => Some(bytes),
+            Memory::Shared(_) => None,
+        }
+    }
+
+    /// Grows the memory by `delta` zeroed pages, unless it would get larger than `maximum` pages
+    /// or the pages can't be allocated. Returns the previous size in pages
+    fn grow(&mut self, delta: u64, maximum: u64) -> Option<u64> {
+        let pages = pages(self);
+        match self {
+            Memory::Borrowed(_) => (delta == 0).then_some(pages),
+            Memory::Owned(bytes) => {
+                let new_pages = pages.checked_add(delta).filter(|&pages| pages <= maximum)?;
+                let new_len = usize::try_from(new_pages).ok()?.checked_mul(PAGE_SIZE)?;
+                bytes.try_reserve_exact(new_len - bytes.len()).ok()?;
+                bytes.resize(new_len, 0);
+                Some(pages)
+            }
+            Memory::Shared(shared) => shared.grow(delta, maximum),
+        }
+    }
+
+    /// The `N` bytes starting at `start`, which have to be inside the memory
+    fn read<const N: usize>(&self, start: usize) -> [u8; N] {
+        match self {
+            Memory::Borrowed(bytes) => bytes[start..start + N].try_into().unwrap(),
+            Memory::Owned(bytes) => bytes[start..start + N].try_into().unwrap(),
+            Memory::Shared(shared) => {
+                let mut bytes = [0; N];
+                shared.read(start, &mut bytes);
+                bytes
+            }
+        }
+    }
+
+    pub fn write(&mut self, start: usize, source: &[u8]) {
+        match self {
+            Memory::Borrowed(bytes) => bytes[start..start + source.len()].copy_from_slice(source),
+            Memory::Owned(bytes) => bytes[start..start + source.len()].copy_from_slice(source),
+            Memory::Shared(shared) => shared.write(start, source),
+        }
+    }
+
+    fn copy_within(&mut self, source: Range<usize>, destination: usize) {
+        match self {
+            Memory::Borrowed(bytes) => bytes.copy_within(source, destination),
+            Memory::Owned(bytes) => bytes.copy_within(source, destination),
+            Memory::Shared(shared) => {
+                let mut bytes = vec![0; source.len()];
+                shared.read(source.start, &mut bytes);
+                shared.write(destination, &bytes);
+            }
+        }
+    }
+
+    fn fill(&mut self, range: Range<usize>, value: u8) {
+        match self {
+            Memory::Borrowed(bytes) => bytes[range].fill(value),
+            Memory::Owned(bytes) => bytes[range].fill(value),
+            Memory::Shared(shared) => shared.write(range.start, &vec![value; range.len()]),
+        }
+    }
+
+    /// Runs `access` while no other thread can make an atomic access to the memory
+    fn atomically<T>(&mut self, access: impl FnOnce(&mut Self) -> T) -> T {
+        match self {
+            Memory::Borrowed(_) | Memory::Owned(_) => access(self),
+            Memory::Shared(shared) => {
+                let shared = shared.clone();
+                let _guard = shared.lock();
+                access(self)
+            }
+        }
+    }
+
+    /// The view of the memory a host function is called with
+    pub fn for_host(&mut self) -> HostMemory<'_> {
+        match self {
+            Memory::Borrowed(bytes) => HostMemory::Borrowed(bytes),
+            Memory::Owned(bytes) => HostMemory::Borrowed(bytes),
+            Memory::Shared(shared) => HostMemory::Shared(shared),
+        }
+    }
+}
+
+/// The memory of the environment, as host functions get it
+pub enum HostMemory<'m> {
+    Borrowed(&'m mut [u8]),
+    /// Other threads can access the memory while the host function runs, so it's only accessed through the handle
+    Shared(&'m SharedMemory),
+}
+
+impl HostMemory<'_> {
+    pub fn len(&self) -> usize {
+        match self {
+            HostMemory::Borrowed(bytes) => bytes.len(),
+            HostMemory::Shared(shared) => shared.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copies the bytes starting at `offset` into `bytes`, traps if they aren't all inside the memory
+    pub fn read(&self, offset: usize, bytes: &mut [u8]) -> Result<(), Trap> {
+        let source = range(self.len(), offset as u64, bytes.len() as u64)?;
+        match self {
+            HostMemory::Borrowed(memory) => bytes.copy_from_slice(&memory[source]),
+            HostMemory::Shared(shared) => shared.read(source.start, bytes),
+        }
+        Ok(())
+    }
+
+    /// Copies `bytes` into the memory starting at `offset`, traps if they don't all fit inside it
+    pub fn write(&mut self, offset: usize, bytes: &[u8]) -> Result<(), Trap> {
+        let destination = range(self.len(), offset as u64, bytes.len() as u64)?;
+        match self {
+            HostMemory::Borrowed(memory) => memory[destination].copy_from_slice(bytes),
+            HostMemory::Shared(shared) => shared.write(destination.start, bytes),
+        }
+        Ok(())
+    }
+}
+
+/// Executes the loads, stores and the instructions that only operate on the memory,
+/// returns `false` for any other instruction. Sizes are pushed as `index_type`, the type of the memory's addresses
+pub(super) fn execute(
+    instruction: &Instructions,
+    memory: &mut Memory,
+    limits: &Limits,
+    stack: &mut Stack,
+) -> Result<bool, Trap> {
+    use Instructions::*;
+
+    let index_type = limits.index_type();
+
+    match instruction {
+        i32_load(memarg) => load(memory, stack, memarg, i32::from_le_bytes)?,
+        i64_load(memarg) => load(memory, stack, memarg, i64::from_le_bytes)?,
+        f32_load(memarg) => load(memory, stack, memarg, f32::from_le_bytes)?,
+        f64_load(memarg) => load(memory, stack, memarg, f64::from_le_bytes)?,
+        i32_load_8s(memarg) => load(memory, stack, memarg, |b| i8::from_le_bytes(b) as i32)?,
+        i32_load_8u(memarg) => load(memory, stack, memarg, |b| u8::from_le_bytes(b) as i32)?,
+        i32_load_16s(memarg) => load(memory, stack, memarg, |b| i16::from_le_bytes(b) as i32)?,
+        i32_load_16u(memarg) => load(memory, stack, memarg, |b| u16::from_le_bytes(b) as i32)?,
+        i64_load_8s(memarg) => load(memory, stack, memarg, |b| i8::from_le_bytes(b) as i64)?,
+        i64_load_8u(memarg) => load(memory, stack, memarg, |b| u8::from_le_bytes(b) as i64)?,
+        i64_load_16s(memarg) => load(memory, stack, memarg, |b| i16::from_le_bytes(b) as i64)?,
+        i64_load_16u(memarg) => load(memory, stack, memarg, |b| u16::from_le_bytes(b) as i64)?,
+        i64_load_32s(memarg) => load(memory, stack, memarg, |b| i32::from_le_bytes(b) as i64)?,
+        i64_load_32u(memarg) => load(memory, stack, memarg, |b| u32::from_le_bytes(b) as i64)?,
+
+        i32_store(memarg) => store(memory, stack, memarg, |v: i32| v.to_le_bytes())?,
+        i64_store(memarg) => store(memory, stack, memarg, |v: i64| v.to_le_bytes())?,
+        f32_store(memarg) => store(memory, stack, memarg, |v: f32| v.to_le_bytes())?,
+        f64_store(memarg) => store(memory, stack, memarg, |v: f64| v.to_le_bytes())?,
+        i32_store_8(memarg) => store(memory, stack, memarg, |v: i32| (v as u8).to_le_bytes())?,
+        i32_store_16(memarg) => store(memory, stack, memarg, |v: i32| (v as u16).to_le_bytes())?,
+        i64_store_8(memarg) => store(memory, stack, memarg, |v: i64| (v as u8).to_le_bytes())?,
+        i64_store_16(memarg) => store(memory, stack, memarg, |v: i64| (v as u16).to_le_bytes())?,
+        i64_store_32(memarg) => store(memory, stack, memarg, |v: i64| (v as u32).to_le_bytes())?,
+
+        MemorySize => stack.push(Value::address(pages(memory), index_type)),
+        MemoryGrow => {
+            let maximum = limits.maximum().unwrap_or(match index_type {
+                NumberTypes::i64 => MAX_MEMORY64_PAGES,
+                _ => MAX_MEMORY_PAGES.into(),
+            });
+            let pages = memory.grow(stack.pop_address(), maximum);
+            stack.push(Value::address(pages.unwrap_or(u64::MAX), index_type));
+        }
+        MemoryCopy => {
+            let length = stack.pop_address();
+            let source = range(memory.len(), stack.pop_address(), length)?;
+            let destination = range(memory.len(), stack.pop_address(), length)?;
+            memory.copy_within(source, destination.start);
+        }
+        MemoryFill => {
+            let length = stack.pop_address();
+            let value = stack.pop::<i32>() as u8;
+            let destination = range(memory.len(), stack.pop_address(), length)?;
+            memory.fill(destination, value);
+        }
+
+        // Vector Memory Instructions
+        v128_load(memarg) => load(memory, stack, memarg, V128::from_bytes)?,
+        v128_load8x8_s(memarg) => load(memory, stack, memarg, widen::<i8, i16, 8, 8>)?,
+        v128_load8x8_u(memarg) => load(memory, stack, memarg, widen::<u8, u16, 8, 8>)?,
+        v128_load16x4_s(memarg) => load(memory, stack, memarg, widen::<i16, i32, 8, 4>)?,
+        v128_load16x4_u(memarg) => load(memory, stack, memarg, widen::<u16, u32, 8, 4>)?,
+        v128_load32x2_s(memarg) => load(memory, stack, memarg, widen::<i32, i64, 8, 2>)?,
+        v128_load32x2_u(memarg) => load(memory, stack, memarg, widen::<u32, u64, 8, 2>)?,
+        v128_load8_splat(memarg) => load(memory, stack, memarg, |b: [u8; 1]| {
+            V128::from_lanes([b[0]; 16])
+        })?,
+        v128_load16_splat(memarg) => load(memory, stack, memarg, |b| {
+            V128::from_lanes([u16::from_le_bytes(b); 8])
+        })?,
+        v128_load32_splat(memarg) => load(memory, stack, memarg, |b| {
+            V128::from_lanes([u32::from_le_bytes(b); 4])
+        })?,
+        v128_load64_splat(memarg) => load(memory, stack, memarg, |b| {
+            V128::from_lanes([u64::from_le_bytes(b); 2])
+        })?,
+        v128_load32_zero(memarg) => load(memory, stack, memarg, |b| {
+            V128::from_bits(u32::from_le_bytes(b) as u128)
+        })?,
+        v128_load64_zero(memarg) => load(memory, stack, memarg, |b| {
+            V128::from_bits(u64::from_le_bytes(b) as u128)
+        })?,
+        v128_store(memarg) => store(memory, stack, memarg, V128::to_bytes)?,
+
+        v128_load8_lane(memarg, lane) => load_lane::<1>(memory, stack, memarg, *lane)?,
+        v128_load16_lane(memarg, lane) => load_lane::<2>(memory, stack, memarg, *lane)?,
+        v128_load32_lane(memarg, lane) => load_lane::<4>(memory, stack, memarg, *lane)?,
+        v128_load64_lane(memarg, lane) => load_lane::<8>(memory, stack, memarg, *lane)?,
+        v128_store8_lane(memarg, lane) => store_lane::<1>(memory, stack, memarg, *lane)?,
+        v128_store16_lane(memarg, lane) => store_lane::<2>(memory, stack, memarg, *lane)?,
+        v128_store32_lane(memarg, lane) => store_lane::<4>(memory, stack, memarg, *lane)?,
+        v128_store64_lane(memarg, lane) => store_lane::<8>(memory, stack, memarg, *lane)?,
+
+        // Atomic Memory Instructions
+        memory_atomic_notify(memarg) => notify(memory, stack, memarg)?,
+        memory_atomic_wait32(memarg) => wait::<i32, 4>(memory, stack, memarg)?,
+        memory_atomic_wait64(memarg) => wait::<i64, 8>(memory, stack, memarg)?,
+        atomic_fence => atomic::fence(Ordering::SeqCst),
+        i32_atomic_load(memarg) => atomic_load::<i32, 4>(memory, stack, memarg)?,
+        i64_atomic_load(memarg) => atomic_load::<i64, 8>(memory, stack, memarg)?,
+        i32_atomic_load8_u(memarg) => atomic_load::<i32, 1>(memory, stack, memarg)?,
+        i32_atomic_load16_u(memarg) => atomic_load::<i32, 2>(memory, stack, memarg)?,
+        i64_atomic_load8_u(memarg) => atomic_load::<i64, 1>(memory, stack, memarg)?,
+        i64_atomic_load16_u(memarg) => atomic_load::<i64, 2>(memory, stack, memarg)?,
+        i64_atomic_load32_u(memarg) => atomic_load::<i64, 4>(memory, stack, memarg)?,
+        i32_atomic_store(memarg) => atomic_store::<i32, 4>(memory, stack, memarg)?,
+        i64_atomic_store(memarg) => atomic_store::<i64, 8>(memory, stack, memarg)?,
+        i32_atomic_store8(memarg) => atomic_store::<i32, 1>(memory, stack, memarg)?,
+        i32_atomic_store16(memarg) => atomic_store::<i32, 2>(memory, stack, memarg)?,
+        i64_atomic_store8(memarg) => atomic_store::<i64, 1>(memory, stack, memarg)?,
+        i64_atomic_store16(memarg) => atomic_store::<i64, 2>(memory, stack, memarg)?,
+        i64_atomic_store32(memarg) => atomic_store::<i64, 4>(memory, stack, memarg)?,
+        i32_atomic_rmw_add(memarg) => rmw::<i32, 4>(memory, stack, memarg, u64::wrapping_add)?,
+        i64_atomic_rmw_add(memarg) => rmw::<i64, 8>(memory, stack, memarg, u64::wrapping_add)?,
+        i32_atomic_rmw8_add_u(memarg) => rmw::<i32, 1>(memory, stack, memarg, u64::wrapping_add)?,
+        i32_atomic_rmw16_add_u(memarg) => rmw::<i32, 2>(memory, stack, memarg, u64::wrapping_add)?,
+        i64_atomic_rmw8_add_u(memarg) => rmw::<i64, 1>(memory, stack, memarg, u64::wrapping_add)?,
+        i64_atomic_rmw16_add_u(memarg) => rmw::<i64, 2>(memory, stack, memarg, u64::wrapping_add)?,
+        i64_atomic_rmw32_add_u(memarg) => rmw::<i64, 4>(memory, stack, memarg, u64::wrapping_add)?,
+        i32_atomic_rmw_sub(memarg) => rmw::<i32, 4>(memory, stack, memarg, u64::wrapping_sub)?,
+        i64_atomic_rmw_sub(memarg) => rmw::<i64, 8>(memory, stack, memarg, u64::wrapping_sub)?,
+        i32_atomic_rmw8_sub_u(memarg) => rmw::<i32, 1>(memory, stack, memarg, u64::wrapping_sub)?,
+        i32_atomic_rmw16_sub_u(memarg) => rmw::<i32, 2>(memory, stack, memarg, u64::wrapping_sub)?,
+        i64_atomic_rmw8_sub_u(memarg) => rmw::<i64, 1>(memory, stack, memarg, u64::wrapping_sub)?,
+        i64_atomic_rmw16_sub_u(memarg) => rmw::<i64, 2>(memory, stack, memarg, u64::wrapping_sub)?,
+        i64_atomic_rmw32_sub_u(memarg) => rmw::<i64, 4>(memory, stack, memarg, u64::wrapping_sub)?,
+        i32_atomic_rmw_and(memarg) => rmw::<i32, 4>(memory, stack, memarg, u64::bitand)?,
+        i64_atomic_rmw_and(memarg) => rmw::<i64, 8>(memory, stack, memarg, u64::bitand)?,
+        i32_atomic_rmw8_and_u(memarg) => rmw::<i32, 1>(memory, stack, memarg, u64::bitand)?,
+        i32_atomic_rmw16_and_u(memarg) => rmw::<i32, 2>(memory, stack, memarg, u64::bitand)?,
+        i64_atomic_rmw8_and_u(memarg) => rmw::<i64, 1>(memory, stack, memarg, u64::bitand)?,
+        i64_atomic_rmw16_and_u(memarg) => rmw::<i64, 2>(memory, stack, memarg, u64::bitand)?,
+        i64_atomic_rmw32_and_u(memarg) => rmw::<i64, 4>(memory, stack, memarg, u64::bitand)?,
+        i32_atomic_rmw_or(memarg) => rmw::<i32, 4>(memory, stack, memarg, u64::bitor)?,
+        i64_atomic_rmw_or(memarg) => rmw::<i64, 8>(memory, stack, memarg, u64::bitor)?,
+        i32_atomic_rmw8_or_u(memarg) => rmw::<i32, 1>(memory, stack, memarg, u64::bitor)?,
+        i32_atomic_rmw16_or_u(memarg) => rmw::<i32, 2>(memory, stack, memarg, u64::bitor)?,
+        i64_atomic_rmw8_or_u(memarg) => rmw::<i64, 1>(memory, stack, memarg, u64::bitor)?,
+        i64_atomic_rmw16_or_u(memarg) => rmw::<i64, 2>(memory, stack, memarg, u64::bitor)?,
+        i64_atomic_rmw32_or_u(memarg) => rmw::<i64, 4>(memory, stack, memarg, u64::bitor)?,
+        i32_atomic_rmw_xor(memarg) => rmw::<i32, 4>(memory, stack, memarg, u64::bitxor)?,
+        i64_atomic_rmw_xor(memarg) => rmw::<i64, 8>(memory, stack, memarg, u64::bitxor)?,
+        i32_atomic_rmw8_xor_u(memarg) => rmw::<i32, 1>(memory, stack, memarg, u64::bitxor)?,
+        i32_atomic_rmw16_xor_u(memarg) => rmw::<i32, 2>(memory, stack, memarg, u64::bitxor)?,
+        i64_atomic_rmw8_xor_u(memarg) => rmw::<i64, 1>(memory, stack, memarg, u64::bitxor)?,
+        i64_atomic_rmw16_xor_u(memarg) => rmw::<i64, 2>(memory, stack, memarg, u64::bitxor)?,
+        i64_atomic_rmw32_xor_u(memarg) => rmw::<i64, 4>(memory, stack, memarg, u64::bitxor)?,
+        i32_atomic_rmw_xchg(memarg) => rmw::<i32, 4>(memory, stack, memarg, |_, operand| operand)?,
+        i64_atomic_rmw_xchg(memarg) => rmw::<i64, 8>(memory, stack, memarg, |_, operand| operand)?,
+        i32_atomic_rmw8_xchg_u(memarg) => {
+            rmw::<i32, 1>(memory, stack, memarg, |_, operand| operand)?
+        }
+        i32_atomic_rmw16_xchg_u(memarg) => {
+            rmw::<i32, 2>(memory, stack, memarg, |_, operand| operand)?
+        }
+        i64_atomic_rmw8_xchg_u(memarg) => {
+            rmw::<i64, 1>(memory, stack, memarg, |_, operand| operand)?
+        }
+        i64_atomic_rmw16_xchg_u(memarg) => {
+            rmw::<i64, 2>(memory, stack, memarg, |_, operand| operand)?
+        }
+        i64_atomic_rmw32_xchg_u(memarg) => {
+            rmw::<i64, 4>(memory, stack, memarg, |_, operand| operand)?
+        }
+        i32_atomic_rmw_cmpxchg(memarg) => cmpxchg::<i32, 4>(memory, stack, memarg)?,
+        i64_atomic_rmw_cmpxchg(memarg) => cmpxchg::<i64, 8>(memory, stack, memarg)?,
+        i32_atomic_rmw8_cmpxchg_u(memarg) => cmpxchg::<i32, 1>(memory, stack, memarg)?,
+        i32_atomic_rmw16_cmpxchg_u(memarg) => cmpxchg::<i32, 2>(memory, stack, memarg)?,
+        i64_atomic_rmw8_cmpxchg_u(memarg) => cmpxchg::<i64, 1>(memory, stack, memarg)?,
+        i64_atomic_rmw16_cmpxchg_u(memarg) => cmpxchg::<i64, 2>(memory, stack, memarg)?,
+        i64_atomic_rmw32_cmpxchg_u(memarg) => cmpxchg::<i64, 4>(memory, stack, memarg)?,
+
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// The size of the memory in pages
+fn pages(memory: &Memory) -> u64 {
+    (memory.len() / PAGE_SIZE) as u64
+}
+
+/// The bytes `length` bytes starting at `address` occupy, or a trap if they aren't all inside
+/// a memory or data segment of `size` bytes
+pub(super) fn range(size: usize, address: u64, length: u64) -> Result<Range<usize>, Trap> {
+    match address.checked_add(length) {
+        Some(end) if end <= size as u64 => Ok(address as usize..end as usize),
+        _ => Err(Trap::OutOfBoundsMemoryAccess),
+    }
+}
+
+/// The bytes accessed by a load or store, the static offset is added to the address operand
+fn effective_range(
+    memory: &Memory,
+    address: u64,
+    memarg: &MemArg,
+    length: usize,
+) -> Result<Range<usize>, Trap> {
+    // Addresses of 64 bit memories can overflow when the offset is added
+    let start = address
+        .checked_add(memarg.offset)
+        .ok_or(Trap::OutOfBoundsMemoryAccess)?;
+    range(memory.len(), start, length as u64)
+}
+
+fn read<const N: usize>(
+    memory: &Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+) -> Result<[u8; N], Trap> {
+    let range = effective_range(memory, stack.pop_address(), memarg, N)?;
+    Ok(memory.read(range.start))
+}
+
+fn load<const N: usize, T: FromValue>(
+    memory: &Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+    conversion: impl FnOnce([u8; N]) -> T,
+) -> Result<(), Trap> {
+    let bytes = read(memory, stack, memarg)?;
+    stack.push(conversion(bytes));
+    Ok(())
+}
+
+fn store<const N: usize, T: FromValue>(
+    memory: &mut Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+    conversion: impl FnOnce(T) -> [u8; N],
+) -> Result<(), Trap> {
+    let bytes = conversion(stack.pop());
+    let range = effective_range(memory, stack.pop_address(), memarg, N)?;
+    memory.write(range.start, &bytes);
+    Ok(())
+}
+
+/// Extends the `M` lanes of type `T` in `bytes` into the lanes of a vector
+fn widen<T: Lane, U: Lane + From<T>, const N: usize, const M: usize>(bytes: [u8; N]) -> V128 {
+    V128::from_lanes::<U, M>(array::from_fn(|i| {
+        U::from(T::from_le(&bytes[i * T::BYTES..(i + 1) * T::BYTES]))
+    }))
+}
+
+/// Replaces lane `lane` of the vector operand, the lane is `N` bytes wide
+fn load_lane<const N: usize>(
+    memory: &Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+    lane: u8,
+) -> Result<(), Trap> {
+    let mut vector = stack.pop::<V128>().to_bytes();
+    let bytes = read::<N>(memory, stack, memarg)?;
+    let start = lane as usize * N;
+    vector[start..start + N].copy_from_slice(&bytes);
+    stack.push(V128::from_bytes(vector));
+    Ok(())
+}
+
+/// Stores lane `lane` of the vector operand, the lane is `N` bytes wide
+fn store_lane<const N: usize>(
+    memory: &mut Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+    lane: u8,
+) -> Result<(), Trap> {
+    let vector = stack.pop::<V128>().to_bytes();
+    let range = effective_range(memory, stack.pop_address(), memarg, N)?;
+    let start = lane as usize * N;
+    memory.write(range.start, &vector[start..start + N]);
+    Ok(())
+}
+
+/// The integer operands of atomic instructions, as bits zero extended to 64 bits
+trait AtomicOperand: FromValue + Into<Value> {
+    fn to_bits(self) -> u64;
+    fn from_bits(bits: u64) -> Self;
+}
+
+impl AtomicOperand for i32 {
+    fn to_bits(self) -> u64 {
+        self as u32 as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        bits as i32
+    }
+}
+
+impl AtomicOperand for i64 {
+    fn to_bits(self) -> u64 {
+        self as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        bits as i64
+    }
+}
+
+/// The start of the `N` bytes accessed by an atomic instruction, which have to be aligned to their size.
+/// Accesses out of bounds trap as such even if they're also unaligned
+fn atomic_address<const N: usize>(
+    memory: &Memory,
+    address: u64,
+    memarg: &MemArg,
+) -> Result<usize, Trap> {
+    let start = effective_range(memory, address, memarg, N)?.start;
+    if !start.is_multiple_of(N) {
+        return Err(Trap::UnalignedAtomic);
+    }
+    Ok(start)
+}
+
+/// Reads the `N` byte wide little endian integer at `start`, zero extended to 64 bits
+fn read_bits<const N: usize>(memory: &Memory, start: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes[..N].copy_from_slice(&memory.read::<N>(start));
+    u64::from_le_bytes(bytes)
+}
+
+/// Writes the low `N` bytes of `bits` to `start`
+fn write_bits<const N: usize>(memory: &mut Memory, start: usize, bits: u64) {
+    memory.write(start, &bits.to_le_bytes()[..N]);
+}
+
+fn atomic_load<T: AtomicOperand, const N: usize>(
+    memory: &mut Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+) -> Result<(), Trap> {
+    let start = atomic_address::<N>(memory, stack.pop_address(), memarg)?;
+    let bits = memory.atomically(|memory| read_bits::<N>(memory, start));
+    stack.push(T::from_bits(bits));
+    Ok(())
+}
+
+fn atomic_store<T: AtomicOperand, const N: usize>(
+    memory: &mut Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+) -> Result<(), Trap> {
+    let bits = stack.pop::<T>().to_bits();
+    let start = atomic_address::<N>(memory, stack.pop_address(), memarg)?;
+    memory.atomically(|memory| write_bits::<N>(memory, start, bits));
+    Ok(())
+}
+
+/// Replaces the value in memory with `operation` applied to it and the operand, and pushes the old value
+fn rmw<T: AtomicOperand, const N: usize>(
+    memory: &mut Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+    operation: impl FnOnce(u64, u64) -> u64,
+) -> Result<(), Trap> {
+    let operand = stack.pop::<T>().to_bits();
+    let start = atomic_address::<N>(memory, stack.pop_address(), memarg)?;
+    let old = memory.atomically(|memory| {
+        let old = read_bits::<N>(memory, start);
+        write_bits::<N>(memory, start, operation(old, operand));
+        old
+    });
+    stack.push(T::from_bits(old));
+    Ok(())
+}
+
+/// Replaces the value in memory if it equals the expected operand, and pushes the old value
+fn cmpxchg<T: AtomicOperand, const N: usize>(
+    memory: &mut Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+) -> Result<(), Trap> {
+    let replacement = stack.pop::<T>().to_bits();
+    // Only the bytes that are accessed are compared
+    let expected = stack.pop::<T>().to_bits() & (u64::MAX >> (64 - 8 * N));
+    let start = atomic_address::<N>(memory, stack.pop_address(), memarg)?;
+    let old = memory.atomically(|memory| {
+        let old = read_bits::<N>(memory, start);
+        if old == expected {
+            write_bits::<N>(memory, start, replacement);
+        }
+        old
+    });
+    stack.push(T::from_bits(old));
+    Ok(())
+}
+
+/// Blocks until another thread notifies the address, if it holds the expected operand.
+/// Only shared memories can be waited on
+fn wait<T: AtomicOperand, const N: usize>(
+    memory: &Memory,
+    stack: &mut Stack,
+    memarg: &MemArg,
+) -> Result<(), Trap> {
+    let timeout = stack.pop::<i64>();
+    let expected = stack.pop::<T>().to_bits();
+    let start = atomic_address::<N>(memory, stack.pop_address(), memarg)?;
+    let Memory::Shared(shared) = memory else {
+        return Err(Trap::ExpectedSharedMemory);
+    };
+
+    // A negative timeout in nanoseconds waits forever
+    let timeout = u64::try_from(timeout).ok().map(Duration::from_nanos);
+    stack.push(shared.wait(start, &expected.to_le_bytes()[..N], timeout) as i32);
+    Ok(())
+}
+
+fn notify(memory: &Memory, stack: &mut Stack, memarg: &MemArg) -> Result<(), Trap> {
+    let count = stack.pop::<i32>() as u32;
+    let start = atomic_address::<4>(memory, stack.pop_address(), memarg)?;
+    // Nobody can wait on a memory that isn't shared
+    let woken = match memory {
+        Memory::Borrowed(_) | Memory::Owned(_) => 0,
+        Memory::Shared(shared) => shared.notify(start, count),
+    };
+    stack.push(woken as i32);
+    Ok(())
+}
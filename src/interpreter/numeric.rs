@@ -0,0 +1,288 @@
+use swai_parser::instructions::Instructions;
+
+use super::value::{FromValue, Stack};
+use crate::error::Trap;
+
+/// Executes the scalar numeric instructions, returns `false` for any other instruction
+pub(super) fn execute(instruction: &Instructions, stack: &mut Stack) -> Result<bool, Trap> {
+    use Instructions::*;
+
+    match instruction {
+        i32_const(value) => stack.push(*value),
+        i64_const(value) => stack.push(*value),
+        f32_const(bits) => stack.push(f32::from_bits(*bits)),
+        f64_const(bits) => stack.push(f64::from_bits(*bits)),
+
+        i32_eqz => unary(stack, |a: i32| i32::from(a == 0)),
+        i32_eq => binary(stack, |a: i32, b| i32::from(a == b)),
+        i32_ne => binary(stack, |a: i32, b| i32::from(a != b)),
+        i32_lt_s => binary(stack, |a: i32, b| i32::from(a < b)),
+        i32_lt_u => binary(stack, |a: i32, b| i32::from((a as u32) < b as u32)),
+        i32_gt_s => binary(stack, |a: i32, b| i32::from(a > b)),
+        i32_gt_u => binary(stack, |a: i32, b| i32::from(a as u32 > b as u32)),
+        i32_le_s => binary(stack, |a: i32, b| i32::from(a <= b)),
+        i32_le_u => binary(stack, |a: i32, b| i32::from(a as u32 <= b as u32)),
+        i32_ge_s => binary(stack, |a: i32, b| i32::from(a >= b)),
+        i32_ge_u => binary(stack, |a: i32, b| i32::from(a as u32 >= b as u32)),
+
+        i64_eqz => unary(stack, |a: i64| i32::from(a == 0)),
+        i64_eq => binary(stack, |a: i64, b| i32::from(a == b)),
+        i64_ne => binary(stack, |a: i64, b| i32::from(a != b)),
+        i64_lt_s => binary(stack, |a: i64, b| i32::from(a < b)),
+        i64_lt_u => binary(stack, |a: i64, b| i32::from((a as u64) < b as u64)),
+        i64_gt_s => binary(stack, |a: i64, b| i32::from(a > b)),
+        i64_gt_u => binary(stack, |a: i64, b| i32::from(a as u64 > b as u64)),
+        i64_le_s => binary(stack, |a: i64, b| i32::from(a <= b)),
+        i64_le_u => binary(stack, |a: i64, b| i32::from(a as u64 <= b as u64)),
+        i64_ge_s => binary(stack, |a: i64, b| i32::from(a >= b)),
+        i64_ge_u => binary(stack, |a: i64, b| i32::from(a as u64 >= b as u64)),
+
+        f32_eq => binary(stack, |a: f32, b| i32::from(a == b)),
+        f32_ne => binary(stack, |a: f32, b| i32::from(a != b)),
+        f32_lt => binary(stack, |a: f32, b| i32::from(a < b)),
+        f32_gt => binary(stack, |a: f32, b| i32::from(a > b)),
+        f32_le => binary(stack, |a: f32, b| i32::from(a <= b)),
+        f32_ge => binary(stack, |a: f32, b| i32::from(a >= b)),
+
+        f64_eq => binary(stack, |a: f64, b| i32::from(a == b)),
+        f64_ne => binary(stack, |a: f64, b| i32::from(a != b)),
+        f64_lt => binary(stack, |a: f64, b| i32::from(a < b)),
+        f64_gt => binary(stack, |a: f64, b| i32::from(a > b)),
+        f64_le => binary(stack, |a: f64, b| i32::from(a <= b)),
+        f64_ge => binary(stack, |a: f64, b| i32::from(a >= b)),
+
+        i32_clz => unary(stack, |a: i32| a.leading_zeros() as i32),
+        i32_ctz => unary(stack, |a: i32| a.trailing_zeros() as i32),
+        i32_popcnt => unary(stack, |a: i32| a.count_ones() as i32),
+        i32_add => binary(stack, i32::wrapping_add),
+        i32_sub => binary(stack, i32::wrapping_sub),
+        i32_mul => binary(stack, i32::wrapping_mul),
+        i32_div_s => try_binary(stack, |a: i32, b| match (a, b) {
+            (_, 0) => Err(Trap::IntegerDivideByZero),
+            (i32::MIN, -1) => Err(Trap::IntegerOverflow),
+            (a, b) => Ok(a / b),
+        })?,
+        i32_div_u => try_binary(stack, |a: i32, b| match b {
+            0 => Err(Trap::IntegerDivideByZero),
+            b => Ok((a as u32 / b as u32) as i32),
+        })?,
+        i32_rem_s => try_binary(stack, |a: i32, b| match b {
+            0 => Err(Trap::IntegerDivideByZero),
+            b => Ok(a.wrapping_rem(b)),
+        })?,
+        i32_rem_u => try_binary(stack, |a: i32, b| match b {
+            0 => Err(Trap::IntegerDivideByZero),
+            b => Ok((a as u32 % b as u32) as i32),
+        })?,
+        i32_and => binary(stack, |a: i32, b| a & b),
+        i32_or => binary(stack, |a: i32, b| a | b),
+        i32_xor => binary(stack, |a: i32, b| a ^ b),
+        i32_shl => binary(stack, |a: i32, b| a.wrapping_shl(b as u32)),
+        i32_shr_s => binary(stack, |a: i32, b| a.wrapping_shr(b as u32)),
+        i32_shr_u => binary(stack, |a: i32, b| (a as u32).wrapping_shr(b as u32) as i32),
+        i32_rotl => binary(stack, |a: i32, b| a.rotate_left(b as u32)),
+        i32_rotr => binary(stack, |a: i32, b| a.rotate_right(b as u32)),
+
+        i64_clz => unary(stack, |a: i64| a.leading_zeros() as i64),
+        i64_ctz => unary(stack, |a: i64| a.trailing_zeros() as i64),
+        i64_popcnt => unary(stack, |a: i64| a.count_ones() as i64),
+        i64_add => binary(stack, i64::wrapping_add),
+        i64_sub => binary(stack, i64::wrapping_sub),
+        i64_mul => binary(stack, i64::wrapping_mul),
+        i64_div_s => try_binary(stack, |a: i64, b| match (a, b) {
+            (_, 0) => Err(Trap::IntegerDivideByZero),
+            (i64::MIN, -1) => Err(Trap::IntegerOverflow),
+            (a, b) => Ok(a / b),
+        })?,
+        i64_div_u => try_binary(stack, |a: i64, b| match b {
+            0 => Err(Trap::IntegerDivideByZero),
+            b => Ok((a as u64 / b as u64) as i64),
+        })?,
+        i64_rem_s => try_binary(stack, |a: i64, b| match b {
+            0 => Err(Trap::IntegerDivideByZero),
+            b => Ok(a.wrapping_rem(b)),
+        })?,
+        i64_rem_u => try_binary(stack, |a: i64, b| match b {
+            0 => Err(Trap::IntegerDivideByZero),
+            b => Ok((a as u64 % b as u64) as i64),
+        })?,
+        i64_and => binary(stack, |a: i64, b| a & b),
+        i64_or => binary(stack, |a: i64, b| a | b),
+        i64_xor => binary(stack, |a: i64, b| a ^ b),
+        i64_shl => binary(stack, |a: i64, b| a.wrapping_shl(b as u32)),
+        i64_shr_s => binary(stack, |a: i64, b| a.wrapping_shr(b as u32)),
+        i64_shr_u => binary(stack, |a: i64, b| (a as u64).wrapping_shr(b as u32) as i64),
+        i64_rotl => binary(stack, |a: i64, b| a.rotate_left(b as u32)),
+        i64_rotr => binary(stack, |a: i64, b| a.rotate_right(b as u32)),
+
+        f32_abs => unary(stack, f32::abs),
+        f32_neg => unary(stack, |a: f32| -a),
+        f32_ceil => unary(stack, f32::ceil),
+        f32_floor => unary(stack, f32::floor),
+        f32_trunc => unary(stack, f32::trunc),
+        f32_nearest => unary(stack, f32::round_ties_even),
+        f32_sqrt => unary(stack, f32::sqrt),
+        f32_add => binary(stack, |a: f32, b| a + b),
+        f32_sub => binary(stack, |a: f32, b| a - b),
+        f32_mul => binary(stack, |a: f32, b| a * b),
+        f32_div => binary(stack, |a: f32, b| a / b),
+        f32_min => binary(stack, min_f32),
+        f32_max => binary(stack, max_f32),
+        f32_copysign => binary(stack, f32::copysign),
+
+        f64_abs => unary(stack, f64::abs),
+        f64_neg => unary(stack, |a: f64| -a),
+        f64_ceil => unary(stack, f64::ceil),
+        f64_floor => unary(stack, f64::floor),
+        f64_trunc => unary(stack, f64::trunc),
+        f64_nearest => unary(stack, f64::round_ties_even),
+        f64_sqrt => unary(stack, f64::sqrt),
+        f64_add => binary(stack, |a: f64, b| a + b),
+        f64_sub => binary(stack, |a: f64, b| a - b),
+        f64_mul => binary(stack, |a: f64, b| a * b),
+        f64_div => binary(stack, |a: f64, b| a / b),
+        f64_min => binary(stack, min_f64),
+        f64_max => binary(stack, max_f64),
+        f64_copysign => binary(stack, f64::copysign),
+
+        i32_wrap_i64 => unary(stack, |a: i64| a as i32),
+        i32_trunc_f32_s => try_unary(stack, |a: f32| Ok(truncate(a as f64, I32_RANGE)? as i32))?,
+        i32_trunc_f32_u => try_unary(stack, |a: f32| {
+            Ok(truncate(a as f64, U32_RANGE)? as u32 as i32)
+        })?,
+        i32_trunc_f64_s => try_unary(stack, |a: f64| Ok(truncate(a, I32_RANGE)? as i32))?,
+        i32_trunc_f64_u => try_unary(stack, |a: f64| Ok(truncate(a, U32_RANGE)? as u32 as i32))?,
+        i64_extend_i32_s => unary(stack, |a: i32| a as i64),
+        i64_extend_i32_u => unary(stack, |a: i32| a as u32 as i64),
+        i64_trunc_f32_s => try_unary(stack, |a: f32| Ok(truncate(a as f64, I64_RANGE)? as i64))?,
+        i64_trunc_f32_u => try_unary(stack, |a: f32| {
+            Ok(truncate(a as f64, U64_RANGE)? as u64 as i64)
+        })?,
+        i64_trunc_f64_s => try_unary(stack, |a: f64| Ok(truncate(a, I64_RANGE)? as i64))?,
+        i64_trunc_f64_u => try_unary(stack, |a: f64| Ok(truncate(a, U64_RANGE)? as u64 as i64))?,
+        f32_convert_i32_s => unary(stack, |a: i32| a as f32),
+        f32_convert_i32_u => unary(stack, |a: i32| a as u32 as f32),
+        f32_convert_i64_s => unary(stack, |a: i64| a as f32),
+        f32_convert_i64_u => unary(stack, |a: i64| a as u64 as f32),
+        f32_demote_f64 => unary(stack, |a: f64| a as f32),
+        f64_convert_i32_s => unary(stack, |a: i32| a as f64),
+        f64_convert_i32_u => unary(stack, |a: i32| a as u32 as f64),
+        f64_convert_i64_s => unary(stack, |a: i64| a as f64),
+        f64_convert_i64_u => unary(stack, |a: i64| a as u64 as f64),
+        f64_promote_f32 => unary(stack, |a: f32| a as f64),
+        i32_reinterpret_f32 => unary(stack, |a: f32| a.to_bits() as i32),
+        i64_reinterpret_f64 => unary(stack, |a: f64| a.to_bits() as i64),
+        f32_reinterpret_i32 => unary(stack, |a: i32| f32::from_bits(a as u32)),
+        f64_reinterpret_i64 => unary(stack, |a: i64| f64::from_bits(a as u64)),
+
+        i32_extend8_s => unary(stack, |a: i32| a as i8 as i32),
+        i32_extend16_s => unary(stack, |a: i32| a as i16 as i32),
+        i64_extend8_s => unary(stack, |a: i64| a as i8 as i64),
+        i64_extend16_s => unary(stack, |a: i64| a as i16 as i64),
+        i64_extend32_s => unary(stack, |a: i64| a as i32 as i64),
+
+        // Rust's float to integer casts saturate and map NaN to zero, just like these
+        i32_trunc_sat_f32_s => unary(stack, |a: f32| a as i32),
+        i32_trunc_sat_f32_u => unary(stack, |a: f32| a as u32 as i32),
+        i32_trunc_sat_f64_s => unary(stack, |a: f64| a as i32),
+        i32_trunc_sat_f64_u => unary(stack, |a: f64| a as u32 as i32),
+        i64_trunc_sat_f32_s => unary(stack, |a: f32| a as i64),
+        i64_trunc_sat_f32_u => unary(stack, |a: f32| a as u64 as i64),
+        i64_trunc_sat_f64_s => unary(stack, |a: f64| a as i64),
+        i64_trunc_sat_f64_u => unary(stack, |a: f64| a as u64 as i64),
+
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+pub(super) fn unary<T: FromValue, R: FromValue>(stack: &mut Stack, operation: impl FnOnce(T) -> R) {
+    let a = stack.pop();
+    stack.push(operation(a));
+}
+
+pub(super) fn binary<T: FromValue, R: FromValue>(
+    stack: &mut Stack,
+    operation: impl FnOnce(T, T) -> R,
+) {
+    let b = stack.pop();
+    let a = stack.pop();
+    stack.push(operation(a, b));
+}
+
+fn try_unary<T: FromValue, R: FromValue>(
+    stack: &mut Stack,
+    operation: impl FnOnce(T) -> Result<R, Trap>,
+) -> Result<(), Trap> {
+    let a = stack.pop();
+    stack.push(operation(a)?);
+    Ok(())
+}
+
+fn try_binary<T: FromValue, R: FromValue>(
+    stack: &mut Stack,
+    operation: impl FnOnce(T, T) -> Result<R, Trap>,
+) -> Result<(), Trap> {
+    let b = stack.pop();
+    let a = stack.pop();
+    stack.push(operation(a, b)?);
+    Ok(())
+}
+
+// The integers a truncated float has to lie within, the upper bounds are exclusive
+// since they aren't exactly representable for 64 bit integers
+const I32_RANGE: (f64, f64) = (-2147483648.0, 2147483648.0);
+const U32_RANGE: (f64, f64) = (0.0, 4294967296.0);
+const I64_RANGE: (f64, f64) = (-9223372036854775808.0, 9223372036854775808.0);
+const U64_RANGE: (f64, f64) = (0.0, 18446744073709551616.0);
+
+/// Truncates a float towards zero, trapping if the result doesn't fit into `(min, max)`
+fn truncate(value: f64, (min, max): (f64, f64)) -> Result<f64, Trap> {
+    if value.is_nan() {
+        return Err(Trap::InvalidConversionToInteger);
+    }
+
+    let truncated = value.trunc();
+    if truncated < min || truncated >= max {
+        return Err(Trap::IntegerOverflow);
+    }
+    Ok(truncated)
+}
+
+macro_rules! float_min_max {
+    ($ty:ty, $min:ident, $max:ident) => {
+        /// Unlike [`f32::min`] a NaN operand results in NaN, and negative zero is smaller than positive zero
+        pub(super) fn $min(a: $ty, b: $ty) -> $ty {
+            if a.is_nan() || b.is_nan() {
+                a + b
+            } else if a == b {
+                if a.is_sign_negative() {
+                    a
+                } else {
+                    b
+                }
+            } else {
+                a.min(b)
+            }
+        }
+
+        /// Unlike [`f32::max`] a NaN operand results in NaN, and positive zero is larger than negative zero
+        pub(super) fn $max(a: $ty, b: $ty) -> $ty {
+            if a.is_nan() || b.is_nan() {
+                a + b
+            } else if a == b {
+                if a.is_sign_positive() {
+                    a
+                } else {
+                    b
+                }
+            } else {
+                a.max(b)
+            }
+        }
+    };
+}
+
+float_min_max!(f32, min_f32, max_f32);
+float_min_max!(f64, min_f64, max_f64);
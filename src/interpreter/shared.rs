@@ -0,0 +1,183 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use super::memory::PAGE_SIZE;
+use crate::error::WasmInterpreterError;
+
+/// A linear memory environments on several threads can use at the same time.
+/// Clones are handles to the same memory
+#[derive(Clone)]
+pub struct SharedMemory {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Plain loads and stores access the bytes one by one, so racing accesses can tear like in the spec.
+    /// Accesses share the lock, growing the memory takes it exclusively
+    bytes: RwLock<Vec<AtomicU8>>,
+    /// Held for the duration of every atomic access, which makes them atomic towards each other
+    waiters: Mutex<Waiters>,
+    notified: Condvar,
+}
+
+/// The threads blocked in `memory.atomic.wait`
+#[derive(Default)]
+pub(super) struct Waiters {
+    next_id: u64,
+    /// The address and id of each waiting thread, in the order they started to wait
+    queue: Vec<(usize, u64)>,
+}
+
+impl SharedMemory {
+    /// A zeroed memory of `pages` pages, fails if its size overflows or it can't be allocated
+    pub fn new(pages: usize) -> Result<SharedMemory, WasmInterpreterError> {
+        let mut bytes = Vec::new();
+        match pages.checked_mul(PAGE_SIZE) {
+            Some(length) if bytes.try_reserve_exact(length).is_ok() => {
+                bytes.resize_with(length, || AtomicU8::new(0))
+            }
+            _ => {
+                return Err(WasmInterpreterError::AllocationFailed {
+                    resource: "memory pages",
+                    size: pages as u64,
+                })
+            }
+        }
+        Ok(SharedMemory {
+            inner: Arc::new(Inner {
+                bytes: RwLock::new(bytes),
+                waiters: Mutex::default(),
+                notified: Condvar::new(),
+            }),
+        })
+    }
+
+    /// The current length in bytes, memories only grow so it never shrinks
+    pub fn len(&self) -> usize {
+        self.bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes().is_empty()
+    }
+
+    /// Copies the bytes starting at `offset` into `bytes`, panics if they aren't all inside the memory
+    pub fn read(&self, offset: usize, bytes: &mut [u8]) {
+        let source = &self.bytes()[offset..offset + bytes.len()];
+        for (byte, source) in bytes.iter_mut().zip(source) {
+            *byte = source.load(Ordering::Relaxed);
+        }
+    }
+
+    /// Copies `bytes` into the memory starting at `offset`, panics if they don't all fit inside it
+    pub fn write(&self, offset: usize, bytes: &[u8]) {
+        let destination = &self.bytes()[offset..offset + bytes.len()];
+        for (byte, destination) in bytes.iter().zip(destination) {
+            destination.store(*byte, Ordering::Relaxed);
+        }
+    }
+
+    /// Grows the memory by `delta` zeroed pages unless that makes it larger than `maximum` pages
+    /// or the pages can't be allocated. Returns the previous size in pages
+    pub(super) fn grow(&self, delta: u64, maximum: u64) -> Option<u64> {
+        let mut bytes = self
+            .inner
+            .bytes
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let pages = (bytes.len() / PAGE_SIZE) as u64;
+        let new_pages = pages.checked_add(delta).filter(|&pages| pages <= maximum)?;
+        let new_len = usize::try_from(new_pages).ok()?.checked_mul(PAGE_SIZE)?;
+        let additional = new_len - bytes.len();
+        bytes.try_reserve_exact(additional).ok()?;
+        bytes.resize_with(new_len, || AtomicU8::new(0));
+        Some(pages)
+    }
+
+    fn bytes(&self) -> RwLockReadGuard<'_, Vec<AtomicU8>> {
+        self.inner
+            .bytes
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Blocks atomic accesses from other threads until the guard is dropped
+    pub(super) fn lock(&self) -> MutexGuard<'_, Waiters> {
+        self.inner
+            .waiters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Blocks the thread until it's notified at `address` or `timeout` elapses, if the bytes at
+    /// `address` equal `expected`. Returns 0 if the thread was notified, 1 if the bytes differ and 2 on a timeout
+    pub(super) fn wait(&self, address: usize, expected: &[u8], timeout: Option<Duration>) -> u32 {
+        let mut waiters = self.lock();
+        let mut actual = vec![0; expected.len()];
+        self.read(address, &mut actual);
+        if actual != expected {
+            return 1;
+        }
+
+        let id = waiters.next_id;
+        waiters.next_id += 1;
+        waiters.queue.push((address, id));
+
+        // A timeout too large to represent waits forever
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        loop {
+            if !waiters.queue.iter().any(|&(_, waiter)| waiter == id) {
+                return 0;
+            }
+            waiters = match deadline {
+                None => self
+                    .inner
+                    .notified
+                    .wait(waiters)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                        waiters.queue.retain(|&(_, waiter)| waiter != id);
+                        return 2;
+                    };
+                    let (waiters, _) = self
+                        .inner
+                        .notified
+                        .wait_timeout(waiters, remaining)
+                        .unwrap_or_else(PoisonError::into_inner);
+                    waiters
+                }
+            };
+        }
+    }
+
+    /// Wakes up to `count` of the threads waiting at `address`, the ones that waited longest first.
+    /// Returns the number of threads woken
+    pub(super) fn notify(&self, address: usize, count: u32) -> u32 {
+        let mut waiters = self.lock();
+        let mut woken = 0;
+        waiters.queue.retain(|&(waiter_address, _)| {
+            let wake = waiter_address == address && woken < count;
+            woken += u32::from(wake);
+            !wake
+        });
+        if woken > 0 {
+            self.inner.notified.notify_all();
+        }
+        woken
+    }
+}
+
+impl fmt::Debug for SharedMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedMemory")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
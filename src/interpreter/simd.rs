@@ -0,0 +1,538 @@
+use std::array;
+
+use swai_parser::instructions::Instructions;
+
+use super::{
+    numeric::{binary, max_f32, max_f64, min_f32, min_f64, unary},
+    value::Stack,
+};
+
+/// A 128 bit vector. Lanes are numbered from the least significant end,
+/// which is also the order they have in memory
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct V128(u128);
+
+impl V128 {
+    pub fn from_bits(bits: u128) -> Self {
+        V128(bits)
+    }
+
+    pub fn to_bits(self) -> u128 {
+        self.0
+    }
+
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        V128(u128::from_le_bytes(bytes))
+    }
+
+    pub fn to_bytes(self) -> [u8; 16] {
+        self.0.to_le_bytes()
+    }
+
+    /// Splits the vector into `N` lanes of type `T`, for example `lanes::<i16, 8>()` for `i16x8`
+    pub fn lanes<T: Lane, const N: usize>(self) -> [T; N] {
+        assert_eq!(N * T::BYTES, 16, "lanes have to make up 128 bits");
+        let bytes = self.to_bytes();
+        array::from_fn(|i| T::from_le(&bytes[i * T::BYTES..(i + 1) * T::BYTES]))
+    }
+
+    pub fn from_lanes<T: Lane, const N: usize>(lanes: [T; N]) -> Self {
+        assert_eq!(N * T::BYTES, 16, "lanes have to make up 128 bits");
+        let mut bytes = [0; 16];
+        for (i, lane) in lanes.into_iter().enumerate() {
+            lane.to_le(&mut bytes[i * T::BYTES..(i + 1) * T::BYTES]);
+        }
+        V128::from_bytes(bytes)
+    }
+}
+
+/// The scalar types a vector can be split into
+pub trait Lane: Copy {
+    const BYTES: usize;
+
+    fn from_le(bytes: &[u8]) -> Self;
+    fn to_le(self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_lane {
+    ($($ty:ty),*) => {
+        $(
+            impl Lane for $ty {
+                const BYTES: usize = std::mem::size_of::<$ty>();
+
+                fn from_le(bytes: &[u8]) -> Self {
+                    <$ty>::from_le_bytes(bytes.try_into().unwrap())
+                }
+
+                fn to_le(self, bytes: &mut [u8]) {
+                    bytes.copy_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_lane!(i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+
+/// The integer lanes comparisons produce, all ones for true and all zeros for false
+trait Mask: Lane {
+    fn mask(condition: bool) -> Self;
+}
+
+macro_rules! impl_mask {
+    ($($ty:ty),*) => {
+        $(
+            impl Mask for $ty {
+                fn mask(condition: bool) -> Self {
+                    if condition { -1 } else { 0 }
+                }
+            }
+        )*
+    };
+}
+
+impl_mask!(i8, i16, i32, i64);
+
+/// Executes the vector instructions that don't access memory, returns `false` for any other instruction.
+/// None of them can trap
+pub(super) fn execute(instruction: &Instructions, stack: &mut Stack) -> bool {
+    use Instructions::*;
+
+    match instruction {
+        v128_const(bits) => stack.push(V128::from_bits(*bits)),
+        i8x16_shuffle(lanes) => {
+            let b = stack.pop::<V128>().lanes::<u8, 16>();
+            let a = stack.pop::<V128>().lanes::<u8, 16>();
+            let selected = lanes.map(|lane| match lane {
+                0..=15 => a[lane as usize],
+                _ => b[lane as usize - 16],
+            });
+            stack.push(V128::from_lanes(selected));
+        }
+        i8x16_swizzle => {
+            let indices = stack.pop::<V128>().lanes::<u8, 16>();
+            let a = stack.pop::<V128>().lanes::<u8, 16>();
+            let selected = indices.map(|lane| a.get(lane as usize).copied().unwrap_or(0));
+            stack.push(V128::from_lanes(selected));
+        }
+
+        i8x16_splat => unary(stack, |a: i32| V128::from_lanes([a as i8; 16])),
+        i16x8_splat => unary(stack, |a: i32| V128::from_lanes([a as i16; 8])),
+        i32x4_splat => unary(stack, |a: i32| V128::from_lanes([a; 4])),
+        i64x2_splat => unary(stack, |a: i64| V128::from_lanes([a; 2])),
+        f32x4_splat => unary(stack, |a: f32| V128::from_lanes([a; 4])),
+        f64x2_splat => unary(stack, |a: f64| V128::from_lanes([a; 2])),
+
+        i8x16_extract_lane_s(lane) => {
+            unary(stack, |a: V128| a.lanes::<i8, 16>()[*lane as usize] as i32)
+        }
+        i8x16_extract_lane_u(lane) => {
+            unary(stack, |a: V128| a.lanes::<u8, 16>()[*lane as usize] as i32)
+        }
+        i16x8_extract_lane_s(lane) => {
+            unary(stack, |a: V128| a.lanes::<i16, 8>()[*lane as usize] as i32)
+        }
+        i16x8_extract_lane_u(lane) => {
+            unary(stack, |a: V128| a.lanes::<u16, 8>()[*lane as usize] as i32)
+        }
+        i32x4_extract_lane(lane) => unary(stack, |a: V128| a.lanes::<i32, 4>()[*lane as usize]),
+        i64x2_extract_lane(lane) => unary(stack, |a: V128| a.lanes::<i64, 2>()[*lane as usize]),
+        f32x4_extract_lane(lane) => unary(stack, |a: V128| a.lanes::<f32, 4>()[*lane as usize]),
+        f64x2_extract_lane(lane) => unary(stack, |a: V128| a.lanes::<f64, 2>()[*lane as usize]),
+
+        i8x16_replace_lane(lane) => replace_lane::<i8, 16, i32>(stack, *lane, |a| a as i8),
+        i16x8_replace_lane(lane) => replace_lane::<i16, 8, i32>(stack, *lane, |a| a as i16),
+        i32x4_replace_lane(lane) => replace_lane::<i32, 4, i32>(stack, *lane, |a| a),
+        i64x2_replace_lane(lane) => replace_lane::<i64, 2, i64>(stack, *lane, |a| a),
+        f32x4_replace_lane(lane) => replace_lane::<f32, 4, f32>(stack, *lane, |a| a),
+        f64x2_replace_lane(lane) => replace_lane::<f64, 2, f64>(stack, *lane, |a| a),
+
+        i8x16_eq => compare::<i8, i8, 16>(stack, |a, b| a == b),
+        i8x16_ne => compare::<i8, i8, 16>(stack, |a, b| a != b),
+        i8x16_lt_s => compare::<i8, i8, 16>(stack, |a, b| a < b),
+        i8x16_lt_u => compare::<u8, i8, 16>(stack, |a, b| a < b),
+        i8x16_gt_s => compare::<i8, i8, 16>(stack, |a, b| a > b),
+        i8x16_gt_u => compare::<u8, i8, 16>(stack, |a, b| a > b),
+        i8x16_le_s => compare::<i8, i8, 16>(stack, |a, b| a <= b),
+        i8x16_le_u => compare::<u8, i8, 16>(stack, |a, b| a <= b),
+        i8x16_ge_s => compare::<i8, i8, 16>(stack, |a, b| a >= b),
+        i8x16_ge_u => compare::<u8, i8, 16>(stack, |a, b| a >= b),
+
+        i16x8_eq => compare::<i16, i16, 8>(stack, |a, b| a == b),
+        i16x8_ne => compare::<i16, i16, 8>(stack, |a, b| a != b),
+        i16x8_lt_s => compare::<i16, i16, 8>(stack, |a, b| a < b),
+        i16x8_lt_u => compare::<u16, i16, 8>(stack, |a, b| a < b),
+        i16x8_gt_s => compare::<i16, i16, 8>(stack, |a, b| a > b),
+        i16x8_gt_u => compare::<u16, i16, 8>(stack, |a, b| a > b),
+        i16x8_le_s => compare::<i16, i16, 8>(stack, |a, b| a <= b),
+        i16x8_le_u => compare::<u16, i16, 8>(stack, |a, b| a <= b),
+        i16x8_ge_s => compare::<i16, i16, 8>(stack, |a, b| a >= b),
+        i16x8_ge_u => compare::<u16, i16, 8>(stack, |a, b| a >= b),
+
+        i32x4_eq => compare::<i32, i32, 4>(stack, |a, b| a == b),
+        i32x4_ne => compare::<i32, i32, 4>(stack, |a, b| a != b),
+        i32x4_lt_s => compare::<i32, i32, 4>(stack, |a, b| a < b),
+        i32x4_lt_u => compare::<u32, i32, 4>(stack, |a, b| a < b),
+        i32x4_gt_s => compare::<i32, i32, 4>(stack, |a, b| a > b),
+        i32x4_gt_u => compare::<u32, i32, 4>(stack, |a, b| a > b),
+        i32x4_le_s => compare::<i32, i32, 4>(stack, |a, b| a <= b),
+        i32x4_le_u => compare::<u32, i32, 4>(stack, |a, b| a <= b),
+        i32x4_ge_s => compare::<i32, i32, 4>(stack, |a, b| a >= b),
+        i32x4_ge_u => compare::<u32, i32, 4>(stack, |a, b| a >= b),
+
+        i64x2_eq => compare::<i64, i64, 2>(stack, |a, b| a == b),
+        i64x2_ne => compare::<i64, i64, 2>(stack, |a, b| a != b),
+        i64x2_lt_s => compare::<i64, i64, 2>(stack, |a, b| a < b),
+        i64x2_gt_s => compare::<i64, i64, 2>(stack, |a, b| a > b),
+        i64x2_le_s => compare::<i64, i64, 2>(stack, |a, b| a <= b),
+        i64x2_ge_s => compare::<i64, i64, 2>(stack, |a, b| a >= b),
+
+        f32x4_eq => compare::<f32, i32, 4>(stack, |a, b| a == b),
+        f32x4_ne => compare::<f32, i32, 4>(stack, |a, b| a != b),
+        f32x4_lt => compare::<f32, i32, 4>(stack, |a, b| a < b),
+        f32x4_gt => compare::<f32, i32, 4>(stack, |a, b| a > b),
+        f32x4_le => compare::<f32, i32, 4>(stack, |a, b| a <= b),
+        f32x4_ge => compare::<f32, i32, 4>(stack, |a, b| a >= b),
+
+        f64x2_eq => compare::<f64, i64, 2>(stack, |a, b| a == b),
+        f64x2_ne => compare::<f64, i64, 2>(stack, |a, b| a != b),
+        f64x2_lt => compare::<f64, i64, 2>(stack, |a, b| a < b),
+        f64x2_gt => compare::<f64, i64, 2>(stack, |a, b| a > b),
+        f64x2_le => compare::<f64, i64, 2>(stack, |a, b| a <= b),
+        f64x2_ge => compare::<f64, i64, 2>(stack, |a, b| a >= b),
+
+        v128_not => unary(stack, |a: V128| V128(!a.0)),
+        v128_and => binary(stack, |a: V128, b| V128(a.0 & b.0)),
+        v128_andnot => binary(stack, |a: V128, b| V128(a.0 & !b.0)),
+        v128_or => binary(stack, |a: V128, b| V128(a.0 | b.0)),
+        v128_xor => binary(stack, |a: V128, b| V128(a.0 ^ b.0)),
+        v128_bitselect => {
+            let mask = stack.pop::<V128>().0;
+            let b = stack.pop::<V128>().0;
+            let a = stack.pop::<V128>().0;
+            stack.push(V128((a & mask) | (b & !mask)));
+        }
+        v128_any_true => unary(stack, |a: V128| i32::from(a.0 != 0)),
+
+        i8x16_abs => lanewise::<i8, 16>(stack, i8::wrapping_abs),
+        i8x16_neg => lanewise::<i8, 16>(stack, i8::wrapping_neg),
+        i8x16_popcnt => lanewise::<u8, 16>(stack, |a| a.count_ones() as u8),
+        i8x16_all_true => all_true::<i8, 16>(stack),
+        i8x16_bitmask => bitmask::<i8, 16>(stack),
+        i8x16_narrow_i16x8_s => narrow::<i16, i8, 8, 16>(stack, |a| a.clamp(-128, 127) as i8),
+        i8x16_narrow_i16x8_u => narrow::<i16, u8, 8, 16>(stack, |a| a.clamp(0, 255) as u8),
+        i8x16_shl => shift::<i8, 16>(stack, |a, n| a.wrapping_shl(n)),
+        i8x16_shr_s => shift::<i8, 16>(stack, |a, n| a.wrapping_shr(n)),
+        i8x16_shr_u => shift::<u8, 16>(stack, |a, n| a.wrapping_shr(n)),
+        i8x16_add => lanewise_binary::<i8, 16>(stack, i8::wrapping_add),
+        i8x16_add_sat_s => lanewise_binary::<i8, 16>(stack, i8::saturating_add),
+        i8x16_add_sat_u => lanewise_binary::<u8, 16>(stack, u8::saturating_add),
+        i8x16_sub => lanewise_binary::<i8, 16>(stack, i8::wrapping_sub),
+        i8x16_sub_sat_s => lanewise_binary::<i8, 16>(stack, i8::saturating_sub),
+        i8x16_sub_sat_u => lanewise_binary::<u8, 16>(stack, u8::saturating_sub),
+        i8x16_min_s => lanewise_binary::<i8, 16>(stack, i8::min),
+        i8x16_min_u => lanewise_binary::<u8, 16>(stack, u8::min),
+        i8x16_max_s => lanewise_binary::<i8, 16>(stack, i8::max),
+        i8x16_max_u => lanewise_binary::<u8, 16>(stack, u8::max),
+        i8x16_avgr_u => {
+            lanewise_binary::<u8, 16>(stack, |a, b| (a as u16 + b as u16).div_ceil(2) as u8)
+        }
+
+        i16x8_extadd_pairwise_i8x16_s => pairwise::<i8, i16, 8>(stack, |a, b| a as i16 + b as i16),
+        i16x8_extadd_pairwise_i8x16_u => pairwise::<u8, i16, 8>(stack, |a, b| a as i16 + b as i16),
+        i32x4_extadd_pairwise_i16x8_s => pairwise::<i16, i32, 4>(stack, |a, b| a as i32 + b as i32),
+        i32x4_extadd_pairwise_i16x8_u => pairwise::<u16, i32, 4>(stack, |a, b| a as i32 + b as i32),
+
+        i16x8_abs => lanewise::<i16, 8>(stack, i16::wrapping_abs),
+        i16x8_neg => lanewise::<i16, 8>(stack, i16::wrapping_neg),
+        i16x8_q15mulr_sat_s => lanewise_binary::<i16, 8>(stack, |a, b| {
+            ((a as i32 * b as i32 + 0x4000) >> 15).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+        }),
+        i16x8_all_true => all_true::<i16, 8>(stack),
+        i16x8_bitmask => bitmask::<i16, 8>(stack),
+        i16x8_narrow_i32x4_s => narrow::<i32, i16, 4, 8>(stack, |a| a.clamp(-32768, 32767) as i16),
+        i16x8_narrow_i32x4_u => narrow::<i32, u16, 4, 8>(stack, |a| a.clamp(0, 65535) as u16),
+        i16x8_extend_low_i8x16_s => extend::<i8, i16, 16, 8>(stack, false, |a| a as i16),
+        i16x8_extend_high_i8x16_s => extend::<i8, i16, 16, 8>(stack, true, |a| a as i16),
+        i16x8_extend_low_i8x16_u => extend::<u8, i16, 16, 8>(stack, false, |a| a as i16),
+        i16x8_extend_high_i8x16_u => extend::<u8, i16, 16, 8>(stack, true, |a| a as i16),
+        i16x8_shl => shift::<i16, 8>(stack, |a, n| a.wrapping_shl(n)),
+        i16x8_shr_s => shift::<i16, 8>(stack, |a, n| a.wrapping_shr(n)),
+        i16x8_shr_u => shift::<u16, 8>(stack, |a, n| a.wrapping_shr(n)),
+        i16x8_add => lanewise_binary::<i16, 8>(stack, i16::wrapping_add),
+        i16x8_add_sat_s => lanewise_binary::<i16, 8>(stack, i16::saturating_add),
+        i16x8_add_sat_u => lanewise_binary::<u16, 8>(stack, u16::saturating_add),
+        i16x8_sub => lanewise_binary::<i16, 8>(stack, i16::wrapping_sub),
+        i16x8_sub_sat_s => lanewise_binary::<i16, 8>(stack, i16::saturating_sub),
+        i16x8_sub_sat_u => lanewise_binary::<u16, 8>(stack, u16::saturating_sub),
+        i16x8_mul => lanewise_binary::<i16, 8>(stack, i16::wrapping_mul),
+        i16x8_min_s => lanewise_binary::<i16, 8>(stack, i16::min),
+        i16x8_min_u => lanewise_binary::<u16, 8>(stack, u16::min),
+        i16x8_max_s => lanewise_binary::<i16, 8>(stack, i16::max),
+        i16x8_max_u => lanewise_binary::<u16, 8>(stack, u16::max),
+        i16x8_avgr_u => {
+            lanewise_binary::<u16, 8>(stack, |a, b| (a as u32 + b as u32).div_ceil(2) as u16)
+        }
+        i16x8_extmul_low_i8x16_s => {
+            extmul::<i8, i16, 16, 8>(stack, false, |a, b| a as i16 * b as i16)
+        }
+        i16x8_extmul_high_i8x16_s => {
+            extmul::<i8, i16, 16, 8>(stack, true, |a, b| a as i16 * b as i16)
+        }
+        i16x8_extmul_low_i8x16_u => {
+            extmul::<u8, u16, 16, 8>(stack, false, |a, b| a as u16 * b as u16)
+        }
+        i16x8_extmul_high_i8x16_u => {
+            extmul::<u8, u16, 16, 8>(stack, true, |a, b| a as u16 * b as u16)
+        }
+
+        i32x4_abs => lanewise::<i32, 4>(stack, i32::wrapping_abs),
+        i32x4_neg => lanewise::<i32, 4>(stack, i32::wrapping_neg),
+        i32x4_all_true => all_true::<i32, 4>(stack),
+        i32x4_bitmask => bitmask::<i32, 4>(stack),
+        i32x4_extend_low_i16x8_s => extend::<i16, i32, 8, 4>(stack, false, |a| a as i32),
+        i32x4_extend_high_i16x8_s => extend::<i16, i32, 8, 4>(stack, true, |a| a as i32),
+        i32x4_extend_low_i16x8_u => extend::<u16, i32, 8, 4>(stack, false, |a| a as i32),
+        i32x4_extend_high_i16x8_u => extend::<u16, i32, 8, 4>(stack, true, |a| a as i32),
+        i32x4_shl => shift::<i32, 4>(stack, |a, n| a.wrapping_shl(n)),
+        i32x4_shr_s => shift::<i32, 4>(stack, |a, n| a.wrapping_shr(n)),
+        i32x4_shr_u => shift::<u32, 4>(stack, |a, n| a.wrapping_shr(n)),
+        i32x4_add => lanewise_binary::<i32, 4>(stack, i32::wrapping_add),
+        i32x4_sub => lanewise_binary::<i32, 4>(stack, i32::wrapping_sub),
+        i32x4_mul => lanewise_binary::<i32, 4>(stack, i32::wrapping_mul),
+        i32x4_min_s => lanewise_binary::<i32, 4>(stack, i32::min),
+        i32x4_min_u => lanewise_binary::<u32, 4>(stack, u32::min),
+        i32x4_max_s => lanewise_binary::<i32, 4>(stack, i32::max),
+        i32x4_max_u => lanewise_binary::<u32, 4>(stack, u32::max),
+        i32x4_dot_i16x8_s => {
+            let b = stack.pop::<V128>().lanes::<i16, 8>();
+            let a = stack.pop::<V128>().lanes::<i16, 8>();
+            let dot: [i32; 4] = array::from_fn(|i| {
+                let low = a[2 * i] as i32 * b[2 * i] as i32;
+                let high = a[2 * i + 1] as i32 * b[2 * i + 1] as i32;
+                low.wrapping_add(high)
+            });
+            stack.push(V128::from_lanes(dot));
+        }
+        i32x4_extmul_low_i16x8_s => {
+            extmul::<i16, i32, 8, 4>(stack, false, |a, b| a as i32 * b as i32)
+        }
+        i32x4_extmul_high_i16x8_s => {
+            extmul::<i16, i32, 8, 4>(stack, true, |a, b| a as i32 * b as i32)
+        }
+        i32x4_extmul_low_i16x8_u => {
+            extmul::<u16, u32, 8, 4>(stack, false, |a, b| a as u32 * b as u32)
+        }
+        i32x4_extmul_high_i16x8_u => {
+            extmul::<u16, u32, 8, 4>(stack, true, |a, b| a as u32 * b as u32)
+        }
+
+        i64x2_abs => lanewise::<i64, 2>(stack, i64::wrapping_abs),
+        i64x2_neg => lanewise::<i64, 2>(stack, i64::wrapping_neg),
+        i64x2_all_true => all_true::<i64, 2>(stack),
+        i64x2_bitmask => bitmask::<i64, 2>(stack),
+        i64x2_extend_low_i32x4_s => extend::<i32, i64, 4, 2>(stack, false, |a| a as i64),
+        i64x2_extend_high_i32x4_s => extend::<i32, i64, 4, 2>(stack, true, |a| a as i64),
+        i64x2_extend_low_i32x4_u => extend::<u32, i64, 4, 2>(stack, false, |a| a as i64),
+        i64x2_extend_high_i32x4_u => extend::<u32, i64, 4, 2>(stack, true, |a| a as i64),
+        i64x2_shl => shift::<i64, 2>(stack, |a, n| a.wrapping_shl(n)),
+        i64x2_shr_s => shift::<i64, 2>(stack, |a, n| a.wrapping_shr(n)),
+        i64x2_shr_u => shift::<u64, 2>(stack, |a, n| a.wrapping_shr(n)),
+        i64x2_add => lanewise_binary::<i64, 2>(stack, i64::wrapping_add),
+        i64x2_sub => lanewise_binary::<i64, 2>(stack, i64::wrapping_sub),
+        i64x2_mul => lanewise_binary::<i64, 2>(stack, i64::wrapping_mul),
+        i64x2_extmul_low_i32x4_s => {
+            extmul::<i32, i64, 4, 2>(stack, false, |a, b| a as i64 * b as i64)
+        }
+        i64x2_extmul_high_i32x4_s => {
+            extmul::<i32, i64, 4, 2>(stack, true, |a, b| a as i64 * b as i64)
+        }
+        i64x2_extmul_low_i32x4_u => {
+            extmul::<u32, u64, 4, 2>(stack, false, |a, b| a as u64 * b as u64)
+        }
+        i64x2_extmul_high_i32x4_u => {
+            extmul::<u32, u64, 4, 2>(stack, true, |a, b| a as u64 * b as u64)
+        }
+
+        f32x4_abs => lanewise::<f32, 4>(stack, f32::abs),
+        f32x4_neg => lanewise::<f32, 4>(stack, |a| -a),
+        f32x4_sqrt => lanewise::<f32, 4>(stack, f32::sqrt),
+        f32x4_ceil => lanewise::<f32, 4>(stack, f32::ceil),
+        f32x4_floor => lanewise::<f32, 4>(stack, f32::floor),
+        f32x4_trunc => lanewise::<f32, 4>(stack, f32::trunc),
+        f32x4_nearest => lanewise::<f32, 4>(stack, f32::round_ties_even),
+        f32x4_add => lanewise_binary::<f32, 4>(stack, |a, b| a + b),
+        f32x4_sub => lanewise_binary::<f32, 4>(stack, |a, b| a - b),
+        f32x4_mul => lanewise_binary::<f32, 4>(stack, |a, b| a * b),
+        f32x4_div => lanewise_binary::<f32, 4>(stack, |a, b| a / b),
+        f32x4_min => lanewise_binary::<f32, 4>(stack, min_f32),
+        f32x4_max => lanewise_binary::<f32, 4>(stack, max_f32),
+        f32x4_pmin => lanewise_binary::<f32, 4>(stack, |a, b| if b < a { b } else { a }),
+        f32x4_pmax => lanewise_binary::<f32, 4>(stack, |a, b| if a < b { b } else { a }),
+
+        f64x2_abs => lanewise::<f64, 2>(stack, f64::abs),
+        f64x2_neg => lanewise::<f64, 2>(stack, |a| -a),
+        f64x2_sqrt => lanewise::<f64, 2>(stack, f64::sqrt),
+        f64x2_ceil => lanewise::<f64, 2>(stack, f64::ceil),
+        f64x2_floor => lanewise::<f64, 2>(stack, f64::floor),
+        f64x2_trunc => lanewise::<f64, 2>(stack, f64::trunc),
+        f64x2_nearest => lanewise::<f64, 2>(stack, f64::round_ties_even),
+        f64x2_add => lanewise_binary::<f64, 2>(stack, |a, b| a + b),
+        f64x2_sub => lanewise_binary::<f64, 2>(stack, |a, b| a - b),
+        f64x2_mul => lanewise_binary::<f64, 2>(stack, |a, b| a * b),
+        f64x2_div => lanewise_binary::<f64, 2>(stack, |a, b| a / b),
+        f64x2_min => lanewise_binary::<f64, 2>(stack, min_f64),
+        f64x2_max => lanewise_binary::<f64, 2>(stack, max_f64),
+        f64x2_pmin => lanewise_binary::<f64, 2>(stack, |a, b| if b < a { b } else { a }),
+        f64x2_pmax => lanewise_binary::<f64, 2>(stack, |a, b| if a < b { b } else { a }),
+
+        // Like the scalar saturating truncations these rely on Rust's saturating casts
+        i32x4_trunc_sat_f32x4_s => convert::<f32, i32, 4>(stack, |a| a as i32),
+        i32x4_trunc_sat_f32x4_u => convert::<f32, u32, 4>(stack, |a| a as u32),
+        f32x4_convert_i32x4_s => convert::<i32, f32, 4>(stack, |a| a as f32),
+        f32x4_convert_i32x4_u => convert::<u32, f32, 4>(stack, |a| a as f32),
+        i32x4_trunc_sat_f64x2_s_zero => unary(stack, |a: V128| {
+            let [low, high] = a.lanes::<f64, 2>();
+            V128::from_lanes([low as i32, high as i32, 0, 0])
+        }),
+        i32x4_trunc_sat_f64x2_u_zero => unary(stack, |a: V128| {
+            let [low, high] = a.lanes::<f64, 2>();
+            V128::from_lanes([low as u32, high as u32, 0, 0])
+        }),
+        f64x2_convert_low_i32x4_s => unary(stack, |a: V128| {
+            let [low, high, ..] = a.lanes::<i32, 4>();
+            V128::from_lanes([low as f64, high as f64])
+        }),
+        f64x2_convert_low_i32x4_u => unary(stack, |a: V128| {
+            let [low, high, ..] = a.lanes::<u32, 4>();
+            V128::from_lanes([low as f64, high as f64])
+        }),
+        f32x4_demote_f64x2_zero => unary(stack, |a: V128| {
+            let [low, high] = a.lanes::<f64, 2>();
+            V128::from_lanes([low as f32, high as f32, 0.0, 0.0])
+        }),
+        f64x2_promote_low_f32x4 => unary(stack, |a: V128| {
+            let [low, high, ..] = a.lanes::<f32, 4>();
+            V128::from_lanes([low as f64, high as f64])
+        }),
+
+        _ => return false,
+    }
+
+    true
+}
+
+fn lanewise<T: Lane, const N: usize>(stack: &mut Stack, operation: impl Fn(T) -> T) {
+    unary(stack, |a: V128| {
+        V128::from_lanes(a.lanes::<T, N>().map(operation))
+    });
+}
+
+fn lanewise_binary<T: Lane, const N: usize>(stack: &mut Stack, operation: impl Fn(T, T) -> T) {
+    binary(stack, |a: V128, b: V128| {
+        let (a, b) = (a.lanes::<T, N>(), b.lanes::<T, N>());
+        V128::from_lanes::<T, N>(array::from_fn(|i| operation(a[i], b[i])))
+    });
+}
+
+fn compare<T: Lane, M: Mask, const N: usize>(stack: &mut Stack, predicate: impl Fn(T, T) -> bool) {
+    binary(stack, |a: V128, b: V128| {
+        let (a, b) = (a.lanes::<T, N>(), b.lanes::<T, N>());
+        V128::from_lanes::<M, N>(array::from_fn(|i| M::mask(predicate(a[i], b[i]))))
+    });
+}
+
+/// Converts every lane to another type of the same width
+fn convert<T: Lane, U: Lane, const N: usize>(stack: &mut Stack, conversion: impl Fn(T) -> U) {
+    unary(stack, |a: V128| {
+        V128::from_lanes::<U, N>(a.lanes::<T, N>().map(conversion))
+    });
+}
+
+/// Shifts every lane by the scalar operand, modulo the lane width
+fn shift<T: Lane, const N: usize>(stack: &mut Stack, operation: impl Fn(T, u32) -> T) {
+    let amount = stack.pop::<i32>() as u32 % (T::BYTES as u32 * 8);
+    lanewise::<T, N>(stack, |a| operation(a, amount));
+}
+
+fn replace_lane<T: Lane, const N: usize, S: super::value::FromValue>(
+    stack: &mut Stack,
+    lane: u8,
+    conversion: impl Fn(S) -> T,
+) {
+    let value = conversion(stack.pop());
+    let mut lanes = stack.pop::<V128>().lanes::<T, N>();
+    lanes[lane as usize] = value;
+    stack.push(V128::from_lanes(lanes));
+}
+
+fn all_true<T: Lane + Default + PartialEq, const N: usize>(stack: &mut Stack) {
+    unary(stack, |a: V128| {
+        i32::from(a.lanes::<T, N>().iter().all(|lane| *lane != T::default()))
+    });
+}
+
+/// Collects the sign bits of the lanes, lane 0 becomes the lowest bit
+fn bitmask<T: Lane + Default + PartialOrd, const N: usize>(stack: &mut Stack) {
+    unary(stack, |a: V128| {
+        a.lanes::<T, N>()
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (i, lane)| {
+                mask | (i32::from(*lane < T::default()) << i)
+            })
+    });
+}
+
+/// Narrows the `N` lanes of both operands into the `M` lanes of the result, the first operand fills the low half
+fn narrow<T: Lane, U: Lane, const N: usize, const M: usize>(
+    stack: &mut Stack,
+    conversion: impl Fn(T) -> U,
+) {
+    binary(stack, |a: V128, b: V128| {
+        let (a, b) = (a.lanes::<T, N>(), b.lanes::<T, N>());
+        V128::from_lanes::<U, M>(array::from_fn(|i| match i < N {
+            true => conversion(a[i]),
+            false => conversion(b[i - N]),
+        }))
+    });
+}
+
+/// Widens the low or high half of the `N` lanes into the `M` lanes of the result
+fn extend<T: Lane, U: Lane, const N: usize, const M: usize>(
+    stack: &mut Stack,
+    high: bool,
+    conversion: impl Fn(T) -> U,
+) {
+    let start = if high { M } else { 0 };
+    unary(stack, |a: V128| {
+        let a = a.lanes::<T, N>();
+        V128::from_lanes::<U, M>(array::from_fn(|i| conversion(a[start + i])))
+    });
+}
+
+/// Multiplies the low or high halves of both operands into lanes twice as wide
+fn extmul<T: Lane, U: Lane, const N: usize, const M: usize>(
+    stack: &mut Stack,
+    high: bool,
+    multiplication: impl Fn(T, T) -> U,
+) {
+    let start = if high { M } else { 0 };
+    binary(stack, |a: V128, b: V128| {
+        let (a, b) = (a.lanes::<T, N>(), b.lanes::<T, N>());
+        V128::from_lanes::<U, M>(array::from_fn(|i| {
+            multiplication(a[start + i], b[start + i])
+        }))
+    });
+}
+
+/// Adds adjacent pairs of lanes into the `M` lanes of the result
+fn pairwise<T: Lane, U: Lane, const M: usize>(stack: &mut Stack, addition: impl Fn(T, T) -> U) {
+    unary(stack, |a: V128| {
+        let bytes = a.to_bytes();
+        V128::from_lanes::<U, M>(array::from_fn(|i| {
+            let lane = |j: usize| T::from_le(&bytes[j * T::BYTES..(j + 1) * T::BYTES]);
+            addition(lane(2 * i), lane(2 * i + 1))
+        }))
+    });
+}
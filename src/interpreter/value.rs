@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use swai_parser::types::{HeapType, NumberTypes, ReferenceTypes, ValueType, VectorTypes};
+
+use super::simd::V128;
+
+/// A value on the operand stack, in a local, global or table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    V128(V128),
+    /// The index of the referenced function, `None` for a null reference
+    FuncRef(Option<u32>),
+    /// A reference handed in by the host, `None` for a null reference
+    ExternRef(Option<u32>),
+    /// A caught exception, `None` for a null reference
+    ExnRef(Option<Arc<Exception>>),
+}
+
+/// A thrown exception, shared by the `exnref`s referring to it
+#[derive(Debug, PartialEq)]
+pub struct Exception {
+    tag: u32,
+    payload: Vec<Value>,
+}
+
+impl Exception {
+    pub(crate) fn new(tag: u32, payload: Vec<Value>) -> Exception {
+        Exception { tag, payload }
+    }
+
+    /// The index of the exception's tag in the tag index space
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// The values the exception carries, of the types of its tag's parameters
+    pub fn payload(&self) -> &[Value] {
+        &self.payload
+    }
+}
+
+impl Value {
+    /// The zero value of a type, which locals and tables are initialized with
+    pub fn default_of(ty: ValueType) -> Value {
+        match ty {
+            ValueType::NumType(NumberTypes::i32) => Value::I32(0),
+            ValueType::NumType(NumberTypes::i64) => Value::I64(0),
+            ValueType::NumType(NumberTypes::f32) => Value::F32(0.0),
+            ValueType::NumType(NumberTypes::f64) => Value::F64(0.0),
+            ValueType::VecType(VectorTypes::v128) => Value::V128(V128::default()),
+            // Non-null references have no default, validation makes sure they're set before they're read
+            ValueType::RefType(ty) => match ty.heap_type() {
+                HeapType::Func | HeapType::TypeIdx(_) => Value::FuncRef(None),
+                HeapType::Extern => Value::ExternRef(None),
+                HeapType::Exn => Value::ExnRef(None),
+            },
+        }
+    }
+
+    pub fn ty(&self) -> ValueType {
+        match self {
+            Value::I32(_) => ValueType::NumType(NumberTypes::i32),
+            Value::I64(_) => ValueType::NumType(NumberTypes::i64),
+            Value::F32(_) => ValueType::NumType(NumberTypes::f32),
+            Value::F64(_) => ValueType::NumType(NumberTypes::f64),
+            Value::V128(_) => ValueType::VecType(VectorTypes::v128),
+            Value::FuncRef(_) => ValueType::RefType(ReferenceTypes::funcref),
+            Value::ExternRef(_) => ValueType::RefType(ReferenceTypes::externref),
+            Value::ExnRef(_) => ValueType::RefType(ReferenceTypes::exnref),
+        }
+    }
+
+    /// Whether the value is a null reference
+    pub fn is_null(&self) -> bool {
+        matches!(
+            self,
+            Value::FuncRef(None) | Value::ExternRef(None) | Value::ExnRef(None)
+        )
+    }
+    /// An address into a memory or an index into a table with limits of `index_type`
+    pub(crate) fn address(address: u64, index_type: NumberTypes) -> Value {
+        match index_type {
+            NumberTypes::i64 => Value::I64(address as i64),
+            _ => Value::I32(address as i32),
+        }
+    }
+
+    /// The unsigned value of an `i32` or `i64`, the types memories and tables are addressed with
+    pub(crate) fn as_address(&self) -> Option<u64> {
+        match *self {
+            Value::I32(value) => Some(value as u32 as u64),
+            Value::I64(value) => Some(value as u64),
+            _ => None,
+        }
+    }
+}
+
+/// The Rust types values of one type can be converted from and to
+pub trait FromValue: Into<Value> {
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+macro_rules! impl_value_conversions {
+    ($($variant:ident($ty:ty)),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Value::$variant(value)
+                }
+            }
+
+            impl FromValue for $ty {
+                fn from_value(value: Value) -> Option<Self> {
+                    match value {
+                        Value::$variant(value) => Some(value),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_value_conversions!(I32(i32), I64(i64), F32(f32), F64(f64), V128(V128));
+
+/// The operand stack. Validation guarantees every instruction finds operands of the
+/// types it expects, so popping the wrong type is a bug in the interpreter
+#[derive(Debug, Default)]
+pub(crate) struct Stack {
+    values: Vec<Value>,
+}
+
+impl Stack {
+    pub fn push(&mut self, value: impl Into<Value>) {
+        self.values.push(value.into());
+    }
+
+    pub fn pop<T: FromValue>(&mut self) -> T {
+        T::from_value(self.pop_value()).expect("operand of the validated type")
+    }
+
+    /// Pops an `i32` or `i64` address, depending on the limits of the memory or table it's used for
+    pub fn pop_address(&mut self) -> u64 {
+        self.pop_value()
+            .as_address()
+            .expect("address of the validated type")
+    }
+
+    pub fn pop_value(&mut self) -> Value {
+        self.values.pop().expect("operand on the validated stack")
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Pops the top `count` values, keeping their order
+    pub fn pop_many(&mut self, count: usize) -> Vec<Value> {
+        self.values.split_off(self.values.len() - count)
+    }
+
+    pub fn extend(&mut self, values: impl IntoIterator<Item = Value>) {
+        self.values.extend(values);
+    }
+
+    /// Drops the values between `height` and the top `keep` values
+    pub fn unwind(&mut self, height: usize, keep: usize) {
+        let top = self.values.len() - keep;
+        self.values.drain(height..top);
+    }
+
+    pub fn into_values(self) -> Vec<Value> {
+        self.values
+    }
+}
@@ -0,0 +1,5 @@
+pub mod error;
+pub mod interpreter;
+
+pub use error::{Trap, WasmInterpreterError};
+pub use interpreter::{HostMemory, SharedMemory, Value, WasmEnvironment};
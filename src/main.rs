@@ -1,18 +1,33 @@
 use std::{error::Error, fs::File};
-use swai_parser::WasmModule;
+use swai::{Value, WasmEnvironment};
+use swai_parser::{wat::PrintOptions, WasmModule};
 
-use crate::interpreter::WasmEnvironment;
-
-mod error;
-mod interpreter;
 fn main() -> Result<(), Box<dyn Error>> {
     let mut add_file = File::open("./tests/asc_test.wasm")?;
 
     let module = WasmModule::from_file(&mut add_file)?;
-    println!("Module: \n{:#?}", module);
+    println!("Module: \n{}", module.to_wat(&PrintOptions::default())?);
 
     let mut memory: [u8; 2048] = [0; 2048];
-    let mut env = WasmEnvironment::new(module, &mut memory);
+    let mut env = WasmEnvironment::new(module, &mut memory)?;
+
+    // AssemblyScript strings are UTF-16, with their byte length stored right before them
+    env.define_function("env", "console.log", |memory, arguments| {
+        let [Value::I32(pointer)] = arguments else {
+            unreachable!("console.log takes a single i32")
+        };
+        let pointer = *pointer as usize;
+        let mut length = [0; 4];
+        memory.read(pointer - 4, &mut length)?;
+        let mut bytes = vec![0; u32::from_le_bytes(length) as usize];
+        memory.read(pointer, &mut bytes)?;
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        println!("{}", String::from_utf16_lossy(&units));
+        Ok(Vec::new())
+    });
 
     env.start()?;
 
@@ -0,0 +1,63 @@
+use swai::{Value, WasmEnvironment};
+use swai_parser::{
+    instructions::{Instructions, MemArg},
+    types::{FunctionType, GlobalType, Indecies, Limits, Mutability, NumberTypes, ValueType},
+    ModuleBuilder,
+};
+
+const I32: ValueType = ValueType::NumType(NumberTypes::i32);
+
+#[test]
+fn built_modules_run() {
+    let mut builder = ModuleBuilder::new();
+    let memory = builder.add_memory(Limits::new(1, None));
+    builder.add_data(
+        memory,
+        vec![Instructions::i32_const(4)],
+        42u32.to_le_bytes(),
+    );
+    let calls = builder.add_global(
+        GlobalType::new(I32, Mutability::Var),
+        vec![Instructions::i32_const(0)],
+    );
+
+    // Loads the value at the address and adds the number of calls so far to it
+    let load = builder.add_function(
+        FunctionType::new(vec![I32], vec![I32]),
+        &[I32],
+        vec![
+            Instructions::GlobalGet(Indecies::GlobalIdx(calls)),
+            Instructions::i32_const(1),
+            Instructions::i32_add,
+            Instructions::LocalTee(Indecies::LocalIdx(1)),
+            Instructions::GlobalSet(Indecies::GlobalIdx(calls)),
+            Instructions::LocalGet(Indecies::LocalIdx(0)),
+            Instructions::i32_load(MemArg {
+                align: 2,
+                offset: 0,
+            }),
+            Instructions::LocalGet(Indecies::LocalIdx(1)),
+            Instructions::i32_add,
+        ],
+    );
+    let start = builder.add_function(
+        FunctionType::new(vec![], vec![]),
+        &[],
+        vec![
+            Instructions::i32_const(4),
+            Instructions::Call(Indecies::FuncIdx(load)),
+            Instructions::Drop,
+        ],
+    );
+    builder.export_function("load", load).start(start);
+
+    let mut memory = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(builder.build(), &mut memory).unwrap();
+    // The start function calls it once
+    environment.start().unwrap();
+    assert_eq!(
+        environment.invoke("load", &[Value::I32(4)]).unwrap(),
+        [Value::I32(44)]
+    );
+    assert_eq!(&environment.memory().unwrap()[4..8], 42u32.to_le_bytes());
+}
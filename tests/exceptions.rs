@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use swai::{Trap, Value, WasmEnvironment, WasmInterpreterError};
+use swai_parser::WasmModule;
+
+const SOURCE: &str = r#"
+    (module
+      (tag $error (param i32))
+      (tag $pair (param i64 f32))
+      (func $fail (param i32)
+        (if (i32.eqz (local.get 0)) (then (return)))
+        (throw $error (local.get 0)))
+      (func $fail_with_pair (throw $pair (i64.const 5) (f32.const 1.5)))
+
+      (func (export "catch") (param i32) (result i32)
+        (block $caught (result i32)
+          (try_table (catch $error $caught)
+            (call $fail (local.get 0)))
+          (i32.const -1)))
+
+      (func (export "catch_all") (result i32)
+        (block $any
+          (block $error (result i32)
+            (try_table (catch $error $error) (catch_all $any)
+              (call $fail_with_pair))
+            (return (i32.const 0)))
+          (return (i32.const 2)))
+        (i32.const 1))
+
+      (func (export "rethrow") (param i32) (result i32)
+        (block $outer (result i32)
+          (try_table (catch $error $outer)
+            (block $caught (result i32 exnref)
+              (try_table (catch_ref $error $caught)
+                (call $fail (local.get 0)))
+              (return (i32.const -1)))
+            ;; Drop the payload and throw the caught exception again
+            (local.set 0 (i32.const 100))
+            (throw_ref)
+            (drop))
+          (unreachable)))
+
+      (func (export "uncaught") (param i32) (call $fail (local.get 0)))
+      (func (export "uncaught_pair") (call $fail_with_pair))
+      (func (export "null") (throw_ref (ref.null exn)))
+
+      (func (export "catch_ref") (param i32) (result exnref)
+        (block $caught (result exnref)
+          (try_table (catch_all_ref $caught)
+            (call $fail (local.get 0)))
+          (ref.null exn)))
+      (func (export "throw_ref") (param exnref) (throw_ref (local.get 0))))
+"#;
+
+fn invoke(name: &str, arguments: &[Value]) -> Result<Vec<Value>, WasmInterpreterError> {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    let mut memory = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(module, &mut memory)?;
+    environment.invoke(name, arguments)
+}
+
+fn trap(name: &str, arguments: &[Value]) -> Trap {
+    match invoke(name, arguments) {
+        Err(WasmInterpreterError::Trap(trap)) => trap,
+        result => panic!("{name} returned {result:?}"),
+    }
+}
+
+#[test]
+fn exceptions_unwind_to_the_catch_clause() {
+    assert_eq!(invoke("catch", &[Value::I32(7)]).unwrap(), [Value::I32(7)]);
+    assert_eq!(invoke("catch", &[Value::I32(0)]).unwrap(), [Value::I32(-1)]);
+    assert_eq!(invoke("catch_all", &[]).unwrap(), [Value::I32(1)]);
+}
+
+#[test]
+fn caught_exceptions_are_thrown_again() {
+    assert_eq!(
+        invoke("rethrow", &[Value::I32(9)]).unwrap(),
+        [Value::I32(9)]
+    );
+    assert_eq!(
+        invoke("rethrow", &[Value::I32(0)]).unwrap(),
+        [Value::I32(-1)]
+    );
+}
+
+#[test]
+fn uncaught_exceptions_trap_with_their_tag_and_payload() {
+    assert_eq!(
+        trap("uncaught", &[Value::I32(3)]),
+        Trap::UncaughtException {
+            tag: 0,
+            payload: vec![Value::I32(3)]
+        }
+    );
+    assert_eq!(
+        trap("uncaught_pair", &[]),
+        Trap::UncaughtException {
+            tag: 1,
+            payload: vec![Value::I64(5), Value::F32(1.5)]
+        }
+    );
+    assert_eq!(trap("null", &[]), Trap::NullExceptionReference);
+}
+
+#[test]
+fn exception_references_outlive_the_call_that_caught_them() {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    let mut memory = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(module, &mut memory).unwrap();
+
+    let mut results = environment.invoke("catch_ref", &[Value::I32(4)]).unwrap();
+    let Some(Value::ExnRef(Some(exception))) = results.pop() else {
+        panic!("expected an exception, got {results:?}");
+    };
+    assert_eq!(
+        (exception.tag(), exception.payload()),
+        (0, [Value::I32(4)].as_slice())
+    );
+    // The environment doesn't hold on to caught exceptions
+    assert_eq!(Arc::strong_count(&exception), 1);
+
+    assert!(matches!(
+        environment.invoke("throw_ref", &[Value::ExnRef(Some(exception))]),
+        Err(WasmInterpreterError::Trap(Trap::UncaughtException { tag: 0, payload }))
+            if payload == [Value::I32(4)]
+    ));
+}
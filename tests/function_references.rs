@@ -0,0 +1,115 @@
+use swai::{Trap, Value, WasmEnvironment, WasmInterpreterError};
+use swai_parser::WasmModule;
+
+const SOURCE: &str = r#"
+    (module
+      (type $unary (func (param i32) (result i32)))
+      (table $functions (ref null $unary) (elem $double $square))
+      (func $double (type $unary) (i32.mul (local.get 0) (i32.const 2)))
+      (func $square (type $unary) (i32.mul (local.get 0) (local.get 0)))
+      (elem declare func $double)
+
+      (func $get (param i32) (result (ref null $unary)) (table.get $functions (local.get 0)))
+      (func (export "apply") (param i32 i32) (result i32)
+        (call_ref $unary (local.get 1) (call $get (local.get 0))))
+      (func (export "apply_null") (result i32)
+        (call_ref $unary (i32.const 1) (ref.null $unary)))
+      (func (export "tail_apply") (param i32 i32) (result i32)
+        (return_call_ref $unary (local.get 1) (call $get (local.get 0))))
+
+      ;; -1 for null, the result of the function otherwise
+      (func (export "apply_or_default") (param (ref null $unary) i32) (result i32)
+        (block $null
+          (return (call_ref $unary (local.get 1) (br_on_null $null (local.get 0)))))
+        (i32.const -1))
+      (func (export "is_set") (param funcref) (result i32)
+        (block $set (result (ref func))
+          (br_on_non_null $set (local.get 0))
+          (return (i32.const 0)))
+        (drop)
+        (i32.const 1))
+      (func (export "as_non_null") (param externref) (result (ref extern))
+        (local (ref extern))
+        (local.set 1 (ref.as_non_null (local.get 0)))
+        (local.get 1))
+      (func (export "double") (result (ref $unary)) (ref.func $double)))
+"#;
+
+fn invoke(name: &str, arguments: &[Value]) -> Result<Vec<Value>, WasmInterpreterError> {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    let mut memory = vec![];
+    let mut environment = WasmEnvironment::new(module, &mut memory)?;
+    environment.invoke(name, arguments)
+}
+
+fn trap(name: &str, arguments: &[Value]) -> Trap {
+    match invoke(name, arguments) {
+        Err(WasmInterpreterError::Trap(trap)) => trap,
+        result => panic!("{name} returned {result:?}"),
+    }
+}
+
+#[test]
+fn function_references_are_called() {
+    assert_eq!(
+        invoke("apply", &[Value::I32(0), Value::I32(7)]).unwrap(),
+        [Value::I32(14)]
+    );
+    assert_eq!(
+        invoke("tail_apply", &[Value::I32(1), Value::I32(7)]).unwrap(),
+        [Value::I32(49)]
+    );
+    assert_eq!(trap("apply_null", &[]), Trap::NullFunctionReference);
+    assert_eq!(
+        trap("apply", &[Value::I32(2), Value::I32(7)]),
+        Trap::OutOfBoundsTableAccess
+    );
+}
+
+#[test]
+fn null_branches() {
+    let double = Value::FuncRef(Some(0));
+    assert_eq!(invoke("double", &[]).unwrap(), [Value::FuncRef(Some(0))]);
+    assert_eq!(
+        invoke("apply_or_default", &[double.clone(), Value::I32(3)]).unwrap(),
+        [Value::I32(6)]
+    );
+    assert_eq!(
+        invoke("apply_or_default", &[Value::FuncRef(None), Value::I32(3)]).unwrap(),
+        [Value::I32(-1)]
+    );
+    assert_eq!(invoke("is_set", &[double]).unwrap(), [Value::I32(1)]);
+    assert_eq!(
+        invoke("is_set", &[Value::FuncRef(None)]).unwrap(),
+        [Value::I32(0)]
+    );
+
+    assert_eq!(
+        invoke("as_non_null", &[Value::ExternRef(Some(4))]).unwrap(),
+        [Value::ExternRef(Some(4))]
+    );
+    assert_eq!(
+        trap("as_non_null", &[Value::ExternRef(None)]),
+        Trap::NullReference
+    );
+}
+
+#[test]
+fn typed_arguments_are_checked() {
+    // $square has the type the parameter asks for, function 2 doesn't
+    assert_eq!(
+        invoke(
+            "apply_or_default",
+            &[Value::FuncRef(Some(1)), Value::I32(3)]
+        )
+        .unwrap(),
+        [Value::I32(9)]
+    );
+    assert!(matches!(
+        invoke(
+            "apply_or_default",
+            &[Value::FuncRef(Some(2)), Value::I32(3)]
+        ),
+        Err(WasmInterpreterError::TypeMismatch { .. })
+    ));
+}
@@ -0,0 +1,417 @@
+use std::{cell::RefCell, rc::Rc};
+
+use swai::{
+    interpreter::{MAX_TABLE_ELEMENTS, PAGE_SIZE},
+    Trap, Value, WasmEnvironment, WasmInterpreterError,
+};
+use swai_parser::WasmModule;
+
+fn invoke(
+    source: &str,
+    name: &str,
+    arguments: &[Value],
+) -> Result<Vec<Value>, WasmInterpreterError> {
+    let module = WasmModule::from_wat(source).unwrap();
+    let mut memory = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(module, &mut memory)?;
+    environment.invoke(name, arguments)
+}
+
+#[test]
+fn start_calls_host_functions() {
+    let module = WasmModule::from_bytes(include_bytes!("asc_test.wasm"))
+        .unwrap()
+        .into_owned();
+    let mut memory = [0; 2048];
+    let mut environment = WasmEnvironment::new(module, &mut memory).unwrap();
+
+    let logged = Rc::new(RefCell::new(Vec::new()));
+    let log = Rc::clone(&logged);
+    environment.define_function("env", "console.log", move |memory, arguments| {
+        let [Value::I32(pointer)] = arguments else {
+            panic!("unexpected arguments {arguments:?}")
+        };
+        let pointer = *pointer as usize;
+        let mut length = [0; 4];
+        memory.read(pointer - 4, &mut length)?;
+        let mut bytes = vec![0; u32::from_le_bytes(length) as usize];
+        memory.read(pointer, &mut bytes)?;
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        log.borrow_mut().push(String::from_utf16_lossy(&units));
+        Ok(Vec::new())
+    });
+    environment.start().unwrap();
+
+    assert_eq!(*logged.borrow(), ["Hello World"]);
+}
+
+#[test]
+fn undefined_imports_fail_when_called() {
+    let module = WasmModule::from_bytes(include_bytes!("asc_test.wasm"))
+        .unwrap()
+        .into_owned();
+    let mut memory = [0; 2048];
+    let mut environment = WasmEnvironment::new(module, &mut memory).unwrap();
+
+    assert!(matches!(
+        environment.start(),
+        Err(WasmInterpreterError::UndefinedImport { module, name }) if module == "env" && name == "console.log"
+    ));
+}
+
+#[test]
+fn loops_and_branches() {
+    let source = r#"
+        (module
+          (func (export "factorial") (param i64) (result i64) (local i64)
+            (local.set 1 (i64.const 1))
+            (block $done
+              (loop $next
+                (br_if $done (i64.eqz (local.get 0)))
+                (local.set 1 (i64.mul (local.get 1) (local.get 0)))
+                (local.set 0 (i64.sub (local.get 0) (i64.const 1)))
+                (br $next)))
+            (local.get 1))
+          (func (export "classify") (param i32) (result i32)
+            (block $two
+              (block $one
+                (block $zero
+                  (br_table $zero $one $two (local.get 0)))
+                (return (i32.const 100)))
+              (return (i32.const 101)))
+            (i32.const 102)))
+    "#;
+
+    assert_eq!(
+        invoke(source, "factorial", &[Value::I64(20)]).unwrap(),
+        [Value::I64(2432902008176640000)]
+    );
+    for (argument, result) in [(0, 100), (1, 101), (2, 102), (7, 102)] {
+        assert_eq!(
+            invoke(source, "classify", &[Value::I32(argument)]).unwrap(),
+            [Value::I32(result)]
+        );
+    }
+}
+
+#[test]
+fn branches_carry_values_out_of_blocks() {
+    let source = r#"
+        (module
+          (func (export "run") (param i32) (result i32)
+            (i32.add
+              (i32.const 1)
+              (block (result i32)
+                (drop (br_if 0 (i32.const 10) (local.get 0)))
+                (i32.const 20)))))
+    "#;
+
+    assert_eq!(
+        invoke(source, "run", &[Value::I32(1)]).unwrap(),
+        [Value::I32(11)]
+    );
+    assert_eq!(
+        invoke(source, "run", &[Value::I32(0)]).unwrap(),
+        [Value::I32(21)]
+    );
+}
+
+#[test]
+fn calls_and_indirect_calls() {
+    let source = r#"
+        (module
+          (type $binary (func (param i32 i32) (result i32)))
+          (table 3 funcref)
+          (elem (i32.const 0) $add $sub)
+          (func $add (type $binary) (i32.add (local.get 0) (local.get 1)))
+          (func $sub (type $binary) (i32.sub (local.get 0) (local.get 1)))
+          (func $nullary (result i32) (i32.const 0))
+          (func (export "apply") (param i32 i32 i32) (result i32)
+            (call_indirect (type $binary) (local.get 1) (local.get 2) (local.get 0)))
+          (func (export "fib") (param i32) (result i32)
+            (if (result i32) (i32.lt_u (local.get 0) (i32.const 2))
+              (then (local.get 0))
+              (else
+                (call $add
+                  (call 4 (i32.sub (local.get 0) (i32.const 1)))
+                  (call 4 (i32.sub (local.get 0) (i32.const 2))))))))
+    "#;
+    let apply = |index| {
+        invoke(
+            source,
+            "apply",
+            &[Value::I32(index), Value::I32(7), Value::I32(3)],
+        )
+    };
+
+    assert_eq!(apply(0).unwrap(), [Value::I32(10)]);
+    assert_eq!(apply(1).unwrap(), [Value::I32(4)]);
+    assert!(matches!(
+        apply(2),
+        Err(WasmInterpreterError::Trap(Trap::UninitializedElement))
+    ));
+    assert!(matches!(
+        apply(3),
+        Err(WasmInterpreterError::Trap(Trap::UndefinedElement))
+    ));
+    assert_eq!(
+        invoke(source, "fib", &[Value::I32(20)]).unwrap(),
+        [Value::I32(6765)]
+    );
+}
+
+#[test]
+fn indirect_calls_check_the_signature() {
+    let source = r#"
+        (module
+          (table funcref (elem $nullary))
+          (func $nullary (result i32) (i32.const 0))
+          (func (export "run") (result i32)
+            (call_indirect (param i32) (result i32) (i32.const 1) (i32.const 0))))
+    "#;
+
+    assert!(matches!(
+        invoke(source, "run", &[]),
+        Err(WasmInterpreterError::Trap(Trap::IndirectCallTypeMismatch))
+    ));
+}
+
+#[test]
+fn deep_recursion_exhausts_the_call_stack() {
+    let source = r#"
+        (module
+          (func $down (export "down") (param i32) (result i32)
+            (if (result i32) (i32.eqz (local.get 0))
+              (then (i32.const 0))
+              (else (call $down (i32.sub (local.get 0) (i32.const 1)))))))
+    "#;
+
+    assert_eq!(
+        invoke(source, "down", &[Value::I32(50_000)]).unwrap(),
+        [Value::I32(0)]
+    );
+    assert!(matches!(
+        invoke(source, "down", &[Value::I32(1_000_000)]),
+        Err(WasmInterpreterError::Trap(Trap::CallStackExhausted))
+    ));
+}
+
+#[test]
+fn numeric_traps() {
+    let source = r#"
+        (module
+          (func (export "div") (param i32 i32) (result i32) (i32.div_s (local.get 0) (local.get 1)))
+          (func (export "trunc") (param f64) (result i32) (i32.trunc_f64_u (local.get 0)))
+          (func (export "unreachable") unreachable))
+    "#;
+    let trap = |name, arguments: &[Value]| match invoke(source, name, arguments) {
+        Err(WasmInterpreterError::Trap(trap)) => trap,
+        result => panic!("{name} returned {result:?}"),
+    };
+
+    assert_eq!(
+        trap("div", &[Value::I32(1), Value::I32(0)]),
+        Trap::IntegerDivideByZero
+    );
+    assert_eq!(
+        trap("div", &[Value::I32(i32::MIN), Value::I32(-1)]),
+        Trap::IntegerOverflow
+    );
+    assert_eq!(
+        trap("trunc", &[Value::F64(f64::NAN)]),
+        Trap::InvalidConversionToInteger
+    );
+    assert_eq!(trap("trunc", &[Value::F64(-1.0)]), Trap::IntegerOverflow);
+    assert_eq!(
+        invoke(source, "trunc", &[Value::F64(-0.9)]).unwrap(),
+        [Value::I32(0)]
+    );
+    assert_eq!(
+        invoke(source, "trunc", &[Value::F64(4294967295.9)]).unwrap(),
+        [Value::I32(-1)]
+    );
+    assert_eq!(trap("unreachable", &[]), Trap::Unreachable);
+}
+
+#[test]
+fn memory_and_globals() {
+    let source = r#"
+        (module
+          (memory 1)
+          (global $counter (mut i32) (i32.const 40))
+          (data (i32.const 8) "\01\02\03\04")
+          (data $passive "\aa\bb")
+          (func (export "run") (result i32 )
+            (global.set $counter (i32.add (global.get $counter) (i32.const 2)))
+            (memory.init $passive (i32.const 100) (i32.const 0) (i32.const 2))
+            (memory.copy (i32.const 200) (i32.const 8) (i32.const 4))
+            (memory.fill (i32.const 202) (i32.const 0xff) (i32.const 1))
+            (i32.add
+              (global.get $counter)
+              (i32.add (i32.load (i32.const 200)) (i32.load16_u (i32.const 100))))))
+    "#;
+
+    assert_eq!(
+        invoke(source, "run", &[]).unwrap(),
+        [Value::I32(42 + 0x04ff0201 + 0xbbaa)]
+    );
+}
+
+#[test]
+fn owned_memories_grow_up_to_their_maximum() {
+    let source = r#"
+        (module
+          (memory 1 3)
+          (data (i32.const 0) "\2a")
+          (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+          (func (export "load") (param i32) (result i32) (i32.load8_u (local.get 0))))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    let mut environment = WasmEnvironment::with_owned_memory(module).unwrap();
+
+    assert_eq!(environment.memory().map(<[u8]>::len), Some(PAGE_SIZE));
+    assert_eq!(
+        environment.invoke("grow", &[Value::I32(2)]).unwrap(),
+        [Value::I32(1)]
+    );
+    assert_eq!(
+        environment.invoke("grow", &[Value::I32(1)]).unwrap(),
+        [Value::I32(-1)]
+    );
+    assert_eq!(environment.memory().map(<[u8]>::len), Some(3 * PAGE_SIZE));
+    assert_eq!(
+        environment
+            .invoke("load", &[Value::I32(2 * PAGE_SIZE as i32)])
+            .unwrap(),
+        [Value::I32(0)]
+    );
+    assert_eq!(
+        environment.invoke("load", &[Value::I32(0)]).unwrap(),
+        [Value::I32(42)]
+    );
+}
+
+#[test]
+fn borrowed_memories_dont_grow() {
+    let source =
+        r#"(module (memory 1) (func (export "grow") (result i32) (memory.grow (i32.const 1))))"#;
+
+    assert_eq!(invoke(source, "grow", &[]).unwrap(), [Value::I32(-1)]);
+}
+
+#[test]
+fn tables_are_limited_in_size() {
+    let module = WasmModule::from_wat("(module (table 4294967295 funcref))").unwrap();
+    let mut memory = [];
+    assert!(matches!(
+        WasmEnvironment::new(module, &mut memory),
+        Err(WasmInterpreterError::AllocationFailed {
+            size: 4294967295,
+            ..
+        })
+    ));
+
+    let source = r#"
+        (module
+          (table 1 funcref)
+          (func (export "grow") (param i32) (result i32)
+            (table.grow (ref.null func) (local.get 0))))
+    "#;
+    assert_eq!(
+        invoke(source, "grow", &[Value::I32(MAX_TABLE_ELEMENTS as i32)]).unwrap(),
+        [Value::I32(-1)]
+    );
+    assert_eq!(
+        invoke(source, "grow", &[Value::I32(2)]).unwrap(),
+        [Value::I32(1)]
+    );
+}
+
+#[test]
+fn arguments_are_type_checked() {
+    let source = r#"(module (func (export "id") (param i32) (result i32) (local.get 0)))"#;
+
+    assert!(matches!(
+        invoke(source, "id", &[Value::I64(1)]),
+        Err(WasmInterpreterError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        invoke(source, "missing", &[]),
+        Err(WasmInterpreterError::UnknownExport(name)) if name == "missing"
+    ));
+}
+
+#[test]
+fn functions_and_blocks_with_several_values() {
+    let source = r#"
+        (module
+          (func $divmod (param i32 i32) (result i32 i32)
+            (i32.div_u (local.get 0) (local.get 1))
+            (i32.rem_u (local.get 0) (local.get 1)))
+          (func (export "divmod") (param i32 i32) (result i32 i32)
+            (call $divmod (local.get 0) (local.get 1)))
+          (func (export "swap") (param i32 i64) (result i64 i32)
+            (local.get 0)
+            (local.get 1)
+            (block (param i32 i64) (result i64 i32)
+              (local.set 1)
+              (local.set 0)
+              (local.get 1)
+              (local.get 0)))
+          (func (export "sum") (param i32) (result i32)
+            ;; Counts down with the running total and the counter as loop params
+            (i32.const 0)
+            (local.get 0)
+            (loop $next (param i32 i32) (result i32)
+              (local.set 0)
+              (i32.add (local.get 0))
+              (local.get 0)
+              (if (param i32 i32) (result i32) (i32.eqz (local.get 0))
+                (then (drop))
+                (else
+                  (drop)
+                  (i32.sub (local.get 0) (i32.const 1))
+                  (br $next))))))
+    "#;
+
+    assert_eq!(
+        invoke(source, "divmod", &[Value::I32(17), Value::I32(5)]).unwrap(),
+        [Value::I32(3), Value::I32(2)]
+    );
+    assert_eq!(
+        invoke(source, "swap", &[Value::I32(1), Value::I64(2)]).unwrap(),
+        [Value::I64(2), Value::I32(1)]
+    );
+    assert_eq!(
+        invoke(source, "sum", &[Value::I32(100)]).unwrap(),
+        [Value::I32(5050)]
+    );
+}
+
+#[test]
+fn host_functions_return_several_values() {
+    let source = r#"
+        (module
+          (import "env" "pair" (func $pair (result f64 i32)))
+          (func (export "run") (result f64)
+            (call $pair)
+            (f64.mul (f64.convert_i32_s))))
+    "#;
+    let module = WasmModule::from_wat(source).unwrap();
+    let mut memory = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(module, &mut memory).unwrap();
+    environment.define_function("env", "pair", |_, _| {
+        Ok(vec![Value::F64(1.5), Value::I32(3)])
+    });
+
+    assert_eq!(environment.invoke("run", &[]).unwrap(), [Value::F64(4.5)]);
+
+    environment.define_function("env", "pair", |_, _| Ok(vec![Value::I32(3)]));
+    assert!(matches!(
+        environment.invoke("run", &[]),
+        Err(WasmInterpreterError::TypeMismatch { .. })
+    ));
+}
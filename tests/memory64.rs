@@ -0,0 +1,85 @@
+use swai::{Trap, Value, WasmEnvironment, WasmInterpreterError};
+use swai_parser::WasmModule;
+
+const SOURCE: &str = r#"
+    (module
+      (memory i64 1)
+      (table $functions i64 2 funcref)
+      (elem (table $functions) (i64.const 1) func $seven)
+      (data (i64.const 16) "\2a")
+      (func $seven (result i32) (i32.const 7))
+
+      (func (export "load") (param i64) (result i32) (i32.load8_u offset=16 (local.get 0)))
+      (func (export "store") (param i64 i64)
+        (i64.store (local.get 0) (local.get 1)))
+      (func (export "sizes") (result i64 i64 i64)
+        (memory.size)
+        (memory.grow (i64.const 0))
+        (memory.grow (i64.const 1)))
+      (func (export "bulk") (result i64)
+        (memory.fill (i64.const 32) (i32.const 0xff) (i64.const 4))
+        (memory.copy (i64.const 40) (i64.const 30) (i64.const 8))
+        (i64.load (i64.const 40)))
+      (func (export "fill_out_of_bounds") (memory.fill (i64.const 0x1_0000_0000) (i32.const 0) (i64.const 0)))
+
+      (func (export "table") (result i64 i64 i32)
+        (table.size $functions)
+        (table.grow $functions (ref.null func) (i64.const 3))
+        (call_indirect $functions (result i32) (i64.const 1))))
+"#;
+
+fn invoke(name: &str, arguments: &[Value]) -> Result<Vec<Value>, WasmInterpreterError> {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    let mut memory = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(module, &mut memory)?;
+    environment.invoke(name, arguments)
+}
+
+fn trap(name: &str, arguments: &[Value]) -> Trap {
+    match invoke(name, arguments) {
+        Err(WasmInterpreterError::Trap(trap)) => trap,
+        result => panic!("{name} returned {result:?}"),
+    }
+}
+
+#[test]
+fn memories_are_addressed_with_i64() {
+    assert_eq!(invoke("load", &[Value::I64(0)]).unwrap(), [Value::I32(42)]);
+    assert_eq!(
+        invoke("sizes", &[]).unwrap(),
+        [Value::I64(1), Value::I64(1), Value::I64(-1)]
+    );
+    assert_eq!(
+        invoke("bulk", &[]).unwrap(),
+        [Value::I64(0x0000_ffff_ffff_0000)]
+    );
+}
+
+#[test]
+fn addresses_beyond_32_bits_trap() {
+    assert_eq!(
+        trap("load", &[Value::I64(0x1_0000_0000)]),
+        Trap::OutOfBoundsMemoryAccess
+    );
+    // Adding the offset to the address overflows
+    assert_eq!(
+        trap("load", &[Value::I64(-8)]),
+        Trap::OutOfBoundsMemoryAccess
+    );
+    assert_eq!(
+        trap("store", &[Value::I64(65532), Value::I64(0)]),
+        Trap::OutOfBoundsMemoryAccess
+    );
+    assert_eq!(
+        trap("fill_out_of_bounds", &[]),
+        Trap::OutOfBoundsMemoryAccess
+    );
+}
+
+#[test]
+fn tables_are_indexed_with_i64() {
+    assert_eq!(
+        invoke("table", &[]).unwrap(),
+        [Value::I64(2), Value::I64(2), Value::I32(7)]
+    );
+}
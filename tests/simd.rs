@@ -0,0 +1,349 @@
+use swai::{interpreter::V128, Value, WasmEnvironment};
+use swai_parser::WasmModule;
+
+/// Runs a function with the given parameters and body, exported as "run"
+fn run(params: &str, result: &str, body: &str, arguments: &[Value]) -> Value {
+    let source = format!(
+        r#"(module (memory 1) (func (export "run") (param {params}) (result {result}) {body}))"#
+    );
+    let module = WasmModule::from_wat(&source).unwrap();
+    let mut memory = vec![0; 65536];
+    for (i, byte) in memory.iter_mut().enumerate().take(32) {
+        *byte = (i as u8).wrapping_mul(0x11);
+    }
+
+    let mut environment = WasmEnvironment::new(module, &mut memory).unwrap();
+    environment.invoke("run", arguments).unwrap().remove(0)
+}
+
+fn unary(instruction: &str, a: V128) -> V128 {
+    match run(
+        "v128",
+        "v128",
+        &format!("local.get 0 {instruction}"),
+        &[Value::V128(a)],
+    ) {
+        Value::V128(result) => result,
+        result => panic!("{instruction} returned {result:?}"),
+    }
+}
+
+fn binary(instruction: &str, a: V128, b: V128) -> V128 {
+    let body = format!("local.get 0 local.get 1 {instruction}");
+    match run(
+        "v128 v128",
+        "v128",
+        &body,
+        &[Value::V128(a), Value::V128(b)],
+    ) {
+        Value::V128(result) => result,
+        result => panic!("{instruction} returned {result:?}"),
+    }
+}
+
+fn i8x16(lanes: [i8; 16]) -> V128 {
+    V128::from_lanes(lanes)
+}
+
+fn i16x8(lanes: [i16; 8]) -> V128 {
+    V128::from_lanes(lanes)
+}
+
+fn i32x4(lanes: [i32; 4]) -> V128 {
+    V128::from_lanes(lanes)
+}
+
+fn f32x4(lanes: [f32; 4]) -> V128 {
+    V128::from_lanes(lanes)
+}
+
+/// Floats are compared by their bits, so NaNs and the sign of zero are checked too
+fn f32_bits(vector: V128) -> [u32; 4] {
+    vector.lanes()
+}
+
+#[test]
+fn integer_arithmetic_wraps_and_saturates() {
+    let a = i8x16([127, -128, 100, -100, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+    let b = i8x16([1, -1, 100, -100, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]);
+
+    assert_eq!(
+        binary("i8x16.add", a, b),
+        i8x16([-128, 127, -56, 56, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13])
+    );
+    assert_eq!(
+        binary("i8x16.add_sat_s", a, b),
+        i8x16([127, -128, 127, -128, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13])
+    );
+    assert_eq!(
+        binary("i8x16.sub_sat_u", b, a).lanes::<u8, 16>(),
+        [0, 127, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(
+        binary(
+            "i8x16.avgr_u",
+            V128::from_lanes([255u8, 0, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            V128::from_lanes([255u8, 1, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+        )
+        .lanes::<u8, 16>()[..4],
+        [255, 1, 4, 4]
+    );
+    assert_eq!(
+        binary(
+            "i16x8.q15mulr_sat_s",
+            i16x8([-32768, 16384, -16384, 1, 0, 0, 0, 0]),
+            i16x8([-32768, 16384, 16384, 1, 0, 0, 0, 0])
+        ),
+        i16x8([32767, 8192, -8192, 0, 0, 0, 0, 0])
+    );
+    assert_eq!(
+        binary(
+            "i32x4.dot_i16x8_s",
+            i16x8([1, 2, 3, 4, -32768, -32768, 5, -6]),
+            i16x8([5, 6, 7, 8, -32768, -32768, 1, 1])
+        ),
+        i32x4([17, 53, i32::MIN, -1])
+    );
+    assert_eq!(
+        unary(
+            "i8x16.popcnt",
+            i8x16([0, 1, 3, 7, 15, 31, 63, 127, -1, 0, 0, 0, 0, 0, 0, 0])
+        ),
+        i8x16([0, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0, 0, 0, 0])
+    );
+    assert_eq!(
+        unary("i32x4.abs", i32x4([i32::MIN, -5, 5, 0])),
+        i32x4([i32::MIN, 5, 5, 0])
+    );
+}
+
+#[test]
+fn lanes_change_width() {
+    assert_eq!(
+        binary(
+            "i8x16.narrow_i16x8_u",
+            i16x8([-5, 300, 255, 0, 1, 2, 3, 4]),
+            i16x8([-32768, 32767, 128, 127, 5, 6, 7, 8])
+        )
+        .lanes::<u8, 16>(),
+        [0, 255, 255, 0, 1, 2, 3, 4, 0, 255, 128, 127, 5, 6, 7, 8]
+    );
+    assert_eq!(
+        unary(
+            "i16x8.extend_high_i8x16_s",
+            i8x16([0, 0, 0, 0, 0, 0, 0, 0, -1, 2, -128, 127, 0, 0, 0, 5])
+        ),
+        i16x8([-1, 2, -128, 127, 0, 0, 0, 5])
+    );
+    assert_eq!(
+        unary(
+            "i16x8.extadd_pairwise_i8x16_u",
+            i8x16([-1, -1, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+        ),
+        i16x8([510, 3, 0, 0, 0, 0, 0, 0])
+    );
+    assert_eq!(
+        binary(
+            "i64x2.extmul_high_i32x4_u",
+            i32x4([0, 0, -1, 3]),
+            i32x4([0, 0, -1, -2])
+        )
+        .lanes::<u64, 2>(),
+        [0xFFFFFFFE_00000001, 3 * 0xFFFFFFFE]
+    );
+}
+
+#[test]
+fn comparisons_produce_masks() {
+    let a = i32x4([-1, 0, 5, i32::MIN]);
+    let b = i32x4([1, 0, 4, i32::MAX]);
+
+    assert_eq!(binary("i32x4.lt_s", a, b), i32x4([-1, 0, 0, -1]));
+    assert_eq!(binary("i32x4.lt_u", a, b), i32x4([0, 0, 0, 0]));
+    assert_eq!(binary("i32x4.eq", a, b), i32x4([0, -1, 0, 0]));
+    assert_eq!(
+        binary(
+            "f32x4.ge",
+            f32x4([f32::NAN, 0.0, 1.0, -1.0]),
+            f32x4([1.0, -0.0, f32::INFINITY, -1.0])
+        ),
+        i32x4([0, -1, 0, -1])
+    );
+}
+
+#[test]
+fn floats_follow_wasm_semantics() {
+    let a = f32x4([-0.0, f32::NAN, 1.0, 2.0]);
+    let b = f32x4([0.0, 1.0, f32::NAN, -3.0]);
+
+    let min = f32_bits(binary("f32x4.min", a, b));
+    assert_eq!(min[0], (-0.0f32).to_bits());
+    assert!(f32::from_bits(min[1]).is_nan() && f32::from_bits(min[2]).is_nan());
+    assert_eq!(min[3], (-3.0f32).to_bits());
+
+    let max = f32_bits(binary("f32x4.max", a, b));
+    assert_eq!(max[0], 0.0f32.to_bits());
+
+    // The pseudo minimum is `b < a ? b : a`, so it returns the first operand for NaNs and zeros
+    let pmin = f32_bits(binary("f32x4.pmin", a, b));
+    assert_eq!(pmin[0], (-0.0f32).to_bits());
+    assert!(f32::from_bits(pmin[1]).is_nan());
+    assert_eq!(pmin[2], 1.0f32.to_bits());
+
+    assert_eq!(
+        f32_bits(unary("f32x4.nearest", f32x4([2.5, 3.5, -0.5, 1.4]))),
+        [2.0f32, 4.0, -0.0, 1.0].map(f32::to_bits)
+    );
+    assert_eq!(
+        unary(
+            "i32x4.trunc_sat_f32x4_s",
+            f32x4([f32::NAN, 3e9, -3e9, -1.5])
+        ),
+        i32x4([0, i32::MAX, i32::MIN, -1])
+    );
+    assert_eq!(
+        unary(
+            "i32x4.trunc_sat_f64x2_u_zero",
+            V128::from_lanes([-1.0f64, 5e9])
+        ),
+        i32x4([0, -1, 0, 0])
+    );
+    assert_eq!(
+        f32_bits(unary("f32x4.convert_i32x4_u", i32x4([-1, 1, 0, 16777217]))),
+        [4294967296.0f32, 1.0, 0.0, 16777216.0].map(f32::to_bits)
+    );
+}
+
+#[test]
+fn lanes_are_shuffled_and_selected() {
+    let a = V128::from_lanes::<u8, 16>(std::array::from_fn(|i| i as u8));
+    let b = V128::from_lanes::<u8, 16>(std::array::from_fn(|i| 0x10 + i as u8));
+
+    assert_eq!(
+        binary(
+            "i8x16.shuffle 31 0 30 1 29 2 28 3 27 4 26 5 25 6 24 7",
+            a,
+            b
+        )
+        .lanes::<u8, 16>(),
+        [0x1F, 0, 0x1E, 1, 0x1D, 2, 0x1C, 3, 0x1B, 4, 0x1A, 5, 0x19, 6, 0x18, 7]
+    );
+    assert_eq!(
+        binary(
+            "i8x16.swizzle",
+            b,
+            V128::from_lanes([15u8, 0, 16, 255, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])
+        )
+        .lanes::<u8, 16>(),
+        [
+            0x1F, 0x10, 0, 0, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x1B,
+            0x1C
+        ]
+    );
+    assert_eq!(
+        unary(
+            "v128.const i64x2 0 0 v128.const i32x4 -1 0 -1 0 v128.bitselect",
+            a
+        ),
+        V128::from_lanes([0x0302_0100u32, 0, 0x0B0A_0908, 0])
+    );
+    assert_eq!(
+        unary("(i32.const 0x12345) i16x8.replace_lane 7", a).lanes::<u16, 8>()[7],
+        0x2345
+    );
+}
+
+#[test]
+fn scalars_are_extracted_and_splatted() {
+    let vector = i8x16([-1, 2, -3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, -16]);
+    let lane = |instruction: &str| {
+        run(
+            "v128",
+            "i32",
+            &format!("local.get 0 {instruction}"),
+            &[Value::V128(vector)],
+        )
+    };
+
+    assert_eq!(lane("i8x16.extract_lane_s 15"), Value::I32(-16));
+    assert_eq!(lane("i8x16.extract_lane_u 15"), Value::I32(240));
+    assert_eq!(lane("i16x8.extract_lane_s 0"), Value::I32(0x02FF));
+    assert_eq!(lane("i8x16.bitmask"), Value::I32(0b1000_0000_0000_0101));
+    assert_eq!(lane("i8x16.all_true"), Value::I32(1));
+    assert_eq!(lane("v128.any_true"), Value::I32(1));
+
+    assert_eq!(
+        run(
+            "i32",
+            "v128",
+            "local.get 0 i16x8.splat",
+            &[Value::I32(0x18000)]
+        ),
+        Value::V128(i16x8([-32768; 8]))
+    );
+    assert_eq!(
+        unary("(i32.const 33) i32x4.shl", i32x4([1, -1, 3, i32::MIN])),
+        i32x4([2, -2, 6, 0])
+    );
+}
+
+#[test]
+fn vectors_are_loaded_and_stored() {
+    // The memory starts with the bytes 00 11 22 33 ..
+    let load = |instruction: &str| match run(
+        "i32",
+        "v128",
+        &format!("local.get 0 {instruction}"),
+        &[Value::I32(0)],
+    ) {
+        Value::V128(vector) => vector,
+        result => panic!("{instruction} returned {result:?}"),
+    };
+
+    assert_eq!(
+        load("v128.load offset=1").lanes::<u8, 16>(),
+        std::array::from_fn(|i| (i as u8 + 1).wrapping_mul(0x11))
+    );
+    assert_eq!(
+        load("v128.load8x8_s"),
+        i16x8([0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77])
+    );
+    assert_eq!(
+        load("v128.load8x8_s offset=8"),
+        i16x8([-0x78, -0x67, -0x56, -0x45, -0x34, -0x23, -0x12, -1])
+    );
+    assert_eq!(load("v128.load32_zero offset=4").to_bits(), 0x77665544);
+    assert_eq!(load("v128.load16_splat").lanes::<u16, 8>(), [0x1100; 8]);
+    assert_eq!(
+        load("v128.const i64x2 -1 -1 v128.load16_lane 5").lanes::<u16, 8>(),
+        [0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0x1100, 0xFFFF, 0xFFFF]
+    );
+
+    let stored = run(
+        "i32",
+        "i64",
+        "(v128.store64_lane offset=100 1 (local.get 0) (v128.const i64x2 1 -2)) \
+         (i64.load offset=100 (local.get 0))",
+        &[Value::I32(0)],
+    );
+    assert_eq!(stored, Value::I64(-2));
+}
+
+#[test]
+fn out_of_bounds_accesses_trap() {
+    let module = WasmModule::from_wat(
+        r#"(module (memory 1)
+             (func (export "load") (param i32) (result v128) (v128.load (local.get 0))))"#,
+    )
+    .unwrap();
+    let mut memory = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(module, &mut memory).unwrap();
+
+    assert!(environment.invoke("load", &[Value::I32(65520)]).is_ok());
+    assert!(matches!(
+        environment.invoke("load", &[Value::I32(65521)]),
+        Err(swai::WasmInterpreterError::Trap(
+            swai::Trap::OutOfBoundsMemoryAccess
+        ))
+    ));
+}
@@ -0,0 +1,65 @@
+use swai::{Trap, Value, WasmEnvironment, WasmInterpreterError};
+use swai_parser::WasmModule;
+
+const SOURCE: &str = r#"
+    (module
+      (type $predicate (func (param i32) (result i32)))
+      (table funcref (elem $is_even $is_odd))
+
+      (func $is_even (export "is_even") (param i32) (result i32)
+        (if (result i32) (i32.eqz (local.get 0))
+          (then (i32.const 1))
+          (else (return_call $is_odd (i32.sub (local.get 0) (i32.const 1))))))
+      (func $is_odd (param i32) (result i32)
+        (if (result i32) (i32.eqz (local.get 0))
+          (then (i32.const 0))
+          (else
+            (return_call_indirect (type $predicate)
+              (i32.sub (local.get 0) (i32.const 1))
+              (i32.const 0)))))
+
+      ;; Operands below the arguments are dropped when the frame is replaced
+      (func (export "leftovers") (result i32)
+        (i64.const 1)
+        (f32.const 2)
+        (return_call $is_even (i32.const 4)))
+
+      (func $count (param i32) (result i32)
+        (if (result i32) (i32.eqz (local.get 0))
+          (then (i32.const 0))
+          (else
+            (i32.add (call $count (i32.sub (local.get 0) (i32.const 1))) (i32.const 1)))))
+      (func (export "count") (param i32) (result i32) (call $count (local.get 0))))
+"#;
+
+fn invoke(name: &str, arguments: &[Value]) -> Result<Vec<Value>, WasmInterpreterError> {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    let mut memory = vec![];
+    let mut environment = WasmEnvironment::new(module, &mut memory)?;
+    environment.invoke(name, arguments)
+}
+
+#[test]
+fn tail_calls_reuse_the_frame() {
+    assert_eq!(
+        invoke("is_even", &[Value::I32(1_000_000)]).unwrap(),
+        [Value::I32(1)]
+    );
+    assert_eq!(
+        invoke("is_even", &[Value::I32(1_000_001)]).unwrap(),
+        [Value::I32(0)]
+    );
+    assert_eq!(invoke("leftovers", &[]).unwrap(), [Value::I32(1)]);
+}
+
+#[test]
+fn regular_calls_still_exhaust_the_call_stack() {
+    assert_eq!(
+        invoke("count", &[Value::I32(1_000)]).unwrap(),
+        [Value::I32(1_000)]
+    );
+    assert!(matches!(
+        invoke("count", &[Value::I32(1_000_000)]),
+        Err(WasmInterpreterError::Trap(Trap::CallStackExhausted))
+    ));
+}
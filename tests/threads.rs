@@ -0,0 +1,215 @@
+use std::thread;
+
+use swai::{
+    interpreter::PAGE_SIZE, HostMemory, SharedMemory, Trap, Value, WasmEnvironment,
+    WasmInterpreterError,
+};
+use swai_parser::WasmModule;
+
+const SOURCE: &str = r#"
+    (module
+      (memory 1 1 shared)
+      (func (export "increment") (param i32)
+        (loop $next
+          (drop (i32.atomic.rmw.add (i32.const 0) (i32.const 1)))
+          (br_if $next (local.tee 0 (i32.sub (local.get 0) (i32.const 1))))))
+      (func (export "load") (param i32) (result i32) (i32.atomic.load (local.get 0)))
+      (func (export "wait") (param i32 i64) (result i32)
+        (memory.atomic.wait32 (i32.const 8) (local.get 0) (local.get 1)))
+      (func (export "notify") (result i32)
+        (i32.atomic.store (i32.const 8) (i32.const 1))
+        (memory.atomic.notify (i32.const 8) (i32.const 1)))
+      (func (export "narrow") (result i32 i32 i64)
+        ;; 8 bit additions wrap inside their byte, compare-exchange ignores the high bits of the operand
+        (i32.atomic.store (i32.const 16) (i32.const 0x01ff))
+        (drop (i32.atomic.rmw8.add_u (i32.const 16) (i32.const 1)))
+        (i32.atomic.load (i32.const 16))
+        (i32.atomic.rmw16.cmpxchg_u (i32.const 16) (i32.const 0xffff0100) (i32.const 0xabcd))
+        (i64.atomic.load32_u (i32.const 16)))
+      (func (export "unaligned") (result i32) (i32.atomic.load (i32.const 2))))
+"#;
+
+fn environment(memory: &SharedMemory) -> WasmEnvironment<'static> {
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    WasmEnvironment::with_shared_memory(module, memory.clone()).unwrap()
+}
+
+#[test]
+fn atomic_operations_from_several_threads() {
+    let memory = SharedMemory::new(1).unwrap();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let memory = memory.clone();
+            thread::spawn(move || {
+                environment(&memory)
+                    .invoke("increment", &[Value::I32(2_000)])
+                    .unwrap();
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(
+        environment(&memory)
+            .invoke("load", &[Value::I32(0)])
+            .unwrap(),
+        [Value::I32(8_000)]
+    );
+    let mut counter = [0; 4];
+    memory.read(0, &mut counter);
+    assert_eq!(u32::from_le_bytes(counter), 8_000);
+}
+
+#[test]
+fn waiting_threads_are_notified() {
+    let memory = SharedMemory::new(1).unwrap();
+    let waiter = {
+        let memory = memory.clone();
+        thread::spawn(move || {
+            // The value can still be 1 from a notification sent before the thread waited
+            let mut environment = environment(&memory);
+            loop {
+                let result = environment
+                    .invoke("wait", &[Value::I32(0), Value::I64(-1)])
+                    .unwrap();
+                if result != [Value::I32(1)] {
+                    return result;
+                }
+            }
+        })
+    };
+
+    // The waiter might not be waiting yet, so notify until a thread is woken
+    let mut notifier = environment(&memory);
+    while notifier.invoke("notify", &[]).unwrap() != [Value::I32(1)] {
+        // The stored value has to be reset, or the waiter might see it and not wait at all
+        memory.write(8, &[0]);
+        thread::yield_now();
+    }
+    assert_eq!(waiter.join().unwrap(), [Value::I32(0)]);
+
+    // Waiting for a value that isn't there returns right away, a timeout returns 2
+    let mut environment = environment(&memory);
+    assert_eq!(
+        environment
+            .invoke("wait", &[Value::I32(0), Value::I64(-1)])
+            .unwrap(),
+        [Value::I32(1)]
+    );
+    assert_eq!(
+        environment
+            .invoke("wait", &[Value::I32(1), Value::I64(1_000_000)])
+            .unwrap(),
+        [Value::I32(2)]
+    );
+}
+
+#[test]
+fn narrow_atomic_operations() {
+    let memory = SharedMemory::new(1).unwrap();
+    assert_eq!(
+        environment(&memory).invoke("narrow", &[]).unwrap(),
+        [Value::I32(0x0100), Value::I32(0x0100), Value::I64(0xabcd)]
+    );
+}
+
+#[test]
+fn atomic_traps() {
+    let memory = SharedMemory::new(1).unwrap();
+    assert!(matches!(
+        environment(&memory).invoke("unaligned", &[]),
+        Err(WasmInterpreterError::Trap(Trap::UnalignedAtomic))
+    ));
+    // Accesses that are out of bounds trap as such, even if they're also unaligned
+    assert!(matches!(
+        environment(&memory).invoke("load", &[Value::I32(65535)]),
+        Err(WasmInterpreterError::Trap(Trap::OutOfBoundsMemoryAccess))
+    ));
+
+    // Only shared memories can be waited on, notifying one that isn't wakes nobody
+    let module = WasmModule::from_wat(SOURCE).unwrap();
+    let mut bytes = vec![0; 65536];
+    let mut environment = WasmEnvironment::new(module, &mut bytes).unwrap();
+    assert!(matches!(
+        environment.invoke("wait", &[Value::I32(0), Value::I64(0)]),
+        Err(WasmInterpreterError::Trap(Trap::ExpectedSharedMemory))
+    ));
+    assert_eq!(environment.invoke("notify", &[]).unwrap(), [Value::I32(0)]);
+}
+
+#[test]
+fn shared_memories_that_are_too_large_are_errors() {
+    assert!(matches!(
+        SharedMemory::new(usize::MAX),
+        Err(WasmInterpreterError::AllocationFailed {
+            resource: "memory pages",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn host_functions_access_shared_memories_through_the_handle() {
+    let source = r#"
+        (module
+          (import "env" "fill" (func $fill (param i32)))
+          (memory 1 1 shared)
+          (func (export "run") (result i32)
+            (call $fill (i32.const 4))
+            (i32.atomic.load (i32.const 4))))
+    "#;
+    let memory = SharedMemory::new(1).unwrap();
+    let module = WasmModule::from_wat(source).unwrap();
+    let mut environment = WasmEnvironment::with_shared_memory(module, memory.clone()).unwrap();
+    environment.define_function("env", "fill", |mut memory, arguments| {
+        let [Value::I32(address)] = arguments else {
+            panic!("unexpected arguments {arguments:?}")
+        };
+        assert!(matches!(memory, HostMemory::Shared(_)));
+        memory.write(*address as usize, &7u32.to_le_bytes())?;
+        // Accesses outside of the memory trap instead of panicking
+        memory.write(memory.len() - 2, &[0; 4])?;
+        Ok(Vec::new())
+    });
+
+    assert!(matches!(
+        environment.invoke("run", &[]),
+        Err(WasmInterpreterError::Trap(Trap::OutOfBoundsMemoryAccess))
+    ));
+    let mut value = [0; 4];
+    memory.read(4, &mut value);
+    assert_eq!(u32::from_le_bytes(value), 7);
+
+    assert!(environment.memory().is_none());
+    assert!(environment.memory_mut().is_none());
+    assert_eq!(
+        environment.shared_memory().map(SharedMemory::len),
+        Some(memory.len())
+    );
+}
+
+#[test]
+fn shared_memories_grow_for_every_handle() {
+    let source = r#"
+        (module
+          (memory 1 2 shared)
+          (func (export "grow") (result i32) (memory.grow (i32.const 1)))
+          (func (export "store") (param i32 i32) (i32.atomic.store (local.get 0) (local.get 1))))
+    "#;
+    let memory = SharedMemory::new(1).unwrap();
+    let module = WasmModule::from_wat(source).unwrap();
+    let mut environment = WasmEnvironment::with_shared_memory(module, memory.clone()).unwrap();
+
+    assert_eq!(environment.invoke("grow", &[]).unwrap(), [Value::I32(1)]);
+    assert_eq!(environment.invoke("grow", &[]).unwrap(), [Value::I32(-1)]);
+    assert_eq!(memory.len(), 2 * PAGE_SIZE);
+
+    environment
+        .invoke("store", &[Value::I32(PAGE_SIZE as i32), Value::I32(7)])
+        .unwrap();
+    let mut bytes = [0; 4];
+    memory.read(PAGE_SIZE, &mut bytes);
+    assert_eq!(bytes, 7u32.to_le_bytes());
+}